@@ -1,11 +1,25 @@
+#[cfg(feature = "write")]
+use object::read::archive::ArchiveKind;
+#[cfg(feature = "write")]
+use object::write::archive::Archive;
 use std::{env, fs, process};
 
 fn main() {
     let mut args = env::args().skip(1);
     if args.len() < 2 {
         eprintln!(
-            "Usage: {} <op> <archive> [<member>...]",
-            env::args().next().unwrap()
+            "Usage: {} <op> <archive> [<member>...]\n\
+            \n\
+            Operations:\n\
+            \x20 t                                   list the members{}",
+            env::args().next().unwrap(),
+            if cfg!(feature = "write") {
+                "\n\
+                \x20 --thin <archive> <member>...        create a thin archive\n\
+                \x20 --flatten <archive> <output>        convert a thin archive to a regular archive"
+            } else {
+                ""
+            }
         );
         process::exit(1);
     }
@@ -13,6 +27,19 @@ fn main() {
     let op = args.next().unwrap();
     let file_path = args.next().unwrap();
 
+    #[cfg(feature = "write")]
+    if op == "--thin" {
+        let archive = match Archive::new_thin(ArchiveKind::Gnu, &file_path, args) {
+            Ok(archive) => archive,
+            Err(err) => {
+                eprintln!("Failed to create thin archive '{}': {}", file_path, err);
+                process::exit(1);
+            }
+        };
+        write_archive(&archive, &file_path);
+        return;
+    }
+
     let file = match fs::File::open(&file_path) {
         Ok(file) => file,
         Err(err) => {
@@ -27,6 +54,27 @@ fn main() {
             return;
         }
     };
+
+    #[cfg(feature = "write")]
+    if op == "--flatten" {
+        let out_path = match args.next() {
+            Some(out_path) => out_path,
+            None => {
+                eprintln!("Missing output path for '--flatten'");
+                process::exit(1);
+            }
+        };
+        let archive = match Archive::flatten_thin(&file, &file_path) {
+            Ok(archive) => archive,
+            Err(err) => {
+                eprintln!("Failed to flatten archive '{}': {}", file_path, err);
+                process::exit(1);
+            }
+        };
+        write_archive(&archive, &out_path);
+        return;
+    }
+
     let archive = match object::read::archive::ArchiveFile::parse(&*file) {
         Ok(file) => file,
         Err(err) => {
@@ -44,3 +92,18 @@ fn main() {
         op => println!("Invalid operation: {}", op),
     }
 }
+
+#[cfg(feature = "write")]
+fn write_archive(archive: &Archive<'_>, path: &str) {
+    let out_file = match fs::File::create(path) {
+        Ok(out_file) => out_file,
+        Err(err) => {
+            eprintln!("Failed to create file '{}': {}", path, err);
+            process::exit(1);
+        }
+    };
+    if let Err(err) = archive.write_stream(std::io::BufWriter::new(out_file)) {
+        eprintln!("Failed to write archive '{}': {}", path, err);
+        process::exit(1);
+    }
+}
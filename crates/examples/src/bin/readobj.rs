@@ -122,7 +122,13 @@ fn main() {
             Arg::new("pe-resources")
                 .long("pe-resources")
                 .action(ArgAction::SetTrue)
-                .help("Print the PE resource directory"),
+                .help("Print the PE resource directory and version information"),
+        )
+        .arg(
+            Arg::new("pe-exceptions")
+                .long("pe-exceptions")
+                .action(ArgAction::SetTrue)
+                .help("Print the PE exception table and unwind information"),
         )
         .arg(
             Arg::new("no-string-indices")
@@ -150,6 +156,7 @@ fn main() {
         pe_imports: matches.get_flag("pe-imports"),
         pe_exports: matches.get_flag("pe-exports"),
         pe_resources: matches.get_flag("pe-resources"),
+        pe_exceptions: matches.get_flag("pe-exceptions"),
         ..readobj::PrintOptions::none()
     };
     if options == readobj::PrintOptions::none() {
@@ -32,6 +32,7 @@ pub struct PrintOptions {
     pub pe_imports: bool,
     pub pe_exports: bool,
     pub pe_resources: bool,
+    pub pe_exceptions: bool,
 
     // Modifiers
     pub string_indices: bool,
@@ -59,6 +60,7 @@ impl PrintOptions {
             pe_imports: true,
             pe_exports: true,
             pe_resources: true,
+            pe_exceptions: true,
             string_indices: true,
         }
     }
@@ -84,6 +86,7 @@ impl PrintOptions {
             pe_imports: false,
             pe_exports: false,
             pe_resources: false,
+            pe_exceptions: false,
             string_indices: true,
         }
     }
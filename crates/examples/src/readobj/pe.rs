@@ -106,6 +106,8 @@ fn print_pe<Pe: ImageNtHeaders>(p: &mut Printer<'_>, data: &[u8]) {
                 print_delay_load_dir::<Pe>(p, data, sections, &data_directories);
                 print_reloc_dir(p, data, machine, sections, &data_directories);
                 print_resource_dir(p, data, sections, &data_directories);
+                print_version_info(p, data, sections, &data_directories);
+                print_exception_dir(p, data, machine, sections, &data_directories);
             }
         }
     }
@@ -507,6 +509,64 @@ fn print_resource_dir(
     Some(())
 }
 
+fn print_version_info(
+    p: &mut Printer<'_>,
+    data: &[u8],
+    sections: &SectionTable,
+    data_directories: &DataDirectories,
+) -> Option<()> {
+    if !p.options.pe_resources {
+        return Some(());
+    }
+    let directory = data_directories
+        .resource_directory(data, sections)
+        .print_err(p)??;
+    let entry = directory.find_data_entry(RT_VERSION).print_err(p)??;
+    let version_data = entry.data(data, sections).print_err(p)?;
+    let version_info = VersionInfo::parse(version_data).print_err(p)?;
+    p.group("VersionInfo", |p| {
+        if let Some(fixed) = version_info.fixed {
+            p.group("VsFixedFileInfo", |p| {
+                p.field_hex("Signature", fixed.signature.get(LE));
+                p.field_hex("StrucVersion", fixed.struc_version.get(LE));
+                if let Some([major, minor, build, revision]) = version_info.file_version() {
+                    p.field_name("FileVersion");
+                    writeln!(p.w, "{}.{}.{}.{}", major, minor, build, revision).unwrap();
+                }
+                if let Some([major, minor, build, revision]) = version_info.product_version() {
+                    p.field_name("ProductVersion");
+                    writeln!(p.w, "{}.{}.{}.{}", major, minor, build, revision).unwrap();
+                }
+                p.field_hex("FileFlagsMask", fixed.file_flags_mask.get(LE));
+                p.field_hex("FileFlags", fixed.file_flags.get(LE));
+                p.flags(fixed.file_flags.get(LE), 0, FLAGS_VS_FF);
+                p.field_enum("FileOS", fixed.file_os.get(LE), FLAGS_VOS);
+                p.field_enum("FileType", fixed.file_type.get(LE), FLAGS_VFT);
+                p.field_hex("FileSubtype", fixed.file_subtype.get(LE));
+                p.field_hex("FileDateMS", fixed.file_date_ms.get(LE));
+                p.field_hex("FileDateLS", fixed.file_date_ls.get(LE));
+            });
+        }
+        for table in &version_info.string_tables {
+            p.group("StringTable", |p| {
+                p.field_hex("Language", table.language);
+                p.field("CodePage", table.code_page);
+                for string in &table.strings {
+                    p.field_name(&string.key_to_string_lossy());
+                    writeln!(p.w, "{:?}", string.value_to_string_lossy()).unwrap();
+                }
+            });
+        }
+        for translation in &version_info.translations {
+            p.group("Translation", |p| {
+                p.field_hex("Language", translation.language);
+                p.field("CodePage", translation.code_page);
+            });
+        }
+    });
+    Some(())
+}
+
 fn print_resource_table(
     p: &mut Printer<'_>,
     directory: ResourceDirectory<'_>,
@@ -837,6 +897,124 @@ fn print_reloc_dir(
     Some(())
 }
 
+fn print_exception_dir(
+    p: &mut Printer<'_>,
+    data: &[u8],
+    machine: u16,
+    sections: &SectionTable,
+    data_directories: &DataDirectories,
+) -> Option<()> {
+    if !p.options.pe_exceptions {
+        return Some(());
+    }
+    let table = data_directories
+        .exception_table(data, sections)
+        .print_err(p)??;
+    match machine {
+        IMAGE_FILE_MACHINE_AMD64 => {
+            for function in table.x64_functions().print_err(p)? {
+                p.group("ImageRuntimeFunctionEntry", |p| {
+                    print_x64_function(p, function);
+                    if let Some(info) = function.unwind_info(data, sections).print_err(p) {
+                        print_x64_unwind_info(p, &info);
+                    }
+                });
+            }
+        }
+        IMAGE_FILE_MACHINE_ARM64 => {
+            for function in table.arm64_functions().print_err(p)? {
+                p.group("ImageArm64RuntimeFunctionEntry", |p| {
+                    p.field_hex("BeginAddress", function.begin_address.get(LE));
+                    p.field_hex("UnwindData", function.unwind_data.get(LE));
+                    p.field_enum("Flag", function.flag(), FLAGS_ARM64_PDATA);
+                    match function.unwind_data(data, sections).print_err(p) {
+                        Some(Arm64UnwindData::Packed(packed)) => {
+                            print_arm64_packed_unwind(p, &packed)
+                        }
+                        Some(Arm64UnwindData::Full(info)) => print_arm64_unwind_info(p, &info),
+                        None => {}
+                    }
+                });
+            }
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+fn print_x64_function(p: &mut Printer<'_>, function: &ImageRuntimeFunctionEntry) {
+    p.field_hex("BeginAddress", function.begin_address.get(LE));
+    p.field_hex("EndAddress", function.end_address.get(LE));
+    p.field_hex(
+        "UnwindInfoAddress",
+        function.unwind_info_address_or_data.get(LE),
+    );
+}
+
+fn print_x64_unwind_info(p: &mut Printer<'_>, info: &X64UnwindInfo) {
+    p.group("UnwindInfo", |p| {
+        p.field("Version", info.version());
+        p.field_hex("Flags", info.flags());
+        p.flags(info.flags(), 0, FLAGS_UNW_FLAG);
+        p.field_hex("SizeOfProlog", info.size_of_prolog());
+        p.field("CountOfCodes", info.count_of_codes());
+        if info.frame_register() != 0 {
+            p.field_enum_display("FrameRegister", info.frame_register(), FLAGS_X64_REGISTER);
+            p.field_hex("FrameOffset", info.frame_offset());
+        }
+        let mut codes = info.codes();
+        while let Some(code) = codes.next().print_err(p).flatten() {
+            p.group("UnwindCode", |p| {
+                p.field_hex("CodeOffset", code.code_offset);
+                p.field("Operation", format_args!("{:?}", code.operation));
+            });
+        }
+        if let Some(handler) = info.handler() {
+            p.field_hex("Handler", handler);
+        }
+        if let Some(function) = info.chained_function() {
+            p.group("ChainedFunction", |p| print_x64_function(p, function));
+        }
+    });
+}
+
+fn print_arm64_packed_unwind(p: &mut Printer<'_>, packed: &Arm64PackedUnwind) {
+    p.group("PackedUnwind", |p| {
+        p.field_hex("FunctionLength", packed.function_length);
+        p.field("RegF", packed.reg_f);
+        p.field("RegI", packed.reg_i);
+        p.field("H", packed.h);
+        p.field("CR", packed.cr);
+        p.field_hex("FrameSize", packed.frame_size);
+    });
+}
+
+fn print_arm64_unwind_info(p: &mut Printer<'_>, info: &Arm64UnwindInfo) {
+    p.group("UnwindInfo", |p| {
+        p.field_hex("FunctionLength", info.function_length());
+        p.field("Version", info.version());
+        if let Some(index) = info.packed_epilog() {
+            p.field("PackedEpilogIndex", index);
+        }
+        for scope in info.epilog_scopes() {
+            p.group("EpilogScope", |p| {
+                p.field_hex("StartOffset", scope.start_offset);
+                p.field("StartIndex", scope.start_index);
+            });
+        }
+        let mut codes = info.codes();
+        while let Some((index, code)) = codes.next().print_err(p).flatten() {
+            p.group("UnwindCode", |p| {
+                p.field("Index", index);
+                p.field("Code", format_args!("{:?}", code));
+            });
+        }
+        if let Some(handler) = info.handler() {
+            p.field_hex("Handler", handler);
+        }
+    });
+}
+
 const FLAGS_IMAGE_FILE: &[Flag<u16>] = &flags!(
     IMAGE_FILE_RELOCS_STRIPPED,
     IMAGE_FILE_EXECUTABLE_IMAGE,
@@ -1366,6 +1544,40 @@ const FLAGS_RT: &[Flag<u16>] = &flags!(
     RT_HTML,
     RT_MANIFEST,
 );
+const FLAGS_VS_FF: &[Flag<u32>] = &flags!(
+    VS_FF_DEBUG,
+    VS_FF_PRERELEASE,
+    VS_FF_PATCHED,
+    VS_FF_PRIVATEBUILD,
+    VS_FF_INFOINFERRED,
+    VS_FF_SPECIALBUILD,
+);
+const FLAGS_VOS: &[Flag<u32>] = &flags!(
+    VOS_UNKNOWN,
+    VOS_DOS,
+    VOS_OS216,
+    VOS_OS232,
+    VOS_NT,
+    VOS_WINCE,
+    VOS__WINDOWS16,
+    VOS__PM16,
+    VOS__PM32,
+    VOS__WINDOWS32,
+    VOS_DOS_WINDOWS16,
+    VOS_DOS_WINDOWS32,
+    VOS_OS216_PM16,
+    VOS_OS232_PM32,
+    VOS_NT_WINDOWS32,
+);
+const FLAGS_VFT: &[Flag<u32>] = &flags!(
+    VFT_UNKNOWN,
+    VFT_APP,
+    VFT_DLL,
+    VFT_DRV,
+    VFT_FONT,
+    VFT_VXD,
+    VFT_STATIC_LIB,
+);
 const FLAGS_IMAGE_OBJECT_TYPE: &[Flag<u16>] =
     &flags!(IMPORT_OBJECT_CODE, IMPORT_OBJECT_DATA, IMPORT_OBJECT_CONST);
 const FLAGS_IMAGE_OBJECT_NAME: &[Flag<u16>] = &flags!(
@@ -1375,3 +1587,76 @@ const FLAGS_IMAGE_OBJECT_NAME: &[Flag<u16>] = &flags!(
     IMPORT_OBJECT_NAME_UNDECORATE,
     IMPORT_OBJECT_NAME_EXPORTAS,
 );
+const FLAGS_ARM64_PDATA: &[Flag<u32>] = &flags!(
+    ARM64_PDATA_REF_TO_FULL_XDATA,
+    ARM64_PDATA_PACKED_UNWIND_FUNCTION,
+    ARM64_PDATA_PACKED_UNWIND_FRAGMENT,
+);
+const FLAGS_UNW_FLAG: &[Flag<u8>] =
+    &flags!(UNW_FLAG_EHANDLER, UNW_FLAG_UHANDLER, UNW_FLAG_CHAININFO);
+const FLAGS_X64_REGISTER: &[Flag<u8>] = &[
+    Flag {
+        value: 0,
+        name: "RAX",
+    },
+    Flag {
+        value: 1,
+        name: "RCX",
+    },
+    Flag {
+        value: 2,
+        name: "RDX",
+    },
+    Flag {
+        value: 3,
+        name: "RBX",
+    },
+    Flag {
+        value: 4,
+        name: "RSP",
+    },
+    Flag {
+        value: 5,
+        name: "RBP",
+    },
+    Flag {
+        value: 6,
+        name: "RSI",
+    },
+    Flag {
+        value: 7,
+        name: "RDI",
+    },
+    Flag {
+        value: 8,
+        name: "R8",
+    },
+    Flag {
+        value: 9,
+        name: "R9",
+    },
+    Flag {
+        value: 10,
+        name: "R10",
+    },
+    Flag {
+        value: 11,
+        name: "R11",
+    },
+    Flag {
+        value: 12,
+        name: "R12",
+    },
+    Flag {
+        value: 13,
+        name: "R13",
+    },
+    Flag {
+        value: 14,
+        name: "R14",
+    },
+    Flag {
+        value: 15,
+        name: "R15",
+    },
+];
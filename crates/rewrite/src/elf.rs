@@ -64,7 +64,10 @@ pub struct ElfOptions {
 impl Rewriter<'_> {
     /// Delete symbols from the symbol table.
     pub fn elf_delete_symbols(&mut self, names: &HashSet<Vec<u8>>) {
-        for symbol in &mut self.builder.dynamic_symbols {
+        let Some(builder) = self.builder.elf_mut() else {
+            return;
+        };
+        for symbol in &mut builder.dynamic_symbols {
             if names.contains(&*symbol.name) {
                 #[cfg(feature = "logging")]
                 info!("Deleting symbol {}", symbol.name);
@@ -76,7 +79,10 @@ impl Rewriter<'_> {
 
     /// Delete symbols from the dynamic symbol table.
     pub fn elf_delete_dynamic_symbols(&mut self, names: &HashSet<Vec<u8>>) {
-        for symbol in &mut self.builder.symbols {
+        let Some(builder) = self.builder.elf_mut() else {
+            return;
+        };
+        for symbol in &mut builder.symbols {
             if names.contains(&*symbol.name) {
                 #[cfg(feature = "logging")]
                 info!("Deleting dynamic symbol {}", symbol.name);
@@ -90,7 +96,10 @@ impl Rewriter<'_> {
     ///
     /// The `names` map is from old names to new names.
     pub fn elf_rename_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
-        for symbol in &mut self.builder.dynamic_symbols {
+        let Some(builder) = self.builder.elf_mut() else {
+            return;
+        };
+        for symbol in &mut builder.dynamic_symbols {
             if let Some(name) = names.get(&*symbol.name) {
                 let name = name.clone().into();
                 #[cfg(feature = "logging")]
@@ -105,7 +114,10 @@ impl Rewriter<'_> {
     ///
     /// The `names` map is from old names to new names.
     pub fn elf_rename_dynamic_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
-        for symbol in &mut self.builder.dynamic_symbols {
+        let Some(builder) = self.builder.elf_mut() else {
+            return;
+        };
+        for symbol in &mut builder.dynamic_symbols {
             if let Some(name) = names.get(&*symbol.name) {
                 let name = name.clone().into();
                 #[cfg(feature = "logging")]
@@ -117,7 +129,10 @@ impl Rewriter<'_> {
     }
 
     pub(crate) fn elf_delete_sections(&mut self, names: &HashSet<Vec<u8>>) {
-        for section in &mut self.builder.sections {
+        let Some(builder) = self.builder.elf_mut() else {
+            return;
+        };
+        for section in &mut builder.sections {
             if names.contains(&*section.name) {
                 #[cfg(feature = "logging")]
                 info!("Deleting section {}", section.name);
@@ -129,7 +144,10 @@ impl Rewriter<'_> {
     }
 
     pub(crate) fn elf_rename_sections(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
-        for section in &mut self.builder.sections {
+        let Some(builder) = self.builder.elf_mut() else {
+            return;
+        };
+        for section in &mut builder.sections {
             if let Some(name) = names.get(&*section.name) {
                 let name = name.clone().into();
                 #[cfg(feature = "logging")]
@@ -181,7 +199,8 @@ impl Rewriter<'_> {
     pub fn elf_add_dynamic_debug(&mut self) -> Result<()> {
         let dynamic = self
             .builder
-            .dynamic_data_mut()
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
             .ok_or_else(|| Error::modify("No dynamic section found; can't add debug entry"))?;
         if dynamic.iter().any(|entry| entry.tag() == elf::DT_DEBUG) {
             return Ok(());
@@ -199,7 +218,7 @@ impl Rewriter<'_> {
 
     /// Find the first `DT_RUNPATH` or `DT_RPATH` entry in the dynamic section.
     pub fn elf_runpath(&self) -> Option<&[u8]> {
-        let dynamic = self.builder.dynamic_data()?;
+        let dynamic = self.builder.elf()?.dynamic_data()?;
         for entry in dynamic.iter() {
             let build::elf::Dynamic::String { tag, val } = entry else {
                 continue;
@@ -216,7 +235,8 @@ impl Rewriter<'_> {
     pub fn elf_delete_runpath(&mut self) -> Result<()> {
         let dynamic = self
             .builder
-            .dynamic_data_mut()
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
             .ok_or_else(|| Error::modify("No dynamic section found; can't delete runpath"))?;
         let mut modified = false;
         dynamic.retain(|entry| {
@@ -247,7 +267,8 @@ impl Rewriter<'_> {
     pub fn elf_set_runpath(&mut self, runpath: Vec<u8>) -> Result<()> {
         let dynamic = self
             .builder
-            .dynamic_data_mut()
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
             .ok_or_else(|| Error::modify("No dynamic section found; can't set runpath"))?;
         let mut found = false;
         for entry in dynamic.iter_mut() {
@@ -288,7 +309,8 @@ impl Rewriter<'_> {
     pub fn elf_add_runpath(&mut self, runpaths: &[Vec<u8>]) -> Result<()> {
         let dynamic = self
             .builder
-            .dynamic_data_mut()
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
             .ok_or_else(|| Error::modify("No dynamic section found; can't add runpath"))?;
         let mut found = false;
         for entry in dynamic.iter_mut() {
@@ -334,7 +356,8 @@ impl Rewriter<'_> {
     pub fn elf_use_runpath(&mut self) -> Result<()> {
         let dynamic = self
             .builder
-            .dynamic_data_mut()
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
             .ok_or_else(|| Error::modify("No dynamic section found; can't change runpath"))?;
         for entry in dynamic.iter_mut() {
             let build::elf::Dynamic::String { tag, .. } = entry else {
@@ -356,7 +379,8 @@ impl Rewriter<'_> {
     pub fn elf_use_rpath(&mut self) -> Result<()> {
         let dynamic = self
             .builder
-            .dynamic_data_mut()
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
             .ok_or_else(|| Error::modify("No dynamic section found; can't change rpath"))?;
         for entry in dynamic.iter_mut() {
             let build::elf::Dynamic::String { tag, .. } = entry else {
@@ -376,7 +400,11 @@ impl Rewriter<'_> {
 
     /// Find the `DT_NEEDED` entries in the dynamic section.
     pub fn elf_needed(&self) -> impl Iterator<Item = &[u8]> {
-        let dynamic = self.builder.dynamic_data().unwrap_or(&[]);
+        let dynamic = self
+            .builder
+            .elf()
+            .and_then(|builder| builder.dynamic_data())
+            .unwrap_or(&[]);
         dynamic.iter().filter_map(|entry| {
             if let build::elf::Dynamic::String { tag, val } = entry {
                 if *tag == elf::DT_NEEDED {
@@ -389,9 +417,13 @@ impl Rewriter<'_> {
 
     /// Delete `DT_NEEDED` entries from the dynamic section.
     pub fn elf_delete_needed(&mut self, names: &HashSet<Vec<u8>>) -> Result<()> {
-        let dynamic = self.builder.dynamic_data_mut().ok_or_else(|| {
-            Error::modify("No dynamic section found; can't delete needed library")
-        })?;
+        let dynamic = self
+            .builder
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
+            .ok_or_else(|| {
+                Error::modify("No dynamic section found; can't delete needed library")
+            })?;
         let mut modified = false;
         dynamic.retain(|entry| {
             let build::elf::Dynamic::String { tag, val } = entry else {
@@ -414,9 +446,13 @@ impl Rewriter<'_> {
 
     /// Replace `DT_NEEDED` entries in the dynamic section.
     pub fn elf_replace_needed(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
-        let dynamic = self.builder.dynamic_data_mut().ok_or_else(|| {
-            Error::modify("No dynamic section found; can't replace needed library")
-        })?;
+        let dynamic = self
+            .builder
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
+            .ok_or_else(|| {
+                Error::modify("No dynamic section found; can't replace needed library")
+            })?;
         for entry in dynamic.iter_mut() {
             let build::elf::Dynamic::String { tag, val } = entry else {
                 continue;
@@ -443,7 +479,8 @@ impl Rewriter<'_> {
     pub fn elf_add_needed(&mut self, names: &[Vec<u8>]) -> Result<()> {
         let dynamic = self
             .builder
-            .dynamic_data_mut()
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
             .ok_or_else(|| Error::modify("No dynamic section found; can't add needed library"))?;
         let mut found = HashSet::new();
         for entry in dynamic.iter() {
@@ -477,8 +514,9 @@ impl Rewriter<'_> {
 
     /// Find the `DT_SONAME` entry in the dynamic section.
     pub fn elf_soname(&self) -> Option<&[u8]> {
-        let id = self.builder.dynamic_section()?;
-        let section = self.builder.sections.get(id);
+        let builder = self.builder.elf()?;
+        let id = builder.dynamic_section()?;
+        let section = builder.sections.get(id);
         let build::elf::SectionData::Dynamic(dynamic) = &section.data else {
             return None;
         };
@@ -499,7 +537,8 @@ impl Rewriter<'_> {
     pub fn elf_set_soname(&mut self, soname: Vec<u8>) -> Result<()> {
         let dynamic = self
             .builder
-            .dynamic_data_mut()
+            .elf_mut()
+            .and_then(|builder| builder.dynamic_data_mut())
             .ok_or_else(|| Error::modify("No dynamic section found; can't set soname"))?;
         let mut found = false;
         for entry in dynamic.iter_mut() {
@@ -530,7 +569,7 @@ impl Rewriter<'_> {
 
     /// Find the interpreter path in the `PT_INTERP` segment.
     pub fn elf_interpreter(&self) -> Option<&[u8]> {
-        self.builder.interp_data()
+        self.builder.elf()?.interp_data()
     }
 
     /// Set the interpreter path in the `PT_INTERP` segment.
@@ -539,7 +578,8 @@ impl Rewriter<'_> {
     pub fn elf_set_interpreter(&mut self, mut interpreter: Vec<u8>) -> Result<()> {
         let data = self
             .builder
-            .interp_data_mut()
+            .elf_mut()
+            .and_then(|builder| builder.interp_data_mut())
             .ok_or_else(|| Error::modify("No interp section found; can't set interpreter"))?;
         #[cfg(feature = "logging")]
         info!(
@@ -555,8 +595,10 @@ impl Rewriter<'_> {
     }
 
     pub(crate) fn elf_finalize(&mut self) -> Result<()> {
-        if self.modified {
-            move_sections(&mut self.builder)?;
+        if let Some(builder) = self.builder.elf_mut() {
+            if self.modified {
+                move_sections(builder)?;
+            }
         }
         Ok(())
     }
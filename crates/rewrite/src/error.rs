@@ -40,7 +40,16 @@ impl fmt::Display for Error {
     }
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.inner {
+            ErrorInner::Io(e) => Some(e),
+            ErrorInner::Parse(e) => Some(e),
+            ErrorInner::Write(e) => Some(e),
+            ErrorInner::Modify(_) => None,
+        }
+    }
+}
 
 impl Error {
     /// Get the kind of error.
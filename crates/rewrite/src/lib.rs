@@ -4,8 +4,8 @@
 //! Modifications can be performed using methods on the [`Rewriter`] struct, or
 //! by passing an [`Options`] struct to the [`Rewriter::modify`] method.
 //!
-//! Currently, only ELF files are supported, and not many modifications are
-//! possible yet.
+//! Currently, only ELF, Mach-O, PE and COFF files are supported, and not many
+//! modifications are possible yet.
 //!
 //! # Example
 //! ```no_run
@@ -35,3 +35,9 @@ pub use rewriter::{Options, Rewriter};
 
 mod elf;
 pub use elf::ElfOptions;
+
+mod macho;
+pub use macho::MachOOptions;
+
+mod pe;
+pub use pe::PeOptions;
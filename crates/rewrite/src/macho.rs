@@ -0,0 +1,342 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "logging")]
+use log::info;
+use object::build::macho::{Dylib, LoadCommand, Section};
+use object::{build, macho};
+
+use super::{Error, Result, Rewriter};
+
+/// Options for modifying a Mach-O file.
+///
+/// This struct contains options for modifying a Mach-O file. It is
+/// contained in the [`Options`](super::Options) struct.
+///
+/// Options are listed in the order they are processed.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct MachOOptions {
+    /// Delete `LC_RPATH` load commands.
+    ///
+    /// See [`Rewriter::macho_delete_rpath`].
+    pub delete_rpath: HashSet<Vec<u8>>,
+    /// Change the paths of `LC_RPATH` load commands.
+    ///
+    /// See [`Rewriter::macho_change_rpath`].
+    pub change_rpath: HashMap<Vec<u8>, Vec<u8>>,
+    /// Add `LC_RPATH` load commands.
+    ///
+    /// See [`Rewriter::macho_add_rpath`].
+    pub add_rpath: Vec<Vec<u8>>,
+    /// Set the install name in the `LC_ID_DYLIB` load command.
+    ///
+    /// See [`Rewriter::macho_set_id_dylib`].
+    pub set_id_dylib: Option<Vec<u8>>,
+    /// Change the paths of dylib load commands such as `LC_LOAD_DYLIB`.
+    ///
+    /// See [`Rewriter::macho_change_load_dylib`].
+    pub change_load_dylib: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Rewriter<'_> {
+    /// Delete symbols from the symbol table.
+    pub fn macho_delete_symbols(&mut self, names: &HashSet<Vec<u8>>) {
+        let Some(builder) = self.builder.macho_mut() else {
+            return;
+        };
+        for symbol in &mut builder.symbols {
+            if names.contains(&*symbol.name) {
+                #[cfg(feature = "logging")]
+                info!("Deleting symbol {}", symbol.name);
+                symbol.delete = true;
+                self.modified = true;
+            }
+        }
+    }
+
+    /// Rename symbols in the symbol table.
+    ///
+    /// The `names` map is from old names to new names.
+    pub fn macho_rename_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
+        let Some(builder) = self.builder.macho_mut() else {
+            return;
+        };
+        for symbol in &mut builder.symbols {
+            if let Some(name) = names.get(&*symbol.name) {
+                let name = name.clone().into();
+                #[cfg(feature = "logging")]
+                info!("Renaming symbol {} to {}", symbol.name, name);
+                symbol.name = name;
+                self.modified = true;
+            }
+        }
+    }
+
+    pub(crate) fn macho_delete_sections(&mut self, names: &HashSet<Vec<u8>>) {
+        let Some(builder) = self.builder.macho_mut() else {
+            return;
+        };
+        for section in &mut builder.sections {
+            if names.iter().any(|name| section_matches(section, name)) {
+                #[cfg(feature = "logging")]
+                info!("Deleting section {},{}", section.segment_name, section.name);
+                // Symbols in the section will be deleted by delete_orphan_symbols.
+                section.delete = true;
+                self.modified = true;
+            }
+        }
+    }
+
+    pub(crate) fn macho_rename_sections(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
+        let Some(builder) = self.builder.macho_mut() else {
+            return;
+        };
+        for section in &mut builder.sections {
+            let Some(name) = names
+                .iter()
+                .find_map(|(old, new)| section_matches(section, old).then_some(new))
+            else {
+                continue;
+            };
+            let name = name.clone().into();
+            #[cfg(feature = "logging")]
+            info!(
+                "Renaming section {},{} to {}",
+                section.segment_name, section.name, name
+            );
+            section.name = name;
+            self.modified = true;
+        }
+    }
+
+    pub(crate) fn macho_modify(&mut self, options: MachOOptions) -> Result<()> {
+        if !options.delete_rpath.is_empty() {
+            self.macho_delete_rpath(&options.delete_rpath)?;
+        }
+        if !options.change_rpath.is_empty() {
+            self.macho_change_rpath(&options.change_rpath)?;
+        }
+        if !options.add_rpath.is_empty() {
+            self.macho_add_rpath(&options.add_rpath)?;
+        }
+        if let Some(name) = options.set_id_dylib {
+            self.macho_set_id_dylib(name)?;
+        }
+        if !options.change_load_dylib.is_empty() {
+            self.macho_change_load_dylib(&options.change_load_dylib)?;
+        }
+        Ok(())
+    }
+
+    /// Find the paths of the `LC_RPATH` load commands.
+    pub fn macho_rpaths(&self) -> impl Iterator<Item = &[u8]> {
+        let commands = self
+            .builder
+            .macho()
+            .map(|builder| builder.load_commands.as_slice())
+            .unwrap_or(&[]);
+        commands.iter().filter_map(|command| match command {
+            LoadCommand::Rpath(path) => Some(path.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Delete `LC_RPATH` load commands with the given paths.
+    pub fn macho_delete_rpath(&mut self, paths: &HashSet<Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .macho_mut()
+            .ok_or_else(|| Error::modify("Not a Mach-O file; can't delete rpath"))?;
+        let mut modified = false;
+        builder.load_commands.retain(|command| {
+            let LoadCommand::Rpath(path) = command else {
+                return true;
+            };
+            if !paths.contains(path.as_slice()) {
+                return true;
+            }
+
+            #[cfg(feature = "logging")]
+            info!("Deleting LC_RPATH {}", path);
+            modified = true;
+            false
+        });
+        if modified {
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    /// Change the paths of `LC_RPATH` load commands.
+    ///
+    /// The `paths` map is from old paths to new paths.
+    pub fn macho_change_rpath(&mut self, paths: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .macho_mut()
+            .ok_or_else(|| Error::modify("Not a Mach-O file; can't change rpath"))?;
+        for command in &mut builder.load_commands {
+            let LoadCommand::Rpath(path) = command else {
+                continue;
+            };
+            let Some(new_path) = paths.get(path.as_slice()) else {
+                continue;
+            };
+
+            let new_path = new_path.clone().into();
+            #[cfg(feature = "logging")]
+            info!("Changing LC_RPATH {} to {}", path, new_path);
+            *path = new_path;
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    /// Add `LC_RPATH` load commands after the existing load commands.
+    ///
+    /// This does not add a load command if the path is already present.
+    pub fn macho_add_rpath(&mut self, paths: &[Vec<u8>]) -> Result<()> {
+        let builder = self
+            .builder
+            .macho_mut()
+            .ok_or_else(|| Error::modify("Not a Mach-O file; can't add rpath"))?;
+        let mut found = HashSet::new();
+        for command in &builder.load_commands {
+            if let LoadCommand::Rpath(path) = command {
+                found.insert(path.clone());
+            }
+        }
+        for path in paths {
+            let path = build::ByteString::from(path.clone());
+            if !found.insert(path.clone()) {
+                continue;
+            }
+
+            #[cfg(feature = "logging")]
+            info!("Adding LC_RPATH {}", path);
+            builder.load_commands.push(LoadCommand::Rpath(path));
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    /// Find the install name in the `LC_ID_DYLIB` load command.
+    pub fn macho_id_dylib(&self) -> Option<&[u8]> {
+        let builder = self.builder.macho()?;
+        builder
+            .load_commands
+            .iter()
+            .find_map(|command| match command {
+                LoadCommand::Dylib(dylib) if dylib.cmd == macho::LC_ID_DYLIB => {
+                    Some(dylib.name.as_slice())
+                }
+                _ => None,
+            })
+    }
+
+    /// Set the install name in the `LC_ID_DYLIB` load command.
+    pub fn macho_set_id_dylib(&mut self, name: Vec<u8>) -> Result<()> {
+        let dylib = self
+            .builder
+            .macho_mut()
+            .and_then(|builder| {
+                builder
+                    .load_commands
+                    .iter_mut()
+                    .find_map(|command| match command {
+                        LoadCommand::Dylib(dylib) if dylib.cmd == macho::LC_ID_DYLIB => Some(dylib),
+                        _ => None,
+                    })
+            })
+            .ok_or_else(|| Error::modify("No LC_ID_DYLIB command found; can't set id"))?;
+        dylib.name = name.into();
+        #[cfg(feature = "logging")]
+        info!("Setting LC_ID_DYLIB to {}", dylib.name);
+        self.modified = true;
+        Ok(())
+    }
+
+    /// Find the paths of the dylib load commands such as `LC_LOAD_DYLIB`.
+    ///
+    /// This does not include `LC_ID_DYLIB`.
+    pub fn macho_load_dylibs(&self) -> impl Iterator<Item = &[u8]> {
+        let commands = self
+            .builder
+            .macho()
+            .map(|builder| builder.load_commands.as_slice())
+            .unwrap_or(&[]);
+        commands.iter().filter_map(|command| match command {
+            LoadCommand::Dylib(dylib) if is_load_dylib(dylib) => Some(dylib.name.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Change the paths of dylib load commands.
+    ///
+    /// This changes `LC_LOAD_DYLIB`, `LC_LOAD_WEAK_DYLIB`, `LC_REEXPORT_DYLIB`,
+    /// `LC_LAZY_LOAD_DYLIB` and `LC_LOAD_UPWARD_DYLIB` load commands.
+    ///
+    /// The `names` map is from old paths to new paths.
+    pub fn macho_change_load_dylib(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .macho_mut()
+            .ok_or_else(|| Error::modify("Not a Mach-O file; can't change load dylib"))?;
+        for command in &mut builder.load_commands {
+            let LoadCommand::Dylib(dylib) = command else {
+                continue;
+            };
+            if !is_load_dylib(dylib) {
+                continue;
+            }
+            let Some(name) = names.get(dylib.name.as_slice()) else {
+                continue;
+            };
+
+            let name = name.clone().into();
+            #[cfg(feature = "logging")]
+            info!("Changing dylib {} to {}", dylib.name, name);
+            dylib.name = name;
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn macho_finalize(&mut self) -> Result<()> {
+        let Some(builder) = self.builder.macho_mut() else {
+            return Ok(());
+        };
+        if self.modified {
+            builder.delete_orphan_symbols();
+            #[cfg(feature = "logging")]
+            if builder.load_commands.iter().any(|command| {
+                matches!(
+                    command,
+                    LoadCommand::LinkeditData {
+                        cmd: macho::LC_CODE_SIGNATURE,
+                        ..
+                    }
+                )
+            }) {
+                info!("Code signature is no longer valid and must be recreated");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return true if the name matches either `sectname` or `segname,sectname`.
+fn section_matches(section: &Section<'_>, name: &[u8]) -> bool {
+    if name == section.name.as_slice() {
+        return true;
+    }
+    let segment_name = section.segment_name.as_slice();
+    name.len() == segment_name.len() + 1 + section.name.len()
+        && name.starts_with(segment_name)
+        && name[segment_name.len()] == b','
+        && name.ends_with(&section.name)
+}
+
+fn is_load_dylib(dylib: &Dylib<'_>) -> bool {
+    dylib.cmd != macho::LC_ID_DYLIB
+}
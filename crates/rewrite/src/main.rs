@@ -124,6 +124,78 @@ fn main() -> Result<()> {
                 .value_name("path")
                 .value_parser(clap::value_parser!(String))
                 .help("Set the interpreter path in the PT_INTERP segment"),
+            Arg::new("macho-print-rpath")
+                .long("macho-print-rpath")
+                .action(ArgAction::SetTrue)
+                .help("Print the paths of the LC_RPATH load commands"),
+            Arg::new("macho-delete-rpath")
+                .long("macho-delete-rpath")
+                .value_name("path")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Delete the LC_RPATH load command with the given path"),
+            Arg::new("macho-change-rpath")
+                .long("macho-change-rpath")
+                .value_name("old=new")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Change the path of an LC_RPATH load command from <old> to <new>"),
+            Arg::new("macho-add-rpath")
+                .long("macho-add-rpath")
+                .value_name("path")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Add an LC_RPATH load command with the given path"),
+            Arg::new("macho-print-id-dylib")
+                .long("macho-print-id-dylib")
+                .action(ArgAction::SetTrue)
+                .help("Print the install name in the LC_ID_DYLIB load command"),
+            Arg::new("macho-set-id-dylib")
+                .long("macho-set-id-dylib")
+                .value_name("name")
+                .value_parser(clap::value_parser!(String))
+                .help("Set the install name in the LC_ID_DYLIB load command"),
+            Arg::new("macho-print-load-dylibs")
+                .long("macho-print-load-dylibs")
+                .action(ArgAction::SetTrue)
+                .help("Print the paths of the dylib load commands such as LC_LOAD_DYLIB"),
+            Arg::new("macho-change-load-dylib")
+                .long("macho-change-load-dylib")
+                .value_name("old=new")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Change the path of a dylib load command from <old> to <new>"),
+            Arg::new("pe-set-subsystem")
+                .long("pe-set-subsystem")
+                .value_name("subsystem")
+                .value_parser(clap::value_parser!(String))
+                .help(
+                    "Set the subsystem in the optional header. \
+                    This is either a number or a name such as 'windows' or 'console'",
+                ),
+            Arg::new("pe-set-dll-characteristics")
+                .long("pe-set-dll-characteristics")
+                .value_name("flags")
+                .value_parser(clap::value_parser!(String))
+                .help("Set the DLL characteristics in the optional header to a number"),
+            Arg::new("pe-delete-certificates")
+                .long("pe-delete-certificates")
+                .action(ArgAction::SetTrue)
+                .help("Delete the certificate table"),
+            Arg::new("pe-strip-debug-directories")
+                .long("pe-strip-debug-directories")
+                .action(ArgAction::SetTrue)
+                .help("Delete the debug directory and the debug data that it refers to"),
+            Arg::new("pe-print-import-dlls")
+                .long("pe-print-import-dlls")
+                .action(ArgAction::SetTrue)
+                .help("Print the names of the DLLs in the import table"),
+            Arg::new("pe-rename-import-dll")
+                .long("pe-rename-import-dll")
+                .value_name("old=new")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Change the name of a DLL in the import table from <old> to <new>"),
             Arg::new("ignore-unknown-format")
                 .long("ignore-unknown-format")
                 .action(ArgAction::SetTrue)
@@ -153,6 +225,16 @@ fn main() -> Result<()> {
                     "elf-add-needed",
                     "elf-set-soname",
                     "elf-set-interpreter",
+                    "macho-delete-rpath",
+                    "macho-change-rpath",
+                    "macho-add-rpath",
+                    "macho-set-id-dylib",
+                    "macho-change-load-dylib",
+                    "pe-set-subsystem",
+                    "pe-set-dll-characteristics",
+                    "pe-delete-certificates",
+                    "pe-strip-debug-directories",
+                    "pe-rename-import-dll",
                 ])
                 .multiple(true)
                 .required(false)
@@ -179,7 +261,13 @@ fn main() -> Result<()> {
 
     if matches.get_flag("ignore-unknown-format") {
         match object::FileKind::parse(in_data) {
-            Ok(object::FileKind::Elf32) | Ok(object::FileKind::Elf64) => {}
+            Ok(object::FileKind::Elf32)
+            | Ok(object::FileKind::Elf64)
+            | Ok(object::FileKind::MachO32)
+            | Ok(object::FileKind::MachO64)
+            | Ok(object::FileKind::Coff)
+            | Ok(object::FileKind::Pe32)
+            | Ok(object::FileKind::Pe64) => {}
             _ => {
                 info!(
                     "Ignoring input file '{}' with unknown format",
@@ -212,6 +300,26 @@ fn main() -> Result<()> {
             println!("{}", String::from_utf8_lossy(interp));
         }
     }
+    if matches.get_flag("macho-print-rpath") {
+        for rpath in rewriter.macho_rpaths() {
+            println!("{}", String::from_utf8_lossy(rpath));
+        }
+    }
+    if matches.get_flag("macho-print-id-dylib") {
+        if let Some(id) = rewriter.macho_id_dylib() {
+            println!("{}", String::from_utf8_lossy(id));
+        }
+    }
+    if matches.get_flag("macho-print-load-dylibs") {
+        for dylib in rewriter.macho_load_dylibs() {
+            println!("{}", String::from_utf8_lossy(dylib));
+        }
+    }
+    if matches.get_flag("pe-print-import-dlls") {
+        for dll in rewriter.pe_import_dlls() {
+            println!("{}", String::from_utf8_lossy(dll));
+        }
+    }
 
     // TODO: allow replacing input file
     let Some(out_path) = matches.get_one::<PathBuf>("output") else {
@@ -332,6 +440,80 @@ fn main() -> Result<()> {
     options.elf.set_interpreter = matches
         .get_one::<String>("elf-set-interpreter")
         .map(|arg| arg.clone().into_bytes());
+    options.macho.delete_rpath = matches
+        .get_many::<String>("macho-delete-rpath")
+        .unwrap_or_default()
+        .map(|arg| arg.clone().into_bytes())
+        .collect();
+    for arg in matches
+        .get_many::<String>("macho-change-rpath")
+        .unwrap_or_default()
+    {
+        let names: Vec<&[u8]> = arg.as_bytes().splitn(2, |byte| *byte == b'=').collect();
+        if names.len() != 2 {
+            return Err(
+                anyhow!(
+                "Invalid change rpath: `{}`. --macho-change-rpath expects argument of the form: <old>=<new>", arg)
+            );
+        }
+        options
+            .macho
+            .change_rpath
+            .insert(names[0].to_vec(), names[1].to_vec());
+    }
+    options.macho.add_rpath = matches
+        .get_many::<String>("macho-add-rpath")
+        .unwrap_or_default()
+        .map(|arg| arg.clone().into_bytes())
+        .collect();
+    options.macho.set_id_dylib = matches
+        .get_one::<String>("macho-set-id-dylib")
+        .map(|arg| arg.clone().into_bytes());
+    for arg in matches
+        .get_many::<String>("macho-change-load-dylib")
+        .unwrap_or_default()
+    {
+        let names: Vec<&[u8]> = arg.as_bytes().splitn(2, |byte| *byte == b'=').collect();
+        if names.len() != 2 {
+            return Err(
+                anyhow!(
+                "Invalid change load dylib: `{}`. --macho-change-load-dylib expects argument of the form: <old>=<new>", arg)
+            );
+        }
+        options
+            .macho
+            .change_load_dylib
+            .insert(names[0].to_vec(), names[1].to_vec());
+    }
+    if let Some(arg) = matches.get_one::<String>("pe-set-subsystem") {
+        options.pe.set_subsystem = Some(parse_pe_subsystem(arg)?);
+    }
+    if let Some(arg) = matches.get_one::<String>("pe-set-dll-characteristics") {
+        options.pe.set_dll_characteristics = Some(parse_u16(arg).ok_or_else(|| {
+            anyhow!(
+                "Invalid DLL characteristics: `{}`. --pe-set-dll-characteristics expects a number",
+                arg
+            )
+        })?);
+    }
+    options.pe.delete_certificates = matches.get_flag("pe-delete-certificates");
+    options.pe.strip_debug_directories = matches.get_flag("pe-strip-debug-directories");
+    for arg in matches
+        .get_many::<String>("pe-rename-import-dll")
+        .unwrap_or_default()
+    {
+        let names: Vec<&[u8]> = arg.as_bytes().splitn(2, |byte| *byte == b'=').collect();
+        if names.len() != 2 {
+            return Err(
+                anyhow!(
+                "Invalid rename import DLL: `{}`. --pe-rename-import-dll expects argument of the form: <old>=<new>", arg)
+            );
+        }
+        options
+            .pe
+            .rename_import_dll
+            .insert(names[0].to_vec(), names[1].to_vec());
+    }
 
     rewriter.modify(options)?;
 
@@ -376,3 +558,38 @@ fn main() -> Result<()> {
     })?;
     Ok(())
 }
+
+/// Parse a decimal or hexadecimal number.
+fn parse_u16(arg: &str) -> Option<u16> {
+    match arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => arg.parse().ok(),
+    }
+}
+
+/// Parse a PE subsystem name or number.
+fn parse_pe_subsystem(arg: &str) -> Result<u16> {
+    use object::pe;
+    let subsystem = match arg.to_ascii_lowercase().as_str() {
+        "native" => pe::IMAGE_SUBSYSTEM_NATIVE,
+        "windows" => pe::IMAGE_SUBSYSTEM_WINDOWS_GUI,
+        "console" => pe::IMAGE_SUBSYSTEM_WINDOWS_CUI,
+        "os2-console" => pe::IMAGE_SUBSYSTEM_OS2_CUI,
+        "posix-console" => pe::IMAGE_SUBSYSTEM_POSIX_CUI,
+        "native-windows" => pe::IMAGE_SUBSYSTEM_NATIVE_WINDOWS,
+        "windows-ce" => pe::IMAGE_SUBSYSTEM_WINDOWS_CE_GUI,
+        "efi-application" => pe::IMAGE_SUBSYSTEM_EFI_APPLICATION,
+        "efi-boot-service-driver" => pe::IMAGE_SUBSYSTEM_EFI_BOOT_SERVICE_DRIVER,
+        "efi-runtime-driver" => pe::IMAGE_SUBSYSTEM_EFI_RUNTIME_DRIVER,
+        "efi-rom" => pe::IMAGE_SUBSYSTEM_EFI_ROM,
+        "xbox" => pe::IMAGE_SUBSYSTEM_XBOX,
+        "windows-boot-application" => pe::IMAGE_SUBSYSTEM_WINDOWS_BOOT_APPLICATION,
+        _ => parse_u16(arg).ok_or_else(|| {
+            anyhow!(
+                "Invalid subsystem: `{}`. --pe-set-subsystem expects a name or a number",
+                arg
+            )
+        })?,
+    };
+    Ok(subsystem)
+}
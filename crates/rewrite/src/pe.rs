@@ -0,0 +1,334 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "logging")]
+use log::info;
+use object::pe;
+
+use super::{Error, Result, Rewriter};
+
+/// Options for modifying a PE or COFF file.
+///
+/// This struct contains options for modifying a PE or COFF file. It is
+/// contained in the [`Options`](super::Options) struct.
+///
+/// Options are listed in the order they are processed.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct PeOptions {
+    /// Set the subsystem in the optional header.
+    ///
+    /// See [`Rewriter::pe_set_subsystem`].
+    pub set_subsystem: Option<u16>,
+    /// Set the DLL characteristics in the optional header.
+    ///
+    /// See [`Rewriter::pe_set_dll_characteristics`].
+    pub set_dll_characteristics: Option<u16>,
+    /// Delete the certificate table.
+    ///
+    /// See [`Rewriter::pe_delete_certificates`].
+    pub delete_certificates: bool,
+    /// Strip the debug directory.
+    ///
+    /// See [`Rewriter::pe_strip_debug_directories`].
+    pub strip_debug_directories: bool,
+    /// Rename DLLs in the import table.
+    ///
+    /// See [`Rewriter::pe_rename_import_dlls`].
+    pub rename_import_dll: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Rewriter<'_> {
+    /// Delete symbols from the COFF symbol table.
+    ///
+    /// This only applies to COFF object files.
+    pub fn coff_delete_symbols(&mut self, names: &HashSet<Vec<u8>>) {
+        let Some(builder) = self.builder.coff_mut() else {
+            return;
+        };
+        for symbol in &mut builder.symbols {
+            if symbol.storage_class == pe::IMAGE_SYM_CLASS_FILE {
+                continue;
+            }
+            if names.contains(&*symbol.name) {
+                #[cfg(feature = "logging")]
+                info!("Deleting symbol {}", symbol.name);
+                symbol.delete = true;
+                self.modified = true;
+            }
+        }
+    }
+
+    /// Rename symbols in the COFF symbol table.
+    ///
+    /// This only applies to COFF object files.
+    ///
+    /// The `names` map is from old names to new names.
+    pub fn coff_rename_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
+        let Some(builder) = self.builder.coff_mut() else {
+            return;
+        };
+        for symbol in &mut builder.symbols {
+            if symbol.storage_class == pe::IMAGE_SYM_CLASS_FILE {
+                continue;
+            }
+            if let Some(name) = names.get(&*symbol.name) {
+                let name = name.clone().into();
+                #[cfg(feature = "logging")]
+                info!("Renaming symbol {} to {}", symbol.name, name);
+                symbol.name = name;
+                self.modified = true;
+            }
+        }
+    }
+
+    pub(crate) fn coff_delete_sections(&mut self, names: &HashSet<Vec<u8>>) {
+        if let Some(builder) = self.builder.coff_mut() {
+            for section in &mut builder.sections {
+                if names.contains(&*section.name) {
+                    #[cfg(feature = "logging")]
+                    info!("Deleting section {}", section.name);
+                    // Associated sections and symbols will be deleted by delete_orphans.
+                    section.delete = true;
+                    self.modified = true;
+                }
+            }
+        } else if let Some(builder) = self.builder.pe_mut() {
+            for section in &mut builder.sections {
+                if names.contains(&*section.name) {
+                    #[cfg(feature = "logging")]
+                    info!("Deleting section {}", section.name);
+                    section.delete = true;
+                    self.modified = true;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn coff_rename_sections(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
+        if let Some(builder) = self.builder.coff_mut() {
+            for section in &mut builder.sections {
+                if let Some(name) = names.get(&*section.name) {
+                    let name = name.clone().into();
+                    #[cfg(feature = "logging")]
+                    info!("Renaming section {} to {}", section.name, name);
+                    section.name = name;
+                    self.modified = true;
+                }
+            }
+        } else if let Some(builder) = self.builder.pe_mut() {
+            for section in &mut builder.sections {
+                if let Some(name) = names.get(&*section.name) {
+                    let name = name.clone().into();
+                    #[cfg(feature = "logging")]
+                    info!("Renaming section {} to {}", section.name, name);
+                    section.name = name;
+                    self.modified = true;
+                }
+            }
+        }
+    }
+
+    /// Check that the sections can be deleted and renamed in a PE image.
+    ///
+    /// Only sections at the end of a PE image can be deleted, and section names
+    /// must not be longer than 8 bytes.
+    pub(crate) fn pe_check_sections(
+        &self,
+        delete: &HashSet<Vec<u8>>,
+        rename: &HashMap<Vec<u8>, Vec<u8>>,
+    ) -> Result<()> {
+        let Some(builder) = self.builder.pe() else {
+            return Ok(());
+        };
+        let mut deleted = None;
+        for section in &builder.sections {
+            if delete.contains(&*section.name) {
+                deleted.get_or_insert(&section.name);
+                continue;
+            }
+            if let Some(deleted) = deleted {
+                return Err(Error::modify(format!(
+                    "Can't delete PE section `{}` that is followed by section `{}`",
+                    deleted, section.name
+                )));
+            }
+            if let Some(name) = rename.get(&*section.name) {
+                if name.len() > 8 {
+                    return Err(Error::modify(format!(
+                        "Can't rename PE section `{}` to `{}` that is longer than 8 bytes",
+                        section.name,
+                        String::from_utf8_lossy(name)
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn pe_modify(&mut self, options: PeOptions) -> Result<()> {
+        if let Some(subsystem) = options.set_subsystem {
+            self.pe_set_subsystem(subsystem)?;
+        }
+        if let Some(dll_characteristics) = options.set_dll_characteristics {
+            self.pe_set_dll_characteristics(dll_characteristics)?;
+        }
+        if options.delete_certificates {
+            self.pe_delete_certificates()?;
+        }
+        if options.strip_debug_directories {
+            self.pe_strip_debug_directories()?;
+        }
+        if !options.rename_import_dll.is_empty() {
+            self.pe_rename_import_dlls(&options.rename_import_dll)?;
+        }
+        Ok(())
+    }
+
+    /// Get the subsystem from the optional header.
+    ///
+    /// Returns `None` if the file is not a PE image.
+    pub fn pe_subsystem(&self) -> Option<u16> {
+        let builder = self.builder.pe()?;
+        Some(builder.nt_headers.subsystem)
+    }
+
+    /// Set the subsystem in the optional header.
+    ///
+    /// This is one of the `IMAGE_SUBSYSTEM_*` values.
+    pub fn pe_set_subsystem(&mut self, subsystem: u16) -> Result<()> {
+        let builder = self
+            .builder
+            .pe_mut()
+            .ok_or_else(|| Error::modify("Not a PE file; can't set subsystem"))?;
+        if builder.nt_headers.subsystem == subsystem {
+            return Ok(());
+        }
+        #[cfg(feature = "logging")]
+        info!(
+            "Setting subsystem from {} to {}",
+            builder.nt_headers.subsystem, subsystem
+        );
+        builder.nt_headers.subsystem = subsystem;
+        self.modified = true;
+        Ok(())
+    }
+
+    /// Get the DLL characteristics from the optional header.
+    ///
+    /// Returns `None` if the file is not a PE image.
+    pub fn pe_dll_characteristics(&self) -> Option<u16> {
+        let builder = self.builder.pe()?;
+        Some(builder.nt_headers.dll_characteristics)
+    }
+
+    /// Set the DLL characteristics in the optional header.
+    ///
+    /// This is a combination of the `IMAGE_DLLCHARACTERISTICS_*` flags.
+    pub fn pe_set_dll_characteristics(&mut self, dll_characteristics: u16) -> Result<()> {
+        let builder = self
+            .builder
+            .pe_mut()
+            .ok_or_else(|| Error::modify("Not a PE file; can't set DLL characteristics"))?;
+        if builder.nt_headers.dll_characteristics == dll_characteristics {
+            return Ok(());
+        }
+        #[cfg(feature = "logging")]
+        info!(
+            "Setting DLL characteristics from {:#x} to {:#x}",
+            builder.nt_headers.dll_characteristics, dll_characteristics
+        );
+        builder.nt_headers.dll_characteristics = dll_characteristics;
+        self.modified = true;
+        Ok(())
+    }
+
+    /// Delete the certificate table.
+    ///
+    /// This removes any Authenticode signatures.
+    pub fn pe_delete_certificates(&mut self) -> Result<()> {
+        let builder = self
+            .builder
+            .pe_mut()
+            .ok_or_else(|| Error::modify("Not a PE file; can't delete certificates"))?;
+        if builder.certificate_table.take().is_some() {
+            #[cfg(feature = "logging")]
+            info!("Deleting certificate table");
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    /// Strip the debug directory.
+    ///
+    /// This removes the debug data directory, and zeroes the debug directory
+    /// entries and the data that they refer to, such as CodeView records.
+    /// The sections containing this data are not resized.
+    pub fn pe_strip_debug_directories(&mut self) -> Result<()> {
+        let builder = self
+            .builder
+            .pe_mut()
+            .ok_or_else(|| Error::modify("Not a PE file; can't strip debug directories"))?;
+        if builder.strip_debug_directories().map_err(Error::parse)? {
+            #[cfg(feature = "logging")]
+            info!("Stripping debug directory");
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    /// Find the names of the DLLs in the import table.
+    pub fn pe_import_dlls(&self) -> impl Iterator<Item = &[u8]> {
+        let imports = self
+            .builder
+            .pe()
+            .map(|builder| builder.imports.as_slice())
+            .unwrap_or(&[]);
+        imports.iter().map(|import| import.name.as_slice())
+    }
+
+    /// Rename DLLs in the import table.
+    ///
+    /// The `names` map is from old names to new names. Names are matched
+    /// ignoring ASCII case, since the Windows loader does the same.
+    ///
+    /// New names that are longer than the old names are appended to the section
+    /// containing the import table. This requires unused address space after
+    /// the end of that section.
+    pub fn pe_rename_import_dlls(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .pe_mut()
+            .ok_or_else(|| Error::modify("Not a PE file; can't rename import DLLs"))?;
+        for import in &mut builder.imports {
+            let Some(name) = names
+                .iter()
+                .find_map(|(old, new)| old.eq_ignore_ascii_case(&import.name).then_some(new))
+            else {
+                continue;
+            };
+
+            let name = name.clone().into();
+            #[cfg(feature = "logging")]
+            info!("Renaming import DLL {} to {}", import.name, name);
+            import.name = name;
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn pe_finalize(&mut self) -> Result<()> {
+        if !self.modified {
+            return Ok(());
+        }
+        if let Some(builder) = self.builder.coff_mut() {
+            builder.delete_orphans();
+        }
+        #[cfg(feature = "logging")]
+        if let Some(builder) = self.builder.pe() {
+            if builder.certificate_table.is_some() {
+                info!("Certificate table is no longer valid and must be recreated");
+            }
+        }
+        Ok(())
+    }
+}
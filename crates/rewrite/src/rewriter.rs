@@ -30,6 +30,10 @@ pub struct Options {
     pub rename_sections: HashMap<Vec<u8>, Vec<u8>>,
     /// Options that are specific to ELF files.
     pub elf: super::ElfOptions,
+    /// Options that are specific to Mach-O files.
+    pub macho: super::MachOOptions,
+    /// Options that are specific to PE and COFF files.
+    pub pe: super::PeOptions,
 }
 
 /// A rewriter for object and executable files.
@@ -37,14 +41,87 @@ pub struct Options {
 /// This struct provides a way to read a file, modify it, and write it back.
 #[derive(Debug)]
 pub struct Rewriter<'data> {
-    pub(crate) builder: build::elf::Builder<'data>,
+    pub(crate) builder: Builder<'data>,
     pub(crate) modified: bool,
 }
 
+/// The builder for the format of the file being rewritten.
+#[derive(Debug)]
+pub(crate) enum Builder<'data> {
+    Elf(build::elf::Builder<'data>),
+    MachO(build::macho::Builder<'data>),
+    Coff(build::coff::Builder<'data>),
+    Pe(build::pe::Builder<'data>),
+}
+
+impl<'data> Builder<'data> {
+    pub(crate) fn elf(&self) -> Option<&build::elf::Builder<'data>> {
+        match self {
+            Builder::Elf(builder) => Some(builder),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn elf_mut(&mut self) -> Option<&mut build::elf::Builder<'data>> {
+        match self {
+            Builder::Elf(builder) => Some(builder),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn macho(&self) -> Option<&build::macho::Builder<'data>> {
+        match self {
+            Builder::MachO(builder) => Some(builder),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn macho_mut(&mut self) -> Option<&mut build::macho::Builder<'data>> {
+        match self {
+            Builder::MachO(builder) => Some(builder),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn coff_mut(&mut self) -> Option<&mut build::coff::Builder<'data>> {
+        match self {
+            Builder::Coff(builder) => Some(builder),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn pe(&self) -> Option<&build::pe::Builder<'data>> {
+        match self {
+            Builder::Pe(builder) => Some(builder),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn pe_mut(&mut self) -> Option<&mut build::pe::Builder<'data>> {
+        match self {
+            Builder::Pe(builder) => Some(builder),
+            _ => None,
+        }
+    }
+}
+
 impl<'data> Rewriter<'data> {
     /// Read a file and create a new rewriter.
+    ///
+    /// ELF, Mach-O, PE and COFF files are supported.
     pub fn read(data: &'data [u8]) -> Result<Self> {
-        let builder = build::elf::Builder::read(data).map_err(Error::parse)?;
+        let builder = match object::FileKind::parse(data) {
+            Ok(object::FileKind::MachO32 | object::FileKind::MachO64) => {
+                Builder::MachO(build::macho::Builder::read(data).map_err(Error::parse)?)
+            }
+            Ok(object::FileKind::Coff) => {
+                Builder::Coff(build::coff::Builder::read(data).map_err(Error::parse)?)
+            }
+            Ok(object::FileKind::Pe32 | object::FileKind::Pe64) => {
+                Builder::Pe(build::pe::Builder::read(data).map_err(Error::parse)?)
+            }
+            _ => Builder::Elf(build::elf::Builder::read(data).map_err(Error::parse)?),
+        };
         Ok(Self {
             builder,
             modified: false,
@@ -54,8 +131,15 @@ impl<'data> Rewriter<'data> {
     /// Write the file to an output stream.
     pub fn write<W: std::io::Write>(mut self, w: W) -> Result<()> {
         self.elf_finalize()?;
+        self.macho_finalize()?;
+        self.pe_finalize()?;
         let mut buffer = object::write::StreamingBuffer::new(w);
-        self.builder.write(&mut buffer).map_err(Error::write)?;
+        match self.builder {
+            Builder::Elf(builder) => builder.write(&mut buffer).map_err(Error::write)?,
+            Builder::MachO(builder) => builder.write(&mut buffer).map_err(Error::write)?,
+            Builder::Coff(builder) => builder.write(&mut buffer).map_err(Error::write)?,
+            Builder::Pe(builder) => builder.write(&mut buffer).map_err(Error::write)?,
+        }
         buffer.result().map_err(Error::io)
     }
 
@@ -67,6 +151,7 @@ impl<'data> Rewriter<'data> {
         if !options.rename_symbols.is_empty() {
             self.rename_symbols(&options.rename_symbols);
         }
+        self.pe_check_sections(&options.delete_sections, &options.rename_sections)?;
         if !options.delete_sections.is_empty() {
             self.delete_sections(&options.delete_sections);
         }
@@ -74,6 +159,8 @@ impl<'data> Rewriter<'data> {
             self.rename_sections(&options.rename_sections);
         }
         self.elf_modify(options.elf)?;
+        self.macho_modify(options.macho)?;
+        self.pe_modify(options.pe)?;
         Ok(())
     }
 
@@ -81,9 +168,18 @@ impl<'data> Rewriter<'data> {
     ///
     /// For ELF files, this deletes symbols from both the symbol table and the
     /// dynamic symbol table.
+    ///
+    /// For Mach-O files, writing the file will fail if a deleted symbol is
+    /// still referenced by a relocation or the indirect symbol table.
+    ///
+    /// For COFF object files, writing the file will fail if a deleted symbol is
+    /// still referenced by a relocation. The symbol table of PE images is not
+    /// modified.
     pub fn delete_symbols(&mut self, names: &HashSet<Vec<u8>>) {
         self.elf_delete_symbols(names);
         self.elf_delete_dynamic_symbols(names);
+        self.macho_delete_symbols(names);
+        self.coff_delete_symbols(names);
     }
 
     /// Rename symbols in the symbol table.
@@ -95,17 +191,39 @@ impl<'data> Rewriter<'data> {
     pub fn rename_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
         self.elf_rename_symbols(names);
         self.elf_rename_dynamic_symbols(names);
+        self.macho_rename_symbols(names);
+        self.coff_rename_symbols(names);
     }
 
     /// Delete sections from the file.
+    ///
+    /// For Mach-O files, a section may be named either by its section name, or by
+    /// its segment and section names separated by a comma, such as `__TEXT,__text`.
+    /// The section data is only removed from object files. For other Mach-O files,
+    /// only the section header is removed, and the data remains in its segment.
+    ///
+    /// For PE images, only sections at the end of the image can be deleted.
+    /// This is checked by [`Self::modify`], but if this method is called directly
+    /// then writing the file will fail instead.
     pub fn delete_sections(&mut self, names: &HashSet<Vec<u8>>) {
         self.elf_delete_sections(names);
+        self.macho_delete_sections(names);
+        self.coff_delete_sections(names);
     }
 
     /// Rename sections in the file.
     ///
     /// The `names` map is from old names to new names.
+    ///
+    /// For Mach-O files, the old names are matched as for [`Self::delete_sections`],
+    /// and the new name only replaces the section name.
+    ///
+    /// For PE images, the new names must not be longer than 8 bytes.
+    /// This is checked by [`Self::modify`], but if this method is called directly
+    /// then writing the file will fail instead.
     pub fn rename_sections(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
         self.elf_rename_sections(names);
+        self.macho_rename_sections(names);
+        self.coff_rename_sections(names);
     }
 }
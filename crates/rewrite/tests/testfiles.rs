@@ -1,3 +1,4 @@
+use object::{LittleEndian as LE, U16, U32};
 use object_examples::readobj;
 use std::path::Path;
 use std::{env, fs};
@@ -279,6 +280,353 @@ fn rewrite_nobits_offset_0() {
     fail_message(fail);
 }
 
+#[test]
+fn rewrite_pe() {
+    let print_options = readobj::PrintOptions {
+        string_indices: false,
+        ..readobj::PrintOptions::all()
+    };
+    let mut fail = false;
+    let in_data = pe_image();
+
+    let options = object_rewrite::Options::default();
+    fail |= testfile_data(&in_data, "pe/base.noop", options, &print_options);
+
+    let print_options = readobj::PrintOptions {
+        file: true,
+        ..readobj::PrintOptions::none()
+    };
+
+    let mut options = object_rewrite::Options::default();
+    options.pe.set_subsystem = Some(object::pe::IMAGE_SUBSYSTEM_WINDOWS_GUI);
+    options.pe.set_dll_characteristics = Some(object::pe::IMAGE_DLLCHARACTERISTICS_NX_COMPAT);
+    fail |= testfile_data(&in_data, "pe/base.set-subsystem", options, &print_options);
+
+    let mut options = object_rewrite::Options::default();
+    options.pe.delete_certificates = true;
+    fail |= testfile_data(
+        &in_data,
+        "pe/base.delete-certificates",
+        options,
+        &print_options,
+    );
+
+    let mut options = object_rewrite::Options::default();
+    options.pe.strip_debug_directories = true;
+    fail |= testfile_data(
+        &in_data,
+        "pe/base.strip-debug-directories",
+        options,
+        &print_options,
+    );
+
+    fail_message(fail);
+}
+
+#[test]
+fn rewrite_pe_import_dlls() {
+    let print_options = readobj::PrintOptions {
+        string_indices: false,
+        file: true,
+        sections: true,
+        pe_imports: true,
+        ..readobj::PrintOptions::none()
+    };
+    let mut fail = false;
+    let in_data = pe_image();
+
+    let mut options = object_rewrite::Options::default();
+    options.pe.rename_import_dll = [(b"kernel32.dll".to_vec(), b"kernelx.dll".to_vec())]
+        .into_iter()
+        .collect();
+    fail |= testfile_data(
+        &in_data,
+        "pe/base.rename-import-dll",
+        options,
+        &print_options,
+    );
+
+    let mut options = object_rewrite::Options::default();
+    options.pe.rename_import_dll = [(b"USER32.dll".to_vec(), b"longer-user32.dll".to_vec())]
+        .into_iter()
+        .collect();
+    fail |= testfile_data(
+        &in_data,
+        "pe/base.rename-import-dll-longer",
+        options,
+        &print_options,
+    );
+
+    fail_message(fail);
+}
+
+#[test]
+fn rewrite_pe_sections() {
+    let print_options = readobj::PrintOptions {
+        string_indices: false,
+        file: true,
+        sections: true,
+        ..readobj::PrintOptions::none()
+    };
+    let mut fail = false;
+    let in_data = pe_image();
+
+    let mut options = object_rewrite::Options::default();
+    options.delete_sections = [b".reloc".to_vec()].into_iter().collect();
+    fail |= testfile_data(&in_data, "pe/base.delete-section", options, &print_options);
+
+    let mut options = object_rewrite::Options::default();
+    options.rename_sections = [(b".rdata".to_vec(), b".rodata".to_vec())]
+        .into_iter()
+        .collect();
+    fail |= testfile_data(&in_data, "pe/base.rename-section", options, &print_options);
+
+    fail_message(fail);
+}
+
+#[test]
+fn rewrite_pe_sections_invalid() {
+    let in_data = pe_image();
+
+    let mut options = object_rewrite::Options::default();
+    options.delete_sections = [b".text".to_vec()].into_iter().collect();
+    let mut rewriter = object_rewrite::Rewriter::read(&in_data).unwrap();
+    let error = rewriter.modify(options).unwrap_err();
+    assert_eq!(error.kind(), object_rewrite::ErrorKind::Modify);
+
+    let mut options = object_rewrite::Options::default();
+    options.rename_sections = [(b".rdata".to_vec(), b".rodata.long".to_vec())]
+        .into_iter()
+        .collect();
+    let mut rewriter = object_rewrite::Rewriter::read(&in_data).unwrap();
+    let error = rewriter.modify(options).unwrap_err();
+    assert_eq!(error.kind(), object_rewrite::ErrorKind::Modify);
+}
+
+#[test]
+fn rewrite_coff() {
+    let print_options = readobj::PrintOptions {
+        string_indices: false,
+        ..readobj::PrintOptions::all()
+    };
+    let mut fail = false;
+    let in_data = coff_object();
+
+    let options = object_rewrite::Options::default();
+    fail |= testfile_data(&in_data, "coff/base.noop", options, &print_options);
+
+    let print_options = readobj::PrintOptions {
+        string_indices: false,
+        sections: true,
+        symbols: true,
+        relocations: true,
+        ..readobj::PrintOptions::none()
+    };
+
+    let mut options = object_rewrite::Options::default();
+    options.delete_symbols = [b"unused".to_vec()].into_iter().collect();
+    fail |= testfile_data(&in_data, "coff/base.delete-symbol", options, &print_options);
+
+    let mut options = object_rewrite::Options::default();
+    options.rename_symbols = [
+        (b"main".to_vec(), b"renamed_main".to_vec()),
+        (b"puts".to_vec(), b"renamed_puts".to_vec()),
+    ]
+    .into_iter()
+    .collect();
+    fail |= testfile_data(&in_data, "coff/base.rename-symbol", options, &print_options);
+
+    let mut options = object_rewrite::Options::default();
+    options.delete_sections = [b".text$inline".to_vec()].into_iter().collect();
+    fail |= testfile_data(
+        &in_data,
+        "coff/base.delete-section",
+        options,
+        &print_options,
+    );
+
+    let mut options = object_rewrite::Options::default();
+    options.rename_sections = [(b".data".to_vec(), b".data$renamed".to_vec())]
+        .into_iter()
+        .collect();
+    fail |= testfile_data(
+        &in_data,
+        "coff/base.rename-section",
+        options,
+        &print_options,
+    );
+
+    fail_message(fail);
+}
+
+#[test]
+fn rewrite_macho() {
+    let print_options = readobj::PrintOptions {
+        string_indices: false,
+        ..readobj::PrintOptions::all()
+    };
+    let mut fail = false;
+    let in_data = macho_object();
+
+    let options = object_rewrite::Options::default();
+    fail |= testfile_data(&in_data, "macho/base.noop", options, &print_options);
+
+    let print_options = readobj::PrintOptions {
+        string_indices: false,
+        sections: true,
+        symbols: true,
+        relocations: true,
+        ..readobj::PrintOptions::none()
+    };
+
+    let mut options = object_rewrite::Options::default();
+    options.delete_symbols = [b"_main".to_vec()].into_iter().collect();
+    fail |= testfile_data(
+        &in_data,
+        "macho/base.delete-symbol",
+        options,
+        &print_options,
+    );
+
+    let mut options = object_rewrite::Options::default();
+    options.rename_symbols = [(b"_helper".to_vec(), b"_renamed_helper".to_vec())]
+        .into_iter()
+        .collect();
+    fail |= testfile_data(
+        &in_data,
+        "macho/base.rename-symbol",
+        options,
+        &print_options,
+    );
+
+    let mut options = object_rewrite::Options::default();
+    options.delete_sections = [b"__LLVM,__bundle".to_vec()].into_iter().collect();
+    fail |= testfile_data(
+        &in_data,
+        "macho/base.delete-section",
+        options,
+        &print_options,
+    );
+
+    fail_message(fail);
+}
+
+#[test]
+fn rewrite_macho_invalid() {
+    let in_data = macho_object();
+
+    // `_helper` is the target of a relocation.
+    let mut options = object_rewrite::Options::default();
+    options.delete_symbols = [b"_helper".to_vec()].into_iter().collect();
+    let mut rewriter = object_rewrite::Rewriter::read(&in_data).unwrap();
+    rewriter.modify(options).unwrap();
+    let error = rewriter.write(&mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), object_rewrite::ErrorKind::Write);
+    let source = std::error::Error::source(&error).unwrap();
+    assert!(source.is::<object::build::Error>());
+}
+
+#[test]
+fn rewrite_macho_dylib() {
+    let print_options = readobj::PrintOptions {
+        string_indices: false,
+        ..readobj::PrintOptions::all()
+    };
+    let mut fail = false;
+    let in_data = macho_dylib();
+
+    let rewriter = object_rewrite::Rewriter::read(&in_data).unwrap();
+    assert_eq!(rewriter.macho_id_dylib(), Some(&b"@rpath/libfoo.dylib"[..]));
+    assert_eq!(
+        rewriter.macho_load_dylibs().collect::<Vec<_>>(),
+        [b"/usr/lib/libSystem.B.dylib"]
+    );
+    assert_eq!(
+        rewriter.macho_rpaths().collect::<Vec<_>>(),
+        [b"@loader_path/../lib"]
+    );
+
+    let options = object_rewrite::Options::default();
+    fail |= testfile_data(&in_data, "macho/libfoo.dylib.noop", options, &print_options);
+
+    let print_options = readobj::PrintOptions {
+        string_indices: false,
+        macho_load_commands: true,
+        ..readobj::PrintOptions::none()
+    };
+
+    let mut options = object_rewrite::Options::default();
+    options.macho.delete_rpath = [b"@loader_path/../lib".to_vec()].into_iter().collect();
+    fail |= testfile_data(
+        &in_data,
+        "macho/libfoo.dylib.delete-rpath",
+        options,
+        &print_options,
+    );
+
+    let mut options = object_rewrite::Options::default();
+    options.macho.change_rpath = [(
+        b"@loader_path/../lib".to_vec(),
+        b"@executable_path".to_vec(),
+    )]
+    .into_iter()
+    .collect();
+    fail |= testfile_data(
+        &in_data,
+        "macho/libfoo.dylib.change-rpath",
+        options,
+        &print_options,
+    );
+
+    let mut options = object_rewrite::Options::default();
+    options.macho.add_rpath = vec![b"/opt/lib".to_vec(), b"@loader_path/../lib".to_vec()];
+    fail |= testfile_data(
+        &in_data,
+        "macho/libfoo.dylib.add-rpath",
+        options,
+        &print_options,
+    );
+
+    let mut options = object_rewrite::Options::default();
+    options.macho.set_id_dylib = Some(b"/usr/local/lib/libfoo.1.dylib".to_vec());
+    fail |= testfile_data(
+        &in_data,
+        "macho/libfoo.dylib.set-id-dylib",
+        options,
+        &print_options,
+    );
+
+    let mut options = object_rewrite::Options::default();
+    options.macho.change_load_dylib = [(
+        b"/usr/lib/libSystem.B.dylib".to_vec(),
+        b"/usr/lib/libc.dylib".to_vec(),
+    )]
+    .into_iter()
+    .collect();
+    fail |= testfile_data(
+        &in_data,
+        "macho/libfoo.dylib.change-load-dylib",
+        options,
+        &print_options,
+    );
+
+    fail_message(fail);
+}
+
+#[test]
+fn rewrite_macho_dylib_invalid() {
+    let in_data = macho_dylib();
+
+    // There is not enough space before the first section for this load command.
+    let mut options = object_rewrite::Options::default();
+    options.macho.add_rpath = vec![vec![b'a'; 0x800]];
+    let mut rewriter = object_rewrite::Rewriter::read(&in_data).unwrap();
+    let result = rewriter
+        .modify(options)
+        .and_then(|()| rewriter.write(&mut Vec::new()));
+    assert!(result.is_err());
+}
+
 fn testfile(
     in_path: &str,
     out_path: &str,
@@ -286,18 +634,27 @@ fn testfile(
     print_options: &readobj::PrintOptions,
 ) -> bool {
     let in_path = Path::new("../../testfiles").join(in_path);
-    let out_path = Path::new("testfiles").join(out_path);
-
-    println!("Test {}", out_path.display());
     let in_data = match fs::read(&in_path) {
         Ok(in_data) => in_data,
         Err(err) => {
+            println!("Test {}", out_path);
             println!("FAIL Couldn't read {}: {}", in_path.display(), err);
             return true;
         }
     };
+    testfile_data(&in_data, out_path, options, print_options)
+}
 
-    let mut rewriter = object_rewrite::Rewriter::read(&in_data).unwrap();
+fn testfile_data(
+    in_data: &[u8],
+    out_path: &str,
+    options: object_rewrite::Options,
+    print_options: &readobj::PrintOptions,
+) -> bool {
+    let out_path = Path::new("testfiles").join(out_path);
+    println!("Test {}", out_path.display());
+
+    let mut rewriter = object_rewrite::Rewriter::read(in_data).unwrap();
     rewriter.modify(options).unwrap();
     let mut rewrite_data = Vec::new();
     rewriter.write(&mut rewrite_data).unwrap();
@@ -333,3 +690,463 @@ fn testfile(
 
     fail
 }
+
+/// Create a PE image containing an import table, a debug directory,
+/// base relocations, a COFF symbol table and a certificate table.
+fn pe_image() -> Vec<u8> {
+    use object::pe;
+    use object::write::pe::{NtHeaders, Writer};
+
+    // Offsets within .rdata.
+    const IMPORT_DESCRIPTORS: u32 = 0x00;
+    const IMPORT_LOOKUP: u32 = 0x40;
+    const IMPORT_ADDRESS: u32 = 0x60;
+    const HINT_NAMES: u32 = 0x80;
+    const DLL_NAMES: u32 = 0xa0;
+    const DEBUG_DIRECTORY: u32 = 0xc0;
+    const CODEVIEW: u32 = 0xe0;
+    const DLLS: [(&[u8], &[u8]); 2] = [
+        (b"KERNEL32.dll", b"ExitProcess"),
+        (b"USER32.dll", b"MessageBoxA"),
+    ];
+    let codeview =
+        b"RSDS\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10\x01\0\0\0test.pdb\0";
+
+    let rdata = |address: u32, offset: u32| {
+        let mut data = Vec::new();
+        for i in 0..DLLS.len() as u32 {
+            data.extend_from_slice(&(address + IMPORT_LOOKUP + i * 16).to_le_bytes());
+            data.extend_from_slice(&[0; 8]);
+            data.extend_from_slice(&(address + DLL_NAMES + i * 16).to_le_bytes());
+            data.extend_from_slice(&(address + IMPORT_ADDRESS + i * 16).to_le_bytes());
+        }
+        data.resize(IMPORT_LOOKUP as usize, 0);
+        // The import lookup table and the import address table.
+        for _ in 0..2 {
+            for i in 0..DLLS.len() as u32 {
+                let hint_name = address + HINT_NAMES + i * 16;
+                data.extend_from_slice(&u64::from(hint_name).to_le_bytes());
+                data.extend_from_slice(&[0; 8]);
+            }
+        }
+        for (_, function) in DLLS {
+            data.extend_from_slice(&[0; 2]);
+            data.extend_from_slice(function);
+            data.resize(data.len().next_multiple_of(16), 0);
+        }
+        for (dll, _) in DLLS {
+            data.extend_from_slice(dll);
+            data.resize(data.len().next_multiple_of(16), 0);
+        }
+        data.extend_from_slice(object::pod::bytes_of(&pe::ImageDebugDirectory {
+            characteristics: U32::new(LE, 0),
+            time_date_stamp: U32::new(LE, 0),
+            major_version: U16::new(LE, 0),
+            minor_version: U16::new(LE, 0),
+            typ: U32::new(LE, pe::IMAGE_DEBUG_TYPE_CODEVIEW),
+            size_of_data: U32::new(LE, codeview.len() as u32),
+            address_of_raw_data: U32::new(LE, address + CODEVIEW),
+            pointer_to_raw_data: U32::new(LE, offset + CODEVIEW),
+        }));
+        data.resize(CODEVIEW as usize, 0);
+        data.extend_from_slice(codeview);
+        data
+    };
+
+    let text_data = [0x48, 0x8b, 0x05, 0, 0, 0, 0, 0xc3];
+    let mut symbol_table = Vec::new();
+    for (name, value) in [(*b"main\0\0\0\0", 0), (*b"\0\0\0\0\x04\0\0\0", 7)] {
+        symbol_table.extend_from_slice(object::pod::bytes_of(&pe::ImageSymbol {
+            name,
+            value: U32::new(LE, value),
+            section_number: U16::new(LE, 1),
+            typ: U16::new(LE, pe::IMAGE_SYM_DTYPE_FUNCTION << pe::N_BTSHFT),
+            storage_class: pe::IMAGE_SYM_CLASS_EXTERNAL,
+            number_of_aux_symbols: 0,
+        }));
+    }
+    let strings = b"long_symbol_name\0";
+    symbol_table.extend_from_slice(&(4 + strings.len() as u32).to_le_bytes());
+    symbol_table.extend_from_slice(strings);
+    let mut certificate_table = Vec::new();
+    certificate_table.extend_from_slice(&16u32.to_le_bytes());
+    // WIN_CERT_REVISION_2_0 and WIN_CERT_TYPE_PKCS_SIGNED_DATA.
+    certificate_table.extend_from_slice(&0x0200u16.to_le_bytes());
+    certificate_table.extend_from_slice(&0x0002u16.to_le_bytes());
+    certificate_table.extend_from_slice(b"signatur");
+
+    let mut data = Vec::new();
+    let mut writer = Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(3);
+    let text = writer.reserve_text_section(text_data.len() as u32);
+    let rdata_range = writer.reserve_rdata_section(rdata(0, 0).len() as u32);
+    let rdata_data = rdata(rdata_range.virtual_address, rdata_range.file_offset);
+    writer.add_reloc(text.virtual_address, pe::IMAGE_REL_BASED_DIR64);
+    writer.reserve_reloc_section();
+    writer.set_data_directory(
+        pe::IMAGE_DIRECTORY_ENTRY_IMPORT,
+        rdata_range.virtual_address + IMPORT_DESCRIPTORS,
+        3 * 20,
+    );
+    writer.set_data_directory(
+        pe::IMAGE_DIRECTORY_ENTRY_IAT,
+        rdata_range.virtual_address + IMPORT_ADDRESS,
+        32,
+    );
+    writer.set_data_directory(
+        pe::IMAGE_DIRECTORY_ENTRY_DEBUG,
+        rdata_range.virtual_address + DEBUG_DIRECTORY,
+        28,
+    );
+    writer.reserve_symbol_table(2, symbol_table.len() as u32);
+    writer.reserve_certificate_table(certificate_table.len() as u32);
+
+    writer.write_dos_header_and_stub().unwrap();
+    writer.write_nt_headers(NtHeaders {
+        machine: pe::IMAGE_FILE_MACHINE_AMD64,
+        time_date_stamp: 0,
+        characteristics: pe::IMAGE_FILE_EXECUTABLE_IMAGE | pe::IMAGE_FILE_LARGE_ADDRESS_AWARE,
+        major_linker_version: 14,
+        minor_linker_version: 0,
+        address_of_entry_point: text.virtual_address,
+        image_base: 0x1_4000_0000,
+        major_operating_system_version: 6,
+        minor_operating_system_version: 0,
+        major_image_version: 0,
+        minor_image_version: 0,
+        major_subsystem_version: 6,
+        minor_subsystem_version: 0,
+        subsystem: pe::IMAGE_SUBSYSTEM_WINDOWS_CUI,
+        dll_characteristics: pe::IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA
+            | pe::IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE
+            | pe::IMAGE_DLLCHARACTERISTICS_NX_COMPAT,
+        size_of_stack_reserve: 0x10_0000,
+        size_of_stack_commit: 0x1000,
+        size_of_heap_reserve: 0x10_0000,
+        size_of_heap_commit: 0x1000,
+    });
+    writer.write_section_headers();
+    writer.write_section(text.file_offset, &text_data);
+    writer.write_section(rdata_range.file_offset, &rdata_data);
+    writer.write_reloc_section();
+    writer.write_symbol_table(&symbol_table);
+    writer.write_certificate_table(&certificate_table);
+    data
+}
+
+/// Create a COFF object containing relocations, a COMDAT and a file symbol.
+fn coff_object() -> Vec<u8> {
+    use object::write;
+
+    let mut object = write::Object::new(
+        object::BinaryFormat::Coff,
+        object::Architecture::X86_64,
+        object::Endianness::Little,
+    );
+    object.add_file_symbol(b"a_long_file_name.c".to_vec());
+    let text = object.section_id(write::StandardSection::Text);
+    let data = object.section_id(write::StandardSection::Data);
+
+    let add_symbol = |object: &mut write::Object, name: &[u8], section, value| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 0,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(section),
+            flags: object::SymbolFlags::None,
+        })
+    };
+    let add_relocation = |object: &mut write::Object, section, offset, symbol, addend, typ| {
+        object
+            .add_relocation(
+                section,
+                write::Relocation {
+                    offset,
+                    symbol,
+                    addend,
+                    flags: object::RelocationFlags::Coff { typ },
+                },
+            )
+            .unwrap();
+    };
+
+    let main_offset = object.append_section_data(text, &[0xe8, 0, 0, 0, 0, 0xc3], 16);
+    let main = add_symbol(&mut object, b"main", text, main_offset);
+    let unused_offset = object.append_section_data(text, &[0xc3], 16);
+    add_symbol(&mut object, b"unused", text, unused_offset);
+    let puts = object.add_symbol(write::Symbol {
+        name: b"puts".to_vec(),
+        value: 0,
+        size: 0,
+        kind: object::SymbolKind::Text,
+        scope: object::SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: object::SymbolFlags::None,
+    });
+    let rel32 = object::pe::IMAGE_REL_AMD64_REL32;
+    let addr64 = object::pe::IMAGE_REL_AMD64_ADDR64;
+    add_relocation(&mut object, text, main_offset + 1, puts, -4, rel32);
+    object.append_section_data(data, &[0; 8], 8);
+    add_relocation(&mut object, data, 0, main, 0, addr64);
+
+    let inline_text = object.add_section(
+        Vec::new(),
+        b".text$inline".to_vec(),
+        object::SectionKind::Text,
+    );
+    object.append_section_data(inline_text, &[0xc3], 16);
+    object.section_symbol(inline_text);
+    let inline = add_symbol(&mut object, b"inline", inline_text, 0);
+    let inline_data = object.add_section(
+        Vec::new(),
+        b".data$inline".to_vec(),
+        object::SectionKind::Data,
+    );
+    object.append_section_data(inline_data, &[0; 8], 8);
+    object.section_symbol(inline_data);
+    add_relocation(&mut object, inline_data, 0, inline, 0, addr64);
+    object.add_comdat(write::Comdat {
+        kind: object::ComdatKind::Any,
+        symbol: inline,
+        sections: vec![inline_text, inline_data],
+    });
+    object.write().unwrap()
+}
+
+/// Create a Mach-O object containing a relocation and an `__LLVM,__bundle` section.
+fn macho_object() -> Vec<u8> {
+    use object::write;
+
+    let mut object = write::Object::new(
+        object::BinaryFormat::MachO,
+        object::Architecture::X86_64,
+        object::Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    let data = object.section_id(write::StandardSection::Data);
+    let bundle = object.add_section(
+        b"__LLVM".to_vec(),
+        b"__bundle".to_vec(),
+        object::SectionKind::Other,
+    );
+    object.append_section_data(bundle, b"bundle", 1);
+
+    let mut symbols = Vec::new();
+    for name in [&b"main"[..], b"helper"] {
+        let offset = object.append_section_data(text, &[0xc3], 1);
+        symbols.push(object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: offset,
+            size: 1,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: object::SymbolFlags::None,
+        }));
+    }
+    object.append_section_data(data, &[0; 8], 8);
+    object
+        .add_relocation(
+            data,
+            write::Relocation {
+                offset: 0,
+                symbol: symbols[1],
+                addend: 0,
+                flags: object::RelocationFlags::Generic {
+                    kind: object::RelocationKind::Absolute,
+                    encoding: object::RelocationEncoding::Generic,
+                    size: 64,
+                },
+            },
+        )
+        .unwrap();
+    object.write().unwrap()
+}
+
+/// Create a Mach-O dylib containing an `LC_ID_DYLIB`, an `LC_LOAD_DYLIB`
+/// and an `LC_RPATH` load command.
+fn macho_dylib() -> Vec<u8> {
+    use object::macho;
+    use object::pod::bytes_of;
+    use object::U64;
+    use std::mem::size_of;
+
+    const TEXT_OFFSET: u32 = 0x800;
+    const LINKEDIT_OFFSET: u32 = 0x1000;
+
+    fn dylib_command(cmd: u32, name: &[u8]) -> Vec<u8> {
+        let size = (size_of::<macho::DylibCommand<LE>>() + name.len() + 1).next_multiple_of(8);
+        let command = macho::DylibCommand {
+            cmd: U32::new(LE, cmd),
+            cmdsize: U32::new(LE, size as u32),
+            dylib: macho::Dylib {
+                name: macho::LcStr {
+                    offset: U32::new(LE, size_of::<macho::DylibCommand<LE>>() as u32),
+                },
+                timestamp: U32::new(LE, 2),
+                current_version: U32::new(LE, 0x10000),
+                compatibility_version: U32::new(LE, 0x10000),
+            },
+        };
+        let mut data = bytes_of(&command).to_vec();
+        data.extend_from_slice(name);
+        data.resize(size, 0);
+        data
+    }
+
+    fn rpath_command(path: &[u8]) -> Vec<u8> {
+        let size = (size_of::<macho::RpathCommand<LE>>() + path.len() + 1).next_multiple_of(8);
+        let command = macho::RpathCommand {
+            cmd: U32::new(LE, macho::LC_RPATH),
+            cmdsize: U32::new(LE, size as u32),
+            path: macho::LcStr {
+                offset: U32::new(LE, size_of::<macho::RpathCommand<LE>>() as u32),
+            },
+        };
+        let mut data = bytes_of(&command).to_vec();
+        data.extend_from_slice(path);
+        data.resize(size, 0);
+        data
+    }
+
+    fn segment_command(name: &[u8], fileoff: u32, filesize: u32, nsects: u32) -> Vec<u8> {
+        let mut segname = [0; 16];
+        segname[..name.len()].copy_from_slice(name);
+        let size = size_of::<macho::SegmentCommand64<LE>>()
+            + nsects as usize * size_of::<macho::Section64<LE>>();
+        let command = macho::SegmentCommand64 {
+            cmd: U32::new(LE, macho::LC_SEGMENT_64),
+            cmdsize: U32::new(LE, size as u32),
+            segname,
+            vmaddr: U64::new(LE, fileoff.into()),
+            vmsize: U64::new(LE, u64::from(filesize).next_multiple_of(0x1000)),
+            fileoff: U64::new(LE, fileoff.into()),
+            filesize: U64::new(LE, filesize.into()),
+            maxprot: U32::new(LE, macho::VM_PROT_READ),
+            initprot: U32::new(LE, macho::VM_PROT_READ),
+            nsects: U32::new(LE, nsects),
+            flags: U32::new(LE, 0),
+        };
+        bytes_of(&command).to_vec()
+    }
+
+    let mut strtab = b"\0_foo\0_bar\0".to_vec();
+    strtab.resize(16, 0);
+    let mut linkedit = Vec::new();
+    for (strx, n_type, n_sect, n_value) in [
+        (1, macho::N_SECT | macho::N_EXT, 1, TEXT_OFFSET),
+        (6, macho::N_UNDF | macho::N_EXT, 0, 0),
+    ] {
+        let nlist = macho::Nlist64 {
+            n_strx: U32::new(LE, strx),
+            n_type,
+            n_sect,
+            n_desc: U16::new(LE, 0),
+            n_value: U64::new(LE, n_value.into()),
+        };
+        linkedit.extend_from_slice(bytes_of(&nlist));
+    }
+    let symoff = LINKEDIT_OFFSET;
+    let stroff = symoff + linkedit.len() as u32;
+    linkedit.extend_from_slice(&strtab);
+
+    let mut commands = Vec::new();
+    commands.push(segment_command(b"__TEXT", 0, LINKEDIT_OFFSET, 1));
+    let mut sectname = [0; 16];
+    sectname[..6].copy_from_slice(b"__text");
+    let mut segname = [0; 16];
+    segname[..6].copy_from_slice(b"__TEXT");
+    let section = macho::Section64 {
+        sectname,
+        segname,
+        addr: U64::new(LE, TEXT_OFFSET.into()),
+        size: U64::new(LE, 4),
+        offset: U32::new(LE, TEXT_OFFSET),
+        align: U32::new(LE, 2),
+        reloff: U32::new(LE, 0),
+        nreloc: U32::new(LE, 0),
+        flags: U32::new(LE, macho::S_ATTR_PURE_INSTRUCTIONS),
+        reserved1: U32::new(LE, 0),
+        reserved2: U32::new(LE, 0),
+        reserved3: U32::new(LE, 0),
+    };
+    commands
+        .last_mut()
+        .unwrap()
+        .extend_from_slice(bytes_of(&section));
+    commands.push(segment_command(
+        b"__LINKEDIT",
+        LINKEDIT_OFFSET,
+        linkedit.len() as u32,
+        0,
+    ));
+    commands.push(dylib_command(macho::LC_ID_DYLIB, b"@rpath/libfoo.dylib"));
+    commands.push(dylib_command(
+        macho::LC_LOAD_DYLIB,
+        b"/usr/lib/libSystem.B.dylib",
+    ));
+    commands.push(rpath_command(b"@loader_path/../lib"));
+    let symtab = macho::SymtabCommand {
+        cmd: U32::new(LE, macho::LC_SYMTAB),
+        cmdsize: U32::new(LE, size_of::<macho::SymtabCommand<LE>>() as u32),
+        symoff: U32::new(LE, symoff),
+        nsyms: U32::new(LE, 2),
+        stroff: U32::new(LE, stroff),
+        strsize: U32::new(LE, strtab.len() as u32),
+    };
+    commands.push(bytes_of(&symtab).to_vec());
+    let dysymtab = macho::DysymtabCommand {
+        cmd: U32::new(LE, macho::LC_DYSYMTAB),
+        cmdsize: U32::new(LE, size_of::<macho::DysymtabCommand<LE>>() as u32),
+        ilocalsym: U32::new(LE, 0),
+        nlocalsym: U32::new(LE, 0),
+        iextdefsym: U32::new(LE, 0),
+        nextdefsym: U32::new(LE, 1),
+        iundefsym: U32::new(LE, 1),
+        nundefsym: U32::new(LE, 1),
+        tocoff: U32::new(LE, 0),
+        ntoc: U32::new(LE, 0),
+        modtaboff: U32::new(LE, 0),
+        nmodtab: U32::new(LE, 0),
+        extrefsymoff: U32::new(LE, 0),
+        nextrefsyms: U32::new(LE, 0),
+        indirectsymoff: U32::new(LE, 0),
+        nindirectsyms: U32::new(LE, 0),
+        extreloff: U32::new(LE, 0),
+        nextrel: U32::new(LE, 0),
+        locreloff: U32::new(LE, 0),
+        nlocrel: U32::new(LE, 0),
+    };
+    commands.push(bytes_of(&dysymtab).to_vec());
+
+    let header = macho::MachHeader64 {
+        magic: U32::new(object::BigEndian, macho::MH_CIGAM_64),
+        cputype: U32::new(LE, macho::CPU_TYPE_X86_64),
+        cpusubtype: U32::new(LE, macho::CPU_SUBTYPE_X86_64_ALL),
+        filetype: U32::new(LE, macho::MH_DYLIB),
+        ncmds: U32::new(LE, commands.len() as u32),
+        sizeofcmds: U32::new(LE, commands.iter().map(Vec::len).sum::<usize>() as u32),
+        flags: U32::new(
+            LE,
+            macho::MH_NOUNDEFS | macho::MH_DYLDLINK | macho::MH_TWOLEVEL,
+        ),
+        reserved: U32::new(LE, 0),
+    };
+
+    let mut data = bytes_of(&header).to_vec();
+    for command in commands {
+        data.extend_from_slice(&command);
+    }
+    data.resize(TEXT_OFFSET as usize, 0);
+    data.extend_from_slice(&[0xc3; 4]);
+    data.resize(LINKEDIT_OFFSET as usize, 0);
+    data.extend_from_slice(&linkedit);
+    data
+}
@@ -0,0 +1,611 @@
+//! This module provides a [`Builder`] for reading, modifying, and then writing COFF object files.
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::build::{ByteString, Bytes, Error, Id, IdPrivate, Item, Result, Table};
+use crate::endian::LittleEndian as LE;
+use crate::pe;
+use crate::pod;
+use crate::read::coff::{CoffHeader, ImageSymbol};
+use crate::read::{FileKind, ReadRef, SymbolIndex};
+use crate::write;
+
+/// A builder for reading, modifying, and then writing COFF object files.
+///
+/// Public fields are available for modifying the values that will be written.
+/// Methods are available to add elements to tables, and elements can be deleted
+/// from tables by setting the `delete` field in the element.
+///
+/// The file is laid out again when writing. Line numbers are discarded.
+///
+/// Bigobj files are not supported. PE images use [`build::pe::Builder`](crate::build::pe::Builder)
+/// instead.
+#[derive(Debug)]
+pub struct Builder<'data> {
+    /// The fields of the file header.
+    pub file_header: write::coff::FileHeader,
+    /// The section table.
+    pub sections: Sections<'data>,
+    /// The symbol table.
+    pub symbols: Symbols<'data>,
+    /// The symbol for each symbol table index in the input file.
+    ///
+    /// This is used for symbol indices in auxiliary symbol records.
+    input_symbols: Vec<Option<SymbolId>>,
+}
+
+impl<'data> Builder<'data> {
+    /// Create a new builder for a COFF object file.
+    pub fn new(machine: u16) -> Self {
+        Builder {
+            file_header: write::coff::FileHeader {
+                machine,
+                ..Default::default()
+            },
+            sections: Sections::new(),
+            symbols: Symbols::new(),
+            input_symbols: Vec::new(),
+        }
+    }
+
+    /// Read the COFF object file from file data.
+    pub fn read<R: ReadRef<'data>>(data: R) -> Result<Self> {
+        match FileKind::parse(data)? {
+            FileKind::Coff => {}
+            _ => return Err(Error::new("Not a COFF file")),
+        }
+        let mut offset = 0;
+        let header = pe::ImageFileHeader::parse(data, &mut offset)?;
+        let section_table = header.sections(data, offset)?;
+        let symbol_table = header.symbols(data)?;
+        let strings = symbol_table.strings();
+
+        let mut builder = Builder {
+            file_header: write::coff::FileHeader {
+                machine: header.machine.get(LE),
+                time_date_stamp: header.time_date_stamp.get(LE),
+                characteristics: header.characteristics.get(LE),
+            },
+            sections: Sections::new(),
+            symbols: Symbols::new(),
+            input_symbols: Vec::new(),
+        };
+
+        for header in section_table.iter() {
+            let name = header.name(strings)?;
+            let section_data = header
+                .coff_data(data)
+                .map_err(|_| Error::new("Invalid COFF section offset or size"))?;
+            let section = builder.sections.add();
+            section.name = name.into();
+            section.characteristics = header.characteristics.get(LE);
+            section.size_of_raw_data = header.size_of_raw_data.get(LE);
+            section.data = section_data.into();
+        }
+
+        let symbol_offset = u64::from(header.pointer_to_symbol_table.get(LE));
+        let mut index = 0;
+        while index < symbol_table.len() {
+            let symbol = symbol_table.symbol(SymbolIndex(index))?;
+            let aux_count = symbol.number_of_aux_symbols();
+            let name = if symbol.has_aux_file_name() {
+                symbol_table.aux_file_name(SymbolIndex(index), aux_count)?
+            } else {
+                symbol.name(strings)?
+            };
+            let aux = data
+                .read_bytes_at(
+                    symbol_offset + ((index + 1) * pe::IMAGE_SIZEOF_SYMBOL) as u64,
+                    (usize::from(aux_count) * pe::IMAGE_SIZEOF_SYMBOL) as u64,
+                )
+                .map_err(|_| Error::new("Invalid COFF auxiliary symbol count"))?;
+            let section = match symbol.section_number() {
+                pe::IMAGE_SYM_UNDEFINED => SymbolSection::Undefined,
+                pe::IMAGE_SYM_ABSOLUTE => SymbolSection::Absolute,
+                pe::IMAGE_SYM_DEBUG => SymbolSection::Debug,
+                number if number > 0 && number as usize <= builder.sections.len() => {
+                    SymbolSection::Section(SectionId(number as usize - 1))
+                }
+                number => {
+                    return Err(Error(format!(
+                        "Invalid COFF section number {} for symbol `{}`",
+                        number,
+                        ByteString::from(name)
+                    )));
+                }
+            };
+            let new_symbol = builder.symbols.add();
+            new_symbol.name = name.into();
+            new_symbol.value = symbol.value();
+            new_symbol.section = section;
+            new_symbol.typ = symbol.typ();
+            new_symbol.storage_class = symbol.storage_class();
+            new_symbol.aux = aux.into();
+            builder.input_symbols.push(Some(new_symbol.id));
+            builder
+                .input_symbols
+                .resize(builder.input_symbols.len() + usize::from(aux_count), None);
+            index += 1 + usize::from(aux_count);
+        }
+
+        for (index, header) in section_table.iter().enumerate() {
+            let relocations = header.coff_relocations(data)?;
+            let mut new_relocations = Vec::with_capacity(relocations.len());
+            for relocation in relocations {
+                let symbol_index = relocation.symbol_table_index.get(LE);
+                let symbol = builder
+                    .input_symbols
+                    .get(symbol_index as usize)
+                    .copied()
+                    .flatten()
+                    .ok_or_else(|| {
+                        Error(format!(
+                            "Invalid COFF relocation symbol index {}",
+                            symbol_index
+                        ))
+                    })?;
+                new_relocations.push(Relocation {
+                    virtual_address: relocation.virtual_address.get(LE),
+                    symbol,
+                    typ: relocation.typ.get(LE),
+                });
+            }
+            builder.sections.get_mut(SectionId(index)).relocations = new_relocations;
+        }
+
+        Ok(builder)
+    }
+
+    /// Delete sections and symbols that refer to deleted sections.
+    ///
+    /// This deletes COMDAT sections that are associated with a deleted section,
+    /// and symbols that are defined in a deleted section.
+    ///
+    /// This is not called by [`Self::write`]. Writing will fail if there
+    /// are any references to deleted symbols or sections.
+    pub fn delete_orphans(&mut self) {
+        // Delete COMDAT sections that are associated with a deleted section.
+        loop {
+            let mut changed = false;
+            for index in 0..self.symbols.len() {
+                let symbol = self.symbols.get(SymbolId(index));
+                let SymbolSection::Section(section) = symbol.section else {
+                    continue;
+                };
+                let Some(aux) = symbol.aux_section() else {
+                    continue;
+                };
+                if aux.selection != pe::IMAGE_COMDAT_SELECT_ASSOCIATIVE {
+                    continue;
+                }
+                let associated = aux_section_number(aux) as usize;
+                let associated_deleted = associated > 0
+                    && associated <= self.sections.len()
+                    && self.sections.get(SectionId(associated - 1)).delete;
+                if associated_deleted && !self.sections.get(section).delete {
+                    self.sections.get_mut(section).delete = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        for symbol in &mut self.symbols {
+            if let SymbolSection::Section(section) = symbol.section {
+                if self.sections.get(section).delete {
+                    symbol.delete = true;
+                }
+            }
+        }
+    }
+
+    /// Write the COFF object file to the buffer.
+    pub fn write(self, buffer: &mut dyn write::WritableBuffer) -> Result<()> {
+        // The section number for each section ID, or 0 if the section is deleted.
+        let mut section_map = vec![0; self.sections.len()];
+        let mut section_num = 0;
+        for section in &self.sections {
+            section_num += 1;
+            section_map[section.id.0] = section_num;
+        }
+        if section_num > u32::from(pe::IMAGE_SYM_SECTION_MAX) {
+            return Err(Error::new("Too many COFF sections"));
+        }
+        let map_section = |section: SectionId| -> Result<i32> {
+            match section_map.get(section.0) {
+                Some(0) => Err(Error::new("COFF symbol refers to deleted section")),
+                Some(number) => Ok(*number as i32),
+                None => Err(Error::new("Invalid COFF section number")),
+            }
+        };
+
+        let mut writer = write::coff::Writer::new(buffer);
+
+        // Assign symbol indices and add names to the string table.
+        // The symbol map is indexed by the symbol ID.
+        let mut symbol_map = vec![None; self.symbols.len()];
+        let mut symbol_names = Vec::new();
+        let mut symbol_aux_counts = Vec::new();
+        for symbol in &self.symbols {
+            if symbol.aux.len() % pe::IMAGE_SIZEOF_SYMBOL != 0 {
+                return Err(Error(format!(
+                    "Invalid COFF auxiliary symbol length for symbol `{}`",
+                    symbol.name
+                )));
+            }
+            symbol_map[symbol.id.0] = Some(writer.reserve_symbol_index());
+            if symbol.storage_class == pe::IMAGE_SYM_CLASS_FILE {
+                symbol_names.push(write::coff::Name::Short(*b".file\0\0\0"));
+                symbol_aux_counts.push(writer.reserve_aux_file_name(&symbol.name));
+            } else {
+                let aux_count = symbol.number_of_aux_symbols();
+                symbol_names.push(writer.add_name(&symbol.name));
+                writer.reserve_symbol_indices(u32::from(aux_count));
+                symbol_aux_counts.push(aux_count);
+            }
+        }
+        let map_symbol = |symbol: SymbolId, user: &str| -> Result<u32> {
+            match symbol_map.get(symbol.0) {
+                Some(Some(index)) => Ok(*index),
+                Some(None) => Err(Error(format!(
+                    "COFF {} refers to deleted symbol `{}`",
+                    user,
+                    self.symbols.get(symbol).name
+                ))),
+                None => Err(Error(format!("Invalid COFF symbol in {}", user))),
+            }
+        };
+        let map_input_symbol = |index: u32| -> Option<u32> {
+            let symbol = self.input_symbols.get(index as usize).copied().flatten()?;
+            symbol_map.get(symbol.0).copied().flatten()
+        };
+
+        let sections: Vec<_> = self.sections.iter().collect();
+        let section_names: Vec<_> = sections
+            .iter()
+            .map(|section| writer.add_name(&section.name))
+            .collect();
+
+        // Reserve file ranges.
+        writer.reserve_file_header();
+        writer.reserve_section_headers(sections.len() as u16);
+        let mut section_offsets = Vec::new();
+        for section in &sections {
+            let offset = writer.reserve_section(section.data.len());
+            let reloc_offset = writer.reserve_relocations(section.relocations.len());
+            section_offsets.push((offset, reloc_offset));
+        }
+        writer.reserve_symtab_strtab();
+
+        // Write.
+        writer.write_file_header(self.file_header.clone())?;
+        for ((section, name), (offset, reloc_offset)) in
+            sections.iter().zip(section_names).zip(&section_offsets)
+        {
+            let mut characteristics = section.characteristics & !pe::IMAGE_SCN_LNK_NRELOC_OVFL;
+            if section.relocations.len() > 0xffff {
+                characteristics |= pe::IMAGE_SCN_LNK_NRELOC_OVFL;
+            }
+            writer.write_section_header(write::coff::SectionHeader {
+                name,
+                size_of_raw_data: if section.is_uninitialized() {
+                    section.size_of_raw_data
+                } else {
+                    section.data.len() as u32
+                },
+                pointer_to_raw_data: *offset,
+                pointer_to_relocations: *reloc_offset,
+                pointer_to_linenumbers: 0,
+                number_of_relocations: section.relocations.len() as u32,
+                number_of_linenumbers: 0,
+                characteristics,
+            });
+        }
+        for section in &sections {
+            writer.write_section(&section.data);
+            writer.write_relocations_count(section.relocations.len());
+            for relocation in &section.relocations {
+                writer.write_relocation(write::coff::Relocation {
+                    virtual_address: relocation.virtual_address,
+                    symbol: map_symbol(relocation.symbol, "relocation")?,
+                    typ: relocation.typ,
+                });
+            }
+        }
+
+        for ((symbol, name), aux_count) in
+            self.symbols.iter().zip(symbol_names).zip(symbol_aux_counts)
+        {
+            let section_number = match symbol.section {
+                SymbolSection::Undefined => pe::IMAGE_SYM_UNDEFINED,
+                SymbolSection::Absolute => pe::IMAGE_SYM_ABSOLUTE,
+                SymbolSection::Debug => pe::IMAGE_SYM_DEBUG,
+                SymbolSection::Section(section) => map_section(section)?,
+            };
+            writer.write_symbol_ex(write::coff::SymbolEx {
+                name,
+                value: symbol.value,
+                section_number,
+                typ: symbol.typ,
+                storage_class: symbol.storage_class,
+                number_of_aux_symbols: aux_count,
+            });
+            if symbol.storage_class == pe::IMAGE_SYM_CLASS_FILE {
+                writer.write_aux_file_name(&symbol.name, aux_count);
+                continue;
+            }
+            if symbol.aux.is_empty() {
+                continue;
+            }
+            let mut aux = symbol.aux.to_vec();
+            if symbol.aux_section().is_some() {
+                let aux: &mut pe::ImageAuxSymbolSection = pod::from_bytes_mut(&mut aux).unwrap().0;
+                if aux.selection == pe::IMAGE_COMDAT_SELECT_ASSOCIATIVE {
+                    let number = aux_section_number(aux) as usize;
+                    let number = match number.checked_sub(1) {
+                        Some(index) => map_section(SectionId(index))?,
+                        None => return Err(Error::new("Invalid COFF section number")),
+                    };
+                    aux.number.set(LE, number as u16);
+                    aux.high_number.set(LE, 0);
+                }
+            } else if symbol.has_aux_weak_external() {
+                let aux: &mut pe::ImageAuxSymbolWeak = pod::from_bytes_mut(&mut aux).unwrap().0;
+                let index = aux.weak_default_sym_index.get(LE);
+                let new_index = map_input_symbol(index).ok_or_else(|| {
+                    Error(format!(
+                        "COFF weak external `{}` refers to deleted or invalid symbol index {}",
+                        symbol.name, index
+                    ))
+                })?;
+                aux.weak_default_sym_index.set(LE, new_index);
+            } else if symbol.has_aux_function() {
+                // These refer to debugging symbols, so don't fail if they were deleted.
+                let aux: &mut pe::ImageAuxSymbolFunction = pod::from_bytes_mut(&mut aux).unwrap().0;
+                for index in [&mut aux.tag_index, &mut aux.pointer_to_next_function] {
+                    index.set(LE, map_input_symbol(index.get(LE)).unwrap_or(0));
+                }
+                aux.pointer_to_linenumber.set(LE, 0);
+            }
+            writer.write(&aux);
+        }
+        writer.write_strtab();
+
+        debug_assert_eq!(writer.reserved_len(), writer.len());
+        Ok(())
+    }
+}
+
+/// An ID for referring to a section in [`Sections`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SectionId(usize);
+
+impl fmt::Debug for SectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SectionId({})", self.0)
+    }
+}
+
+impl Id for SectionId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for SectionId {
+    fn new(id: usize) -> Self {
+        SectionId(id)
+    }
+}
+
+/// A section in [`Sections`].
+///
+/// This corresponds to [`pe::ImageSectionHeader`].
+#[derive(Debug)]
+pub struct Section<'data> {
+    id: SectionId,
+    /// Ignore this section when writing the COFF file.
+    pub delete: bool,
+    /// The name of the section.
+    pub name: ByteString<'data>,
+    /// The `characteristics` field in the section header.
+    pub characteristics: u32,
+    /// The `size_of_raw_data` field in the section header.
+    ///
+    /// This is only used for sections containing uninitialized data.
+    /// For other sections, the size of `data` is used instead.
+    pub size_of_raw_data: u32,
+    /// The section data.
+    ///
+    /// This must be empty for sections containing uninitialized data.
+    pub data: Bytes<'data>,
+    /// The relocations for the section.
+    pub relocations: Vec<Relocation>,
+}
+
+impl<'data> Item for Section<'data> {
+    type Id = SectionId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Section<'data> {
+    /// The ID used for referring to this section.
+    pub fn id(&self) -> SectionId {
+        self.id
+    }
+
+    fn is_uninitialized(&self) -> bool {
+        self.characteristics & pe::IMAGE_SCN_CNT_UNINITIALIZED_DATA != 0
+    }
+}
+
+/// A section table.
+pub type Sections<'data> = Table<Section<'data>>;
+
+impl<'data> Sections<'data> {
+    /// Add a new section to the table.
+    pub fn add(&mut self) -> &mut Section<'data> {
+        let id = self.next_id();
+        self.push(Section {
+            id,
+            delete: false,
+            name: ByteString::default(),
+            characteristics: 0,
+            size_of_raw_data: 0,
+            data: Bytes::default(),
+            relocations: Vec::new(),
+        })
+    }
+}
+
+/// A relocation in a [`Section`].
+///
+/// This corresponds to [`pe::ImageRelocation`].
+#[derive(Debug, Clone, Copy)]
+pub struct Relocation {
+    /// The `virtual_address` field in the relocation.
+    pub virtual_address: u32,
+    /// The symbol that the relocation refers to.
+    pub symbol: SymbolId,
+    /// The `typ` field in the relocation.
+    pub typ: u16,
+}
+
+/// An ID for referring to a symbol in [`Symbols`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SymbolId(usize);
+
+impl fmt::Debug for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SymbolId({})", self.0)
+    }
+}
+
+impl Id for SymbolId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for SymbolId {
+    fn new(id: usize) -> Self {
+        SymbolId(id)
+    }
+}
+
+/// The section of a [`Symbol`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolSection {
+    /// The symbol is undefined, or is a common symbol.
+    Undefined,
+    /// The symbol has an absolute value.
+    Absolute,
+    /// The symbol is a debugging symbol.
+    Debug,
+    /// The symbol is defined in the given section.
+    Section(SectionId),
+}
+
+/// A symbol in [`Symbols`].
+///
+/// This corresponds to [`pe::ImageSymbol`].
+#[derive(Debug)]
+pub struct Symbol<'data> {
+    id: SymbolId,
+    /// Ignore this symbol when writing the COFF file.
+    ///
+    /// Writing will fail if a deleted symbol is referenced by a relocation
+    /// or a weak external.
+    pub delete: bool,
+    /// The name of the symbol.
+    ///
+    /// For file symbols, this is the file name, which is written to the
+    /// auxiliary symbol records.
+    pub name: ByteString<'data>,
+    /// The `value` field in the symbol.
+    pub value: u32,
+    /// The section that the symbol is defined in.
+    pub section: SymbolSection,
+    /// The `typ` field in the symbol.
+    pub typ: u16,
+    /// The `storage_class` field in the symbol.
+    pub storage_class: u8,
+    /// The auxiliary symbol records.
+    ///
+    /// The length must be a multiple of [`pe::IMAGE_SIZEOF_SYMBOL`].
+    /// Section numbers and symbol indices in the records refer to the input
+    /// file, and are updated when writing.
+    ///
+    /// This is ignored for file symbols.
+    pub aux: Bytes<'data>,
+}
+
+impl<'data> Item for Symbol<'data> {
+    type Id = SymbolId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Symbol<'data> {
+    /// The ID used for referring to this symbol.
+    pub fn id(&self) -> SymbolId {
+        self.id
+    }
+
+    fn number_of_aux_symbols(&self) -> u8 {
+        (self.aux.len() / pe::IMAGE_SIZEOF_SYMBOL) as u8
+    }
+
+    fn aux_section(&self) -> Option<&pe::ImageAuxSymbolSection> {
+        if self.storage_class != pe::IMAGE_SYM_CLASS_STATIC || self.typ != 0 {
+            return None;
+        }
+        let (aux, _) = pod::from_bytes::<pe::ImageAuxSymbolSection>(&self.aux).ok()?;
+        Some(aux)
+    }
+
+    fn has_aux_function(&self) -> bool {
+        self.aux.len() >= pe::IMAGE_SIZEOF_SYMBOL
+            && (self.typ & pe::N_TMASK) >> pe::N_BTSHFT == pe::IMAGE_SYM_DTYPE_FUNCTION
+            && (self.storage_class == pe::IMAGE_SYM_CLASS_EXTERNAL
+                || self.storage_class == pe::IMAGE_SYM_CLASS_STATIC)
+    }
+
+    fn has_aux_weak_external(&self) -> bool {
+        self.aux.len() >= pe::IMAGE_SIZEOF_SYMBOL
+            && self.storage_class == pe::IMAGE_SYM_CLASS_WEAK_EXTERNAL
+            && self.section == SymbolSection::Undefined
+            && self.value == 0
+    }
+}
+
+/// A symbol table.
+pub type Symbols<'data> = Table<Symbol<'data>>;
+
+impl<'data> Symbols<'data> {
+    /// Add a new symbol to the table.
+    pub fn add(&mut self) -> &mut Symbol<'data> {
+        let id = self.next_id();
+        self.push(Symbol {
+            id,
+            delete: false,
+            name: ByteString::default(),
+            value: 0,
+            section: SymbolSection::Undefined,
+            typ: 0,
+            storage_class: 0,
+            aux: Bytes::default(),
+        })
+    }
+}
+
+fn aux_section_number(aux: &pe::ImageAuxSymbolSection) -> u32 {
+    u32::from(aux.number.get(LE)) | u32::from(aux.high_number.get(LE)) << 16
+}
@@ -2,6 +2,7 @@
 use alloc::vec::Vec;
 use core::fmt;
 use core::marker::PhantomData;
+use core::mem;
 #[cfg(not(feature = "std"))]
 use hashbrown::HashMap;
 #[cfg(feature = "std")]
@@ -12,7 +13,9 @@ use crate::elf;
 use crate::read::elf::{FileHeader, ProgramHeader, Rela, SectionHeader, Sym};
 use crate::read::{self, FileKind, ReadRef};
 use crate::write;
-use crate::Endianness;
+#[cfg(feature = "compression")]
+use crate::{pod, CompressionFormat};
+use crate::{Endian, Endianness};
 
 /// A builder for reading, modifying, and then writing ELF files.
 ///
@@ -1119,6 +1122,7 @@ impl<'data> Builder<'data> {
         let mut versym_addr = None;
         let mut verdef_addr = None;
         let mut verneed_addr = None;
+        let mut relr_range = None;
 
         if !self.segments.is_empty() {
             // TODO: support program headers in other locations.
@@ -1177,6 +1181,9 @@ impl<'data> Builder<'data> {
                 writer.reserve_until(section.sh_offset as usize);
                 out_section.offset = match &section.data {
                     SectionData::Data(data) => {
+                        if section.sh_type == elf::SHT_RELR {
+                            relr_range = Some((section.sh_addr, data.len() as u64));
+                        }
                         writer.reserve(data.len(), section.sh_addralign as usize)
                     }
                     SectionData::DynamicRelocation(relocations) => writer
@@ -1379,6 +1386,21 @@ impl<'data> Builder<'data> {
                                             "Missing .gnu.version_r section for DT_VERNEED",
                                         ))?,
                                         elf::DT_VERNEEDNUM => verneed_count as u64,
+                                        elf::DT_RELR => {
+                                            relr_range
+                                                .ok_or(Error::new(
+                                                    "Missing SHT_RELR section for DT_RELR",
+                                                ))?
+                                                .0
+                                        }
+                                        elf::DT_RELRSZ => {
+                                            relr_range
+                                                .ok_or(Error::new(
+                                                    "Missing SHT_RELR section for DT_RELRSZ",
+                                                ))?
+                                                .1
+                                        }
+                                        elf::DT_RELRENT => self.class().relr_size() as u64,
                                         _ => {
                                             return Err(Error(format!(
                                                 "Cannot generate value for dynamic tag 0x{:x}",
@@ -1804,7 +1826,11 @@ impl<'data> Builder<'data> {
         let mut have_versym = false;
         let mut have_verdef = false;
         let mut have_verneed = false;
+        let mut have_relr = false;
         for section in &self.sections {
+            if section.sh_type == elf::SHT_RELR {
+                have_relr = true;
+            }
             match &section.data {
                 SectionData::DynamicSymbol => have_dynsym = true,
                 SectionData::DynamicString => have_dynstr = true,
@@ -1845,6 +1871,13 @@ impl<'data> Builder<'data> {
                     | Dynamic::Auto {
                         tag: elf::DT_VERDEFNUM,
                     } => have_verdef,
+                    Dynamic::Auto { tag: elf::DT_RELR }
+                    | Dynamic::Auto {
+                        tag: elf::DT_RELRSZ,
+                    }
+                    | Dynamic::Auto {
+                        tag: elf::DT_RELRENT,
+                    } => have_relr,
                     _ => true,
                 });
             }
@@ -1880,6 +1913,364 @@ impl<'data> Builder<'data> {
         }
     }
 
+    /// Pack the relative dynamic relocations into a `SHT_RELR` section.
+    ///
+    /// This finds the relocation section referenced by `DT_RELA` or `DT_REL`,
+    /// removes its relative relocations (such as [`elf::R_X86_64_RELATIVE`]),
+    /// and encodes their offsets in a new `.relr.dyn` section.
+    /// `SHT_RELR` relocations have implicit addends, so for `SHT_RELA` the addends
+    /// are stored in the section data at the relocated address.
+    /// Relocations that cannot be packed are left unchanged.
+    ///
+    /// The new section is placed in the space that is freed at the end of the
+    /// relocation section, and is added to the same segments.
+    /// The `DT_RELASZ` or `DT_RELSZ` entry is updated, the `DT_RELACOUNT` or
+    /// `DT_RELCOUNT` entry is removed, and `DT_RELR`, `DT_RELRSZ` and `DT_RELRENT`
+    /// entries are added. If there is a version dependency on `libc.so.6`, then a
+    /// dependency on the `GLIBC_ABI_DT_RELR` version is also added.
+    ///
+    /// This increases the size of the dynamic section, and may increase the size
+    /// of the dynamic string and version dependency sections, so the caller may
+    /// need to move these sections before writing.
+    ///
+    /// Returns `false` if there were no relocations to pack.
+    pub fn pack_relative_relocations(&mut self) -> Result<bool> {
+        if self
+            .sections
+            .iter()
+            .any(|section| section.sh_type == elf::SHT_RELR)
+        {
+            return Err(Error::new("Unsupported existing SHT_RELR section"));
+        }
+        let relative_type = self.relative_relocation_type().ok_or_else(|| {
+            Error(format!(
+                "Unsupported machine 0x{:x} for SHT_RELR",
+                self.header.e_machine
+            ))
+        })?;
+        let dynamics = self
+            .dynamic_data()
+            .ok_or(Error::new("Missing dynamic section"))?;
+        let rel_addr = dynamics.iter().find_map(|dynamic| match *dynamic {
+            Dynamic::Integer {
+                tag: elf::DT_RELA | elf::DT_REL,
+                val,
+            } => Some(val),
+            _ => None,
+        });
+        let rel_addr = match rel_addr {
+            Some(rel_addr) => rel_addr,
+            None => return Ok(false),
+        };
+        let rel_section = self
+            .sections
+            .iter_mut()
+            .find(|section| {
+                section.sh_addr == rel_addr
+                    && matches!(section.data, SectionData::DynamicRelocation(_))
+            })
+            .ok_or_else(|| {
+                Error(format!(
+                    "Missing dynamic relocation section at address 0x{:x}",
+                    rel_addr
+                ))
+            })?;
+        let rel_id = rel_section.id;
+        let is_rela = rel_section.sh_type == elf::SHT_RELA;
+        let relocations = match &mut rel_section.data {
+            SectionData::DynamicRelocation(relocations) => mem::take(relocations),
+            _ => Vec::new(),
+        };
+
+        let word_size = self.class().relr_size() as u64;
+        let mut offsets = Vec::new();
+        let mut kept = Vec::new();
+        for relocation in relocations {
+            if relocation.r_type == relative_type
+                && relocation.symbol.is_none()
+                && relocation.r_offset % word_size == 0
+                && (!is_rela || self.write_address(relocation.r_offset, relocation.r_addend as u64))
+            {
+                offsets.push(relocation.r_offset);
+            } else {
+                kept.push(relocation);
+            }
+        }
+        let rel_size = (kept.len() * self.class().rel_size(is_rela)) as u64;
+        let rel_section = self.sections.get_mut(rel_id);
+        rel_section.data = SectionData::DynamicRelocation(kept);
+        if offsets.is_empty() {
+            return Ok(false);
+        }
+        offsets.sort_unstable();
+        let relr_data = self.relr_data(&offsets);
+
+        // Each packed relocation needs at most one word, and the relocations
+        // that were removed needed at least two words each.
+        let rel_section = self.sections.get_mut(rel_id);
+        debug_assert!(rel_size + relr_data.len() as u64 <= rel_section.sh_size);
+        rel_section.sh_size = rel_size;
+        let sh_offset = rel_section.sh_offset + rel_size;
+        let sh_addr = rel_section.sh_addr + rel_size;
+        let relr_section = self.sections.add();
+        relr_section.name = b".relr.dyn"[..].into();
+        relr_section.sh_type = elf::SHT_RELR;
+        relr_section.sh_flags = elf::SHF_ALLOC.into();
+        relr_section.sh_addr = sh_addr;
+        relr_section.sh_offset = sh_offset;
+        relr_section.sh_size = relr_data.len() as u64;
+        relr_section.sh_addralign = word_size;
+        relr_section.sh_entsize = word_size;
+        relr_section.data = SectionData::Data(relr_data.into());
+        let relr_id = relr_section.id;
+        for segment in &mut self.segments {
+            if let Some(index) = segment.sections.iter().position(|id| *id == rel_id) {
+                segment.sections.insert(index + 1, relr_id);
+            }
+        }
+
+        let (size_tag, count_tag) = if is_rela {
+            (elf::DT_RELASZ, elf::DT_RELACOUNT)
+        } else {
+            (elf::DT_RELSZ, elf::DT_RELCOUNT)
+        };
+        if let Some(dynamics) = self.dynamic_data_mut() {
+            dynamics.retain(|dynamic| dynamic.tag() != count_tag);
+            for dynamic in dynamics.iter_mut() {
+                if let Dynamic::Integer { tag, val } = dynamic {
+                    if *tag == size_tag {
+                        *val = rel_size;
+                    }
+                }
+            }
+            for tag in [elf::DT_RELR, elf::DT_RELRSZ, elf::DT_RELRENT] {
+                dynamics.push(Dynamic::Auto { tag });
+            }
+        }
+
+        let libc = self
+            .version_files
+            .iter()
+            .find(|file| file.name.as_slice() == b"libc.so.6")
+            .map(|file| file.id);
+        if let Some(file) = libc {
+            let have_need = self.versions.iter().any(|version| match &version.data {
+                VersionData::Need(need) => need.name.as_slice() == b"GLIBC_ABI_DT_RELR",
+                _ => false,
+            });
+            if !have_need {
+                self.versions.add(VersionData::Need(VersionNeed {
+                    file,
+                    name: b"GLIBC_ABI_DT_RELR"[..].into(),
+                    flags: 0,
+                }));
+            }
+        }
+        Ok(true)
+    }
+
+    /// Return the relative relocation type for the file's machine.
+    fn relative_relocation_type(&self) -> Option<u32> {
+        Some(match self.header.e_machine {
+            elf::EM_386 => elf::R_386_RELATIVE,
+            elf::EM_X86_64 => elf::R_X86_64_RELATIVE,
+            elf::EM_AARCH64 if self.is_64 => elf::R_AARCH64_RELATIVE,
+            elf::EM_AARCH64 => elf::R_AARCH64_P32_RELATIVE,
+            elf::EM_ARM => elf::R_ARM_RELATIVE,
+            elf::EM_LOONGARCH => elf::R_LARCH_RELATIVE,
+            elf::EM_PPC => elf::R_PPC_RELATIVE,
+            elf::EM_PPC64 => elf::R_PPC64_RELATIVE,
+            elf::EM_RISCV => elf::R_RISCV_RELATIVE,
+            elf::EM_S390 => elf::R_390_RELATIVE,
+            elf::EM_SPARC | elf::EM_SPARCV9 => elf::R_SPARC_RELATIVE,
+            _ => return None,
+        })
+    }
+
+    /// Write an address to the section data at the given address.
+    ///
+    /// Returns `false` if there is no section data at the address.
+    fn write_address(&mut self, address: u64, value: u64) -> bool {
+        let endian = self.endian;
+        let is_64 = self.is_64;
+        for section in &mut self.sections {
+            if !section.is_alloc()
+                || section.sh_flags & u64::from(elf::SHF_TLS) != 0
+                || address < section.sh_addr
+                || address - section.sh_addr >= section.sh_size
+            {
+                continue;
+            }
+            if let SectionData::Data(data) = &mut section.data {
+                let offset = (address - section.sh_addr) as usize;
+                let bytes = if is_64 {
+                    &endian.write_u64(value)[..]
+                } else {
+                    &endian.write_u32(value as u32)[..]
+                };
+                if let Some(data) = data.to_mut().get_mut(offset..offset + bytes.len()) {
+                    data.copy_from_slice(bytes);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Encode the sorted offsets of relative relocations as `SHT_RELR` data.
+    fn relr_data(&self, offsets: &[u64]) -> Vec<u8> {
+        let word_size = self.class().relr_size() as u64;
+        // The number of words that each bitmap entry can relocate.
+        let bitmap_size = word_size * 8 - 1;
+        let mut entries = Vec::new();
+        let mut i = 0;
+        while i < offsets.len() {
+            // An address entry relocates a single word, and is followed by bitmap
+            // entries for the words after it.
+            entries.push(offsets[i]);
+            let mut base = offsets[i] + word_size;
+            i += 1;
+            loop {
+                let mut bitmap = 0;
+                while let Some(offset) = offsets.get(i) {
+                    let delta = offset.wrapping_sub(base);
+                    if delta >= bitmap_size * word_size || delta % word_size != 0 {
+                        break;
+                    }
+                    bitmap |= 1 << (delta / word_size);
+                    i += 1;
+                }
+                if bitmap == 0 {
+                    break;
+                }
+                entries.push((bitmap << 1) | 1);
+                base += bitmap_size * word_size;
+            }
+        }
+
+        let mut data = Vec::with_capacity(entries.len() * word_size as usize);
+        for entry in entries {
+            if self.is_64 {
+                data.extend_from_slice(&self.endian.write_u64(entry));
+            } else {
+                data.extend_from_slice(&self.endian.write_u32(entry as u32));
+            }
+        }
+        data
+    }
+
+    /// Compress the data for debug sections.
+    ///
+    /// This compresses the data for sections that are not allocated and have a name
+    /// starting with `.debug`, and sets the `SHF_COMPRESSED` flag for them.
+    /// Sections that are already compressed are not changed.
+    ///
+    /// Requires `feature = "compression"`.
+    #[cfg(feature = "compression")]
+    pub fn compress_debug_sections(&mut self, format: CompressionFormat) -> Result<()> {
+        let endian = self.endian;
+        let is_64 = self.is_64;
+        let align = self.class().align() as u64;
+        for section in &mut self.sections {
+            if section.is_alloc()
+                || section.sh_flags & u64::from(elf::SHF_COMPRESSED) != 0
+                || !section.name.as_slice().starts_with(b".debug")
+            {
+                continue;
+            }
+            let data = match &section.data {
+                SectionData::Data(data) if !data.is_empty() => data,
+                _ => continue,
+            };
+            let data = write::elf::compress_section_data(
+                endian,
+                is_64,
+                format,
+                section.sh_addralign,
+                data,
+            )?;
+            section.sh_size = data.len() as u64;
+            section.data = SectionData::Data(data.into());
+            section.sh_flags |= u64::from(elf::SHF_COMPRESSED);
+            section.sh_addralign = align;
+        }
+        Ok(())
+    }
+
+    /// Decompress the data for compressed sections.
+    ///
+    /// This handles sections with the `SHF_COMPRESSED` flag, and GNU compressed
+    /// `.zdebug` sections. `.zdebug` sections are renamed to `.debug`.
+    ///
+    /// Requires `feature = "compression"`.
+    #[cfg(feature = "compression")]
+    pub fn decompress_sections(&mut self) -> Result<()> {
+        let endian = self.endian;
+        let is_64 = self.is_64;
+        for section in &mut self.sections {
+            let data = match &section.data {
+                SectionData::Data(data) => data.as_slice(),
+                _ => continue,
+            };
+            if section.sh_flags & u64::from(elf::SHF_COMPRESSED) != 0 {
+                let (ch_type, ch_size, ch_addralign, data) = if is_64 {
+                    let (header, data) =
+                        pod::from_bytes::<elf::CompressionHeader64<Endianness>>(data)
+                            .map_err(|_| Error::new("Invalid ELF compression header"))?;
+                    (
+                        header.ch_type.get(endian),
+                        header.ch_size.get(endian),
+                        header.ch_addralign.get(endian),
+                        data,
+                    )
+                } else {
+                    let (header, data) =
+                        pod::from_bytes::<elf::CompressionHeader32<Endianness>>(data)
+                            .map_err(|_| Error::new("Invalid ELF compression header"))?;
+                    (
+                        header.ch_type.get(endian),
+                        header.ch_size.get(endian).into(),
+                        header.ch_addralign.get(endian).into(),
+                        data,
+                    )
+                };
+                let format = match ch_type {
+                    elf::ELFCOMPRESS_ZLIB => CompressionFormat::Zlib,
+                    elf::ELFCOMPRESS_ZSTD => CompressionFormat::Zstandard,
+                    _ => {
+                        return Err(Error(format!(
+                            "Unsupported compression type {} for section '{}'",
+                            ch_type, section.name
+                        )));
+                    }
+                };
+                let data = read::CompressedData {
+                    format,
+                    data,
+                    uncompressed_size: ch_size,
+                }
+                .decompress()?
+                .into_owned();
+                section.sh_size = data.len() as u64;
+                section.data = SectionData::Data(data.into());
+                section.sh_flags &= !u64::from(elf::SHF_COMPRESSED);
+                section.sh_addralign = ch_addralign;
+            } else if let Some(suffix) = section.name.as_slice().strip_prefix(b".zdebug") {
+                let data =
+                    read::gnu_compression::compressed_file_range(data, 0, data.len() as u64)?
+                        .data(data)?
+                        .decompress()?
+                        .into_owned();
+                let mut name = b".debug".to_vec();
+                name.extend_from_slice(suffix);
+                section.name = name.into();
+                section.sh_size = data.len() as u64;
+                section.data = SectionData::Data(data.into());
+            }
+        }
+        Ok(())
+    }
+
     /// Return the ELF file class that will be written.
     ///
     /// This can be useful for calculating sizes.
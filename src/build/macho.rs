@@ -0,0 +1,2377 @@
+//! This module provides a [`Builder`] for reading, modifying, and then writing Mach-O files.
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+#[cfg(not(feature = "std"))]
+use hashbrown::HashMap;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use crate::build::{ByteString, Bytes, Error, Id, IdPrivate, Item, Result, Table};
+use crate::endian::{BigEndian, Endian, Endianness, U16, U32, U64};
+use crate::macho;
+use crate::pod::{self, Pod};
+use crate::read::macho::{MachHeader, Nlist, Section as _, Segment as _};
+use crate::read::{FileKind, ReadRef};
+use crate::write;
+
+/// A builder for reading, modifying, and then writing Mach-O files.
+///
+/// Public fields are available for modifying the values that will be written.
+/// Methods are available to add elements to tables, and elements can be deleted
+/// from tables by setting the `delete` field in the element.
+///
+/// For object files, the file is laid out again when writing. For all other
+/// file types, the segment data is written at its existing file offset, and
+/// only the load commands and the contents of the `__LINKEDIT` segment are
+/// regenerated. The load commands must fit in the space before the first
+/// section or segment data.
+/// For linked images that are created from scratch, [`Builder::layout_image`]
+/// can be used to assign these file offsets.
+#[derive(Debug)]
+pub struct Builder<'data> {
+    /// The endianness.
+    ///
+    /// Used to set the magic number when writing the Mach-O file.
+    pub endian: Endianness,
+    /// Whether file is 64-bit.
+    ///
+    /// Use to set the magic number and the format of the load commands
+    /// when writing the Mach-O file.
+    pub is_64: bool,
+    /// The file header.
+    pub header: Header,
+    /// The load commands.
+    ///
+    /// The load commands are written in this order.
+    pub load_commands: Vec<LoadCommand<'data>>,
+    /// The segment table.
+    ///
+    /// Segments are only written if they are referenced by a
+    /// [`LoadCommand::Segment`].
+    pub segments: Segments<'data>,
+    /// The section table.
+    ///
+    /// Sections are only written if they are referenced by a [`Segment`].
+    pub sections: Sections<'data>,
+    /// The symbol table.
+    ///
+    /// This is only written if there is a [`LoadCommand::Symtab`].
+    pub symbols: Symbols<'data>,
+    /// The indirect symbol table.
+    ///
+    /// This is only written if there is a [`LoadCommand::Dysymtab`].
+    pub indirect_symbols: Vec<IndirectSymbol>,
+    /// The external relocations.
+    ///
+    /// This is only written if there is a [`LoadCommand::Dysymtab`].
+    pub external_relocations: Vec<Relocation>,
+    /// The local relocations.
+    ///
+    /// This is only written if there is a [`LoadCommand::Dysymtab`].
+    pub local_relocations: Vec<Relocation>,
+    marker: PhantomData<()>,
+}
+
+impl<'data> Builder<'data> {
+    /// Create a new Mach-O builder.
+    pub fn new(endian: Endianness, is_64: bool) -> Self {
+        Self {
+            endian,
+            is_64,
+            header: Header::default(),
+            load_commands: Vec::new(),
+            segments: Segments::new(),
+            sections: Sections::new(),
+            symbols: Symbols::new(),
+            indirect_symbols: Vec::new(),
+            external_relocations: Vec::new(),
+            local_relocations: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Read the Mach-O file from file data.
+    pub fn read<R: ReadRef<'data>>(data: R) -> Result<Self> {
+        match FileKind::parse(data)? {
+            FileKind::MachO32 => Self::read32(data),
+            FileKind::MachO64 => Self::read64(data),
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::new("Not a Mach-O file")),
+        }
+    }
+
+    /// Read a 32-bit Mach-O file from file data.
+    pub fn read32<R: ReadRef<'data>>(data: R) -> Result<Self> {
+        Self::read_file::<macho::MachHeader32<Endianness>, R>(data)
+    }
+
+    /// Read a 64-bit Mach-O file from file data.
+    pub fn read64<R: ReadRef<'data>>(data: R) -> Result<Self> {
+        Self::read_file::<macho::MachHeader64<Endianness>, R>(data)
+    }
+
+    fn read_file<Mach, R>(data: R) -> Result<Self>
+    where
+        Mach: MachHeader<Endian = Endianness>,
+        R: ReadRef<'data>,
+    {
+        let header = Mach::parse(data, 0)?;
+        let endian = header.endian()?;
+        let mut builder = Builder {
+            endian,
+            is_64: header.is_type_64(),
+            header: Header {
+                cputype: header.cputype(endian),
+                cpusubtype: header.cpusubtype(endian),
+                filetype: header.filetype(endian),
+                flags: header.flags(endian),
+            },
+            ..Builder::new(endian, header.is_type_64())
+        };
+        let is_object = builder.is_object();
+
+        // Relocations and symbols refer to sections and symbols that may not have been
+        // read yet, so the raw relocations are saved and converted at the end.
+        let mut section_relocations = Vec::new();
+        let mut external_relocations: &[macho::Relocation<Endianness>] = &[];
+        let mut local_relocations: &[macho::Relocation<Endianness>] = &[];
+        let mut indirect_symbols: &[U32<Endianness>] = &[];
+        let mut symtab = None;
+
+        let mut commands = header.load_commands(endian, data, 0)?;
+        while let Some(command) = commands.next()? {
+            if let Some((segment, section_data)) = Mach::Segment::from_command(command)? {
+                let segment_data = if is_object || segment.name() == b"__LINKEDIT" {
+                    &[]
+                } else {
+                    segment
+                        .data(endian, data)
+                        .map_err(|_| Error::new("Invalid Mach-O segment offset or size"))?
+                };
+                let segment_id = builder.segments.next_id();
+                let mut sections = Vec::new();
+                for section in segment.sections(endian, section_data)? {
+                    let section_data = section
+                        .data(endian, data)
+                        .map_err(|_| Error::new("Invalid Mach-O section offset or size"))?;
+                    let id = builder.sections.next_id();
+                    section_relocations.push((id, section.relocations(endian, data)?));
+                    builder.sections.push(Section {
+                        id,
+                        delete: false,
+                        name: section.name().into(),
+                        segment_name: section.segment_name().into(),
+                        addr: section.addr(endian).into(),
+                        size: section.size(endian).into(),
+                        offset: section.offset(endian),
+                        align: section.align(endian),
+                        flags: section.flags(endian),
+                        reserved1: section.reserved1(endian),
+                        reserved2: section.reserved2(endian),
+                        reserved3: section.reserved3(endian),
+                        data: section_data.into(),
+                        relocations: Vec::new(),
+                    });
+                    sections.push(id);
+                }
+                let (fileoff, filesize) = segment.file_range(endian);
+                builder.segments.push(Segment {
+                    id: segment_id,
+                    delete: false,
+                    name: segment.name().into(),
+                    vmaddr: segment.vmaddr(endian).into(),
+                    vmsize: segment.vmsize(endian).into(),
+                    fileoff,
+                    filesize,
+                    maxprot: segment.maxprot(endian),
+                    initprot: segment.initprot(endian),
+                    flags: segment.flags(endian),
+                    sections,
+                    data: segment_data.into(),
+                });
+                builder.load_commands.push(LoadCommand::Segment(segment_id));
+                continue;
+            }
+
+            let cmd = command.cmd();
+            let load_command = match cmd {
+                macho::LC_SYMTAB => {
+                    symtab = command.symtab()?;
+                    LoadCommand::Symtab
+                }
+                macho::LC_DYSYMTAB => {
+                    let dysymtab = command.data::<macho::DysymtabCommand<Endianness>>()?;
+                    if dysymtab.ntoc.get(endian) != 0
+                        || dysymtab.nmodtab.get(endian) != 0
+                        || dysymtab.nextrefsyms.get(endian) != 0
+                    {
+                        return Err(Error::new(
+                            "Unsupported Mach-O dysymtab with a module table",
+                        ));
+                    }
+                    indirect_symbols = dysymtab.indirect_symbols(endian, data)?;
+                    external_relocations = data
+                        .read_slice_at(
+                            dysymtab.extreloff.get(endian).into(),
+                            dysymtab.nextrel.get(endian) as usize,
+                        )
+                        .map_err(|_| Error::new("Invalid Mach-O external relocation offset"))?;
+                    local_relocations = data
+                        .read_slice_at(
+                            dysymtab.locreloff.get(endian).into(),
+                            dysymtab.nlocrel.get(endian) as usize,
+                        )
+                        .map_err(|_| Error::new("Invalid Mach-O local relocation offset"))?;
+                    LoadCommand::Dysymtab
+                }
+                macho::LC_ID_DYLIB
+                | macho::LC_LOAD_DYLIB
+                | macho::LC_LOAD_WEAK_DYLIB
+                | macho::LC_REEXPORT_DYLIB
+                | macho::LC_LAZY_LOAD_DYLIB
+                | macho::LC_LOAD_UPWARD_DYLIB => {
+                    let dylib = command.data::<macho::DylibCommand<Endianness>>()?;
+                    LoadCommand::Dylib(Dylib {
+                        cmd,
+                        name: command.string(endian, dylib.dylib.name)?.into(),
+                        timestamp: dylib.dylib.timestamp.get(endian),
+                        current_version: dylib.dylib.current_version.get(endian),
+                        compatibility_version: dylib.dylib.compatibility_version.get(endian),
+                    })
+                }
+                macho::LC_RPATH => {
+                    let rpath = command.data::<macho::RpathCommand<Endianness>>()?;
+                    LoadCommand::Rpath(command.string(endian, rpath.path)?.into())
+                }
+                macho::LC_CODE_SIGNATURE
+                | macho::LC_SEGMENT_SPLIT_INFO
+                | macho::LC_FUNCTION_STARTS
+                | macho::LC_DATA_IN_CODE
+                | macho::LC_DYLIB_CODE_SIGN_DRS
+                | macho::LC_LINKER_OPTIMIZATION_HINT
+                | macho::LC_DYLD_EXPORTS_TRIE
+                | macho::LC_DYLD_CHAINED_FIXUPS => {
+                    let linkedit = command.data::<macho::LinkeditDataCommand<Endianness>>()?;
+                    LoadCommand::LinkeditData {
+                        cmd,
+                        data: read_linkedit(data, endian, linkedit.dataoff, linkedit.datasize)?,
+                    }
+                }
+                macho::LC_DYLD_INFO | macho::LC_DYLD_INFO_ONLY => {
+                    let dyld_info = command.data::<macho::DyldInfoCommand<Endianness>>()?;
+                    LoadCommand::DyldInfo(DyldInfo {
+                        cmd,
+                        rebase: read_linkedit(
+                            data,
+                            endian,
+                            dyld_info.rebase_off,
+                            dyld_info.rebase_size,
+                        )?,
+                        bind: read_linkedit(data, endian, dyld_info.bind_off, dyld_info.bind_size)?,
+                        weak_bind: read_linkedit(
+                            data,
+                            endian,
+                            dyld_info.weak_bind_off,
+                            dyld_info.weak_bind_size,
+                        )?,
+                        lazy_bind: read_linkedit(
+                            data,
+                            endian,
+                            dyld_info.lazy_bind_off,
+                            dyld_info.lazy_bind_size,
+                        )?,
+                        export: read_linkedit(
+                            data,
+                            endian,
+                            dyld_info.export_off,
+                            dyld_info.export_size,
+                        )?,
+                    })
+                }
+                macho::LC_TWOLEVEL_HINTS => {
+                    return Err(Error::new("Unsupported Mach-O LC_TWOLEVEL_HINTS command"));
+                }
+                _ => LoadCommand::Other(command.raw_data().into()),
+            };
+            builder.load_commands.push(load_command);
+        }
+
+        if let Some(symtab) = symtab {
+            let table = symtab.symbols::<Mach, R>(endian, data)?;
+            let strings = table.strings();
+            for nlist in table.iter() {
+                let n_sect = nlist.n_sect();
+                let section = if n_sect == macho::NO_SECT {
+                    None
+                } else {
+                    let index = usize::from(n_sect) - 1;
+                    (index < builder.sections.len()).then_some(SectionId(index))
+                };
+                let id = builder.symbols.next_id();
+                builder.symbols.push(Symbol {
+                    id,
+                    delete: false,
+                    name: nlist.name(endian, strings)?.into(),
+                    n_type: nlist.n_type(),
+                    section,
+                    n_sect,
+                    n_desc: nlist.n_desc(endian),
+                    n_value: nlist.n_value(endian).into(),
+                });
+            }
+        }
+
+        for index in indirect_symbols {
+            let index = index.get(endian);
+            let symbol = if index & (macho::INDIRECT_SYMBOL_LOCAL | macho::INDIRECT_SYMBOL_ABS) != 0
+            {
+                IndirectSymbol::Value(index)
+            } else {
+                IndirectSymbol::Symbol(builder.symbol_id(index)?)
+            };
+            builder.indirect_symbols.push(symbol);
+        }
+        for (id, relocations) in section_relocations {
+            let relocations = builder.read_relocations(relocations)?;
+            builder.sections.get_mut(id).relocations = relocations;
+        }
+        builder.external_relocations = builder.read_relocations(external_relocations)?;
+        builder.local_relocations = builder.read_relocations(local_relocations)?;
+        Ok(builder)
+    }
+
+    fn read_relocations(
+        &self,
+        relocations: &[macho::Relocation<Endianness>],
+    ) -> Result<Vec<Relocation>> {
+        let endian = self.endian;
+        let mut result = Vec::with_capacity(relocations.len());
+        for relocation in relocations {
+            if relocation.r_scattered(endian, self.header.cputype) {
+                let info = relocation.scattered_info(endian);
+                result.push(Relocation {
+                    r_address: info.r_address,
+                    target: RelocationTarget::Value(info.r_value),
+                    r_pcrel: info.r_pcrel,
+                    r_length: info.r_length,
+                    r_type: info.r_type,
+                    r_scattered: true,
+                });
+                continue;
+            }
+            let info = relocation.info(endian);
+            let target = if info.r_extern {
+                RelocationTarget::Symbol(self.symbol_id(info.r_symbolnum)?)
+            } else if info.r_symbolnum == u32::from(macho::R_ABS) || self.is_pair(info.r_type) {
+                RelocationTarget::Value(info.r_symbolnum)
+            } else {
+                // `r_symbolnum` is a 1-based section ordinal.
+                let index = info.r_symbolnum as usize - 1;
+                if index >= self.sections.len() {
+                    return Err(Error(format!(
+                        "Invalid Mach-O relocation section index {}",
+                        info.r_symbolnum
+                    )));
+                }
+                RelocationTarget::Section(SectionId(index))
+            };
+            result.push(Relocation {
+                r_address: info.r_address,
+                target,
+                r_pcrel: info.r_pcrel,
+                r_length: info.r_length,
+                r_type: info.r_type,
+                r_scattered: false,
+            });
+        }
+        Ok(result)
+    }
+
+    fn symbol_id(&self, index: u32) -> Result<SymbolId> {
+        if index as usize >= self.symbols.len() {
+            return Err(Error(format!("Invalid Mach-O symbol index {}", index)));
+        }
+        Ok(SymbolId(index as usize))
+    }
+
+    /// Return true if the file type is `MH_OBJECT`.
+    ///
+    /// Object files are laid out again when writing.
+    pub fn is_object(&self) -> bool {
+        self.header.filetype == macho::MH_OBJECT
+    }
+
+    /// Return true if `r_symbolnum` is not a symbol or section for this relocation type.
+    fn is_pair(&self, r_type: u8) -> bool {
+        match self.header.cputype {
+            macho::CPU_TYPE_ARM64 | macho::CPU_TYPE_ARM64_32 => r_type == macho::ARM64_RELOC_ADDEND,
+            macho::CPU_TYPE_X86 => r_type == macho::GENERIC_RELOC_PAIR,
+            macho::CPU_TYPE_ARM => r_type == macho::ARM_RELOC_PAIR,
+            macho::CPU_TYPE_POWERPC | macho::CPU_TYPE_POWERPC64 => r_type == macho::PPC_RELOC_PAIR,
+            _ => false,
+        }
+    }
+
+    fn page_size(&self) -> u64 {
+        match self.header.cputype {
+            macho::CPU_TYPE_ARM64 | macho::CPU_TYPE_ARM64_32 => 0x4000,
+            _ => 0x1000,
+        }
+    }
+
+    fn pointer_align(&self) -> usize {
+        if self.is_64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// Return the segment for a segment load command if it will be written.
+    fn live_segment(&self, command: &LoadCommand<'data>) -> Option<&Segment<'data>> {
+        match command {
+            LoadCommand::Segment(id) => Some(self.segments.get(*id)).filter(|s| !s.delete),
+            _ => None,
+        }
+    }
+
+    /// Iterate over the sections of a segment that will be written.
+    fn live_sections<'a>(
+        &'a self,
+        segment: &'a Segment<'data>,
+    ) -> impl Iterator<Item = &'a Section<'data>> + 'a {
+        segment
+            .sections
+            .iter()
+            .map(move |id| self.sections.get(*id))
+            .filter(|section| !section.delete)
+    }
+
+    /// Delete segments, symbols, and relocations that refer to deleted items.
+    ///
+    /// This calls `delete_orphan_segments`, `delete_orphan_symbols`,
+    /// and `delete_orphan_relocations`.
+    ///
+    /// This is not called by [`Self::write`]. Writing will fail if there
+    /// are any references to deleted symbols or sections.
+    pub fn delete_orphans(&mut self) {
+        self.delete_orphan_segments();
+        self.delete_orphan_symbols();
+        self.delete_orphan_relocations();
+    }
+
+    /// Set the delete flag for segments that only refer to deleted sections.
+    pub fn delete_orphan_segments(&mut self) {
+        let sections = &self.sections;
+        for segment in &mut self.segments {
+            // We only delete segments that have become empty due to section deletions.
+            if segment.sections.is_empty() {
+                continue;
+            }
+            segment.sections.retain(|id| !sections.get(*id).delete);
+            segment.delete = segment.sections.is_empty();
+        }
+    }
+
+    /// Set the delete flag for symbols that refer to deleted sections.
+    pub fn delete_orphan_symbols(&mut self) {
+        for symbol in &mut self.symbols {
+            if let Some(section) = symbol.section {
+                if self.sections.get_mut(section).delete {
+                    symbol.delete = true;
+                }
+            }
+        }
+    }
+
+    /// Delete relocations that refer to deleted symbols or sections.
+    pub fn delete_orphan_relocations(&mut self) {
+        let deleted_symbols: Vec<bool> = (0..self.symbols.len())
+            .map(|index| self.symbols.get(SymbolId(index)).delete)
+            .collect();
+        let deleted_sections: Vec<bool> = (0..self.sections.len())
+            .map(|index| self.sections.get(SectionId(index)).delete)
+            .collect();
+        let is_live = |relocation: &Relocation| match relocation.target {
+            RelocationTarget::Symbol(id) => !deleted_symbols[id.0],
+            RelocationTarget::Section(id) => !deleted_sections[id.0],
+            RelocationTarget::Value(_) => true,
+        };
+        self.external_relocations.retain(is_live);
+        self.local_relocations.retain(is_live);
+        for section in &mut self.sections {
+            section.relocations.retain(is_live);
+        }
+    }
+
+    /// Set the addresses and file offsets of the segments and sections of a linked image.
+    ///
+    /// This is intended for images that are created from scratch, since the file data
+    /// of other file types is otherwise written at its existing offset.
+    ///
+    /// The segments are laid out in the order of their load commands, starting at the
+    /// `vmaddr` of the first segment, and each segment is page aligned. Segments without
+    /// any sections, such as `__PAGEZERO`, are given no file data and keep their `vmsize`.
+    /// The file header and load commands are placed at the start of the first segment
+    /// that has sections, so all load commands must be added before calling this.
+    /// The `__LINKEDIT` segment must be the last segment, and its size is calculated
+    /// when writing.
+    ///
+    /// The size of each section that is not zero fill is set to the size of its data.
+    /// Zero fill sections must follow all other sections in their segment.
+    ///
+    /// Symbol values are not changed.
+    pub fn layout_image(&mut self) -> Result<()> {
+        if self.is_object() {
+            return Err(Error::new(
+                "Mach-O object files are laid out when they are written",
+            ));
+        }
+        let page_size = self.page_size();
+        let mut commands_end = self.header_size() as u64;
+        for command in &self.load_commands {
+            if let Some(size) = self.command_size(command) {
+                commands_end += size as u64;
+            }
+        }
+        let segment_ids: Vec<SegmentId> = self
+            .load_commands
+            .iter()
+            .filter_map(|command| self.live_segment(command))
+            .map(|segment| segment.id)
+            .collect();
+
+        let mut vmaddr = match segment_ids.first() {
+            Some(id) => self.segments.get(*id).vmaddr,
+            None => return Ok(()),
+        };
+        let mut fileoff = 0;
+        let mut have_header = false;
+        for (index, id) in segment_ids.iter().enumerate() {
+            let segment = self.segments.get_mut(*id);
+            segment.vmaddr = vmaddr;
+            if &*segment.name == b"__LINKEDIT" {
+                if index + 1 != segment_ids.len() {
+                    return Err(Error::new("Mach-O __LINKEDIT must be the last segment"));
+                }
+                segment.fileoff = fileoff;
+                continue;
+            }
+            let sections = &mut self.sections;
+            if segment.sections.iter().all(|id| sections.get(*id).delete) {
+                segment.vmsize = write::align_u64(segment.vmsize, page_size);
+                segment.fileoff = 0;
+                segment.filesize = 0;
+                vmaddr += segment.vmsize;
+                continue;
+            }
+
+            // Offset relative to the start of the segment.
+            let mut offset = 0;
+            if !have_header {
+                have_header = true;
+                offset = commands_end;
+            }
+            let mut file_end = offset;
+            let mut zerofill = false;
+            for section_id in &segment.sections {
+                let section = sections.get_mut(*section_id);
+                if section.delete {
+                    continue;
+                }
+                let align = 1u64
+                    .checked_shl(section.align)
+                    .ok_or_else(|| Error::new("Invalid Mach-O section alignment"))?;
+                offset = write::align_u64(offset, align);
+                section.addr = vmaddr + offset;
+                if section.is_zerofill() {
+                    zerofill = true;
+                    section.offset = 0;
+                } else {
+                    if zerofill {
+                        return Err(Error(format!(
+                            "Mach-O section `{}` must be before the zero fill sections in its segment",
+                            section.name
+                        )));
+                    }
+                    section.size = section.data.len() as u64;
+                    section.offset = u32::try_from(fileoff + offset)
+                        .map_err(|_| Error::new("Mach-O section offset is too large"))?;
+                    file_end = offset + section.size;
+                }
+                offset += section.size;
+            }
+            segment.fileoff = fileoff;
+            segment.filesize = write::align_u64(file_end, page_size);
+            segment.vmsize = write::align_u64(offset, page_size);
+            fileoff += segment.filesize;
+            vmaddr += segment.vmsize;
+        }
+        Ok(())
+    }
+
+    /// Return the exports for the defined external symbols.
+    ///
+    /// This is intended to be used with [`write_exports_trie`]. Private external
+    /// and indirect symbols are not included.
+    ///
+    /// The addresses are relative to the segment containing the file header,
+    /// except for absolute symbols.
+    pub fn exports(&self) -> Vec<Export<'data>> {
+        let base = self
+            .load_commands
+            .iter()
+            .filter_map(|command| self.live_segment(command))
+            .find(|segment| segment.fileoff == 0 && segment.filesize != 0)
+            .map(|segment| segment.vmaddr)
+            .unwrap_or(0);
+        let mut exports = Vec::new();
+        for symbol in &self.symbols {
+            if symbol.is_local() || symbol.is_undefined() || symbol.n_type & macho::N_PEXT != 0 {
+                continue;
+            }
+            let mut flags = if symbol.n_desc & macho::N_WEAK_DEF != 0 {
+                macho::EXPORT_SYMBOL_FLAGS_WEAK_DEFINITION
+            } else {
+                0
+            };
+            let address = match symbol.n_type & macho::N_TYPE {
+                macho::N_ABS => {
+                    flags |= macho::EXPORT_SYMBOL_FLAGS_KIND_ABSOLUTE;
+                    symbol.n_value
+                }
+                macho::N_SECT => {
+                    let is_tls = symbol.section.map_or(false, |id| {
+                        self.sections.get(id).flags & macho::SECTION_TYPE
+                            == macho::S_THREAD_LOCAL_VARIABLES
+                    });
+                    if is_tls {
+                        flags |= macho::EXPORT_SYMBOL_FLAGS_KIND_THREAD_LOCAL;
+                    }
+                    symbol.n_value.wrapping_sub(base)
+                }
+                _ => continue,
+            };
+            exports.push(Export {
+                name: symbol.name.clone(),
+                flags,
+                data: ExportData::Regular { address },
+            });
+        }
+        exports
+    }
+
+    /// Replace the code signature with an ad hoc signature.
+    ///
+    /// The first `LC_CODE_SIGNATURE` load command is replaced with a
+    /// [`LoadCommand::AdhocSignature`], and any others are removed.
+    /// If there is no existing code signature, then the load command is added after
+    /// all other load commands, and it must fit in the space before the first section.
+    ///
+    /// Writing the signature requires the `codesign` feature.
+    pub fn set_adhoc_signature(&mut self, identifier: ByteString<'data>) {
+        let signature = AdhocSignature {
+            identifier,
+            flags: 0,
+        };
+        let mut signature = Some(LoadCommand::AdhocSignature(signature));
+        self.load_commands.retain_mut(|command| {
+            let is_signature = matches!(
+                command,
+                LoadCommand::LinkeditData {
+                    cmd: macho::LC_CODE_SIGNATURE,
+                    ..
+                } | LoadCommand::AdhocSignature(_)
+            );
+            if !is_signature {
+                return true;
+            }
+            match signature.take() {
+                Some(signature) => {
+                    *command = signature;
+                    true
+                }
+                None => false,
+            }
+        });
+        if let Some(signature) = signature {
+            self.load_commands.push(signature);
+        }
+    }
+
+    /// Write the Mach-O file.
+    pub fn write(self, buffer: &mut dyn write::WritableBuffer) -> Result<()> {
+        let endian = self.endian;
+        let pointer_align = self.pointer_align();
+
+        // Map from section IDs to 1-based section ordinals in the output.
+        let mut section_ordinals = vec![None; self.sections.len()];
+        let mut section_count = 0;
+        for command in &self.load_commands {
+            let Some(segment) = self.live_segment(command) else {
+                continue;
+            };
+            for section in self.live_sections(segment) {
+                section_count += 1;
+                section_ordinals[section.id.0] = Some(section_count);
+            }
+        }
+        if section_count > usize::from(macho::MAX_SECT) {
+            return Err(Error::new("Too many Mach-O sections"));
+        }
+
+        // Determine the symbol order, and the map from symbol IDs to output indices.
+        let has_symtab = self
+            .load_commands
+            .iter()
+            .any(|command| matches!(command, LoadCommand::Symtab));
+        let has_dysymtab = self
+            .load_commands
+            .iter()
+            .any(|command| matches!(command, LoadCommand::Dysymtab));
+        let mut order: Vec<&Symbol<'data>> = Vec::new();
+        let mut nlocalsym = 0;
+        let mut nextdefsym = 0;
+        if has_dysymtab {
+            // Local symbols, then defined external symbols, then undefined symbols.
+            // The external symbols are sorted by name.
+            let mut extdefs = Vec::new();
+            let mut undefs = Vec::new();
+            for symbol in &self.symbols {
+                if symbol.is_local() {
+                    order.push(symbol);
+                } else if symbol.is_undefined() {
+                    undefs.push(symbol);
+                } else {
+                    extdefs.push(symbol);
+                }
+            }
+            extdefs.sort_by(|a, b| a.name.as_slice().cmp(b.name.as_slice()));
+            undefs.sort_by(|a, b| a.name.as_slice().cmp(b.name.as_slice()));
+            nlocalsym = order.len();
+            nextdefsym = extdefs.len();
+            order.append(&mut extdefs);
+            order.append(&mut undefs);
+        } else if has_symtab {
+            order.extend(&self.symbols);
+        }
+        let mut symbol_map = vec![None; self.symbols.len()];
+        for (index, symbol) in order.iter().enumerate() {
+            symbol_map[symbol.id.0] = Some(index as u32);
+        }
+        let map = OutputMap {
+            builder: &self,
+            symbols: &symbol_map,
+            sections: &section_ordinals,
+        };
+
+        // Build the symbol table and string table.
+        let mut strtab = vec![0];
+        let mut strings = HashMap::new();
+        let mut symtab = Vec::new();
+        for symbol in &order {
+            let n_strx = if symbol.name.is_empty() {
+                0
+            } else {
+                *strings.entry(symbol.name.as_slice()).or_insert_with(|| {
+                    let offset = strtab.len() as u32;
+                    strtab.extend_from_slice(&symbol.name);
+                    strtab.push(0);
+                    offset
+                })
+            };
+            let n_sect = match symbol.section {
+                Some(id) => map.section(id).map_err(|_| {
+                    Error(format!(
+                        "Mach-O symbol `{}` refers to deleted section",
+                        symbol.name
+                    ))
+                })? as u8,
+                None => symbol.n_sect,
+            };
+            if self.is_64 {
+                write_pod(
+                    &mut symtab,
+                    &macho::Nlist64 {
+                        n_strx: U32::new(endian, n_strx),
+                        n_type: symbol.n_type,
+                        n_sect,
+                        n_desc: U16::new(endian, symbol.n_desc),
+                        n_value: U64::new(endian, symbol.n_value),
+                    },
+                );
+            } else {
+                write_pod(
+                    &mut symtab,
+                    &macho::Nlist32 {
+                        n_strx: U32::new(endian, n_strx),
+                        n_type: symbol.n_type,
+                        n_sect,
+                        n_desc: U16::new(endian, symbol.n_desc),
+                        n_value: U32::new(endian, symbol.n_value as u32),
+                    },
+                );
+            }
+        }
+        strtab.resize(write::align(strtab.len(), pointer_align), 0);
+
+        let mut indirect_symbols = Vec::with_capacity(self.indirect_symbols.len() * 4);
+        for symbol in &self.indirect_symbols {
+            let index = match *symbol {
+                IndirectSymbol::Symbol(id) => map.symbol(id, "indirect symbol table")?,
+                IndirectSymbol::Value(value) => value,
+            };
+            write_pod(&mut indirect_symbols, &U32::new(endian, index));
+        }
+        let external_relocations = map.relocations(&self.external_relocations)?;
+        let local_relocations = map.relocations(&self.local_relocations)?;
+        let mut section_relocations = HashMap::new();
+        for section in &self.sections {
+            if section_ordinals[section.id.0].is_some() && !section.relocations.is_empty() {
+                section_relocations.insert(section.id.0, map.relocations(&section.relocations)?);
+            }
+        }
+
+        // Collect the data that is placed after the segment data, in the order
+        // that it is written.
+        let mut blobs = Vec::new();
+        for command in &self.load_commands {
+            let Some(segment) = self.live_segment(command) else {
+                continue;
+            };
+            for section in self.live_sections(segment) {
+                if let Some(data) = section_relocations.get(&section.id.0) {
+                    blobs.push(Blob::new(BlobId::Relocations(section.id.0), 0, data));
+                }
+            }
+        }
+        if has_dysymtab {
+            blobs.push(Blob::new(BlobId::LocalRelocations, 0, &local_relocations));
+            blobs.push(Blob::new(
+                BlobId::ExternalRelocations,
+                0,
+                &external_relocations,
+            ));
+        }
+        for (index, command) in self.load_commands.iter().enumerate() {
+            match command {
+                LoadCommand::DyldInfo(dyld_info) => {
+                    for (i, data) in dyld_info.blobs().iter().enumerate() {
+                        blobs.push(Blob::new(BlobId::Linkedit(index, i), 1, data));
+                    }
+                }
+                LoadCommand::LinkeditData { cmd, data } => {
+                    let rank = match *cmd {
+                        macho::LC_DYLD_CHAINED_FIXUPS => 2,
+                        macho::LC_DYLD_EXPORTS_TRIE => 3,
+                        macho::LC_DYLIB_CODE_SIGN_DRS => 6,
+                        // The code signature must be last, since it covers everything before it.
+                        macho::LC_CODE_SIGNATURE => 7,
+                        _ => 4,
+                    };
+                    blobs.push(Blob::new(BlobId::Linkedit(index, 0), rank, data));
+                }
+                _ => {}
+            }
+        }
+        // The ad hoc signature size depends on its offset, so it is not a blob.
+        let mut signatures =
+            self.load_commands
+                .iter()
+                .enumerate()
+                .filter_map(|(index, command)| match command {
+                    LoadCommand::AdhocSignature(signature) => Some((index, signature)),
+                    _ => None,
+                });
+        let adhoc_signature = signatures.next();
+        if adhoc_signature.is_some() {
+            if signatures.next().is_some() {
+                return Err(Error::new("Multiple Mach-O ad hoc signatures"));
+            }
+            if self.is_object() {
+                return Err(Error::new("Mach-O object files cannot be code signed"));
+            }
+            if cfg!(not(feature = "codesign")) {
+                return Err(Error::new(
+                    "Mach-O ad hoc signatures require the `codesign` feature",
+                ));
+            }
+        }
+        if has_symtab {
+            blobs.push(Blob::new(BlobId::Symtab, 5, &symtab));
+            if has_dysymtab {
+                blobs.push(Blob::new(BlobId::IndirectSymbols, 5, &indirect_symbols));
+            }
+            blobs.push(Blob::new(BlobId::Strtab, 5, &strtab));
+        }
+        blobs.retain(|blob| !blob.data.is_empty());
+        blobs.sort_by_key(|blob| blob.rank);
+
+        // Calculate the size of the load commands.
+        let header_size = self.header_size();
+        let mut ncmds = 0;
+        let mut sizeofcmds = 0;
+        for command in &self.load_commands {
+            if let Some(size) = self.command_size(command) {
+                ncmds += 1;
+                sizeofcmds += size;
+            }
+        }
+        let commands_end = header_size + sizeofcmds;
+
+        // Layout the segment data, and write everything before the blobs.
+        let mut data;
+        let mut segment_layouts = HashMap::new();
+        let mut section_offsets = vec![0; self.sections.len()];
+        let mut linkedit = None;
+        if self.is_object() {
+            data = vec![0; commands_end];
+            for command in &self.load_commands {
+                let Some(segment) = self.live_segment(command) else {
+                    continue;
+                };
+                let mut fileoff = None;
+                for section in self.live_sections(segment) {
+                    if section.is_zerofill() {
+                        continue;
+                    }
+                    let align = 1usize
+                        .checked_shl(section.align)
+                        .ok_or_else(|| Error::new("Invalid Mach-O section alignment"))?;
+                    data.resize(write::align(data.len(), align), 0);
+                    fileoff.get_or_insert(data.len());
+                    section_offsets[section.id.0] = data.len() as u32;
+                    data.extend_from_slice(&section.data);
+                }
+                let fileoff = fileoff.unwrap_or(data.len());
+                segment_layouts.insert(
+                    segment.id.0,
+                    (fileoff as u64, (data.len() - fileoff) as u64),
+                );
+            }
+        } else {
+            // The load commands must fit before the first section or segment data.
+            // Deleted sections are included, since their data is still present
+            // in the segment data.
+            let mut header_space = u64::MAX;
+            let mut end = 0;
+            for command in &self.load_commands {
+                let Some(segment) = self.live_segment(command) else {
+                    continue;
+                };
+                if &*segment.name == b"__LINKEDIT" {
+                    linkedit = Some(segment.id);
+                    continue;
+                }
+                if segment.fileoff != 0 && segment.filesize != 0 {
+                    header_space = header_space.min(segment.fileoff);
+                }
+                end = end.max(segment.fileoff + segment.data.len() as u64);
+                for id in &segment.sections {
+                    let section = self.sections.get(*id);
+                    if !section.is_zerofill() && section.offset != 0 && section.size != 0 {
+                        header_space = header_space.min(section.offset.into());
+                    }
+                    if !section.delete && !section.is_zerofill() {
+                        section_offsets[section.id.0] = section.offset;
+                        end = end.max(u64::from(section.offset) + section.data.len() as u64);
+                    }
+                }
+            }
+            if commands_end as u64 > header_space {
+                return Err(Error(format!(
+                    "Not enough space for Mach-O load commands: {:#x} bytes are needed, but only {:#x} are available",
+                    commands_end, header_space
+                )));
+            }
+            if let Some(id) = linkedit {
+                let fileoff = self.segments.get(id).fileoff;
+                if fileoff < end {
+                    return Err(Error::new("Mach-O segment data overlaps __LINKEDIT"));
+                }
+                end = fileoff;
+            } else if !blobs.is_empty() || adhoc_signature.is_some() {
+                return Err(Error::new("Missing Mach-O __LINKEDIT segment"));
+            }
+            data = vec![0; end as usize];
+            for command in &self.load_commands {
+                let Some(segment) = self.live_segment(command) else {
+                    continue;
+                };
+                let fileoff = segment.fileoff as usize;
+                data[fileoff..][..segment.data.len()].copy_from_slice(&segment.data);
+            }
+            for command in &self.load_commands {
+                let Some(segment) = self.live_segment(command) else {
+                    continue;
+                };
+                for section in self.live_sections(segment) {
+                    if !section.is_zerofill() {
+                        let offset = section.offset as usize;
+                        data[offset..][..section.data.len()].copy_from_slice(&section.data);
+                    }
+                }
+            }
+            // Clear the old load commands, since the new ones may be smaller.
+            let old_end = header_space.min(end) as usize;
+            data[..old_end].fill(0);
+        }
+
+        // Layout the blobs.
+        let mut blob_offsets = HashMap::new();
+        let blobs_start = data.len();
+        for blob in &blobs {
+            // The code signature must be 16 byte aligned.
+            let align = if blob.rank == 7 { 16 } else { pointer_align };
+            data.resize(write::align(data.len(), align), 0);
+            blob_offsets.insert(blob.id, data.len() as u32);
+            data.extend_from_slice(blob.data);
+        }
+        // The ad hoc signature is written last, since it covers everything before it.
+        // Space is reserved here, and its contents are calculated after all other data
+        // has been written.
+        let mut signature_layout = None;
+        if let Some((index, signature)) = adhoc_signature {
+            data.resize(write::align(data.len(), 16), 0);
+            let offset = data.len();
+            let size = signature.size(offset);
+            blob_offsets.insert(BlobId::Linkedit(index, 0), offset as u32);
+            data.resize(offset + size, 0);
+            signature_layout = Some((signature, offset, size));
+        }
+        if linkedit.is_some() {
+            data.resize(write::align(data.len(), pointer_align), 0);
+        }
+        let blobs_size = (data.len() - blobs_start) as u64;
+        // Offsets in load commands are 32-bit.
+        if data.len() as u64 > u64::from(u32::MAX) {
+            return Err(Error::new("Mach-O file is too large"));
+        }
+
+        // Write the header and load commands.
+        let (magic, magic_64) = if endian.is_big_endian() {
+            (macho::MH_MAGIC, macho::MH_MAGIC_64)
+        } else {
+            (macho::MH_CIGAM, macho::MH_CIGAM_64)
+        };
+        let mut commands = Vec::with_capacity(commands_end);
+        if self.is_64 {
+            write_pod(
+                &mut commands,
+                &macho::MachHeader64 {
+                    magic: U32::new(BigEndian, magic_64),
+                    cputype: U32::new(endian, self.header.cputype),
+                    cpusubtype: U32::new(endian, self.header.cpusubtype),
+                    filetype: U32::new(endian, self.header.filetype),
+                    ncmds: U32::new(endian, ncmds),
+                    sizeofcmds: U32::new(endian, sizeofcmds as u32),
+                    flags: U32::new(endian, self.header.flags),
+                    reserved: U32::default(),
+                },
+            );
+        } else {
+            write_pod(
+                &mut commands,
+                &macho::MachHeader32 {
+                    magic: U32::new(BigEndian, magic),
+                    cputype: U32::new(endian, self.header.cputype),
+                    cpusubtype: U32::new(endian, self.header.cpusubtype),
+                    filetype: U32::new(endian, self.header.filetype),
+                    ncmds: U32::new(endian, ncmds),
+                    sizeofcmds: U32::new(endian, sizeofcmds as u32),
+                    flags: U32::new(endian, self.header.flags),
+                },
+            );
+        }
+        let blob_offset =
+            |id: BlobId| U32::new(endian, blob_offsets.get(&id).copied().unwrap_or(0));
+        let blob_size = |data: &[u8]| U32::new(endian, data.len() as u32);
+        for (index, command) in self.load_commands.iter().enumerate() {
+            let Some(cmdsize) = self.command_size(command) else {
+                continue;
+            };
+            let cmdsize = U32::new(endian, cmdsize as u32);
+            match command {
+                LoadCommand::Segment(id) => {
+                    let segment = self.segments.get(*id);
+                    let (fileoff, filesize, vmsize) = if Some(*id) == linkedit {
+                        (
+                            segment.fileoff,
+                            blobs_size,
+                            write::align_u64(blobs_size, self.page_size()),
+                        )
+                    } else if let Some((fileoff, filesize)) = segment_layouts.get(&id.0) {
+                        (*fileoff, *filesize, segment.vmsize)
+                    } else {
+                        (segment.fileoff, segment.filesize, segment.vmsize)
+                    };
+                    let nsects = U32::new(endian, self.live_sections(segment).count() as u32);
+                    let segname = name16(&segment.name)?;
+                    if self.is_64 {
+                        write_pod(
+                            &mut commands,
+                            &macho::SegmentCommand64 {
+                                cmd: U32::new(endian, macho::LC_SEGMENT_64),
+                                cmdsize,
+                                segname,
+                                vmaddr: U64::new(endian, segment.vmaddr),
+                                vmsize: U64::new(endian, vmsize),
+                                fileoff: U64::new(endian, fileoff),
+                                filesize: U64::new(endian, filesize),
+                                maxprot: U32::new(endian, segment.maxprot),
+                                initprot: U32::new(endian, segment.initprot),
+                                nsects,
+                                flags: U32::new(endian, segment.flags),
+                            },
+                        );
+                    } else {
+                        write_pod(
+                            &mut commands,
+                            &macho::SegmentCommand32 {
+                                cmd: U32::new(endian, macho::LC_SEGMENT),
+                                cmdsize,
+                                segname,
+                                vmaddr: U32::new(endian, segment.vmaddr as u32),
+                                vmsize: U32::new(endian, vmsize as u32),
+                                fileoff: U32::new(endian, fileoff as u32),
+                                filesize: U32::new(endian, filesize as u32),
+                                maxprot: U32::new(endian, segment.maxprot),
+                                initprot: U32::new(endian, segment.initprot),
+                                nsects,
+                                flags: U32::new(endian, segment.flags),
+                            },
+                        );
+                    }
+                    for section in self.live_sections(segment) {
+                        let sectname = name16(&section.name)?;
+                        let segname = name16(&section.segment_name)?;
+                        let offset = U32::new(endian, section_offsets[section.id.0]);
+                        let reloff = blob_offset(BlobId::Relocations(section.id.0));
+                        let nreloc = U32::new(endian, section.relocations.len() as u32);
+                        if self.is_64 {
+                            write_pod(
+                                &mut commands,
+                                &macho::Section64 {
+                                    sectname,
+                                    segname,
+                                    addr: U64::new(endian, section.addr),
+                                    size: U64::new(endian, section.size),
+                                    offset,
+                                    align: U32::new(endian, section.align),
+                                    reloff,
+                                    nreloc,
+                                    flags: U32::new(endian, section.flags),
+                                    reserved1: U32::new(endian, section.reserved1),
+                                    reserved2: U32::new(endian, section.reserved2),
+                                    reserved3: U32::new(endian, section.reserved3),
+                                },
+                            );
+                        } else {
+                            write_pod(
+                                &mut commands,
+                                &macho::Section32 {
+                                    sectname,
+                                    segname,
+                                    addr: U32::new(endian, section.addr as u32),
+                                    size: U32::new(endian, section.size as u32),
+                                    offset,
+                                    align: U32::new(endian, section.align),
+                                    reloff,
+                                    nreloc,
+                                    flags: U32::new(endian, section.flags),
+                                    reserved1: U32::new(endian, section.reserved1),
+                                    reserved2: U32::new(endian, section.reserved2),
+                                },
+                            );
+                        }
+                    }
+                }
+                LoadCommand::Symtab => {
+                    write_pod(
+                        &mut commands,
+                        &macho::SymtabCommand {
+                            cmd: U32::new(endian, macho::LC_SYMTAB),
+                            cmdsize,
+                            symoff: blob_offset(BlobId::Symtab),
+                            nsyms: U32::new(endian, order.len() as u32),
+                            stroff: blob_offset(BlobId::Strtab),
+                            strsize: blob_size(&strtab),
+                        },
+                    );
+                }
+                LoadCommand::Dysymtab => {
+                    let nundefsym = order.len() - nlocalsym - nextdefsym;
+                    write_pod(
+                        &mut commands,
+                        &macho::DysymtabCommand {
+                            cmd: U32::new(endian, macho::LC_DYSYMTAB),
+                            cmdsize,
+                            ilocalsym: U32::new(endian, 0),
+                            nlocalsym: U32::new(endian, nlocalsym as u32),
+                            iextdefsym: U32::new(endian, nlocalsym as u32),
+                            nextdefsym: U32::new(endian, nextdefsym as u32),
+                            iundefsym: U32::new(endian, (nlocalsym + nextdefsym) as u32),
+                            nundefsym: U32::new(endian, nundefsym as u32),
+                            tocoff: U32::default(),
+                            ntoc: U32::default(),
+                            modtaboff: U32::default(),
+                            nmodtab: U32::default(),
+                            extrefsymoff: U32::default(),
+                            nextrefsyms: U32::default(),
+                            indirectsymoff: blob_offset(BlobId::IndirectSymbols),
+                            nindirectsyms: U32::new(endian, self.indirect_symbols.len() as u32),
+                            extreloff: blob_offset(BlobId::ExternalRelocations),
+                            nextrel: U32::new(endian, self.external_relocations.len() as u32),
+                            locreloff: blob_offset(BlobId::LocalRelocations),
+                            nlocrel: U32::new(endian, self.local_relocations.len() as u32),
+                        },
+                    );
+                }
+                LoadCommand::Dylib(dylib) => {
+                    write_pod(
+                        &mut commands,
+                        &macho::DylibCommand {
+                            cmd: U32::new(endian, dylib.cmd),
+                            cmdsize,
+                            dylib: macho::Dylib {
+                                name: macho::LcStr {
+                                    offset: U32::new(
+                                        endian,
+                                        mem::size_of::<macho::DylibCommand<Endianness>>() as u32,
+                                    ),
+                                },
+                                timestamp: U32::new(endian, dylib.timestamp),
+                                current_version: U32::new(endian, dylib.current_version),
+                                compatibility_version: U32::new(
+                                    endian,
+                                    dylib.compatibility_version,
+                                ),
+                            },
+                        },
+                    );
+                    write_string(&mut commands, &dylib.name, pointer_align);
+                }
+                LoadCommand::Rpath(path) => {
+                    write_pod(
+                        &mut commands,
+                        &macho::RpathCommand {
+                            cmd: U32::new(endian, macho::LC_RPATH),
+                            cmdsize,
+                            path: macho::LcStr {
+                                offset: U32::new(
+                                    endian,
+                                    mem::size_of::<macho::RpathCommand<Endianness>>() as u32,
+                                ),
+                            },
+                        },
+                    );
+                    write_string(&mut commands, path, pointer_align);
+                }
+                LoadCommand::LinkeditData { cmd, data } => {
+                    write_pod(
+                        &mut commands,
+                        &macho::LinkeditDataCommand {
+                            cmd: U32::new(endian, *cmd),
+                            cmdsize,
+                            dataoff: blob_offset(BlobId::Linkedit(index, 0)),
+                            datasize: blob_size(data),
+                        },
+                    );
+                }
+                LoadCommand::AdhocSignature(_) => {
+                    let size = signature_layout.map_or(0, |(_, _, size)| size);
+                    write_pod(
+                        &mut commands,
+                        &macho::LinkeditDataCommand {
+                            cmd: U32::new(endian, macho::LC_CODE_SIGNATURE),
+                            cmdsize,
+                            dataoff: blob_offset(BlobId::Linkedit(index, 0)),
+                            datasize: U32::new(endian, size as u32),
+                        },
+                    );
+                }
+                LoadCommand::DyldInfo(dyld_info) => {
+                    let offset = |i| blob_offset(BlobId::Linkedit(index, i));
+                    write_pod(
+                        &mut commands,
+                        &macho::DyldInfoCommand {
+                            cmd: U32::new(endian, dyld_info.cmd),
+                            cmdsize,
+                            rebase_off: offset(0),
+                            rebase_size: blob_size(&dyld_info.rebase),
+                            bind_off: offset(1),
+                            bind_size: blob_size(&dyld_info.bind),
+                            weak_bind_off: offset(2),
+                            weak_bind_size: blob_size(&dyld_info.weak_bind),
+                            lazy_bind_off: offset(3),
+                            lazy_bind_size: blob_size(&dyld_info.lazy_bind),
+                            export_off: offset(4),
+                            export_size: blob_size(&dyld_info.export),
+                        },
+                    );
+                }
+                LoadCommand::Other(data) => {
+                    commands.extend_from_slice(data);
+                }
+            }
+        }
+        debug_assert_eq!(commands.len(), commands_end);
+        data[..commands_end].copy_from_slice(&commands);
+
+        #[cfg(feature = "codesign")]
+        if let Some((signature, offset, size)) = signature_layout {
+            let (exec_seg_base, exec_seg_limit) = self
+                .load_commands
+                .iter()
+                .filter_map(|command| self.live_segment(command))
+                .find(|segment| &*segment.name == b"__TEXT")
+                .map_or((0, 0), |segment| (segment.fileoff, segment.filesize));
+            let exec_seg_flags = if self.header.filetype == macho::MH_EXECUTE {
+                macho::CS_EXECSEG_MAIN_BINARY
+            } else {
+                0
+            };
+            let (code, rest) = data.split_at_mut(offset);
+            signature.write(
+                code,
+                &mut rest[..size],
+                exec_seg_base,
+                exec_seg_limit,
+                exec_seg_flags,
+            );
+        }
+
+        buffer
+            .reserve(data.len())
+            .map_err(|_| Error::new("Cannot allocate buffer"))?;
+        buffer.write_bytes(&data);
+        Ok(())
+    }
+
+    /// Return the size of the Mach-O header.
+    fn header_size(&self) -> usize {
+        if self.is_64 {
+            mem::size_of::<macho::MachHeader64<Endianness>>()
+        } else {
+            mem::size_of::<macho::MachHeader32<Endianness>>()
+        }
+    }
+
+    /// Return the size of a load command, or `None` if it will not be written.
+    fn command_size(&self, command: &LoadCommand<'_>) -> Option<usize> {
+        let pointer_align = self.pointer_align();
+        Some(match command {
+            LoadCommand::Segment(_) => {
+                let segment = self.live_segment(command)?;
+                let nsects = self.live_sections(segment).count();
+                if self.is_64 {
+                    mem::size_of::<macho::SegmentCommand64<Endianness>>()
+                        + nsects * mem::size_of::<macho::Section64<Endianness>>()
+                } else {
+                    mem::size_of::<macho::SegmentCommand32<Endianness>>()
+                        + nsects * mem::size_of::<macho::Section32<Endianness>>()
+                }
+            }
+            LoadCommand::Symtab => mem::size_of::<macho::SymtabCommand<Endianness>>(),
+            LoadCommand::Dysymtab => mem::size_of::<macho::DysymtabCommand<Endianness>>(),
+            LoadCommand::Dylib(dylib) => write::align(
+                mem::size_of::<macho::DylibCommand<Endianness>>() + dylib.name.len() + 1,
+                pointer_align,
+            ),
+            LoadCommand::Rpath(path) => write::align(
+                mem::size_of::<macho::RpathCommand<Endianness>>() + path.len() + 1,
+                pointer_align,
+            ),
+            LoadCommand::LinkeditData { .. } => {
+                mem::size_of::<macho::LinkeditDataCommand<Endianness>>()
+            }
+            LoadCommand::AdhocSignature(_) => {
+                mem::size_of::<macho::LinkeditDataCommand<Endianness>>()
+            }
+            LoadCommand::DyldInfo(_) => mem::size_of::<macho::DyldInfoCommand<Endianness>>(),
+            LoadCommand::Other(data) => data.len(),
+        })
+    }
+}
+
+/// Maps IDs to the indices used in the output file.
+struct OutputMap<'a, 'data> {
+    builder: &'a Builder<'data>,
+    symbols: &'a [Option<u32>],
+    sections: &'a [Option<usize>],
+}
+
+impl<'a, 'data> OutputMap<'a, 'data> {
+    fn symbol(&self, id: SymbolId, user: &str) -> Result<u32> {
+        self.symbols[id.0].ok_or_else(|| {
+            Error(format!(
+                "Mach-O {} refers to deleted symbol `{}`",
+                user,
+                self.builder.symbols.get(id).name
+            ))
+        })
+    }
+
+    fn section(&self, id: SectionId) -> Result<usize> {
+        self.sections[id.0].ok_or_else(|| Error::new("Mach-O relocation refers to deleted section"))
+    }
+
+    fn relocations(&self, relocations: &[Relocation]) -> Result<Vec<u8>> {
+        let endian = self.builder.endian;
+        let mut data = Vec::with_capacity(relocations.len() * 8);
+        for relocation in relocations {
+            let relocation = if relocation.r_scattered {
+                let RelocationTarget::Value(r_value) = relocation.target else {
+                    return Err(Error::new(
+                        "Mach-O scattered relocation must have a value target",
+                    ));
+                };
+                macho::ScatteredRelocationInfo {
+                    r_address: relocation.r_address,
+                    r_type: relocation.r_type,
+                    r_length: relocation.r_length,
+                    r_pcrel: relocation.r_pcrel,
+                    r_value,
+                }
+                .relocation(endian)
+            } else {
+                let (r_extern, r_symbolnum) = match relocation.target {
+                    RelocationTarget::Symbol(id) => (true, self.symbol(id, "relocation")?),
+                    RelocationTarget::Section(id) => (false, self.section(id)? as u32),
+                    RelocationTarget::Value(value) => (false, value),
+                };
+                macho::RelocationInfo {
+                    r_address: relocation.r_address,
+                    r_symbolnum,
+                    r_pcrel: relocation.r_pcrel,
+                    r_length: relocation.r_length,
+                    r_extern,
+                    r_type: relocation.r_type,
+                }
+                .relocation(endian)
+            };
+            write_pod(&mut data, &relocation);
+        }
+        Ok(data)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BlobId {
+    Relocations(usize),
+    LocalRelocations,
+    ExternalRelocations,
+    Symtab,
+    IndirectSymbols,
+    Strtab,
+    Linkedit(usize, usize),
+}
+
+/// Data that is placed after the segment data.
+struct Blob<'a> {
+    id: BlobId,
+    /// Blobs are sorted by this value, and then by the order they were added.
+    rank: u8,
+    data: &'a [u8],
+}
+
+impl<'a> Blob<'a> {
+    fn new(id: BlobId, rank: u8, data: &'a [u8]) -> Self {
+        Blob { id, rank, data }
+    }
+}
+
+fn read_linkedit<'data, R: ReadRef<'data>>(
+    data: R,
+    endian: Endianness,
+    offset: U32<Endianness>,
+    size: U32<Endianness>,
+) -> Result<Bytes<'data>> {
+    let size = size.get(endian);
+    if size == 0 {
+        return Ok(Bytes::default());
+    }
+    data.read_bytes_at(offset.get(endian).into(), size.into())
+        .map(Bytes::from)
+        .map_err(|_| Error::new("Invalid Mach-O linkedit data offset or size"))
+}
+
+fn write_pod<T: Pod>(buffer: &mut Vec<u8>, value: &T) {
+    buffer.extend_from_slice(pod::bytes_of(value));
+}
+
+fn write_string(buffer: &mut Vec<u8>, string: &[u8], align: usize) {
+    buffer.extend_from_slice(string);
+    buffer.push(0);
+    buffer.resize(write::align(buffer.len(), align), 0);
+}
+
+fn name16(name: &[u8]) -> Result<[u8; 16]> {
+    let mut result = [0; 16];
+    result
+        .get_mut(..name.len())
+        .ok_or_else(|| {
+            Error(format!(
+                "Mach-O name `{}` is longer than 16 bytes",
+                ByteString::from(name)
+            ))
+        })?
+        .copy_from_slice(name);
+    Ok(result)
+}
+
+/// Mach-O file header.
+///
+/// This corresponds to fields in [`macho::MachHeader32`] or [`macho::MachHeader64`].
+/// This only contains the Mach-O file header fields that can be modified.
+/// The other fields are automatically calculated.
+#[derive(Debug, Default)]
+pub struct Header {
+    /// The CPU type in the file header.
+    ///
+    /// One of the `CPU_TYPE_*` constants.
+    pub cputype: u32,
+    /// The CPU subtype in the file header.
+    ///
+    /// One of the `CPU_SUBTYPE_*` constants.
+    pub cpusubtype: u32,
+    /// The file type in the file header.
+    ///
+    /// One of the `MH_*` file type constants.
+    pub filetype: u32,
+    /// The flags in the file header.
+    ///
+    /// A combination of the `MH_*` flag constants.
+    pub flags: u32,
+}
+
+/// A load command.
+#[derive(Debug)]
+pub enum LoadCommand<'data> {
+    /// `LC_SEGMENT` or `LC_SEGMENT_64`.
+    ///
+    /// The command is not written if the segment is deleted.
+    Segment(SegmentId),
+    /// `LC_SYMTAB`.
+    ///
+    /// The symbol table and string table are generated from [`Builder::symbols`].
+    Symtab,
+    /// `LC_DYSYMTAB`.
+    ///
+    /// The symbol ranges are calculated when writing, and the tables are generated
+    /// from [`Builder::indirect_symbols`], [`Builder::external_relocations`]
+    /// and [`Builder::local_relocations`].
+    Dysymtab,
+    /// `LC_ID_DYLIB`, `LC_LOAD_DYLIB`, `LC_LOAD_WEAK_DYLIB`, `LC_REEXPORT_DYLIB`,
+    /// `LC_LAZY_LOAD_DYLIB`, or `LC_LOAD_UPWARD_DYLIB`.
+    Dylib(Dylib<'data>),
+    /// `LC_RPATH`.
+    Rpath(ByteString<'data>),
+    /// A command that refers to a single blob of data in `__LINKEDIT`.
+    ///
+    /// This is used for `LC_CODE_SIGNATURE`, `LC_SEGMENT_SPLIT_INFO`,
+    /// `LC_FUNCTION_STARTS`, `LC_DATA_IN_CODE`, `LC_DYLIB_CODE_SIGN_DRS`,
+    /// `LC_LINKER_OPTIMIZATION_HINT`, `LC_DYLD_EXPORTS_TRIE` and
+    /// `LC_DYLD_CHAINED_FIXUPS`.
+    LinkeditData {
+        /// The command type.
+        cmd: u32,
+        /// The data that the command refers to.
+        data: Bytes<'data>,
+    },
+    /// `LC_DYLD_INFO` or `LC_DYLD_INFO_ONLY`.
+    DyldInfo(DyldInfo<'data>),
+    /// `LC_CODE_SIGNATURE` for an ad hoc signature that is generated when writing.
+    ///
+    /// See [`Builder::set_adhoc_signature`].
+    AdhocSignature(AdhocSignature<'data>),
+    /// Any other load command.
+    ///
+    /// The data includes the command header, and is written unchanged.
+    Other(Bytes<'data>),
+}
+
+/// A dylib load command.
+///
+/// This corresponds to [`macho::DylibCommand`].
+#[derive(Debug, Clone)]
+pub struct Dylib<'data> {
+    /// The command type.
+    ///
+    /// One of `LC_ID_DYLIB`, `LC_LOAD_DYLIB`, `LC_LOAD_WEAK_DYLIB`,
+    /// `LC_REEXPORT_DYLIB`, `LC_LAZY_LOAD_DYLIB`, or `LC_LOAD_UPWARD_DYLIB`.
+    pub cmd: u32,
+    /// The path name of the dylib.
+    pub name: ByteString<'data>,
+    /// The `timestamp` field in the dylib command.
+    pub timestamp: u32,
+    /// The `current_version` field in the dylib command.
+    pub current_version: u32,
+    /// The `compatibility_version` field in the dylib command.
+    pub compatibility_version: u32,
+}
+
+/// A `LC_DYLD_INFO` or `LC_DYLD_INFO_ONLY` load command.
+///
+/// This corresponds to [`macho::DyldInfoCommand`].
+#[derive(Debug, Clone)]
+pub struct DyldInfo<'data> {
+    /// The command type.
+    ///
+    /// One of `LC_DYLD_INFO` or `LC_DYLD_INFO_ONLY`.
+    pub cmd: u32,
+    /// The rebase opcodes.
+    pub rebase: Bytes<'data>,
+    /// The bind opcodes.
+    pub bind: Bytes<'data>,
+    /// The weak bind opcodes.
+    pub weak_bind: Bytes<'data>,
+    /// The lazy bind opcodes.
+    pub lazy_bind: Bytes<'data>,
+    /// The export trie.
+    pub export: Bytes<'data>,
+}
+
+impl<'data> DyldInfo<'data> {
+    fn blobs(&self) -> [&[u8]; 5] {
+        [
+            &self.rebase,
+            &self.bind,
+            &self.weak_bind,
+            &self.lazy_bind,
+            &self.export,
+        ]
+    }
+}
+
+/// An ad hoc code signature.
+///
+/// This is written as an embedded signature containing a code directory and an empty
+/// requirements set. The code directory contains the SHA-256 hash of each 4096 byte page
+/// of the file up to the signature. There is no CMS signature.
+///
+/// Writing the signature requires the `codesign` feature.
+#[derive(Debug, Clone)]
+pub struct AdhocSignature<'data> {
+    /// The identifier in the code directory.
+    ///
+    /// This is usually the file name, or the bundle identifier.
+    pub identifier: ByteString<'data>,
+    /// The code directory flags.
+    ///
+    /// A combination of the `CS_*` flag constants, such as `CS_RUNTIME`.
+    /// `CS_ADHOC` is always set.
+    pub flags: u32,
+}
+
+/// Compute the SHA-256 hash of `data`.
+#[cfg(feature = "codesign")]
+fn sha256(data: &[u8]) -> [u8; macho::CS_SHA256_LEN] {
+    use sha2::Digest;
+
+    sha2::Sha256::digest(data).into()
+}
+
+/// The log2 of the page size used for ad hoc signatures.
+const ADHOC_PAGE_SHIFT: u8 = 12;
+/// The size of a version `CS_SUPPORTSEXECSEG` code directory header.
+const ADHOC_CODE_DIRECTORY_SIZE: usize = 88;
+/// The number of special slots, which includes the requirements slot.
+const ADHOC_SPECIAL_SLOTS: usize = macho::CSSLOT_REQUIREMENTS as usize;
+
+impl<'data> AdhocSignature<'data> {
+    fn code_directory_size(&self, code_limit: usize) -> usize {
+        let n_code_slots = (code_limit + (1 << ADHOC_PAGE_SHIFT) - 1) >> ADHOC_PAGE_SHIFT;
+        ADHOC_CODE_DIRECTORY_SIZE
+            + self.identifier.len()
+            + 1
+            + (ADHOC_SPECIAL_SLOTS + n_code_slots) * macho::CS_SHA256_LEN
+    }
+
+    /// Return the size of the signature for the given code limit.
+    fn size(&self, code_limit: usize) -> usize {
+        mem::size_of::<macho::CsSuperBlob>()
+            + 2 * mem::size_of::<macho::CsBlobIndex>()
+            + self.code_directory_size(code_limit)
+            + mem::size_of::<macho::CsSuperBlob>()
+    }
+
+    /// Write the signature for `code` to `buffer`.
+    ///
+    /// The size of `buffer` must be the value returned by `size`.
+    #[cfg(feature = "codesign")]
+    fn write(
+        &self,
+        code: &[u8],
+        buffer: &mut [u8],
+        exec_seg_base: u64,
+        exec_seg_limit: u64,
+        exec_seg_flags: u64,
+    ) {
+        let mut requirements = Vec::new();
+        write_pod(
+            &mut requirements,
+            &macho::CsSuperBlob {
+                magic: U32::new(BigEndian, macho::CSMAGIC_REQUIREMENTS),
+                length: U32::new(BigEndian, mem::size_of::<macho::CsSuperBlob>() as u32),
+                count: U32::new(BigEndian, 0),
+            },
+        );
+
+        let code_limit = code.len();
+        let n_code_slots = code.chunks(1 << ADHOC_PAGE_SHIFT).len();
+        let ident_offset = ADHOC_CODE_DIRECTORY_SIZE;
+        let hash_offset =
+            ident_offset + self.identifier.len() + 1 + ADHOC_SPECIAL_SLOTS * macho::CS_SHA256_LEN;
+        let code_directory_size = self.code_directory_size(code_limit);
+        let mut code_directory = Vec::with_capacity(code_directory_size);
+        write_pod(
+            &mut code_directory,
+            &macho::CsCodeDirectory {
+                magic: U32::new(BigEndian, macho::CSMAGIC_CODEDIRECTORY),
+                length: U32::new(BigEndian, code_directory_size as u32),
+                version: U32::new(BigEndian, macho::CS_SUPPORTSEXECSEG),
+                flags: U32::new(BigEndian, self.flags | macho::CS_ADHOC),
+                hash_offset: U32::new(BigEndian, hash_offset as u32),
+                ident_offset: U32::new(BigEndian, ident_offset as u32),
+                n_special_slots: U32::new(BigEndian, ADHOC_SPECIAL_SLOTS as u32),
+                n_code_slots: U32::new(BigEndian, n_code_slots as u32),
+                code_limit: U32::new(BigEndian, code_limit as u32),
+                hash_size: macho::CS_SHA256_LEN as u8,
+                hash_type: macho::CS_HASHTYPE_SHA256,
+                platform: 0,
+                page_size: ADHOC_PAGE_SHIFT,
+                spare2: U32::default(),
+                scatter_offset: U32::default(),
+                team_offset: U32::default(),
+                spare3: U32::default(),
+                code_limit64: U64::default(),
+                exec_seg_base: U64::new(BigEndian, exec_seg_base),
+                exec_seg_limit: U64::new(BigEndian, exec_seg_limit),
+                exec_seg_flags: U64::new(BigEndian, exec_seg_flags),
+                runtime: U32::default(),
+                pre_encrypt_offset: U32::default(),
+                linkage_hash_type: 0,
+                linkage_application_type: 0,
+                linkage_application_sub_type: U16::default(),
+                linkage_offset: U32::default(),
+                linkage_size: U32::default(),
+            },
+        );
+        // Fields after `exec_seg_flags` are not present in this version.
+        code_directory.truncate(ADHOC_CODE_DIRECTORY_SIZE);
+        code_directory.extend_from_slice(&self.identifier);
+        code_directory.push(0);
+        // Special slots are stored in reverse order before the code slots.
+        for slot in (1..=ADHOC_SPECIAL_SLOTS as u32).rev() {
+            if slot == macho::CSSLOT_REQUIREMENTS {
+                code_directory.extend_from_slice(&sha256(&requirements));
+            } else {
+                code_directory.extend_from_slice(&[0; macho::CS_SHA256_LEN]);
+            }
+        }
+        for page in code.chunks(1 << ADHOC_PAGE_SHIFT) {
+            code_directory.extend_from_slice(&sha256(page));
+        }
+        debug_assert_eq!(code_directory.len(), code_directory_size);
+
+        let mut signature = Vec::with_capacity(buffer.len());
+        let mut offset =
+            mem::size_of::<macho::CsSuperBlob>() + 2 * mem::size_of::<macho::CsBlobIndex>();
+        write_pod(
+            &mut signature,
+            &macho::CsSuperBlob {
+                magic: U32::new(BigEndian, macho::CSMAGIC_EMBEDDED_SIGNATURE),
+                length: U32::new(BigEndian, buffer.len() as u32),
+                count: U32::new(BigEndian, 2),
+            },
+        );
+        for (typ, blob) in [
+            (macho::CSSLOT_CODEDIRECTORY, &code_directory),
+            (macho::CSSLOT_REQUIREMENTS, &requirements),
+        ] {
+            write_pod(
+                &mut signature,
+                &macho::CsBlobIndex {
+                    typ: U32::new(BigEndian, typ),
+                    offset: U32::new(BigEndian, offset as u32),
+                },
+            );
+            offset += blob.len();
+        }
+        signature.extend_from_slice(&code_directory);
+        signature.extend_from_slice(&requirements);
+        buffer.copy_from_slice(&signature);
+    }
+}
+
+/// An exported symbol in an exports trie.
+///
+/// See [`write_exports_trie`].
+#[derive(Debug, Clone)]
+pub struct Export<'data> {
+    /// The name of the exported symbol.
+    pub name: ByteString<'data>,
+    /// The export flags.
+    ///
+    /// A combination of an `EXPORT_SYMBOL_FLAGS_KIND_*` constant and
+    /// `EXPORT_SYMBOL_FLAGS_WEAK_DEFINITION`. The `EXPORT_SYMBOL_FLAGS_REEXPORT`
+    /// and `EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER` flags are set from `data`.
+    pub flags: u8,
+    /// The terminal data for the exported symbol.
+    pub data: ExportData<'data>,
+}
+
+/// The terminal data for an [`Export`].
+#[derive(Debug, Clone)]
+pub enum ExportData<'data> {
+    /// A regular export.
+    Regular {
+        /// The address of the export, relative to the file header.
+        address: u64,
+    },
+    /// A re-exported symbol.
+    Reexport {
+        /// The ordinal of the dylib to re-export from.
+        dylib_ordinal: u64,
+        /// The name of the symbol to re-export.
+        ///
+        /// This is empty if the name is the same as the exported name.
+        import_name: ByteString<'data>,
+    },
+    /// A stub-and-resolver symbol.
+    StubAndResolver {
+        /// The address of the stub.
+        stub_address: u64,
+        /// The address of the resolver.
+        resolver_address: u64,
+    },
+}
+
+#[derive(Default)]
+struct TrieNode<'a> {
+    terminal: Vec<u8>,
+    children: Vec<(&'a [u8], usize)>,
+}
+
+/// Encode an exports trie.
+///
+/// The result can be used for [`DyldInfo::export`] or for a
+/// [`LoadCommand::LinkeditData`] with a `cmd` of `LC_DYLD_EXPORTS_TRIE`.
+pub fn write_exports_trie(exports: &[Export<'_>]) -> Result<Vec<u8>> {
+    let mut sorted: Vec<&Export<'_>> = exports.iter().collect();
+    sorted.sort_by(|a, b| a.name[..].cmp(&b.name[..]));
+
+    // Build the trie. Edges are split as needed so that the edges of a node
+    // never share a prefix.
+    let mut nodes = vec![TrieNode::default()];
+    for export in sorted {
+        let name = &export.name[..];
+        if name.contains(&0) {
+            return Err(Error(format!(
+                "Mach-O export name `{}` contains a null byte",
+                export.name
+            )));
+        }
+        let mut node = 0;
+        let mut rest = name;
+        'outer: while !rest.is_empty() {
+            for i in 0..nodes[node].children.len() {
+                let (edge, child) = nodes[node].children[i];
+                let common = edge.iter().zip(rest).take_while(|(a, b)| a == b).count();
+                if common == 0 {
+                    continue;
+                }
+                if common < edge.len() {
+                    let split = nodes.len();
+                    nodes.push(TrieNode {
+                        terminal: Vec::new(),
+                        children: vec![(&edge[common..], child)],
+                    });
+                    nodes[node].children[i] = (&edge[..common], split);
+                    node = split;
+                } else {
+                    node = child;
+                }
+                rest = &rest[common..];
+                continue 'outer;
+            }
+            let child = nodes.len();
+            nodes.push(TrieNode::default());
+            nodes[node].children.push((rest, child));
+            node = child;
+            rest = &[];
+        }
+        if !nodes[node].terminal.is_empty() {
+            return Err(Error(format!(
+                "Duplicate Mach-O export name `{}`",
+                export.name
+            )));
+        }
+        let terminal = &mut nodes[node].terminal;
+        match &export.data {
+            ExportData::Regular { address } => {
+                write::write_uleb128(terminal, export.flags.into());
+                write::write_uleb128(terminal, *address);
+            }
+            ExportData::Reexport {
+                dylib_ordinal,
+                import_name,
+            } => {
+                let flags = export.flags | macho::EXPORT_SYMBOL_FLAGS_REEXPORT;
+                write::write_uleb128(terminal, flags.into());
+                write::write_uleb128(terminal, *dylib_ordinal);
+                terminal.extend_from_slice(import_name);
+                terminal.push(0);
+            }
+            ExportData::StubAndResolver {
+                stub_address,
+                resolver_address,
+            } => {
+                let flags = export.flags | macho::EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER;
+                write::write_uleb128(terminal, flags.into());
+                write::write_uleb128(terminal, *stub_address);
+                write::write_uleb128(terminal, *resolver_address);
+            }
+        }
+    }
+
+    // Nodes are written in pre-order.
+    let mut order = Vec::with_capacity(nodes.len());
+    let mut stack = vec![0];
+    while let Some(node) = stack.pop() {
+        order.push(node);
+        stack.extend(nodes[node].children.iter().rev().map(|(_, child)| *child));
+    }
+
+    // The size of a node depends on the offsets of its children, so iterate
+    // until the offsets no longer change. Offsets only ever increase, so this
+    // terminates.
+    let mut offsets = vec![0u64; nodes.len()];
+    loop {
+        let mut offset = 0;
+        let mut changed = false;
+        for &index in &order {
+            if offsets[index] != offset {
+                offsets[index] = offset;
+                changed = true;
+            }
+            let node = &nodes[index];
+            let terminal_size = node.terminal.len() as u64;
+            offset += uleb128_size(terminal_size) + terminal_size + 1;
+            for (edge, child) in &node.children {
+                offset += edge.len() as u64 + 1 + uleb128_size(offsets[*child]);
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut data = Vec::new();
+    for &index in &order {
+        debug_assert_eq!(data.len() as u64, offsets[index]);
+        let node = &nodes[index];
+        write::write_uleb128(&mut data, node.terminal.len() as u64);
+        data.extend_from_slice(&node.terminal);
+        // Edges can't start with a null byte, so there are at most 255 children.
+        data.push(node.children.len() as u8);
+        for (edge, child) in &node.children {
+            data.extend_from_slice(edge);
+            data.push(0);
+            write::write_uleb128(&mut data, offsets[*child]);
+        }
+    }
+    Ok(data)
+}
+
+fn uleb128_size(mut value: u64) -> u64 {
+    let mut size = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        size += 1;
+    }
+    size
+}
+
+/// An ID for referring to a segment in [`Segments`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SegmentId(usize);
+
+impl fmt::Debug for SegmentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SegmentId({})", self.0)
+    }
+}
+
+impl Id for SegmentId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for SegmentId {
+    fn new(id: usize) -> Self {
+        SegmentId(id)
+    }
+}
+
+/// A segment in [`Segments`].
+///
+/// This corresponds to [`macho::SegmentCommand32`] or [`macho::SegmentCommand64`].
+#[derive(Debug)]
+pub struct Segment<'data> {
+    id: SegmentId,
+    /// Ignore this segment and its sections when writing the Mach-O file.
+    pub delete: bool,
+    /// The name of the segment.
+    pub name: ByteString<'data>,
+    /// The `vmaddr` field in the segment command.
+    pub vmaddr: u64,
+    /// The `vmsize` field in the segment command.
+    ///
+    /// This is calculated when writing the `__LINKEDIT` segment.
+    pub vmsize: u64,
+    /// The `fileoff` field in the segment command.
+    ///
+    /// This is calculated when writing object files.
+    pub fileoff: u64,
+    /// The `filesize` field in the segment command.
+    ///
+    /// This is calculated when writing object files or the `__LINKEDIT` segment.
+    pub filesize: u64,
+    /// The `maxprot` field in the segment command.
+    pub maxprot: u32,
+    /// The `initprot` field in the segment command.
+    pub initprot: u32,
+    /// The `flags` field in the segment command.
+    pub flags: u32,
+    /// The sections contained in this segment.
+    pub sections: Vec<SectionId>,
+    /// The file data for the segment.
+    ///
+    /// This is written at `fileoff`, and section data is then written over it.
+    /// This allows the segment to contain data that is not in any section,
+    /// such as the file header.
+    ///
+    /// This is not used for object files, or for the `__LINKEDIT` segment.
+    pub data: Bytes<'data>,
+}
+
+impl<'data> Item for Segment<'data> {
+    type Id = SegmentId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Segment<'data> {
+    /// The ID used for referring to this segment.
+    pub fn id(&self) -> SegmentId {
+        self.id
+    }
+}
+
+/// A segment table.
+pub type Segments<'data> = Table<Segment<'data>>;
+
+impl<'data> Segments<'data> {
+    /// Add a new segment to the table.
+    ///
+    /// A [`LoadCommand::Segment`] must also be added for the segment to be written.
+    pub fn add(&mut self) -> &mut Segment<'data> {
+        let id = self.next_id();
+        self.push(Segment {
+            id,
+            delete: false,
+            name: ByteString::default(),
+            vmaddr: 0,
+            vmsize: 0,
+            fileoff: 0,
+            filesize: 0,
+            maxprot: 0,
+            initprot: 0,
+            flags: 0,
+            sections: Vec::new(),
+            data: Bytes::default(),
+        })
+    }
+}
+
+/// An ID for referring to a section in [`Sections`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SectionId(usize);
+
+impl fmt::Debug for SectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SectionId({})", self.0)
+    }
+}
+
+impl Id for SectionId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for SectionId {
+    fn new(id: usize) -> Self {
+        SectionId(id)
+    }
+}
+
+/// A section in [`Sections`].
+///
+/// This corresponds to [`macho::Section32`] or [`macho::Section64`].
+#[derive(Debug)]
+pub struct Section<'data> {
+    id: SectionId,
+    /// Ignore this section when writing the Mach-O file.
+    pub delete: bool,
+    /// The name of the section.
+    pub name: ByteString<'data>,
+    /// The name of the segment in the section header.
+    ///
+    /// This is normally the same as the name of the segment containing the
+    /// section, but this is not required for object files.
+    pub segment_name: ByteString<'data>,
+    /// The `addr` field in the section header.
+    pub addr: u64,
+    /// The `size` field in the section header.
+    ///
+    /// For sections that are not zero fill, this should be the size of `data`.
+    pub size: u64,
+    /// The `offset` field in the section header.
+    ///
+    /// This is the file offset of the data in the section.
+    /// This is calculated when writing object files.
+    pub offset: u32,
+    /// The `align` field in the section header.
+    ///
+    /// This is a power of two.
+    pub align: u32,
+    /// The `flags` field in the section header.
+    ///
+    /// A combination of a `S_*` section type and `S_ATTR_*` attributes.
+    pub flags: u32,
+    /// The `reserved1` field in the section header.
+    pub reserved1: u32,
+    /// The `reserved2` field in the section header.
+    pub reserved2: u32,
+    /// The `reserved3` field in the section header.
+    ///
+    /// Only used for 64-bit files.
+    pub reserved3: u32,
+    /// The section data.
+    ///
+    /// This is not used for zero fill sections.
+    pub data: Bytes<'data>,
+    /// The relocations for the section.
+    pub relocations: Vec<Relocation>,
+}
+
+impl<'data> Item for Section<'data> {
+    type Id = SectionId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Section<'data> {
+    /// The ID used for referring to this section.
+    pub fn id(&self) -> SectionId {
+        self.id
+    }
+
+    /// Returns true if the section type is a zero fill type.
+    pub fn is_zerofill(&self) -> bool {
+        matches!(
+            self.flags & macho::SECTION_TYPE,
+            macho::S_ZEROFILL | macho::S_GB_ZEROFILL | macho::S_THREAD_LOCAL_ZEROFILL
+        )
+    }
+}
+
+/// A section table.
+pub type Sections<'data> = Table<Section<'data>>;
+
+impl<'data> Sections<'data> {
+    /// Add a new section to the table.
+    ///
+    /// The section must also be added to a [`Segment`] for it to be written.
+    pub fn add(&mut self) -> &mut Section<'data> {
+        let id = self.next_id();
+        self.push(Section {
+            id,
+            delete: false,
+            name: ByteString::default(),
+            segment_name: ByteString::default(),
+            addr: 0,
+            size: 0,
+            offset: 0,
+            align: 0,
+            flags: 0,
+            reserved1: 0,
+            reserved2: 0,
+            reserved3: 0,
+            data: Bytes::default(),
+            relocations: Vec::new(),
+        })
+    }
+}
+
+/// An ID for referring to a symbol in [`Symbols`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SymbolId(usize);
+
+impl fmt::Debug for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Id for SymbolId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for SymbolId {
+    fn new(id: usize) -> Self {
+        SymbolId(id)
+    }
+}
+
+/// A symbol in [`Symbols`].
+///
+/// This corresponds to [`macho::Nlist32`] or [`macho::Nlist64`].
+#[derive(Debug)]
+pub struct Symbol<'data> {
+    id: SymbolId,
+    /// Ignore this symbol when writing the Mach-O file.
+    pub delete: bool,
+    /// The name of the symbol.
+    pub name: ByteString<'data>,
+    /// The `n_type` field in the Mach-O symbol.
+    ///
+    /// A combination of the `N_*` constants.
+    pub n_type: u8,
+    /// The section referenced by the symbol.
+    ///
+    /// Used to set the `n_sect` field in the Mach-O symbol.
+    pub section: Option<SectionId>,
+    /// The `n_sect` field in the Mach-O symbol.
+    ///
+    /// Only used if `Self::section` is `None`.
+    pub n_sect: u8,
+    /// The `n_desc` field in the Mach-O symbol.
+    pub n_desc: u16,
+    /// The `n_value` field in the Mach-O symbol.
+    pub n_value: u64,
+}
+
+impl<'data> Item for Symbol<'data> {
+    type Id = SymbolId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Symbol<'data> {
+    /// The ID used for referring to this symbol.
+    pub fn id(&self) -> SymbolId {
+        self.id
+    }
+
+    /// Returns true if the symbol is a debugging symbol.
+    pub fn is_stab(&self) -> bool {
+        self.n_type & macho::N_STAB != 0
+    }
+
+    /// Returns true if the symbol is a debugging symbol or is not external.
+    pub fn is_local(&self) -> bool {
+        self.is_stab() || self.n_type & macho::N_EXT == 0
+    }
+
+    /// Returns true if the symbol is undefined.
+    pub fn is_undefined(&self) -> bool {
+        let n_type = self.n_type & macho::N_TYPE;
+        !self.is_stab() && (n_type == macho::N_UNDF || n_type == macho::N_PBUD)
+    }
+}
+
+/// A symbol table.
+pub type Symbols<'data> = Table<Symbol<'data>>;
+
+impl<'data> Symbols<'data> {
+    /// Add a new symbol to the table.
+    pub fn add(&mut self) -> &mut Symbol<'data> {
+        let id = self.next_id();
+        self.push(Symbol {
+            id,
+            delete: false,
+            name: ByteString::default(),
+            n_type: 0,
+            section: None,
+            n_sect: 0,
+            n_desc: 0,
+            n_value: 0,
+        })
+    }
+}
+
+/// A relocation stored in a [`Section`] or in the dynamic symbol table.
+///
+/// This corresponds to [`macho::Relocation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Relocation {
+    /// The `r_address` field in the Mach-O relocation.
+    pub r_address: u32,
+    /// The target of the relocation.
+    ///
+    /// Used to set the `r_symbolnum` and `r_extern` fields, or the `r_value`
+    /// field for scattered relocations.
+    pub target: RelocationTarget,
+    /// The `r_pcrel` field in the Mach-O relocation.
+    pub r_pcrel: bool,
+    /// The `r_length` field in the Mach-O relocation.
+    pub r_length: u8,
+    /// The `r_type` field in the Mach-O relocation.
+    pub r_type: u8,
+    /// Whether the relocation is a scattered relocation.
+    ///
+    /// The target must be [`RelocationTarget::Value`] for scattered relocations.
+    pub r_scattered: bool,
+}
+
+/// The target of a [`Relocation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationTarget {
+    /// An external relocation against a symbol.
+    Symbol(SymbolId),
+    /// A local relocation against a section.
+    Section(SectionId),
+    /// A raw `r_symbolnum` value for a local relocation, or the `r_value`
+    /// field for a scattered relocation.
+    ///
+    /// This is used for `R_ABS` and for relocation types that are paired
+    /// with a preceding relocation.
+    Value(u32),
+}
+
+/// An entry in the indirect symbol table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndirectSymbol {
+    /// A reference to a symbol.
+    Symbol(SymbolId),
+    /// A raw value.
+    ///
+    /// This is a combination of `INDIRECT_SYMBOL_LOCAL` and `INDIRECT_SYMBOL_ABS`.
+    Value(u32),
+}
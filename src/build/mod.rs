@@ -14,5 +14,14 @@ mod table;
 use table::IdPrivate;
 pub use table::{Id, Item, Table};
 
+#[cfg(feature = "coff")]
+pub mod coff;
+
 #[cfg(feature = "elf")]
 pub mod elf;
+
+#[cfg(feature = "macho")]
+pub mod macho;
+
+#[cfg(feature = "pe")]
+pub mod pe;
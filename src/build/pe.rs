@@ -0,0 +1,922 @@
+//! This module provides a [`Builder`] for reading, modifying, and then writing PE images.
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+
+use crate::build::{ByteString, Bytes, Error, Id, IdPrivate, Item, Result, Table};
+use crate::endian::{LittleEndian as LE, U32};
+use crate::pe;
+use crate::pod;
+use crate::read::coff::CoffHeader;
+use crate::read::pe::{ImageNtHeaders, ImageOptionalHeader};
+use crate::read::{FileKind, ReadRef};
+use crate::write;
+
+/// A builder for reading, modifying, and then writing PE images.
+///
+/// Public fields are available for modifying the values that will be written.
+/// Methods are available to add elements to tables, and elements can be deleted
+/// from tables by setting the `delete` field in the element.
+///
+/// Only the headers, the sections, the base relocations, the import DLL names
+/// and the certificate table are modelled. Section data is written unchanged,
+/// and sections keep their virtual addresses and, where possible, their file
+/// offsets. Any data between the last section and the certificate table is
+/// copied unchanged. This includes the COFF symbol table, if present.
+///
+/// Other data, such as the imported functions, the export table, resources
+/// and exception data, is not modelled. It is written as part of the data of
+/// the sections containing it, so those sections must not be moved.
+#[derive(Debug)]
+pub struct Builder<'data> {
+    /// Whether the image is a PE32+ image.
+    pub is_64: bool,
+    /// The section alignment in the optional header.
+    pub section_alignment: u32,
+    /// The file alignment in the optional header.
+    pub file_alignment: u32,
+    /// The DOS header.
+    ///
+    /// The `e_lfanew` field is set when writing.
+    pub dos_header: pe::ImageDosHeader,
+    /// The data between the DOS header and the NT headers.
+    ///
+    /// This includes the DOS stub and the rich header.
+    pub dos_stub: Bytes<'data>,
+    /// The fields of the file header and the optional header.
+    pub nt_headers: write::pe::NtHeaders,
+    /// The data directories.
+    ///
+    /// The entries for the base relocations and the certificate table are set
+    /// when writing, based on [`Self::base_relocations`] and
+    /// [`Self::certificate_table`]. Entries that refer to deleted sections are
+    /// cleared when writing.
+    pub data_directories: Vec<DataDirectory>,
+    /// The section table.
+    ///
+    /// Sections are written in this order, and their virtual addresses must be
+    /// increasing.
+    pub sections: Sections<'data>,
+    /// The base relocations.
+    ///
+    /// When writing, these replace the contents of the base relocation
+    /// directory. If the image has no base relocation directory, then
+    /// a `.reloc` section is added for them.
+    ///
+    /// Relocations are discarded if the section containing the base relocation
+    /// directory is deleted.
+    pub base_relocations: Vec<BaseRelocation>,
+    /// The DLLs in the import table.
+    ///
+    /// Only the names of the DLLs can be modified. DLLs can't be added or
+    /// deleted.
+    pub imports: Vec<ImportDll<'data>>,
+    /// The certificate table.
+    ///
+    /// This is written after all other data.
+    pub certificate_table: Option<Bytes<'data>>,
+    /// The data following the last section in the input file.
+    trailing_data: Bytes<'data>,
+    /// The file offset of `trailing_data` in the input file.
+    trailing_offset: u32,
+    /// The offset within `trailing_data` and the number of entries of the
+    /// COFF symbol table.
+    symbol_table: Option<(u32, u32)>,
+    /// The size of the COFF symbol table and string table.
+    symbol_table_len: u32,
+    marker: PhantomData<()>,
+}
+
+impl<'data> Builder<'data> {
+    /// Read the PE image from file data.
+    pub fn read<R: ReadRef<'data>>(data: R) -> Result<Self> {
+        match FileKind::parse(data)? {
+            FileKind::Pe32 => Self::read32(data),
+            FileKind::Pe64 => Self::read64(data),
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::new("Not a PE file")),
+        }
+    }
+
+    /// Read a PE32 image from file data.
+    pub fn read32<R: ReadRef<'data>>(data: R) -> Result<Self> {
+        Self::read_file::<pe::ImageNtHeaders32, R>(data)
+    }
+
+    /// Read a PE32+ image from file data.
+    pub fn read64<R: ReadRef<'data>>(data: R) -> Result<Self> {
+        Self::read_file::<pe::ImageNtHeaders64, R>(data)
+    }
+
+    fn read_file<Pe, R>(data: R) -> Result<Self>
+    where
+        Pe: ImageNtHeaders,
+        R: ReadRef<'data>,
+    {
+        let dos_header = pe::ImageDosHeader::parse(data)?;
+        let mut offset = u64::from(dos_header.nt_headers_offset());
+        let stub_offset = mem::size_of::<pe::ImageDosHeader>() as u64;
+        let dos_stub = offset
+            .checked_sub(stub_offset)
+            .and_then(|size| data.read_bytes_at(stub_offset, size).ok())
+            .ok_or_else(|| Error::new("Invalid PE NT headers offset"))?;
+        let (nt_headers, data_directories) = Pe::parse(data, &mut offset)?;
+        let file_header = nt_headers.file_header();
+        let optional_header = nt_headers.optional_header();
+        let section_table = file_header.sections(data, offset)?;
+        let strings = file_header.symbols(data)?.strings();
+
+        let mut sections = Sections::new();
+        let mut sections_end = 0;
+        for header in section_table.iter() {
+            let name = header.name(strings)?;
+            let section_data = header.pe_data(data)?;
+            let file_offset = header.pointer_to_raw_data.get(LE);
+            let file_size = header.size_of_raw_data.get(LE);
+            if file_size != 0 {
+                sections_end = sections_end.max(file_offset.saturating_add(file_size));
+            }
+            let section = sections.add();
+            section.name = name.into();
+            section.original_name = name;
+            section.raw_name = header.name;
+            section.characteristics = header.characteristics.get(LE);
+            section.virtual_address = header.virtual_address.get(LE);
+            section.virtual_size = header.virtual_size.get(LE);
+            section.pointer_to_raw_data = file_offset;
+            section.data = section_data.into();
+        }
+
+        let data_len = data
+            .len()
+            .map_err(|_| Error::new("Unknown PE file length"))?;
+        let mut certificate_table = None;
+        let mut trailing_end = data_len;
+        if let Some(dir) = data_directories.get(pe::IMAGE_DIRECTORY_ENTRY_SECURITY) {
+            // The address of the certificate table is a file offset.
+            let (offset, size) = dir.address_range();
+            certificate_table = Some(
+                data.read_bytes_at(offset.into(), size.into())
+                    .map_err(|_| Error::new("Invalid PE certificate table"))?
+                    .into(),
+            );
+            if offset >= sections_end {
+                trailing_end = offset.into();
+            }
+        }
+        let trailing_offset = u64::from(sections_end).min(trailing_end);
+        let trailing_data = data
+            .read_bytes_at(trailing_offset, trailing_end - trailing_offset)
+            .map_err(|_| Error::new("Invalid PE section file range"))?;
+        let trailing_offset = trailing_offset as u32;
+
+        let mut symbol_table = None;
+        let mut symbol_table_len = 0;
+        let symbol_offset = file_header.pointer_to_symbol_table.get(LE);
+        if symbol_offset != 0 {
+            let symbols = file_header.symbols(data)?;
+            let symbols_len = symbols.len() as u32 * pe::IMAGE_SIZEOF_SYMBOL as u32;
+            // The string table length includes the length field.
+            let strings_len = data
+                .read_at::<U32<LE>>(u64::from(symbol_offset) + u64::from(symbols_len))
+                .map_err(|_| Error::new("Invalid PE string table length"))?
+                .get(LE);
+            symbol_table_len = symbols_len
+                .checked_add(strings_len)
+                .ok_or_else(|| Error::new("Invalid PE string table length"))?;
+            if symbol_offset
+                .checked_sub(trailing_offset)
+                .and_then(|offset| offset.checked_add(symbol_table_len))
+                .map_or(true, |end| end > trailing_data.len() as u32)
+            {
+                return Err(Error::new(
+                    "Unsupported PE symbol table that is not at the end of the file",
+                ));
+            }
+            symbol_table = Some((symbol_offset - trailing_offset, symbols.len() as u32));
+        }
+
+        let mut base_relocations = Vec::new();
+        if let Some(mut blocks) = data_directories.relocation_blocks(data, &section_table)? {
+            while let Some(block) = blocks.next()? {
+                for relocation in block {
+                    base_relocations.push(BaseRelocation {
+                        virtual_address: relocation.virtual_address,
+                        typ: relocation.typ,
+                    });
+                }
+            }
+        }
+
+        let mut imports = Vec::new();
+        if let Some(import_table) = data_directories.import_table(data, &section_table)? {
+            let mut descriptor = data_directories
+                .get(pe::IMAGE_DIRECTORY_ENTRY_IMPORT)
+                .map(|dir| dir.virtual_address.get(LE))
+                .unwrap_or_default();
+            let mut descriptors = import_table.descriptors()?;
+            while let Some(import_descriptor) = descriptors.next()? {
+                let name_address = import_descriptor.name.get(LE);
+                let name = import_table.name(name_address)?;
+                imports.push(ImportDll {
+                    descriptor,
+                    name_address,
+                    original_name: name,
+                    name: name.into(),
+                });
+                descriptor += mem::size_of::<pe::ImageImportDescriptor>() as u32;
+            }
+        }
+
+        Ok(Builder {
+            is_64: nt_headers.is_type_64(),
+            section_alignment: optional_header.section_alignment(),
+            file_alignment: optional_header.file_alignment(),
+            dos_header: *dos_header,
+            dos_stub: dos_stub.into(),
+            nt_headers: write::pe::NtHeaders {
+                machine: file_header.machine.get(LE),
+                time_date_stamp: file_header.time_date_stamp.get(LE),
+                characteristics: file_header.characteristics.get(LE),
+                major_linker_version: optional_header.major_linker_version(),
+                minor_linker_version: optional_header.minor_linker_version(),
+                address_of_entry_point: optional_header.address_of_entry_point(),
+                image_base: optional_header.image_base(),
+                major_operating_system_version: optional_header.major_operating_system_version(),
+                minor_operating_system_version: optional_header.minor_operating_system_version(),
+                major_image_version: optional_header.major_image_version(),
+                minor_image_version: optional_header.minor_image_version(),
+                major_subsystem_version: optional_header.major_subsystem_version(),
+                minor_subsystem_version: optional_header.minor_subsystem_version(),
+                subsystem: optional_header.subsystem(),
+                dll_characteristics: optional_header.dll_characteristics(),
+                size_of_stack_reserve: optional_header.size_of_stack_reserve(),
+                size_of_stack_commit: optional_header.size_of_stack_commit(),
+                size_of_heap_reserve: optional_header.size_of_heap_reserve(),
+                size_of_heap_commit: optional_header.size_of_heap_commit(),
+            },
+            data_directories: data_directories
+                .iter()
+                .map(|dir| DataDirectory {
+                    virtual_address: dir.virtual_address.get(LE),
+                    size: dir.size.get(LE),
+                })
+                .collect(),
+            sections,
+            base_relocations,
+            imports,
+            certificate_table,
+            trailing_data: trailing_data.into(),
+            trailing_offset,
+            symbol_table,
+            symbol_table_len,
+            marker: PhantomData,
+        })
+    }
+
+    /// Return the address following the last section.
+    ///
+    /// This is aligned to the section alignment, and is suitable for use as the
+    /// virtual address of a new section.
+    pub fn next_section_address(&self) -> Result<u32> {
+        let mut end = 0;
+        for section in self.sections.iter() {
+            let section_end = section
+                .virtual_address
+                .checked_add(section.virtual_size)
+                .ok_or_else(|| Error::new("Invalid PE section virtual size"))?;
+            end = end.max(section_end);
+        }
+        let align = self.section_alignment.max(1);
+        end.checked_add(align - 1)
+            .map(|end| end & !(align - 1))
+            .ok_or_else(|| Error::new("PE section addresses overflow"))
+    }
+
+    /// Return the mutable section data at the given virtual address.
+    fn image_data_mut(&mut self, address: u32, size: u32) -> Option<&mut [u8]> {
+        self.sections.iter_mut().find_map(|section| {
+            let offset = address.checked_sub(section.virtual_address)?;
+            if offset.checked_add(size)? > section.data.len() as u32 {
+                return None;
+            }
+            Some(&mut section.data.to_mut()[offset as usize..][..size as usize])
+        })
+    }
+
+    /// Return the mutable data at the given file offset in the input file.
+    ///
+    /// Only section data and trailing data are supported.
+    fn file_data_mut(&mut self, offset: u32, size: u32) -> Option<&mut [u8]> {
+        if let Some(offset) = offset.checked_sub(self.trailing_offset) {
+            if offset.checked_add(size)? <= self.trailing_data.len() as u32 {
+                return Some(&mut self.trailing_data.to_mut()[offset as usize..][..size as usize]);
+            }
+        }
+        self.sections.iter_mut().find_map(|section| {
+            let offset = offset.checked_sub(section.pointer_to_raw_data)?;
+            if offset.checked_add(size)? > section.data.len() as u32 {
+                return None;
+            }
+            Some(&mut section.data.to_mut()[offset as usize..][..size as usize])
+        })
+    }
+
+    /// Remove the debug directory.
+    ///
+    /// The debug directory entries and the data that they refer to are zeroed.
+    /// The sections containing this data are not resized.
+    ///
+    /// Returns true if the image had a debug directory.
+    pub fn strip_debug_directories(&mut self) -> Result<bool> {
+        let Some(dir) = self
+            .data_directories
+            .get_mut(pe::IMAGE_DIRECTORY_ENTRY_DEBUG)
+        else {
+            return Ok(false);
+        };
+        let dir = mem::take(dir);
+        if dir.virtual_address == 0 {
+            return Ok(false);
+        }
+
+        let directory = self
+            .image_data_mut(dir.virtual_address, dir.size)
+            .ok_or_else(|| Error::new("Invalid PE debug directory"))?;
+        let entries = pod::slice_from_all_bytes::<pe::ImageDebugDirectory>(directory)
+            .map_err(|_| Error::new("Invalid PE debug directory size"))?
+            .to_vec();
+        directory.fill(0);
+        for entry in entries {
+            let size = entry.size_of_data.get(LE);
+            let address = entry.address_of_raw_data.get(LE);
+            let offset = entry.pointer_to_raw_data.get(LE);
+            let data = if address != 0 {
+                self.image_data_mut(address, size)
+            } else if offset != 0 {
+                self.file_data_mut(offset, size)
+            } else {
+                None
+            };
+            if let Some(data) = data {
+                data.fill(0);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Return the section containing the given virtual address.
+    ///
+    /// Deleted sections are included.
+    fn section_containing(&self, address: u32) -> Option<&Section<'data>> {
+        self.all_sections().find(|section| {
+            address >= section.virtual_address
+                && address - section.virtual_address < section.virtual_size
+        })
+    }
+
+    /// Return an iterator for all sections, including deleted sections.
+    fn all_sections(&self) -> impl Iterator<Item = &Section<'data>> {
+        (0..self.sections.len()).map(move |index| self.sections.get(SectionId(index)))
+    }
+
+    /// Update the import table for any renamed DLLs.
+    ///
+    /// Names are replaced in place if they fit in the space of the original name.
+    /// Otherwise, the names are appended to the section containing the import
+    /// table, which must have enough free address space before the next section.
+    fn finalize_imports(&mut self) -> Result<()> {
+        let Some(first) = self.imports.first() else {
+            return Ok(());
+        };
+        let section = self
+            .sections
+            .iter()
+            .find(|section| {
+                first.descriptor >= section.virtual_address
+                    && first.descriptor - section.virtual_address < section.virtual_size
+            })
+            .ok_or_else(|| Error::new("Invalid PE import table address"))?;
+        let section_id = section.id();
+        let new_offset = section
+            .virtual_size
+            .checked_add(1)
+            .map(|size| size & !1)
+            .ok_or_else(|| Error::new("Invalid PE section virtual size"))?;
+
+        let mut updates = Vec::new();
+        let mut new_names = Vec::new();
+        for import in &self.imports {
+            if import.name.as_slice() == import.original_name {
+                continue;
+            }
+            if import.name.len() <= import.original_name.len() {
+                let mut name = import.name.to_vec();
+                name.resize(import.original_name.len(), 0);
+                updates.push((import.name_address, name));
+            } else {
+                let address = section
+                    .virtual_address
+                    .checked_add(new_offset)
+                    .and_then(|address| address.checked_add(new_names.len() as u32))
+                    .ok_or_else(|| Error::new("Invalid PE section virtual size"))?;
+                new_names.extend_from_slice(&import.name);
+                new_names.push(0);
+                new_names.resize(write::align(new_names.len(), 2), 0);
+                // The name field is at offset 12 in the import descriptor.
+                let name_field = import
+                    .descriptor
+                    .checked_add(12)
+                    .ok_or_else(|| Error::new("Invalid PE import descriptor"))?;
+                updates.push((name_field, address.to_le_bytes().to_vec()));
+            }
+        }
+
+        if !new_names.is_empty() {
+            let virtual_size = new_offset
+                .checked_add(new_names.len() as u32)
+                .ok_or_else(|| Error::new("Invalid PE section virtual size"))?;
+            let end = section
+                .virtual_address
+                .checked_add(virtual_size)
+                .ok_or_else(|| Error::new("Invalid PE section virtual size"))?;
+            let limit = self.next_section_after(section.virtual_address);
+            if limit.map_or(false, |limit| end > limit) {
+                return Err(Error(format!(
+                    "Not enough space in PE section `{}` for import DLL names",
+                    section.name
+                )));
+            }
+            let section = self.sections.get_mut(section_id);
+            let data = section.data.to_mut();
+            data.resize(new_offset as usize, 0);
+            data.extend_from_slice(&new_names);
+            section.virtual_size = virtual_size;
+        }
+
+        for (address, data) in updates {
+            self.image_data_mut(address, data.len() as u32)
+                .ok_or_else(|| Error::new("Invalid PE import descriptor"))?
+                .copy_from_slice(&data);
+        }
+        Ok(())
+    }
+
+    /// Return the address of the first section after the given address.
+    fn next_section_after(&self, address: u32) -> Option<u32> {
+        self.sections
+            .iter()
+            .map(|section| section.virtual_address)
+            .filter(|&other| other > address)
+            .min()
+    }
+
+    /// Write the base relocations to the section containing the base
+    /// relocation directory, or to a new `.reloc` section.
+    fn finalize_base_relocations(&mut self) -> Result<()> {
+        let dir = self
+            .data_directories
+            .get(pe::IMAGE_DIRECTORY_ENTRY_BASERELOC)
+            .copied()
+            .unwrap_or_default();
+        if dir.virtual_address == 0 && self.base_relocations.is_empty() {
+            return Ok(());
+        }
+
+        let mut relocations = self.base_relocations.clone();
+        relocations.sort_by_key(|relocation| relocation.virtual_address);
+        let data = base_relocation_data(&relocations);
+        let size = data.len() as u32;
+
+        if dir.virtual_address == 0 {
+            let virtual_address = self.next_section_address()?;
+            let section = self.sections.add();
+            section.name = ByteString::from(&b".reloc"[..]);
+            section.characteristics = pe::IMAGE_SCN_CNT_INITIALIZED_DATA
+                | pe::IMAGE_SCN_MEM_READ
+                | pe::IMAGE_SCN_MEM_DISCARDABLE;
+            section.virtual_address = virtual_address;
+            section.virtual_size = size;
+            section.data = data.into();
+            self.set_data_directory(pe::IMAGE_DIRECTORY_ENTRY_BASERELOC, virtual_address, size);
+            return Ok(());
+        }
+
+        let section = match self.section_containing(dir.virtual_address) {
+            Some(section) if !section.delete => section,
+            // The directory is cleared when writing.
+            Some(_) => return Ok(()),
+            None => return Err(Error::new("Invalid PE base relocation directory address")),
+        };
+        let offset = (dir.virtual_address - section.virtual_address) as usize;
+        let end = offset
+            .checked_add(dir.size as usize)
+            .filter(|&end| end <= section.data.len())
+            .ok_or_else(|| Error::new("Invalid PE base relocation directory size"))?;
+        if size != dir.size && end != section.data.len() {
+            return Err(Error(format!(
+                "Can't resize PE base relocations that are not at the end of section `{}`",
+                section.name
+            )));
+        }
+        let virtual_size = section
+            .virtual_size
+            .checked_sub(dir.size)
+            .and_then(|virtual_size| virtual_size.checked_add(size))
+            .ok_or_else(|| Error::new("Invalid PE base relocation directory size"))?;
+        let section_end = section
+            .virtual_address
+            .checked_add(virtual_size)
+            .ok_or_else(|| Error::new("Invalid PE section virtual size"))?;
+        let limit = self.next_section_after(section.virtual_address);
+        if limit.map_or(false, |limit| section_end > limit) {
+            return Err(Error(format!(
+                "Not enough space in PE section `{}` for base relocations",
+                section.name
+            )));
+        }
+        let section_id = section.id();
+        let section = self.sections.get_mut(section_id);
+        let section_data = section.data.to_mut();
+        section_data.truncate(offset);
+        section_data.extend_from_slice(&data);
+        section_data.resize(section_data.len() + (end - offset) - dir.size as usize, 0);
+        section.virtual_size = virtual_size;
+        self.set_data_directory(
+            pe::IMAGE_DIRECTORY_ENTRY_BASERELOC,
+            dir.virtual_address,
+            size,
+        );
+        Ok(())
+    }
+
+    fn set_data_directory(&mut self, index: usize, virtual_address: u32, size: u32) {
+        if self.data_directories.len() <= index {
+            self.data_directories
+                .resize(index + 1, DataDirectory::default());
+        }
+        self.data_directories[index] = DataDirectory {
+            virtual_address,
+            size,
+        };
+    }
+
+    /// Write the PE image to the buffer.
+    pub fn write(mut self, buffer: &mut dyn write::WritableBuffer) -> Result<()> {
+        self.finalize_imports()?;
+        self.finalize_base_relocations()?;
+        if self.certificate_table.is_some() {
+            // The directory is set by the writer, but the entry must exist.
+            self.set_data_directory(pe::IMAGE_DIRECTORY_ENTRY_SECURITY, 0, 0);
+        }
+
+        // Sections can only be deleted from the end of an image, because the
+        // loader requires section addresses to be contiguous.
+        if let Some(deleted) = self.all_sections().find(|section| section.delete) {
+            if let Some(section) = self
+                .all_sections()
+                .skip(deleted.id().0)
+                .find(|section| !section.delete)
+            {
+                return Err(Error(format!(
+                    "Can't delete PE section `{}` that is followed by section `{}`",
+                    deleted.name, section.name
+                )));
+            }
+        }
+        let sections: Vec<_> = self.sections.iter().collect();
+
+        let mut names = Vec::new();
+        for section in &sections {
+            if section.name.as_slice() == section.original_name {
+                names.push(section.raw_name);
+            } else if section.name.len() <= 8 {
+                let mut name = [0; 8];
+                name[..section.name.len()].copy_from_slice(&section.name);
+                names.push(name);
+            } else {
+                return Err(Error(format!(
+                    "PE section name `{}` is longer than 8 bytes",
+                    section.name
+                )));
+            }
+        }
+
+        let trailing = &*self.trailing_data;
+        let (trailing_before, symbol_table, trailing_after) = match self.symbol_table {
+            Some((offset, _)) => {
+                let (before, rest) = trailing.split_at(offset as usize);
+                let (symbol_table, after) = rest.split_at(self.symbol_table_len as usize);
+                (before, symbol_table, after)
+            }
+            None => (trailing, &[][..], &[][..]),
+        };
+
+        let mut writer = write::pe::Writer::new(
+            self.is_64,
+            self.section_alignment,
+            self.file_alignment,
+            buffer,
+        );
+
+        // Reserve file ranges and virtual addresses.
+        writer.reserve_dos_header();
+        writer.reserve(self.dos_stub.len() as u32, 1);
+        writer.reserve_nt_headers(self.data_directories.len());
+        for (index, dir) in self.data_directories.iter().enumerate() {
+            // The bound import table is stored in the headers, which aren't copied.
+            // It is only an optimization, so it is safe to remove.
+            if index == pe::IMAGE_DIRECTORY_ENTRY_SECURITY
+                || index == pe::IMAGE_DIRECTORY_ENTRY_BOUND_IMPORT
+            {
+                continue;
+            }
+            // Remove data directories that refer to deleted sections.
+            if self
+                .section_containing(dir.virtual_address)
+                .map_or(false, |section| section.delete)
+            {
+                continue;
+            }
+            writer.set_data_directory(index, dir.virtual_address, dir.size);
+        }
+        writer.reserve_section_headers(sections.len() as u16);
+
+        let mut ranges = Vec::new();
+        for (section, name) in sections.iter().zip(&names) {
+            if section.virtual_address < writer.virtual_len() {
+                return Err(Error(format!(
+                    "Not enough space for PE section `{}` at address {:#x}",
+                    section.name, section.virtual_address
+                )));
+            }
+            writer.reserve_virtual_until(section.virtual_address);
+            // Keep the file offset from the input file if possible, so that
+            // any file offsets in the section data remain valid.
+            if !section.data.is_empty() && section.pointer_to_raw_data >= writer.reserved_len() {
+                writer.reserve_until(section.pointer_to_raw_data);
+            }
+            let range = writer.reserve_section(
+                *name,
+                section.characteristics,
+                section.virtual_size,
+                section.data.len() as u32,
+            );
+            ranges.push(range);
+        }
+
+        let trailing_offset = writer.reserve(trailing_before.len() as u32, 1);
+        if let Some((_, symbol_num)) = self.symbol_table {
+            writer.reserve_symbol_table(symbol_num, symbol_table.len() as u32);
+        }
+        let trailing_after_offset = writer.reserve(trailing_after.len() as u32, 1);
+        if let Some(certificate_table) = &self.certificate_table {
+            writer.reserve_certificate_table(certificate_table.len() as u32);
+        }
+
+        // Update the file offsets in the debug directory for any data that has moved.
+        let map_offset = |offset: u32| -> Option<u32> {
+            if let Some(delta) = offset.checked_sub(self.trailing_offset) {
+                if (delta as usize) < trailing.len() {
+                    return Some(trailing_offset + delta);
+                }
+            }
+            sections.iter().zip(&ranges).find_map(|(section, range)| {
+                let delta = offset.checked_sub(section.pointer_to_raw_data)?;
+                ((delta as usize) < section.data.len()).then_some(range.file_offset + delta)
+            })
+        };
+        let mut section_data: Vec<Cow<'_, [u8]>> = sections
+            .iter()
+            .map(|section| Cow::Borrowed(&*section.data))
+            .collect();
+        if let Some(dir) = self
+            .data_directories
+            .get(pe::IMAGE_DIRECTORY_ENTRY_DEBUG)
+            .filter(|dir| dir.virtual_address != 0)
+        {
+            let (index, offset) = sections
+                .iter()
+                .enumerate()
+                .find_map(|(index, section)| {
+                    let offset = dir.virtual_address.checked_sub(section.virtual_address)?;
+                    (offset.checked_add(dir.size)? <= section.data.len() as u32)
+                        .then_some((index, offset as usize))
+                })
+                .ok_or_else(|| Error::new("Invalid PE debug directory"))?;
+            let entry_size = mem::size_of::<pe::ImageDebugDirectory>();
+            let data = &section_data[index][offset..][..dir.size as usize];
+            let entries = pod::slice_from_all_bytes::<pe::ImageDebugDirectory>(data)
+                .map_err(|_| Error::new("Invalid PE debug directory size"))?
+                .to_vec();
+            for (i, mut entry) in entries.into_iter().enumerate() {
+                let old_offset = entry.pointer_to_raw_data.get(LE);
+                if old_offset == 0 {
+                    continue;
+                }
+                match map_offset(old_offset) {
+                    Some(new_offset) if new_offset != old_offset => {
+                        entry.pointer_to_raw_data.set(LE, new_offset);
+                        section_data[index].to_mut()[offset + i * entry_size..][..entry_size]
+                            .copy_from_slice(pod::bytes_of(&entry));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Write.
+        let mut dos_header = self.dos_header;
+        dos_header.e_lfanew.set(LE, writer.nt_headers_offset());
+        writer.write_custom_dos_header(&dos_header)?;
+        writer.write(&self.dos_stub);
+        writer.write_nt_headers(self.nt_headers.clone());
+        writer.write_section_headers();
+        for (data, range) in section_data.iter().zip(&ranges) {
+            writer.write_section(range.file_offset, data);
+        }
+        writer.pad_until(trailing_offset);
+        writer.write(trailing_before);
+        if self.symbol_table.is_some() {
+            writer.write_symbol_table(symbol_table);
+        }
+        writer.pad_until(trailing_after_offset);
+        writer.write(trailing_after);
+        if let Some(certificate_table) = &self.certificate_table {
+            writer.write_certificate_table(certificate_table);
+        }
+
+        debug_assert_eq!(writer.reserved_len() as usize, writer.len());
+        Ok(())
+    }
+}
+
+/// Encode base relocations into blocks.
+///
+/// The relocations must be sorted by address.
+fn base_relocation_data(relocations: &[BaseRelocation]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut block_offset = 0;
+    let mut block_address = None;
+    for relocation in relocations {
+        let page = relocation.virtual_address & !0xfff;
+        if block_address != Some(page) {
+            finish_base_relocation_block(&mut data, block_offset);
+            block_offset = data.len();
+            block_address = Some(page);
+            data.extend_from_slice(&page.to_le_bytes());
+            data.extend_from_slice(&0u32.to_le_bytes());
+        }
+        let entry = relocation.typ << 12 | (relocation.virtual_address & 0xfff) as u16;
+        data.extend_from_slice(&entry.to_le_bytes());
+    }
+    finish_base_relocation_block(&mut data, block_offset);
+    data
+}
+
+fn finish_base_relocation_block(data: &mut Vec<u8>, block_offset: usize) {
+    if data.len() == block_offset {
+        return;
+    }
+    // Blocks must have an even number of relocations.
+    data.resize(write::align(data.len(), 4), 0);
+    let size = (data.len() - block_offset) as u32;
+    data[block_offset + 4..][..4].copy_from_slice(&size.to_le_bytes());
+}
+
+/// A data directory in a PE image.
+///
+/// This corresponds to [`pe::ImageDataDirectory`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DataDirectory {
+    /// The virtual address of the data.
+    ///
+    /// For the certificate table, this is a file offset instead.
+    pub virtual_address: u32,
+    /// The size of the data.
+    pub size: u32,
+}
+
+/// A base relocation in a PE image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BaseRelocation {
+    /// The virtual address of the location to relocate.
+    pub virtual_address: u32,
+    /// One of the `pe::IMAGE_REL_BASED_*` constants.
+    pub typ: u16,
+}
+
+/// A DLL in the import table of a PE image.
+#[derive(Debug)]
+pub struct ImportDll<'data> {
+    /// The address of the import descriptor.
+    descriptor: u32,
+    /// The address of the name in the input file.
+    name_address: u32,
+    /// The name in the input file.
+    original_name: &'data [u8],
+    /// The name of the DLL.
+    ///
+    /// If this is changed, then the import descriptor is updated when
+    /// writing.
+    pub name: ByteString<'data>,
+}
+
+/// An ID for referring to a section in [`Sections`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SectionId(usize);
+
+impl fmt::Debug for SectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SectionId({})", self.0)
+    }
+}
+
+impl Id for SectionId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for SectionId {
+    fn new(id: usize) -> Self {
+        SectionId(id)
+    }
+}
+
+/// A section in [`Sections`].
+///
+/// This corresponds to [`pe::ImageSectionHeader`].
+#[derive(Debug)]
+pub struct Section<'data> {
+    id: SectionId,
+    /// Ignore this section when writing the PE image.
+    ///
+    /// Only sections at the end of the image can be deleted.
+    pub delete: bool,
+    /// The name of the section.
+    ///
+    /// This must be at most 8 bytes unless it is unchanged from the input file.
+    pub name: ByteString<'data>,
+    /// The name of the section in the input file.
+    original_name: &'data [u8],
+    /// The name field of the section header in the input file.
+    ///
+    /// This is used if the section has not been renamed, since the name may be
+    /// an offset into the string table.
+    raw_name: [u8; 8],
+    /// The `characteristics` field in the section header.
+    pub characteristics: u32,
+    /// The `virtual_address` field in the section header.
+    pub virtual_address: u32,
+    /// The `virtual_size` field in the section header.
+    pub virtual_size: u32,
+    /// The file offset of the section data in the input file.
+    ///
+    /// When writing, the data is kept at this offset if possible, so that file
+    /// offsets in other data remain valid.
+    pub pointer_to_raw_data: u32,
+    /// The section data.
+    ///
+    /// This may be shorter than the virtual size.
+    pub data: Bytes<'data>,
+}
+
+impl<'data> Item for Section<'data> {
+    type Id = SectionId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Section<'data> {
+    /// The ID used for referring to this section.
+    pub fn id(&self) -> SectionId {
+        self.id
+    }
+}
+
+/// A section table.
+pub type Sections<'data> = Table<Section<'data>>;
+
+impl<'data> Sections<'data> {
+    /// Add a new section to the table.
+    pub fn add(&mut self) -> &mut Section<'data> {
+        let id = self.next_id();
+        self.push(Section {
+            id,
+            delete: false,
+            name: ByteString::default(),
+            original_name: &[],
+            raw_name: [0; 8],
+            characteristics: 0,
+            virtual_address: 0,
+            virtual_size: 0,
+            pointer_to_raw_data: 0,
+            data: Bytes::default(),
+        })
+    }
+}
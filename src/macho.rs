@@ -2628,6 +2628,700 @@ pub struct NoteCommand<E: Endian> {
     pub size: U64<E>,
 }
 
+// Definitions from "/usr/include/mach-o/fixup-chains.h".
+
+/// Header of the `LC_DYLD_CHAINED_FIXUPS` payload.
+///
+/// Corresponds to struct dyld_chained_fixups_header from fixup-chains.h.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DyldChainedFixupsHeader<E: Endian> {
+    /// 0
+    pub fixups_version: U32<E>,
+    /// offset of `DyldChainedStartsInImage` in chain data
+    pub starts_offset: U32<E>,
+    /// offset of imports table in chain data
+    pub imports_offset: U32<E>,
+    /// offset of symbol strings in chain data
+    pub symbols_offset: U32<E>,
+    /// number of imported symbol names
+    pub imports_count: U32<E>,
+    /// `DYLD_CHAINED_IMPORT*`
+    pub imports_format: U32<E>,
+    /// 0 => uncompressed, 1 => zlib compressed
+    pub symbols_format: U32<E>,
+}
+
+/// This struct is embedded in `LC_DYLD_CHAINED_FIXUPS` payload.
+///
+/// It is followed by `seg_count` entries of `seg_info_offset: U32<E>`.
+/// Each entry is the offset of a `DyldChainedStartsInSegment` from the start
+/// of this struct, or 0 if the segment has no fixups.
+///
+/// Corresponds to struct dyld_chained_starts_in_image from fixup-chains.h.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DyldChainedStartsInImage<E: Endian> {
+    pub seg_count: U32<E>,
+}
+
+/// This struct is embedded in `DyldChainedStartsInImage` and passed down to the kernel
+/// for page-in linking.
+///
+/// It is followed by `page_count` entries of `page_start: U16<E>`. Each entry is the
+/// offset in the page of the first element in the chain, or `DYLD_CHAINED_PTR_START_NONE`
+/// if there are no fixups on the page.
+///
+/// Some 32-bit formats may require multiple starts per page. For those, if
+/// `DYLD_CHAINED_PTR_START_MULTI` is set in an entry, then the remaining bits
+/// are an index into the `page_start` array of a list of starts, the last of which
+/// has `DYLD_CHAINED_PTR_START_LAST` set.
+///
+/// Corresponds to struct dyld_chained_starts_in_segment from fixup-chains.h.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DyldChainedStartsInSegment<E: Endian> {
+    /// size of this (amount kernel needs to copy)
+    pub size: U32<E>,
+    /// 0x1000 or 0x4000
+    pub page_size: U16<E>,
+    /// `DYLD_CHAINED_PTR_*`
+    pub pointer_format: U16<E>,
+    /// offset in memory to start of segment
+    pub segment_offset: U64<E>,
+    /// for 32-bit OS, any value beyond this is not a pointer
+    pub max_valid_pointer: U32<E>,
+    /// how many pages are in array
+    pub page_count: U16<E>,
+}
+
+// Values for `DyldChainedStartsInSegment::page_start`.
+/// Used in `page_start[]` to denote a page with no fixups.
+pub const DYLD_CHAINED_PTR_START_NONE: u16 = 0xFFFF;
+/// Used in `page_start[]` to denote a page which has multiple starts.
+pub const DYLD_CHAINED_PTR_START_MULTI: u16 = 0x8000;
+/// Used in `chain_starts[]` to denote last start in list for page.
+pub const DYLD_CHAINED_PTR_START_LAST: u16 = 0x8000;
+
+// Values for `DyldChainedStartsInSegment::pointer_format`.
+/// stride 8, unauth target is vmaddr
+pub const DYLD_CHAINED_PTR_ARM64E: u16 = 1;
+/// target is vmaddr
+pub const DYLD_CHAINED_PTR_64: u16 = 2;
+pub const DYLD_CHAINED_PTR_32: u16 = 3;
+pub const DYLD_CHAINED_PTR_32_CACHE: u16 = 4;
+pub const DYLD_CHAINED_PTR_32_FIRMWARE: u16 = 5;
+/// target is vm offset
+pub const DYLD_CHAINED_PTR_64_OFFSET: u16 = 6;
+/// old name
+pub const DYLD_CHAINED_PTR_ARM64E_OFFSET: u16 = 7;
+/// stride 4, unauth target is vm offset
+pub const DYLD_CHAINED_PTR_ARM64E_KERNEL: u16 = 7;
+pub const DYLD_CHAINED_PTR_64_KERNEL_CACHE: u16 = 8;
+/// stride 8, unauth target is vm offset
+pub const DYLD_CHAINED_PTR_ARM64E_USERLAND: u16 = 9;
+/// stride 4, unauth target is vmaddr
+pub const DYLD_CHAINED_PTR_ARM64E_FIRMWARE: u16 = 10;
+/// stride 1, x86_64 kernel caches
+pub const DYLD_CHAINED_PTR_X86_64_KERNEL_CACHE: u16 = 11;
+/// stride 8, unauth target is vm offset, 24-bit bind
+pub const DYLD_CHAINED_PTR_ARM64E_USERLAND24: u16 = 12;
+/// stride 8, regular/auth targets both vm offsets.  Only A keys supported
+pub const DYLD_CHAINED_PTR_ARM64E_SHARED_CACHE: u16 = 13;
+/// stride 4, rebase offsets use segIndex and segOffset
+pub const DYLD_CHAINED_PTR_ARM64E_SEGMENTED: u16 = 14;
+
+// Values for `DyldChainedFixupsHeader::imports_format`.
+/// `DyldChainedImport`
+pub const DYLD_CHAINED_IMPORT: u32 = 1;
+/// `DyldChainedImport` followed by a 32-bit addend
+pub const DYLD_CHAINED_IMPORT_ADDEND: u32 = 2;
+/// `DyldChainedImport64` followed by a 64-bit addend
+pub const DYLD_CHAINED_IMPORT_ADDEND64: u32 = 3;
+
+// Values for `DyldChainedFixupsHeader::symbols_format`.
+pub const DYLD_CHAINED_SYMBOL_UNCOMPRESSED: u32 = 0;
+pub const DYLD_CHAINED_SYMBOL_ZLIB: u32 = 1;
+
+/// Corresponds to the structs for `DYLD_CHAINED_PTR_ARM64E` and its variants from
+/// fixup-chains.h.
+///
+/// This is a union of struct dyld_chained_ptr_arm64e_rebase,
+/// struct dyld_chained_ptr_arm64e_bind, struct dyld_chained_ptr_arm64e_auth_rebase,
+/// struct dyld_chained_ptr_arm64e_auth_bind, struct dyld_chained_ptr_arm64e_bind24,
+/// and struct dyld_chained_ptr_arm64e_auth_bind24.
+#[derive(Debug, Clone, Copy)]
+pub struct DyldChainedPtrArm64e(pub u64);
+
+impl DyldChainedPtrArm64e {
+    /// Whether the pointer is authenticated.
+    pub fn is_auth(&self) -> bool {
+        ((self.0 >> 63) & 1) != 0
+    }
+
+    /// Whether the pointer is a bind.
+    pub fn is_bind(&self) -> bool {
+        ((self.0 >> 62) & 1) != 0
+    }
+
+    /// The offset to the next pointer in the chain, in units of the stride.
+    ///
+    /// 0 if this is the last pointer in the chain.
+    pub fn next(&self) -> u64 {
+        (self.0 >> 51) & ((1 << 11) - 1)
+    }
+
+    /// The target of the pointer.
+    ///
+    /// Only valid for a rebase if `is_auth` is false.
+    pub fn target(&self) -> u64 {
+        self.0 & ((1 << 43) - 1)
+    }
+
+    /// The high 8 bits of the pointer.
+    ///
+    /// Only valid for a rebase if `is_auth` is false.
+    pub fn high8(&self) -> u64 {
+        (self.0 >> 43) & 0xff
+    }
+
+    /// The target of the pointer as a runtime offset.
+    ///
+    /// Only valid for a rebase if `is_auth` is true.
+    pub fn runtime_offset(&self) -> u64 {
+        self.0 & ((1 << 32) - 1)
+    }
+
+    /// The index of the import.
+    ///
+    /// Only valid for a bind, and for formats other than `DYLD_CHAINED_PTR_ARM64E_USERLAND24`.
+    pub fn ordinal(&self) -> u32 {
+        (self.0 & 0xffff) as u32
+    }
+
+    /// The index of the import.
+    ///
+    /// Only valid for a bind using `DYLD_CHAINED_PTR_ARM64E_USERLAND24`.
+    pub fn ordinal24(&self) -> u32 {
+        (self.0 & ((1 << 24) - 1)) as u32
+    }
+
+    /// The signed addend.
+    ///
+    /// Only valid for a bind if `is_auth` is false.
+    pub fn addend(&self) -> i64 {
+        // Sign extend the 19 bit value.
+        ((self.0 << 13) as i64) >> 45
+    }
+
+    /// The diversity value for authentication.
+    ///
+    /// Only valid if `is_auth` is true.
+    pub fn diversity(&self) -> u16 {
+        ((self.0 >> 32) & 0xffff) as u16
+    }
+
+    /// Whether to use address diversity for authentication.
+    ///
+    /// Only valid if `is_auth` is true.
+    pub fn addr_div(&self) -> bool {
+        ((self.0 >> 48) & 1) != 0
+    }
+
+    /// The key for authentication.
+    ///
+    /// Only valid if `is_auth` is true.
+    pub fn key(&self) -> u8 {
+        ((self.0 >> 49) & 3) as u8
+    }
+}
+
+/// Corresponds to the structs for `DYLD_CHAINED_PTR_64` and `DYLD_CHAINED_PTR_64_OFFSET`
+/// from fixup-chains.h.
+///
+/// This is a union of struct dyld_chained_ptr_64_rebase and struct dyld_chained_ptr_64_bind.
+#[derive(Debug, Clone, Copy)]
+pub struct DyldChainedPtr64(pub u64);
+
+impl DyldChainedPtr64 {
+    /// Whether the pointer is a bind.
+    pub fn is_bind(&self) -> bool {
+        ((self.0 >> 63) & 1) != 0
+    }
+
+    /// The offset to the next pointer in the chain, in 4-byte units.
+    ///
+    /// 0 if this is the last pointer in the chain.
+    pub fn next(&self) -> u64 {
+        (self.0 >> 51) & ((1 << 12) - 1)
+    }
+
+    /// The target of the pointer.
+    ///
+    /// Only valid if `is_bind` is false.
+    pub fn target(&self) -> u64 {
+        self.0 & ((1 << 36) - 1)
+    }
+
+    /// The high 8 bits of the pointer.
+    ///
+    /// Only valid if `is_bind` is false.
+    pub fn high8(&self) -> u64 {
+        (self.0 >> 36) & 0xff
+    }
+
+    /// The index of the import.
+    ///
+    /// Only valid if `is_bind` is true.
+    pub fn ordinal(&self) -> u32 {
+        (self.0 & ((1 << 24) - 1)) as u32
+    }
+
+    /// The unsigned addend.
+    ///
+    /// Only valid if `is_bind` is true.
+    pub fn addend(&self) -> u64 {
+        (self.0 >> 24) & 0xff
+    }
+}
+
+/// Corresponds to struct dyld_chained_ptr_64_kernel_cache_rebase from fixup-chains.h.
+///
+/// Used for `DYLD_CHAINED_PTR_64_KERNEL_CACHE` and `DYLD_CHAINED_PTR_X86_64_KERNEL_CACHE`.
+#[derive(Debug, Clone, Copy)]
+pub struct DyldChainedPtr64KernelCache(pub u64);
+
+impl DyldChainedPtr64KernelCache {
+    /// Whether the pointer is authenticated.
+    pub fn is_auth(&self) -> bool {
+        ((self.0 >> 63) & 1) != 0
+    }
+
+    /// The offset to the next pointer in the chain, in units of the stride.
+    ///
+    /// 0 if this is the last pointer in the chain.
+    pub fn next(&self) -> u64 {
+        (self.0 >> 51) & ((1 << 12) - 1)
+    }
+
+    /// The target of the pointer as an offset from the start of the cache.
+    pub fn target(&self) -> u64 {
+        self.0 & ((1 << 30) - 1)
+    }
+
+    /// The index of the cache that contains the target.
+    pub fn cache_level(&self) -> u8 {
+        ((self.0 >> 30) & 3) as u8
+    }
+
+    /// The diversity value for authentication.
+    ///
+    /// Only valid if `is_auth` is true.
+    pub fn diversity(&self) -> u16 {
+        ((self.0 >> 32) & 0xffff) as u16
+    }
+
+    /// Whether to use address diversity for authentication.
+    ///
+    /// Only valid if `is_auth` is true.
+    pub fn addr_div(&self) -> bool {
+        ((self.0 >> 48) & 1) != 0
+    }
+
+    /// The key for authentication.
+    ///
+    /// Only valid if `is_auth` is true.
+    pub fn key(&self) -> u8 {
+        ((self.0 >> 49) & 3) as u8
+    }
+}
+
+/// Corresponds to the structs for `DYLD_CHAINED_PTR_32` from fixup-chains.h.
+///
+/// This is a union of struct dyld_chained_ptr_32_rebase and struct dyld_chained_ptr_32_bind.
+#[derive(Debug, Clone, Copy)]
+pub struct DyldChainedPtr32(pub u32);
+
+impl DyldChainedPtr32 {
+    /// Whether the pointer is a bind.
+    pub fn is_bind(&self) -> bool {
+        ((self.0 >> 31) & 1) != 0
+    }
+
+    /// The offset to the next pointer in the chain, in 4-byte units.
+    ///
+    /// 0 if this is the last pointer in the chain.
+    pub fn next(&self) -> u32 {
+        (self.0 >> 26) & ((1 << 5) - 1)
+    }
+
+    /// The target of the pointer.
+    ///
+    /// Only valid if `is_bind` is false. If the value is greater than
+    /// `max_valid_pointer`, then this is not a pointer.
+    pub fn target(&self) -> u32 {
+        self.0 & ((1 << 26) - 1)
+    }
+
+    /// The index of the import.
+    ///
+    /// Only valid if `is_bind` is true.
+    pub fn ordinal(&self) -> u32 {
+        self.0 & ((1 << 20) - 1)
+    }
+
+    /// The unsigned addend.
+    ///
+    /// Only valid if `is_bind` is true.
+    pub fn addend(&self) -> u32 {
+        (self.0 >> 20) & ((1 << 6) - 1)
+    }
+}
+
+/// Corresponds to struct dyld_chained_ptr_32_cache_rebase from fixup-chains.h.
+///
+/// Used for `DYLD_CHAINED_PTR_32_CACHE`.
+#[derive(Debug, Clone, Copy)]
+pub struct DyldChainedPtr32Cache(pub u32);
+
+impl DyldChainedPtr32Cache {
+    /// The offset to the next pointer in the chain, in 4-byte units.
+    ///
+    /// 0 if this is the last pointer in the chain.
+    pub fn next(&self) -> u32 {
+        self.0 >> 30
+    }
+
+    /// The target of the pointer as an offset from the start of the cache.
+    pub fn target(&self) -> u32 {
+        self.0 & ((1 << 30) - 1)
+    }
+}
+
+/// Corresponds to struct dyld_chained_ptr_32_firmware_rebase from fixup-chains.h.
+///
+/// Used for `DYLD_CHAINED_PTR_32_FIRMWARE`.
+#[derive(Debug, Clone, Copy)]
+pub struct DyldChainedPtr32Firmware(pub u32);
+
+impl DyldChainedPtr32Firmware {
+    /// The offset to the next pointer in the chain, in 4-byte units.
+    ///
+    /// 0 if this is the last pointer in the chain.
+    pub fn next(&self) -> u32 {
+        self.0 >> 26
+    }
+
+    /// The target of the pointer.
+    pub fn target(&self) -> u32 {
+        self.0 & ((1 << 26) - 1)
+    }
+}
+
+/// Corresponds to struct dyld_chained_import from fixup-chains.h.
+///
+/// Used for `DYLD_CHAINED_IMPORT` and `DYLD_CHAINED_IMPORT_ADDEND`.
+#[derive(Debug, Clone, Copy)]
+pub struct DyldChainedImport(pub u32);
+
+impl DyldChainedImport {
+    /// The library ordinal.
+    ///
+    /// Values greater than 0xf0 are special ordinals, which are
+    /// `BIND_SPECIAL_DYLIB_*` when sign extended.
+    pub fn lib_ordinal(&self) -> u8 {
+        (self.0 & 0xff) as u8
+    }
+
+    /// Whether the import is weak.
+    pub fn weak_import(&self) -> bool {
+        ((self.0 >> 8) & 1) != 0
+    }
+
+    /// The offset of the symbol name in the symbol strings.
+    pub fn name_offset(&self) -> u32 {
+        self.0 >> 9
+    }
+}
+
+/// Corresponds to struct dyld_chained_import_addend64 from fixup-chains.h,
+/// excluding the addend.
+///
+/// Used for `DYLD_CHAINED_IMPORT_ADDEND64`.
+#[derive(Debug, Clone, Copy)]
+pub struct DyldChainedImport64(pub u64);
+
+impl DyldChainedImport64 {
+    /// The library ordinal.
+    ///
+    /// Values greater than 0xfff0 are special ordinals, which are
+    /// `BIND_SPECIAL_DYLIB_*` when sign extended.
+    pub fn lib_ordinal(&self) -> u16 {
+        (self.0 & 0xffff) as u16
+    }
+
+    /// Whether the import is weak.
+    pub fn weak_import(&self) -> bool {
+        ((self.0 >> 16) & 1) != 0
+    }
+
+    /// The offset of the symbol name in the symbol strings.
+    pub fn name_offset(&self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+}
+
+// Definitions from "osfmk/kern/cs_blobs.h" in xnu.
+//
+// All structures defined here are always written and read to/from disk
+// in big-endian order.
+
+// Magic numbers used by code signing blobs.
+
+/// single Requirement blob
+pub const CSMAGIC_REQUIREMENT: u32 = 0xfade_0c00;
+/// Requirements vector (internal requirements)
+pub const CSMAGIC_REQUIREMENTS: u32 = 0xfade_0c01;
+/// CodeDirectory blob
+pub const CSMAGIC_CODEDIRECTORY: u32 = 0xfade_0c02;
+/// embedded form of signature data
+pub const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade_0cc0;
+pub const CSMAGIC_EMBEDDED_SIGNATURE_OLD: u32 = 0xfade_0b02;
+/// embedded entitlements
+pub const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xfade_7171;
+/// embedded DER encoded entitlements
+pub const CSMAGIC_EMBEDDED_DER_ENTITLEMENTS: u32 = 0xfade_7172;
+/// multi-arch collection of embedded signatures
+pub const CSMAGIC_DETACHED_SIGNATURE: u32 = 0xfade_0cc1;
+/// CMS Signature, among other things
+pub const CSMAGIC_BLOBWRAPPER: u32 = 0xfade_0b01;
+/// Light weight code requirement
+pub const CSMAGIC_EMBEDDED_LAUNCH_CONSTRAINT: u32 = 0xfade_8181;
+
+pub const CS_SUPPORTSSCATTER: u32 = 0x20100;
+pub const CS_SUPPORTSTEAMID: u32 = 0x20200;
+pub const CS_SUPPORTSCODELIMIT64: u32 = 0x20300;
+pub const CS_SUPPORTSEXECSEG: u32 = 0x20400;
+pub const CS_SUPPORTSRUNTIME: u32 = 0x20500;
+pub const CS_SUPPORTSLINKAGE: u32 = 0x20600;
+
+// Slot numbers within a SuperBlob.
+
+/// slot index for CodeDirectory
+pub const CSSLOT_CODEDIRECTORY: u32 = 0;
+pub const CSSLOT_INFOSLOT: u32 = 1;
+pub const CSSLOT_REQUIREMENTS: u32 = 2;
+pub const CSSLOT_RESOURCEDIR: u32 = 3;
+pub const CSSLOT_APPLICATION: u32 = 4;
+pub const CSSLOT_ENTITLEMENTS: u32 = 5;
+pub const CSSLOT_DER_ENTITLEMENTS: u32 = 7;
+pub const CSSLOT_LAUNCH_CONSTRAINT_SELF: u32 = 8;
+pub const CSSLOT_LAUNCH_CONSTRAINT_PARENT: u32 = 9;
+pub const CSSLOT_LAUNCH_CONSTRAINT_RESPONSIBLE: u32 = 10;
+pub const CSSLOT_LIBRARY_CONSTRAINT: u32 = 11;
+
+/// first alternate CodeDirectory, if any
+pub const CSSLOT_ALTERNATE_CODEDIRECTORIES: u32 = 0x1000;
+/// max number of alternate CD slots
+pub const CSSLOT_ALTERNATE_CODEDIRECTORY_MAX: u32 = 5;
+/// one past the last
+pub const CSSLOT_ALTERNATE_CODEDIRECTORY_LIMIT: u32 =
+    CSSLOT_ALTERNATE_CODEDIRECTORIES + CSSLOT_ALTERNATE_CODEDIRECTORY_MAX;
+
+/// CMS Signature
+pub const CSSLOT_SIGNATURESLOT: u32 = 0x10000;
+pub const CSSLOT_IDENTIFICATIONSLOT: u32 = 0x10001;
+pub const CSSLOT_TICKETSLOT: u32 = 0x10002;
+
+/// compat with amfi
+pub const CSTYPE_INDEX_REQUIREMENTS: u32 = 0x0000_0002;
+/// compat with amfi
+pub const CSTYPE_INDEX_ENTITLEMENTS: u32 = 0x0000_0005;
+
+// Requirement types within a requirements vector.
+
+pub const CS_HOST_REQUIREMENT_TYPE: u32 = 1;
+pub const CS_GUEST_REQUIREMENT_TYPE: u32 = 2;
+pub const CS_DESIGNATED_REQUIREMENT_TYPE: u32 = 3;
+pub const CS_LIBRARY_REQUIREMENT_TYPE: u32 = 4;
+pub const CS_PLUGIN_REQUIREMENT_TYPE: u32 = 5;
+
+// Hash types in a CodeDirectory.
+
+pub const CS_HASHTYPE_SHA1: u8 = 1;
+pub const CS_HASHTYPE_SHA256: u8 = 2;
+pub const CS_HASHTYPE_SHA256_TRUNCATED: u8 = 3;
+pub const CS_HASHTYPE_SHA384: u8 = 4;
+
+pub const CS_SHA1_LEN: usize = 20;
+pub const CS_SHA256_LEN: usize = 32;
+pub const CS_SHA256_TRUNCATED_LEN: usize = 20;
+
+/// always - larger hashes are truncated
+pub const CS_CDHASH_LEN: usize = 20;
+/// max size of the hash we'll support
+pub const CS_HASH_MAX_SIZE: usize = 48;
+
+// Code signing flags in a CodeDirectory.
+
+/// dynamically valid
+pub const CS_VALID: u32 = 0x0000_0001;
+/// ad hoc signed
+pub const CS_ADHOC: u32 = 0x0000_0002;
+/// has get-task-allow entitlement
+pub const CS_GET_TASK_ALLOW: u32 = 0x0000_0004;
+/// has installer entitlement
+pub const CS_INSTALLER: u32 = 0x0000_0008;
+/// Library Validation required by Hardened System Policy
+pub const CS_FORCED_LV: u32 = 0x0000_0010;
+/// (macOS Only) Page invalidation allowed by task port policy
+pub const CS_INVALID_ALLOWED: u32 = 0x0000_0020;
+/// don't load invalid pages
+pub const CS_HARD: u32 = 0x0000_0100;
+/// kill process if it becomes invalid
+pub const CS_KILL: u32 = 0x0000_0200;
+/// force expiration checking
+pub const CS_CHECK_EXPIRATION: u32 = 0x0000_0400;
+/// tell dyld to treat restricted
+pub const CS_RESTRICT: u32 = 0x0000_0800;
+/// require enforcement
+pub const CS_ENFORCEMENT: u32 = 0x0000_1000;
+/// require library validation
+pub const CS_REQUIRE_LV: u32 = 0x0000_2000;
+/// code signature permits restricted entitlements
+pub const CS_ENTITLEMENTS_VALIDATED: u32 = 0x0000_4000;
+/// has com.apple.rootless.restricted-nvram-variables.heritable entitlement
+pub const CS_NVRAM_UNRESTRICTED: u32 = 0x0000_8000;
+/// Apply hardened runtime policies
+pub const CS_RUNTIME: u32 = 0x0001_0000;
+/// Automatically signed by the linker
+pub const CS_LINKER_SIGNED: u32 = 0x0002_0000;
+
+// Executable segment flags in a CodeDirectory.
+
+/// executable segment denotes main binary
+pub const CS_EXECSEG_MAIN_BINARY: u64 = 0x1;
+/// allow unsigned pages (for debugging)
+pub const CS_EXECSEG_ALLOW_UNSIGNED: u64 = 0x10;
+/// main binary is debugger
+pub const CS_EXECSEG_DEBUGGER: u64 = 0x20;
+/// JIT enabled
+pub const CS_EXECSEG_JIT: u64 = 0x40;
+/// OBSOLETE: skip library validation
+pub const CS_EXECSEG_SKIP_LV: u64 = 0x80;
+/// can bless cdhash for execution
+pub const CS_EXECSEG_CAN_LOAD_CDHASH: u64 = 0x100;
+/// can execute blessed cdhash
+pub const CS_EXECSEG_CAN_EXEC_CDHASH: u64 = 0x200;
+
+/// Structure of an embedded-signature SuperBlob
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct CsBlobIndex {
+    /// type of entry
+    pub typ: U32<BigEndian>,
+    /// offset of entry
+    pub offset: U32<BigEndian>,
+}
+
+/// Corresponds to `CS_SuperBlob`.
+///
+/// This is followed by `count` entries of [`CsBlobIndex`].
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct CsSuperBlob {
+    /// magic number
+    pub magic: U32<BigEndian>,
+    /// total length of SuperBlob
+    pub length: U32<BigEndian>,
+    /// number of index entries following
+    pub count: U32<BigEndian>,
+}
+
+/// Corresponds to `CS_GenericBlob`.
+///
+/// This is followed by the data of the blob.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct CsGenericBlob {
+    /// magic number
+    pub magic: U32<BigEndian>,
+    /// total length of blob
+    pub length: U32<BigEndian>,
+}
+
+/// C form of a CodeDirectory.
+///
+/// Corresponds to `CS_CodeDirectory`. Fields after `spare2` are only present
+/// if `version` is large enough.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct CsCodeDirectory {
+    /// magic number (CSMAGIC_CODEDIRECTORY)
+    pub magic: U32<BigEndian>,
+    /// total length of CodeDirectory blob
+    pub length: U32<BigEndian>,
+    /// compatibility version
+    pub version: U32<BigEndian>,
+    /// setup and mode flags
+    pub flags: U32<BigEndian>,
+    /// offset of hash slot element at index zero
+    pub hash_offset: U32<BigEndian>,
+    /// offset of identifier string
+    pub ident_offset: U32<BigEndian>,
+    /// number of special hash slots
+    pub n_special_slots: U32<BigEndian>,
+    /// number of ordinary (code) hash slots
+    pub n_code_slots: U32<BigEndian>,
+    /// limit to main image signature range
+    pub code_limit: U32<BigEndian>,
+    /// size of each hash in bytes
+    pub hash_size: u8,
+    /// type of hash (cdHashType* constants)
+    pub hash_type: u8,
+    /// platform identifier; zero if not platform binary
+    pub platform: u8,
+    /// log2(page size in bytes); 0 => infinite
+    pub page_size: u8,
+    /// unused (must be zero)
+    pub spare2: U32<BigEndian>,
+
+    // Version 0x20100
+    /// offset of optional scatter vector
+    pub scatter_offset: U32<BigEndian>,
+
+    // Version 0x20200
+    /// offset of optional team identifier
+    pub team_offset: U32<BigEndian>,
+
+    // Version 0x20300
+    /// unused (must be zero)
+    pub spare3: U32<BigEndian>,
+    /// limit to main image signature range, 64 bits
+    pub code_limit64: U64<BigEndian>,
+
+    // Version 0x20400
+    /// offset of executable segment
+    pub exec_seg_base: U64<BigEndian>,
+    /// limit of executable segment
+    pub exec_seg_limit: U64<BigEndian>,
+    /// executable segment flags
+    pub exec_seg_flags: U64<BigEndian>,
+
+    // Version 0x20500
+    /// Runtime version
+    pub runtime: U32<BigEndian>,
+    /// offset of pre-encrypt hash slots
+    pub pre_encrypt_offset: U32<BigEndian>,
+
+    // Version 0x20600
+    pub linkage_hash_type: u8,
+    pub linkage_application_type: u8,
+    pub linkage_application_sub_type: U16<BigEndian>,
+    pub linkage_offset: U32<BigEndian>,
+    pub linkage_size: U32<BigEndian>,
+}
+
 // Definitions from "/usr/include/mach-o/nlist.h".
 
 #[derive(Debug, Clone, Copy)]
@@ -3539,7 +4233,15 @@ pub const X86_64_RELOC_SIGNED_4: u8 = 8;
 /// for thread local variables
 pub const X86_64_RELOC_TLV: u8 = 9;
 
-unsafe_impl_pod!(FatHeader, FatArch32, FatArch64,);
+unsafe_impl_pod!(
+    FatHeader,
+    FatArch32,
+    FatArch64,
+    CsBlobIndex,
+    CsSuperBlob,
+    CsGenericBlob,
+    CsCodeDirectory,
+);
 unsafe_impl_endian_pod!(
     DyldCacheHeader,
     DyldCacheMappingInfo,
@@ -3550,6 +4252,9 @@ unsafe_impl_endian_pod!(
     DyldCacheSlideInfo5,
     DyldSubCacheEntryV1,
     DyldSubCacheEntryV2,
+    DyldChainedFixupsHeader,
+    DyldChainedStartsInImage,
+    DyldChainedStartsInSegment,
     MachHeader32,
     MachHeader64,
     LoadCommand,
@@ -2139,6 +2139,103 @@ pub const RT_HTML: u16 = 23;
 /// ID for: Side-by-Side Assembly Manifest.
 pub const RT_MANIFEST: u16 = 24;
 
+/// Resource ID of the manifest that is used when creating a process.
+pub const CREATEPROCESS_MANIFEST_RESOURCE_ID: u16 = 1;
+/// Resource ID of the manifest that is used by DLLs.
+pub const ISOLATIONAWARE_MANIFEST_RESOURCE_ID: u16 = 2;
+/// Resource ID of the manifest that is used by DLLs that do not activate their own context.
+pub const ISOLATIONAWARE_NOSTATICIMPORT_MANIFEST_RESOURCE_ID: u16 = 3;
+
+//
+// Version information resource
+//
+// The `RT_VERSION` resource contains a `VS_VERSIONINFO` block. Each block in the
+// tree has a header of `wLength`, `wValueLength` and `wType`, followed by a
+// NUL terminated UTF-16 key, the value and the child blocks, each aligned to 4 bytes.
+//
+
+/// Fixed file information in the `VS_VERSIONINFO` block of a version resource.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct VsFixedFileInfo {
+    /// `VS_FFI_SIGNATURE`
+    pub signature: U32<LE>,
+    pub struc_version: U32<LE>,
+    pub file_version_ms: U32<LE>,
+    pub file_version_ls: U32<LE>,
+    pub product_version_ms: U32<LE>,
+    pub product_version_ls: U32<LE>,
+    pub file_flags_mask: U32<LE>,
+    /// Combination of `VS_FF_*` flags.
+    pub file_flags: U32<LE>,
+    /// Combination of `VOS_*` values.
+    pub file_os: U32<LE>,
+    /// One of the `VFT_*` values.
+    pub file_type: U32<LE>,
+    /// One of the `VFT2_*` values.
+    pub file_subtype: U32<LE>,
+    pub file_date_ms: U32<LE>,
+    pub file_date_ls: U32<LE>,
+}
+
+pub const VS_FFI_SIGNATURE: u32 = 0xFEEF_04BD;
+pub const VS_FFI_STRUCVERSION: u32 = 0x0001_0000;
+pub const VS_FFI_FILEFLAGSMASK: u32 = 0x0000_003F;
+
+// Values for `VsFixedFileInfo::file_flags`.
+pub const VS_FF_DEBUG: u32 = 0x0000_0001;
+pub const VS_FF_PRERELEASE: u32 = 0x0000_0002;
+pub const VS_FF_PATCHED: u32 = 0x0000_0004;
+pub const VS_FF_PRIVATEBUILD: u32 = 0x0000_0008;
+pub const VS_FF_INFOINFERRED: u32 = 0x0000_0010;
+pub const VS_FF_SPECIALBUILD: u32 = 0x0000_0020;
+
+// Values for `VsFixedFileInfo::file_os`.
+pub const VOS_UNKNOWN: u32 = 0x0000_0000;
+pub const VOS_DOS: u32 = 0x0001_0000;
+pub const VOS_OS216: u32 = 0x0002_0000;
+pub const VOS_OS232: u32 = 0x0003_0000;
+pub const VOS_NT: u32 = 0x0004_0000;
+pub const VOS_WINCE: u32 = 0x0005_0000;
+pub const VOS__BASE: u32 = 0x0000_0000;
+pub const VOS__WINDOWS16: u32 = 0x0000_0001;
+pub const VOS__PM16: u32 = 0x0000_0002;
+pub const VOS__PM32: u32 = 0x0000_0003;
+pub const VOS__WINDOWS32: u32 = 0x0000_0004;
+pub const VOS_DOS_WINDOWS16: u32 = 0x0001_0001;
+pub const VOS_DOS_WINDOWS32: u32 = 0x0001_0004;
+pub const VOS_OS216_PM16: u32 = 0x0002_0002;
+pub const VOS_OS232_PM32: u32 = 0x0003_0003;
+pub const VOS_NT_WINDOWS32: u32 = 0x0004_0004;
+
+// Values for `VsFixedFileInfo::file_type`.
+pub const VFT_UNKNOWN: u32 = 0x0000_0000;
+pub const VFT_APP: u32 = 0x0000_0001;
+pub const VFT_DLL: u32 = 0x0000_0002;
+pub const VFT_DRV: u32 = 0x0000_0003;
+pub const VFT_FONT: u32 = 0x0000_0004;
+pub const VFT_VXD: u32 = 0x0000_0005;
+pub const VFT_STATIC_LIB: u32 = 0x0000_0007;
+
+// Values for `VsFixedFileInfo::file_subtype` when `file_type` is `VFT_DRV`.
+pub const VFT2_UNKNOWN: u32 = 0x0000_0000;
+pub const VFT2_DRV_PRINTER: u32 = 0x0000_0001;
+pub const VFT2_DRV_KEYBOARD: u32 = 0x0000_0002;
+pub const VFT2_DRV_LANGUAGE: u32 = 0x0000_0003;
+pub const VFT2_DRV_DISPLAY: u32 = 0x0000_0004;
+pub const VFT2_DRV_MOUSE: u32 = 0x0000_0005;
+pub const VFT2_DRV_NETWORK: u32 = 0x0000_0006;
+pub const VFT2_DRV_SYSTEM: u32 = 0x0000_0007;
+pub const VFT2_DRV_INSTALLABLE: u32 = 0x0000_0008;
+pub const VFT2_DRV_SOUND: u32 = 0x0000_0009;
+pub const VFT2_DRV_COMM: u32 = 0x0000_000A;
+pub const VFT2_DRV_VERSIONED_PRINTER: u32 = 0x0000_000C;
+
+// Values for `VsFixedFileInfo::file_subtype` when `file_type` is `VFT_FONT`.
+pub const VFT2_FONT_RASTER: u32 = 0x0000_0001;
+pub const VFT2_FONT_VECTOR: u32 = 0x0000_0002;
+pub const VFT2_FONT_TRUETYPE: u32 = 0x0000_0003;
+
 //
 // Code Integrity in loadconfig (CI)
 //
@@ -2336,6 +2433,19 @@ pub struct ImageLoadConfigDirectory32 {
     pub enclave_configuration_pointer: U32<LE>,
     /// VA
     pub volatile_metadata_pointer: U32<LE>,
+    /// VA
+    pub guard_eh_continuation_table: U32<LE>,
+    pub guard_eh_continuation_count: U32<LE>,
+    /// VA
+    pub guard_xfg_check_function_pointer: U32<LE>,
+    /// VA
+    pub guard_xfg_dispatch_function_pointer: U32<LE>,
+    /// VA
+    pub guard_xfg_table_dispatch_function_pointer: U32<LE>,
+    /// VA
+    pub cast_guard_os_determined_failure_mode: U32<LE>,
+    /// VA
+    pub guard_memcpy_function_pointer: U32<LE>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -2399,6 +2509,19 @@ pub struct ImageLoadConfigDirectory64 {
     pub enclave_configuration_pointer: U64<LE>,
     /// VA
     pub volatile_metadata_pointer: U64<LE>,
+    /// VA
+    pub guard_eh_continuation_table: U64<LE>,
+    pub guard_eh_continuation_count: U64<LE>,
+    /// VA
+    pub guard_xfg_check_function_pointer: U64<LE>,
+    /// VA
+    pub guard_xfg_dispatch_function_pointer: U64<LE>,
+    /// VA
+    pub guard_xfg_table_dispatch_function_pointer: U64<LE>,
+    /// VA
+    pub cast_guard_os_determined_failure_mode: U64<LE>,
+    /// VA
+    pub guard_memcpy_function_pointer: U64<LE>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -2484,6 +2607,14 @@ pub const IMAGE_GUARD_RF_ENABLE: u32 = 0x0004_0000;
 pub const IMAGE_GUARD_RF_STRICT: u32 = 0x0008_0000;
 /// Module was built with retpoline support
 pub const IMAGE_GUARD_RETPOLINE_PRESENT: u32 = 0x0010_0000;
+/// Module contains EH continuation target information
+pub const IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT: u32 = 0x0040_0000;
+/// Module was built with xfg
+pub const IMAGE_GUARD_XFG_ENABLED: u32 = 0x0080_0000;
+/// Module has CastGuard instrumentation present
+pub const IMAGE_GUARD_CASTGUARD_PRESENT: u32 = 0x0100_0000;
+/// Module has Guarded Memcpy instrumentation present
+pub const IMAGE_GUARD_MEMCPY_PRESENT: u32 = 0x0200_0000;
 
 /// Stride of Guard CF function table encoded in these bits (additional count of bytes per element)
 pub const IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK: u32 = 0xF000_0000;
@@ -2498,6 +2629,10 @@ pub const IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT: u32 = 28;
 pub const IMAGE_GUARD_FLAG_FID_SUPPRESSED: u16 = 0x01;
 /// The containing GFID entry is export suppressed
 pub const IMAGE_GUARD_FLAG_EXPORT_SUPPRESSED: u16 = 0x02;
+/// The containing GFID entry is a language exception handler
+pub const IMAGE_GUARD_FLAG_FID_LANGEXCPTHANDLER: u16 = 0x04;
+/// The containing GFID entry is a target of an indirect call that is checked by XFG
+pub const IMAGE_GUARD_FLAG_FID_XFG: u16 = 0x08;
 
 //
 // WIN CE Exception table format
@@ -2563,6 +2698,79 @@ pub struct ImageRuntimeFunctionEntry {
     pub unwind_info_address_or_data: U32<LE>,
 }
 
+// Values for the low 2 bits of `ImageArm64RuntimeFunctionEntry::unwind_data`.
+/// `unwind_data` is the address of the `.xdata` record.
+pub const ARM64_PDATA_REF_TO_FULL_XDATA: u32 = 0;
+/// `unwind_data` contains packed unwind data for a function with a single prolog and epilog.
+pub const ARM64_PDATA_PACKED_UNWIND_FUNCTION: u32 = 1;
+/// `unwind_data` contains packed unwind data for a function fragment without a prolog.
+pub const ARM64_PDATA_PACKED_UNWIND_FRAGMENT: u32 = 2;
+
+//
+// x64 unwind information, referenced by `ImageRuntimeFunctionEntry::unwind_info_address_or_data`.
+//
+
+/// The header of x64 unwind information.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct UnwindInfo {
+    /// The version in the low 3 bits, and the `UNW_FLAG_*` flags in the high 5 bits.
+    pub version_flags: u8,
+    /// The length of the function prolog in bytes.
+    pub size_of_prolog: u8,
+    /// The number of `UnwindCode` slots that follow the header.
+    pub count_of_codes: u8,
+    /// The frame register in the low 4 bits, and the scaled frame offset in the high 4 bits.
+    pub frame_register_offset: u8,
+    // Followed by the unwind codes.
+}
+
+/// A slot in the array of x64 unwind codes.
+///
+/// Some operations use additional slots to store their operand.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct UnwindCode {
+    /// The offset of the end of the prolog instruction that this code describes.
+    pub code_offset: u8,
+    /// The `UWOP_*` operation in the low 4 bits, and the operation info in the high 4 bits.
+    pub unwind_op_info: u8,
+}
+
+// Values for the flags in `UnwindInfo::version_flags`.
+/// The function has no handler.
+pub const UNW_FLAG_NHANDLER: u8 = 0x0;
+/// The function has an exception handler.
+pub const UNW_FLAG_EHANDLER: u8 = 0x1;
+/// The function has a termination handler.
+pub const UNW_FLAG_UHANDLER: u8 = 0x2;
+/// The unwind information is chained to a previous `ImageRuntimeFunctionEntry`.
+pub const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+// Values for the operation in `UnwindCode::unwind_op_info`.
+/// Push a nonvolatile integer register.
+pub const UWOP_PUSH_NONVOL: u8 = 0;
+/// Allocate a large area on the stack.
+pub const UWOP_ALLOC_LARGE: u8 = 1;
+/// Allocate a small area on the stack.
+pub const UWOP_ALLOC_SMALL: u8 = 2;
+/// Establish the frame pointer register.
+pub const UWOP_SET_FPREG: u8 = 3;
+/// Save a nonvolatile integer register using a move.
+pub const UWOP_SAVE_NONVOL: u8 = 4;
+/// Save a nonvolatile integer register using a move with a long offset.
+pub const UWOP_SAVE_NONVOL_FAR: u8 = 5;
+/// Describes an epilog. Only valid for version 2.
+pub const UWOP_EPILOG: u8 = 6;
+/// Reserved.
+pub const UWOP_SPARE_CODE: u8 = 7;
+/// Save all 128 bits of a nonvolatile XMM register.
+pub const UWOP_SAVE_XMM128: u8 = 8;
+/// Save all 128 bits of a nonvolatile XMM register with a long offset.
+pub const UWOP_SAVE_XMM128_FAR: u8 = 9;
+/// Push a machine frame.
+pub const UWOP_PUSH_MACHFRAME: u8 = 10;
+
 //
 // Software enclave information
 //
@@ -2630,6 +2838,38 @@ pub const IMAGE_ENCLAVE_IMPORT_MATCH_AUTHOR_ID: u32 = 0x0000_0002;
 pub const IMAGE_ENCLAVE_IMPORT_MATCH_FAMILY_ID: u32 = 0x0000_0003;
 pub const IMAGE_ENCLAVE_IMPORT_MATCH_IMAGE_ID: u32 = 0x0000_0004;
 
+//
+// Attribute certificate table
+//
+// The `IMAGE_DIRECTORY_ENTRY_SECURITY` data directory gives the file offset
+// and size of the table, which is a sequence of `WinCertificate` entries,
+// each followed by its certificate data and aligned to 8 bytes.
+//
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct WinCertificate {
+    /// The length of the entry, including this header, but excluding the alignment padding.
+    pub length: U32<LE>,
+    /// One of the `WIN_CERT_REVISION_*` values.
+    pub revision: U16<LE>,
+    /// One of the `WIN_CERT_TYPE_*` values.
+    pub certificate_type: U16<LE>,
+    //pub certificate: [u8; 1],
+}
+
+pub const WIN_CERT_REVISION_1_0: u16 = 0x0100;
+pub const WIN_CERT_REVISION_2_0: u16 = 0x0200;
+
+/// `bCertificate` contains an X.509 certificate.
+pub const WIN_CERT_TYPE_X509: u16 = 0x0001;
+/// `bCertificate` contains a PKCS#7 `SignedData` structure.
+pub const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+/// Reserved.
+pub const WIN_CERT_TYPE_RESERVED_1: u16 = 0x0003;
+/// Terminal Server Protocol Stack Certificate signing.
+pub const WIN_CERT_TYPE_TS_STACK_SIGNED: u16 = 0x0004;
+
 //
 // Debug Format
 //
@@ -2664,6 +2904,63 @@ pub const IMAGE_DEBUG_TYPE_POGO: u32 = 13;
 pub const IMAGE_DEBUG_TYPE_ILTCG: u32 = 14;
 pub const IMAGE_DEBUG_TYPE_MPX: u32 = 15;
 pub const IMAGE_DEBUG_TYPE_REPRO: u32 = 16;
+/// Embedded portable PDB data.
+pub const IMAGE_DEBUG_TYPE_EMBEDDED_PORTABLE_PDB: u32 = 17;
+/// Sample profile guided optimization data.
+pub const IMAGE_DEBUG_TYPE_SPGO: u32 = 18;
+/// Checksum of the PDB.
+pub const IMAGE_DEBUG_TYPE_PDBCHECKSUM: u32 = 19;
+/// Extended DLL characteristics bits.
+pub const IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS: u32 = 20;
+
+// Extended DLL characteristics, stored in an `IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS` entry.
+/// Image is CET compatible.
+pub const IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT: u32 = 0x01;
+/// Image is CET compatible in strict mode.
+pub const IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT_STRICT_MODE: u32 = 0x02;
+/// Relaxed mode for CET context IP validation.
+pub const IMAGE_DLLCHARACTERISTICS_EX_CET_SET_CONTEXT_IP_VALIDATION_RELAXED_MODE: u32 = 0x04;
+/// Use of dynamic APIs is restricted to processes that are not CET compatible.
+pub const IMAGE_DLLCHARACTERISTICS_EX_CET_DYNAMIC_APIS_ALLOW_IN_PROC: u32 = 0x08;
+/// Reserved for CET.
+pub const IMAGE_DLLCHARACTERISTICS_EX_CET_RESERVED_1: u32 = 0x10;
+/// Reserved for CET.
+pub const IMAGE_DLLCHARACTERISTICS_EX_CET_RESERVED_2: u32 = 0x20;
+/// Image is compatible with forward control flow integrity.
+pub const IMAGE_DLLCHARACTERISTICS_EX_FORWARD_CFI_COMPAT: u32 = 0x40;
+/// Image is hot patch compatible.
+pub const IMAGE_DLLCHARACTERISTICS_EX_HOTPATCH_COMPATIBLE: u32 = 0x80;
+
+/// The signature of CodeView data that contains a PDB 7.0 GUID and path.
+pub const CODEVIEW_SIGNATURE_RSDS: [u8; 4] = *b"RSDS";
+
+/// The signature of `IMAGE_DEBUG_TYPE_POGO` data for link time code generation.
+pub const IMAGE_DEBUG_POGO_SIGNATURE_LTCG: u32 = 0x4c54_4347;
+/// The signature of `IMAGE_DEBUG_TYPE_POGO` data for profile guided optimization.
+pub const IMAGE_DEBUG_POGO_SIGNATURE_PGU: u32 = 0x5047_5500;
+/// The signature of `IMAGE_DEBUG_TYPE_POGO` data for profile guided instrumentation.
+pub const IMAGE_DEBUG_POGO_SIGNATURE_PGI: u32 = 0x5047_4900;
+
+/// The signature of `IMAGE_DEBUG_TYPE_EMBEDDED_PORTABLE_PDB` data.
+pub const IMAGE_DEBUG_EMBEDDED_PORTABLE_PDB_SIGNATURE: [u8; 4] = *b"MPDB";
+
+/// The data for an `IMAGE_DEBUG_TYPE_VC_FEATURE` entry.
+///
+/// Each field is the number of object files that were compiled with the feature.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ImageDebugVcFeature {
+    /// Object files compiled by a compiler older than VC++ 11.0.
+    pub pre_vc11: U32<LE>,
+    /// Object files compiled as C or C++.
+    pub c_cpp: U32<LE>,
+    /// Object files compiled with `/GS`.
+    pub gs: U32<LE>,
+    /// Object files compiled with `/sdl`.
+    pub sdl: U32<LE>,
+    /// Object files compiled with `/guard:N`.
+    pub guard_n: U32<LE>,
+}
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -3017,6 +3314,7 @@ unsafe_impl_pod!(
     ImageResourceDirectoryString,
     ImageResourceDirStringU,
     ImageResourceDataEntry,
+    VsFixedFileInfo,
     ImageLoadConfigCodeIntegrity,
     ImageDynamicRelocationTable,
     ImageDynamicRelocation32,
@@ -3039,10 +3337,14 @@ unsafe_impl_pod!(
     ImageAlpha64RuntimeFunctionEntry,
     ImageAlphaRuntimeFunctionEntry,
     ImageRuntimeFunctionEntry,
+    UnwindInfo,
+    UnwindCode,
     ImageEnclaveConfig32,
     ImageEnclaveConfig64,
     ImageEnclaveImport,
+    WinCertificate,
     ImageDebugDirectory,
+    ImageDebugVcFeature,
     ImageCoffSymbolsHeader,
     //FpoData,
     ImageDebugMisc,
@@ -450,22 +450,32 @@ where
         .map(|inner| SymbolTable { inner })
     }
 
-    #[cfg(feature = "elf")]
+    #[cfg(any(feature = "elf", feature = "macho"))]
     fn dynamic_relocations(&self) -> Option<DynamicRelocationIterator<'data, '_, R>> {
         let inner = match self {
+            #[cfg(feature = "elf")]
             File::Elf32(ref elf) => {
                 DynamicRelocationIteratorInternal::Elf32(elf.dynamic_relocations()?)
             }
+            #[cfg(feature = "elf")]
             File::Elf64(ref elf) => {
                 DynamicRelocationIteratorInternal::Elf64(elf.dynamic_relocations()?)
             }
+            #[cfg(feature = "macho")]
+            File::MachO32(ref macho) => {
+                DynamicRelocationIteratorInternal::MachO32(macho.dynamic_relocations()?)
+            }
+            #[cfg(feature = "macho")]
+            File::MachO64(ref macho) => {
+                DynamicRelocationIteratorInternal::MachO64(macho.dynamic_relocations()?)
+            }
             #[allow(unreachable_patterns)]
             _ => return None,
         };
         Some(DynamicRelocationIterator { inner })
     }
 
-    #[cfg(not(feature = "elf"))]
+    #[cfg(not(any(feature = "elf", feature = "macho")))]
     fn dynamic_relocations(&self) -> Option<DynamicRelocationIterator<'data, '_, R>> {
         None
     }
@@ -1320,6 +1330,10 @@ where
     Elf32(elf::ElfDynamicRelocationIterator32<'data, 'file, Endianness, R>),
     #[cfg(feature = "elf")]
     Elf64(elf::ElfDynamicRelocationIterator64<'data, 'file, Endianness, R>),
+    #[cfg(feature = "macho")]
+    MachO32(macho::MachODynamicRelocationIterator32<'data, 'file, Endianness, R>),
+    #[cfg(feature = "macho")]
+    MachO64(macho::MachODynamicRelocationIterator64<'data, 'file, Endianness, R>),
     // We need to always use the lifetime parameters.
     #[allow(unused)]
     None(PhantomData<(&'data (), &'file (), R)>),
@@ -1334,6 +1348,10 @@ impl<'data, 'file, R: ReadRef<'data>> Iterator for DynamicRelocationIterator<'da
             DynamicRelocationIteratorInternal::Elf32(ref mut elf) => elf.next(),
             #[cfg(feature = "elf")]
             DynamicRelocationIteratorInternal::Elf64(ref mut elf) => elf.next(),
+            #[cfg(feature = "macho")]
+            DynamicRelocationIteratorInternal::MachO32(ref mut macho) => macho.next(),
+            #[cfg(feature = "macho")]
+            DynamicRelocationIteratorInternal::MachO64(ref mut macho) => macho.next(),
             DynamicRelocationIteratorInternal::None(_) => None,
         }
     }
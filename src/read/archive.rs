@@ -617,6 +617,8 @@ enum SymbolIteratorInternal<'data> {
     },
     /// A GNU 64-bit symbol table
     ///
+    /// This is also used for the AIX big archive symbol tables.
+    ///
     /// Contains:
     /// - the number of symbols as a 64-bit big-endian integer
     /// - the offsets of the member headers as 64-bit big-endian integers
@@ -682,7 +684,7 @@ impl<'data> ArchiveSymbolIterator<'data> {
                     names: data,
                 }))
             }
-            ArchiveKind::Gnu64 => {
+            ArchiveKind::Gnu64 | ArchiveKind::AixBig => {
                 let offsets_count = data.read::<U64<BE>>()?.get(BE);
                 let offsets = data.read_slice::<U64<BE>>(offsets_count as usize)?;
                 Ok(ArchiveSymbolIterator(SymbolIteratorInternal::Gnu64 {
@@ -721,8 +723,6 @@ impl<'data> ArchiveSymbolIterator<'data> {
                     names: data,
                 }))
             }
-            // TODO: Implement AIX big archive symbol table.
-            ArchiveKind::AixBig => Ok(ArchiveSymbolIterator(SymbolIteratorInternal::None)),
         }
     }
 }
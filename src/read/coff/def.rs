@@ -0,0 +1,532 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::read::{Error, Result};
+
+/// A module-definition (`.def`) file.
+///
+/// This is the subset of the module-definition file format that is relevant
+/// to DLL exports: the `NAME` or `LIBRARY` statement, the `EXPORTS` statement,
+/// and the `HEAPSIZE`, `STACKSIZE` and `VERSION` statements.
+///
+/// Use [`ModuleDefinition::parse`] to parse a `.def` file and
+/// [`ModuleDefinition::write`] to emit one.
+/// With the `write_core` feature, the exports can also be used to generate an
+/// import library or the linker directives for a COFF object.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ModuleDefinition {
+    /// The name of the image from the `NAME` or `LIBRARY` statement.
+    pub name: Option<Vec<u8>>,
+    /// True if the image is a DLL.
+    ///
+    /// This is true if the file used a `LIBRARY` statement instead of a `NAME` statement.
+    pub is_dll: bool,
+    /// The image base from the `BASE=` argument of the `NAME` or `LIBRARY` statement.
+    pub base: Option<u64>,
+    /// The reserve and commit sizes from the `HEAPSIZE` statement.
+    pub heap_size: Option<(u64, Option<u64>)>,
+    /// The reserve and commit sizes from the `STACKSIZE` statement.
+    pub stack_size: Option<(u64, Option<u64>)>,
+    /// The major and minor image version from the `VERSION` statement.
+    pub version: Option<(u32, u32)>,
+    /// The exports from the `EXPORTS` statements.
+    pub exports: Vec<DefExport>,
+}
+
+/// An export in a [`ModuleDefinition`].
+///
+/// This corresponds to a line in the `EXPORTS` statement:
+/// `name[=internal_name] [@ordinal [NONAME]] [DATA] [CONSTANT] [PRIVATE]`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DefExport {
+    /// The name that is exported from the image.
+    pub name: Vec<u8>,
+    /// The name of the symbol in the image that is exported.
+    ///
+    /// This is empty if it is the same as `name`.
+    /// This is a forwarder if it has the form `dll.name` or `dll.#ordinal`.
+    pub internal_name: Vec<u8>,
+    /// The ordinal of the export.
+    pub ordinal: Option<u16>,
+    /// The export is only exported by ordinal.
+    pub noname: bool,
+    /// The export is data instead of code.
+    pub data: bool,
+    /// The export is a constant.
+    ///
+    /// This is deprecated, but is supported by import libraries.
+    pub constant: bool,
+    /// The export is omitted from the import library.
+    pub private: bool,
+}
+
+impl DefExport {
+    /// Create an export with the given name.
+    pub fn new(name: Vec<u8>) -> Self {
+        DefExport {
+            name,
+            ..Default::default()
+        }
+    }
+
+    /// Return the DLL and the name or ordinal if this export is a forwarder.
+    ///
+    /// An ordinal is returned as a name with a `#` prefix.
+    pub fn forward(&self) -> Option<(&[u8], &[u8])> {
+        // The DLL name may itself contain dots, so split at the last one.
+        let index = self.internal_name.iter().rposition(|&c| c == b'.')?;
+        Some((
+            &self.internal_name[..index],
+            &self.internal_name[index + 1..],
+        ))
+    }
+}
+
+impl ModuleDefinition {
+    /// Parse a module-definition file.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let tokens = tokenize(data)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            index: 0,
+        };
+        let mut def = ModuleDefinition::default();
+        while let Some(token) = parser.next() {
+            match token.keyword() {
+                Some(b"NAME") | Some(b"LIBRARY") => {
+                    def.is_dll = token.keyword() == Some(&b"LIBRARY"[..]);
+                    if let Some(name) = parser.next_identifier() {
+                        def.name = Some(name.to_vec());
+                    }
+                    if parser.peek().and_then(|token| token.keyword()) == Some(&b"BASE"[..]) {
+                        parser.next();
+                        parser.expect(TokenKind::Equal)?;
+                        def.base = Some(parser.number()?);
+                    }
+                }
+                Some(b"EXPORTS") => {
+                    while let Some(name) = parser.next_identifier() {
+                        def.exports.push(parser.export(name)?);
+                    }
+                }
+                Some(b"HEAPSIZE") => def.heap_size = Some(parser.sizes()?),
+                Some(b"STACKSIZE") => def.stack_size = Some(parser.sizes()?),
+                Some(b"VERSION") => {
+                    let version = parser.identifier()?;
+                    let mut parts = version.splitn(2, |&c| c == b'.');
+                    let major = parts.next().and_then(parse_number);
+                    let minor = match parts.next() {
+                        Some(minor) => parse_number(minor),
+                        None => Some(0),
+                    };
+                    match (major, minor) {
+                        (Some(major), Some(minor))
+                            if major <= u32::MAX.into() && minor <= u32::MAX.into() =>
+                        {
+                            def.version = Some((major as u32, minor as u32));
+                        }
+                        _ => return Err(Error("Invalid module-definition version")),
+                    }
+                }
+                Some(b"DESCRIPTION") => {
+                    parser.identifier()?;
+                }
+                Some(b"SECTIONS") | Some(b"SEGMENTS") => {
+                    // Section attributes are not relevant to exports.
+                    while parser.peek().map_or(false, |token| {
+                        !token.keyword().map_or(false, is_statement_keyword)
+                    }) {
+                        parser.next();
+                    }
+                }
+                _ => return Err(Error("Invalid module-definition statement")),
+            }
+        }
+        Ok(def)
+    }
+
+    /// Write the module-definition file.
+    pub fn write(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if self.name.is_some() || self.base.is_some() || self.is_dll {
+            if self.is_dll {
+                out.extend_from_slice(b"LIBRARY");
+            } else {
+                out.extend_from_slice(b"NAME");
+            }
+            if let Some(name) = &self.name {
+                out.push(b' ');
+                write_identifier(&mut out, name);
+            }
+            if let Some(base) = self.base {
+                out.extend_from_slice(format!(" BASE={}", base).as_bytes());
+            }
+            out.push(b'\n');
+        }
+        for (keyword, size) in [("HEAPSIZE", self.heap_size), ("STACKSIZE", self.stack_size)] {
+            if let Some((reserve, commit)) = size {
+                out.extend_from_slice(format!("{} {}", keyword, reserve).as_bytes());
+                if let Some(commit) = commit {
+                    out.extend_from_slice(format!(",{}", commit).as_bytes());
+                }
+                out.push(b'\n');
+            }
+        }
+        if let Some((major, minor)) = self.version {
+            out.extend_from_slice(format!("VERSION {}.{}\n", major, minor).as_bytes());
+        }
+        if !self.exports.is_empty() {
+            out.extend_from_slice(b"EXPORTS\n");
+        }
+        for export in &self.exports {
+            out.extend_from_slice(b"    ");
+            write_identifier(&mut out, &export.name);
+            if !export.internal_name.is_empty() {
+                out.push(b'=');
+                write_identifier(&mut out, &export.internal_name);
+            }
+            if let Some(ordinal) = export.ordinal {
+                out.extend_from_slice(format!(" @{}", ordinal).as_bytes());
+                if export.noname {
+                    out.extend_from_slice(b" NONAME");
+                }
+            }
+            if export.data {
+                out.extend_from_slice(b" DATA");
+            }
+            if export.constant {
+                out.extend_from_slice(b" CONSTANT");
+            }
+            if export.private {
+                out.extend_from_slice(b" PRIVATE");
+            }
+            out.push(b'\n');
+        }
+        out
+    }
+
+    /// Create a module definition from the export table of a PE file.
+    ///
+    /// Exports that are only exported by ordinal are given the name `ord<ordinal>`
+    /// and marked as `NONAME`. Exports whose address is not in an executable
+    /// section are marked as `DATA`.
+    #[cfg(feature = "pe")]
+    pub fn from_pe_file<'data, Pe, R>(file: &crate::read::pe::PeFile<'data, Pe, R>) -> Result<Self>
+    where
+        Pe: crate::read::pe::ImageNtHeaders,
+        R: crate::read::ReadRef<'data>,
+    {
+        use crate::pe;
+        use crate::read::pe::ExportTarget;
+        use crate::read::ReadError;
+        use crate::LittleEndian as LE;
+
+        let mut def = ModuleDefinition {
+            is_dll: file.nt_headers().file_header().characteristics.get(LE) & pe::IMAGE_FILE_DLL
+                != 0,
+            ..Default::default()
+        };
+        let export_table = match file.export_table()? {
+            Some(export_table) => export_table,
+            None => return Ok(def),
+        };
+        let name = export_table.name_from_pointer(export_table.directory().name.get(LE))?;
+        if !name.is_empty() {
+            def.name = Some(name.to_vec());
+        }
+        let sections = file.section_table();
+        for export in export_table.exports()? {
+            let ordinal = u16::try_from(export.ordinal)
+                .ok()
+                .read_error("Invalid PE export ordinal")?;
+            let mut def_export = DefExport::new(match export.name {
+                Some(name) => name.to_vec(),
+                None => format!("ord{}", ordinal).into_bytes(),
+            });
+            def_export.ordinal = Some(ordinal);
+            def_export.noname = export.name.is_none();
+            match export.target {
+                ExportTarget::Address(0) if export.name.is_none() => continue,
+                ExportTarget::Address(address) => {
+                    def_export.data =
+                        sections
+                            .section_containing(address)
+                            .map_or(true, |section| {
+                                section.characteristics.get(LE)
+                                    & (pe::IMAGE_SCN_CNT_CODE | pe::IMAGE_SCN_MEM_EXECUTE)
+                                    == 0
+                            });
+                }
+                ExportTarget::ForwardByName(dll, name) => {
+                    def_export.internal_name = [dll, b".", name].concat();
+                }
+                ExportTarget::ForwardByOrdinal(dll, ordinal) => {
+                    def_export.internal_name = [dll, format!(".#{}", ordinal).as_bytes()].concat();
+                }
+            }
+            def.exports.push(def_export);
+        }
+        Ok(def)
+    }
+
+    /// Return the name of the image, including its extension.
+    ///
+    /// If the name has no extension, then `.dll` or `.exe` is appended.
+    pub fn image_name(&self) -> Option<Vec<u8>> {
+        let name = self.name.as_ref()?;
+        let mut name = name.clone();
+        if !name.contains(&b'.') {
+            if self.is_dll {
+                name.extend_from_slice(b".dll");
+            } else {
+                name.extend_from_slice(b".exe");
+            }
+        }
+        Some(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Identifier,
+    QuotedIdentifier,
+    Equal,
+    EqualEqual,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: TokenKind,
+    value: &'a [u8],
+}
+
+impl<'a> Token<'a> {
+    /// Return the value if this is an unquoted keyword.
+    fn keyword(&self) -> Option<&'a [u8]> {
+        if self.kind == TokenKind::Identifier && is_keyword(self.value) {
+            Some(self.value)
+        } else {
+            None
+        }
+    }
+}
+
+fn is_statement_keyword(keyword: &[u8]) -> bool {
+    matches!(
+        keyword,
+        b"NAME"
+            | b"LIBRARY"
+            | b"EXPORTS"
+            | b"HEAPSIZE"
+            | b"STACKSIZE"
+            | b"VERSION"
+            | b"DESCRIPTION"
+            | b"SECTIONS"
+            | b"SEGMENTS"
+    )
+}
+
+fn is_keyword(value: &[u8]) -> bool {
+    is_statement_keyword(value)
+        || matches!(
+            value,
+            b"BASE" | b"NONAME" | b"DATA" | b"CONSTANT" | b"PRIVATE"
+        )
+}
+
+fn tokenize(data: &[u8]) -> Result<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while let Some(&c) = data.get(i) {
+        let start = i;
+        let kind = match c {
+            b' ' | b'\t' | b'\r' | b'\n' => {
+                i += 1;
+                continue;
+            }
+            b';' => {
+                while data.get(i).map_or(false, |&c| c != b'\n') {
+                    i += 1;
+                }
+                continue;
+            }
+            b'=' => {
+                if data.get(i + 1) == Some(&b'=') {
+                    i += 2;
+                    TokenKind::EqualEqual
+                } else {
+                    i += 1;
+                    TokenKind::Equal
+                }
+            }
+            b',' => {
+                i += 1;
+                TokenKind::Comma
+            }
+            b'"' => {
+                let len = data[i + 1..]
+                    .iter()
+                    .position(|&c| c == b'"')
+                    .ok_or(Error("Unterminated module-definition quoted string"))?;
+                tokens.push(Token {
+                    kind: TokenKind::QuotedIdentifier,
+                    value: &data[i + 1..i + 1 + len],
+                });
+                i += len + 2;
+                continue;
+            }
+            _ => {
+                while data.get(i).map_or(false, |&c| {
+                    !matches!(c, b' ' | b'\t' | b'\r' | b'\n' | b';' | b'=' | b',' | b'"')
+                }) {
+                    i += 1;
+                }
+                TokenKind::Identifier
+            }
+        };
+        tokens.push(Token {
+            kind,
+            value: &data[start..i],
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a, 'data> {
+    tokens: &'a [Token<'data>],
+    index: usize,
+}
+
+impl<'a, 'data> Parser<'a, 'data> {
+    fn peek(&self) -> Option<Token<'data>> {
+        self.tokens.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Option<Token<'data>> {
+        let token = self.peek()?;
+        self.index += 1;
+        Some(token)
+    }
+
+    /// Consume the next token if it is an identifier that is not a keyword.
+    fn next_identifier(&mut self) -> Option<&'data [u8]> {
+        let token = self.peek()?;
+        match token.kind {
+            TokenKind::Identifier if !is_keyword(token.value) => {}
+            TokenKind::QuotedIdentifier => {}
+            _ => return None,
+        }
+        self.index += 1;
+        Some(token.value)
+    }
+
+    fn identifier(&mut self) -> Result<&'data [u8]> {
+        self.next_identifier()
+            .ok_or(Error("Missing module-definition identifier"))
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<()> {
+        match self.next() {
+            Some(token) if token.kind == kind => Ok(()),
+            _ => Err(Error("Unexpected module-definition token")),
+        }
+    }
+
+    fn number(&mut self) -> Result<u64> {
+        match self.next() {
+            Some(token) if token.kind == TokenKind::Identifier => {
+                parse_number(token.value).ok_or(Error("Invalid module-definition number"))
+            }
+            _ => Err(Error("Missing module-definition number")),
+        }
+    }
+
+    /// Parse `reserve[,commit]`.
+    fn sizes(&mut self) -> Result<(u64, Option<u64>)> {
+        let reserve = self.number()?;
+        let mut commit = None;
+        if self.peek().map(|token| token.kind) == Some(TokenKind::Comma) {
+            self.next();
+            commit = Some(self.number()?);
+        }
+        Ok((reserve, commit))
+    }
+
+    /// Parse the remainder of an export after its name.
+    fn export(&mut self, name: &'data [u8]) -> Result<DefExport> {
+        let mut export = DefExport::new(name.to_vec());
+        if let Some(token) = self.peek() {
+            match token.kind {
+                TokenKind::Equal => {
+                    self.next();
+                    export.internal_name = self.identifier()?.to_vec();
+                }
+                TokenKind::EqualEqual => {
+                    return Err(Error("Unsupported module-definition export name"));
+                }
+                _ => {}
+            }
+        }
+        while let Some(token) = self.peek() {
+            if token.kind == TokenKind::Identifier && token.value.first() == Some(&b'@') {
+                self.next();
+                let ordinal = if token.value.len() == 1 {
+                    self.number()?
+                } else {
+                    parse_number(&token.value[1..])
+                        .ok_or(Error("Invalid module-definition ordinal"))?
+                };
+                export.ordinal = Some(
+                    u16::try_from(ordinal)
+                        .ok()
+                        .filter(|&ordinal| ordinal != 0)
+                        .ok_or(Error("Invalid module-definition ordinal"))?,
+                );
+                continue;
+            }
+            let flag = match token.keyword() {
+                Some(b"NONAME") => &mut export.noname,
+                Some(b"DATA") => &mut export.data,
+                Some(b"CONSTANT") => &mut export.constant,
+                Some(b"PRIVATE") => &mut export.private,
+                _ => break,
+            };
+            *flag = true;
+            self.next();
+        }
+        if export.noname && export.ordinal.is_none() {
+            return Err(Error("Missing module-definition ordinal for NONAME export"));
+        }
+        Ok(export)
+    }
+}
+
+/// Parse a decimal, or hexadecimal with a `0x` prefix, number.
+fn parse_number(value: &[u8]) -> Option<u64> {
+    let value = core::str::from_utf8(value).ok()?;
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        value.parse().ok()
+    }
+}
+
+fn write_identifier(out: &mut Vec<u8>, name: &[u8]) {
+    let quote = name.is_empty()
+        || is_keyword(name)
+        || name.first() == Some(&b'@')
+        || name
+            .iter()
+            .any(|&c| matches!(c, b' ' | b'\t' | b'\r' | b'\n' | b';' | b'=' | b','));
+    if quote {
+        out.push(b'"');
+        out.extend_from_slice(name);
+        out.push(b'"');
+    } else {
+        out.extend_from_slice(name);
+    }
+}
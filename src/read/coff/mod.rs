@@ -13,6 +13,9 @@
 //! [`ImportFile`] allows reading COFF short imports that are used in import
 //! libraries. Currently these are not integrated with the unified read API.
 //!
+//! [`ModuleDefinition`] allows reading and writing module-definition (`.def`)
+//! files, which describe the exports of a DLL.
+//!
 //! ## Low level API
 //!
 //! The [`CoffHeader`] trait can be directly used to parse both COFF
@@ -64,3 +67,6 @@ pub use comdat::*;
 
 mod import;
 pub use import::*;
+
+mod def;
+pub use def::*;
@@ -4,7 +4,7 @@ use crate::{endian, CompressedFileRange, CompressionFormat, ReadRef, U32};
 // Attempt to parse the the CompressedFileRange for a section using the GNU-style
 // inline compression header format. This is used by the Go compiler in Mach-O files
 // as well as by the GNU linker in some ELF files.
-pub(super) fn compressed_file_range<'data, R: ReadRef<'data>>(
+pub(crate) fn compressed_file_range<'data, R: ReadRef<'data>>(
     file_data: R,
     section_offset: u64,
     section_size: u64,
@@ -0,0 +1,658 @@
+use core::mem;
+
+use crate::endian::{Endian, U16, U32, U64};
+use crate::macho;
+use crate::read::{Bytes, Error, ReadError, ReadRef, Result};
+
+use super::DyldRelocationAuth;
+
+/// The chained fixups in a `LC_DYLD_CHAINED_FIXUPS` load command.
+///
+/// Returned by [`macho::LinkeditDataCommand::chained_fixups`].
+#[derive(Debug, Clone, Copy)]
+pub struct ChainedFixups<'data, E: Endian> {
+    endian: E,
+    data: Bytes<'data>,
+    header: &'data macho::DyldChainedFixupsHeader<E>,
+}
+
+impl<'data, E: Endian> ChainedFixups<'data, E> {
+    /// Parse the chained fixups from the data of the load command.
+    pub fn parse(endian: E, data: &'data [u8]) -> Result<Self> {
+        let data = Bytes(data);
+        let header = data
+            .read_at::<macho::DyldChainedFixupsHeader<E>>(0)
+            .read_error("Invalid chained fixups header size or alignment")?;
+        if header.fixups_version.get(endian) != 0 {
+            return Err(Error("Unsupported chained fixups version"));
+        }
+        Ok(ChainedFixups {
+            endian,
+            data,
+            header,
+        })
+    }
+
+    /// Return the header.
+    pub fn header(&self) -> &'data macho::DyldChainedFixupsHeader<E> {
+        self.header
+    }
+
+    /// Return the table of imported symbols.
+    ///
+    /// Binds refer to entries in this table by index.
+    pub fn imports(&self) -> Result<ChainedImportTable<'data, E>> {
+        if self.header.symbols_format.get(self.endian) != macho::DYLD_CHAINED_SYMBOL_UNCOMPRESSED {
+            return Err(Error("Unsupported chained fixups symbol format"));
+        }
+        let format = self.header.imports_format.get(self.endian);
+        let entry_size = match format {
+            macho::DYLD_CHAINED_IMPORT => 4,
+            macho::DYLD_CHAINED_IMPORT_ADDEND => 8,
+            macho::DYLD_CHAINED_IMPORT_ADDEND64 => 16,
+            _ => return Err(Error("Unsupported chained fixups import format")),
+        };
+        let count = self.header.imports_count.get(self.endian) as usize;
+        let imports = self
+            .data
+            .read_bytes_at(
+                self.header.imports_offset.get(self.endian) as usize,
+                count
+                    .checked_mul(entry_size)
+                    .read_error("Invalid chained fixups import count")?,
+            )
+            .read_error("Invalid chained fixups imports offset or count")?;
+        let symbols = self
+            .data
+            .0
+            .get(self.header.symbols_offset.get(self.endian) as usize..)
+            .read_error("Invalid chained fixups symbols offset")?;
+        Ok(ChainedImportTable {
+            endian: self.endian,
+            format,
+            entry_size,
+            imports,
+            symbols: Bytes(symbols),
+        })
+    }
+
+    /// Return an iterator over the fixup chain starts for each segment.
+    ///
+    /// Segments without fixups are skipped.
+    pub fn segments(&self) -> Result<ChainedSegmentIterator<'data, E>> {
+        let offset = self.header.starts_offset.get(self.endian) as usize;
+        let starts = Bytes(
+            self.data
+                .0
+                .get(offset..)
+                .read_error("Invalid chained fixups starts offset")?,
+        );
+        let seg_count = starts
+            .read_at::<macho::DyldChainedStartsInImage<E>>(0)
+            .read_error("Invalid chained fixups starts offset")?
+            .seg_count
+            .get(self.endian);
+        let offsets = starts
+            .read_slice_at::<U32<E>>(
+                mem::size_of::<macho::DyldChainedStartsInImage<E>>(),
+                seg_count as usize,
+            )
+            .read_error("Invalid chained fixups segment count")?;
+        Ok(ChainedSegmentIterator {
+            endian: self.endian,
+            starts,
+            offsets: offsets.iter().enumerate(),
+        })
+    }
+}
+
+/// The table of imported symbols for chained fixups.
+///
+/// Returned by [`ChainedFixups::imports`].
+#[derive(Debug, Clone, Copy)]
+pub struct ChainedImportTable<'data, E: Endian> {
+    endian: E,
+    format: u32,
+    entry_size: usize,
+    imports: Bytes<'data>,
+    symbols: Bytes<'data>,
+}
+
+impl<'data, E: Endian> ChainedImportTable<'data, E> {
+    /// Return the number of imports.
+    pub fn len(&self) -> usize {
+        self.imports.len() / self.entry_size
+    }
+
+    /// Return true if there are no imports.
+    pub fn is_empty(&self) -> bool {
+        self.imports.is_empty()
+    }
+
+    /// Return the import at the given index.
+    pub fn get(&self, index: u32) -> Result<ChainedImport<'data>> {
+        let offset = (index as usize)
+            .checked_mul(self.entry_size)
+            .read_error("Invalid chained fixups import index")?;
+        let mut entry = self
+            .imports
+            .read_bytes_at(offset, self.entry_size)
+            .read_error("Invalid chained fixups import index")?;
+        let (lib_ordinal, weak_import, name_offset, addend) = match self.format {
+            macho::DYLD_CHAINED_IMPORT_ADDEND64 => {
+                let import = entry
+                    .read::<U64<E>>()
+                    .read_error("Invalid chained fixups import")?
+                    .get(self.endian);
+                let import = macho::DyldChainedImport64(import);
+                let addend = entry
+                    .read::<U64<E>>()
+                    .read_error("Invalid chained fixups import")?
+                    .get(self.endian);
+                // Ordinals greater than 0xfff0 are negative special ordinals.
+                let lib_ordinal = import.lib_ordinal();
+                let lib_ordinal = if lib_ordinal > 0xfff0 {
+                    i32::from(lib_ordinal as i16)
+                } else {
+                    i32::from(lib_ordinal)
+                };
+                (
+                    lib_ordinal,
+                    import.weak_import(),
+                    import.name_offset(),
+                    addend as i64,
+                )
+            }
+            _ => {
+                let import = entry
+                    .read::<U32<E>>()
+                    .read_error("Invalid chained fixups import")?
+                    .get(self.endian);
+                let import = macho::DyldChainedImport(import);
+                let addend = if self.format == macho::DYLD_CHAINED_IMPORT_ADDEND {
+                    let addend = entry
+                        .read::<U32<E>>()
+                        .read_error("Invalid chained fixups import")?
+                        .get(self.endian);
+                    i64::from(addend as i32)
+                } else {
+                    0
+                };
+                // Ordinals greater than 0xf0 are negative special ordinals.
+                let lib_ordinal = import.lib_ordinal();
+                let lib_ordinal = if lib_ordinal > 0xf0 {
+                    i32::from(lib_ordinal as i8)
+                } else {
+                    i32::from(lib_ordinal)
+                };
+                (
+                    lib_ordinal,
+                    import.weak_import(),
+                    import.name_offset(),
+                    addend,
+                )
+            }
+        };
+        let name = self
+            .symbols
+            .read_string_at(name_offset as usize)
+            .read_error("Invalid chained fixups import name offset")?;
+        Ok(ChainedImport {
+            lib_ordinal,
+            weak_import,
+            name,
+            addend,
+        })
+    }
+
+    /// Return an iterator over the imports.
+    pub fn iter(&self) -> ChainedImportIterator<'data, E> {
+        ChainedImportIterator {
+            table: *self,
+            index: 0,
+        }
+    }
+}
+
+/// An iterator over the imported symbols for chained fixups.
+///
+/// Returned by [`ChainedImportTable::iter`].
+#[derive(Debug, Clone, Copy)]
+pub struct ChainedImportIterator<'data, E: Endian> {
+    table: ChainedImportTable<'data, E>,
+    index: u32,
+}
+
+impl<'data, E: Endian> Iterator for ChainedImportIterator<'data, E> {
+    type Item = Result<ChainedImport<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index as usize >= self.table.len() {
+            return None;
+        }
+        let import = self.table.get(self.index);
+        self.index += 1;
+        Some(import)
+    }
+}
+
+/// An imported symbol for chained fixups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainedImport<'data> {
+    /// The library ordinal.
+    ///
+    /// This is either a 1-based index into the dylib load commands,
+    /// or one of the `BIND_SPECIAL_DYLIB_*` values.
+    pub lib_ordinal: i32,
+    /// Whether the import is weak.
+    pub weak_import: bool,
+    /// The name of the symbol.
+    pub name: &'data [u8],
+    /// The addend to apply to all binds of this import.
+    pub addend: i64,
+}
+
+/// An iterator over the fixup chain starts for each segment.
+///
+/// Returned by [`ChainedFixups::segments`].
+#[derive(Debug, Clone)]
+pub struct ChainedSegmentIterator<'data, E: Endian> {
+    endian: E,
+    starts: Bytes<'data>,
+    offsets: core::iter::Enumerate<core::slice::Iter<'data, U32<E>>>,
+}
+
+impl<'data, E: Endian> ChainedSegmentIterator<'data, E> {
+    /// Return the fixup chain starts for the next segment that has fixups.
+    pub fn next(&mut self) -> Result<Option<ChainedStartsInSegment<'data, E>>> {
+        for (segment_index, offset) in &mut self.offsets {
+            let offset = offset.get(self.endian) as usize;
+            if offset == 0 {
+                continue;
+            }
+            let header = self
+                .starts
+                .read_at::<macho::DyldChainedStartsInSegment<E>>(offset)
+                .read_error("Invalid chained fixups segment starts offset")?;
+            // The size includes the page starts, and any extra chain starts that follow them.
+            let page_starts_offset = mem::size_of::<macho::DyldChainedStartsInSegment<E>>();
+            let page_starts_count = (header.size.get(self.endian) as usize)
+                .checked_sub(page_starts_offset)
+                .read_error("Invalid chained fixups segment starts size")?
+                / 2;
+            let page_starts = self
+                .starts
+                .read_slice_at::<U16<E>>(offset + page_starts_offset, page_starts_count)
+                .read_error("Invalid chained fixups segment starts size")?;
+            if page_starts.len() < header.page_count.get(self.endian) as usize {
+                return Err(Error("Invalid chained fixups page count"));
+            }
+            return Ok(Some(ChainedStartsInSegment {
+                endian: self.endian,
+                segment_index,
+                header,
+                page_starts,
+            }));
+        }
+        Ok(None)
+    }
+}
+
+impl<'data, E: Endian> Iterator for ChainedSegmentIterator<'data, E> {
+    type Item = Result<ChainedStartsInSegment<'data, E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// The fixup chain starts for a segment.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainedStartsInSegment<'data, E: Endian> {
+    endian: E,
+    segment_index: usize,
+    header: &'data macho::DyldChainedStartsInSegment<E>,
+    page_starts: &'data [U16<E>],
+}
+
+impl<'data, E: Endian> ChainedStartsInSegment<'data, E> {
+    /// Return the index of the segment load command that this applies to.
+    pub fn segment_index(&self) -> usize {
+        self.segment_index
+    }
+
+    /// Return the header.
+    pub fn header(&self) -> &'data macho::DyldChainedStartsInSegment<E> {
+        self.header
+    }
+
+    /// Return the pointer format.
+    ///
+    /// This is one of the `DYLD_CHAINED_PTR_*` values.
+    pub fn pointer_format(&self) -> u16 {
+        self.header.pointer_format.get(self.endian)
+    }
+
+    /// Return the offset of the segment from the start of the image in memory.
+    pub fn segment_offset(&self) -> u64 {
+        self.header.segment_offset.get(self.endian)
+    }
+
+    /// Return the start of the first chain in each page.
+    pub fn page_starts(&self) -> &'data [U16<E>] {
+        &self.page_starts[..self.header.page_count.get(self.endian) as usize]
+    }
+
+    /// Return an iterator over the fixups in the segment.
+    ///
+    /// `data` and `file_offset` are used to read the segment contents.
+    /// `image_base` is the address of the Mach-O header, and is added to targets
+    /// that are stored as offsets.
+    pub fn fixups<R: ReadRef<'data>>(
+        &self,
+        data: R,
+        file_offset: u64,
+        image_base: u64,
+    ) -> Result<ChainedFixupIterator<'data, E, R>> {
+        let pointer_format = self.pointer_format();
+        let stride = match pointer_format {
+            macho::DYLD_CHAINED_PTR_ARM64E
+            | macho::DYLD_CHAINED_PTR_ARM64E_USERLAND
+            | macho::DYLD_CHAINED_PTR_ARM64E_USERLAND24 => 8,
+            macho::DYLD_CHAINED_PTR_ARM64E_KERNEL
+            | macho::DYLD_CHAINED_PTR_ARM64E_FIRMWARE
+            | macho::DYLD_CHAINED_PTR_64
+            | macho::DYLD_CHAINED_PTR_64_OFFSET
+            | macho::DYLD_CHAINED_PTR_64_KERNEL_CACHE
+            | macho::DYLD_CHAINED_PTR_32
+            | macho::DYLD_CHAINED_PTR_32_CACHE
+            | macho::DYLD_CHAINED_PTR_32_FIRMWARE => 4,
+            macho::DYLD_CHAINED_PTR_X86_64_KERNEL_CACHE => 1,
+            _ => return Err(Error("Unsupported chained fixups pointer format")),
+        };
+        Ok(ChainedFixupIterator {
+            endian: self.endian,
+            data,
+            file_offset,
+            image_base,
+            pointer_format,
+            stride,
+            max_valid_pointer: self.header.max_valid_pointer.get(self.endian),
+            page_size: self.header.page_size.get(self.endian).into(),
+            page_starts: self.page_starts,
+            page_count: self.header.page_count.get(self.endian).into(),
+            page_index: 0,
+            page_offset: 0,
+            multi_index: None,
+            offset: None,
+        })
+    }
+}
+
+/// An iterator over the fixups in a segment.
+///
+/// Returned by [`ChainedStartsInSegment::fixups`].
+#[derive(Debug)]
+pub struct ChainedFixupIterator<'data, E: Endian, R: ReadRef<'data>> {
+    endian: E,
+    data: R,
+    file_offset: u64,
+    image_base: u64,
+    pointer_format: u16,
+    stride: u64,
+    max_valid_pointer: u32,
+    page_size: u64,
+    page_starts: &'data [U16<E>],
+    page_count: usize,
+    /// The index of the next page.
+    page_index: usize,
+    /// The offset of the current page within the segment.
+    page_offset: u64,
+    /// The index of the next chain start for a page with multiple starts.
+    multi_index: Option<usize>,
+    /// The offset of the next fixup in the current chain.
+    offset: Option<u64>,
+}
+
+impl<'data, E: Endian, R: ReadRef<'data>> ChainedFixupIterator<'data, E, R> {
+    /// Return the next fixup.
+    pub fn next(&mut self) -> Result<Option<ChainedFixup>> {
+        let result = self.parse();
+        if result.is_err() {
+            self.page_index = self.page_count;
+            self.multi_index = None;
+            self.offset = None;
+        }
+        result
+    }
+
+    fn parse(&mut self) -> Result<Option<ChainedFixup>> {
+        loop {
+            if let Some(offset) = self.offset {
+                let (next, fixup) = self.read_fixup(offset)?;
+                self.offset = if next == 0 {
+                    None
+                } else {
+                    Some(offset + next * self.stride)
+                };
+                if let Some((value, auth)) = fixup {
+                    return Ok(Some(ChainedFixup {
+                        offset,
+                        value,
+                        auth,
+                    }));
+                }
+                continue;
+            }
+
+            if let Some(index) = self.multi_index {
+                let start = self
+                    .page_starts
+                    .get(index)
+                    .read_error("Invalid chained fixups chain start index")?
+                    .get(self.endian);
+                self.multi_index = if start & macho::DYLD_CHAINED_PTR_START_LAST != 0 {
+                    None
+                } else {
+                    Some(index + 1)
+                };
+                self.offset =
+                    Some(self.page_offset + u64::from(start & !macho::DYLD_CHAINED_PTR_START_LAST));
+                continue;
+            }
+
+            if self.page_index >= self.page_count {
+                return Ok(None);
+            }
+            let start = self.page_starts[self.page_index].get(self.endian);
+            self.page_offset = self.page_index as u64 * self.page_size;
+            self.page_index += 1;
+            if start == macho::DYLD_CHAINED_PTR_START_NONE {
+                continue;
+            }
+            if start & macho::DYLD_CHAINED_PTR_START_MULTI != 0 {
+                self.multi_index = Some(usize::from(start & !macho::DYLD_CHAINED_PTR_START_MULTI));
+            } else {
+                self.offset = Some(self.page_offset + u64::from(start));
+            }
+        }
+    }
+
+    /// Read and decode the pointer at the given offset within the segment.
+    ///
+    /// Returns the offset to the next pointer in units of the stride, and the fixup,
+    /// if any.
+    #[allow(clippy::type_complexity)]
+    fn read_fixup(
+        &self,
+        offset: u64,
+    ) -> Result<(u64, Option<(ChainedFixupValue, Option<DyldRelocationAuth>)>)> {
+        let file_offset = self
+            .file_offset
+            .checked_add(offset)
+            .read_error("Invalid chained fixups offset")?;
+        match self.pointer_format {
+            macho::DYLD_CHAINED_PTR_ARM64E
+            | macho::DYLD_CHAINED_PTR_ARM64E_KERNEL
+            | macho::DYLD_CHAINED_PTR_ARM64E_USERLAND
+            | macho::DYLD_CHAINED_PTR_ARM64E_FIRMWARE
+            | macho::DYLD_CHAINED_PTR_ARM64E_USERLAND24 => {
+                let pointer = macho::DyldChainedPtrArm64e(self.read_u64(file_offset)?);
+                let auth = if pointer.is_auth() {
+                    Some(DyldRelocationAuth {
+                        key: ptrauth_key(pointer.key()),
+                        diversity: pointer.diversity(),
+                        addr_div: pointer.addr_div(),
+                    })
+                } else {
+                    None
+                };
+                let value = if pointer.is_bind() {
+                    let ordinal =
+                        if self.pointer_format == macho::DYLD_CHAINED_PTR_ARM64E_USERLAND24 {
+                            pointer.ordinal24()
+                        } else {
+                            pointer.ordinal()
+                        };
+                    let addend = if pointer.is_auth() {
+                        0
+                    } else {
+                        pointer.addend()
+                    };
+                    ChainedFixupValue::Bind { ordinal, addend }
+                } else if pointer.is_auth() {
+                    ChainedFixupValue::Rebase(
+                        self.image_base.wrapping_add(pointer.runtime_offset()),
+                    )
+                } else {
+                    let target = match self.pointer_format {
+                        macho::DYLD_CHAINED_PTR_ARM64E
+                        | macho::DYLD_CHAINED_PTR_ARM64E_FIRMWARE => pointer.target(),
+                        _ => self.image_base.wrapping_add(pointer.target()),
+                    };
+                    ChainedFixupValue::Rebase(target | pointer.high8() << 56)
+                };
+                Ok((pointer.next(), Some((value, auth))))
+            }
+            macho::DYLD_CHAINED_PTR_64 | macho::DYLD_CHAINED_PTR_64_OFFSET => {
+                let pointer = macho::DyldChainedPtr64(self.read_u64(file_offset)?);
+                let value = if pointer.is_bind() {
+                    ChainedFixupValue::Bind {
+                        ordinal: pointer.ordinal(),
+                        addend: pointer.addend() as i64,
+                    }
+                } else {
+                    let target = if self.pointer_format == macho::DYLD_CHAINED_PTR_64 {
+                        pointer.target()
+                    } else {
+                        self.image_base.wrapping_add(pointer.target())
+                    };
+                    ChainedFixupValue::Rebase(target | pointer.high8() << 56)
+                };
+                Ok((pointer.next(), Some((value, None))))
+            }
+            macho::DYLD_CHAINED_PTR_64_KERNEL_CACHE
+            | macho::DYLD_CHAINED_PTR_X86_64_KERNEL_CACHE => {
+                let pointer = macho::DyldChainedPtr64KernelCache(self.read_u64(file_offset)?);
+                let auth = if pointer.is_auth() {
+                    Some(DyldRelocationAuth {
+                        key: ptrauth_key(pointer.key()),
+                        diversity: pointer.diversity(),
+                        addr_div: pointer.addr_div(),
+                    })
+                } else {
+                    None
+                };
+                let value =
+                    ChainedFixupValue::Rebase(self.image_base.wrapping_add(pointer.target()));
+                Ok((pointer.next(), Some((value, auth))))
+            }
+            macho::DYLD_CHAINED_PTR_32 => {
+                let pointer = macho::DyldChainedPtr32(self.read_u32(file_offset)?);
+                let value = if pointer.is_bind() {
+                    ChainedFixupValue::Bind {
+                        ordinal: pointer.ordinal(),
+                        addend: pointer.addend().into(),
+                    }
+                } else if pointer.target() > self.max_valid_pointer {
+                    // This is a non-pointer value that was encoded in the chain.
+                    return Ok((pointer.next().into(), None));
+                } else {
+                    ChainedFixupValue::Rebase(pointer.target().into())
+                };
+                Ok((pointer.next().into(), Some((value, None))))
+            }
+            macho::DYLD_CHAINED_PTR_32_CACHE => {
+                let pointer = macho::DyldChainedPtr32Cache(self.read_u32(file_offset)?);
+                let value = ChainedFixupValue::Rebase(
+                    self.image_base.wrapping_add(pointer.target().into()),
+                );
+                Ok((pointer.next().into(), Some((value, None))))
+            }
+            macho::DYLD_CHAINED_PTR_32_FIRMWARE => {
+                let pointer = macho::DyldChainedPtr32Firmware(self.read_u32(file_offset)?);
+                let value = ChainedFixupValue::Rebase(pointer.target().into());
+                Ok((pointer.next().into(), Some((value, None))))
+            }
+            _ => Err(Error("Unsupported chained fixups pointer format")),
+        }
+    }
+
+    fn read_u64(&self, offset: u64) -> Result<u64> {
+        self.data
+            .read_at::<U64<E>>(offset)
+            .read_error("Invalid chained fixups pointer offset")
+            .map(|pointer| pointer.get(self.endian))
+    }
+
+    fn read_u32(&self, offset: u64) -> Result<u32> {
+        self.data
+            .read_at::<U32<E>>(offset)
+            .read_error("Invalid chained fixups pointer offset")
+            .map(|pointer| pointer.get(self.endian))
+    }
+}
+
+impl<'data, E: Endian, R: ReadRef<'data>> Iterator for ChainedFixupIterator<'data, E, R> {
+    type Item = Result<ChainedFixup>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+fn ptrauth_key(key: u8) -> macho::PtrauthKey {
+    match key {
+        1 => macho::PtrauthKey::IB,
+        2 => macho::PtrauthKey::DA,
+        3 => macho::PtrauthKey::DB,
+        _ => macho::PtrauthKey::IA,
+    }
+}
+
+/// A fixup in a chain.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainedFixup {
+    /// The offset of the fixup within the segment.
+    pub offset: u64,
+    /// The value that the fixup is resolved to.
+    pub value: ChainedFixupValue,
+    /// The pointer authentication data, if present.
+    pub auth: Option<DyldRelocationAuth>,
+}
+
+/// The value of a [`ChainedFixup`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainedFixupValue {
+    /// A pointer that must be adjusted by the slide of the image.
+    ///
+    /// Contains the unslid target address, including any high bits.
+    Rebase(u64),
+    /// A pointer to an imported symbol.
+    Bind {
+        /// The index of the import in [`ChainedFixups::imports`].
+        ordinal: u32,
+        /// The addend. This is in addition to the addend of the import.
+        addend: i64,
+    },
+}
@@ -0,0 +1,497 @@
+#[cfg(feature = "codesign")]
+use alloc::vec::Vec;
+use core::{mem, result, slice};
+
+use crate::endian::{BigEndian as BE, U32};
+use crate::macho;
+use crate::pod;
+use crate::read::{Bytes, Error, ReadError, ReadRef, Result};
+
+/// The embedded code signature referenced by a `LC_CODE_SIGNATURE` load command.
+///
+/// This is a SuperBlob containing the CodeDirectory, requirements, entitlements
+/// and CMS signature blobs.
+#[derive(Debug, Clone, Copy)]
+pub struct CodeSignature<'data> {
+    data: &'data [u8],
+    header: &'data macho::CsSuperBlob,
+    index: &'data [macho::CsBlobIndex],
+}
+
+impl<'data> CodeSignature<'data> {
+    /// Parse the SuperBlob of an embedded code signature.
+    ///
+    /// `data` may contain padding after the SuperBlob.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let (data, header, index) = parse_super_blob(data, macho::CSMAGIC_EMBEDDED_SIGNATURE)
+            .read_error("Invalid Mach-O code signature")?;
+        Ok(CodeSignature {
+            data,
+            header,
+            index,
+        })
+    }
+
+    /// Return the header of the SuperBlob.
+    pub fn header(&self) -> &'data macho::CsSuperBlob {
+        self.header
+    }
+
+    /// Return the data of the SuperBlob, including the header.
+    pub fn data(&self) -> &'data [u8] {
+        self.data
+    }
+
+    /// Return an iterator over the blobs in the SuperBlob.
+    pub fn blobs(&self) -> CodeSignatureBlobIterator<'data> {
+        CodeSignatureBlobIterator {
+            data: self.data,
+            index: self.index.iter(),
+        }
+    }
+
+    /// Return the blob in the given slot.
+    ///
+    /// `slot` is one of the `CSSLOT_*` values.
+    pub fn blob(&self, slot: u32) -> Result<Option<CodeSignatureBlob<'data>>> {
+        for blob in self.blobs() {
+            let blob = blob?;
+            if blob.typ == slot {
+                return Ok(Some(blob));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the CodeDirectory in `CSSLOT_CODEDIRECTORY`.
+    ///
+    /// Signatures may contain additional CodeDirectories that use other hash types
+    /// in the `CSSLOT_ALTERNATE_CODEDIRECTORIES` slots.
+    pub fn code_directory(&self) -> Result<Option<CodeDirectory<'data>>> {
+        self.blob(macho::CSSLOT_CODEDIRECTORY)?
+            .map(|blob| blob.code_directory())
+            .transpose()
+    }
+
+    /// Return an iterator over the requirements in `CSSLOT_REQUIREMENTS`.
+    pub fn requirements(&self) -> Result<Option<CodeSignatureBlobIterator<'data>>> {
+        self.blob(macho::CSSLOT_REQUIREMENTS)?
+            .map(|blob| blob.requirements())
+            .transpose()
+    }
+
+    /// Return the XML entitlements in `CSSLOT_ENTITLEMENTS`.
+    pub fn entitlements(&self) -> Result<Option<&'data [u8]>> {
+        self.blob_payload(
+            macho::CSSLOT_ENTITLEMENTS,
+            macho::CSMAGIC_EMBEDDED_ENTITLEMENTS,
+        )
+    }
+
+    /// Return the DER encoded entitlements in `CSSLOT_DER_ENTITLEMENTS`.
+    pub fn der_entitlements(&self) -> Result<Option<&'data [u8]>> {
+        self.blob_payload(
+            macho::CSSLOT_DER_ENTITLEMENTS,
+            macho::CSMAGIC_EMBEDDED_DER_ENTITLEMENTS,
+        )
+    }
+
+    /// Return the CMS signature in `CSSLOT_SIGNATURESLOT`.
+    ///
+    /// This is empty for ad hoc signatures.
+    pub fn cms_signature(&self) -> Result<Option<&'data [u8]>> {
+        self.blob_payload(macho::CSSLOT_SIGNATURESLOT, macho::CSMAGIC_BLOBWRAPPER)
+    }
+
+    fn blob_payload(&self, slot: u32, magic: u32) -> Result<Option<&'data [u8]>> {
+        let Some(blob) = self.blob(slot)? else {
+            return Ok(None);
+        };
+        if blob.magic != magic {
+            return Err(Error("Invalid Mach-O code signature blob magic"));
+        }
+        Ok(Some(blob.payload()))
+    }
+}
+
+/// Parse a SuperBlob with the given magic.
+///
+/// Returns the data of the SuperBlob truncated to its length, the header and the index.
+fn parse_super_blob(
+    data: &[u8],
+    magic: u32,
+) -> result::Result<(&[u8], &macho::CsSuperBlob, &[macho::CsBlobIndex]), ()> {
+    let mut bytes = Bytes(data);
+    let header = bytes.read::<macho::CsSuperBlob>()?;
+    if header.magic.get(BE) != magic {
+        return Err(());
+    }
+    let data = data.get(..header.length.get(BE) as usize).ok_or(())?;
+    let mut bytes = Bytes(data);
+    bytes.skip(mem::size_of::<macho::CsSuperBlob>())?;
+    let index = bytes.read_slice::<macho::CsBlobIndex>(header.count.get(BE) as usize)?;
+    Ok((data, header, index))
+}
+
+/// An iterator over the blobs in a SuperBlob.
+///
+/// Returned by [`CodeSignature::blobs`] and [`CodeSignature::requirements`].
+#[derive(Debug, Clone)]
+pub struct CodeSignatureBlobIterator<'data> {
+    data: &'data [u8],
+    index: slice::Iter<'data, macho::CsBlobIndex>,
+}
+
+impl<'data> CodeSignatureBlobIterator<'data> {
+    /// Return the next blob.
+    pub fn next(&mut self) -> Result<Option<CodeSignatureBlob<'data>>> {
+        let Some(index) = self.index.next() else {
+            return Ok(None);
+        };
+        let typ = index.typ.get(BE);
+        let offset = index.offset.get(BE) as usize;
+        let blob = self
+            .data
+            .get(offset..)
+            .and_then(|data| {
+                let header = Bytes(data).read::<macho::CsGenericBlob>().ok()?;
+                let length = header.length.get(BE) as usize;
+                if length < mem::size_of::<macho::CsGenericBlob>() {
+                    return None;
+                }
+                Some(CodeSignatureBlob {
+                    typ,
+                    magic: header.magic.get(BE),
+                    data: data.get(..length)?,
+                })
+            })
+            .read_error("Invalid Mach-O code signature blob offset or length");
+        if blob.is_err() {
+            self.index = [].iter();
+        }
+        blob.map(Some)
+    }
+}
+
+impl<'data> Iterator for CodeSignatureBlobIterator<'data> {
+    type Item = Result<CodeSignatureBlob<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// A blob in a SuperBlob.
+#[derive(Debug, Clone, Copy)]
+pub struct CodeSignatureBlob<'data> {
+    /// The type of the blob in the SuperBlob index.
+    ///
+    /// For an embedded signature, this is one of the `CSSLOT_*` values.
+    /// For requirements, this is one of the `CS_*_REQUIREMENT_TYPE` values.
+    pub typ: u32,
+    /// The magic number of the blob.
+    ///
+    /// This is one of the `CSMAGIC_*` values.
+    pub magic: u32,
+    /// The data of the blob, including the header.
+    pub data: &'data [u8],
+}
+
+impl<'data> CodeSignatureBlob<'data> {
+    /// Return the data of the blob after the header.
+    pub fn payload(&self) -> &'data [u8] {
+        &self.data[mem::size_of::<macho::CsGenericBlob>()..]
+    }
+
+    /// Parse the blob as a CodeDirectory.
+    pub fn code_directory(&self) -> Result<CodeDirectory<'data>> {
+        CodeDirectory::parse(self.data)
+    }
+
+    /// Parse the blob as a requirements SuperBlob, and return an iterator over
+    /// the requirements in it.
+    pub fn requirements(&self) -> Result<CodeSignatureBlobIterator<'data>> {
+        let (data, _, index) = parse_super_blob(self.data, macho::CSMAGIC_REQUIREMENTS)
+            .read_error("Invalid Mach-O code signature requirements")?;
+        Ok(CodeSignatureBlobIterator {
+            data,
+            index: index.iter(),
+        })
+    }
+}
+
+/// Return the size of the CodeDirectory header for the given version.
+fn code_directory_header_size(version: u32) -> usize {
+    if version >= macho::CS_SUPPORTSLINKAGE {
+        mem::size_of::<macho::CsCodeDirectory>()
+    } else if version >= macho::CS_SUPPORTSRUNTIME {
+        96
+    } else if version >= macho::CS_SUPPORTSEXECSEG {
+        88
+    } else if version >= macho::CS_SUPPORTSCODELIMIT64 {
+        64
+    } else if version >= macho::CS_SUPPORTSTEAMID {
+        52
+    } else if version >= macho::CS_SUPPORTSSCATTER {
+        48
+    } else {
+        44
+    }
+}
+
+/// A CodeDirectory blob in a code signature.
+///
+/// The CodeDirectory contains the hashes of the pages of the file (the code slots),
+/// and the hashes of other blobs in the signature (the special slots).
+#[derive(Debug, Clone, Copy)]
+pub struct CodeDirectory<'data> {
+    data: &'data [u8],
+    header: macho::CsCodeDirectory,
+}
+
+impl<'data> CodeDirectory<'data> {
+    /// Parse a CodeDirectory blob.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let header = Bytes(data)
+            .read::<macho::CsGenericBlob>()
+            .read_error("Invalid Mach-O CodeDirectory size")?;
+        if header.magic.get(BE) != macho::CSMAGIC_CODEDIRECTORY {
+            return Err(Error("Invalid Mach-O CodeDirectory magic"));
+        }
+        let data = data
+            .get(..header.length.get(BE) as usize)
+            .read_error("Invalid Mach-O CodeDirectory length")?;
+        // Later versions add fields, so only read the fields for this version,
+        // and set the missing fields to zero.
+        let version = Bytes(data)
+            .read_at::<U32<BE>>(8)
+            .read_error("Invalid Mach-O CodeDirectory size")?
+            .get(BE);
+        let fields = data
+            .get(..code_directory_header_size(version))
+            .read_error("Invalid Mach-O CodeDirectory size")?;
+        let mut bytes = vec![0; mem::size_of::<macho::CsCodeDirectory>()];
+        bytes[..fields.len()].copy_from_slice(fields);
+        let (header, _) = pod::from_bytes::<macho::CsCodeDirectory>(&bytes)
+            .read_error("Invalid Mach-O CodeDirectory size")?;
+        let header = *header;
+        if header.hash_size == 0 {
+            return Err(Error("Invalid Mach-O CodeDirectory hash size"));
+        }
+        let directory = CodeDirectory { data, header };
+        // Validate the range of the hash slots.
+        directory.slots(header.n_special_slots.get(BE), header.n_code_slots.get(BE))?;
+        Ok(directory)
+    }
+
+    /// Return the header of the CodeDirectory.
+    ///
+    /// Fields that are not supported by the version of the CodeDirectory are zero.
+    pub fn header(&self) -> &macho::CsCodeDirectory {
+        &self.header
+    }
+
+    /// Return the data of the CodeDirectory blob, including the header.
+    pub fn data(&self) -> &'data [u8] {
+        self.data
+    }
+
+    /// Return the version.
+    pub fn version(&self) -> u32 {
+        self.header.version.get(BE)
+    }
+
+    /// Return the flags.
+    ///
+    /// This is a combination of the `CS_*` code signing flags, such as `CS_ADHOC`.
+    pub fn flags(&self) -> u32 {
+        self.header.flags.get(BE)
+    }
+
+    /// Return the hash type.
+    ///
+    /// This is one of the `CS_HASHTYPE_*` values.
+    pub fn hash_type(&self) -> u8 {
+        self.header.hash_type
+    }
+
+    /// Return the size in bytes of each hash.
+    pub fn hash_size(&self) -> usize {
+        self.header.hash_size.into()
+    }
+
+    /// Return the size in bytes of the pages that are hashed by the code slots.
+    ///
+    /// Returns 0 if the code is hashed as a single page.
+    pub fn page_size(&self) -> u64 {
+        match self.header.page_size {
+            0 => 0,
+            shift => 1u64.checked_shl(shift.into()).unwrap_or(0),
+        }
+    }
+
+    /// Return the size in bytes of the file data that is hashed by the code slots.
+    pub fn code_limit(&self) -> u64 {
+        match self.header.code_limit64.get(BE) {
+            0 => self.header.code_limit.get(BE).into(),
+            code_limit => code_limit,
+        }
+    }
+
+    /// Return the number of code slots.
+    pub fn n_code_slots(&self) -> u32 {
+        self.header.n_code_slots.get(BE)
+    }
+
+    /// Return the number of special slots.
+    pub fn n_special_slots(&self) -> u32 {
+        self.header.n_special_slots.get(BE)
+    }
+
+    /// Return the identifier, such as a bundle identifier.
+    pub fn identifier(&self) -> Result<&'data [u8]> {
+        Bytes(self.data)
+            .read_string_at(self.header.ident_offset.get(BE) as usize)
+            .read_error("Invalid Mach-O CodeDirectory identifier offset")
+    }
+
+    /// Return the team identifier, if present.
+    pub fn team_id(&self) -> Result<Option<&'data [u8]>> {
+        match self.header.team_offset.get(BE) {
+            0 => Ok(None),
+            offset => Bytes(self.data)
+                .read_string_at(offset as usize)
+                .read_error("Invalid Mach-O CodeDirectory team identifier offset")
+                .map(Some),
+        }
+    }
+
+    /// Return the data of the hash slots, starting at the given number of special slots.
+    fn slots(&self, special: u32, count: u32) -> Result<&'data [u8]> {
+        let hash_size = self.hash_size();
+        let offset = (self.header.hash_offset.get(BE) as usize)
+            .checked_sub(special as usize * hash_size)
+            .read_error("Invalid Mach-O CodeDirectory hash offset")?;
+        Bytes(self.data)
+            .read_bytes_at(offset, count as usize * hash_size)
+            .map(|bytes| bytes.0)
+            .read_error("Invalid Mach-O CodeDirectory hash offset")
+    }
+
+    /// Return the hashes in the code slots.
+    ///
+    /// The hash in slot `n` is the hash of page `n` of the file.
+    pub fn code_slots(&self) -> slice::ChunksExact<'data, u8> {
+        self.slots(0, self.n_code_slots())
+            .unwrap_or(&[])
+            .chunks_exact(self.hash_size())
+    }
+
+    /// Return the hash in the given special slot, if present.
+    ///
+    /// `slot` is one of the `CSSLOT_*` values, and the hash is the hash of the blob
+    /// in the same slot of the SuperBlob.
+    /// Special slots that do not have a blob contain a hash of zeros.
+    pub fn special_slot(&self, slot: u32) -> Result<Option<&'data [u8]>> {
+        if slot == 0 || slot > self.n_special_slots() {
+            return Ok(None);
+        }
+        self.slots(slot, 1).map(Some)
+    }
+
+    /// Compute the hash of the given data using the hash type of this CodeDirectory.
+    ///
+    /// The hash is truncated to the hash size.
+    ///
+    /// Requires the `codesign` feature.
+    #[cfg(feature = "codesign")]
+    pub fn hash(&self, data: &[u8]) -> Result<Vec<u8>> {
+        use sha2::Digest;
+
+        let mut hash = match self.hash_type() {
+            macho::CS_HASHTYPE_SHA1 => sha1::Sha1::digest(data).to_vec(),
+            macho::CS_HASHTYPE_SHA256 | macho::CS_HASHTYPE_SHA256_TRUNCATED => {
+                sha2::Sha256::digest(data).to_vec()
+            }
+            _ => return Err(Error("Unsupported Mach-O CodeDirectory hash type")),
+        };
+        if hash.len() < self.hash_size() {
+            return Err(Error("Invalid Mach-O CodeDirectory hash size"));
+        }
+        hash.truncate(self.hash_size());
+        Ok(hash)
+    }
+
+    /// Compute the CDHash of this CodeDirectory.
+    ///
+    /// This is the hash of the CodeDirectory blob, truncated to `CS_CDHASH_LEN`.
+    ///
+    /// Requires the `codesign` feature.
+    #[cfg(feature = "codesign")]
+    pub fn cdhash(&self) -> Result<Vec<u8>> {
+        let mut hash = self.hash(self.data)?;
+        hash.truncate(macho::CS_CDHASH_LEN);
+        Ok(hash)
+    }
+
+    /// Return an iterator over the pages of the file that are hashed by the code slots.
+    ///
+    /// `offset` is the file offset of the Mach-O header in `data`.
+    ///
+    /// Use [`Self::hash`] to compute the hash of each page and compare it with the
+    /// hash in the code slot.
+    pub fn code_pages<R: ReadRef<'data>>(
+        &self,
+        data: R,
+        offset: u64,
+    ) -> Result<CodePageIterator<'data>> {
+        let code_limit = self.code_limit();
+        let data = data
+            .read_bytes_at(offset, code_limit)
+            .read_error("Invalid Mach-O CodeDirectory code limit")?;
+        let page_size = match self.page_size() {
+            0 => code_limit.max(1),
+            page_size => page_size,
+        };
+        let n_pages = (code_limit + page_size - 1) / page_size;
+        if n_pages != u64::from(self.n_code_slots()) {
+            return Err(Error("Invalid Mach-O CodeDirectory code slot count"));
+        }
+        Ok(CodePageIterator {
+            pages: data.chunks(page_size as usize),
+            slots: self.code_slots(),
+        })
+    }
+}
+
+/// An iterator over the pages of a file that are hashed by the code slots of a
+/// [`CodeDirectory`].
+///
+/// Returned by [`CodeDirectory::code_pages`].
+#[derive(Debug, Clone)]
+pub struct CodePageIterator<'data> {
+    pages: slice::Chunks<'data, u8>,
+    slots: slice::ChunksExact<'data, u8>,
+}
+
+impl<'data> Iterator for CodePageIterator<'data> {
+    type Item = CodePage<'data>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(CodePage {
+            data: self.pages.next()?,
+            hash: self.slots.next()?,
+        })
+    }
+}
+
+/// A page of a file that is hashed by a code slot of a [`CodeDirectory`].
+#[derive(Debug, Clone, Copy)]
+pub struct CodePage<'data> {
+    /// The data of the page.
+    ///
+    /// The last page may be shorter than the page size.
+    pub data: &'data [u8],
+    /// The hash in the code slot for the page.
+    pub hash: &'data [u8],
+}
@@ -927,6 +927,7 @@ impl Debug for DyldRelocation {
 /// Pointer authentication data.
 ///
 /// This is used for signing pointers for the arm64e ABI.
+#[derive(Clone, Copy)]
 pub struct DyldRelocationAuth {
     /// The key used to generate the signed value.
     pub key: macho::PtrauthKey,
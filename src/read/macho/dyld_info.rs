@@ -0,0 +1,360 @@
+use core::convert::TryFrom;
+
+use crate::macho;
+use crate::read::{Bytes, Error, ReadError, Result};
+
+/// An iterator over the rebases in the rebase information of a `LC_DYLD_INFO` load command.
+///
+/// This executes the `REBASE_OPCODE_*` stream.
+///
+/// Returned by [`macho::DyldInfoCommand::rebases`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DyldRebaseIterator<'data> {
+    data: Bytes<'data>,
+    pointer_size: u64,
+    typ: u8,
+    segment_index: usize,
+    segment_offset: u64,
+    /// The number of rebases remaining for the current opcode.
+    remaining: u64,
+    /// The number of bytes to skip after each rebase, in addition to the pointer size.
+    skip: u64,
+}
+
+impl<'data> DyldRebaseIterator<'data> {
+    pub(super) fn new(data: &'data [u8], is_64: bool) -> Self {
+        DyldRebaseIterator {
+            data: Bytes(data),
+            pointer_size: if is_64 { 8 } else { 4 },
+            ..Default::default()
+        }
+    }
+
+    /// Returns the next rebase.
+    pub fn next(&mut self) -> Result<Option<DyldRebase>> {
+        let result = self.parse();
+        if result.is_err() {
+            self.data = Bytes(&[]);
+            self.remaining = 0;
+        }
+        result
+    }
+
+    fn parse(&mut self) -> Result<Option<DyldRebase>> {
+        while self.remaining == 0 {
+            let Ok(byte) = self.data.read::<u8>() else {
+                return Ok(None);
+            };
+            let immediate = byte & macho::REBASE_IMMEDIATE_MASK;
+            match byte & macho::REBASE_OPCODE_MASK {
+                macho::REBASE_OPCODE_DONE => {
+                    self.data = Bytes(&[]);
+                    return Ok(None);
+                }
+                macho::REBASE_OPCODE_SET_TYPE_IMM => self.typ = immediate,
+                macho::REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
+                    self.segment_index = immediate.into();
+                    self.segment_offset = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O rebase segment offset")?;
+                }
+                macho::REBASE_OPCODE_ADD_ADDR_ULEB => {
+                    let delta = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O rebase address delta")?;
+                    self.segment_offset = self.segment_offset.wrapping_add(delta);
+                }
+                macho::REBASE_OPCODE_ADD_ADDR_IMM_SCALED => {
+                    let delta = u64::from(immediate) * self.pointer_size;
+                    self.segment_offset = self.segment_offset.wrapping_add(delta);
+                }
+                macho::REBASE_OPCODE_DO_REBASE_IMM_TIMES => {
+                    self.remaining = immediate.into();
+                    self.skip = 0;
+                }
+                macho::REBASE_OPCODE_DO_REBASE_ULEB_TIMES => {
+                    self.remaining = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O rebase count")?;
+                    self.skip = 0;
+                }
+                macho::REBASE_OPCODE_DO_REBASE_ADD_ADDR_ULEB => {
+                    self.remaining = 1;
+                    self.skip = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O rebase address delta")?;
+                }
+                macho::REBASE_OPCODE_DO_REBASE_ULEB_TIMES_SKIPPING_ULEB => {
+                    self.remaining = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O rebase count")?;
+                    self.skip = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O rebase skip")?;
+                }
+                _ => return Err(Error("Unknown Mach-O rebase opcode")),
+            }
+        }
+
+        self.remaining -= 1;
+        let rebase = DyldRebase {
+            segment_index: self.segment_index,
+            segment_offset: self.segment_offset,
+            typ: self.typ,
+        };
+        self.segment_offset = self
+            .segment_offset
+            .wrapping_add(self.pointer_size)
+            .wrapping_add(self.skip);
+        Ok(Some(rebase))
+    }
+}
+
+impl<'data> Iterator for DyldRebaseIterator<'data> {
+    type Item = Result<DyldRebase>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// A rebase in the rebase information of a `LC_DYLD_INFO` load command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DyldRebase {
+    /// The index of the segment containing the location to rebase.
+    pub segment_index: usize,
+    /// The offset of the location within the segment.
+    pub segment_offset: u64,
+    /// The type of the rebase.
+    ///
+    /// This is one of the `REBASE_TYPE_*` values.
+    pub typ: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DyldBindKind {
+    Bind,
+    WeakBind,
+    LazyBind,
+}
+
+/// An iterator over the binds in the bind information of a `LC_DYLD_INFO` load command.
+///
+/// This executes the `BIND_OPCODE_*` stream.
+///
+/// Returned by [`macho::DyldInfoCommand::binds`], [`macho::DyldInfoCommand::weak_binds`]
+/// and [`macho::DyldInfoCommand::lazy_binds`].
+#[derive(Debug, Clone, Copy)]
+pub struct DyldBindIterator<'data> {
+    data: Bytes<'data>,
+    kind: DyldBindKind,
+    pointer_size: u64,
+    typ: u8,
+    segment_index: usize,
+    segment_offset: u64,
+    lib_ordinal: i32,
+    name: &'data [u8],
+    flags: u8,
+    addend: i64,
+    /// The number of binds remaining for the current opcode.
+    remaining: u64,
+    /// The number of bytes to skip after each bind, in addition to the pointer size.
+    skip: u64,
+}
+
+impl<'data> DyldBindIterator<'data> {
+    fn new(data: &'data [u8], is_64: bool, kind: DyldBindKind) -> Self {
+        // Weak binds do not set an ordinal, because they are always looked up
+        // in all images.
+        let lib_ordinal = if kind == DyldBindKind::WeakBind {
+            macho::BIND_SPECIAL_DYLIB_WEAK_LOOKUP.into()
+        } else {
+            0
+        };
+        DyldBindIterator {
+            data: Bytes(data),
+            kind,
+            pointer_size: if is_64 { 8 } else { 4 },
+            typ: macho::BIND_TYPE_POINTER,
+            segment_index: 0,
+            segment_offset: 0,
+            lib_ordinal,
+            name: &[],
+            flags: 0,
+            addend: 0,
+            remaining: 0,
+            skip: 0,
+        }
+    }
+
+    pub(super) fn bind(data: &'data [u8], is_64: bool) -> Self {
+        Self::new(data, is_64, DyldBindKind::Bind)
+    }
+
+    pub(super) fn weak_bind(data: &'data [u8], is_64: bool) -> Self {
+        Self::new(data, is_64, DyldBindKind::WeakBind)
+    }
+
+    pub(super) fn lazy_bind(data: &'data [u8], is_64: bool) -> Self {
+        Self::new(data, is_64, DyldBindKind::LazyBind)
+    }
+
+    /// Returns the next bind.
+    pub fn next(&mut self) -> Result<Option<DyldBind<'data>>> {
+        let result = self.parse();
+        if result.is_err() {
+            self.data = Bytes(&[]);
+            self.remaining = 0;
+        }
+        result
+    }
+
+    fn parse(&mut self) -> Result<Option<DyldBind<'data>>> {
+        while self.remaining == 0 {
+            let Ok(byte) = self.data.read::<u8>() else {
+                return Ok(None);
+            };
+            let immediate = byte & macho::BIND_IMMEDIATE_MASK;
+            match byte & macho::BIND_OPCODE_MASK {
+                macho::BIND_OPCODE_DONE => {
+                    // Lazy binds are terminated individually so that dyld can start
+                    // binding at the offset of any entry.
+                    if self.kind != DyldBindKind::LazyBind {
+                        self.data = Bytes(&[]);
+                        return Ok(None);
+                    }
+                }
+                macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM => self.lib_ordinal = immediate.into(),
+                macho::BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB => {
+                    self.lib_ordinal = self
+                        .data
+                        .read_uleb128()
+                        .ok()
+                        .and_then(|ordinal| i32::try_from(ordinal).ok())
+                        .read_error("Invalid Mach-O bind dylib ordinal")?;
+                }
+                macho::BIND_OPCODE_SET_DYLIB_SPECIAL_IMM => {
+                    // The immediate is the low bits of a negative special ordinal.
+                    self.lib_ordinal = if immediate == 0 {
+                        0
+                    } else {
+                        i32::from((immediate | macho::BIND_OPCODE_MASK) as i8)
+                    };
+                }
+                macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM => {
+                    self.name = self
+                        .data
+                        .read_string()
+                        .read_error("Invalid Mach-O bind symbol name")?;
+                    self.flags = immediate;
+                }
+                macho::BIND_OPCODE_SET_TYPE_IMM => self.typ = immediate,
+                macho::BIND_OPCODE_SET_ADDEND_SLEB => {
+                    self.addend = self
+                        .data
+                        .read_sleb128()
+                        .read_error("Invalid Mach-O bind addend")?;
+                }
+                macho::BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
+                    self.segment_index = immediate.into();
+                    self.segment_offset = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O bind segment offset")?;
+                }
+                macho::BIND_OPCODE_ADD_ADDR_ULEB => {
+                    let delta = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O bind address delta")?;
+                    self.segment_offset = self.segment_offset.wrapping_add(delta);
+                }
+                macho::BIND_OPCODE_DO_BIND => {
+                    self.remaining = 1;
+                    self.skip = 0;
+                }
+                macho::BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB => {
+                    self.remaining = 1;
+                    self.skip = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O bind address delta")?;
+                }
+                macho::BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED => {
+                    self.remaining = 1;
+                    self.skip = u64::from(immediate) * self.pointer_size;
+                }
+                macho::BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB => {
+                    self.remaining = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O bind count")?;
+                    self.skip = self
+                        .data
+                        .read_uleb128()
+                        .read_error("Invalid Mach-O bind skip")?;
+                }
+                macho::BIND_OPCODE_THREADED => {
+                    return Err(Error("Unsupported Mach-O threaded bind opcode"));
+                }
+                _ => return Err(Error("Unknown Mach-O bind opcode")),
+            }
+        }
+
+        self.remaining -= 1;
+        let bind = DyldBind {
+            segment_index: self.segment_index,
+            segment_offset: self.segment_offset,
+            typ: self.typ,
+            lib_ordinal: self.lib_ordinal,
+            name: self.name,
+            flags: self.flags,
+            addend: self.addend,
+        };
+        self.segment_offset = self
+            .segment_offset
+            .wrapping_add(self.pointer_size)
+            .wrapping_add(self.skip);
+        Ok(Some(bind))
+    }
+}
+
+impl<'data> Iterator for DyldBindIterator<'data> {
+    type Item = Result<DyldBind<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// A bind in the bind information of a `LC_DYLD_INFO` load command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DyldBind<'data> {
+    /// The index of the segment containing the location to bind.
+    pub segment_index: usize,
+    /// The offset of the location within the segment.
+    pub segment_offset: u64,
+    /// The type of the bind.
+    ///
+    /// This is one of the `BIND_TYPE_*` values.
+    pub typ: u8,
+    /// The ordinal of the dylib that the symbol is imported from.
+    ///
+    /// Library ordinals are 1-based. Otherwise, this is one of the
+    /// `BIND_SPECIAL_DYLIB_*` values.
+    pub lib_ordinal: i32,
+    /// The name of the symbol.
+    pub name: &'data [u8],
+    /// The symbol flags.
+    ///
+    /// This is a combination of the `BIND_SYMBOL_FLAGS_*` values.
+    pub flags: u8,
+    /// The addend.
+    pub addend: i64,
+}
@@ -1,3 +1,4 @@
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use core::fmt::Debug;
 use core::{mem, str};
@@ -5,14 +6,15 @@ use core::{mem, str};
 use crate::endian::{self, BigEndian, Endian, Endianness, NativeEndian};
 use crate::pod::Pod;
 use crate::read::{
-    self, Architecture, ByteString, ComdatKind, Error, Export, FileFlags, Import,
-    NoDynamicRelocationIterator, Object, ObjectComdat, ObjectKind, ObjectMap, ObjectSection,
-    ReadError, ReadRef, Result, SectionIndex, SubArchitecture, SymbolIndex,
+    self, Architecture, ByteString, ComdatKind, Error, Export, FileFlags, Import, Object,
+    ObjectComdat, ObjectKind, ObjectMap, ObjectSection, ReadError, ReadRef, Result, SectionIndex,
+    SubArchitecture, SymbolIndex,
 };
 use crate::{macho, SkipDebugList};
 
 use super::{
-    DyldCacheImage, LoadCommandIterator, MachOSection, MachOSectionInternal, MachOSectionIterator,
+    ChainedFixups, CodeSignature, DyldCacheImage, LoadCommandIterator,
+    MachODynamicRelocationIterator, MachOSection, MachOSectionInternal, MachOSectionIterator,
     MachOSegment, MachOSegmentInternal, MachOSegmentIterator, MachOSymbol, MachOSymbolIterator,
     MachOSymbolTable, Nlist, Section, Segment, SymbolTable,
 };
@@ -204,6 +206,117 @@ where
         &self.symbols
     }
 
+    /// Return the chained fixups from the `LC_DYLD_CHAINED_FIXUPS` load command if present.
+    pub fn chained_fixups(&self) -> Result<Option<ChainedFixups<'data, Mach::Endian>>> {
+        let mut commands =
+            self.header
+                .load_commands(self.endian, self.data.0, self.header_offset)?;
+        while let Some(command) = commands.next()? {
+            if let Some(command) = command.dyld_chained_fixups()? {
+                return command.chained_fixups(self.endian, self.data.0).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the embedded code signature, if present.
+    ///
+    /// This is the code signature referenced by the `LC_CODE_SIGNATURE` load command.
+    pub fn code_signature(&self) -> Result<Option<CodeSignature<'data>>> {
+        let mut commands =
+            self.header
+                .load_commands(self.endian, self.data.0, self.header_offset)?;
+        while let Some(command) = commands.next()? {
+            if command.cmd() == macho::LC_CODE_SIGNATURE {
+                let command = command.data::<macho::LinkeditDataCommand<Mach::Endian>>()?;
+                return command.code_signature(self.endian, self.data.0).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the `LC_DYLD_INFO` or `LC_DYLD_INFO_ONLY` load command if present.
+    pub fn dyld_info(&self) -> Result<Option<&'data macho::DyldInfoCommand<Mach::Endian>>> {
+        let mut commands =
+            self.header
+                .load_commands(self.endian, self.data.0, self.header_offset)?;
+        while let Some(command) = commands.next()? {
+            if let Some(dyld_info) = command.dyld_info()? {
+                return Ok(Some(dyld_info));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the imports in the `LC_DYLD_CHAINED_FIXUPS` load command.
+    ///
+    /// `libraries` is indexed by library ordinal.
+    fn chained_fixups_imports(
+        &self,
+        chained_fixups: &'data macho::LinkeditDataCommand<Mach::Endian>,
+        libraries: &[&'data [u8]],
+        twolevel: bool,
+    ) -> Result<Vec<Import<'data>>> {
+        let chained_fixups = chained_fixups.chained_fixups(self.endian, self.data.0)?;
+        let mut imports = Vec::new();
+        for import in chained_fixups.imports()?.iter() {
+            let import = import?;
+            // Library ordinals are only meaningful for two-level namespace images.
+            let library = if twolevel && import.lib_ordinal > 0 {
+                libraries
+                    .get(import.lib_ordinal as usize)
+                    .copied()
+                    .read_error("Invalid Mach-O chained fixups library ordinal")?
+            } else {
+                &[]
+            };
+            imports.push(Import {
+                name: ByteString(import.name),
+                library: ByteString(library),
+            });
+        }
+        Ok(imports)
+    }
+
+    /// Return the imports that are bound by the opcodes in the `LC_DYLD_INFO` load command.
+    ///
+    /// `libraries` is indexed by library ordinal.
+    fn dyld_info_imports(
+        &self,
+        dyld_info: &'data macho::DyldInfoCommand<Mach::Endian>,
+        libraries: &[&'data [u8]],
+        twolevel: bool,
+    ) -> Result<Vec<Import<'data>>> {
+        // Each import may be bound at multiple locations, so only add it once.
+        let is_64 = self.is_64();
+        let mut seen = BTreeSet::new();
+        let mut imports = Vec::new();
+        for binds in [
+            dyld_info.binds(self.endian, self.data.0, is_64)?,
+            dyld_info.lazy_binds(self.endian, self.data.0, is_64)?,
+        ] {
+            for bind in binds {
+                let bind = bind?;
+                if !seen.insert((bind.lib_ordinal, bind.name)) {
+                    continue;
+                }
+                let library = if twolevel && bind.lib_ordinal > 0 {
+                    libraries
+                        .get(bind.lib_ordinal as usize)
+                        .copied()
+                        .read_error("Invalid Mach-O bind library ordinal")?
+                } else {
+                    &[]
+                };
+                imports.push(Import {
+                    name: ByteString(bind.name),
+                    library: ByteString(library),
+                });
+            }
+        }
+        Ok(imports)
+    }
+
     /// Return the `LC_BUILD_VERSION` load command if present.
     pub fn build_version(&self) -> Result<Option<&'data macho::BuildVersionCommand<Mach::Endian>>> {
         let mut commands =
@@ -276,7 +389,7 @@ where
         Self: 'file,
         'data: 'file;
     type DynamicRelocationIterator<'file>
-        = NoDynamicRelocationIterator
+        = MachODynamicRelocationIterator<'data, 'file, Mach, R>
     where
         Self: 'file,
         'data: 'file;
@@ -412,11 +525,11 @@ where
 
     fn imports(&self) -> Result<Vec<Import<'data>>> {
         let mut dysymtab = None;
-        let mut libraries = Vec::new();
+        let mut chained_fixups = None;
+        let mut dyld_info = None;
+        // Library ordinals are 1-based.
+        let mut libraries = vec![&[][..]];
         let twolevel = self.header.flags(self.endian) & macho::MH_TWOLEVEL != 0;
-        if twolevel {
-            libraries.push(&[][..]);
-        }
         let mut commands =
             self.header
                 .load_commands(self.endian, self.data.0, self.header_offset)?;
@@ -424,6 +537,12 @@ where
             if let Some(command) = command.dysymtab()? {
                 dysymtab = Some(command);
             }
+            if let Some(command) = command.dyld_chained_fixups()? {
+                chained_fixups = Some(command);
+            }
+            if let Some(command) = command.dyld_info()? {
+                dyld_info = Some(command);
+            }
             if twolevel {
                 if let Some(dylib) = command.dylib()? {
                     libraries.push(command.string(self.endian, dylib.dylib.name)?);
@@ -432,7 +551,20 @@ where
         }
 
         let mut imports = Vec::new();
-        if let Some(dysymtab) = dysymtab {
+        if let Some(chained_fixups_imports) = chained_fixups.and_then(|chained_fixups| {
+            // The chained fixups imports are what dyld binds, so prefer them over the
+            // symbol table, which may have been stripped. Fall back to the symbol table
+            // if the chained fixups are malformed.
+            self.chained_fixups_imports(chained_fixups, &libraries, twolevel)
+                .ok()
+        }) {
+            imports = chained_fixups_imports;
+        } else if let Some(dyld_info_imports) = dyld_info.and_then(|dyld_info| {
+            // Fall back to the symbol table if the bind opcodes are malformed.
+            self.dyld_info_imports(dyld_info, &libraries, twolevel).ok()
+        }) {
+            imports = dyld_info_imports;
+        } else if let Some(dysymtab) = dysymtab {
             let index = dysymtab.iundefsym.get(self.endian) as usize;
             let number = dysymtab.nundefsym.get(self.endian) as usize;
             for i in index..(index.wrapping_add(number)) {
@@ -484,9 +616,15 @@ where
         Ok(exports)
     }
 
-    #[inline]
-    fn dynamic_relocations(&self) -> Option<NoDynamicRelocationIterator> {
-        None
+    fn dynamic_relocations(&self) -> Option<MachODynamicRelocationIterator<'data, '_, Mach, R>> {
+        if let Some(chained_fixups) = self.chained_fixups().ok()? {
+            return Some(MachODynamicRelocationIterator::chained_fixups(
+                self,
+                chained_fixups,
+            ));
+        }
+        let dyld_info = self.dyld_info().ok()??;
+        Some(MachODynamicRelocationIterator::dyld_info(self, dyld_info))
     }
 
     fn has_debug_symbols(&self) -> bool {
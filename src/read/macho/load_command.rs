@@ -4,7 +4,10 @@ use core::mem;
 use crate::endian::{Endian, U32};
 use crate::macho;
 use crate::pod::Pod;
-use crate::read::macho::{ExportsTrieIterator, FunctionStartsIterator, MachHeader, SymbolTable};
+use crate::read::macho::{
+    ChainedFixups, CodeSignature, DyldBindIterator, DyldRebaseIterator, ExportsTrieIterator,
+    FunctionStartsIterator, MachHeader, SymbolTable,
+};
 use crate::read::{Bytes, Error, ReadError, ReadRef, Result, StringTable};
 
 /// An iterator for the load commands from a [`MachHeader`].
@@ -255,6 +258,15 @@ impl<'data, E: Endian> LoadCommandData<'data, E> {
         }
     }
 
+    /// Try to parse this command as an `LC_DYLD_CHAINED_FIXUPS` [`macho::LinkeditDataCommand`].
+    pub fn dyld_chained_fixups(self) -> Result<Option<&'data macho::LinkeditDataCommand<E>>> {
+        if self.cmd == macho::LC_DYLD_CHAINED_FIXUPS {
+            Some(self.data()).transpose()
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Try to parse this command as an [`macho::EntryPointCommand`].
     pub fn entry_point(self) -> Result<Option<&'data macho::EntryPointCommand<E>>> {
         if self.cmd == macho::LC_MAIN {
@@ -414,6 +426,78 @@ impl<E: Endian> macho::DysymtabCommand<E> {
     }
 }
 
+impl<E: Endian> macho::DyldInfoCommand<E> {
+    /// Return an iterator over the rebases in the rebase information.
+    ///
+    /// `is_64` determines the pointer size used by the rebase opcodes.
+    pub fn rebases<'data, R: ReadRef<'data>>(
+        &self,
+        endian: E,
+        data: R,
+        is_64: bool,
+    ) -> Result<DyldRebaseIterator<'data>> {
+        let data = data
+            .read_bytes_at(
+                self.rebase_off.get(endian).into(),
+                self.rebase_size.get(endian).into(),
+            )
+            .read_error("Invalid Mach-O rebase info offset or size")?;
+        Ok(DyldRebaseIterator::new(data, is_64))
+    }
+
+    /// Return an iterator over the binds in the bind information.
+    ///
+    /// `is_64` determines the pointer size used by the bind opcodes.
+    pub fn binds<'data, R: ReadRef<'data>>(
+        &self,
+        endian: E,
+        data: R,
+        is_64: bool,
+    ) -> Result<DyldBindIterator<'data>> {
+        let data = data
+            .read_bytes_at(
+                self.bind_off.get(endian).into(),
+                self.bind_size.get(endian).into(),
+            )
+            .read_error("Invalid Mach-O bind info offset or size")?;
+        Ok(DyldBindIterator::bind(data, is_64))
+    }
+
+    /// Return an iterator over the binds in the weak bind information.
+    ///
+    /// The library ordinal of these binds is `BIND_SPECIAL_DYLIB_WEAK_LOOKUP`.
+    pub fn weak_binds<'data, R: ReadRef<'data>>(
+        &self,
+        endian: E,
+        data: R,
+        is_64: bool,
+    ) -> Result<DyldBindIterator<'data>> {
+        let data = data
+            .read_bytes_at(
+                self.weak_bind_off.get(endian).into(),
+                self.weak_bind_size.get(endian).into(),
+            )
+            .read_error("Invalid Mach-O weak bind info offset or size")?;
+        Ok(DyldBindIterator::weak_bind(data, is_64))
+    }
+
+    /// Return an iterator over the binds in the lazy bind information.
+    pub fn lazy_binds<'data, R: ReadRef<'data>>(
+        &self,
+        endian: E,
+        data: R,
+        is_64: bool,
+    ) -> Result<DyldBindIterator<'data>> {
+        let data = data
+            .read_bytes_at(
+                self.lazy_bind_off.get(endian).into(),
+                self.lazy_bind_size.get(endian).into(),
+            )
+            .read_error("Invalid Mach-O lazy bind info offset or size")?;
+        Ok(DyldBindIterator::lazy_bind(data, is_64))
+    }
+}
+
 impl<E: Endian> macho::LinkeditDataCommand<E> {
     /// Return an iterator over the function start addresses.
     ///
@@ -458,6 +542,46 @@ impl<E: Endian> macho::LinkeditDataCommand<E> {
             .read_error("Invalid exports trie offset or size")?;
         Ok(ExportsTrieIterator::new(data))
     }
+
+    /// Return the embedded code signature.
+    ///
+    /// Only works if the command is a `LC_CODE_SIGNATURE` command.
+    pub fn code_signature<'data, R: ReadRef<'data>>(
+        &self,
+        endian: E,
+        data: R,
+    ) -> Result<CodeSignature<'data>> {
+        if self.cmd.get(endian) != macho::LC_CODE_SIGNATURE {
+            return Err(Error("Not a code signature command"));
+        }
+        let data = data
+            .read_bytes_at(
+                self.dataoff.get(endian).into(),
+                self.datasize.get(endian).into(),
+            )
+            .read_error("Invalid code signature offset or size")?;
+        CodeSignature::parse(data)
+    }
+
+    /// Return the chained fixups.
+    ///
+    /// Only works if the command is a `LC_DYLD_CHAINED_FIXUPS` command.
+    pub fn chained_fixups<'data, R: ReadRef<'data>>(
+        &self,
+        endian: E,
+        data: R,
+    ) -> Result<ChainedFixups<'data, E>> {
+        if self.cmd.get(endian) != macho::LC_DYLD_CHAINED_FIXUPS {
+            return Err(Error("Not a chained fixups command"));
+        }
+        let data = data
+            .read_bytes_at(
+                self.dataoff.get(endian).into(),
+                self.datasize.get(endian).into(),
+            )
+            .read_error("Invalid chained fixups offset or size")?;
+        ChainedFixups::parse(endian, data)
+    }
 }
 
 #[cfg(test)]
@@ -47,9 +47,18 @@
 #[cfg(doc)]
 use crate::macho;
 
+mod chained_fixups;
+pub use chained_fixups::*;
+
+mod code_signature;
+pub use code_signature::*;
+
 mod dyld_cache;
 pub use dyld_cache::*;
 
+mod dyld_info;
+pub use dyld_info::*;
+
 mod exports_trie;
 pub use exports_trie::*;
 
@@ -1,13 +1,18 @@
+use alloc::vec::Vec;
 use core::{fmt, slice};
 
-use crate::endian::Endianness;
+use crate::endian::{Endianness, U32, U64};
 use crate::macho;
 use crate::read::{
     ReadRef, Relocation, RelocationEncoding, RelocationFlags, RelocationKind, RelocationTarget,
     SectionIndex, SymbolIndex,
 };
 
-use super::{MachHeader, MachOFile};
+use super::{
+    ChainedFixup, ChainedFixupIterator, ChainedFixupValue, ChainedFixups, ChainedSegmentIterator,
+    DyldBind, DyldBindIterator, DyldRebase, DyldRebaseIterator, MachHeader, MachOFile, Nlist,
+    Segment,
+};
 
 /// An iterator for the relocations in a [`MachOSection32`](super::MachOSection32).
 pub type MachORelocationIterator32<'data, 'file, Endian = Endianness, R = &'data [u8]> =
@@ -162,3 +167,313 @@ where
         f.debug_struct("MachORelocationIterator").finish()
     }
 }
+
+/// An iterator for the dynamic relocations in a [`MachOFile32`](super::MachOFile32).
+pub type MachODynamicRelocationIterator32<'data, 'file, Endian = Endianness, R = &'data [u8]> =
+    MachODynamicRelocationIterator<'data, 'file, macho::MachHeader32<Endian>, R>;
+/// An iterator for the dynamic relocations in a [`MachOFile64`](super::MachOFile64).
+pub type MachODynamicRelocationIterator64<'data, 'file, Endian = Endianness, R = &'data [u8]> =
+    MachODynamicRelocationIterator<'data, 'file, macho::MachHeader64<Endian>, R>;
+
+/// An iterator for the dynamic relocations in a [`MachOFile`].
+///
+/// This iterates over the fixups in the `LC_DYLD_CHAINED_FIXUPS` load command,
+/// or else the rebases and binds in the `LC_DYLD_INFO` load command.
+/// The offset of each relocation is the address of the fixup.
+///
+/// Rebases are returned as relocations with an absolute target and an addend of the
+/// unslid target address. Binds are returned as relocations that target the symbol
+/// in the symbol table with the same name as the import. Binds for imports that do
+/// not have a symbol are skipped, since there is no symbol index to return for them.
+/// Use [`Object::imports`](crate::read::Object::imports) to find all imports.
+pub struct MachODynamicRelocationIterator<'data, 'file, Mach, R = &'data [u8]>
+where
+    Mach: MachHeader,
+    R: ReadRef<'data>,
+{
+    file: &'file MachOFile<'data, Mach, R>,
+    image_base: u64,
+    /// The external symbols, sorted by name.
+    symbols: Vec<(&'data [u8], bool, usize)>,
+    /// The symbol and addend for each import in the chained fixups.
+    imports: Vec<Option<(SymbolIndex, i64)>>,
+    segments: Option<ChainedSegmentIterator<'data, Mach::Endian>>,
+    /// The fixups for the current segment, and the address of the segment.
+    fixups: Option<(ChainedFixupIterator<'data, Mach::Endian, R>, u64)>,
+    rebases: Option<DyldRebaseIterator<'data>>,
+    /// The bind, weak bind and lazy bind iterators, in that order.
+    binds: Vec<DyldBindIterator<'data>>,
+}
+
+impl<'data, 'file, Mach, R> MachODynamicRelocationIterator<'data, 'file, Mach, R>
+where
+    Mach: MachHeader,
+    R: ReadRef<'data>,
+{
+    fn new(file: &'file MachOFile<'data, Mach, R>) -> Self {
+        let endian = file.endian;
+        let image_base = file
+            .segments
+            .iter()
+            .find(|segment| {
+                segment.segment.fileoff(endian).into() == 0
+                    && segment.segment.filesize(endian).into() != 0
+            })
+            .map_or(0, |segment| segment.segment.vmaddr(endian).into());
+
+        // Sort the external symbols by name so that imports can be matched to them.
+        // Undefined symbols are preferred over definitions with the same name.
+        let strings = file.symbols.strings();
+        let mut symbols = Vec::new();
+        for (index, symbol) in file.symbols.iter().enumerate() {
+            if symbol.is_stab() || symbol.n_type() & macho::N_EXT == 0 {
+                continue;
+            }
+            if let Ok(name) = symbol.name(endian, strings) {
+                symbols.push((name, !symbol.is_undefined(), index));
+            }
+        }
+        symbols.sort_unstable();
+
+        MachODynamicRelocationIterator {
+            file,
+            image_base,
+            symbols,
+            imports: Vec::new(),
+            segments: None,
+            fixups: None,
+            rebases: None,
+            binds: Vec::new(),
+        }
+    }
+
+    pub(super) fn chained_fixups(
+        file: &'file MachOFile<'data, Mach, R>,
+        chained_fixups: ChainedFixups<'data, Mach::Endian>,
+    ) -> Self {
+        let mut iter = Self::new(file);
+        if let Ok(table) = chained_fixups.imports() {
+            for import in table.iter() {
+                let import = import
+                    .ok()
+                    .and_then(|import| Some((iter.symbol(import.name)?, import.addend)));
+                iter.imports.push(import);
+            }
+        }
+        iter.segments = chained_fixups.segments().ok();
+        iter
+    }
+
+    pub(super) fn dyld_info(
+        file: &'file MachOFile<'data, Mach, R>,
+        dyld_info: &'data macho::DyldInfoCommand<Mach::Endian>,
+    ) -> Self {
+        let endian = file.endian;
+        let data = file.data.0;
+        let is_64 = file.header.is_type_64();
+        let mut iter = Self::new(file);
+        iter.rebases = dyld_info.rebases(endian, data, is_64).ok();
+        iter.binds = [
+            dyld_info.binds(endian, data, is_64),
+            dyld_info.weak_binds(endian, data, is_64),
+            dyld_info.lazy_binds(endian, data, is_64),
+        ]
+        .iter()
+        .flatten()
+        .copied()
+        .collect();
+        iter
+    }
+
+    fn symbol(&self, name: &[u8]) -> Option<SymbolIndex> {
+        let index = self.symbols.partition_point(|symbol| symbol.0 < name);
+        let symbol = self.symbols.get(index).filter(|symbol| symbol.0 == name)?;
+        Some(SymbolIndex(symbol.2))
+    }
+
+    fn pointer_size(&self) -> u8 {
+        if self.file.header.is_type_64() {
+            64
+        } else {
+            32
+        }
+    }
+
+    fn relocation(
+        kind: RelocationKind,
+        size: u8,
+        target: RelocationTarget,
+        addend: i64,
+    ) -> Relocation {
+        Relocation {
+            kind,
+            encoding: RelocationEncoding::Generic,
+            size,
+            target,
+            subtractor: None,
+            addend,
+            implicit_addend: false,
+            flags: RelocationFlags::Generic {
+                kind,
+                encoding: RelocationEncoding::Generic,
+                size,
+            },
+        }
+    }
+
+    fn chained_fixup(&self, fixup: &ChainedFixup) -> Option<Relocation> {
+        let (target, addend) = match fixup.value {
+            ChainedFixupValue::Rebase(address) => (RelocationTarget::Absolute, address as i64),
+            ChainedFixupValue::Bind { ordinal, addend } => {
+                let (symbol, import_addend) = (*self.imports.get(ordinal as usize)?)?;
+                (
+                    RelocationTarget::Symbol(symbol),
+                    addend.wrapping_add(import_addend),
+                )
+            }
+        };
+        Some(Self::relocation(
+            RelocationKind::Absolute,
+            self.pointer_size(),
+            target,
+            addend,
+        ))
+    }
+
+    /// Return the address of a location in a segment, and its kind and size.
+    fn dyld_info_location(
+        &self,
+        segment_index: usize,
+        segment_offset: u64,
+        typ: u8,
+    ) -> Option<(u64, RelocationKind, u8)> {
+        let segment = self.file.segments.get(segment_index)?;
+        let address = segment
+            .segment
+            .vmaddr(self.file.endian)
+            .into()
+            .wrapping_add(segment_offset);
+        // The `REBASE_TYPE_*` and `BIND_TYPE_*` values are the same.
+        let (kind, size) = match typ {
+            macho::REBASE_TYPE_POINTER => (RelocationKind::Absolute, self.pointer_size()),
+            macho::REBASE_TYPE_TEXT_ABSOLUTE32 => (RelocationKind::Absolute, 32),
+            macho::REBASE_TYPE_TEXT_PCREL32 => (RelocationKind::Relative, 32),
+            _ => return None,
+        };
+        Some((address, kind, size))
+    }
+
+    fn rebase(&self, rebase: &DyldRebase) -> Option<(u64, Relocation)> {
+        let endian = self.file.endian;
+        let (address, kind, size) =
+            self.dyld_info_location(rebase.segment_index, rebase.segment_offset, rebase.typ)?;
+        // The location contains the unslid target address.
+        let segment = self.file.segments.get(rebase.segment_index)?;
+        let offset = segment
+            .segment
+            .fileoff(endian)
+            .into()
+            .checked_add(rebase.segment_offset)?;
+        let addend = if size == 64 {
+            segment
+                .data
+                .read_at::<U64<Mach::Endian>>(offset)
+                .ok()?
+                .get(endian) as i64
+        } else {
+            segment
+                .data
+                .read_at::<U32<Mach::Endian>>(offset)
+                .ok()?
+                .get(endian) as i32 as i64
+        };
+        Some((
+            address,
+            Self::relocation(kind, size, RelocationTarget::Absolute, addend),
+        ))
+    }
+
+    fn bind(&self, bind: &DyldBind<'data>) -> Option<(u64, Relocation)> {
+        let (address, kind, size) =
+            self.dyld_info_location(bind.segment_index, bind.segment_offset, bind.typ)?;
+        let symbol = self.symbol(bind.name)?;
+        Some((
+            address,
+            Self::relocation(kind, size, RelocationTarget::Symbol(symbol), bind.addend),
+        ))
+    }
+
+    fn next_dyld_info(&mut self) -> Option<(u64, Relocation)> {
+        while let Some(rebases) = self.rebases.as_mut() {
+            match rebases.next() {
+                Ok(Some(rebase)) => {
+                    if let Some(relocation) = self.rebase(&rebase) {
+                        return Some(relocation);
+                    }
+                }
+                _ => self.rebases = None,
+            }
+        }
+        while let Some(binds) = self.binds.first_mut() {
+            match binds.next() {
+                Ok(Some(bind)) => {
+                    if let Some(relocation) = self.bind(&bind) {
+                        return Some(relocation);
+                    }
+                }
+                _ => {
+                    self.binds.remove(0);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'data, 'file, Mach, R> Iterator for MachODynamicRelocationIterator<'data, 'file, Mach, R>
+where
+    Mach: MachHeader,
+    R: ReadRef<'data>,
+{
+    type Item = (u64, Relocation);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let endian = self.file.endian;
+        loop {
+            if let Some((ref mut fixups, address)) = self.fixups {
+                if let Ok(Some(fixup)) = fixups.next() {
+                    if let Some(relocation) = self.chained_fixup(&fixup) {
+                        return Some((address.wrapping_add(fixup.offset), relocation));
+                    }
+                    continue;
+                }
+                self.fixups = None;
+            }
+
+            let Some(segments) = self.segments.as_mut() else {
+                return self.next_dyld_info();
+            };
+            let starts = segments.next().ok()??;
+            let Some(segment) = self.file.segments.get(starts.segment_index()) else {
+                continue;
+            };
+            if let Ok(fixups) = starts.fixups(
+                segment.data,
+                segment.segment.fileoff(endian).into(),
+                self.image_base,
+            ) {
+                self.fixups = Some((fixups, segment.segment.vmaddr(endian).into()));
+            }
+        }
+    }
+}
+
+impl<'data, 'file, Mach, R> fmt::Debug for MachODynamicRelocationIterator<'data, 'file, Mach, R>
+where
+    Mach: MachHeader,
+    R: ReadRef<'data>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MachODynamicRelocationIterator").finish()
+    }
+}
@@ -298,6 +298,13 @@ pub trait Section: Debug + Pod {
     fn reserved1(&self, endian: Self::Endian) -> u32;
     fn reserved2(&self, endian: Self::Endian) -> u32;
 
+    /// Return the `reserved3` field.
+    ///
+    /// Only 64-bit sections have this field, so this returns 0 for 32-bit sections.
+    fn reserved3(&self, _endian: Self::Endian) -> u32 {
+        0
+    }
+
     /// Return the `sectname` bytes up until the null terminator.
     fn name(&self) -> &[u8] {
         let sectname = &self.sectname()[..];
@@ -477,4 +484,7 @@ impl<Endian: endian::Endian> Section for macho::Section64<Endian> {
     fn reserved2(&self, endian: Self::Endian) -> u32 {
         self.reserved2.get(endian)
     }
+    fn reserved3(&self, endian: Self::Endian) -> u32 {
+        self.reserved3.get(endian)
+    }
 }
@@ -65,7 +65,7 @@ mod util;
 pub use util::*;
 
 #[cfg(any(feature = "elf", feature = "macho"))]
-mod gnu_compression;
+pub(crate) mod gnu_compression;
 
 #[cfg(any(
     feature = "coff",
@@ -705,24 +705,6 @@ struct RelocationMapEntry {
     addend: u64,
 }
 
-/// A data compression format.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-#[non_exhaustive]
-pub enum CompressionFormat {
-    /// The data is uncompressed.
-    None,
-    /// The data is compressed, but the compression format is unknown.
-    Unknown,
-    /// ZLIB/DEFLATE.
-    ///
-    /// Used for ELF compression and GNU compressed debug information.
-    Zlib,
-    /// Zstandard.
-    ///
-    /// Used for ELF compression.
-    Zstandard,
-}
-
 /// A range in a file that may be compressed.
 ///
 /// Returned by [`ObjectSection::compressed_file_range`].
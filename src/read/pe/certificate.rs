@@ -0,0 +1,90 @@
+use crate::endian::LittleEndian as LE;
+use crate::pe;
+use crate::read::{Bytes, ReadError, Result};
+
+/// An iterator over the entries in the attribute certificate table of a PE file.
+///
+/// This is the table referenced by the `IMAGE_DIRECTORY_ENTRY_SECURITY` data directory.
+///
+/// Returned by [`DataDirectories::certificate_table`](super::DataDirectories::certificate_table).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CertificateIterator<'data> {
+    data: Bytes<'data>,
+}
+
+impl<'data> CertificateIterator<'data> {
+    /// Construct a new iterator from the data of the attribute certificate table.
+    pub fn new(data: &'data [u8]) -> Self {
+        CertificateIterator { data: Bytes(data) }
+    }
+
+    /// Read the next certificate.
+    pub fn next(&mut self) -> Result<Option<Certificate<'data>>> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+
+        let result = self.parse().map(Some);
+        if result.is_err() {
+            self.data = Bytes(&[]);
+        }
+        result
+    }
+
+    fn parse(&mut self) -> Result<Certificate<'data>> {
+        let mut data = self.data;
+        let header = data
+            .read::<pe::WinCertificate>()
+            .read_error("Invalid PE certificate header")?;
+        let length = header.length.get(LE) as usize;
+        let data = length
+            .checked_sub(core::mem::size_of::<pe::WinCertificate>())
+            .and_then(|len| data.read_bytes(len).ok())
+            .read_error("Invalid PE certificate length")?;
+        // Entries are aligned to 8 bytes. The padding may be missing after the last entry.
+        let aligned = (length + 7) & !7;
+        if self.data.skip(aligned).is_err() {
+            self.data = Bytes(&[]);
+        }
+        Ok(Certificate {
+            header,
+            data: data.0,
+        })
+    }
+}
+
+impl<'data> Iterator for CertificateIterator<'data> {
+    type Item = Result<Certificate<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// An entry in the attribute certificate table of a PE file.
+#[derive(Debug, Clone, Copy)]
+pub struct Certificate<'data> {
+    /// The certificate header.
+    pub header: &'data pe::WinCertificate,
+    /// The certificate data.
+    ///
+    /// For `WIN_CERT_TYPE_PKCS_SIGNED_DATA`, this is the DER encoded PKCS#7
+    /// `SignedData` structure that contains the Authenticode signature.
+    pub data: &'data [u8],
+}
+
+impl<'data> Certificate<'data> {
+    /// Return the revision.
+    ///
+    /// This is one of the `WIN_CERT_REVISION_*` values.
+    pub fn revision(&self) -> u16 {
+        self.header.revision.get(LE)
+    }
+
+    /// Return the certificate type.
+    ///
+    /// This is one of the `WIN_CERT_TYPE_*` values.
+    pub fn certificate_type(&self) -> u16 {
+        self.header.certificate_type.get(LE)
+    }
+}
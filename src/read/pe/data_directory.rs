@@ -2,11 +2,12 @@ use core::slice;
 
 use crate::endian::LittleEndian as LE;
 use crate::pe;
+use crate::pod;
 use crate::read::{Error, ReadError, ReadRef, Result};
 
 use super::{
-    DelayLoadImportTable, ExportTable, ImportTable, RelocationBlockIterator, ResourceDirectory,
-    SectionTable,
+    CertificateIterator, DebugDirectoryIterator, DelayLoadImportTable, ExceptionTable, ExportTable,
+    ImportTable, LoadConfig, RelocationBlockIterator, ResourceDirectory, SectionTable,
 };
 
 /// The table of data directories in a PE file.
@@ -165,6 +166,84 @@ impl<'data> DataDirectories<'data> {
         let rsrc_data = data_dir.data(data, sections)?;
         Ok(Some(ResourceDirectory::new(rsrc_data)))
     }
+
+    /// Returns the exception table.
+    ///
+    /// `data` must be the entire file data.
+    pub fn exception_table<R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<Option<ExceptionTable<'data>>> {
+        let data_dir = match self.get(pe::IMAGE_DIRECTORY_ENTRY_EXCEPTION) {
+            Some(data_dir) => data_dir,
+            None => return Ok(None),
+        };
+        let pdata = data_dir.data(data, sections)?;
+        Ok(Some(ExceptionTable::new(pdata)))
+    }
+
+    /// Returns the load configuration directory.
+    ///
+    /// `is_64` and `image_base` must be obtained from the optional header.
+    ///
+    /// `data` must be the entire file data.
+    pub fn load_config<R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+        is_64: bool,
+        image_base: u64,
+    ) -> Result<Option<LoadConfig>> {
+        let data_dir = match self.get(pe::IMAGE_DIRECTORY_ENTRY_LOAD_CONFIG) {
+            Some(data_dir) => data_dir,
+            None => return Ok(None),
+        };
+        // The size of the data directory is unreliable, so use the size stored in the
+        // directory itself.
+        let config_data = sections
+            .pe_data_at(data, data_dir.virtual_address.get(LE))
+            .read_error("Invalid PE load config address")?;
+        LoadConfig::parse(config_data, is_64, image_base).map(Some)
+    }
+
+    /// Returns the entries in the debug directory.
+    ///
+    /// `data` must be the entire file data.
+    pub fn debug_directory<R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<Option<DebugDirectoryIterator<'data, R>>> {
+        let data_dir = match self.get(pe::IMAGE_DIRECTORY_ENTRY_DEBUG) {
+            Some(data_dir) => data_dir,
+            None => return Ok(None),
+        };
+        let debug_data = data_dir.data(data, sections)?;
+        let entries = pod::slice_from_all_bytes::<pe::ImageDebugDirectory>(debug_data)
+            .read_error("Invalid PE debug dir size")?;
+        Ok(Some(DebugDirectoryIterator::new(entries, data, *sections)))
+    }
+
+    /// Returns the entries in the attribute certificate table.
+    ///
+    /// The `virtual_address` of this data directory is a file offset.
+    ///
+    /// `data` must be the entire file data.
+    pub fn certificate_table<R: ReadRef<'data>>(
+        &self,
+        data: R,
+    ) -> Result<Option<CertificateIterator<'data>>> {
+        let data_dir = match self.get(pe::IMAGE_DIRECTORY_ENTRY_SECURITY) {
+            Some(data_dir) => data_dir,
+            None => return Ok(None),
+        };
+        let (offset, size) = data_dir.address_range();
+        let certificate_data = data
+            .read_bytes_at(offset.into(), size.into())
+            .read_error("Invalid PE certificate table range")?;
+        Ok(Some(CertificateIterator::new(certificate_data)))
+    }
 }
 
 impl pe::ImageDataDirectory {
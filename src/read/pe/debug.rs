@@ -0,0 +1,285 @@
+use core::slice;
+
+use crate::endian::{LittleEndian as LE, U32};
+use crate::pe;
+use crate::read::{ByteString, Bytes, CodeView, Error, ReadError, ReadRef, Result};
+
+use super::SectionTable;
+
+/// An iterator over the entries in the debug directory of a PE file.
+///
+/// Returned by [`DataDirectories::debug_directory`](super::DataDirectories::debug_directory).
+#[derive(Debug, Clone)]
+pub struct DebugDirectoryIterator<'data, R: ReadRef<'data> = &'data [u8]> {
+    entries: slice::Iter<'data, pe::ImageDebugDirectory>,
+    data: R,
+    sections: SectionTable<'data>,
+}
+
+impl<'data, R: ReadRef<'data>> DebugDirectoryIterator<'data, R> {
+    /// Construct a new iterator from the entries of the debug directory.
+    ///
+    /// `data` must be the entire file data. `sections` are used to locate the data
+    /// of entries that do not have a file offset.
+    pub fn new(
+        entries: &'data [pe::ImageDebugDirectory],
+        data: R,
+        sections: SectionTable<'data>,
+    ) -> Self {
+        DebugDirectoryIterator {
+            entries: entries.iter(),
+            data,
+            sections,
+        }
+    }
+
+    /// Read the next debug directory entry.
+    pub fn next(&mut self) -> Result<Option<DebugEntry<'data>>> {
+        let Some(directory) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.entry(directory).map(Some)
+    }
+
+    /// Read the next debug directory entry with the given type.
+    ///
+    /// The data for entries of other types is not read, so errors in those
+    /// entries are ignored.
+    pub(super) fn next_of_type(&mut self, typ: u32) -> Result<Option<DebugEntry<'data>>> {
+        while let Some(directory) = self.entries.next() {
+            if directory.typ.get(LE) == typ {
+                return self.entry(directory).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn entry(&self, directory: &'data pe::ImageDebugDirectory) -> Result<DebugEntry<'data>> {
+        let size = directory.size_of_data.get(LE);
+        let offset = directory.pointer_to_raw_data.get(LE);
+        let address = directory.address_of_raw_data.get(LE);
+        let data = if size == 0 {
+            &[][..]
+        } else if offset != 0 {
+            self.data
+                .read_bytes_at(offset.into(), size.into())
+                .read_error("Invalid PE debug data range")?
+        } else {
+            self.sections
+                .pe_data_at(self.data, address)
+                .and_then(|data| data.get(..size as usize))
+                .read_error("Invalid PE debug data address")?
+        };
+        Ok(DebugEntry { directory, data })
+    }
+}
+
+impl<'data, R: ReadRef<'data>> Iterator for DebugDirectoryIterator<'data, R> {
+    type Item = Result<DebugEntry<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// An entry in the debug directory of a PE file.
+#[derive(Debug, Clone, Copy)]
+pub struct DebugEntry<'data> {
+    /// The debug directory entry.
+    pub directory: &'data pe::ImageDebugDirectory,
+    /// The data referenced by the entry.
+    pub data: &'data [u8],
+}
+
+impl<'data> DebugEntry<'data> {
+    /// Return the type of the entry.
+    ///
+    /// This is one of the `IMAGE_DEBUG_TYPE_*` values.
+    pub fn typ(&self) -> u32 {
+        self.directory.typ.get(LE)
+    }
+
+    /// Decode the data of the entry.
+    ///
+    /// Returns [`DebugInfo::Other`] for types that are not decoded.
+    pub fn info(&self) -> Result<DebugInfo<'data>> {
+        let mut data = Bytes(self.data);
+        match self.typ() {
+            pe::IMAGE_DEBUG_TYPE_CODEVIEW => {
+                let sig = data
+                    .read_bytes(4)
+                    .read_error("Invalid CodeView signature")?;
+                if sig.0 != pe::CODEVIEW_SIGNATURE_RSDS {
+                    return Ok(DebugInfo::Other);
+                }
+                let guid = *data
+                    .read::<[u8; 16]>()
+                    .read_error("Invalid CodeView GUID")?;
+                let age = data.read::<U32<LE>>().read_error("Invalid CodeView Age")?;
+                let path = data
+                    .read_string()
+                    .read_error("Invalid CodeView file path")?;
+                Ok(DebugInfo::CodeView(CodeView {
+                    path: ByteString(path),
+                    guid,
+                    age: age.get(LE),
+                }))
+            }
+            pe::IMAGE_DEBUG_TYPE_POGO => {
+                let signature = data
+                    .read::<U32<LE>>()
+                    .read_error("Invalid PE POGO signature")?;
+                Ok(DebugInfo::Pogo(PogoIterator {
+                    signature: signature.get(LE),
+                    data,
+                }))
+            }
+            pe::IMAGE_DEBUG_TYPE_VC_FEATURE => data
+                .read::<pe::ImageDebugVcFeature>()
+                .read_error("Invalid PE VC feature size")
+                .map(DebugInfo::VcFeature),
+            pe::IMAGE_DEBUG_TYPE_REPRO => {
+                // Older linkers emit an entry without any data.
+                if data.is_empty() {
+                    return Ok(DebugInfo::Repro(&[]));
+                }
+                let len = data
+                    .read::<U32<LE>>()
+                    .read_error("Invalid PE repro hash size")?;
+                data.read_bytes(len.get(LE) as usize)
+                    .read_error("Invalid PE repro hash size")
+                    .map(|hash| DebugInfo::Repro(hash.0))
+            }
+            pe::IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS => data
+                .read::<U32<LE>>()
+                .read_error("Invalid PE extended DLL characteristics size")
+                .map(|value| DebugInfo::ExDllCharacteristics(value.get(LE))),
+            pe::IMAGE_DEBUG_TYPE_EMBEDDED_PORTABLE_PDB => {
+                let sig = data
+                    .read_bytes(4)
+                    .read_error("Invalid PE embedded PDB signature")?;
+                if sig.0 != pe::IMAGE_DEBUG_EMBEDDED_PORTABLE_PDB_SIGNATURE {
+                    return Err(Error("Invalid PE embedded PDB signature"));
+                }
+                let uncompressed_size = data
+                    .read::<U32<LE>>()
+                    .read_error("Invalid PE embedded PDB size")?;
+                Ok(DebugInfo::EmbeddedPortablePdb(EmbeddedPortablePdb {
+                    uncompressed_size: uncompressed_size.get(LE),
+                    data: data.0,
+                }))
+            }
+            _ => Ok(DebugInfo::Other),
+        }
+    }
+}
+
+/// The decoded data of a [`DebugEntry`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum DebugInfo<'data> {
+    /// `IMAGE_DEBUG_TYPE_CODEVIEW` with an `RSDS` signature.
+    CodeView(CodeView<'data>),
+    /// `IMAGE_DEBUG_TYPE_POGO`.
+    Pogo(PogoIterator<'data>),
+    /// `IMAGE_DEBUG_TYPE_VC_FEATURE`.
+    VcFeature(&'data pe::ImageDebugVcFeature),
+    /// `IMAGE_DEBUG_TYPE_REPRO`.
+    ///
+    /// Contains the hash that the linker used in place of timestamps.
+    /// This is empty if the entry does not contain a hash.
+    Repro(&'data [u8]),
+    /// `IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS`.
+    ///
+    /// This is a combination of `IMAGE_DLLCHARACTERISTICS_EX_*` flags.
+    ExDllCharacteristics(u32),
+    /// `IMAGE_DEBUG_TYPE_EMBEDDED_PORTABLE_PDB`.
+    EmbeddedPortablePdb(EmbeddedPortablePdb<'data>),
+    /// Any other entry, including CodeView data with an unsupported signature.
+    Other,
+}
+
+/// An iterator over the entries in `IMAGE_DEBUG_TYPE_POGO` data.
+///
+/// Each entry describes a range of an image section that was arranged by the linker.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PogoIterator<'data> {
+    signature: u32,
+    data: Bytes<'data>,
+}
+
+impl<'data> PogoIterator<'data> {
+    /// Return the signature of the POGO data.
+    ///
+    /// This is usually one of the `IMAGE_DEBUG_POGO_SIGNATURE_*` values.
+    pub fn signature(&self) -> u32 {
+        self.signature
+    }
+
+    /// Read the next POGO entry.
+    pub fn next(&mut self) -> Result<Option<PogoEntry<'data>>> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+
+        let result = self.parse().map(Some);
+        if result.is_err() {
+            self.data = Bytes(&[]);
+        }
+        result
+    }
+
+    fn parse(&mut self) -> Result<PogoEntry<'data>> {
+        let rva = self
+            .data
+            .read::<U32<LE>>()
+            .read_error("Invalid PE POGO entry size")?;
+        let size = self
+            .data
+            .read::<U32<LE>>()
+            .read_error("Invalid PE POGO entry size")?;
+        let name = self
+            .data
+            .read_string()
+            .read_error("Invalid PE POGO entry name")?;
+        // Names are padded to a multiple of 4 bytes. The padding may be missing
+        // after the last entry.
+        let padding = (4 - (name.len() + 1) % 4) % 4;
+        if self.data.skip(padding).is_err() {
+            self.data = Bytes(&[]);
+        }
+        Ok(PogoEntry {
+            rva: rva.get(LE),
+            size: size.get(LE),
+            name,
+        })
+    }
+}
+
+impl<'data> Iterator for PogoIterator<'data> {
+    type Item = Result<PogoEntry<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// An entry in `IMAGE_DEBUG_TYPE_POGO` data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PogoEntry<'data> {
+    /// The relative virtual address of the start of the range.
+    pub rva: u32,
+    /// The size of the range.
+    pub size: u32,
+    /// The name of the range, such as `.text$mn`.
+    pub name: &'data [u8],
+}
+
+/// The data of an `IMAGE_DEBUG_TYPE_EMBEDDED_PORTABLE_PDB` entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddedPortablePdb<'data> {
+    /// The size of the PDB after decompression.
+    pub uncompressed_size: u32,
+    /// The PDB compressed with raw deflate.
+    pub data: &'data [u8],
+}
@@ -0,0 +1,732 @@
+use core::slice;
+
+use crate::endian::{LittleEndian as LE, U32};
+use crate::pe;
+use crate::pod;
+use crate::read::{Bytes, Error, ReadError, ReadRef, Result};
+
+use super::SectionTable;
+
+/// The exception table of a PE file.
+///
+/// This is the `.pdata` data referenced by the `IMAGE_DIRECTORY_ENTRY_EXCEPTION`
+/// data directory. The format of the entries depends on the machine type.
+///
+/// Returned by [`DataDirectories::exception_table`](super::DataDirectories::exception_table).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExceptionTable<'data> {
+    data: &'data [u8],
+}
+
+impl<'data> ExceptionTable<'data> {
+    /// Construct a new exception table from the data of the `.pdata` section.
+    pub fn new(data: &'data [u8]) -> Self {
+        ExceptionTable { data }
+    }
+
+    /// Return the function table entries for an x86-64 image.
+    pub fn x64_functions(&self) -> Result<&'data [pe::ImageRuntimeFunctionEntry]> {
+        pod::slice_from_all_bytes(self.data).read_error("Invalid PE exception table size")
+    }
+
+    /// Return the function table entries for an AArch64 image.
+    pub fn arm64_functions(&self) -> Result<&'data [pe::ImageArm64RuntimeFunctionEntry]> {
+        pod::slice_from_all_bytes(self.data).read_error("Invalid PE exception table size")
+    }
+}
+
+impl pe::ImageRuntimeFunctionEntry {
+    /// Parse the unwind information for this function.
+    ///
+    /// `data` must be the entire file data.
+    pub fn unwind_info<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<X64UnwindInfo<'data>> {
+        let address = self.unwind_info_address_or_data.get(LE);
+        let unwind_data = sections
+            .pe_data_at(data, address)
+            .read_error("Invalid PE unwind info address")?;
+        X64UnwindInfo::parse(unwind_data)
+    }
+}
+
+/// The unwind information for a function in an x86-64 image.
+///
+/// Returned by [`pe::ImageRuntimeFunctionEntry::unwind_info`].
+#[derive(Debug, Clone, Copy)]
+pub struct X64UnwindInfo<'data> {
+    header: &'data pe::UnwindInfo,
+    codes: &'data [pe::UnwindCode],
+    chained: Option<&'data pe::ImageRuntimeFunctionEntry>,
+    handler: Option<u32>,
+    handler_data: &'data [u8],
+}
+
+impl<'data> X64UnwindInfo<'data> {
+    /// Parse the unwind information at the start of the given data.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let mut data = Bytes(data);
+        let header = data
+            .read::<pe::UnwindInfo>()
+            .read_error("Invalid PE unwind info size")?;
+        let count = usize::from(header.count_of_codes);
+        let codes = data
+            .read_slice::<pe::UnwindCode>(count)
+            .read_error("Invalid PE unwind code count")?;
+        // The array of unwind codes is padded to an even number of slots.
+        if count % 2 != 0 {
+            data.skip(2).read_error("Invalid PE unwind code count")?;
+        }
+        let flags = header.version_flags >> 3;
+        let mut chained = None;
+        let mut handler = None;
+        if flags & pe::UNW_FLAG_CHAININFO != 0 {
+            chained = Some(
+                data.read::<pe::ImageRuntimeFunctionEntry>()
+                    .read_error("Invalid PE chained unwind info")?,
+            );
+        } else if flags & (pe::UNW_FLAG_EHANDLER | pe::UNW_FLAG_UHANDLER) != 0 {
+            handler = Some(
+                data.read::<U32<LE>>()
+                    .read_error("Invalid PE unwind handler address")?
+                    .get(LE),
+            );
+        }
+        let handler_data = if handler.is_some() { data.0 } else { &[] };
+        Ok(X64UnwindInfo {
+            header,
+            codes,
+            chained,
+            handler,
+            handler_data,
+        })
+    }
+
+    /// Return the version of the unwind information.
+    pub fn version(&self) -> u8 {
+        self.header.version_flags & 0x7
+    }
+
+    /// Return the `UNW_FLAG_*` flags.
+    pub fn flags(&self) -> u8 {
+        self.header.version_flags >> 3
+    }
+
+    /// Return the length of the function prolog in bytes.
+    pub fn size_of_prolog(&self) -> u8 {
+        self.header.size_of_prolog
+    }
+
+    /// Return the number of unwind code slots.
+    pub fn count_of_codes(&self) -> u8 {
+        self.header.count_of_codes
+    }
+
+    /// Return the frame register, or 0 if the function does not use a frame pointer.
+    ///
+    /// This uses the same register numbering as [`X64UnwindOperation::PushNonvol`].
+    pub fn frame_register(&self) -> u8 {
+        self.header.frame_register_offset & 0xf
+    }
+
+    /// Return the offset from RSP that is applied to the frame register when it is established.
+    pub fn frame_offset(&self) -> u32 {
+        u32::from(self.header.frame_register_offset >> 4) * 16
+    }
+
+    /// Return an iterator over the unwind codes.
+    pub fn codes(&self) -> X64UnwindCodeIterator<'data> {
+        X64UnwindCodeIterator {
+            codes: self.codes.iter(),
+        }
+    }
+
+    /// Return the function table entry containing the primary unwind information,
+    /// if this is chained unwind information.
+    pub fn chained_function(&self) -> Option<&'data pe::ImageRuntimeFunctionEntry> {
+        self.chained
+    }
+
+    /// Return the address of the exception or termination handler, if any.
+    pub fn handler(&self) -> Option<u32> {
+        self.handler
+    }
+
+    /// Return the language specific handler data that follows the handler address.
+    ///
+    /// The size of this data is not recorded, so this extends to the end of the section.
+    pub fn handler_data(&self) -> &'data [u8] {
+        self.handler_data
+    }
+}
+
+/// An iterator over the unwind codes in [`X64UnwindInfo`].
+///
+/// The codes are in reverse order of the prolog instructions that they describe.
+#[derive(Debug, Clone)]
+pub struct X64UnwindCodeIterator<'data> {
+    codes: slice::Iter<'data, pe::UnwindCode>,
+}
+
+impl<'data> X64UnwindCodeIterator<'data> {
+    /// Read the next unwind code.
+    pub fn next(&mut self) -> Result<Option<X64UnwindCode>> {
+        let Some(code) = self.codes.next() else {
+            return Ok(None);
+        };
+        let result = self.parse(code).map(Some);
+        if result.is_err() {
+            self.codes = [].iter();
+        }
+        result
+    }
+
+    fn slot(&mut self) -> Result<u32> {
+        let slot = self
+            .codes
+            .next()
+            .read_error("Missing PE unwind code operand")?;
+        Ok(u32::from(slot.code_offset) | u32::from(slot.unwind_op_info) << 8)
+    }
+
+    fn parse(&mut self, code: &pe::UnwindCode) -> Result<X64UnwindCode> {
+        let op = code.unwind_op_info & 0xf;
+        let info = code.unwind_op_info >> 4;
+        let operation = match op {
+            pe::UWOP_PUSH_NONVOL => X64UnwindOperation::PushNonvol(info),
+            pe::UWOP_ALLOC_LARGE => match info {
+                0 => X64UnwindOperation::Alloc(self.slot()? * 8),
+                1 => X64UnwindOperation::Alloc(self.slot()? | self.slot()? << 16),
+                _ => return Err(Error("Invalid PE unwind code info")),
+            },
+            pe::UWOP_ALLOC_SMALL => X64UnwindOperation::Alloc(u32::from(info) * 8 + 8),
+            pe::UWOP_SET_FPREG => X64UnwindOperation::SetFpreg,
+            pe::UWOP_SAVE_NONVOL => X64UnwindOperation::SaveNonvol {
+                register: info,
+                offset: self.slot()? * 8,
+            },
+            pe::UWOP_SAVE_NONVOL_FAR => X64UnwindOperation::SaveNonvol {
+                register: info,
+                offset: self.slot()? | self.slot()? << 16,
+            },
+            pe::UWOP_EPILOG => {
+                self.slot()?;
+                X64UnwindOperation::Other { op, info }
+            }
+            pe::UWOP_SPARE_CODE => {
+                self.slot()?;
+                self.slot()?;
+                X64UnwindOperation::Other { op, info }
+            }
+            pe::UWOP_SAVE_XMM128 => X64UnwindOperation::SaveXmm128 {
+                register: info,
+                offset: self.slot()? * 16,
+            },
+            pe::UWOP_SAVE_XMM128_FAR => X64UnwindOperation::SaveXmm128 {
+                register: info,
+                offset: self.slot()? | self.slot()? << 16,
+            },
+            pe::UWOP_PUSH_MACHFRAME => X64UnwindOperation::PushMachframe {
+                error_code: info != 0,
+            },
+            _ => return Err(Error("Unknown PE unwind code operation")),
+        };
+        Ok(X64UnwindCode {
+            code_offset: code.code_offset,
+            operation,
+        })
+    }
+}
+
+impl<'data> Iterator for X64UnwindCodeIterator<'data> {
+    type Item = Result<X64UnwindCode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// A decoded x86-64 unwind code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct X64UnwindCode {
+    /// The offset of the end of the prolog instruction that this code describes.
+    pub code_offset: u8,
+    /// The operation performed by the prolog instruction.
+    pub operation: X64UnwindOperation,
+}
+
+/// An operation in an [`X64UnwindCode`].
+///
+/// Integer registers are numbered in the order RAX, RCX, RDX, RBX, RSP, RBP,
+/// RSI, RDI, R8 to R15.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum X64UnwindOperation {
+    /// `UWOP_PUSH_NONVOL`: push a nonvolatile integer register.
+    PushNonvol(u8),
+    /// `UWOP_ALLOC_SMALL` or `UWOP_ALLOC_LARGE`: allocate the given number of bytes on the stack.
+    Alloc(u32),
+    /// `UWOP_SET_FPREG`: establish the frame register.
+    ///
+    /// The register and offset are given by [`X64UnwindInfo::frame_register`]
+    /// and [`X64UnwindInfo::frame_offset`].
+    SetFpreg,
+    /// `UWOP_SAVE_NONVOL` or `UWOP_SAVE_NONVOL_FAR`: save an integer register
+    /// at the given offset from the stack pointer.
+    SaveNonvol {
+        /// The register number.
+        register: u8,
+        /// The offset in bytes.
+        offset: u32,
+    },
+    /// `UWOP_SAVE_XMM128` or `UWOP_SAVE_XMM128_FAR`: save an XMM register at the
+    /// given offset from the stack pointer.
+    SaveXmm128 {
+        /// The XMM register number.
+        register: u8,
+        /// The offset in bytes.
+        offset: u32,
+    },
+    /// `UWOP_PUSH_MACHFRAME`: push a machine frame, optionally including an error code.
+    PushMachframe {
+        /// Whether an error code was pushed.
+        error_code: bool,
+    },
+    /// Any other operation, such as `UWOP_EPILOG`.
+    Other {
+        /// The `UWOP_*` operation.
+        op: u8,
+        /// The operation info.
+        info: u8,
+    },
+}
+
+impl pe::ImageArm64RuntimeFunctionEntry {
+    /// Return the `ARM64_PDATA_*` flag stored in the low bits of the unwind data.
+    pub fn flag(&self) -> u32 {
+        self.unwind_data.get(LE) & 0x3
+    }
+
+    /// Parse the unwind data for this function.
+    ///
+    /// This is either packed into the function table entry, or is stored in a
+    /// separate `.xdata` record.
+    ///
+    /// `data` must be the entire file data.
+    pub fn unwind_data<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<Arm64UnwindData<'data>> {
+        let unwind_data = self.unwind_data.get(LE);
+        match self.flag() {
+            pe::ARM64_PDATA_REF_TO_FULL_XDATA => {
+                let xdata = sections
+                    .pe_data_at(data, unwind_data)
+                    .read_error("Invalid PE unwind info address")?;
+                Arm64UnwindInfo::parse(xdata).map(Arm64UnwindData::Full)
+            }
+            pe::ARM64_PDATA_PACKED_UNWIND_FUNCTION | pe::ARM64_PDATA_PACKED_UNWIND_FRAGMENT => {
+                Ok(Arm64UnwindData::Packed(Arm64PackedUnwind {
+                    flag: unwind_data & 0x3,
+                    function_length: ((unwind_data >> 2) & 0x7ff) * 4,
+                    reg_f: ((unwind_data >> 13) & 0x7) as u8,
+                    reg_i: ((unwind_data >> 16) & 0xf) as u8,
+                    h: (unwind_data >> 20) & 0x1 != 0,
+                    cr: ((unwind_data >> 21) & 0x3) as u8,
+                    frame_size: (unwind_data >> 23) * 16,
+                }))
+            }
+            _ => Err(Error("Invalid PE ARM64 unwind data flag")),
+        }
+    }
+}
+
+/// The unwind data for a function in an AArch64 image.
+///
+/// Returned by [`pe::ImageArm64RuntimeFunctionEntry::unwind_data`].
+#[derive(Debug, Clone, Copy)]
+pub enum Arm64UnwindData<'data> {
+    /// Unwind data that is packed into the function table entry.
+    Packed(Arm64PackedUnwind),
+    /// Unwind data that is stored in an `.xdata` record.
+    Full(Arm64UnwindInfo<'data>),
+}
+
+/// Packed unwind data for a function in an AArch64 image.
+///
+/// The fields describe a canonical prolog and epilog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arm64PackedUnwind {
+    /// One of `ARM64_PDATA_PACKED_UNWIND_FUNCTION` or `ARM64_PDATA_PACKED_UNWIND_FRAGMENT`.
+    pub flag: u32,
+    /// The length of the function in bytes.
+    pub function_length: u32,
+    /// The `RegF` field, which encodes the number of saved FP registers.
+    pub reg_f: u8,
+    /// The `RegI` field, which is the number of saved nonvolatile integer registers.
+    pub reg_i: u8,
+    /// Whether the integer parameter registers are homed.
+    pub h: bool,
+    /// The `CR` field, which describes how the frame and link registers are saved.
+    pub cr: u8,
+    /// The number of bytes of stack allocated for the frame.
+    pub frame_size: u32,
+}
+
+/// An `.xdata` record for a function in an AArch64 image.
+#[derive(Debug, Clone, Copy)]
+pub struct Arm64UnwindInfo<'data> {
+    header: u32,
+    epilog_count: u16,
+    epilog_scopes: &'data [U32<LE>],
+    codes: &'data [u8],
+    handler: Option<u32>,
+    handler_data: &'data [u8],
+}
+
+impl<'data> Arm64UnwindInfo<'data> {
+    /// Parse the `.xdata` record at the start of the given data.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let mut data = Bytes(data);
+        let header = data
+            .read::<U32<LE>>()
+            .read_error("Invalid PE ARM64 unwind info size")?
+            .get(LE);
+        let mut epilog_count = ((header >> 22) & 0x1f) as u16;
+        let mut code_words = header >> 27;
+        if epilog_count == 0 && code_words == 0 {
+            let extended = data
+                .read::<U32<LE>>()
+                .read_error("Invalid PE ARM64 unwind info size")?
+                .get(LE);
+            epilog_count = extended as u16;
+            code_words = (extended >> 16) & 0xff;
+        }
+        // If the E bit is set, the epilog count is the code index of a single epilog.
+        let scope_count = if header & (1 << 21) != 0 {
+            0
+        } else {
+            usize::from(epilog_count)
+        };
+        let epilog_scopes = data
+            .read_slice::<U32<LE>>(scope_count)
+            .read_error("Invalid PE ARM64 epilog count")?;
+        let codes = data
+            .read_bytes(code_words as usize * 4)
+            .read_error("Invalid PE ARM64 unwind code words")?
+            .0;
+        let mut handler = None;
+        if header & (1 << 20) != 0 {
+            handler = Some(
+                data.read::<U32<LE>>()
+                    .read_error("Invalid PE unwind handler address")?
+                    .get(LE),
+            );
+        }
+        let handler_data = if handler.is_some() { data.0 } else { &[] };
+        Ok(Arm64UnwindInfo {
+            header,
+            epilog_count,
+            epilog_scopes,
+            codes,
+            handler,
+            handler_data,
+        })
+    }
+
+    /// Return the length of the function in bytes.
+    pub fn function_length(&self) -> u32 {
+        (self.header & 0x3ffff) * 4
+    }
+
+    /// Return the version of the record.
+    pub fn version(&self) -> u8 {
+        ((self.header >> 18) & 0x3) as u8
+    }
+
+    /// Return the index of the unwind code for a single epilog that is described
+    /// by the prolog codes.
+    ///
+    /// Returns `None` if the epilogs are described by [`Self::epilog_scopes`] instead.
+    pub fn packed_epilog(&self) -> Option<u16> {
+        if self.header & (1 << 21) != 0 {
+            Some(self.epilog_count)
+        } else {
+            None
+        }
+    }
+
+    /// Return an iterator over the epilog scopes.
+    pub fn epilog_scopes(&self) -> impl Iterator<Item = Arm64EpilogScope> + 'data {
+        self.epilog_scopes.iter().map(|scope| {
+            let scope = scope.get(LE);
+            Arm64EpilogScope {
+                start_offset: (scope & 0x3ffff) * 4,
+                start_index: (scope >> 22) as u16,
+            }
+        })
+    }
+
+    /// Return the raw unwind code bytes.
+    pub fn code_bytes(&self) -> &'data [u8] {
+        self.codes
+    }
+
+    /// Return an iterator over the unwind codes.
+    ///
+    /// This iterates over the codes for both the prolog and the epilogs.
+    pub fn codes(&self) -> Arm64UnwindCodeIterator<'data> {
+        Arm64UnwindCodeIterator {
+            data: Bytes(self.codes),
+            offset: 0,
+        }
+    }
+
+    /// Return the address of the exception handler, if any.
+    pub fn handler(&self) -> Option<u32> {
+        self.handler
+    }
+
+    /// Return the language specific handler data that follows the handler address.
+    ///
+    /// The size of this data is not recorded, so this extends to the end of the section.
+    pub fn handler_data(&self) -> &'data [u8] {
+        self.handler_data
+    }
+}
+
+/// An epilog scope in an [`Arm64UnwindInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arm64EpilogScope {
+    /// The offset in bytes of the epilog relative to the start of the function.
+    pub start_offset: u32,
+    /// The byte index of the first unwind code that describes the epilog.
+    pub start_index: u16,
+}
+
+/// An iterator over the unwind codes in [`Arm64UnwindInfo`].
+#[derive(Debug, Clone, Copy)]
+pub struct Arm64UnwindCodeIterator<'data> {
+    data: Bytes<'data>,
+    offset: usize,
+}
+
+impl<'data> Arm64UnwindCodeIterator<'data> {
+    /// Read the next unwind code.
+    ///
+    /// Returns the byte index of the code and the decoded code.
+    pub fn next(&mut self) -> Result<Option<(usize, Arm64UnwindCode<'data>)>> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+
+        let offset = self.offset;
+        let result = self.parse().map(|code| Some((offset, code)));
+        if result.is_err() {
+            self.data = Bytes(&[]);
+        }
+        result
+    }
+
+    fn parse(&mut self) -> Result<Arm64UnwindCode<'data>> {
+        let first = self.data.0[0];
+        let len = match first {
+            0x00..=0xbf => 1,
+            0xc0..=0xdf => 2,
+            0xe0 => 4,
+            0xe1 => 1,
+            0xe2 => 2,
+            0xe3..=0xe6 => 1,
+            0xe7 => 3,
+            0xe8..=0xf7 => 1,
+            0xf8 => 2,
+            0xf9 => 3,
+            0xfa => 4,
+            0xfb => 5,
+            0xfc..=0xff => 1,
+        };
+        let bytes = self
+            .data
+            .read_bytes(len)
+            .read_error("Invalid PE ARM64 unwind code size")?
+            .0;
+        self.offset += len;
+
+        let value = bytes
+            .iter()
+            .fold(0u32, |value, byte| value << 8 | u32::from(*byte));
+        let z = |bits: u32| (value & ((1 << bits) - 1)) as u16 * 8;
+        let z_x = |bits: u32| ((value & ((1 << bits) - 1)) as u16 + 1) * 8;
+        let code = match first {
+            0x00..=0x1f => Arm64UnwindCode::Alloc(u32::from(first & 0x1f) * 16),
+            0x20..=0x3f => Arm64UnwindCode::SaveRegPair {
+                register: 19,
+                offset: z(5),
+                pre_index: true,
+            },
+            0x40..=0x7f => Arm64UnwindCode::SaveRegPair {
+                register: 29,
+                offset: z(6),
+                pre_index: false,
+            },
+            0x80..=0xbf => Arm64UnwindCode::SaveRegPair {
+                register: 29,
+                offset: z_x(6),
+                pre_index: true,
+            },
+            0xc0..=0xc7 => Arm64UnwindCode::Alloc((value & 0x7ff) * 16),
+            0xc8..=0xcb => Arm64UnwindCode::SaveRegPair {
+                register: 19 + ((value >> 6) & 0xf) as u8,
+                offset: z(6),
+                pre_index: false,
+            },
+            0xcc..=0xcf => Arm64UnwindCode::SaveRegPair {
+                register: 19 + ((value >> 6) & 0xf) as u8,
+                offset: z_x(6),
+                pre_index: true,
+            },
+            0xd0..=0xd3 => Arm64UnwindCode::SaveReg {
+                register: 19 + ((value >> 6) & 0xf) as u8,
+                offset: z(6),
+                pre_index: false,
+            },
+            0xd4..=0xd5 => Arm64UnwindCode::SaveReg {
+                register: 19 + ((value >> 5) & 0xf) as u8,
+                offset: z_x(5),
+                pre_index: true,
+            },
+            0xd6..=0xd7 => Arm64UnwindCode::SaveLrPair {
+                register: 19 + 2 * ((value >> 6) & 0x7) as u8,
+                offset: z(6),
+            },
+            0xd8..=0xd9 => Arm64UnwindCode::SaveFRegPair {
+                register: 8 + ((value >> 6) & 0x7) as u8,
+                offset: z(6),
+                pre_index: false,
+            },
+            0xda..=0xdb => Arm64UnwindCode::SaveFRegPair {
+                register: 8 + ((value >> 6) & 0x7) as u8,
+                offset: z_x(6),
+                pre_index: true,
+            },
+            0xdc..=0xdd => Arm64UnwindCode::SaveFReg {
+                register: 8 + ((value >> 6) & 0x7) as u8,
+                offset: z(6),
+                pre_index: false,
+            },
+            0xde => Arm64UnwindCode::SaveFReg {
+                register: 8 + ((value >> 5) & 0x7) as u8,
+                offset: z_x(5),
+                pre_index: true,
+            },
+            0xe0 => Arm64UnwindCode::Alloc((value & 0xff_ffff) * 16),
+            0xe1 => Arm64UnwindCode::SetFp,
+            0xe2 => Arm64UnwindCode::AddFp(u32::from(bytes[1]) * 8),
+            0xe3 => Arm64UnwindCode::Nop,
+            0xe4 => Arm64UnwindCode::End,
+            0xe5 => Arm64UnwindCode::EndC,
+            0xe6 => Arm64UnwindCode::SaveNext,
+            0xe8 => Arm64UnwindCode::TrapFrame,
+            0xe9 => Arm64UnwindCode::MachineFrame,
+            0xea => Arm64UnwindCode::Context,
+            0xeb => Arm64UnwindCode::EcContext,
+            0xec => Arm64UnwindCode::ClearUnwoundToCall,
+            0xfc => Arm64UnwindCode::PacSignLr,
+            _ => Arm64UnwindCode::Other(bytes),
+        };
+        Ok(code)
+    }
+}
+
+impl<'data> Iterator for Arm64UnwindCodeIterator<'data> {
+    type Item = Result<(usize, Arm64UnwindCode<'data>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// A decoded AArch64 unwind code.
+///
+/// Offsets are in bytes from the stack pointer. For pre-indexed stores, the
+/// stack pointer is decremented by the offset before the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arm64UnwindCode<'data> {
+    /// `alloc_s`, `alloc_m` or `alloc_l`: allocate the given number of bytes on the stack.
+    Alloc(u32),
+    /// `save_r19r20_x`, `save_fplr`, `save_fplr_x`, `save_regp` or `save_regp_x`:
+    /// save the pair of integer registers starting at the given register.
+    SaveRegPair {
+        /// The number of the first X register.
+        register: u8,
+        /// The offset in bytes.
+        offset: u16,
+        /// Whether the store is pre-indexed.
+        pre_index: bool,
+    },
+    /// `save_reg` or `save_reg_x`: save an integer register.
+    SaveReg {
+        /// The number of the X register.
+        register: u8,
+        /// The offset in bytes.
+        offset: u16,
+        /// Whether the store is pre-indexed.
+        pre_index: bool,
+    },
+    /// `save_lrpair`: save the given integer register and LR.
+    SaveLrPair {
+        /// The number of the X register.
+        register: u8,
+        /// The offset in bytes.
+        offset: u16,
+    },
+    /// `save_fregp` or `save_fregp_x`: save the pair of FP registers starting at
+    /// the given register.
+    SaveFRegPair {
+        /// The number of the first D register.
+        register: u8,
+        /// The offset in bytes.
+        offset: u16,
+        /// Whether the store is pre-indexed.
+        pre_index: bool,
+    },
+    /// `save_freg` or `save_freg_x`: save an FP register.
+    SaveFReg {
+        /// The number of the D register.
+        register: u8,
+        /// The offset in bytes.
+        offset: u16,
+        /// Whether the store is pre-indexed.
+        pre_index: bool,
+    },
+    /// `set_fp`: set up the frame pointer with `mov x29, sp`.
+    SetFp,
+    /// `add_fp`: set up the frame pointer with `add x29, sp, #offset`.
+    AddFp(u32),
+    /// `nop`: an instruction that does not require unwinding.
+    Nop,
+    /// `end`: the end of the unwind codes.
+    End,
+    /// `end_c`: the end of the unwind codes in the current chained scope.
+    EndC,
+    /// `save_next`: save the next register pair.
+    SaveNext,
+    /// `MSFT_OP_TRAP_FRAME`.
+    TrapFrame,
+    /// `MSFT_OP_MACHINE_FRAME`.
+    MachineFrame,
+    /// `MSFT_OP_CONTEXT`.
+    Context,
+    /// `MSFT_OP_EC_CONTEXT`.
+    EcContext,
+    /// `MSFT_OP_CLEAR_UNWOUND_TO_CALL`.
+    ClearUnwoundToCall,
+    /// `pac_sign_lr`: sign the return address in LR.
+    PacSignLr,
+    /// Any other unwind code, such as `save_any_reg` or the SVE codes.
+    ///
+    /// Contains all of the bytes of the code.
+    Other(&'data [u8]),
+}
@@ -1,22 +1,22 @@
 use alloc::vec::Vec;
 use core::fmt::Debug;
+use core::ops::Range;
 use core::{mem, str};
 
-use core::convert::TryInto;
-
-use crate::endian::{LittleEndian as LE, U32};
-use crate::pod::{self, Pod};
+use crate::endian::LittleEndian as LE;
+use crate::pod::Pod;
 use crate::read::coff::{CoffCommon, CoffSymbol, CoffSymbolIterator, CoffSymbolTable, SymbolTable};
 use crate::read::{
-    self, Architecture, ByteString, Bytes, CodeView, ComdatKind, Error, Export, FileFlags, Import,
+    self, Architecture, ByteString, CodeView, ComdatKind, Error, Export, FileFlags, Import,
     NoDynamicRelocationIterator, Object, ObjectComdat, ObjectKind, ReadError, ReadRef, Result,
     SectionIndex, SubArchitecture, SymbolIndex,
 };
 use crate::{pe, SkipDebugList};
 
 use super::{
-    DataDirectories, ExportTable, ImageThunkData, ImportTable, PeSection, PeSectionIterator,
-    PeSegment, PeSegmentIterator, RichHeaderInfo, SectionTable,
+    CertificateIterator, DataDirectories, DebugDirectoryIterator, DebugInfo, ExceptionTable,
+    ExportTable, ImageThunkData, ImportTable, LoadConfig, PeSection, PeSectionIterator, PeSegment,
+    PeSegmentIterator, RichHeaderInfo, SectionTable, VersionInfo,
 };
 
 /// A PE32 (32-bit) image file.
@@ -135,6 +135,143 @@ where
             .import_table(self.data.0, &self.common.sections)
     }
 
+    /// Returns the exception table of this file.
+    ///
+    /// The exception table is located using the data directory.
+    pub fn exception_table(&self) -> Result<Option<ExceptionTable<'data>>> {
+        self.data_directories
+            .exception_table(self.data.0, &self.common.sections)
+    }
+
+    /// Returns the load configuration directory of this file.
+    pub fn load_config(&self) -> Result<Option<LoadConfig>> {
+        let optional_header = self.nt_headers.optional_header();
+        self.data_directories.load_config(
+            self.data.0,
+            &self.common.sections,
+            self.nt_headers.is_type_64(),
+            optional_header.image_base(),
+        )
+    }
+
+    /// Returns the entries in the debug directory.
+    pub fn debug_directory(&self) -> Result<Option<DebugDirectoryIterator<'data, R>>> {
+        self.data_directories
+            .debug_directory(self.data.0, &self.common.sections)
+    }
+
+    /// Returns the entries in the attribute certificate table.
+    pub fn certificate_table(&self) -> Result<Option<CertificateIterator<'data>>> {
+        self.data_directories.certificate_table(self.data.0)
+    }
+
+    /// Return the file offset of the `check_sum` field in the optional header.
+    pub fn check_sum_offset(&self) -> u64 {
+        // The field is at the same offset in both PE32 and PE32+ optional headers.
+        self.dos_header.nt_headers_offset() as u64
+            + 4
+            + mem::size_of::<pe::ImageFileHeader>() as u64
+            + 64
+    }
+
+    /// Return the file offset of the `IMAGE_DIRECTORY_ENTRY_SECURITY` data directory.
+    pub fn certificate_directory_offset(&self) -> Result<u64> {
+        if self.data_directories.len() <= pe::IMAGE_DIRECTORY_ENTRY_SECURITY {
+            return Err(Error("Missing PE certificate data directory"));
+        }
+        Ok(self.dos_header.nt_headers_offset() as u64
+            + mem::size_of::<Pe>() as u64
+            + (pe::IMAGE_DIRECTORY_ENTRY_SECURITY * mem::size_of::<pe::ImageDataDirectory>())
+                as u64)
+    }
+
+    /// Compute the checksum of the file.
+    ///
+    /// This can be compared with the `check_sum` field in the optional header.
+    pub fn compute_check_sum(&self) -> Result<u32> {
+        let len = self.data.0.len().read_error("Unknown PE file length")?;
+        let data = self
+            .data
+            .0
+            .read_bytes_at(0, len)
+            .read_error("Invalid PE file length")?;
+        Ok(image_check_sum(data, self.check_sum_offset() as usize))
+    }
+
+    /// Return the file ranges that are hashed to compute the Authenticode image digest.
+    ///
+    /// The ranges cover the entire file, except for the `check_sum` field in the
+    /// optional header, the `IMAGE_DIRECTORY_ENTRY_SECURITY` data directory, and
+    /// the attribute certificate table. This is equivalent to hashing the headers,
+    /// the sections sorted by file offset, and any data following the sections.
+    pub fn authenticode_ranges(&self) -> Result<Vec<Range<u64>>> {
+        let len = self.data.0.len().read_error("Unknown PE file length")?;
+        let check_sum = self.check_sum_offset();
+        let directory = self.certificate_directory_offset()?;
+        let (table_offset, table_size) = self
+            .data_directories
+            .get(pe::IMAGE_DIRECTORY_ENTRY_SECURITY)
+            .map(|dir| dir.address_range())
+            .unwrap_or((0, 0));
+        let (table_start, table_end) = if table_size != 0 {
+            let start = u64::from(table_offset);
+            let end = start + u64::from(table_size);
+            if start < directory + 8 || end > len {
+                return Err(Error("Invalid PE certificate table range"));
+            }
+            (start, end)
+        } else {
+            (len, len)
+        };
+        if directory + 8 > len {
+            return Err(Error("Invalid PE certificate data directory offset"));
+        }
+
+        let mut ranges = vec![
+            0..check_sum,
+            check_sum + 4..directory,
+            directory + 8..table_start,
+        ];
+        if table_end < len {
+            ranges.push(table_end..len);
+        }
+        ranges.retain(|range| !range.is_empty());
+        Ok(ranges)
+    }
+
+    /// Returns the version information of this file.
+    ///
+    /// This is parsed from the first `RT_VERSION` resource.
+    pub fn version_info(&self) -> Result<Option<VersionInfo<'data>>> {
+        let Some(entry) = self.resource_data_entry(pe::RT_VERSION)? else {
+            return Ok(None);
+        };
+        let data = entry.data(self.data.0, &self.common.sections)?;
+        VersionInfo::parse(data).map(Some)
+    }
+
+    /// Returns the data of the embedded manifest of this file.
+    ///
+    /// This is the first `RT_MANIFEST` resource. This is usually the resource with ID
+    /// `CREATEPROCESS_MANIFEST_RESOURCE_ID` for executables, or
+    /// `ISOLATIONAWARE_MANIFEST_RESOURCE_ID` for DLLs.
+    pub fn manifest(&self) -> Result<Option<&'data [u8]>> {
+        let Some(entry) = self.resource_data_entry(pe::RT_MANIFEST)? else {
+            return Ok(None);
+        };
+        entry.data(self.data.0, &self.common.sections).map(Some)
+    }
+
+    fn resource_data_entry(&self, typ: u16) -> Result<Option<&'data pe::ImageResourceDataEntry>> {
+        let Some(directory) = self
+            .data_directories
+            .resource_directory(self.data.0, &self.common.sections)?
+        else {
+            return Ok(None);
+        };
+        directory.find_data_entry(typ)
+    }
+
     pub(super) fn section_alignment(&self) -> u64 {
         u64::from(self.nt_headers.optional_header().section_alignment())
     }
@@ -355,54 +492,13 @@ where
     }
 
     fn pdb_info(&self) -> Result<Option<CodeView<'_>>> {
-        let data_dir = match self.data_directory(pe::IMAGE_DIRECTORY_ENTRY_DEBUG) {
-            Some(data_dir) => data_dir,
-            None => return Ok(None),
+        let Some(mut entries) = self.debug_directory()? else {
+            return Ok(None);
         };
-        let debug_data = data_dir.data(self.data.0, &self.common.sections)?;
-        let debug_dirs = pod::slice_from_all_bytes::<pe::ImageDebugDirectory>(debug_data)
-            .read_error("Invalid PE debug dir size")?;
-
-        for debug_dir in debug_dirs {
-            if debug_dir.typ.get(LE) != pe::IMAGE_DEBUG_TYPE_CODEVIEW {
-                continue;
+        while let Some(entry) = entries.next_of_type(pe::IMAGE_DEBUG_TYPE_CODEVIEW)? {
+            if let DebugInfo::CodeView(info) = entry.info()? {
+                return Ok(Some(info));
             }
-
-            let info = self
-                .data
-                .read_slice_at::<u8>(
-                    debug_dir.pointer_to_raw_data.get(LE) as u64,
-                    debug_dir.size_of_data.get(LE) as usize,
-                )
-                .read_error("Invalid CodeView Info address")?;
-
-            let mut info = Bytes(info);
-
-            let sig = info
-                .read_bytes(4)
-                .read_error("Invalid CodeView signature")?;
-            if sig.0 != b"RSDS" {
-                continue;
-            }
-
-            let guid: [u8; 16] = info
-                .read_bytes(16)
-                .read_error("Invalid CodeView GUID")?
-                .0
-                .try_into()
-                .unwrap();
-
-            let age = info.read::<U32<LE>>().read_error("Invalid CodeView Age")?;
-
-            let path = info
-                .read_string()
-                .read_error("Invalid CodeView file path")?;
-
-            return Ok(Some(CodeView {
-                path: ByteString(path),
-                guid,
-                age: age.get(LE),
-            }));
         }
         Ok(None)
     }
@@ -1080,3 +1176,28 @@ impl ImageOptionalHeader for pe::ImageOptionalHeader64 {
         self.number_of_rva_and_sizes.get(LE)
     }
 }
+
+/// Compute the checksum of a PE image, as stored in the `check_sum` field of
+/// the optional header.
+///
+/// `data` is the entire file data, and `check_sum_offset` is the file offset
+/// of the `check_sum` field, which is treated as zero.
+///
+/// This is the algorithm used by `CheckSumMappedFile`: the file is summed as
+/// 16-bit little endian words with the carries folded back in, and then the
+/// file length is added.
+pub(crate) fn image_check_sum(data: &[u8], check_sum_offset: usize) -> u32 {
+    let check_sum_range = check_sum_offset..check_sum_offset.saturating_add(4);
+    let mut sum = 0u32;
+    for (index, word) in data.chunks(2).enumerate() {
+        let offset = index * 2;
+        let mut value = u32::from(word[0]) | u32::from(*word.get(1).unwrap_or(&0)) << 8;
+        if check_sum_range.contains(&offset) {
+            value = 0;
+        }
+        sum += value;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum = (sum & 0xffff) + (sum >> 16);
+    sum.wrapping_add(data.len() as u32)
+}
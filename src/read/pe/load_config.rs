@@ -0,0 +1,535 @@
+use alloc::vec;
+use core::convert::TryFrom;
+use core::{mem, slice};
+
+use crate::endian::{LittleEndian as LE, U32};
+use crate::pe;
+use crate::pod::{self, Pod};
+use crate::read::{Bytes, Error, ReadError, ReadRef, Result, SectionIndex};
+
+use super::{RelocationBlockIterator, SectionTable};
+
+/// The load configuration directory of a PE file.
+///
+/// This is a copy of the directory, so that both [`pe::ImageLoadConfigDirectory32`]
+/// and [`pe::ImageLoadConfigDirectory64`] can be accessed in the same way.
+/// The directory is smaller in images that were linked for older versions of
+/// Windows; fields that are beyond the size of the directory are 0.
+///
+/// Returned by [`DataDirectories::load_config`](super::DataDirectories::load_config).
+#[derive(Debug, Clone, Copy)]
+pub struct LoadConfig {
+    image_base: u64,
+    directory: LoadConfigDirectory,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LoadConfigDirectory {
+    Config32(pe::ImageLoadConfigDirectory32),
+    Config64(pe::ImageLoadConfigDirectory64),
+}
+
+macro_rules! field {
+    ($self:ident, $name:ident) => {
+        match &$self.directory {
+            LoadConfigDirectory::Config32(directory) => directory.$name.get(LE).into(),
+            LoadConfigDirectory::Config64(directory) => directory.$name.get(LE).into(),
+        }
+    };
+}
+
+impl LoadConfig {
+    /// Parse the load configuration directory at the start of the given data.
+    ///
+    /// `image_base` is used to convert the virtual addresses in the directory to
+    /// relative virtual addresses.
+    pub fn parse(data: &[u8], is_64: bool, image_base: u64) -> Result<Self> {
+        let size = Bytes(data)
+            .read::<U32<LE>>()
+            .read_error("Invalid PE load config size")?
+            .get(LE);
+        let data = data
+            .get(..size as usize)
+            .read_error("Invalid PE load config size")?;
+        let directory = if is_64 {
+            LoadConfigDirectory::Config64(read_partial(data)?)
+        } else {
+            LoadConfigDirectory::Config32(read_partial(data)?)
+        };
+        Ok(LoadConfig {
+            image_base,
+            directory,
+        })
+    }
+
+    /// Return the directory if the image is 32-bit.
+    pub fn directory32(&self) -> Option<&pe::ImageLoadConfigDirectory32> {
+        match &self.directory {
+            LoadConfigDirectory::Config32(directory) => Some(directory),
+            LoadConfigDirectory::Config64(_) => None,
+        }
+    }
+
+    /// Return the directory if the image is 64-bit.
+    pub fn directory64(&self) -> Option<&pe::ImageLoadConfigDirectory64> {
+        match &self.directory {
+            LoadConfigDirectory::Config32(_) => None,
+            LoadConfigDirectory::Config64(directory) => Some(directory),
+        }
+    }
+
+    /// Return the size of the directory as stored in the file.
+    pub fn size(&self) -> u32 {
+        field!(self, size)
+    }
+
+    /// Return the time and date stamp.
+    pub fn time_date_stamp(&self) -> u32 {
+        field!(self, time_date_stamp)
+    }
+
+    /// Return the major version.
+    pub fn major_version(&self) -> u16 {
+        field!(self, major_version)
+    }
+
+    /// Return the minor version.
+    pub fn minor_version(&self) -> u16 {
+        field!(self, minor_version)
+    }
+
+    /// Return the virtual address of the `/GS` security cookie.
+    pub fn security_cookie(&self) -> u64 {
+        field!(self, security_cookie)
+    }
+
+    /// Return the virtual address of the table of safe exception handlers.
+    ///
+    /// This is only used by 32-bit images.
+    pub fn se_handler_table(&self) -> u64 {
+        field!(self, sehandler_table)
+    }
+
+    /// Return the number of entries in the table of safe exception handlers.
+    pub fn se_handler_count(&self) -> u64 {
+        field!(self, sehandler_count)
+    }
+
+    /// Return the virtual address of the control flow guard check function pointer.
+    pub fn guard_cf_check_function_pointer(&self) -> u64 {
+        field!(self, guard_cf_check_function_pointer)
+    }
+
+    /// Return the virtual address of the control flow guard dispatch function pointer.
+    pub fn guard_cf_dispatch_function_pointer(&self) -> u64 {
+        field!(self, guard_cf_dispatch_function_pointer)
+    }
+
+    /// Return the virtual address of the control flow guard function table.
+    pub fn guard_cf_function_table(&self) -> u64 {
+        field!(self, guard_cf_function_table)
+    }
+
+    /// Return the number of entries in the control flow guard function table.
+    pub fn guard_cf_function_count(&self) -> u64 {
+        field!(self, guard_cf_function_count)
+    }
+
+    /// Return the control flow guard flags.
+    ///
+    /// This is a combination of the `IMAGE_GUARD_*` flags, and the stride of the
+    /// guard tables in `IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK`.
+    pub fn guard_flags(&self) -> u32 {
+        field!(self, guard_flags)
+    }
+
+    /// Return the virtual address of the table of address taken IAT entries.
+    pub fn guard_address_taken_iat_entry_table(&self) -> u64 {
+        field!(self, guard_address_taken_iat_entry_table)
+    }
+
+    /// Return the number of entries in the table of address taken IAT entries.
+    pub fn guard_address_taken_iat_entry_count(&self) -> u64 {
+        field!(self, guard_address_taken_iat_entry_count)
+    }
+
+    /// Return the virtual address of the table of long jump targets.
+    pub fn guard_long_jump_target_table(&self) -> u64 {
+        field!(self, guard_long_jump_target_table)
+    }
+
+    /// Return the number of entries in the table of long jump targets.
+    pub fn guard_long_jump_target_count(&self) -> u64 {
+        field!(self, guard_long_jump_target_count)
+    }
+
+    /// Return the virtual address of the table of EH continuation targets.
+    pub fn guard_eh_continuation_table(&self) -> u64 {
+        field!(self, guard_eh_continuation_table)
+    }
+
+    /// Return the number of entries in the table of EH continuation targets.
+    pub fn guard_eh_continuation_count(&self) -> u64 {
+        field!(self, guard_eh_continuation_count)
+    }
+
+    /// Return the virtual address of the dynamic value relocation table.
+    ///
+    /// Newer images use [`Self::dynamic_value_reloc_table_offset`] instead.
+    pub fn dynamic_value_reloc_table(&self) -> u64 {
+        field!(self, dynamic_value_reloc_table)
+    }
+
+    /// Return the offset of the dynamic value relocation table within its section.
+    pub fn dynamic_value_reloc_table_offset(&self) -> u32 {
+        field!(self, dynamic_value_reloc_table_offset)
+    }
+
+    /// Return the one-based index of the section containing the dynamic value relocation table.
+    pub fn dynamic_value_reloc_table_section(&self) -> u16 {
+        field!(self, dynamic_value_reloc_table_section)
+    }
+
+    /// Return the virtual address of the compiled hybrid PE metadata.
+    pub fn chpe_metadata_pointer(&self) -> u64 {
+        field!(self, chpe_metadata_pointer)
+    }
+
+    /// Return the code integrity information.
+    pub fn code_integrity(&self) -> &pe::ImageLoadConfigCodeIntegrity {
+        match &self.directory {
+            LoadConfigDirectory::Config32(directory) => &directory.code_integrity,
+            LoadConfigDirectory::Config64(directory) => &directory.code_integrity,
+        }
+    }
+
+    /// Return the virtual address of the enclave configuration.
+    pub fn enclave_configuration_pointer(&self) -> u64 {
+        field!(self, enclave_configuration_pointer)
+    }
+
+    /// Return the virtual address of the volatile metadata.
+    pub fn volatile_metadata_pointer(&self) -> u64 {
+        field!(self, volatile_metadata_pointer)
+    }
+
+    /// Return the table of safe exception handlers.
+    ///
+    /// Returns `None` if the table is not present.
+    ///
+    /// `data` must be the entire file data.
+    pub fn se_handlers<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<Option<SeHandlerIterator<'data>>> {
+        let Some(table) = self.table(
+            data,
+            sections,
+            self.se_handler_table(),
+            self.se_handler_count(),
+            4,
+        )?
+        else {
+            return Ok(None);
+        };
+        let handlers = pod::slice_from_all_bytes::<U32<LE>>(table)
+            .read_error("Invalid PE SafeSEH table size")?;
+        Ok(Some(SeHandlerIterator {
+            handlers: handlers.iter(),
+        }))
+    }
+
+    /// Return the control flow guard function table.
+    ///
+    /// Returns `None` if the table is not present.
+    ///
+    /// `data` must be the entire file data.
+    pub fn guard_cf_functions<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<Option<GuardFunctionIterator<'data>>> {
+        self.guard_table(
+            data,
+            sections,
+            self.guard_cf_function_table(),
+            self.guard_cf_function_count(),
+        )
+    }
+
+    /// Return the table of address taken IAT entries.
+    ///
+    /// Returns `None` if the table is not present.
+    ///
+    /// `data` must be the entire file data.
+    pub fn guard_address_taken_iat_entries<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<Option<GuardFunctionIterator<'data>>> {
+        self.guard_table(
+            data,
+            sections,
+            self.guard_address_taken_iat_entry_table(),
+            self.guard_address_taken_iat_entry_count(),
+        )
+    }
+
+    /// Return the table of long jump targets.
+    ///
+    /// Returns `None` if the table is not present.
+    ///
+    /// `data` must be the entire file data.
+    pub fn guard_long_jump_targets<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<Option<GuardFunctionIterator<'data>>> {
+        self.guard_table(
+            data,
+            sections,
+            self.guard_long_jump_target_table(),
+            self.guard_long_jump_target_count(),
+        )
+    }
+
+    /// Return the table of EH continuation targets.
+    ///
+    /// Returns `None` if the table is not present.
+    ///
+    /// `data` must be the entire file data.
+    pub fn guard_eh_continuation_targets<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<Option<GuardFunctionIterator<'data>>> {
+        self.guard_table(
+            data,
+            sections,
+            self.guard_eh_continuation_table(),
+            self.guard_eh_continuation_count(),
+        )
+    }
+
+    /// Return the dynamic value relocation table.
+    ///
+    /// Returns `None` if the table is not present.
+    ///
+    /// `data` must be the entire file data.
+    pub fn dynamic_relocations<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<Option<DynamicRelocationIterator<'data>>> {
+        let table_data = if self.dynamic_value_reloc_table_section() != 0 {
+            let section = sections.section(SectionIndex(
+                self.dynamic_value_reloc_table_section().into(),
+            ))?;
+            section
+                .pe_data(data)?
+                .get(self.dynamic_value_reloc_table_offset() as usize..)
+                .read_error("Invalid PE dynamic relocation table offset")?
+        } else if self.dynamic_value_reloc_table() != 0 {
+            let address = self.rva(self.dynamic_value_reloc_table())?;
+            sections
+                .pe_data_at(data, address)
+                .read_error("Invalid PE dynamic relocation table address")?
+        } else {
+            return Ok(None);
+        };
+        let mut table_data = Bytes(table_data);
+        let header = table_data
+            .read::<pe::ImageDynamicRelocationTable>()
+            .read_error("Invalid PE dynamic relocation table size")?;
+        if header.version.get(LE) != 1 {
+            return Err(Error("Unsupported PE dynamic relocation table version"));
+        }
+        let relocations = table_data
+            .read_bytes(header.size.get(LE) as usize)
+            .read_error("Invalid PE dynamic relocation table size")?;
+        Ok(Some(DynamicRelocationIterator {
+            is_64: matches!(self.directory, LoadConfigDirectory::Config64(_)),
+            data: relocations,
+        }))
+    }
+
+    fn rva(&self, address: u64) -> Result<u32> {
+        address
+            .checked_sub(self.image_base)
+            .and_then(|rva| u32::try_from(rva).ok())
+            .read_error("Invalid PE load config address")
+    }
+
+    fn table<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+        address: u64,
+        count: u64,
+        stride: usize,
+    ) -> Result<Option<&'data [u8]>> {
+        if address == 0 {
+            return Ok(None);
+        }
+        let address = self.rva(address)?;
+        let len = usize::try_from(count)
+            .ok()
+            .and_then(|count| count.checked_mul(stride))
+            .read_error("Invalid PE load config table count")?;
+        sections
+            .pe_data_at(data, address)
+            .and_then(|data| data.get(..len))
+            .read_error("Invalid PE load config table address")
+            .map(Some)
+    }
+
+    fn guard_table<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+        address: u64,
+        count: u64,
+    ) -> Result<Option<GuardFunctionIterator<'data>>> {
+        let stride = 4
+            + ((self.guard_flags() & pe::IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK)
+                >> pe::IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT) as usize;
+        let table = self.table(data, sections, address, count, stride)?;
+        Ok(table.map(|data| GuardFunctionIterator { data, stride }))
+    }
+}
+
+/// Read a structure that may be truncated, filling the missing fields with zeros.
+fn read_partial<T: Pod>(data: &[u8]) -> Result<T> {
+    let mut bytes = vec![0; mem::size_of::<T>()];
+    let len = data.len().min(bytes.len());
+    bytes[..len].copy_from_slice(&data[..len]);
+    pod::from_bytes::<T>(&bytes)
+        .map(|(value, _)| *value)
+        .read_error("Invalid PE load config alignment")
+}
+
+/// An iterator over the table of safe exception handlers.
+///
+/// Each entry is the relative virtual address of a handler.
+///
+/// Returned by [`LoadConfig::se_handlers`].
+#[derive(Debug, Clone)]
+pub struct SeHandlerIterator<'data> {
+    handlers: slice::Iter<'data, U32<LE>>,
+}
+
+impl<'data> Iterator for SeHandlerIterator<'data> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.handlers.next().map(|handler| handler.get(LE))
+    }
+}
+
+/// An iterator over a control flow guard table.
+///
+/// Returned by [`LoadConfig::guard_cf_functions`] and similar methods.
+#[derive(Debug, Clone, Copy)]
+pub struct GuardFunctionIterator<'data> {
+    data: &'data [u8],
+    stride: usize,
+}
+
+impl<'data> Iterator for GuardFunctionIterator<'data> {
+    type Item = GuardFunction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < self.stride {
+            return None;
+        }
+        let (entry, rest) = self.data.split_at(self.stride);
+        self.data = rest;
+        Some(GuardFunction {
+            rva: u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]),
+            flags: entry.get(4).copied().map_or(0, u16::from),
+        })
+    }
+}
+
+/// An entry in a control flow guard table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuardFunction {
+    /// The relative virtual address of the target.
+    pub rva: u32,
+    /// The `IMAGE_GUARD_FLAG_*` flags for the target.
+    ///
+    /// This is 0 if the table does not contain flags.
+    pub flags: u16,
+}
+
+/// An iterator over the entries in a version 1 dynamic value relocation table.
+///
+/// Returned by [`LoadConfig::dynamic_relocations`].
+#[derive(Debug, Clone, Copy)]
+pub struct DynamicRelocationIterator<'data> {
+    is_64: bool,
+    data: Bytes<'data>,
+}
+
+impl<'data> DynamicRelocationIterator<'data> {
+    /// Read the next entry.
+    pub fn next(&mut self) -> Result<Option<DynamicRelocation<'data>>> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+
+        let result = self.parse().map(Some);
+        if result.is_err() {
+            self.data = Bytes(&[]);
+        }
+        result
+    }
+
+    fn parse(&mut self) -> Result<DynamicRelocation<'data>> {
+        let (symbol, size) = if self.is_64 {
+            let header = self
+                .data
+                .read::<pe::ImageDynamicRelocation64>()
+                .read_error("Invalid PE dynamic relocation size")?;
+            (header.symbol.get(LE), header.base_reloc_size.get(LE))
+        } else {
+            let header = self
+                .data
+                .read::<pe::ImageDynamicRelocation32>()
+                .read_error("Invalid PE dynamic relocation size")?;
+            (header.symbol.get(LE).into(), header.base_reloc_size.get(LE))
+        };
+        let relocations = self
+            .data
+            .read_bytes(size as usize)
+            .read_error("Invalid PE dynamic relocation size")?;
+        Ok(DynamicRelocation {
+            symbol,
+            blocks: RelocationBlockIterator::new(relocations.0),
+        })
+    }
+}
+
+impl<'data> Iterator for DynamicRelocationIterator<'data> {
+    type Item = Result<DynamicRelocation<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// An entry in a dynamic value relocation table.
+#[derive(Debug, Clone, Copy)]
+pub struct DynamicRelocation<'data> {
+    /// The symbol that the relocations refer to.
+    ///
+    /// This is either one of the `IMAGE_DYNAMIC_RELOCATION_*` values, or the
+    /// virtual address of a symbol.
+    pub symbol: u64,
+    /// The relocation blocks.
+    ///
+    /// These use the same format as base relocations, but the type field
+    /// depends on the symbol.
+    pub blocks: RelocationBlockIterator<'data>,
+}
@@ -50,12 +50,24 @@ pub use section::*;
 mod data_directory;
 pub use data_directory::*;
 
+mod certificate;
+pub use certificate::*;
+
+mod debug;
+pub use debug::*;
+
+mod exception;
+pub use exception::*;
+
 mod export;
 pub use export::*;
 
 mod import;
 pub use import::*;
 
+mod load_config;
+pub use load_config::*;
+
 mod relocation;
 pub use relocation::*;
 
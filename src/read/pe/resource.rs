@@ -1,9 +1,12 @@
 use alloc::string::String;
+use alloc::vec::Vec;
 use core::char;
 
 use crate::endian::{LittleEndian as LE, U16};
 use crate::pe;
-use crate::read::{ReadError, ReadRef, Result};
+use crate::read::{Error, ReadError, ReadRef, Result};
+
+use super::SectionTable;
 
 /// The `.rsrc` section of a PE file.
 ///
@@ -23,6 +26,31 @@ impl<'data> ResourceDirectory<'data> {
     pub fn root(&self) -> Result<ResourceDirectoryTable<'data>> {
         ResourceDirectoryTable::parse(self.data, 0)
     }
+
+    /// Find the first data entry with the given resource type ID.
+    ///
+    /// This uses the first name and the first language for the type.
+    /// Names sort before IDs, and IDs are sorted in ascending order.
+    pub fn find_data_entry(&self, typ: u16) -> Result<Option<&'data pe::ImageResourceDataEntry>> {
+        let root = self.root()?;
+        let Some(entry) = root
+            .entries
+            .iter()
+            .find(|entry| entry.name_or_id().id() == Some(typ))
+        else {
+            return Ok(None);
+        };
+        let mut entry_data = entry.data(*self)?;
+        loop {
+            match entry_data {
+                ResourceDirectoryEntryData::Table(table) => match table.entries.first() {
+                    Some(entry) => entry_data = entry.data(*self)?,
+                    None => return Ok(None),
+                },
+                ResourceDirectoryEntryData::Data(data) => return Ok(Some(data)),
+            }
+        }
+    }
 }
 
 /// A table of resource entries.
@@ -107,6 +135,22 @@ impl pe::ImageResourceDirectoryEntry {
     }
 }
 
+impl pe::ImageResourceDataEntry {
+    /// Returns the resource data.
+    ///
+    /// The data is located using the virtual address in the entry.
+    pub fn data<'data, R: ReadRef<'data>>(
+        &self,
+        data: R,
+        sections: &SectionTable<'data>,
+    ) -> Result<&'data [u8]> {
+        sections
+            .pe_data_at(data, self.offset_to_data.get(LE))
+            .and_then(|data| data.get(..self.size.get(LE) as usize))
+            .read_error("Invalid resource data entry")
+    }
+}
+
 /// Data associated with a resource directory entry.
 #[derive(Debug, Clone)]
 pub enum ResourceDirectoryEntryData<'data> {
@@ -147,11 +191,7 @@ pub struct ResourceName {
 impl ResourceName {
     /// Converts to a `String`.
     pub fn to_string_lossy(&self, directory: ResourceDirectory<'_>) -> Result<String> {
-        let d = self.data(directory)?.iter().map(|c| c.get(LE));
-
-        Ok(char::decode_utf16(d)
-            .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
-            .collect::<String>())
+        Ok(utf16_to_string_lossy(self.data(directory)?))
     }
 
     /// Returns the string unicode buffer.
@@ -205,3 +245,283 @@ impl ResourceNameOrId {
         }
     }
 }
+
+fn utf16_to_string_lossy(data: &[U16<LE>]) -> String {
+    char::decode_utf16(data.iter().map(|c| c.get(LE)))
+        .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect::<String>()
+}
+
+/// The version information in an `RT_VERSION` resource.
+///
+/// This is parsed from the `VS_VERSIONINFO` block, which contains a
+/// [`pe::VsFixedFileInfo`], followed by an optional `StringFileInfo` block
+/// containing string tables, and an optional `VarFileInfo` block
+/// containing the supported translations.
+///
+/// Returned by [`PeFile::version_info`](super::PeFile::version_info).
+#[derive(Debug, Clone)]
+pub struct VersionInfo<'data> {
+    /// The fixed file information, if present.
+    pub fixed: Option<&'data pe::VsFixedFileInfo>,
+    /// The string tables from the `StringFileInfo` block.
+    pub string_tables: Vec<VersionStringTable<'data>>,
+    /// The translations from the `Translation` value in the `VarFileInfo` block.
+    pub translations: Vec<VersionTranslation>,
+}
+
+impl<'data> VersionInfo<'data> {
+    /// Parse the data of an `RT_VERSION` resource.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let (root, _) = VersionBlock::parse(data)?;
+        if !utf16_eq(root.key, "VS_VERSION_INFO") {
+            return Err(Error("Invalid VS_VERSIONINFO key"));
+        }
+        let mut info = VersionInfo {
+            fixed: None,
+            string_tables: Vec::new(),
+            translations: Vec::new(),
+        };
+        if !root.value.is_empty() {
+            let fixed = root
+                .value
+                .read_at::<pe::VsFixedFileInfo>(0)
+                .read_error("Invalid VS_FIXEDFILEINFO size")?;
+            if fixed.signature.get(LE) != pe::VS_FFI_SIGNATURE {
+                return Err(Error("Invalid VS_FIXEDFILEINFO signature"));
+            }
+            info.fixed = Some(fixed);
+        }
+
+        let mut children = root.children;
+        while !children.is_empty() {
+            let (child, next) = VersionBlock::parse(children)?;
+            children = next;
+            if utf16_eq(child.key, "StringFileInfo") {
+                let mut tables = child.children;
+                while !tables.is_empty() {
+                    let (table, next) = VersionBlock::parse(tables)?;
+                    tables = next;
+                    info.string_tables.push(VersionStringTable::parse(table)?);
+                }
+            } else if utf16_eq(child.key, "VarFileInfo") {
+                let mut vars = child.children;
+                while !vars.is_empty() {
+                    let (var, next) = VersionBlock::parse(vars)?;
+                    vars = next;
+                    if utf16_eq(var.key, "Translation") {
+                        let translations = var
+                            .value
+                            .read_slice_at::<U16<LE>>(0, var.value.len() / 4 * 2)
+                            .read_error("Invalid version translation")?;
+                        for translation in translations.chunks_exact(2) {
+                            info.translations.push(VersionTranslation {
+                                language: translation[0].get(LE),
+                                code_page: translation[1].get(LE),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(info)
+    }
+
+    /// Returns the file version from the fixed file information.
+    ///
+    /// The version is returned as the major, minor, build and revision numbers.
+    pub fn file_version(&self) -> Option<[u16; 4]> {
+        self.fixed.map(|fixed| {
+            split_version(fixed.file_version_ms.get(LE), fixed.file_version_ls.get(LE))
+        })
+    }
+
+    /// Returns the product version from the fixed file information.
+    ///
+    /// The version is returned as the major, minor, build and revision numbers.
+    pub fn product_version(&self) -> Option<[u16; 4]> {
+        self.fixed.map(|fixed| {
+            split_version(
+                fixed.product_version_ms.get(LE),
+                fixed.product_version_ls.get(LE),
+            )
+        })
+    }
+
+    /// Returns the string table for the given language and code page.
+    pub fn string_table(
+        &self,
+        language: u16,
+        code_page: u16,
+    ) -> Option<&VersionStringTable<'data>> {
+        self.string_tables
+            .iter()
+            .find(|table| table.language == language && table.code_page == code_page)
+    }
+
+    /// Returns the value of a string, such as `FileVersion` or `ProductName`.
+    ///
+    /// The string table for the first translation is used if present, otherwise
+    /// the first string table is used.
+    pub fn string(&self, key: &str) -> Option<&'data [U16<LE>]> {
+        let table = self
+            .translations
+            .first()
+            .and_then(|translation| self.string_table(translation.language, translation.code_page))
+            .or_else(|| self.string_tables.first())?;
+        table.string(key)
+    }
+}
+
+/// A string table in a [`VersionInfo`].
+#[derive(Debug, Clone)]
+pub struct VersionStringTable<'data> {
+    /// The language ID.
+    pub language: u16,
+    /// The code page.
+    pub code_page: u16,
+    /// The strings in the table.
+    pub strings: Vec<VersionString<'data>>,
+}
+
+impl<'data> VersionStringTable<'data> {
+    fn parse(table: VersionBlock<'data>) -> Result<Self> {
+        // The key is the language and code page as 8 hexadecimal digits.
+        let key = utf16_to_string_lossy(table.key);
+        let language_code_page = if key.len() == 8 {
+            u32::from_str_radix(&key, 16).ok()
+        } else {
+            None
+        }
+        .read_error("Invalid version string table key")?;
+        let mut strings = Vec::new();
+        let mut children = table.children;
+        while !children.is_empty() {
+            let (string, next) = VersionBlock::parse(children)?;
+            children = next;
+            let mut value = string
+                .value
+                .read_slice_at::<U16<LE>>(0, string.value.len() / 2)
+                .read_error("Invalid version string value")?;
+            while let Some((last, rest)) = value.split_last() {
+                if last.get(LE) != 0 {
+                    break;
+                }
+                value = rest;
+            }
+            strings.push(VersionString {
+                key: string.key,
+                value,
+            });
+        }
+        Ok(VersionStringTable {
+            language: (language_code_page >> 16) as u16,
+            code_page: language_code_page as u16,
+            strings,
+        })
+    }
+
+    /// Returns the value of the string with the given key.
+    pub fn string(&self, key: &str) -> Option<&'data [U16<LE>]> {
+        self.strings
+            .iter()
+            .find(|string| utf16_eq(string.key, key))
+            .map(|string| string.value)
+    }
+}
+
+/// A string in a [`VersionStringTable`].
+#[derive(Debug, Clone, Copy)]
+pub struct VersionString<'data> {
+    /// The key, such as `FileVersion` or `ProductName`.
+    pub key: &'data [U16<LE>],
+    /// The value, without any trailing NUL characters.
+    pub value: &'data [U16<LE>],
+}
+
+impl<'data> VersionString<'data> {
+    /// Converts the key to a `String`.
+    pub fn key_to_string_lossy(&self) -> String {
+        utf16_to_string_lossy(self.key)
+    }
+
+    /// Converts the value to a `String`.
+    pub fn value_to_string_lossy(&self) -> String {
+        utf16_to_string_lossy(self.value)
+    }
+}
+
+/// A translation in a [`VersionInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionTranslation {
+    /// The language ID.
+    pub language: u16,
+    /// The code page.
+    pub code_page: u16,
+}
+
+/// A block in a `VS_VERSIONINFO` tree.
+#[derive(Debug, Clone, Copy)]
+struct VersionBlock<'data> {
+    key: &'data [U16<LE>],
+    value: &'data [u8],
+    children: &'data [u8],
+}
+
+impl<'data> VersionBlock<'data> {
+    /// Parse a block, and return the data following it.
+    fn parse(data: &'data [u8]) -> Result<(Self, &'data [u8])> {
+        let header = data
+            .read_slice_at::<U16<LE>>(0, 3)
+            .read_error("Invalid version block header")?;
+        let length = usize::from(header[0].get(LE));
+        let value_length = usize::from(header[1].get(LE));
+        let is_text = header[2].get(LE) == 1;
+        let block = data
+            .get(..length)
+            .filter(|_| length >= 6)
+            .read_error("Invalid version block length")?;
+
+        let mut offset = 6;
+        let key_len = block[offset..]
+            .chunks_exact(2)
+            .position(|c| c == [0, 0])
+            .read_error("Invalid version block key")?;
+        let key = block
+            .read_slice_at::<U16<LE>>(offset as u64, key_len)
+            .read_error("Invalid version block key")?;
+        offset = align4(offset + (key_len + 1) * 2);
+
+        // Text values give their length in characters, but some tools use bytes,
+        // so limit the value to the block length.
+        let value_size = if is_text {
+            value_length * 2
+        } else {
+            value_length
+        };
+        let value_end = core::cmp::min(offset + value_size, block.len());
+        let value = block.get(offset..value_end).unwrap_or(&[]);
+        let children = block.get(align4(value_end)..).unwrap_or(&[]);
+        let next = data.get(align4(length)..).unwrap_or(&[]);
+        Ok((
+            VersionBlock {
+                key,
+                value,
+                children,
+            },
+            next,
+        ))
+    }
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn utf16_eq(data: &[U16<LE>], s: &str) -> bool {
+    data.iter().map(|c| c.get(LE)).eq(s.encode_utf16())
+}
+
+fn split_version(ms: u32, ls: u32) -> [u16; 4] {
+    [(ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16]
+}
@@ -0,0 +1,1177 @@
+//! Support for writing archive files.
+//!
+//! Provides [`Archive`] for writing the GNU, BSD, COFF and AIX big archive formats.
+//!
+//! The symbol table is generated by parsing each member with [`read::File`].
+//! Members that are not recognized object files are included in the archive,
+//! but do not contribute any symbols.
+//!
+//! GNU thin archives are also supported. These only contain the symbol table
+//! and the paths of the members. [`Archive::new_thin`] creates a thin archive
+//! from a list of paths, and [`Archive::flatten_thin`] converts a thin archive
+//! into a regular archive.
+//!
+//! ## Example
+//!  ```no_run
+//! use object::read::archive::ArchiveKind;
+//! use object::write::archive::{Archive, ArchiveMember};
+//! use std::error::Error;
+//! use std::fs;
+//!
+//! /// Creates an archive containing a single object file.
+//! fn main() -> Result<(), Box<dyn Error>> {
+//! #   #[cfg(feature = "std")] {
+//!     let data = fs::read("path/to/object.o")?;
+//!     let mut archive = Archive::new(ArchiveKind::Gnu);
+//!     archive.add_member(ArchiveMember::new(b"object.o".to_vec(), data));
+//!     fs::write("path/to/archive.a", archive.write()?)?;
+//! #   }
+//!     Ok(())
+//! }
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::mem;
+#[cfg(feature = "std")]
+use std::path::{Component, Path, PathBuf};
+#[cfg(feature = "std")]
+use std::{boxed::Box, env, error, fs, io};
+
+use crate::archive;
+use crate::endian::{BigEndian as BE, LittleEndian as LE, U16, U32, U64};
+#[cfg(feature = "std")]
+use crate::read::archive::ArchiveFile;
+use crate::read::archive::ArchiveKind;
+#[cfg(any(
+    feature = "coff",
+    feature = "elf",
+    feature = "macho",
+    feature = "pe",
+    feature = "wasm",
+    feature = "xcoff"
+))]
+use crate::read::{self, Object, ObjectSymbol};
+#[cfg(feature = "std")]
+use crate::write::StreamingBuffer;
+use crate::write::{util, Error, Result, WritableBuffer};
+
+/// A member to be written to an archive.
+#[derive(Debug, Clone)]
+pub struct ArchiveMember<'a> {
+    /// The file name.
+    ///
+    /// This is usually the file name without any directory components.
+    pub name: Vec<u8>,
+    /// The file data.
+    pub data: Cow<'a, [u8]>,
+    /// The file modification timestamp.
+    pub date: u64,
+    /// The user ID.
+    pub uid: u64,
+    /// The group ID.
+    pub gid: u64,
+    /// The file mode.
+    pub mode: u64,
+}
+
+impl<'a> ArchiveMember<'a> {
+    /// Create a new member with the given name and data.
+    ///
+    /// The timestamp, user ID and group ID are zero, and the mode is `0o644`.
+    pub fn new<T>(name: Vec<u8>, data: T) -> Self
+    where
+        T: Into<Cow<'a, [u8]>>,
+    {
+        ArchiveMember {
+            name,
+            data: data.into(),
+            date: 0,
+            uid: 0,
+            gid: 0,
+            mode: 0o644,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ArchiveMember<'static> {
+    /// Create a new member by reading the file at the given path.
+    ///
+    /// The member name is the file name of the path.
+    /// The timestamp, user ID, group ID and mode are read from the file metadata
+    /// if the platform supports them.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .ok_or_else(|| Error(format!("Invalid archive member path '{}'", path.display())))?;
+        let name = path_bytes(Path::new(name))?;
+        let data = fs::read(path)
+            .map_err(|e| Error(format!("Failed to read '{}': {}", path.display(), e)))?;
+        let metadata = fs::metadata(path)
+            .map_err(|e| Error(format!("Failed to read '{}': {}", path.display(), e)))?;
+        let mut member = ArchiveMember::new(name, data);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            member.date = metadata.mtime().max(0) as u64;
+            member.uid = metadata.uid().into();
+            member.gid = metadata.gid().into();
+            member.mode = metadata.mode().into();
+        }
+        #[cfg(not(unix))]
+        {
+            if let Some(duration) = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            {
+                member.date = duration.as_secs();
+            }
+        }
+        Ok(member)
+    }
+}
+
+/// A writable archive file.
+#[derive(Debug)]
+pub struct Archive<'a> {
+    kind: ArchiveKind,
+    members: Vec<ArchiveMember<'a>>,
+    /// Write a symbol table.
+    ///
+    /// Defaults to true.
+    pub symbol_table: bool,
+    /// Use zero for the timestamp, user ID and group ID of all members.
+    ///
+    /// Defaults to false.
+    pub deterministic: bool,
+    /// Write a thin archive.
+    ///
+    /// The member data is used to determine the member size and symbols,
+    /// but is not written to the archive. The member name should be the path
+    /// of the file relative to the directory containing the archive.
+    ///
+    /// Only supported for [`ArchiveKind::Gnu`] and [`ArchiveKind::Gnu64`].
+    ///
+    /// Defaults to false.
+    pub thin: bool,
+}
+
+impl<'a> Archive<'a> {
+    /// Create an empty archive of the given kind.
+    ///
+    /// The kind must not be [`ArchiveKind::Unknown`].
+    ///
+    /// For [`ArchiveKind::Gnu`] and [`ArchiveKind::Bsd`], the 64-bit variant
+    /// of the symbol table is used if the archive is too large for 32-bit offsets.
+    pub fn new(kind: ArchiveKind) -> Self {
+        Archive {
+            kind,
+            members: Vec::new(),
+            symbol_table: true,
+            deterministic: false,
+            thin: false,
+        }
+    }
+
+    /// Return the archive format.
+    #[inline]
+    pub fn kind(&self) -> ArchiveKind {
+        self.kind
+    }
+
+    /// Return the members that have been added.
+    #[inline]
+    pub fn members(&self) -> &[ArchiveMember<'a>] {
+        &self.members
+    }
+
+    /// Mutably get the members that have been added.
+    #[inline]
+    pub fn members_mut(&mut self) -> &mut [ArchiveMember<'a>] {
+        &mut self.members
+    }
+
+    /// Add a member and return its index.
+    pub fn add_member(&mut self, member: ArchiveMember<'a>) -> usize {
+        let index = self.members.len();
+        self.members.push(member);
+        index
+    }
+
+    /// Write the archive to a `Vec`.
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.emit(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Write the archive to a `Write` implementation.
+    ///
+    /// Also flushes the writer.
+    ///
+    /// It is advisable to use a buffered writer like [`BufWriter`](std::io::BufWriter)
+    /// instead of an unbuffered writer like [`File`](std::fs::File).
+    #[cfg(feature = "std")]
+    pub fn write_stream<W: io::Write>(
+        &self,
+        w: W,
+    ) -> core::result::Result<(), Box<dyn error::Error>> {
+        let mut stream = StreamingBuffer::new(w);
+        self.emit(&mut stream)?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    /// Write the archive to a `WritableBuffer`.
+    pub fn emit(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        for member in &self.members {
+            if member.name.is_empty() {
+                return Err(Error(String::from("Archive member name must not be empty")));
+            }
+        }
+        if self.thin && self.kind != ArchiveKind::Gnu && self.kind != ArchiveKind::Gnu64 {
+            return Err(Error(String::from(
+                "Thin archives are only supported for the GNU format",
+            )));
+        }
+
+        let mut symbols = Vec::new();
+        if self.symbol_table {
+            for (index, member) in self.members.iter().enumerate() {
+                member_symbols(index, &member.data, &mut symbols)?;
+            }
+        }
+
+        match self.kind {
+            ArchiveKind::Gnu | ArchiveKind::Gnu64 | ArchiveKind::Bsd | ArchiveKind::Bsd64 => {
+                let layout = match self.layout(self.kind, &symbols) {
+                    Ok(layout) => layout,
+                    Err(_) if self.kind == ArchiveKind::Gnu => {
+                        self.layout(ArchiveKind::Gnu64, &symbols)?
+                    }
+                    Err(_) if self.kind == ArchiveKind::Bsd => {
+                        self.layout(ArchiveKind::Bsd64, &symbols)?
+                    }
+                    Err(e) => return Err(e),
+                };
+                self.write_common(buffer, &layout, &symbols)
+            }
+            ArchiveKind::Coff => {
+                let layout = self.layout(self.kind, &symbols)?;
+                self.write_common(buffer, &layout, &symbols)
+            }
+            ArchiveKind::AixBig => self.write_aixbig(buffer, &symbols),
+            ArchiveKind::Unknown => Err(Error(String::from("Unsupported archive kind"))),
+        }
+    }
+
+    fn member_fields(&self, member: &ArchiveMember<'_>) -> HeaderFields {
+        if self.deterministic {
+            HeaderFields {
+                date: 0,
+                uid: 0,
+                gid: 0,
+                mode: member.mode,
+            }
+        } else {
+            HeaderFields {
+                date: member.date,
+                uid: member.uid,
+                gid: member.gid,
+                mode: member.mode,
+            }
+        }
+    }
+
+    /// Calculate the file offsets for the archive formats that use the common header.
+    ///
+    /// Returns an error if an offset does not fit in the symbol table for `kind`.
+    fn layout(&self, kind: ArchiveKind, symbols: &[ArchiveSymbol<'_>]) -> Result<Layout> {
+        let is_bsd = kind == ArchiveKind::Bsd || kind == ArchiveKind::Bsd64;
+
+        // Build the extended name table.
+        let mut names = Vec::new();
+        let mut member_names = Vec::with_capacity(self.members.len());
+        for member in &self.members {
+            // Thin archives always use the extended name table, since the names are paths.
+            if is_bsd || (!self.thin && !needs_extended_name(&member.name)) {
+                member_names.push(None);
+                continue;
+            }
+            member_names.push(Some(names.len()));
+            names.extend_from_slice(&member.name);
+            if kind == ArchiveKind::Coff {
+                names.push(0);
+            } else {
+                names.extend_from_slice(b"/\n");
+            }
+        }
+
+        let mut offset = archive::MAGIC.len() as u64;
+
+        let mut symtab_offset = 0;
+        let mut symtab_size = 0;
+        let mut coff_symtab_offset = 0;
+        let mut coff_symtab_size = 0;
+        if self.symbol_table {
+            symtab_offset = offset;
+            symtab_size = symbol_table_size(kind, symbols);
+            let name: &[u8] = if kind == ArchiveKind::Bsd64 {
+                b"__.SYMDEF_64"
+            } else {
+                b"__.SYMDEF"
+            };
+            offset += member_size(is_bsd, offset, name, symtab_size);
+
+            if kind == ArchiveKind::Coff {
+                coff_symtab_offset = offset;
+                coff_symtab_size = coff_symbol_table_size(self.members.len(), symbols);
+                offset += member_size(false, offset, b"/", coff_symtab_size);
+            }
+        }
+
+        let mut names_offset = 0;
+        if !names.is_empty() || kind == ArchiveKind::Coff {
+            names_offset = offset;
+            offset += member_size(false, offset, b"//", names.len() as u64);
+        }
+
+        let mut members = Vec::with_capacity(self.members.len());
+        for (member, name) in self.members.iter().zip(member_names) {
+            members.push(MemberLayout {
+                offset,
+                extended_name: name,
+            });
+            if self.thin {
+                offset += mem::size_of::<archive::Header>() as u64;
+            } else {
+                offset += member_size(is_bsd, offset, &member.name, member.data.len() as u64);
+            }
+        }
+
+        let max_offset = match kind {
+            ArchiveKind::Gnu | ArchiveKind::Bsd | ArchiveKind::Coff => u64::from(u32::MAX),
+            _ => u64::MAX,
+        };
+        if members.iter().any(|member| member.offset > max_offset) {
+            return Err(Error(String::from(
+                "Archive member offset is too large for symbol table",
+            )));
+        }
+        if kind == ArchiveKind::Coff && self.members.len() > 0xffff {
+            return Err(Error(String::from("Too many members for COFF archive")));
+        }
+
+        Ok(Layout {
+            kind,
+            len: offset,
+            symtab_offset,
+            symtab_size,
+            coff_symtab_offset,
+            coff_symtab_size,
+            names,
+            names_offset,
+            members,
+        })
+    }
+
+    fn write_common(
+        &self,
+        buffer: &mut dyn WritableBuffer,
+        layout: &Layout,
+        symbols: &[ArchiveSymbol<'_>],
+    ) -> Result<()> {
+        let kind = layout.kind;
+        let is_bsd = kind == ArchiveKind::Bsd || kind == ArchiveKind::Bsd64;
+
+        let len =
+            usize::try_from(layout.len).map_err(|_| Error(String::from("Archive is too large")))?;
+        buffer
+            .reserve(len)
+            .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
+        if self.thin {
+            buffer.write_bytes(&archive::THIN_MAGIC);
+        } else {
+            buffer.write_bytes(&archive::MAGIC);
+        }
+
+        if self.symbol_table {
+            debug_assert_eq!(buffer.len() as u64, layout.symtab_offset);
+            let fields = HeaderFields::default();
+            match kind {
+                ArchiveKind::Bsd => {
+                    write_bsd_header(buffer, b"__.SYMDEF", &fields, layout.symtab_size)?
+                }
+                ArchiveKind::Bsd64 => {
+                    write_bsd_header(buffer, b"__.SYMDEF_64", &fields, layout.symtab_size)?
+                }
+                ArchiveKind::Gnu64 => {
+                    write_header(buffer, b"/SYM64/", Some(&fields), layout.symtab_size)?
+                }
+                _ => write_header(buffer, b"/", Some(&fields), layout.symtab_size)?,
+            }
+            write_symbol_table(buffer, kind, symbols, &layout.members);
+            write_padding(buffer, layout.symtab_size);
+
+            if kind == ArchiveKind::Coff {
+                debug_assert_eq!(buffer.len() as u64, layout.coff_symtab_offset);
+                write_header(buffer, b"/", Some(&fields), layout.coff_symtab_size)?;
+                write_coff_symbol_table(buffer, symbols, &layout.members);
+                write_padding(buffer, layout.coff_symtab_size);
+            }
+        }
+
+        if layout.names_offset != 0 {
+            debug_assert_eq!(buffer.len() as u64, layout.names_offset);
+            let size = layout.names.len() as u64;
+            write_header(buffer, b"//", None, size)?;
+            buffer.write_bytes(&layout.names);
+            write_padding(buffer, size);
+        }
+
+        for (member, member_layout) in self.members.iter().zip(&layout.members) {
+            debug_assert_eq!(buffer.len() as u64, member_layout.offset);
+            let fields = self.member_fields(member);
+            let size = member.data.len() as u64;
+            if is_bsd {
+                write_bsd_header(buffer, &member.name, &fields, size)?;
+            } else if let Some(offset) = member_layout.extended_name {
+                let mut name = [b' '; 16];
+                name[0] = b'/';
+                write_decimal(&mut name[1..], offset as u64)?;
+                write_header(buffer, &name, Some(&fields), size)?;
+            } else {
+                let mut name = member.name.clone();
+                name.push(b'/');
+                write_header(buffer, &name, Some(&fields), size)?;
+            }
+            if !self.thin {
+                buffer.write_bytes(&member.data);
+                write_padding(buffer, size);
+            }
+        }
+
+        debug_assert_eq!(buffer.len() as u64, layout.len);
+        Ok(())
+    }
+
+    fn write_aixbig(
+        &self,
+        buffer: &mut dyn WritableBuffer,
+        symbols: &[ArchiveSymbol<'_>],
+    ) -> Result<()> {
+        // The format is described at
+        // https://www.ibm.com/docs/en/aix/7.3?topic=formats-ar-file-format-big
+        let mut offset = mem::size_of::<archive::AixFileHeader>() as u64;
+
+        let mut member_offsets = Vec::with_capacity(self.members.len());
+        for member in &self.members {
+            member_offsets.push(offset);
+            offset += aixbig_member_size(&member.name, member.data.len() as u64);
+        }
+
+        // The member table contains the member count, the member offsets,
+        // and the null terminated member names.
+        let mut member_table = Vec::new();
+        if !self.members.is_empty() {
+            let mut field = [b' '; 20];
+            write_decimal(&mut field, self.members.len() as u64)?;
+            member_table.extend_from_slice(&field);
+            for member_offset in &member_offsets {
+                let mut field = [b' '; 20];
+                write_decimal(&mut field, *member_offset)?;
+                member_table.extend_from_slice(&field);
+            }
+            for member in &self.members {
+                member_table.extend_from_slice(&member.name);
+                member_table.push(0);
+            }
+        }
+        let member_table_offset = if member_table.is_empty() { 0 } else { offset };
+        offset += aixbig_member_size(&[], member_table.len() as u64);
+
+        // 32-bit and 64-bit objects have separate symbol tables.
+        let symbols32: Vec<_> = symbols.iter().filter(|s| !s.is_64).cloned().collect();
+        let symbols64: Vec<_> = symbols.iter().filter(|s| s.is_64).cloned().collect();
+        let mut symtab_offset = 0;
+        let symtab_size = symbol_table_size(ArchiveKind::AixBig, &symbols32);
+        if !symbols32.is_empty() {
+            symtab_offset = offset;
+            offset += aixbig_member_size(&[], symtab_size);
+        }
+        let mut symtab64_offset = 0;
+        let symtab64_size = symbol_table_size(ArchiveKind::AixBig, &symbols64);
+        if !symbols64.is_empty() {
+            symtab64_offset = offset;
+            offset += aixbig_member_size(&[], symtab64_size);
+        }
+
+        let len =
+            usize::try_from(offset).map_err(|_| Error(String::from("Archive is too large")))?;
+        buffer
+            .reserve(len)
+            .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
+
+        let mut file_header = archive::AixFileHeader {
+            magic: archive::AIX_BIG_MAGIC,
+            memoff: [b' '; 20],
+            gstoff: [b' '; 20],
+            gst64off: [b' '; 20],
+            fstmoff: [b' '; 20],
+            lstmoff: [b' '; 20],
+            freeoff: [b' '; 20],
+        };
+        write_decimal(&mut file_header.memoff, member_table_offset)?;
+        write_decimal(&mut file_header.gstoff, symtab_offset)?;
+        write_decimal(&mut file_header.gst64off, symtab64_offset)?;
+        write_decimal(
+            &mut file_header.fstmoff,
+            member_offsets.first().copied().unwrap_or(0),
+        )?;
+        write_decimal(
+            &mut file_header.lstmoff,
+            member_offsets.last().copied().unwrap_or(0),
+        )?;
+        write_decimal(&mut file_header.freeoff, 0)?;
+        buffer.write(&file_header);
+
+        let mut prev_offset = 0;
+        for (index, member) in self.members.iter().enumerate() {
+            debug_assert_eq!(buffer.len() as u64, member_offsets[index]);
+            let next_offset = member_offsets.get(index + 1).copied().unwrap_or(0);
+            write_aixbig_header(
+                buffer,
+                &member.name,
+                &self.member_fields(member),
+                member.data.len() as u64,
+                prev_offset,
+                next_offset,
+            )?;
+            buffer.write_bytes(&member.data);
+            write_padding(buffer, member.data.len() as u64);
+            prev_offset = member_offsets[index];
+        }
+
+        let fields = HeaderFields::default();
+        if member_table_offset != 0 {
+            debug_assert_eq!(buffer.len() as u64, member_table_offset);
+            let next_offset = if symtab_offset != 0 {
+                symtab_offset
+            } else {
+                symtab64_offset
+            };
+            write_aixbig_header(
+                buffer,
+                &[],
+                &fields,
+                member_table.len() as u64,
+                prev_offset,
+                next_offset,
+            )?;
+            buffer.write_bytes(&member_table);
+            write_padding(buffer, member_table.len() as u64);
+        }
+
+        let members: Vec<_> = member_offsets
+            .iter()
+            .map(|&offset| MemberLayout {
+                offset,
+                extended_name: None,
+            })
+            .collect();
+        if symtab_offset != 0 {
+            debug_assert_eq!(buffer.len() as u64, symtab_offset);
+            write_aixbig_header(
+                buffer,
+                &[],
+                &fields,
+                symtab_size,
+                member_table_offset,
+                symtab64_offset,
+            )?;
+            write_symbol_table(buffer, ArchiveKind::AixBig, &symbols32, &members);
+            write_padding(buffer, symtab_size);
+        }
+        if symtab64_offset != 0 {
+            debug_assert_eq!(buffer.len() as u64, symtab64_offset);
+            let prev_offset = if symtab_offset != 0 {
+                symtab_offset
+            } else {
+                member_table_offset
+            };
+            write_aixbig_header(buffer, &[], &fields, symtab64_size, prev_offset, 0)?;
+            write_symbol_table(buffer, ArchiveKind::AixBig, &symbols64, &members);
+            write_padding(buffer, symtab64_size);
+        }
+
+        debug_assert_eq!(buffer.len() as u64, offset);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> Archive<'a> {
+    /// Create a thin archive that references the files at the given paths.
+    ///
+    /// `archive_path` is the path that the archive will be written to.
+    /// The member names are the paths of the files relative to the directory
+    /// containing the archive.
+    ///
+    /// The files are read in order to determine the member sizes and symbols.
+    pub fn new_thin<P, I>(kind: ArchiveKind, archive_path: P, paths: I) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        let dir = archive_dir(archive_path.as_ref());
+        let mut archive = Archive::new(kind);
+        archive.thin = true;
+        for path in paths {
+            let path = path.as_ref();
+            let mut member = ArchiveMember::from_path(path)?;
+            member.name = path_bytes(&relative_path(dir, path)?)?;
+            archive.add_member(member);
+        }
+        Ok(archive)
+    }
+
+    /// Create a regular archive from the members of a thin archive.
+    ///
+    /// `data` is the contents of the thin archive, and `archive_path` is the
+    /// path that it was read from. The paths of the thin members are resolved
+    /// relative to the directory containing the archive, and the referenced files
+    /// are read to obtain the member data. The member names are the file names
+    /// of the referenced files.
+    ///
+    /// Members that are not thin are copied as is.
+    /// The symbol table is regenerated when the archive is written.
+    ///
+    /// Returns an error if a thin member has the same file name as another member,
+    /// since the members would no longer be distinguishable.
+    pub fn flatten_thin<P: AsRef<Path>>(data: &'a [u8], archive_path: P) -> Result<Self> {
+        let file =
+            ArchiveFile::parse(data).map_err(|e| Error(format!("Invalid archive: {}", e)))?;
+        let kind = match file.kind() {
+            ArchiveKind::Unknown => ArchiveKind::Gnu,
+            kind => kind,
+        };
+        let dir = archive_dir(archive_path.as_ref());
+        let mut archive = Archive::new(kind);
+        let mut thin_names = Vec::new();
+        for member in file.members() {
+            let member = member.map_err(|e| Error(format!("Invalid archive member: {}", e)))?;
+            let mut new_member = if member.is_thin() {
+                let path = core::str::from_utf8(member.name())
+                    .map_err(|_| Error(String::from("Invalid thin archive member path")))?;
+                let new_member = ArchiveMember::from_path(dir.join(path))?;
+                if archive
+                    .members
+                    .iter()
+                    .any(|other| other.name == new_member.name)
+                {
+                    return Err(Error(format!(
+                        "Duplicate archive member name '{}' for thin member '{}'",
+                        String::from_utf8_lossy(&new_member.name),
+                        path
+                    )));
+                }
+                thin_names.push(new_member.name.clone());
+                new_member
+            } else {
+                let data = member
+                    .data(data)
+                    .map_err(|e| Error(format!("Invalid archive member: {}", e)))?;
+                if thin_names.iter().any(|name| name == member.name()) {
+                    return Err(Error(format!(
+                        "Duplicate archive member name '{}' for thin member",
+                        String::from_utf8_lossy(member.name())
+                    )));
+                }
+                ArchiveMember::new(member.name().to_vec(), data)
+            };
+            new_member.date = member.date().unwrap_or(0);
+            new_member.uid = member.uid().unwrap_or(0);
+            new_member.gid = member.gid().unwrap_or(0);
+            new_member.mode = member.mode().unwrap_or(new_member.mode);
+            archive.add_member(new_member);
+        }
+        Ok(archive)
+    }
+}
+
+/// A symbol to be written to the archive symbol table.
+#[derive(Debug, Clone)]
+struct ArchiveSymbol<'a> {
+    name: Cow<'a, [u8]>,
+    member: usize,
+    is_64: bool,
+}
+
+/// File offsets for the archive formats that use the common header.
+#[derive(Debug)]
+struct Layout {
+    kind: ArchiveKind,
+    len: u64,
+    symtab_offset: u64,
+    symtab_size: u64,
+    coff_symtab_offset: u64,
+    coff_symtab_size: u64,
+    names: Vec<u8>,
+    names_offset: u64,
+    members: Vec<MemberLayout>,
+}
+
+#[derive(Debug)]
+struct MemberLayout {
+    /// The offset of the member header.
+    offset: u64,
+    /// The offset of the name in the extended name table.
+    extended_name: Option<usize>,
+}
+
+/// Header fields other than the name and size.
+#[derive(Debug, Default)]
+struct HeaderFields {
+    date: u64,
+    uid: u64,
+    gid: u64,
+    mode: u64,
+}
+
+/// Add the global symbols defined by a member to the symbol list.
+///
+/// Does nothing if the member is not a recognized object file.
+#[cfg(any(
+    feature = "coff",
+    feature = "elf",
+    feature = "macho",
+    feature = "pe",
+    feature = "wasm",
+    feature = "xcoff"
+))]
+fn member_symbols<'data>(
+    member: usize,
+    data: &'data [u8],
+    symbols: &mut Vec<ArchiveSymbol<'data>>,
+) -> Result<()> {
+    let kind = match read::FileKind::parse(data) {
+        Ok(kind) => kind,
+        Err(_) => return Ok(()),
+    };
+    match kind {
+        #[cfg(feature = "coff")]
+        read::FileKind::CoffImport => {
+            let file = read::coff::ImportFile::parse(data)
+                .map_err(|e| Error(format!("Invalid archive member: {}", e)))?;
+            let mut name = b"__imp_".to_vec();
+            name.extend_from_slice(file.symbol());
+            symbols.push(ArchiveSymbol {
+                name: Cow::Owned(name),
+                member,
+                is_64: false,
+            });
+            if file.import_type() != read::coff::ImportType::Data {
+                symbols.push(ArchiveSymbol {
+                    name: Cow::Borrowed(file.symbol()),
+                    member,
+                    is_64: false,
+                });
+            }
+        }
+        read::FileKind::Archive => {}
+        #[cfg(feature = "macho")]
+        read::FileKind::DyldCache | read::FileKind::MachOFat32 | read::FileKind::MachOFat64 => {}
+        _ => {
+            let file = read::File::parse(data)
+                .map_err(|e| Error(format!("Invalid archive member: {}", e)))?;
+            let is_64 = file.is_64();
+            for symbol in file.symbols() {
+                if !symbol.is_global() || symbol.is_undefined() {
+                    continue;
+                }
+                let name = symbol
+                    .name_bytes()
+                    .map_err(|e| Error(format!("Invalid archive member symbol: {}", e)))?;
+                if name.is_empty() {
+                    continue;
+                }
+                symbols.push(ArchiveSymbol {
+                    name: Cow::Borrowed(name),
+                    member,
+                    is_64,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add the global symbols defined by a member to the symbol list.
+///
+/// Does nothing because no object file formats are enabled.
+#[cfg(not(any(
+    feature = "coff",
+    feature = "elf",
+    feature = "macho",
+    feature = "pe",
+    feature = "wasm",
+    feature = "xcoff"
+)))]
+fn member_symbols(
+    _member: usize,
+    _data: &[u8],
+    _symbols: &mut Vec<ArchiveSymbol<'_>>,
+) -> Result<()> {
+    Ok(())
+}
+
+/// Return true if the name does not fit in the header of the GNU or COFF formats.
+fn needs_extended_name(name: &[u8]) -> bool {
+    // The name is terminated by a slash, so it can't contain one.
+    name.len() > 15 || name.contains(&b'/')
+}
+
+/// Return the size of the symbol table data.
+fn symbol_table_size(kind: ArchiveKind, symbols: &[ArchiveSymbol<'_>]) -> u64 {
+    let count = symbols.len() as u64;
+    let names_size = symbols
+        .iter()
+        .map(|symbol| symbol.name.len() as u64 + 1)
+        .sum::<u64>();
+    match kind {
+        ArchiveKind::Gnu | ArchiveKind::Coff => 4 + 4 * count + names_size,
+        ArchiveKind::Gnu64 | ArchiveKind::AixBig => 8 + 8 * count + names_size,
+        ArchiveKind::Bsd => 4 + 8 * count + 4 + util::align_u64(names_size, 4),
+        ArchiveKind::Bsd64 => 8 + 16 * count + 8 + util::align_u64(names_size, 8),
+        ArchiveKind::Unknown => 0,
+    }
+}
+
+/// Return the size of the second linker member data for the COFF format.
+fn coff_symbol_table_size(member_count: usize, symbols: &[ArchiveSymbol<'_>]) -> u64 {
+    let names_size = symbols
+        .iter()
+        .map(|symbol| symbol.name.len() as u64 + 1)
+        .sum::<u64>();
+    4 + 4 * member_count as u64 + 4 + 2 * symbols.len() as u64 + names_size
+}
+
+/// Return the size of a member including the header and padding.
+fn member_size(is_bsd: bool, offset: u64, name: &[u8], size: u64) -> u64 {
+    let mut header_size = mem::size_of::<archive::Header>() as u64;
+    if is_bsd {
+        header_size += bsd_name_size(offset, name);
+    }
+    header_size + size + (size & 1)
+}
+
+/// Return the size of a BSD extended name including padding.
+///
+/// The padding ensures the member data is 8 byte aligned.
+fn bsd_name_size(offset: u64, name: &[u8]) -> u64 {
+    let header_end = offset + mem::size_of::<archive::Header>() as u64;
+    util::align_u64(header_end + name.len() as u64, 8) - header_end
+}
+
+/// Return the size of an AIX big archive member including the header and padding.
+fn aixbig_member_size(name: &[u8], size: u64) -> u64 {
+    let name_size = name.len() as u64;
+    mem::size_of::<archive::AixHeader>() as u64
+        + name_size
+        + (name_size & 1)
+        + archive::TERMINATOR.len() as u64
+        + size
+        + (size & 1)
+}
+
+/// Write a decimal number into a space padded field.
+fn write_decimal(field: &mut [u8], value: u64) -> Result<()> {
+    write_number(field, value, 10)
+}
+
+/// Write an octal number into a space padded field.
+fn write_octal(field: &mut [u8], value: u64) -> Result<()> {
+    write_number(field, value, 8)
+}
+
+fn write_number(field: &mut [u8], mut value: u64, radix: u64) -> Result<()> {
+    let mut digits = [0u8; 22];
+    let mut len = 0;
+    loop {
+        digits[len] = b'0' + (value % radix) as u8;
+        len += 1;
+        value /= radix;
+        if value == 0 {
+            break;
+        }
+    }
+    if len > field.len() {
+        return Err(Error(String::from(
+            "Archive header field value is too large",
+        )));
+    }
+    for (dst, src) in field.iter_mut().zip(digits[..len].iter().rev()) {
+        *dst = *src;
+    }
+    for dst in &mut field[len..] {
+        *dst = b' ';
+    }
+    Ok(())
+}
+
+/// Write a header in the common format.
+///
+/// The name is written as is and must fit in the header.
+/// If `fields` is `None` then the fields other than the name and size are left blank.
+fn write_header(
+    buffer: &mut dyn WritableBuffer,
+    name: &[u8],
+    fields: Option<&HeaderFields>,
+    size: u64,
+) -> Result<()> {
+    let mut header = archive::Header {
+        name: [b' '; 16],
+        date: [b' '; 12],
+        uid: [b' '; 6],
+        gid: [b' '; 6],
+        mode: [b' '; 8],
+        size: [b' '; 10],
+        terminator: archive::TERMINATOR,
+    };
+    debug_assert!(name.len() <= header.name.len());
+    header.name[..name.len()].copy_from_slice(name);
+    if let Some(fields) = fields {
+        write_decimal(&mut header.date, fields.date)?;
+        write_decimal(&mut header.uid, fields.uid)?;
+        write_decimal(&mut header.gid, fields.gid)?;
+        write_octal(&mut header.mode, fields.mode)?;
+    }
+    write_decimal(&mut header.size, size)?;
+    buffer.write(&header);
+    Ok(())
+}
+
+/// Write a header using a BSD extended name, followed by the name and its padding.
+fn write_bsd_header(
+    buffer: &mut dyn WritableBuffer,
+    name: &[u8],
+    fields: &HeaderFields,
+    size: u64,
+) -> Result<()> {
+    let name_size = bsd_name_size(buffer.len() as u64, name);
+    let mut header_name = [b' '; 16];
+    header_name[..3].copy_from_slice(b"#1/");
+    write_decimal(&mut header_name[3..], name_size)?;
+    write_header(buffer, &header_name, Some(fields), name_size + size)?;
+    buffer.write_bytes(name);
+    buffer.resize(buffer.len() + (name_size as usize - name.len()));
+    Ok(())
+}
+
+/// Write an AIX big archive member header, followed by the name and terminator.
+fn write_aixbig_header(
+    buffer: &mut dyn WritableBuffer,
+    name: &[u8],
+    fields: &HeaderFields,
+    size: u64,
+    prev_offset: u64,
+    next_offset: u64,
+) -> Result<()> {
+    let mut header = archive::AixHeader {
+        size: [b' '; 20],
+        nxtmem: [b' '; 20],
+        prvmem: [b' '; 20],
+        date: [b' '; 12],
+        uid: [b' '; 12],
+        gid: [b' '; 12],
+        mode: [b' '; 12],
+        namlen: [b' '; 4],
+    };
+    write_decimal(&mut header.size, size)?;
+    write_decimal(&mut header.nxtmem, next_offset)?;
+    write_decimal(&mut header.prvmem, prev_offset)?;
+    write_decimal(&mut header.date, fields.date)?;
+    write_decimal(&mut header.uid, fields.uid)?;
+    write_decimal(&mut header.gid, fields.gid)?;
+    write_octal(&mut header.mode, fields.mode)?;
+    write_decimal(&mut header.namlen, name.len() as u64)?;
+    buffer.write(&header);
+    buffer.write_bytes(name);
+    write_padding(buffer, name.len() as u64);
+    buffer.write_bytes(&archive::TERMINATOR);
+    Ok(())
+}
+
+/// Write the padding after member data.
+///
+/// Members are padded to an even number of bytes.
+fn write_padding(buffer: &mut dyn WritableBuffer, size: u64) {
+    if size & 1 != 0 {
+        buffer.write_bytes(b"\n");
+    }
+}
+
+/// Write the symbol table data.
+///
+/// For the COFF format, this is the first linker member.
+fn write_symbol_table(
+    buffer: &mut dyn WritableBuffer,
+    kind: ArchiveKind,
+    symbols: &[ArchiveSymbol<'_>],
+    members: &[MemberLayout],
+) {
+    match kind {
+        ArchiveKind::Gnu | ArchiveKind::Coff => {
+            buffer.write(&U32::new(BE, symbols.len() as u32));
+            for symbol in symbols {
+                buffer.write(&U32::new(BE, members[symbol.member].offset as u32));
+            }
+            for symbol in symbols {
+                buffer.write_bytes(&symbol.name);
+                buffer.write_bytes(&[0]);
+            }
+        }
+        ArchiveKind::Gnu64 | ArchiveKind::AixBig => {
+            buffer.write(&U64::new(BE, symbols.len() as u64));
+            for symbol in symbols {
+                buffer.write(&U64::new(BE, members[symbol.member].offset));
+            }
+            for symbol in symbols {
+                buffer.write_bytes(&symbol.name);
+                buffer.write_bytes(&[0]);
+            }
+        }
+        ArchiveKind::Bsd => {
+            buffer.write(&U32::new(LE, symbols.len() as u32 * 8));
+            let mut name_offset = 0;
+            for symbol in symbols {
+                buffer.write(&U32::new(LE, name_offset as u32));
+                buffer.write(&U32::new(LE, members[symbol.member].offset as u32));
+                name_offset += symbol.name.len() + 1;
+            }
+            let names_size = util::align(name_offset, 4);
+            buffer.write(&U32::new(LE, names_size as u32));
+            for symbol in symbols {
+                buffer.write_bytes(&symbol.name);
+                buffer.write_bytes(&[0]);
+            }
+            buffer.resize(buffer.len() + names_size - name_offset);
+        }
+        ArchiveKind::Bsd64 => {
+            buffer.write(&U64::new(LE, symbols.len() as u64 * 16));
+            let mut name_offset = 0;
+            for symbol in symbols {
+                buffer.write(&U64::new(LE, name_offset as u64));
+                buffer.write(&U64::new(LE, members[symbol.member].offset));
+                name_offset += symbol.name.len() + 1;
+            }
+            let names_size = util::align(name_offset, 8);
+            buffer.write(&U64::new(LE, names_size as u64));
+            for symbol in symbols {
+                buffer.write_bytes(&symbol.name);
+                buffer.write_bytes(&[0]);
+            }
+            buffer.resize(buffer.len() + names_size - name_offset);
+        }
+        ArchiveKind::Unknown => {}
+    }
+}
+
+/// Write the second linker member data for the COFF format.
+///
+/// This contains the member offsets, followed by the symbols sorted by name.
+fn write_coff_symbol_table(
+    buffer: &mut dyn WritableBuffer,
+    symbols: &[ArchiveSymbol<'_>],
+    members: &[MemberLayout],
+) {
+    buffer.write(&U32::new(LE, members.len() as u32));
+    for member in members {
+        buffer.write(&U32::new(LE, member.offset as u32));
+    }
+
+    let mut sorted: Vec<_> = symbols.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    buffer.write(&U32::new(LE, sorted.len() as u32));
+    for symbol in &sorted {
+        // Indices are 1-based.
+        buffer.write(&U16::new(LE, symbol.member as u16 + 1));
+    }
+    for symbol in &sorted {
+        buffer.write_bytes(&symbol.name);
+        buffer.write_bytes(&[0]);
+    }
+}
+
+/// Return the directory containing the archive.
+#[cfg(feature = "std")]
+fn archive_dir(archive_path: &Path) -> &Path {
+    match archive_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Return `path` relative to `dir`.
+///
+/// Returns an absolute path if a relative path can't be determined.
+#[cfg(feature = "std")]
+fn relative_path(dir: &Path, path: &Path) -> Result<PathBuf> {
+    let current_dir =
+        || env::current_dir().map_err(|e| Error(format!("Failed to get current directory: {}", e)));
+    let (dir, path) = if dir.is_absolute() == path.is_absolute() {
+        (dir.to_path_buf(), path.to_path_buf())
+    } else {
+        let current_dir = current_dir()?;
+        (current_dir.join(dir), current_dir.join(path))
+    };
+
+    let dir_components: Vec<_> = dir
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let path_components: Vec<_> = path
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let common = dir_components
+        .iter()
+        .zip(&path_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let remaining = &dir_components[common..];
+    if remaining.iter().any(|c| !matches!(c, Component::Normal(_))) {
+        return if path.is_absolute() {
+            Ok(path)
+        } else {
+            Ok(current_dir()?.join(path))
+        };
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in remaining {
+        relative.push("..");
+    }
+    for component in &path_components[common..] {
+        relative.push(component);
+    }
+    Ok(relative)
+}
+
+/// Convert a path to the bytes used for an archive member name.
+#[cfg(feature = "std")]
+fn path_bytes(path: &Path) -> Result<Vec<u8>> {
+    match path.to_str() {
+        Some(path) => Ok(path.as_bytes().to_vec()),
+        None => Err(Error(format!(
+            "Archive member path '{}' is not valid UTF-8",
+            path.display()
+        ))),
+    }
+}
@@ -0,0 +1,99 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::pe;
+use crate::read::coff::ModuleDefinition;
+use crate::write::coff::{CoffExportStyle, ImportLibrary, ShortImport};
+use crate::write::{Error, Result};
+
+impl ModuleDefinition {
+    /// Create an import library for the exports.
+    ///
+    /// `PRIVATE` exports are omitted. For `IMAGE_FILE_MACHINE_I386`, the
+    /// symbol names are given a `_` prefix, unless they start with `?` or `@`.
+    pub fn import_library(&self, machine: u16) -> Result<ImportLibrary> {
+        let dll = self
+            .image_name()
+            .ok_or_else(|| Error(String::from("missing module-definition library name")))?;
+        let mut library = ImportLibrary::new(dll, machine);
+        for export in &self.exports {
+            if export.private {
+                continue;
+            }
+            let import_type = if export.data {
+                pe::IMPORT_OBJECT_DATA
+            } else if export.constant {
+                pe::IMPORT_OBJECT_CONST
+            } else {
+                pe::IMPORT_OBJECT_CODE
+            };
+            let mut import = if machine == pe::IMAGE_FILE_MACHINE_I386 {
+                let mut import = ShortImport::new(Vec::new(), import_type);
+                match export.name.first() {
+                    Some(b'?') => {}
+                    Some(b'@') => import.name_type = pe::IMPORT_OBJECT_NAME_UNDECORATE,
+                    _ => {
+                        import.symbol.push(b'_');
+                        import.name_type = pe::IMPORT_OBJECT_NAME_UNDECORATE;
+                    }
+                }
+                import.symbol.extend_from_slice(&export.name);
+                import
+            } else {
+                ShortImport::new(export.name.clone(), import_type)
+            };
+            if let Some(ordinal) = export.ordinal {
+                if export.noname {
+                    import.name_type = pe::IMPORT_OBJECT_ORDINAL;
+                }
+                import.ordinal_or_hint = ordinal;
+            } else if export.noname {
+                return Err(Error(format!(
+                    "missing ordinal for NONAME export `{}`",
+                    String::from_utf8_lossy(&export.name)
+                )));
+            }
+            library.imports.push(import);
+        }
+        Ok(library)
+    }
+
+    /// Return the linker directives for the exports.
+    ///
+    /// The directives can be used as the contents of a `.drectve` section.
+    pub fn coff_directives(&self, style: CoffExportStyle) -> Vec<u8> {
+        let mut directives = Vec::new();
+        for export in &self.exports {
+            match style {
+                CoffExportStyle::Msvc => directives.extend(b" /EXPORT:\""),
+                CoffExportStyle::Gnu => directives.extend(b" -export:\""),
+            }
+            directives.extend(&export.name);
+            if !export.internal_name.is_empty() {
+                directives.push(b'=');
+                directives.extend(&export.internal_name);
+            }
+            directives.push(b'"');
+            if let Some(ordinal) = export.ordinal {
+                directives.extend(format!(",@{}", ordinal).as_bytes());
+                if export.noname {
+                    directives.extend(b",NONAME");
+                }
+            }
+            let flags: [(bool, &[u8], &[u8]); 3] = [
+                (export.data, b",DATA", b",data"),
+                (export.constant, b",CONSTANT", b",constant"),
+                (export.private, b",PRIVATE", b",private"),
+            ];
+            for (set, msvc, gnu) in flags {
+                if set {
+                    match style {
+                        CoffExportStyle::Msvc => directives.extend(msvc),
+                        CoffExportStyle::Gnu => directives.extend(gnu),
+                    }
+                }
+            }
+        }
+        directives
+    }
+}
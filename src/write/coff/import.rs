@@ -0,0 +1,431 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::pe;
+use crate::write::coff::writer;
+use crate::write::{Error, Result, WritableBuffer};
+
+/// An import in an [`ImportLibrary`].
+///
+/// This is written as a short import object, which consists of a
+/// [`pe::ImportObjectHeader`] followed by the symbol name and DLL name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortImport {
+    /// The name of the public symbol for the import.
+    ///
+    /// This is the name that is used by object files that link against the
+    /// import library, and includes any decoration. The import library also
+    /// defines the symbol with an `__imp_` prefix.
+    pub symbol: Vec<u8>,
+    /// The import type.
+    ///
+    /// One of `IMPORT_OBJECT_CODE`, `IMPORT_OBJECT_DATA`, or `IMPORT_OBJECT_CONST`.
+    pub import_type: u16,
+    /// The name type, which determines the name that is imported from the DLL.
+    ///
+    /// One of `IMPORT_OBJECT_ORDINAL`, `IMPORT_OBJECT_NAME`,
+    /// `IMPORT_OBJECT_NAME_NO_PREFIX`, `IMPORT_OBJECT_NAME_UNDECORATE`,
+    /// or `IMPORT_OBJECT_NAME_EXPORTAS`.
+    pub name_type: u16,
+    /// The ordinal if the name type is `IMPORT_OBJECT_ORDINAL`, otherwise the hint.
+    pub ordinal_or_hint: u16,
+    /// The name that is imported from the DLL.
+    ///
+    /// Only used if the name type is `IMPORT_OBJECT_NAME_EXPORTAS`.
+    pub export_name: Vec<u8>,
+}
+
+impl ShortImport {
+    /// Create an import by name.
+    ///
+    /// The name type is `IMPORT_OBJECT_NAME` and the hint is 0.
+    pub fn new(symbol: Vec<u8>, import_type: u16) -> Self {
+        ShortImport {
+            symbol,
+            import_type,
+            name_type: pe::IMPORT_OBJECT_NAME,
+            ordinal_or_hint: 0,
+            export_name: Vec::new(),
+        }
+    }
+}
+
+/// A writer for a COFF import library.
+///
+/// An import library is an archive that allows linking against the exports
+/// of a DLL. It contains a short import object for each import, and three
+/// regular COFF objects that the linker uses to build the import directory
+/// entry for the DLL:
+/// - the import descriptor, which defines `__IMPORT_DESCRIPTOR_<name>`
+/// - the null import descriptor, which defines `__NULL_IMPORT_DESCRIPTOR`
+/// - the null thunk, which defines `\x7f<name>_NULL_THUNK_DATA`
+///
+/// `<name>` is the DLL name without its extension.
+#[derive(Debug, Clone)]
+pub struct ImportLibrary {
+    /// The name of the DLL, including its extension.
+    pub dll: Vec<u8>,
+    /// The machine type.
+    ///
+    /// One of `IMAGE_FILE_MACHINE_I386`, `IMAGE_FILE_MACHINE_AMD64`,
+    /// `IMAGE_FILE_MACHINE_ARMNT`, `IMAGE_FILE_MACHINE_ARM64`,
+    /// or `IMAGE_FILE_MACHINE_ARM64EC`.
+    pub machine: u16,
+    /// The imports.
+    pub imports: Vec<ShortImport>,
+}
+
+impl ImportLibrary {
+    /// Create an import library with no imports.
+    pub fn new(dll: Vec<u8>, machine: u16) -> Self {
+        ImportLibrary {
+            dll,
+            machine,
+            imports: Vec::new(),
+        }
+    }
+
+    /// Write the import library as a COFF archive.
+    ///
+    /// All archive members are named after the DLL, matching the output of
+    /// `lib.exe` and `llvm-dlltool`.
+    #[cfg(all(feature = "archive", feature = "read_core"))]
+    pub fn write(&self) -> Result<Vec<u8>> {
+        use crate::read::archive::ArchiveKind;
+        use crate::write::archive::{Archive, ArchiveMember};
+
+        let mut archive = Archive::new(ArchiveKind::Coff);
+        archive.deterministic = true;
+        let mut data = Vec::new();
+        self.write_import_descriptor(&mut data)?;
+        archive.add_member(ArchiveMember::new(self.dll.clone(), data));
+        let mut data = Vec::new();
+        self.write_null_import_descriptor(&mut data)?;
+        archive.add_member(ArchiveMember::new(self.dll.clone(), data));
+        let mut data = Vec::new();
+        self.write_null_thunk(&mut data)?;
+        archive.add_member(ArchiveMember::new(self.dll.clone(), data));
+        for import in &self.imports {
+            let mut data = Vec::new();
+            self.write_short_import(import, &mut data)?;
+            archive.add_member(ArchiveMember::new(self.dll.clone(), data));
+        }
+        archive.write()
+    }
+
+    /// Write a short import object for the given import.
+    pub fn write_short_import(
+        &self,
+        import: &ShortImport,
+        buffer: &mut dyn WritableBuffer,
+    ) -> Result<()> {
+        self.relocation_type()?;
+        if import.import_type & !pe::IMPORT_OBJECT_TYPE_MASK != 0 {
+            return Err(Error(format!(
+                "invalid COFF import type {} for `{}`",
+                import.import_type,
+                String::from_utf8_lossy(&import.symbol)
+            )));
+        }
+        if import.name_type & !pe::IMPORT_OBJECT_NAME_MASK != 0
+            || import.name_type > pe::IMPORT_OBJECT_NAME_EXPORTAS
+        {
+            return Err(Error(format!(
+                "invalid COFF import name type {} for `{}`",
+                import.name_type,
+                String::from_utf8_lossy(&import.symbol)
+            )));
+        }
+        let mut size_of_data = import.symbol.len() + 1 + self.dll.len() + 1;
+        if import.name_type == pe::IMPORT_OBJECT_NAME_EXPORTAS {
+            size_of_data += import.export_name.len() + 1;
+        }
+        let header = pe::ImportObjectHeader {
+            sig1: pe::IMAGE_FILE_MACHINE_UNKNOWN.into(),
+            sig2: pe::IMPORT_OBJECT_HDR_SIG2.into(),
+            version: 0.into(),
+            machine: self.machine.into(),
+            time_date_stamp: 0.into(),
+            size_of_data: (size_of_data as u32).into(),
+            ordinal_or_hint: import.ordinal_or_hint.into(),
+            name_type: ((import.name_type << pe::IMPORT_OBJECT_NAME_SHIFT)
+                | (import.import_type << pe::IMPORT_OBJECT_TYPE_SHIFT))
+                .into(),
+        };
+        buffer
+            .reserve(core::mem::size_of::<pe::ImportObjectHeader>() + size_of_data)
+            .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
+        buffer.write(&header);
+        buffer.write_bytes(&import.symbol);
+        buffer.write_bytes(&[0]);
+        buffer.write_bytes(&self.dll);
+        buffer.write_bytes(&[0]);
+        if import.name_type == pe::IMPORT_OBJECT_NAME_EXPORTAS {
+            buffer.write_bytes(&import.export_name);
+            buffer.write_bytes(&[0]);
+        }
+        Ok(())
+    }
+
+    /// Write the object file containing the import descriptor.
+    ///
+    /// This contains the `.idata$2` import directory entry and the `.idata$6`
+    /// DLL name.
+    pub fn write_import_descriptor(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        let relocation_type = self.relocation_type()?;
+        let import_descriptor = self.import_descriptor_symbol();
+        let null_thunk = self.null_thunk_symbol();
+        let mut dll_name = self.dll.clone();
+        dll_name.push(0);
+        dll_name.resize(crate::write::util::align(dll_name.len(), 2), 0);
+
+        let sections = [
+            ImportSection {
+                name: b".idata$2",
+                characteristics: pe::IMAGE_SCN_ALIGN_4BYTES | IDATA_CHARACTERISTICS,
+                data: vec![0; core::mem::size_of::<pe::ImageImportDescriptor>()],
+                relocations: vec![
+                    // Name
+                    (12, 2),
+                    // OriginalFirstThunk
+                    (0, 3),
+                    // FirstThunk
+                    (16, 4),
+                ],
+            },
+            ImportSection {
+                name: b".idata$6",
+                characteristics: pe::IMAGE_SCN_ALIGN_2BYTES | IDATA_CHARACTERISTICS,
+                data: dll_name,
+                relocations: Vec::new(),
+            },
+        ];
+        let symbols = [
+            ImportSymbol::external(&import_descriptor, 1),
+            ImportSymbol {
+                name: b".idata$2",
+                value: 0,
+                section_number: 1,
+                storage_class: pe::IMAGE_SYM_CLASS_SECTION,
+            },
+            ImportSymbol {
+                name: b".idata$6",
+                value: 0,
+                section_number: 2,
+                storage_class: pe::IMAGE_SYM_CLASS_STATIC,
+            },
+            ImportSymbol {
+                name: b".idata$4",
+                value: 0,
+                section_number: pe::IMAGE_SYM_UNDEFINED,
+                storage_class: pe::IMAGE_SYM_CLASS_SECTION,
+            },
+            ImportSymbol {
+                name: b".idata$5",
+                value: 0,
+                section_number: pe::IMAGE_SYM_UNDEFINED,
+                storage_class: pe::IMAGE_SYM_CLASS_SECTION,
+            },
+            ImportSymbol::external(NULL_IMPORT_DESCRIPTOR, pe::IMAGE_SYM_UNDEFINED),
+            ImportSymbol::external(&null_thunk, pe::IMAGE_SYM_UNDEFINED),
+        ];
+        self.write_object(buffer, relocation_type, &sections, &symbols)
+    }
+
+    /// Write the object file containing the null import descriptor.
+    ///
+    /// This contains the `.idata$3` entry that terminates the import directory.
+    pub fn write_null_import_descriptor(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        let relocation_type = self.relocation_type()?;
+        let sections = [ImportSection {
+            name: b".idata$3",
+            characteristics: pe::IMAGE_SCN_ALIGN_4BYTES | IDATA_CHARACTERISTICS,
+            data: vec![0; core::mem::size_of::<pe::ImageImportDescriptor>()],
+            relocations: Vec::new(),
+        }];
+        let symbols = [ImportSymbol::external(NULL_IMPORT_DESCRIPTOR, 1)];
+        self.write_object(buffer, relocation_type, &sections, &symbols)
+    }
+
+    /// Write the object file containing the null thunk.
+    ///
+    /// This contains the `.idata$5` and `.idata$4` entries that terminate the
+    /// import address table and import lookup table for the DLL.
+    pub fn write_null_thunk(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        let relocation_type = self.relocation_type()?;
+        let null_thunk = self.null_thunk_symbol();
+        let (align, characteristics) = if self.is_32bit() {
+            (4, pe::IMAGE_SCN_ALIGN_4BYTES | IDATA_CHARACTERISTICS)
+        } else {
+            (8, pe::IMAGE_SCN_ALIGN_8BYTES | IDATA_CHARACTERISTICS)
+        };
+        let sections = [
+            ImportSection {
+                name: b".idata$5",
+                characteristics,
+                data: vec![0; align],
+                relocations: Vec::new(),
+            },
+            ImportSection {
+                name: b".idata$4",
+                characteristics,
+                data: vec![0; align],
+                relocations: Vec::new(),
+            },
+        ];
+        let symbols = [ImportSymbol::external(&null_thunk, 1)];
+        self.write_object(buffer, relocation_type, &sections, &symbols)
+    }
+
+    /// The DLL name without its extension.
+    fn library_name(&self) -> &[u8] {
+        match self.dll.iter().rposition(|&c| c == b'.') {
+            Some(0) | None => &self.dll,
+            Some(end) => &self.dll[..end],
+        }
+    }
+
+    fn import_descriptor_symbol(&self) -> Vec<u8> {
+        [&b"__IMPORT_DESCRIPTOR_"[..], self.library_name()].concat()
+    }
+
+    fn null_thunk_symbol(&self) -> Vec<u8> {
+        [&b"\x7f"[..], self.library_name(), b"_NULL_THUNK_DATA"].concat()
+    }
+
+    fn is_32bit(&self) -> bool {
+        matches!(
+            self.machine,
+            pe::IMAGE_FILE_MACHINE_I386 | pe::IMAGE_FILE_MACHINE_ARMNT
+        )
+    }
+
+    /// The relocation type for image relative addresses.
+    fn relocation_type(&self) -> Result<u16> {
+        match self.machine {
+            pe::IMAGE_FILE_MACHINE_I386 => Ok(pe::IMAGE_REL_I386_DIR32NB),
+            pe::IMAGE_FILE_MACHINE_AMD64 => Ok(pe::IMAGE_REL_AMD64_ADDR32NB),
+            pe::IMAGE_FILE_MACHINE_ARMNT => Ok(pe::IMAGE_REL_ARM_ADDR32NB),
+            pe::IMAGE_FILE_MACHINE_ARM64 | pe::IMAGE_FILE_MACHINE_ARM64EC => {
+                Ok(pe::IMAGE_REL_ARM64_ADDR32NB)
+            }
+            _ => Err(Error(format!(
+                "unimplemented COFF import library machine {:#x}",
+                self.machine
+            ))),
+        }
+    }
+
+    fn write_object(
+        &self,
+        buffer: &mut dyn WritableBuffer,
+        relocation_type: u16,
+        sections: &[ImportSection],
+        symbols: &[ImportSymbol<'_>],
+    ) -> Result<()> {
+        let mut writer = writer::Writer::new(buffer);
+
+        let section_names: Vec<_> = sections
+            .iter()
+            .map(|section| writer.add_name(section.name))
+            .collect();
+        let symbol_names: Vec<_> = symbols
+            .iter()
+            .map(|symbol| {
+                writer.reserve_symbol_index();
+                writer.add_name(symbol.name)
+            })
+            .collect();
+
+        writer.reserve_file_header();
+        writer.reserve_section_headers(sections.len() as u16);
+        let section_offsets: Vec<_> = sections
+            .iter()
+            .map(|section| {
+                (
+                    writer.reserve_section(section.data.len()),
+                    writer.reserve_relocations(section.relocations.len()),
+                )
+            })
+            .collect();
+        writer.reserve_symtab_strtab();
+
+        writer.write_file_header(writer::FileHeader {
+            machine: self.machine,
+            time_date_stamp: 0,
+            characteristics: if self.is_32bit() {
+                pe::IMAGE_FILE_32BIT_MACHINE
+            } else {
+                0
+            },
+        })?;
+        for ((section, name), (offset, reloc_offset)) in
+            sections.iter().zip(section_names).zip(&section_offsets)
+        {
+            writer.write_section_header(writer::SectionHeader {
+                name,
+                size_of_raw_data: section.data.len() as u32,
+                pointer_to_raw_data: *offset,
+                pointer_to_relocations: *reloc_offset,
+                pointer_to_linenumbers: 0,
+                number_of_relocations: section.relocations.len() as u32,
+                number_of_linenumbers: 0,
+                characteristics: section.characteristics,
+            });
+        }
+        for section in sections {
+            writer.write_section(&section.data);
+            for (virtual_address, symbol) in &section.relocations {
+                writer.write_relocation(writer::Relocation {
+                    virtual_address: *virtual_address,
+                    symbol: *symbol,
+                    typ: relocation_type,
+                });
+            }
+        }
+        for (symbol, name) in symbols.iter().zip(symbol_names) {
+            writer.write_symbol_ex(writer::SymbolEx {
+                name,
+                value: symbol.value,
+                section_number: symbol.section_number,
+                typ: 0,
+                storage_class: symbol.storage_class,
+                number_of_aux_symbols: 0,
+            });
+        }
+        writer.write_strtab();
+        debug_assert_eq!(writer.reserved_len(), writer.len());
+        Ok(())
+    }
+}
+
+const IDATA_CHARACTERISTICS: u32 =
+    pe::IMAGE_SCN_CNT_INITIALIZED_DATA | pe::IMAGE_SCN_MEM_READ | pe::IMAGE_SCN_MEM_WRITE;
+
+const NULL_IMPORT_DESCRIPTOR: &[u8] = b"__NULL_IMPORT_DESCRIPTOR";
+
+struct ImportSection {
+    name: &'static [u8],
+    characteristics: u32,
+    data: Vec<u8>,
+    /// Pairs of offset and symbol index.
+    relocations: Vec<(u32, u32)>,
+}
+
+struct ImportSymbol<'a> {
+    name: &'a [u8],
+    value: u32,
+    section_number: i32,
+    storage_class: u8,
+}
+
+impl<'a> ImportSymbol<'a> {
+    fn external(name: &'a [u8], section_number: i32) -> Self {
+        ImportSymbol {
+            name,
+            value: 0,
+            section_number,
+            storage_class: pe::IMAGE_SYM_CLASS_EXTERNAL,
+        }
+    }
+}
@@ -2,6 +2,16 @@
 //!
 //! Provides [`Writer`] for low level writing of COFF files.
 //! This is also used to provide COFF support for [`write::Object`](crate::write::Object).
+//!
+//! Also provides [`ImportLibrary`] for writing COFF import libraries.
+//! With the `read_core` feature, an import library or linker directives can be
+//! created from a [`ModuleDefinition`](crate::read::coff::ModuleDefinition).
+
+#[cfg(feature = "read_core")]
+mod def;
+
+mod import;
+pub use self::import::*;
 
 mod object;
 pub use self::object::*;
@@ -369,6 +369,21 @@ impl<'a> Object<'a> {
         Ok(stub_id)
     }
 
+    /// Specify whether to write a bigobj file.
+    ///
+    /// Bigobj files are always written if the number of sections is greater than
+    /// [`coff::IMAGE_SYM_SECTION_MAX`]. Setting this to true causes them to be
+    /// written for any number of sections.
+    ///
+    /// This does nothing if the format is not COFF.
+    ///
+    /// Requires `feature = "coff"`.
+    pub fn set_coff_bigobj(&mut self, bigobj: bool) {
+        if self.format == BinaryFormat::Coff {
+            self.coff_bigobj = bigobj;
+        }
+    }
+
     /// Appends linker directives to the `.drectve` section to tell the linker
     /// to export all symbols with `SymbolScope::Dynamic`.
     ///
@@ -398,7 +413,12 @@ impl<'a> Object<'a> {
     }
 
     pub(crate) fn coff_write(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
-        let mut writer = writer::Writer::new(buffer);
+        let mut writer =
+            if self.coff_bigobj || self.sections.len() > usize::from(coff::IMAGE_SYM_SECTION_MAX) {
+                writer::Writer::new_bigobj(buffer)
+            } else {
+                writer::Writer::new(buffer)
+            };
 
         // Add section strings to strtab.
         let mut section_offsets = vec![SectionOffsets::default(); self.sections.len()];
@@ -554,7 +574,7 @@ impl<'a> Object<'a> {
 
         // Reserve file ranges.
         writer.reserve_file_header();
-        writer.reserve_section_headers(self.sections.len() as u16);
+        writer.reserve_section_headers_bigobj(self.sections.len() as u32);
         for (index, section) in self.sections.iter().enumerate() {
             section_offsets[index].offset = writer.reserve_section(section.data.len());
             section_offsets[index].reloc_offset =
@@ -679,15 +699,15 @@ impl<'a> Object<'a> {
             };
             let section_number = match symbol.section {
                 // weak symbols are always undefined
-                _ if symbol.weak => coff::IMAGE_SYM_UNDEFINED as u16,
+                _ if symbol.weak => coff::IMAGE_SYM_UNDEFINED,
                 SymbolSection::None => {
                     debug_assert_eq!(symbol.kind, SymbolKind::File);
-                    coff::IMAGE_SYM_DEBUG as u16
+                    coff::IMAGE_SYM_DEBUG
                 }
-                SymbolSection::Undefined => coff::IMAGE_SYM_UNDEFINED as u16,
-                SymbolSection::Absolute => coff::IMAGE_SYM_ABSOLUTE as u16,
-                SymbolSection::Common => coff::IMAGE_SYM_UNDEFINED as u16,
-                SymbolSection::Section(id) => id.0 as u16 + 1,
+                SymbolSection::Undefined => coff::IMAGE_SYM_UNDEFINED,
+                SymbolSection::Absolute => coff::IMAGE_SYM_ABSOLUTE,
+                SymbolSection::Common => coff::IMAGE_SYM_UNDEFINED,
+                SymbolSection::Section(id) => id.0 as i32 + 1,
             };
             let typ = if symbol.kind == SymbolKind::Text {
                 coff::IMAGE_SYM_DTYPE_FUNCTION << coff::IMAGE_SYM_DTYPE_SHIFT
@@ -756,12 +776,12 @@ impl<'a> Object<'a> {
                     unreachable!("weak symbol should have a weak default offset")
                 });
 
-                writer.write_symbol(writer::Symbol {
+                writer.write_symbol_ex(writer::SymbolEx {
                     name: weak_default_symbol.name,
                     value: symbol.value as u32,
                     section_number: match symbol.section {
-                        SymbolSection::Section(id) => id.0 as u16 + 1,
-                        SymbolSection::Undefined => coff::IMAGE_SYM_ABSOLUTE as u16,
+                        SymbolSection::Section(id) => id.0 as i32 + 1,
+                        SymbolSection::Undefined => coff::IMAGE_SYM_ABSOLUTE,
                         o => {
                             return Err(Error(format!(
                                 "invalid symbol section for weak external `{}` section {o:?}",
@@ -775,7 +795,7 @@ impl<'a> Object<'a> {
                 });
             }
 
-            writer.write_symbol(writer::Symbol {
+            writer.write_symbol_ex(writer::SymbolEx {
                 name: symbol_offsets[index].name,
                 value,
                 section_number,
@@ -24,12 +24,17 @@ use crate::write::{Error, Result, WritableBuffer};
 /// The second phase writes everything out in order. Thus the caller must ensure writing
 /// is in the same order that file ranges were reserved. There are debug asserts to assist
 /// with checking this.
+///
+/// The writer can also produce bigobj files, which use [`pe::AnonObjectHeaderBigobj`]
+/// for the file header and [`pe::ImageSymbolEx`] for symbols, and allow up to
+/// [`pe::IMAGE_SYM_SECTION_MAX_EX`] sections.
 #[allow(missing_debug_implementations)]
 pub struct Writer<'a> {
     buffer: &'a mut dyn WritableBuffer,
     len: usize,
+    bigobj: bool,
 
-    section_num: u16,
+    section_num: u32,
 
     symtab_offset: u32,
     symtab_num: u32,
@@ -43,9 +48,19 @@ pub struct Writer<'a> {
 impl<'a> Writer<'a> {
     /// Create a new `Writer`.
     pub fn new(buffer: &'a mut dyn WritableBuffer) -> Self {
+        Self::new_impl(buffer, false)
+    }
+
+    /// Create a new `Writer` for a bigobj file.
+    pub fn new_bigobj(buffer: &'a mut dyn WritableBuffer) -> Self {
+        Self::new_impl(buffer, true)
+    }
+
+    fn new_impl(buffer: &'a mut dyn WritableBuffer, bigobj: bool) -> Self {
         Writer {
             buffer,
             len: 0,
+            bigobj,
 
             section_num: 0,
 
@@ -59,6 +74,23 @@ impl<'a> Writer<'a> {
         }
     }
 
+    /// Return true if writing a bigobj file.
+    pub fn is_bigobj(&self) -> bool {
+        self.bigobj
+    }
+
+    /// Return the size of a symbol table entry.
+    ///
+    /// This is [`pe::IMAGE_SIZEOF_SYMBOL_EX`] for bigobj files, and
+    /// [`pe::IMAGE_SIZEOF_SYMBOL`] otherwise.
+    pub fn symbol_size(&self) -> usize {
+        if self.bigobj {
+            pe::IMAGE_SIZEOF_SYMBOL_EX
+        } else {
+            pe::IMAGE_SIZEOF_SYMBOL
+        }
+    }
+
     /// Return the current file length that has been reserved.
     pub fn reserved_len(&self) -> usize {
         self.len
@@ -113,7 +145,11 @@ impl<'a> Writer<'a> {
     /// This must be at the start of the file.
     pub fn reserve_file_header(&mut self) {
         debug_assert_eq!(self.len, 0);
-        self.reserve(mem::size_of::<pe::ImageFileHeader>(), 1);
+        if self.bigobj {
+            self.reserve(mem::size_of::<pe::AnonObjectHeaderBigobj>(), 1);
+        } else {
+            self.reserve(mem::size_of::<pe::ImageFileHeader>(), 1);
+        }
     }
 
     /// Write the file header.
@@ -121,18 +157,53 @@ impl<'a> Writer<'a> {
     /// This must be at the start of the file.
     ///
     /// Fields that can be derived from known information are automatically set by this function.
+    ///
+    /// The `characteristics` field is not written for bigobj files.
     pub fn write_file_header(&mut self, header: FileHeader) -> Result<()> {
         debug_assert_eq!(self.buffer.len(), 0);
 
+        if self.bigobj {
+            if self.section_num > pe::IMAGE_SYM_SECTION_MAX_EX {
+                return Err(Error(format!(
+                    "too many COFF sections: {}",
+                    self.section_num
+                )));
+            }
+        } else if self.section_num > u32::from(pe::IMAGE_SYM_SECTION_MAX) {
+            return Err(Error(format!(
+                "too many COFF sections for a non-bigobj file: {}",
+                self.section_num
+            )));
+        }
+
         // Start writing.
         self.buffer
             .reserve(self.len)
             .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
 
         // Write file header.
+        if self.bigobj {
+            let header = pe::AnonObjectHeaderBigobj {
+                sig1: pe::IMAGE_FILE_MACHINE_UNKNOWN.into(),
+                sig2: 0xffff.into(),
+                version: 2.into(),
+                machine: header.machine.into(),
+                time_date_stamp: header.time_date_stamp.into(),
+                class_id: pe::ANON_OBJECT_HEADER_BIGOBJ_CLASS_ID,
+                size_of_data: 0.into(),
+                flags: 0.into(),
+                meta_data_size: 0.into(),
+                meta_data_offset: 0.into(),
+                number_of_sections: self.section_num.into(),
+                pointer_to_symbol_table: self.symtab_offset.into(),
+                number_of_symbols: self.symtab_num.into(),
+            };
+            self.buffer.write(&header);
+            return Ok(());
+        }
         let header = pe::ImageFileHeader {
             machine: header.machine.into(),
-            number_of_sections: self.section_num.into(),
+            number_of_sections: (self.section_num as u16).into(),
             time_date_stamp: header.time_date_stamp.into(),
             pointer_to_symbol_table: self.symtab_offset.into(),
             number_of_symbols: self.symtab_num.into(),
@@ -146,6 +217,14 @@ impl<'a> Writer<'a> {
 
     /// Reserve the range for the section headers.
     pub fn reserve_section_headers(&mut self, section_num: u16) {
+        self.reserve_section_headers_bigobj(section_num.into());
+    }
+
+    /// Reserve the range for the section headers of a bigobj file.
+    ///
+    /// This allows more sections than [`Self::reserve_section_headers`].
+    /// The section count is checked when writing the file header.
+    pub fn reserve_section_headers_bigobj(&mut self, section_num: u32) {
         debug_assert_eq!(self.section_num, 0);
         self.section_num = section_num;
         self.reserve(
@@ -317,23 +396,60 @@ impl<'a> Writer<'a> {
     }
 
     /// Write a symbol table entry.
+    ///
+    /// For bigobj files, the special section numbers such as
+    /// [`pe::IMAGE_SYM_ABSOLUTE`] are sign extended. Use [`Self::write_symbol_ex`]
+    /// for section numbers that do not fit in 16 bits.
     pub fn write_symbol(&mut self, symbol: Symbol) {
-        let mut coff_symbol = pe::ImageSymbol {
-            name: [0; 8],
-            value: symbol.value.into(),
-            section_number: symbol.section_number.into(),
-            typ: symbol.typ.into(),
+        let section_number = if symbol.section_number > pe::IMAGE_SYM_SECTION_MAX {
+            i32::from(symbol.section_number as i16)
+        } else {
+            i32::from(symbol.section_number)
+        };
+        self.write_symbol_ex(SymbolEx {
+            name: symbol.name,
+            value: symbol.value,
+            section_number,
+            typ: symbol.typ,
             storage_class: symbol.storage_class,
             number_of_aux_symbols: symbol.number_of_aux_symbols,
-        };
-        match symbol.name {
-            Name::Short(name) => coff_symbol.name = name,
+        });
+    }
+
+    /// Write a symbol table entry with a 32-bit section number.
+    ///
+    /// The section number is truncated to 16 bits if not writing a bigobj file.
+    pub fn write_symbol_ex(&mut self, symbol: SymbolEx) {
+        let name = match symbol.name {
+            Name::Short(name) => name,
             Name::Long(str_id) => {
                 let str_offset = self.strtab.get_offset(str_id);
-                coff_symbol.name[4..8].copy_from_slice(&u32::to_le_bytes(str_offset as u32));
+                let mut name = [0; 8];
+                name[4..8].copy_from_slice(&u32::to_le_bytes(str_offset as u32));
+                name
             }
+        };
+        if self.bigobj {
+            let coff_symbol = pe::ImageSymbolEx {
+                name,
+                value: symbol.value.into(),
+                section_number: symbol.section_number.into(),
+                typ: symbol.typ.into(),
+                storage_class: symbol.storage_class,
+                number_of_aux_symbols: symbol.number_of_aux_symbols,
+            };
+            self.buffer.write(&coff_symbol);
+        } else {
+            let coff_symbol = pe::ImageSymbol {
+                name,
+                value: symbol.value.into(),
+                section_number: (symbol.section_number as u16).into(),
+                typ: symbol.typ.into(),
+                storage_class: symbol.storage_class,
+                number_of_aux_symbols: symbol.number_of_aux_symbols,
+            };
+            self.buffer.write(&coff_symbol);
         }
-        self.buffer.write(&coff_symbol);
     }
 
     /// Reserve auxiliary symbols for a file name.
@@ -343,14 +459,15 @@ impl<'a> Writer<'a> {
     /// This must be called before [`Self::reserve_symtab_strtab`].
     pub fn reserve_aux_file_name(&mut self, name: &[u8]) -> u8 {
         debug_assert_eq!(self.symtab_offset, 0);
-        let aux_count = (name.len() + pe::IMAGE_SIZEOF_SYMBOL - 1) / pe::IMAGE_SIZEOF_SYMBOL;
+        let symbol_size = self.symbol_size();
+        let aux_count = (name.len() + symbol_size - 1) / symbol_size;
         self.symtab_num += aux_count as u32;
         aux_count as u8
     }
 
     /// Write auxiliary symbols for a file name.
     pub fn write_aux_file_name(&mut self, name: &[u8], aux_count: u8) {
-        let aux_len = aux_count as usize * pe::IMAGE_SIZEOF_SYMBOL;
+        let aux_len = aux_count as usize * self.symbol_size();
         debug_assert!(aux_len >= name.len());
         let old_len = self.buffer.len();
         self.buffer.write_bytes(name);
@@ -385,6 +502,7 @@ impl<'a> Writer<'a> {
             high_number: ((section.number >> 16) as u16).into(),
         };
         self.buffer.write(&aux);
+        self.write_aux_padding(mem::size_of::<pe::ImageAuxSymbolSection>());
     }
 
     /// Reserve an auxiliary symbol for a weak external.
@@ -406,8 +524,13 @@ impl<'a> Writer<'a> {
         };
         self.buffer.write(&aux);
         // write padding for the unused field
-        const PAD_LEN: usize = pe::IMAGE_SIZEOF_SYMBOL - mem::size_of::<pe::ImageAuxSymbolWeak>();
-        self.buffer.write_bytes(&[0u8; PAD_LEN]);
+        self.write_aux_padding(mem::size_of::<pe::ImageAuxSymbolWeak>());
+    }
+
+    /// Write padding after an auxiliary symbol of the given size.
+    fn write_aux_padding(&mut self, len: usize) {
+        let old_len = self.buffer.len();
+        self.buffer.resize(old_len + self.symbol_size() - len);
     }
 
     /// Return the number of reserved symbol table entries.
@@ -442,7 +565,7 @@ impl<'a> Writer<'a> {
     /// indices or add strings.
     pub fn reserve_symtab_strtab(&mut self) {
         debug_assert_eq!(self.symtab_offset, 0);
-        self.symtab_offset = self.reserve(self.symtab_num as usize * pe::IMAGE_SIZEOF_SYMBOL, 1);
+        self.symtab_offset = self.reserve(self.symtab_num as usize * self.symbol_size(), 1);
 
         debug_assert_eq!(self.strtab_offset, 0);
         // First 4 bytes of strtab are the length.
@@ -519,6 +642,19 @@ pub struct Symbol {
     pub number_of_aux_symbols: u8,
 }
 
+/// Native endian version of [`pe::ImageSymbolEx`].
+#[allow(missing_docs)]
+#[derive(Debug, Default, Clone)]
+pub struct SymbolEx {
+    pub name: Name,
+    pub value: u32,
+    /// One of the `IMAGE_SYM_*` constants, or a 1-based section index.
+    pub section_number: i32,
+    pub typ: u16,
+    pub storage_class: u8,
+    pub number_of_aux_symbols: u8,
+}
+
 /// Native endian version of [`pe::ImageAuxSymbolSection`].
 #[allow(missing_docs)]
 #[derive(Debug, Default, Clone)]
@@ -11,13 +11,17 @@ struct ComdatOffsets {
     str_id: StringId,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 struct SectionOffsets {
     index: SectionIndex,
     offset: usize,
     str_id: StringId,
     reloc_offset: usize,
     reloc_str_id: Option<StringId>,
+    /// The encoded relocations for a CREL section.
+    crel_data: Vec<u8>,
+    /// The section data if it is compressed.
+    compressed_data: Option<Vec<u8>>,
 }
 
 #[derive(Default, Clone, Copy)]
@@ -28,6 +32,36 @@ struct SymbolOffsets {
 
 // Public methods.
 impl<'a> Object<'a> {
+    /// Specify whether to write relocations in the compact `SHT_CREL` format.
+    ///
+    /// By default, `SHT_REL` or `SHT_RELA` sections are written. CREL sections
+    /// are much smaller, but require a linker that supports them.
+    ///
+    /// This does nothing if the format is not ELF.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_elf_crel(&mut self, crel: bool) {
+        if self.format == BinaryFormat::Elf {
+            self.elf_crel = crel;
+        }
+    }
+
+    /// Specify the compression format for debug sections.
+    ///
+    /// By default, debug sections are not compressed. Otherwise, the data for
+    /// sections with a kind of [`SectionKind::Debug`] or [`SectionKind::DebugString`]
+    /// is compressed, and the sections are written with the `SHF_COMPRESSED` flag.
+    ///
+    /// This does nothing if the format is not ELF.
+    ///
+    /// Requires `feature = "elf"` and `feature = "compression"`.
+    #[cfg(feature = "compression")]
+    pub fn set_elf_compression(&mut self, format: CompressionFormat) {
+        if self.format == BinaryFormat::Elf {
+            self.elf_compression = format;
+        }
+    }
+
     /// Add a property with a u32 value to the ELF ".note.gnu.property" section.
     ///
     /// Requires `feature = "elf"`.
@@ -578,6 +612,28 @@ impl<'a> Object<'a> {
         size.ok_or_else(|| Error(format!("unsupported relocation for size {:?}", reloc)))
     }
 
+    /// Return the compressed data for a section, or `None` if it is not compressed.
+    #[cfg(feature = "compression")]
+    fn elf_compressed_data(
+        &self,
+        writer: &Writer<'_>,
+        section: &Section<'_>,
+    ) -> Result<Option<Vec<u8>>> {
+        if self.elf_compression == CompressionFormat::None
+            || section.data.is_empty()
+            || !matches!(section.kind, SectionKind::Debug | SectionKind::DebugString)
+        {
+            return Ok(None);
+        }
+        match self.section_flags(section) {
+            SectionFlags::Elf { sh_flags } if sh_flags & u64::from(elf::SHF_ALLOC) == 0 => {}
+            _ => return Ok(None),
+        }
+        writer
+            .compress_section_data(self.elf_compression, section.align, &section.data)
+            .map(Some)
+    }
+
     pub(crate) fn elf_is_64(&self) -> bool {
         match self.architecture.address_size().unwrap() {
             AddressSize::U8 | AddressSize::U16 | AddressSize::U32 => false,
@@ -588,19 +644,20 @@ impl<'a> Object<'a> {
     pub(crate) fn elf_write(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
         // Create reloc section header names so we can reference them.
         let is_rela = self.elf_has_relocation_addend()?;
+        let reloc_prefix = if self.elf_crel {
+            &b".crel"[..]
+        } else if is_rela {
+            &b".rela"[..]
+        } else {
+            &b".rel"[..]
+        };
         let reloc_names: Vec<_> = self
             .sections
             .iter()
             .map(|section| {
-                let mut reloc_name = Vec::with_capacity(
-                    if is_rela { ".rela".len() } else { ".rel".len() } + section.name.len(),
-                );
+                let mut reloc_name = Vec::with_capacity(reloc_prefix.len() + section.name.len());
                 if !section.relocations.is_empty() {
-                    reloc_name.extend_from_slice(if is_rela {
-                        &b".rela"[..]
-                    } else {
-                        &b".rel"[..]
-                    });
+                    reloc_name.extend_from_slice(reloc_prefix);
                     reloc_name.extend_from_slice(&section.name);
                 }
                 reloc_name
@@ -609,6 +666,9 @@ impl<'a> Object<'a> {
 
         // Start calculating offsets of everything.
         let mut writer = Writer::new(self.endian, self.elf_is_64(), buffer);
+        let class = Class {
+            is_64: self.elf_is_64(),
+        };
         writer.reserve_file_header();
 
         // Calculate size of section data.
@@ -630,7 +690,14 @@ impl<'a> Object<'a> {
         let mut section_offsets = Vec::with_capacity(self.sections.len());
         for (section, reloc_name) in self.sections.iter().zip(reloc_names.iter()) {
             let index = writer.reserve_section_index();
-            let offset = writer.reserve(section.data.len(), section.align as usize);
+            #[cfg(feature = "compression")]
+            let compressed_data = self.elf_compressed_data(&writer, section)?;
+            #[cfg(not(feature = "compression"))]
+            let compressed_data: Option<Vec<u8>> = None;
+            let offset = match &compressed_data {
+                Some(data) => writer.reserve(data.len(), class.align()),
+                None => writer.reserve(section.data.len(), section.align as usize),
+            };
             let str_id = writer.add_section_name(&section.name);
             let mut reloc_str_id = None;
             if !section.relocations.is_empty() {
@@ -644,6 +711,8 @@ impl<'a> Object<'a> {
                 // Relocation data is reserved later.
                 reloc_offset: 0,
                 reloc_str_id,
+                crel_data: Vec::new(),
+                compressed_data,
             });
         }
 
@@ -681,9 +750,34 @@ impl<'a> Object<'a> {
         writer.reserve_strtab();
 
         // Calculate size of relocations.
+        let elf_rel = |reloc: &RelocationInternal| -> Result<Rel> {
+            let r_type = if let RelocationFlags::Elf { r_type } = reloc.flags {
+                r_type
+            } else {
+                return Err(Error("invalid relocation flags".into()));
+            };
+            Ok(Rel {
+                r_offset: reloc.offset,
+                r_sym: symbol_offsets[reloc.symbol.0].index.0,
+                r_type,
+                r_addend: reloc.addend,
+            })
+        };
         for (index, section) in self.sections.iter().enumerate() {
             let count = section.relocations.len();
-            if count != 0 {
+            if count == 0 {
+                continue;
+            }
+            if self.elf_crel {
+                let rels = section
+                    .relocations
+                    .iter()
+                    .map(elf_rel)
+                    .collect::<Result<Vec<_>>>()?;
+                let data = writer.crel_data(is_rela, &rels);
+                section_offsets[index].reloc_offset = writer.reserve_crel(data.len());
+                section_offsets[index].crel_data = data;
+            } else {
                 section_offsets[index].reloc_offset = writer.reserve_relocations(count, is_rela);
             }
         }
@@ -768,6 +862,12 @@ impl<'a> Object<'a> {
             }
         }
         for (index, section) in self.sections.iter().enumerate() {
+            if let Some(data) = &section_offsets[index].compressed_data {
+                writer.write_align(class.align());
+                debug_assert_eq!(section_offsets[index].offset, writer.len());
+                writer.write(data);
+                continue;
+            }
             writer.write_align(section.align as usize);
             debug_assert_eq!(section_offsets[index].offset, writer.len());
             writer.write(&section.data);
@@ -819,25 +919,17 @@ impl<'a> Object<'a> {
 
         // Write relocations.
         for (index, section) in self.sections.iter().enumerate() {
-            if !section.relocations.is_empty() {
+            if section.relocations.is_empty() {
+                continue;
+            }
+            if self.elf_crel {
+                debug_assert_eq!(section_offsets[index].reloc_offset, writer.len());
+                writer.write_crel(&section_offsets[index].crel_data);
+            } else {
                 writer.write_align_relocation();
                 debug_assert_eq!(section_offsets[index].reloc_offset, writer.len());
                 for reloc in &section.relocations {
-                    let r_type = if let RelocationFlags::Elf { r_type } = reloc.flags {
-                        r_type
-                    } else {
-                        return Err(Error("invalid relocation flags".into()));
-                    };
-                    let r_sym = symbol_offsets[reloc.symbol.0].index.0;
-                    writer.write_relocation(
-                        is_rela,
-                        &Rel {
-                            r_offset: reloc.offset,
-                            r_sym,
-                            r_type,
-                            r_addend: reloc.addend,
-                        },
-                    );
+                    writer.write_relocation(is_rela, &elf_rel(reloc)?);
                 }
             }
         }
@@ -864,7 +956,7 @@ impl<'a> Object<'a> {
                 SectionKind::Elf(sh_type) => sh_type,
                 _ => elf::SHT_PROGBITS,
             };
-            let SectionFlags::Elf { sh_flags } = self.section_flags(section) else {
+            let SectionFlags::Elf { mut sh_flags } = self.section_flags(section) else {
                 return Err(Error(format!(
                     "unimplemented section `{}` kind {:?}",
                     section.name().unwrap_or(""),
@@ -876,20 +968,38 @@ impl<'a> Object<'a> {
                 SectionKind::ReadOnlyString | SectionKind::OtherString => 1,
                 _ => 0,
             };
+            let mut sh_size = section.size;
+            let mut sh_addralign = section.align;
+            if let Some(data) = &section_offsets[index].compressed_data {
+                sh_flags |= u64::from(elf::SHF_COMPRESSED);
+                sh_size = data.len() as u64;
+                sh_addralign = class.align() as u64;
+            }
             writer.write_section_header(&SectionHeader {
                 name: Some(section_offsets[index].str_id),
                 sh_type,
                 sh_flags,
                 sh_addr: 0,
                 sh_offset: section_offsets[index].offset as u64,
-                sh_size: section.size,
+                sh_size,
                 sh_link: 0,
                 sh_info: 0,
-                sh_addralign: section.align,
+                sh_addralign,
                 sh_entsize,
             });
 
-            if !section.relocations.is_empty() {
+            if section.relocations.is_empty() {
+                continue;
+            }
+            if self.elf_crel {
+                writer.write_crel_section_header(
+                    section_offsets[index].reloc_str_id.unwrap(),
+                    section_offsets[index].index,
+                    symtab_index,
+                    section_offsets[index].reloc_offset,
+                    section_offsets[index].crel_data.len(),
+                );
+            } else {
                 writer.write_relocation_section_header(
                     section_offsets[index].reloc_str_id.unwrap(),
                     section_offsets[index].index,
@@ -10,6 +10,8 @@ use crate::pod;
 use crate::write::string::{StringId, StringTable};
 use crate::write::util;
 use crate::write::{Error, Result, WritableBuffer};
+#[cfg(feature = "compression")]
+use crate::CompressionFormat;
 
 const ALIGN_SYMTAB_SHNDX: usize = 4;
 const ALIGN_HASH: usize = 4;
@@ -1986,6 +1988,134 @@ impl<'a> Writer<'a> {
         });
     }
 
+    /// Compress the data for a section that has the `SHF_COMPRESSED` flag.
+    ///
+    /// The returned data begins with an [`elf::CompressionHeader32`] or
+    /// [`elf::CompressionHeader64`], and should be written with an alignment
+    /// of [`Class::align`]. `sh_addralign` is the alignment of the uncompressed data.
+    ///
+    /// Requires `feature = "compression"`.
+    #[cfg(feature = "compression")]
+    pub fn compress_section_data(
+        &self,
+        format: CompressionFormat,
+        sh_addralign: u64,
+        data: &[u8],
+    ) -> Result<Vec<u8>> {
+        compress_section_data(self.endian, self.is_64, format, sh_addralign, data)
+    }
+
+    /// Encode relocations in the compact `SHT_CREL` format.
+    ///
+    /// Returns the data for [`Self::write_crel`]. Relocations are encoded in
+    /// the given order; sorting them by offset gives the smallest encoding.
+    pub fn crel_data(&self, is_rela: bool, rels: &[Rel]) -> Vec<u8> {
+        let flag_bits = if is_rela { 3 } else { 2 };
+        let offset_mask = if self.is_64 { !0 } else { u64::from(u32::MAX) };
+        // Offsets are encoded in units of the largest common power of two, up to 8.
+        let shift = rels
+            .iter()
+            .fold(8, |mask, rel| mask | rel.r_offset)
+            .trailing_zeros();
+
+        let mut data = Vec::new();
+        let mut header = (rels.len() as u64) << 3 | u64::from(shift);
+        if is_rela {
+            header |= 4;
+        }
+        util::write_uleb128(&mut data, header);
+        let mut prev = Rel {
+            r_offset: 0,
+            r_sym: 0,
+            r_type: 0,
+            r_addend: 0,
+        };
+        for rel in rels {
+            let offset = (rel.r_offset & offset_mask) >> shift;
+            let delta_offset = offset.wrapping_sub(prev.r_offset);
+            let mut flags = 0;
+            if rel.r_sym != prev.r_sym {
+                flags |= 1;
+            }
+            if rel.r_type != prev.r_type {
+                flags |= 2;
+            }
+            if is_rela && rel.r_addend != prev.r_addend {
+                flags |= 4;
+            }
+            let byte = (delta_offset << flag_bits) as u8 | flags;
+            if delta_offset >> (7 - flag_bits) == 0 {
+                data.push(byte);
+            } else {
+                data.push(byte | 0x80);
+                util::write_uleb128(&mut data, delta_offset >> (7 - flag_bits));
+            }
+            if flags & 1 != 0 {
+                util::write_sleb128(&mut data, rel.r_sym.wrapping_sub(prev.r_sym) as i32 as i64);
+            }
+            if flags & 2 != 0 {
+                util::write_sleb128(
+                    &mut data,
+                    rel.r_type.wrapping_sub(prev.r_type) as i32 as i64,
+                );
+            }
+            if flags & 4 != 0 {
+                util::write_sleb128(&mut data, rel.r_addend.wrapping_sub(prev.r_addend));
+            }
+            prev = Rel {
+                r_offset: offset,
+                ..*rel
+            };
+        }
+        data
+    }
+
+    /// Reserve a file range for a CREL relocation section.
+    ///
+    /// `size` is the length of the data returned by [`Self::crel_data`].
+    ///
+    /// Returns the offset of the range.
+    pub fn reserve_crel(&mut self, size: usize) -> usize {
+        self.reserve(size, 1)
+    }
+
+    /// Write the data for a CREL relocation section.
+    pub fn write_crel(&mut self, data: &[u8]) {
+        self.buffer.write_bytes(data);
+    }
+
+    /// Write the section header for a CREL relocation section.
+    ///
+    /// `section` is the index of the section the relocations apply to,
+    /// or 0 if none.
+    ///
+    /// `symtab` is the index of the symbol table the relocations refer to,
+    /// or 0 if none.
+    ///
+    /// `offset` is the file offset of the relocations.
+    /// `size` is the size of the section in bytes.
+    pub fn write_crel_section_header(
+        &mut self,
+        name: StringId,
+        section: SectionIndex,
+        symtab: SectionIndex,
+        offset: usize,
+        size: usize,
+    ) {
+        self.write_section_header(&SectionHeader {
+            name: Some(name),
+            sh_type: elf::SHT_CREL,
+            sh_flags: elf::SHF_INFO_LINK.into(),
+            sh_addr: 0,
+            sh_offset: offset as u64,
+            sh_size: size as u64,
+            sh_link: symtab.0,
+            sh_info: section.0,
+            sh_addralign: 1,
+            sh_entsize: 0,
+        });
+    }
+
     /// Write the section header for a relative relocation section.
     ///
     /// `offset` is the file offset of the relocations.
@@ -2400,3 +2530,54 @@ pub struct Vernaux {
     pub index: u16,
     pub name: StringId,
 }
+
+#[cfg(feature = "compression")]
+pub(crate) fn compress_section_data(
+    endian: Endianness,
+    is_64: bool,
+    format: CompressionFormat,
+    sh_addralign: u64,
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    let ch_type = match format {
+        CompressionFormat::Zlib => elf::ELFCOMPRESS_ZLIB,
+        CompressionFormat::Zstandard => elf::ELFCOMPRESS_ZSTD,
+        _ => {
+            return Err(Error(format!(
+                "unsupported compression format {:?}",
+                format
+            )))
+        }
+    };
+    let mut compressed = Vec::new();
+    if is_64 {
+        compressed.extend_from_slice(pod::bytes_of(&elf::CompressionHeader64 {
+            ch_type: U32::new(endian, ch_type),
+            ch_reserved: U32::new(endian, 0),
+            ch_size: U64::new(endian, data.len() as u64),
+            ch_addralign: U64::new(endian, sh_addralign),
+        }));
+    } else {
+        compressed.extend_from_slice(pod::bytes_of(&elf::CompressionHeader32 {
+            ch_type: U32::new(endian, ch_type),
+            ch_size: U32::new(endian, data.len() as u32),
+            ch_addralign: U32::new(endian, sh_addralign as u32),
+        }));
+    }
+    match format {
+        CompressionFormat::Zlib => {
+            use std::io::Write;
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(compressed, flate2::Compression::default());
+            encoder
+                .write_all(data)
+                .and_then(|_| encoder.finish())
+                .map_err(|e| Error(format!("zlib compression failed: {}", e)))
+        }
+        _ => {
+            let level = ruzstd::encoding::CompressionLevel::Fastest;
+            compressed.extend(ruzstd::encoding::compress_to_vec(data, level));
+            Ok(compressed)
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::mem;
+#[cfg(feature = "std")]
+use std::{boxed::Box, error, io};
+
+use crate::endian::{BigEndian as BE, U32, U64};
+use crate::macho;
+#[cfg(feature = "read_core")]
+use crate::read::{
+    macho::{FatArch, MachOFatFile32, MachOFatFile64},
+    FileKind,
+};
+#[cfg(feature = "std")]
+use crate::write::StreamingBuffer;
+use crate::write::{util, BinaryFormat, Error, Object, Result, WritableBuffer};
+
+/// The maximum alignment of an arch in a fat file, as a power of 2.
+const MAX_ALIGN: u32 = 15;
+
+/// An arch to be written to a Mach-O fat file.
+#[derive(Debug, Clone)]
+pub struct MachOFatArch<'a> {
+    /// The CPU type.
+    ///
+    /// One of the `CPU_TYPE_*` constants.
+    pub cputype: u32,
+    /// The CPU subtype.
+    ///
+    /// One of the `CPU_SUBTYPE_*` constants.
+    pub cpusubtype: u32,
+    /// The alignment of the arch data in the file, as a power of 2.
+    ///
+    /// This must not be greater than 15.
+    pub align: u32,
+    /// The Mach-O file data.
+    pub data: Cow<'a, [u8]>,
+}
+
+impl<'a> MachOFatArch<'a> {
+    /// Create a new arch with the given CPU type, CPU subtype and data.
+    ///
+    /// The alignment is set to the page size for the CPU type, which is the
+    /// alignment required for linked images. This is 2^14 for ARM64, and 2^12
+    /// for other CPU types.
+    pub fn new<T>(cputype: u32, cpusubtype: u32, data: T) -> Self
+    where
+        T: Into<Cow<'a, [u8]>>,
+    {
+        MachOFatArch {
+            cputype,
+            cpusubtype,
+            align: page_align(cputype),
+            data: data.into(),
+        }
+    }
+
+    /// Create a new arch by writing a relocatable object file.
+    ///
+    /// The CPU type and subtype are determined from the object's architecture.
+    /// The alignment is set to the largest section alignment, since object files
+    /// do not need to be page aligned.
+    pub fn from_object(object: &Object<'_>) -> Result<MachOFatArch<'static>> {
+        if object.format() != BinaryFormat::MachO {
+            return Err(Error(String::from(
+                "Mach-O fat arch must be a Mach-O object file",
+            )));
+        }
+        let (cputype, cpusubtype) = object.macho_cpu_type()?;
+        let min_align = if cputype & macho::CPU_ARCH_ABI64 != 0 {
+            3
+        } else {
+            2
+        };
+        let align = object
+            .sections
+            .iter()
+            .map(|section| section.align.trailing_zeros())
+            .fold(min_align, u32::max)
+            .min(MAX_ALIGN);
+        Ok(MachOFatArch {
+            cputype,
+            cpusubtype,
+            align,
+            data: object.write()?.into(),
+        })
+    }
+
+    /// Return true if this arch has the given CPU type and subtype.
+    ///
+    /// The capability bits of the CPU subtype are ignored.
+    pub fn matches(&self, cputype: u32, cpusubtype: u32) -> bool {
+        self.cputype == cputype
+            && self.cpusubtype & !macho::CPU_SUBTYPE_MASK == cpusubtype & !macho::CPU_SUBTYPE_MASK
+    }
+}
+
+/// A writable Mach-O fat (universal) file.
+///
+/// This is similar to `lipo -create`. Existing fat files can be read with
+/// [`MachOFat::read`], and then arches can be added, removed or extracted.
+#[derive(Debug, Default)]
+pub struct MachOFat<'a> {
+    arches: Vec<MachOFatArch<'a>>,
+    /// Always use `FAT_MAGIC_64`.
+    ///
+    /// If this is false, then `FAT_MAGIC_64` is only used if an arch offset or size
+    /// does not fit in 32 bits.
+    ///
+    /// Defaults to false.
+    pub fat64: bool,
+}
+
+impl<'a> MachOFat<'a> {
+    /// Create an empty fat file.
+    pub fn new() -> Self {
+        MachOFat::default()
+    }
+
+    /// Read the arches from an existing fat file.
+    ///
+    /// `fat64` is set if the file uses `FAT_MAGIC_64`.
+    #[cfg(feature = "read_core")]
+    pub fn read(data: &'a [u8]) -> Result<Self> {
+        fn arches<'a, Fat: FatArch>(
+            data: &'a [u8],
+            arches: &[Fat],
+        ) -> Result<Vec<MachOFatArch<'a>>> {
+            arches
+                .iter()
+                .map(|arch| {
+                    Ok(MachOFatArch {
+                        cputype: arch.cputype(),
+                        cpusubtype: arch.cpusubtype(),
+                        align: arch.align(),
+                        data: arch
+                            .data(data)
+                            .map_err(|e| Error(format!("Invalid Mach-O fat arch: {}", e)))?
+                            .into(),
+                    })
+                })
+                .collect()
+        }
+
+        let kind =
+            FileKind::parse(data).map_err(|e| Error(format!("Invalid Mach-O fat file: {}", e)))?;
+        let (arches, fat64) = match kind {
+            FileKind::MachOFat32 => {
+                let file = MachOFatFile32::parse(data)
+                    .map_err(|e| Error(format!("Invalid Mach-O fat file: {}", e)))?;
+                (arches(data, file.arches())?, false)
+            }
+            FileKind::MachOFat64 => {
+                let file = MachOFatFile64::parse(data)
+                    .map_err(|e| Error(format!("Invalid Mach-O fat file: {}", e)))?;
+                (arches(data, file.arches())?, true)
+            }
+            _ => return Err(Error(String::from("Not a Mach-O fat file"))),
+        };
+        Ok(MachOFat { arches, fat64 })
+    }
+
+    /// Return the arches that have been added.
+    #[inline]
+    pub fn arches(&self) -> &[MachOFatArch<'a>] {
+        &self.arches
+    }
+
+    /// Mutably get the arches that have been added.
+    #[inline]
+    pub fn arches_mut(&mut self) -> &mut [MachOFatArch<'a>] {
+        &mut self.arches
+    }
+
+    /// Add an arch and return its index.
+    ///
+    /// The arches are written in the order that they are added.
+    pub fn add_arch(&mut self, arch: MachOFatArch<'a>) -> usize {
+        let index = self.arches.len();
+        self.arches.push(arch);
+        index
+    }
+
+    /// Remove the arch at the given index and return it.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn remove_arch(&mut self, index: usize) -> MachOFatArch<'a> {
+        self.arches.remove(index)
+    }
+
+    /// Find the arch with the given CPU type and subtype.
+    ///
+    /// The data of the arch is a thin Mach-O file. This is similar to `lipo -thin`.
+    /// The capability bits of the CPU subtype are ignored.
+    pub fn find_arch(&self, cputype: u32, cpusubtype: u32) -> Option<&MachOFatArch<'a>> {
+        self.arches
+            .iter()
+            .find(|arch| arch.matches(cputype, cpusubtype))
+    }
+
+    /// Write the fat file to a `Vec`.
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.emit(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Write the fat file to a `Write` implementation.
+    ///
+    /// Also flushes the writer.
+    ///
+    /// It is advisable to use a buffered writer like [`BufWriter`](std::io::BufWriter)
+    /// instead of an unbuffered writer like [`File`](std::fs::File).
+    #[cfg(feature = "std")]
+    pub fn write_stream<W: io::Write>(
+        &self,
+        w: W,
+    ) -> core::result::Result<(), Box<dyn error::Error>> {
+        let mut stream = StreamingBuffer::new(w);
+        self.emit(&mut stream)?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    /// Write the fat file to a `WritableBuffer`.
+    pub fn emit(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        if self.arches.is_empty() {
+            return Err(Error(String::from(
+                "Mach-O fat file must contain at least one arch",
+            )));
+        }
+        for (index, arch) in self.arches.iter().enumerate() {
+            if arch.align > MAX_ALIGN {
+                return Err(Error(format!(
+                    "Invalid Mach-O fat arch alignment 2^{}",
+                    arch.align
+                )));
+            }
+            if self.arches[..index]
+                .iter()
+                .any(|other| other.matches(arch.cputype, arch.cpusubtype))
+            {
+                return Err(Error(format!(
+                    "Duplicate Mach-O fat arch with CPU type {:#x} and subtype {:#x}",
+                    arch.cputype, arch.cpusubtype
+                )));
+            }
+        }
+
+        // All offsets and sizes are less than the end of the file, so this is the
+        // only value that needs checking.
+        let mut fat64 = self.fat64;
+        let (mut offsets, mut end) = self.layout(fat64);
+        if !fat64 && end > u64::from(u32::MAX) {
+            fat64 = true;
+            (offsets, end) = self.layout(fat64);
+        }
+        let end = usize::try_from(end)
+            .map_err(|_| Error(String::from("Mach-O fat file is too large")))?;
+        buffer
+            .reserve(end)
+            .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
+
+        let magic = if fat64 {
+            macho::FAT_MAGIC_64
+        } else {
+            macho::FAT_MAGIC
+        };
+        buffer.write(&macho::FatHeader {
+            magic: U32::new(BE, magic),
+            nfat_arch: U32::new(BE, self.arches.len() as u32),
+        });
+        for (arch, offset) in self.arches.iter().zip(offsets.iter()) {
+            let size = arch.data.len() as u64;
+            if fat64 {
+                buffer.write(&macho::FatArch64 {
+                    cputype: U32::new(BE, arch.cputype),
+                    cpusubtype: U32::new(BE, arch.cpusubtype),
+                    offset: U64::new(BE, *offset),
+                    size: U64::new(BE, size),
+                    align: U32::new(BE, arch.align),
+                    reserved: U32::default(),
+                });
+            } else {
+                buffer.write(&macho::FatArch32 {
+                    cputype: U32::new(BE, arch.cputype),
+                    cpusubtype: U32::new(BE, arch.cpusubtype),
+                    offset: U32::new(BE, *offset as u32),
+                    size: U32::new(BE, size as u32),
+                    align: U32::new(BE, arch.align),
+                });
+            }
+        }
+        for (arch, offset) in self.arches.iter().zip(offsets.iter()) {
+            buffer.resize(*offset as usize);
+            buffer.write_bytes(&arch.data);
+        }
+        debug_assert_eq!(buffer.len(), end);
+        Ok(())
+    }
+
+    /// Calculate the offset of each arch and the end of the file.
+    fn layout(&self, fat64: bool) -> (Vec<u64>, u64) {
+        let arch_size = if fat64 {
+            mem::size_of::<macho::FatArch64>()
+        } else {
+            mem::size_of::<macho::FatArch32>()
+        };
+        let mut offset =
+            (mem::size_of::<macho::FatHeader>() + self.arches.len() * arch_size) as u64;
+        let mut offsets = Vec::with_capacity(self.arches.len());
+        for arch in &self.arches {
+            offset = util::align_u64(offset, 1 << arch.align);
+            offsets.push(offset);
+            offset += arch.data.len() as u64;
+        }
+        (offsets, offset)
+    }
+}
+
+/// Return the page alignment for a CPU type, as a power of 2.
+fn page_align(cputype: u32) -> u32 {
+    match cputype {
+        macho::CPU_TYPE_ARM64 | macho::CPU_TYPE_ARM64_32 => 14,
+        _ => 12,
+    }
+}
@@ -0,0 +1,10 @@
+//! Support for writing Mach-O files.
+//!
+//! Provides [`MachOFat`] for writing Mach-O fat (universal) files.
+//! This is also used to provide Mach-O support for [`write::Object`](crate::write::Object).
+
+mod fat;
+pub use self::fat::*;
+
+mod object;
+pub use self::object::*;
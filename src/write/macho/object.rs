@@ -66,6 +66,39 @@ impl<'a> Object<'a> {
 
 // Private methods.
 impl<'a> Object<'a> {
+    /// Return the Mach-O CPU type and subtype for the architecture.
+    pub(crate) fn macho_cpu_type(&self) -> Result<(u32, u32)> {
+        let (cputype, mut cpusubtype) = match (self.architecture, self.sub_architecture) {
+            (Architecture::Arm, None) => (macho::CPU_TYPE_ARM, macho::CPU_SUBTYPE_ARM_ALL),
+            (Architecture::Aarch64, None) => (macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64_ALL),
+            (Architecture::Aarch64, Some(SubArchitecture::Arm64E)) => {
+                (macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64E)
+            }
+            (Architecture::Aarch64_Ilp32, None) => {
+                (macho::CPU_TYPE_ARM64_32, macho::CPU_SUBTYPE_ARM64_32_V8)
+            }
+            (Architecture::I386, None) => (macho::CPU_TYPE_X86, macho::CPU_SUBTYPE_I386_ALL),
+            (Architecture::X86_64, None) => (macho::CPU_TYPE_X86_64, macho::CPU_SUBTYPE_X86_64_ALL),
+            (Architecture::PowerPc, None) => {
+                (macho::CPU_TYPE_POWERPC, macho::CPU_SUBTYPE_POWERPC_ALL)
+            }
+            (Architecture::PowerPc64, None) => {
+                (macho::CPU_TYPE_POWERPC64, macho::CPU_SUBTYPE_POWERPC_ALL)
+            }
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented architecture {:?} with sub-architecture {:?}",
+                    self.architecture, self.sub_architecture
+                )));
+            }
+        };
+
+        if let Some(cpu_subtype) = self.macho_cpu_subtype {
+            cpusubtype = cpu_subtype;
+        }
+        Ok((cputype, cpusubtype))
+    }
+
     pub(crate) fn macho_segment_name(&self, segment: StandardSegment) -> &'static [u8] {
         match segment {
             StandardSegment::Text => &b"__TEXT"[..],
@@ -595,34 +628,7 @@ impl<'a> Object<'a> {
             .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
 
         // Write file header.
-        let (cputype, mut cpusubtype) = match (self.architecture, self.sub_architecture) {
-            (Architecture::Arm, None) => (macho::CPU_TYPE_ARM, macho::CPU_SUBTYPE_ARM_ALL),
-            (Architecture::Aarch64, None) => (macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64_ALL),
-            (Architecture::Aarch64, Some(SubArchitecture::Arm64E)) => {
-                (macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64E)
-            }
-            (Architecture::Aarch64_Ilp32, None) => {
-                (macho::CPU_TYPE_ARM64_32, macho::CPU_SUBTYPE_ARM64_32_V8)
-            }
-            (Architecture::I386, None) => (macho::CPU_TYPE_X86, macho::CPU_SUBTYPE_I386_ALL),
-            (Architecture::X86_64, None) => (macho::CPU_TYPE_X86_64, macho::CPU_SUBTYPE_X86_64_ALL),
-            (Architecture::PowerPc, None) => {
-                (macho::CPU_TYPE_POWERPC, macho::CPU_SUBTYPE_POWERPC_ALL)
-            }
-            (Architecture::PowerPc64, None) => {
-                (macho::CPU_TYPE_POWERPC64, macho::CPU_SUBTYPE_POWERPC_ALL)
-            }
-            _ => {
-                return Err(Error(format!(
-                    "unimplemented architecture {:?} with sub-architecture {:?}",
-                    self.architecture, self.sub_architecture
-                )));
-            }
-        };
-
-        if let Some(cpu_subtype) = self.macho_cpu_subtype {
-            cpusubtype = cpu_subtype;
-        }
+        let (cputype, cpusubtype) = self.macho_cpu_type()?;
 
         let mut flags = match self.flags {
             FileFlags::MachO { flags } => flags,
@@ -972,7 +978,7 @@ struct SegmentCommand {
     flags: u32,
 }
 
-pub struct SectionHeader {
+struct SectionHeader {
     sectname: [u8; 16],
     segname: [u8; 16],
     addr: u64,
@@ -7,7 +7,8 @@
 //! The submodules define helpers for writing the raw structs. These support
 //! writing both relocatable and executable files. There are writers for
 //! the following file formats: [COFF](coff::Writer), [ELF](elf::Writer),
-//! and [PE](pe::Writer).
+//! and [PE](pe::Writer). There is also a writer for Mach-O fat files
+//! ([`MachOFat`](macho::MachOFat)).
 
 use alloc::borrow::Cow;
 use alloc::string::String;
@@ -22,6 +23,9 @@ use crate::endian::{Endianness, U32, U64};
 
 pub use crate::common::*;
 
+#[cfg(all(feature = "archive", feature = "read_core"))]
+pub mod archive;
+
 #[cfg(feature = "coff")]
 pub mod coff;
 #[cfg(feature = "coff")]
@@ -31,7 +35,7 @@ pub use coff::CoffExportStyle;
 pub mod elf;
 
 #[cfg(feature = "macho")]
-mod macho;
+pub mod macho;
 #[cfg(feature = "macho")]
 pub use macho::MachOBuildVersion;
 
@@ -84,6 +88,15 @@ pub struct Object<'a> {
     pub mangling: Mangling,
     #[cfg(feature = "coff")]
     stub_symbols: HashMap<SymbolId, SymbolId>,
+    /// Write a COFF bigobj file. Only ever set if format is COFF.
+    #[cfg(feature = "coff")]
+    coff_bigobj: bool,
+    /// Use `SHT_CREL` relocation sections. Only ever set if format is ELF.
+    #[cfg(feature = "elf")]
+    elf_crel: bool,
+    /// The compression format for debug sections. Only ever set if format is ELF.
+    #[cfg(all(feature = "elf", feature = "compression"))]
+    elf_compression: CompressionFormat,
     /// Mach-O "_tlv_bootstrap" symbol.
     #[cfg(feature = "macho")]
     tlv_bootstrap: Option<SymbolId>,
@@ -114,6 +127,12 @@ impl<'a> Object<'a> {
             mangling: Mangling::default(format, architecture),
             #[cfg(feature = "coff")]
             stub_symbols: HashMap::new(),
+            #[cfg(feature = "coff")]
+            coff_bigobj: false,
+            #[cfg(feature = "elf")]
+            elf_crel: false,
+            #[cfg(all(feature = "elf", feature = "compression"))]
+            elf_compression: CompressionFormat::None,
             #[cfg(feature = "macho")]
             tlv_bootstrap: None,
             #[cfg(feature = "macho")]
@@ -1,6 +1,8 @@
 //! Helper for writing PE files.
+use alloc::collections::btree_map::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::convert::TryFrom;
 use core::mem;
 
 use crate::endian::{LittleEndian as LE, *};
@@ -43,6 +45,17 @@ pub struct Writer<'a> {
     reloc_blocks: Vec<RelocBlock>,
     relocs: Vec<U16<LE>>,
     reloc_offset: u32,
+
+    import_data: Vec<u8>,
+    import_offset: u32,
+    import_address_tables: Vec<u32>,
+    export_data: Vec<u8>,
+    export_offset: u32,
+    resource_data: Vec<u8>,
+    resource_offset: u32,
+    debug_data: Vec<u8>,
+    debug_offset: u32,
+    certificate_data: Vec<u8>,
 }
 
 impl<'a> Writer<'a> {
@@ -82,6 +95,17 @@ impl<'a> Writer<'a> {
             reloc_blocks: Vec::new(),
             relocs: Vec::new(),
             reloc_offset: 0,
+
+            import_data: Vec::new(),
+            import_offset: 0,
+            import_address_tables: Vec::new(),
+            export_data: Vec::new(),
+            export_offset: 0,
+            resource_data: Vec::new(),
+            resource_offset: 0,
+            debug_data: Vec::new(),
+            debug_offset: 0,
+            certificate_data: Vec::new(),
         }
     }
 
@@ -619,6 +643,279 @@ impl<'a> Writer<'a> {
         range
     }
 
+    /// Reserve an `.idata` section containing the import tables for the given DLLs.
+    ///
+    /// The section contains the import descriptors, followed by the import lookup
+    /// tables, the import address tables, the hint/name table, and the DLL names.
+    ///
+    /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_IMPORT` and
+    /// `pe::IMAGE_DIRECTORY_ENTRY_IAT` data directories.
+    ///
+    /// Use [`Self::import_address`] to get the address of the import address table
+    /// entry for an import.
+    pub fn reserve_import_section(&mut self, dlls: &[ImportDll<'_>]) -> SectionRange {
+        let thunk_size = if self.is_64 { 8 } else { 4 };
+        let descriptors_size =
+            (dlls.len() as u32 + 1) * mem::size_of::<pe::ImageImportDescriptor>() as u32;
+        let thunks_size = dlls
+            .iter()
+            .map(|dll| (dll.imports.len() as u32 + 1) * thunk_size)
+            .sum::<u32>();
+        let lookup_offset = util::align_u32(descriptors_size, thunk_size);
+        let address_offset = lookup_offset + thunks_size;
+        let mut hint_name_offset = address_offset + thunks_size;
+        let mut dll_name_offset = hint_name_offset;
+        for dll in dlls {
+            for import in &dll.imports {
+                if let Import::Name(_, name) = import {
+                    dll_name_offset += util::align_u32(2 + name.len() as u32 + 1, 2);
+                }
+            }
+        }
+        let size = dll_name_offset
+            + dlls
+                .iter()
+                .map(|dll| dll.name.len() as u32 + 1)
+                .sum::<u32>();
+
+        let range = self.reserve_idata_section(size);
+        let address = range.virtual_address;
+        self.set_data_directory(pe::IMAGE_DIRECTORY_ENTRY_IMPORT, address, descriptors_size);
+        self.set_data_directory(
+            pe::IMAGE_DIRECTORY_ENTRY_IAT,
+            address + address_offset,
+            thunks_size,
+        );
+
+        let mut data = Vec::with_capacity(size as usize);
+        let mut thunks = Vec::with_capacity(thunks_size as usize);
+        let mut hint_names = Vec::new();
+        let mut dll_names = Vec::new();
+        let mut thunk_offset = 0;
+        self.import_address_tables.clear();
+        for dll in dlls {
+            self.import_address_tables
+                .push(address + address_offset + thunk_offset);
+            data.write_pod(&pe::ImageImportDescriptor {
+                original_first_thunk: U32::new(LE, address + lookup_offset + thunk_offset),
+                time_date_stamp: U32::new(LE, 0),
+                forwarder_chain: U32::new(LE, 0),
+                name: U32::new(LE, address + dll_name_offset),
+                first_thunk: U32::new(LE, address + address_offset + thunk_offset),
+            });
+            dll_names.extend_from_slice(dll.name);
+            dll_names.push(0);
+            dll_name_offset += dll.name.len() as u32 + 1;
+
+            for import in &dll.imports {
+                let thunk = match *import {
+                    Import::Ordinal(ordinal) => {
+                        if self.is_64 {
+                            pe::IMAGE_ORDINAL_FLAG64 | u64::from(ordinal)
+                        } else {
+                            u64::from(pe::IMAGE_ORDINAL_FLAG32 | u32::from(ordinal))
+                        }
+                    }
+                    Import::Name(hint, name) => {
+                        let thunk = u64::from(address + hint_name_offset);
+                        hint_names.extend_from_slice(&hint.to_le_bytes());
+                        hint_names.extend_from_slice(name);
+                        hint_names.push(0);
+                        if hint_names.len() & 1 != 0 {
+                            hint_names.push(0);
+                        }
+                        hint_name_offset += util::align_u32(2 + name.len() as u32 + 1, 2);
+                        thunk
+                    }
+                };
+                if self.is_64 {
+                    thunks.extend_from_slice(&thunk.to_le_bytes());
+                } else {
+                    thunks.extend_from_slice(&(thunk as u32).to_le_bytes());
+                }
+            }
+            thunks.resize(thunks.len() + thunk_size as usize, 0);
+            thunk_offset += (dll.imports.len() as u32 + 1) * thunk_size;
+        }
+        data.resize(lookup_offset as usize, 0);
+        // The import lookup table and the import address table are identical.
+        data.extend_from_slice(&thunks);
+        data.extend_from_slice(&thunks);
+        data.extend_from_slice(&hint_names);
+        data.extend_from_slice(&dll_names);
+        debug_assert_eq!(data.len(), size as usize);
+
+        self.import_data = data;
+        self.import_offset = range.file_offset;
+        range
+    }
+
+    /// Return the address of the import address table entry for an import.
+    ///
+    /// `dll` and `index` are indices into the `dlls` and `imports` that were passed to
+    /// [`Self::reserve_import_section`].
+    pub fn import_address(&self, dll: usize, index: usize) -> u32 {
+        let thunk_size = if self.is_64 { 8 } else { 4 };
+        self.import_address_tables[dll] + index as u32 * thunk_size
+    }
+
+    /// Write the `.idata` section that was reserved with [`Self::reserve_import_section`].
+    pub fn write_import_section(&mut self) {
+        let data = mem::take(&mut self.import_data);
+        self.write_section(self.import_offset, &data);
+        self.import_data = data;
+    }
+
+    /// Reserve an `.edata` section containing the export tables for the given exports.
+    ///
+    /// `dll` is the name of the image. The ordinal base is the lowest ordinal of
+    /// the exports, and any unused ordinals between the lowest and highest ordinals
+    /// are given an address of 0. The export name pointer table is sorted by name.
+    ///
+    /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_EXPORT` data directory.
+    ///
+    /// Returns an error if an ordinal or name is duplicated, or if the range of
+    /// ordinals is too large.
+    pub fn reserve_export_section(
+        &mut self,
+        dll: &[u8],
+        exports: &[Export<'_>],
+    ) -> Result<SectionRange> {
+        let base = exports
+            .iter()
+            .map(|export| export.ordinal)
+            .min()
+            .unwrap_or(1);
+        let function_num = exports
+            .iter()
+            .map(|export| u64::from(export.ordinal - base) + 1)
+            .max()
+            .unwrap_or(0);
+        if function_num > 0x1_0000 {
+            return Err(Error(format!(
+                "PE export ordinal range {}..={} is too large",
+                base,
+                u64::from(base) + function_num - 1
+            )));
+        }
+        let function_num = function_num as u32;
+        let mut functions = vec![None; function_num as usize];
+        for export in exports {
+            let function = &mut functions[(export.ordinal - base) as usize];
+            if function.is_some() {
+                return Err(Error(format!(
+                    "duplicate PE export ordinal {}",
+                    export.ordinal
+                )));
+            }
+            *function = Some(export);
+        }
+        let mut names = exports
+            .iter()
+            .filter_map(|export| Some((export.name?, (export.ordinal - base) as u16)))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        for pair in names.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                return Err(Error(format!(
+                    "duplicate PE export name `{}`",
+                    String::from_utf8_lossy(pair[0].0)
+                )));
+            }
+        }
+
+        let functions_offset = mem::size_of::<pe::ImageExportDirectory>() as u32;
+        let names_offset = functions_offset + function_num * 4;
+        let ordinals_offset = names_offset + names.len() as u32 * 4;
+        let dll_offset = ordinals_offset + names.len() as u32 * 2;
+        let mut strings_offset = dll_offset + dll.len() as u32 + 1;
+        let strings_size = names
+            .iter()
+            .map(|(name, _)| name.len() as u32 + 1)
+            .sum::<u32>()
+            + exports
+                .iter()
+                .map(|export| match export.target {
+                    ExportTarget::Address(_) => 0,
+                    ExportTarget::ForwardByName(dll, name) => {
+                        dll.len() as u32 + 1 + name.len() as u32 + 1
+                    }
+                    ExportTarget::ForwardByOrdinal(dll, ordinal) => {
+                        dll.len() as u32 + 2 + ordinal_len(ordinal) + 1
+                    }
+                })
+                .sum::<u32>();
+        let size = strings_offset + strings_size;
+
+        let range = self.reserve_edata_section(size);
+        let address = range.virtual_address;
+
+        let mut data = Vec::with_capacity(size as usize);
+        data.write_pod(&pe::ImageExportDirectory {
+            characteristics: U32::new(LE, 0),
+            time_date_stamp: U32::new(LE, 0),
+            major_version: U16::new(LE, 0),
+            minor_version: U16::new(LE, 0),
+            name: U32::new(LE, address + dll_offset),
+            base: U32::new(LE, base),
+            number_of_functions: U32::new(LE, function_num),
+            number_of_names: U32::new(LE, names.len() as u32),
+            address_of_functions: U32::new(LE, address + functions_offset),
+            address_of_names: U32::new(LE, address + names_offset),
+            address_of_name_ordinals: U32::new(LE, address + ordinals_offset),
+        });
+        let mut strings = Vec::with_capacity(strings_size as usize);
+        for function in &functions {
+            let function_address = match function.map(|export| export.target) {
+                None => 0,
+                Some(ExportTarget::Address(function_address)) => function_address,
+                Some(ExportTarget::ForwardByName(dll, name)) => {
+                    let forward_address = address + strings_offset;
+                    strings.extend_from_slice(dll);
+                    strings.push(b'.');
+                    strings.extend_from_slice(name);
+                    strings.push(0);
+                    strings_offset += dll.len() as u32 + 1 + name.len() as u32 + 1;
+                    forward_address
+                }
+                Some(ExportTarget::ForwardByOrdinal(dll, ordinal)) => {
+                    let forward_address = address + strings_offset;
+                    let forward = format!(".#{}", ordinal);
+                    strings.extend_from_slice(dll);
+                    strings.extend_from_slice(forward.as_bytes());
+                    strings.push(0);
+                    strings_offset += dll.len() as u32 + forward.len() as u32 + 1;
+                    forward_address
+                }
+            };
+            data.extend_from_slice(&function_address.to_le_bytes());
+        }
+        for (name, _) in &names {
+            data.extend_from_slice(&(address + strings_offset).to_le_bytes());
+            strings.extend_from_slice(name);
+            strings.push(0);
+            strings_offset += name.len() as u32 + 1;
+        }
+        for (_, index) in &names {
+            data.extend_from_slice(&index.to_le_bytes());
+        }
+        data.extend_from_slice(dll);
+        data.push(0);
+        data.extend_from_slice(&strings);
+        debug_assert_eq!(data.len(), size as usize);
+
+        self.export_data = data;
+        self.export_offset = range.file_offset;
+        Ok(range)
+    }
+
+    /// Write the `.edata` section that was reserved with [`Self::reserve_export_section`].
+    pub fn write_export_section(&mut self) {
+        let data = mem::take(&mut self.export_data);
+        self.write_section(self.export_offset, &data);
+        self.export_data = data;
+    }
+
     /// Reserve a `.pdata` section.
     ///
     /// Contains exception information.
@@ -673,6 +970,282 @@ impl<'a> Writer<'a> {
         range
     }
 
+    /// Reserve a `.rsrc` section containing the resource directory for the given resources.
+    ///
+    /// The resources are arranged into the standard three level tree of type, name,
+    /// and language. Within each directory table, the named entries are sorted by
+    /// their UTF-16 code units and are followed by the ID entries sorted by ID.
+    ///
+    /// The section contains the directory tables, followed by the data entries,
+    /// the directory strings, and the resource data.
+    ///
+    /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_RESOURCE` data directory.
+    ///
+    /// Returns an error if the same type, name and language is used more than once.
+    pub fn reserve_resource_section(&mut self, resources: &[Resource<'_>]) -> Result<SectionRange> {
+        let mut tree = BTreeMap::new();
+        for resource in resources {
+            let languages = tree
+                .entry(resource.typ)
+                .or_insert_with(BTreeMap::new)
+                .entry(resource.name)
+                .or_insert_with(BTreeMap::new);
+            if languages.insert(resource.language, resource).is_some() {
+                return Err(Error(format!(
+                    "duplicate PE resource type {:?} name {:?} language {}",
+                    resource.typ, resource.name, resource.language
+                )));
+            }
+        }
+
+        let table_size = |entries: usize| {
+            (mem::size_of::<pe::ImageResourceDirectory>()
+                + entries * mem::size_of::<pe::ImageResourceDirectoryEntry>()) as u32
+        };
+        let name_tables_size = tree
+            .values()
+            .map(|names| table_size(names.len()))
+            .sum::<u32>();
+        let language_tables_size = tree
+            .values()
+            .flat_map(|names| names.values())
+            .map(|languages| table_size(languages.len()))
+            .sum::<u32>();
+        let data_entries_offset = table_size(tree.len()) + name_tables_size + language_tables_size;
+        let strings_offset = data_entries_offset
+            + resources.len() as u32 * mem::size_of::<pe::ImageResourceDataEntry>() as u32;
+        let mut strings_size = 0;
+        let mut string_offsets = BTreeMap::new();
+        for name in tree
+            .iter()
+            .flat_map(|(typ, names)| core::iter::once(typ).chain(names.keys()))
+        {
+            if let ResourceNameOrId::Name(name) = *name {
+                string_offsets.entry(name).or_insert_with(|| {
+                    let offset = strings_offset + strings_size;
+                    strings_size += 2 + name.len() as u32 * 2;
+                    offset
+                });
+            }
+        }
+        let data_offset = util::align_u32(strings_offset + strings_size, 8);
+        let mut size = data_offset;
+        for languages in tree.values().flat_map(|names| names.values()) {
+            for resource in languages.values() {
+                size = util::align_u32(size, 8) + resource.data.len() as u32;
+            }
+        }
+
+        let range = self.reserve_rsrc_section(size);
+        let address = range.virtual_address;
+
+        let mut data = Vec::with_capacity(size as usize);
+        let name_or_id = |name: &ResourceNameOrId<'_>| match *name {
+            ResourceNameOrId::Name(name) => {
+                pe::IMAGE_RESOURCE_NAME_IS_STRING | string_offsets[name]
+            }
+            ResourceNameOrId::Id(id) => u32::from(id),
+        };
+        let write_table = |data: &mut Vec<u8>, names: &[&ResourceNameOrId<'_>]| {
+            let named = names
+                .iter()
+                .filter(|name| matches!(name, ResourceNameOrId::Name(_)))
+                .count();
+            data.write_pod(&pe::ImageResourceDirectory {
+                characteristics: U32::new(LE, 0),
+                time_date_stamp: U32::new(LE, 0),
+                major_version: U16::new(LE, 0),
+                minor_version: U16::new(LE, 0),
+                number_of_named_entries: U16::new(LE, named as u16),
+                number_of_id_entries: U16::new(LE, (names.len() - named) as u16),
+            });
+        };
+
+        // Root table, containing the types.
+        let types = tree.keys().collect::<Vec<_>>();
+        write_table(&mut data, &types);
+        let mut table_offset = table_size(tree.len());
+        for (typ, names) in &tree {
+            data.write_pod(&pe::ImageResourceDirectoryEntry {
+                name_or_id: U32::new(LE, name_or_id(typ)),
+                offset_to_data_or_directory: U32::new(
+                    LE,
+                    pe::IMAGE_RESOURCE_DATA_IS_DIRECTORY | table_offset,
+                ),
+            });
+            table_offset += table_size(names.len());
+        }
+        // Tables for each type, containing the names.
+        for names in tree.values() {
+            write_table(&mut data, &names.keys().collect::<Vec<_>>());
+            for (name, languages) in names {
+                data.write_pod(&pe::ImageResourceDirectoryEntry {
+                    name_or_id: U32::new(LE, name_or_id(name)),
+                    offset_to_data_or_directory: U32::new(
+                        LE,
+                        pe::IMAGE_RESOURCE_DATA_IS_DIRECTORY | table_offset,
+                    ),
+                });
+                table_offset += table_size(languages.len());
+            }
+        }
+        // Tables for each name, containing the languages.
+        let mut data_entry_offset = data_entries_offset;
+        for languages in tree.values().flat_map(|names| names.values()) {
+            let ids = languages
+                .keys()
+                .map(|&language| ResourceNameOrId::Id(language))
+                .collect::<Vec<_>>();
+            write_table(&mut data, &ids.iter().collect::<Vec<_>>());
+            for language in languages.keys() {
+                data.write_pod(&pe::ImageResourceDirectoryEntry {
+                    name_or_id: U32::new(LE, u32::from(*language)),
+                    offset_to_data_or_directory: U32::new(LE, data_entry_offset),
+                });
+                data_entry_offset += mem::size_of::<pe::ImageResourceDataEntry>() as u32;
+            }
+        }
+        debug_assert_eq!(data.len(), data_entries_offset as usize);
+
+        // Data entries, in the same order as the language tables.
+        let mut resource_offset = data_offset;
+        for resource in tree
+            .values()
+            .flat_map(|names| names.values())
+            .flat_map(|languages| languages.values())
+        {
+            resource_offset = util::align_u32(resource_offset, 8);
+            data.write_pod(&pe::ImageResourceDataEntry {
+                offset_to_data: U32::new(LE, address + resource_offset),
+                size: U32::new(LE, resource.data.len() as u32),
+                code_page: U32::new(LE, resource.code_page),
+                reserved: U32::new(LE, 0),
+            });
+            resource_offset += resource.data.len() as u32;
+        }
+
+        // Directory strings, in the same order as their offsets were assigned.
+        let mut strings = string_offsets.iter().collect::<Vec<_>>();
+        strings.sort_unstable_by_key(|(_, offset)| **offset);
+        for (name, _) in strings {
+            data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            for c in name.iter() {
+                data.extend_from_slice(&c.to_le_bytes());
+            }
+        }
+
+        // Resource data.
+        for resource in tree
+            .values()
+            .flat_map(|names| names.values())
+            .flat_map(|languages| languages.values())
+        {
+            data.resize(util::align(data.len(), 8), 0);
+            data.extend_from_slice(resource.data);
+        }
+        debug_assert_eq!(data.len(), size as usize);
+
+        self.resource_data = data;
+        self.resource_offset = range.file_offset;
+        Ok(range)
+    }
+
+    /// Write the `.rsrc` section that was reserved with [`Self::reserve_resource_section`].
+    pub fn write_resource_section(&mut self) {
+        let data = mem::take(&mut self.resource_data);
+        self.write_section(self.resource_offset, &data);
+        self.resource_data = data;
+    }
+
+    /// Reserve a `.debug` section containing a debug directory with the given entries.
+    ///
+    /// The section contains the debug directory, followed by the data for each entry.
+    ///
+    /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_DEBUG` data directory.
+    pub fn reserve_debug_section(&mut self, entries: &[DebugEntry<'_>]) -> SectionRange {
+        let directory_size = (entries.len() * mem::size_of::<pe::ImageDebugDirectory>()) as u32;
+        let mut size = directory_size;
+        for entry in entries {
+            let data_size = entry.data.size();
+            if data_size != 0 {
+                size = util::align_u32(size, 4) + data_size;
+            }
+        }
+
+        let range = self.reserve_section(
+            *b".debug\0\0",
+            pe::IMAGE_SCN_CNT_INITIALIZED_DATA | pe::IMAGE_SCN_MEM_READ,
+            size,
+            size,
+        );
+        let dir = &mut self.data_directories[pe::IMAGE_DIRECTORY_ENTRY_DEBUG];
+        debug_assert_eq!(dir.virtual_address, 0);
+        *dir = DataDirectory {
+            virtual_address: range.virtual_address,
+            size: directory_size,
+        };
+
+        let mut data = Vec::with_capacity(size as usize);
+        let mut data_offset = directory_size;
+        for entry in entries {
+            let data_size = entry.data.size();
+            let (address, pointer) = if data_size != 0 {
+                data_offset = util::align_u32(data_offset, 4);
+                let offset = data_offset;
+                data_offset += data_size;
+                (range.virtual_address + offset, range.file_offset + offset)
+            } else {
+                (0, 0)
+            };
+            data.write_pod(&pe::ImageDebugDirectory {
+                characteristics: U32::new(LE, 0),
+                time_date_stamp: U32::new(LE, entry.time_date_stamp),
+                major_version: U16::new(LE, entry.major_version),
+                minor_version: U16::new(LE, entry.minor_version),
+                typ: U32::new(LE, entry.data.typ()),
+                size_of_data: U32::new(LE, data_size),
+                address_of_raw_data: U32::new(LE, address),
+                pointer_to_raw_data: U32::new(LE, pointer),
+            });
+        }
+        for entry in entries {
+            if entry.data.size() == 0 {
+                continue;
+            }
+            data.resize(util::align(data.len(), 4), 0);
+            match entry.data {
+                DebugData::CodeView { guid, age, path } => {
+                    data.extend_from_slice(&pe::CODEVIEW_SIGNATURE_RSDS);
+                    data.extend_from_slice(&guid);
+                    data.write_pod(&U32::new(LE, age));
+                    data.extend_from_slice(path);
+                    data.push(0);
+                }
+                DebugData::Repro(hash) => {
+                    data.write_pod(&U32::new(LE, hash.len() as u32));
+                    data.extend_from_slice(hash);
+                }
+                DebugData::ExDllCharacteristics(characteristics) => {
+                    data.write_pod(&U32::new(LE, characteristics));
+                }
+                DebugData::Other { data: other, .. } => {
+                    data.extend_from_slice(other);
+                }
+            }
+        }
+        debug_assert_eq!(data.len(), size as usize);
+        self.debug_data = data;
+        self.debug_offset = range.file_offset;
+        range
+    }
+
+    /// Write the `.debug` section that was reserved with [`Self::reserve_debug_section`].
+    pub fn write_debug_section(&mut self) {
+        let data = mem::take(&mut self.debug_data);
+        self.write_section(self.debug_offset, &data);
+        self.debug_data = data;
+    }
+
     /// Add a base relocation.
     ///
     /// `typ` must be one of the `IMAGE_REL_BASED_*` constants.
@@ -760,10 +1333,31 @@ impl<'a> Writer<'a> {
         self.write_align(self.file_alignment);
     }
 
+    /// Reserve the file range for a COFF symbol table and string table.
+    ///
+    /// COFF symbol tables are deprecated for images, but some linkers still emit them.
+    /// `symbol_num` is the number of symbol table entries, including auxiliary entries,
+    /// and `len` is the combined size of the symbol table and the string table.
+    ///
+    /// This sets the symbol table fields in the file header.
+    ///
+    /// Returns the file offset of the symbol table.
+    pub fn reserve_symbol_table(&mut self, symbol_num: u32, len: u32) -> u32 {
+        debug_assert_eq!(self.symbol_offset, 0);
+        self.symbol_offset = self.reserve(len, 1);
+        self.symbol_num = symbol_num;
+        self.symbol_offset
+    }
+
+    /// Write the COFF symbol table and string table.
+    pub fn write_symbol_table(&mut self, data: &[u8]) {
+        self.pad_until(self.symbol_offset);
+        self.write(data);
+    }
+
     /// Reserve the certificate table.
     ///
     /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_SECURITY` data directory.
-    // TODO: reserve individual certificates
     pub fn reserve_certificate_table(&mut self, size: u32) {
         let size = util::align_u32(size, 8);
         let offset = self.reserve(size, 8);
@@ -776,13 +1370,77 @@ impl<'a> Writer<'a> {
     }
 
     /// Write the certificate table.
-    // TODO: write individual certificates
     pub fn write_certificate_table(&mut self, data: &[u8]) {
         let dir = self.data_directories[pe::IMAGE_DIRECTORY_ENTRY_SECURITY];
         self.pad_until(dir.virtual_address);
         self.write(data);
         self.pad_until(dir.virtual_address + dir.size);
     }
+
+    /// Reserve a certificate table containing the given certificates.
+    ///
+    /// Each certificate is written with a `pe::WinCertificate` header and is
+    /// padded to a multiple of 8 bytes.
+    ///
+    /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_SECURITY` data directory.
+    pub fn reserve_certificates(&mut self, certificates: &[Certificate<'_>]) -> Result<()> {
+        let mut size = 0;
+        for certificate in certificates {
+            let len = mem::size_of::<pe::WinCertificate>() + certificate.data.len();
+            size = util::align(size + len, 8);
+        }
+        let size = u32::try_from(size)
+            .map_err(|_| Error(String::from("certificate table is too large")))?;
+
+        let mut data = Vec::with_capacity(size as usize);
+        for certificate in certificates {
+            let length = mem::size_of::<pe::WinCertificate>() + certificate.data.len();
+            data.write_pod(&pe::WinCertificate {
+                length: U32::new(LE, length as u32),
+                revision: U16::new(LE, certificate.revision),
+                certificate_type: U16::new(LE, certificate.certificate_type),
+            });
+            data.extend_from_slice(certificate.data);
+            data.resize(util::align(data.len(), 8), 0);
+        }
+        debug_assert_eq!(data.len(), size as usize);
+        self.reserve_certificate_table(size);
+        self.certificate_data = data;
+        Ok(())
+    }
+
+    /// Write the certificate table that was reserved with [`Self::reserve_certificates`].
+    pub fn write_certificates(&mut self) {
+        let data = mem::take(&mut self.certificate_data);
+        self.write_certificate_table(&data);
+        self.certificate_data = data;
+    }
+
+    /// Return the file offset of the `check_sum` field in the optional header.
+    ///
+    /// This is only valid after the NT headers have been reserved.
+    /// The checksum can be set after writing using [`update_check_sum`].
+    pub fn check_sum_offset(&self) -> u32 {
+        // The field is at the same offset in both PE32 and PE32+ optional headers.
+        self.nt_headers_offset + 4 + mem::size_of::<pe::ImageFileHeader>() as u32 + 64
+    }
+}
+
+/// Compute the checksum of a PE image and store it in the optional header.
+///
+/// `data` is the entire file data, and `check_sum_offset` is the file offset
+/// of the `check_sum` field, as returned by [`Writer::check_sum_offset`].
+///
+/// Returns the checksum.
+#[cfg(feature = "read_core")]
+pub fn update_check_sum(data: &mut [u8], check_sum_offset: usize) -> Result<u32> {
+    let check_sum = crate::read::pe::image_check_sum(data, check_sum_offset);
+    let field = check_sum_offset
+        .checked_add(4)
+        .and_then(|end| data.get_mut(check_sum_offset..end))
+        .ok_or_else(|| Error(String::from("invalid PE checksum offset")))?;
+    field.copy_from_slice(&check_sum.to_le_bytes());
+    Ok(check_sum)
 }
 
 /// Information required for writing [`pe::ImageNtHeaders32`] or [`pe::ImageNtHeaders64`].
@@ -837,6 +1495,181 @@ pub struct SectionRange {
     pub file_size: u32,
 }
 
+/// A DLL to import from, for use with [`Writer::reserve_import_section`].
+#[derive(Debug, Clone)]
+pub struct ImportDll<'a> {
+    /// The name of the DLL.
+    pub name: &'a [u8],
+    /// The imports from the DLL.
+    pub imports: Vec<Import<'a>>,
+}
+
+/// An import from a DLL, for use with [`Writer::reserve_import_section`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Import<'a> {
+    /// Import by ordinal.
+    Ordinal(u16),
+    /// Import by name.
+    ///
+    /// Includes a hint for the index into the export name pointer table in the target library.
+    Name(u16, &'a [u8]),
+}
+
+/// An export, for use with [`Writer::reserve_export_section`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Export<'a> {
+    /// The ordinal of the export.
+    pub ordinal: u32,
+    /// The name of the export, if any.
+    pub name: Option<&'a [u8]>,
+    /// The target of the export.
+    pub target: ExportTarget<'a>,
+}
+
+/// The target of an [`Export`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportTarget<'a> {
+    /// The address of the export, relative to the image base.
+    Address(u32),
+    /// Forwarded to an export ordinal in another DLL.
+    ///
+    /// This gives the name of the DLL, and the ordinal.
+    ForwardByOrdinal(&'a [u8], u32),
+    /// Forwarded to an export name in another DLL.
+    ///
+    /// This gives the name of the DLL, and the export name.
+    ForwardByName(&'a [u8], &'a [u8]),
+}
+
+/// A resource, for use with [`Writer::reserve_resource_section`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resource<'a> {
+    /// The resource type.
+    ///
+    /// This is usually one of the `RT_*` constants.
+    pub typ: ResourceNameOrId<'a>,
+    /// The resource name.
+    pub name: ResourceNameOrId<'a>,
+    /// The language ID.
+    pub language: u16,
+    /// The code page that is used to decode code point values within the resource data.
+    ///
+    /// This is usually 0.
+    pub code_page: u32,
+    /// The resource data.
+    pub data: &'a [u8],
+}
+
+/// A resource name or ID, for use with [`Resource`].
+///
+/// Names sort before IDs, which is the order required for directory tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResourceNameOrId<'a> {
+    /// A resource name, as UTF-16 code units.
+    ///
+    /// Resource names are usually upper case, since lookups are case insensitive.
+    Name(&'a [u16]),
+    /// A resource ID.
+    Id(u16),
+}
+
+/// An entry in the debug directory, for use with [`Writer::reserve_debug_section`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugEntry<'a> {
+    /// The time and date that the debug data was created.
+    ///
+    /// For reproducible builds, this is usually derived from the hash in the
+    /// [`DebugData::Repro`] entry instead.
+    pub time_date_stamp: u32,
+    /// The major version number of the debug data format.
+    pub major_version: u16,
+    /// The minor version number of the debug data format.
+    pub minor_version: u16,
+    /// The debug data.
+    pub data: DebugData<'a>,
+}
+
+/// The data for a [`DebugEntry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugData<'a> {
+    /// `pe::IMAGE_DEBUG_TYPE_CODEVIEW` data referencing a PDB.
+    CodeView {
+        /// The GUID of the PDB.
+        guid: [u8; 16],
+        /// The age of the PDB.
+        age: u32,
+        /// The path to the PDB.
+        path: &'a [u8],
+    },
+    /// `pe::IMAGE_DEBUG_TYPE_REPRO` data.
+    ///
+    /// This marks the image as a reproducible build. The hash is usually a
+    /// SHA-256 hash of the image contents, and may be empty.
+    Repro(&'a [u8]),
+    /// `pe::IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS` data.
+    ///
+    /// This is a combination of `pe::IMAGE_DLLCHARACTERISTICS_EX_*` flags.
+    ExDllCharacteristics(u32),
+    /// Data for any other type.
+    Other {
+        /// The debug type.
+        ///
+        /// This is one of the `pe::IMAGE_DEBUG_TYPE_*` constants.
+        typ: u32,
+        /// The debug data.
+        data: &'a [u8],
+    },
+}
+
+impl<'a> DebugData<'a> {
+    fn typ(&self) -> u32 {
+        match self {
+            DebugData::CodeView { .. } => pe::IMAGE_DEBUG_TYPE_CODEVIEW,
+            DebugData::Repro(_) => pe::IMAGE_DEBUG_TYPE_REPRO,
+            DebugData::ExDllCharacteristics(_) => pe::IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS,
+            DebugData::Other { typ, .. } => *typ,
+        }
+    }
+
+    fn size(&self) -> u32 {
+        match self {
+            DebugData::CodeView { path, .. } => 4 + 16 + 4 + path.len() as u32 + 1,
+            DebugData::Repro([]) => 0,
+            DebugData::Repro(hash) => 4 + hash.len() as u32,
+            DebugData::ExDllCharacteristics(_) => 4,
+            DebugData::Other { data, .. } => data.len() as u32,
+        }
+    }
+}
+
+/// A certificate, for use with [`Writer::reserve_certificates`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Certificate<'a> {
+    /// The certificate revision.
+    ///
+    /// This is usually `pe::WIN_CERT_REVISION_2_0`.
+    pub revision: u16,
+    /// The certificate type.
+    ///
+    /// This is one of the `WIN_CERT_TYPE_*` constants.
+    pub certificate_type: u16,
+    /// The certificate data.
+    ///
+    /// For `pe::WIN_CERT_TYPE_PKCS_SIGNED_DATA`, this is the DER encoded PKCS#7
+    /// `SignedData` structure.
+    pub data: &'a [u8],
+}
+
+/// Return the length of the decimal representation of an ordinal.
+fn ordinal_len(mut ordinal: u32) -> u32 {
+    let mut len = 1;
+    while ordinal >= 10 {
+        ordinal /= 10;
+        len += 1;
+    }
+    len
+}
+
 struct RelocBlock {
     virtual_address: u32,
     count: u32,
@@ -0,0 +1,126 @@
+use object::read::{Object, ObjectSection, ObjectSymbol};
+use object::{build, pe, write};
+
+fn object() -> Vec<u8> {
+    let mut object = write::Object::new(
+        object::BinaryFormat::Coff,
+        object::Architecture::X86_64,
+        object::Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    let data = object.section_id(write::StandardSection::Data);
+    let extra = object.add_section(
+        Vec::new(),
+        b".text$extra".to_vec(),
+        object::SectionKind::Text,
+    );
+
+    let mut symbols = Vec::new();
+    for (name, section) in [(&b"main"[..], text), (b"extra", extra)] {
+        let offset = object.append_section_data(section, &[0xc3], 1);
+        symbols.push(object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: offset,
+            size: 1,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(section),
+            flags: object::SymbolFlags::None,
+        }));
+    }
+    object.append_section_data(data, &[0; 16], 8);
+    for (offset, symbol) in [(0, symbols[0]), (8, symbols[1])] {
+        object
+            .add_relocation(
+                data,
+                write::Relocation {
+                    offset,
+                    symbol,
+                    addend: 0,
+                    flags: object::RelocationFlags::Coff {
+                        typ: pe::IMAGE_REL_AMD64_ADDR64,
+                    },
+                },
+            )
+            .unwrap();
+    }
+    object.write().unwrap()
+}
+
+// Test that an object file can be read and written without changes.
+#[test]
+fn test_noop() {
+    let data = object();
+    let builder = build::coff::Builder::read(&*data).unwrap();
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+
+    let in_file = object::File::parse(&*data).unwrap();
+    let out_file = object::File::parse(&*out).unwrap();
+    let names = |file: &object::File| {
+        file.sections()
+            .map(|section| section.name().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&in_file), names(&out_file));
+    let symbols = |file: &object::File| {
+        file.symbols()
+            .map(|symbol| (symbol.name().unwrap().to_string(), symbol.address()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(symbols(&in_file), symbols(&out_file));
+    for (in_section, out_section) in in_file.sections().zip(out_file.sections()) {
+        assert_eq!(in_section.data().unwrap(), out_section.data().unwrap());
+        assert_eq!(
+            in_section.relocations().count(),
+            out_section.relocations().count()
+        );
+    }
+}
+
+// Test that deleting a section deletes the symbols defined in it, and that
+// writing fails if a relocation refers to a deleted symbol.
+#[test]
+fn test_delete_section() {
+    let data = object();
+    let mut builder = build::coff::Builder::read(&*data).unwrap();
+    for section in &mut builder.sections {
+        if section.name.as_slice() == b".text$extra" {
+            section.delete = true;
+        }
+    }
+    builder.delete_orphans();
+    assert!(builder
+        .symbols
+        .iter()
+        .all(|symbol| symbol.name.as_slice() != b"extra"));
+    let mut out = Vec::new();
+    assert!(builder.write(&mut out).is_err());
+
+    let mut builder = build::coff::Builder::read(&*data).unwrap();
+    for section in &mut builder.sections {
+        if section.name.as_slice() == b".text$extra" {
+            section.delete = true;
+        } else if section.name.as_slice() == b".data" {
+            section.relocations.truncate(1);
+        }
+    }
+    builder.delete_orphans();
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+
+    let file = object::File::parse(&*out).unwrap();
+    let sections = file
+        .sections()
+        .map(|section| section.name().unwrap())
+        .collect::<Vec<_>>();
+    assert!(!sections.contains(&".text$extra"));
+    let data = file.section_by_name(".data").unwrap();
+    let relocations = data.relocations().collect::<Vec<_>>();
+    assert_eq!(relocations.len(), 1);
+    let object::RelocationTarget::Symbol(index) = relocations[0].1.target() else {
+        panic!("unexpected relocation target");
+    };
+    assert_eq!(file.symbol_by_index(index).unwrap().name(), Ok("main"));
+}
@@ -252,3 +252,315 @@ fn test_dynsym() {
         }
     }
 }
+
+#[test]
+fn test_pack_relative_relocations() {
+    let mut builder = build::elf::Builder::new(object::Endianness::Little, true);
+    builder.header.e_type = elf::ET_DYN;
+    builder.header.e_machine = elf::EM_X86_64;
+    builder.header.e_phoff = 0x40;
+
+    let section = builder.sections.add();
+    section.name = b".shstrtab"[..].into();
+    section.sh_type = elf::SHT_STRTAB;
+    section.data = build::elf::SectionData::SectionString;
+
+    let section = builder.sections.add();
+    section.name = b".rela.dyn"[..].into();
+    section.sh_type = elf::SHT_RELA;
+    section.sh_flags = elf::SHF_ALLOC as u64;
+    section.sh_addralign = 8;
+    section.sh_entsize = 24;
+    let rela_id = section.id();
+
+    let section = builder.sections.add();
+    section.name = b".data"[..].into();
+    section.sh_type = elf::SHT_PROGBITS;
+    section.sh_flags = (elf::SHF_ALLOC | elf::SHF_WRITE) as u64;
+    section.sh_addralign = 8;
+    section.data = build::elf::SectionData::Data(vec![0; 0x200].into());
+    let data_id = section.id();
+
+    let mut dynamic_ids = Vec::new();
+    for (name, sh_type, data) in [
+        (
+            &b".dynsym"[..],
+            elf::SHT_DYNSYM,
+            build::elf::SectionData::DynamicSymbol,
+        ),
+        (
+            &b".dynstr"[..],
+            elf::SHT_STRTAB,
+            build::elf::SectionData::DynamicString,
+        ),
+        (
+            &b".gnu.version"[..],
+            elf::SHT_GNU_VERSYM,
+            build::elf::SectionData::GnuVersym,
+        ),
+        (
+            &b".gnu.version_r"[..],
+            elf::SHT_GNU_VERNEED,
+            build::elf::SectionData::GnuVerneed,
+        ),
+        (
+            &b".dynamic"[..],
+            elf::SHT_DYNAMIC,
+            build::elf::SectionData::Dynamic(vec![
+                build::elf::Dynamic::String {
+                    tag: elf::DT_NEEDED,
+                    val: b"libc.so.6"[..].into(),
+                },
+                build::elf::Dynamic::Integer {
+                    tag: elf::DT_RELA,
+                    val: 0x1000,
+                },
+                build::elf::Dynamic::Integer {
+                    tag: elf::DT_RELASZ,
+                    val: 4 * 24,
+                },
+                build::elf::Dynamic::Integer {
+                    tag: elf::DT_RELAENT,
+                    val: 24,
+                },
+                build::elf::Dynamic::Integer {
+                    tag: elf::DT_RELACOUNT,
+                    val: 3,
+                },
+                build::elf::Dynamic::Auto {
+                    tag: elf::DT_SYMTAB,
+                },
+                build::elf::Dynamic::Auto {
+                    tag: elf::DT_STRTAB,
+                },
+                build::elf::Dynamic::Auto { tag: elf::DT_STRSZ },
+                build::elf::Dynamic::Auto {
+                    tag: elf::DT_VERSYM,
+                },
+                build::elf::Dynamic::Auto {
+                    tag: elf::DT_VERNEED,
+                },
+                build::elf::Dynamic::Auto {
+                    tag: elf::DT_VERNEEDNUM,
+                },
+            ]),
+        ),
+    ] {
+        let section = builder.sections.add();
+        section.name = name.into();
+        section.sh_type = sh_type;
+        section.sh_flags = (elf::SHF_ALLOC | elf::SHF_WRITE) as u64;
+        section.sh_addralign = 8;
+        section.data = data;
+        dynamic_ids.push(section.id());
+    }
+    let dynamic_id = *dynamic_ids.last().unwrap();
+    builder.sections.get_mut(rela_id).sh_link_section = Some(dynamic_ids[0]);
+    builder.sections.get_mut(dynamic_id).sh_link_section = Some(dynamic_ids[1]);
+
+    let file = builder.version_files.add(b"libc.so.6"[..].into());
+    let version = builder
+        .versions
+        .add(build::elf::VersionData::Need(build::elf::VersionNeed {
+            file,
+            name: b"GLIBC_2.2.5"[..].into(),
+            flags: 0,
+        }));
+    let symbol = builder.dynamic_symbols.add();
+    symbol.name = b"malloc"[..].into();
+    symbol.set_st_info(elf::STB_GLOBAL, elf::STT_FUNC);
+    symbol.version = version;
+    let malloc_id = symbol.id();
+
+    let relative = |r_offset, r_addend| build::elf::DynamicRelocation {
+        r_offset,
+        symbol: None,
+        r_type: elf::R_X86_64_RELATIVE,
+        r_addend,
+    };
+    builder.sections.get_mut(rela_id).data = build::elf::SectionData::DynamicRelocation(vec![
+        relative(0x2100, 0x3333),
+        build::elf::DynamicRelocation {
+            r_offset: 0x2010,
+            symbol: Some(malloc_id),
+            r_type: elf::R_X86_64_64,
+            r_addend: 0,
+        },
+        relative(0x2000, 0x1111),
+        relative(0x2008, 0x2222),
+    ]);
+
+    builder.set_section_sizes();
+    for (address, ids) in [
+        (0x1000, vec![rela_id]),
+        (0x2000, vec![data_id]),
+        (0x3000, dynamic_ids),
+    ] {
+        let segment = builder.segments.add();
+        segment.p_type = elf::PT_LOAD;
+        segment.p_flags = elf::PF_R | elf::PF_W;
+        segment.p_offset = address;
+        segment.p_vaddr = address;
+        segment.p_paddr = address;
+        segment.p_align = 8;
+        for id in ids {
+            segment.append_section(builder.sections.get_mut(id));
+        }
+    }
+    let segment = builder.segments.add();
+    segment.p_type = elf::PT_DYNAMIC;
+    segment.p_flags = elf::PF_R | elf::PF_W;
+    segment.p_align = 8;
+    segment.append_section(builder.sections.get_mut(dynamic_id));
+
+    assert!(builder.pack_relative_relocations().unwrap());
+
+    // The dynamic sections have grown, so lay them out again.
+    builder.set_section_sizes();
+    for segment in &mut builder.segments {
+        if segment.sections.contains(&dynamic_id) {
+            let ids = std::mem::take(&mut segment.sections);
+            segment.remove_sections();
+            for id in ids {
+                segment.append_section(builder.sections.get_mut(id));
+            }
+        }
+    }
+
+    let mut buf = Vec::new();
+    builder.write(&mut buf).unwrap();
+
+    let builder = build::elf::Builder::read(&*buf).unwrap();
+    let section = |name: &[u8]| {
+        builder
+            .sections
+            .iter()
+            .find(|section| section.name.as_slice() == name)
+            .unwrap()
+    };
+    let relr = section(b".relr.dyn");
+    assert_eq!(relr.sh_type, elf::SHT_RELR);
+    assert_eq!(relr.sh_addr, 0x1018);
+    assert_eq!(relr.sh_entsize, 8);
+    match &relr.data {
+        build::elf::SectionData::Data(data) => {
+            let mut expect = Vec::new();
+            expect.extend_from_slice(&0x2000u64.to_le_bytes());
+            expect.extend_from_slice(&((1u64 << 32) | (1 << 1) | 1).to_le_bytes());
+            assert_eq!(data.as_slice(), expect);
+        }
+        _ => panic!("Unexpected .relr.dyn data"),
+    }
+    match &section(b".rela.dyn").data {
+        build::elf::SectionData::DynamicRelocation(relocations) => {
+            assert_eq!(relocations.len(), 1);
+            assert_eq!(relocations[0].r_type, elf::R_X86_64_64);
+        }
+        _ => panic!("Unexpected .rela.dyn data"),
+    }
+    match &section(b".data").data {
+        build::elf::SectionData::Data(data) => {
+            assert_eq!(data[0..8], 0x1111u64.to_le_bytes());
+            assert_eq!(data[8..16], 0x2222u64.to_le_bytes());
+            assert_eq!(data[0x100..0x108], 0x3333u64.to_le_bytes());
+        }
+        _ => panic!("Unexpected .data data"),
+    }
+
+    let dynamics = builder.dynamic_data().unwrap();
+    let dynamic = |tag| {
+        dynamics.iter().find_map(|dynamic| match *dynamic {
+            build::elf::Dynamic::Integer { tag: t, val } if t == tag => Some(val),
+            _ => None,
+        })
+    };
+    assert_eq!(dynamic(elf::DT_RELASZ), Some(24));
+    assert_eq!(dynamic(elf::DT_RELACOUNT), None);
+    assert_eq!(dynamic(elf::DT_RELR), Some(0x1018));
+    assert_eq!(dynamic(elf::DT_RELRSZ), Some(16));
+    assert_eq!(dynamic(elf::DT_RELRENT), Some(8));
+
+    let needs = builder
+        .versions
+        .iter()
+        .filter_map(|version| match &version.data {
+            build::elf::VersionData::Need(need) => Some(need.name.as_slice()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(needs, [&b"GLIBC_2.2.5"[..], &b"GLIBC_ABI_DT_RELR"[..]]);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_compression() {
+    use object::read::{Object, ObjectSection};
+
+    let debug_info = (0..0x1000u32).map(|i| (i % 7) as u8).collect::<Vec<_>>();
+    let mut builder = build::elf::Builder::new(object::Endianness::Little, true);
+    builder.header.e_type = elf::ET_REL;
+    builder.header.e_machine = elf::EM_X86_64;
+
+    let section = builder.sections.add();
+    section.name = b".shstrtab"[..].into();
+    section.sh_type = elf::SHT_STRTAB;
+    section.data = build::elf::SectionData::SectionString;
+
+    let section = builder.sections.add();
+    section.name = b".debug_info"[..].into();
+    section.sh_type = elf::SHT_PROGBITS;
+    section.sh_addralign = 1;
+    section.data = build::elf::SectionData::Data(debug_info.clone().into());
+
+    let section = builder.sections.add();
+    section.name = b".comment"[..].into();
+    section.sh_type = elf::SHT_PROGBITS;
+    section.sh_addralign = 1;
+    section.data = build::elf::SectionData::Data(vec![0; 0x100].into());
+
+    builder
+        .compress_debug_sections(object::CompressionFormat::Zstandard)
+        .unwrap();
+    let section = builder
+        .sections
+        .iter()
+        .find(|section| section.name.as_slice() == b".debug_info")
+        .unwrap();
+    let build::elf::SectionData::Data(data) = &section.data else {
+        panic!("unexpected section data");
+    };
+    assert_eq!(section.sh_size, data.len() as u64);
+    let mut compressed = Vec::new();
+    builder.write(&mut compressed).unwrap();
+
+    let file = object::read::File::parse(&*compressed).unwrap();
+    let section = file.section_by_name(".debug_info").unwrap();
+    let data = section.compressed_data().unwrap();
+    assert_eq!(data.format, object::CompressionFormat::Zstandard);
+    assert_eq!(&*section.uncompressed_data().unwrap(), &debug_info[..]);
+    let section = file.section_by_name(".comment").unwrap();
+    assert_eq!(
+        section.compressed_data().unwrap().format,
+        object::CompressionFormat::None
+    );
+
+    let mut builder = build::elf::Builder::read(&*compressed).unwrap();
+    builder.decompress_sections().unwrap();
+    let section = builder
+        .sections
+        .iter()
+        .find(|section| section.name.as_slice() == b".debug_info")
+        .unwrap();
+    assert_eq!(section.sh_size, debug_info.len() as u64);
+    let mut decompressed = Vec::new();
+    builder.write(&mut decompressed).unwrap();
+
+    let file = object::read::File::parse(&*decompressed).unwrap();
+    let section = file.section_by_name(".debug_info").unwrap();
+    assert_eq!(
+        section.compressed_data().unwrap().format,
+        object::CompressionFormat::None
+    );
+    assert_eq!(section.align(), 1);
+    assert_eq!(section.data().unwrap(), &debug_info[..]);
+}
@@ -0,0 +1,1681 @@
+use object::read::macho::{
+    DyldBindIterator, DyldRebase, ExportData, LoadCommandVariant, MachHeader, MachOFile64,
+};
+use object::read::{Object, ObjectSection, ObjectSegment, ObjectSymbol};
+use object::{build, macho, write};
+
+// Test that an object file can be read, modified and written.
+#[test]
+fn test_object() {
+    let mut object = write::Object::new(
+        object::BinaryFormat::MachO,
+        object::Architecture::X86_64,
+        object::Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    let data = object.section_id(write::StandardSection::Data);
+    let bss = object.section_id(write::StandardSection::UninitializedData);
+    let bundle = object.add_section(
+        b"__LLVM".to_vec(),
+        b"__bundle".to_vec(),
+        object::SectionKind::Other,
+    );
+    object.append_section_data(bundle, b"bundle", 1);
+    object.append_section_bss(bss, 16, 8);
+
+    let mut symbols = Vec::new();
+    for (name, section) in [(&b"main"[..], text), (b"bundle", bundle)] {
+        let offset = object.append_section_data(section, &[0xc3], 1);
+        symbols.push(object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: offset,
+            size: 1,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(section),
+            flags: object::SymbolFlags::None,
+        }));
+    }
+    object.append_section_data(data, &[0; 16], 8);
+    for (offset, symbol) in [(0, symbols[0]), (8, symbols[1])] {
+        object
+            .add_relocation(
+                data,
+                write::Relocation {
+                    offset,
+                    symbol,
+                    addend: 0,
+                    flags: object::RelocationFlags::Generic {
+                        kind: object::RelocationKind::Absolute,
+                        encoding: object::RelocationEncoding::Generic,
+                        size: 64,
+                    },
+                },
+            )
+            .unwrap();
+    }
+    let data = object.write().unwrap();
+
+    let mut builder = build::macho::Builder::read(&*data).unwrap();
+    assert!(builder.is_object());
+    assert_eq!(builder.sections.count(), 4);
+    for section in &mut builder.sections {
+        if section.name.as_slice() == b"__bundle" {
+            section.delete = true;
+        }
+    }
+    for symbol in &mut builder.symbols {
+        if symbol.name.as_slice() == b"_main" {
+            symbol.name = b"_start"[..].into();
+        }
+    }
+    builder.delete_orphans();
+    assert_eq!(builder.segments.count(), 1);
+    assert_eq!(builder.symbols.count(), 1);
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+
+    let file = object::File::parse(&*out).unwrap();
+    let sections = file
+        .sections()
+        .map(|section| section.name().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(sections, ["__text", "__data", "__bss"]);
+    let bss = file.section_by_name("__bss").unwrap();
+    assert_eq!(bss.size(), 16);
+    assert_eq!(bss.file_range(), None);
+
+    let symbols = file
+        .symbols()
+        .map(|symbol| symbol.name().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(symbols, ["_start"]);
+
+    let data = file.section_by_name("__data").unwrap();
+    assert_eq!(data.data().unwrap(), &[0; 16]);
+    let relocations = data.relocations().collect::<Vec<_>>();
+    assert_eq!(relocations.len(), 1);
+    assert_eq!(relocations[0].0, 0);
+    let object::RelocationTarget::Symbol(index) = relocations[0].1.target() else {
+        panic!("unexpected relocation target");
+    };
+    assert_eq!(file.symbol_by_index(index).unwrap().name(), Ok("_start"));
+}
+
+// Test that writing fails if a relocation refers to a deleted symbol.
+#[test]
+fn test_object_deleted_symbol() {
+    let mut builder = build::macho::Builder::new(object::Endianness::Little, true);
+    builder.header.cputype = macho::CPU_TYPE_X86_64;
+    builder.header.filetype = macho::MH_OBJECT;
+
+    let section = builder.sections.add();
+    section.name = b"__data"[..].into();
+    section.segment_name = b"__DATA"[..].into();
+    section.size = 8;
+    section.align = 3;
+    section.data = vec![0; 8].into();
+    let section_id = section.id();
+
+    let symbol = builder.symbols.add();
+    symbol.name = b"_foo"[..].into();
+    symbol.n_type = macho::N_SECT | macho::N_EXT;
+    symbol.section = Some(section_id);
+    let symbol_id = symbol.id();
+
+    builder
+        .sections
+        .get_mut(section_id)
+        .relocations
+        .push(build::macho::Relocation {
+            r_address: 0,
+            target: build::macho::RelocationTarget::Symbol(symbol_id),
+            r_pcrel: false,
+            r_length: 3,
+            r_type: macho::X86_64_RELOC_UNSIGNED,
+            r_scattered: false,
+        });
+
+    let segment = builder.segments.add();
+    segment.vmsize = 8;
+    segment.maxprot = macho::VM_PROT_READ | macho::VM_PROT_WRITE;
+    segment.initprot = macho::VM_PROT_READ | macho::VM_PROT_WRITE;
+    segment.sections.push(section_id);
+    let segment_id = segment.id();
+    builder
+        .load_commands
+        .push(build::macho::LoadCommand::Segment(segment_id));
+    builder
+        .load_commands
+        .push(build::macho::LoadCommand::Symtab);
+    builder
+        .load_commands
+        .push(build::macho::LoadCommand::Dysymtab);
+
+    builder.symbols.get_mut(symbol_id).delete = true;
+    let mut out = Vec::new();
+    assert!(builder.write(&mut out).is_err());
+}
+
+fn dylib() -> build::macho::Builder<'static> {
+    let mut builder = build::macho::Builder::new(object::Endianness::Little, true);
+    builder.header.cputype = macho::CPU_TYPE_ARM64;
+    builder.header.cpusubtype = macho::CPU_SUBTYPE_ARM64_ALL;
+    builder.header.filetype = macho::MH_DYLIB;
+    builder.header.flags = macho::MH_NOUNDEFS | macho::MH_DYLDLINK | macho::MH_TWOLEVEL;
+
+    let section = builder.sections.add();
+    section.name = b"__text"[..].into();
+    section.segment_name = b"__TEXT"[..].into();
+    section.addr = 0x1000;
+    section.size = 4;
+    section.offset = 0x1000;
+    section.align = 2;
+    section.flags = macho::S_REGULAR | macho::S_ATTR_PURE_INSTRUCTIONS;
+    // ret
+    section.data = vec![0xc0, 0x03, 0x5f, 0xd6].into();
+    let text_id = section.id();
+
+    let segment = builder.segments.add();
+    segment.name = b"__TEXT"[..].into();
+    segment.vmsize = 0x4000;
+    segment.filesize = 0x4000;
+    segment.maxprot = macho::VM_PROT_READ | macho::VM_PROT_EXECUTE;
+    segment.initprot = macho::VM_PROT_READ | macho::VM_PROT_EXECUTE;
+    segment.sections.push(text_id);
+    segment.data = vec![0; 0x4000].into();
+    let text_segment_id = segment.id();
+
+    let segment = builder.segments.add();
+    segment.name = b"__LINKEDIT"[..].into();
+    segment.vmaddr = 0x4000;
+    segment.fileoff = 0x4000;
+    segment.maxprot = macho::VM_PROT_READ;
+    segment.initprot = macho::VM_PROT_READ;
+    let linkedit_segment_id = segment.id();
+
+    let symbol = builder.symbols.add();
+    symbol.name = b"_foo"[..].into();
+    symbol.n_type = macho::N_SECT | macho::N_EXT;
+    symbol.section = Some(text_id);
+    symbol.n_value = 0x1000;
+    let symbol = builder.symbols.add();
+    symbol.name = b"_bar"[..].into();
+    symbol.n_type = macho::N_UNDF | macho::N_EXT;
+    symbol.n_desc = 1 << 8;
+    let symbol = builder.symbols.add();
+    symbol.name = b"_local"[..].into();
+    symbol.n_type = macho::N_SECT;
+    symbol.section = Some(text_id);
+    symbol.n_value = 0x1000;
+
+    // An exports trie containing `_foo` at offset 0x1000.
+    let exports = vec![
+        0x00, 0x01, b'_', b'f', b'o', b'o', 0x00, 0x08, 0x03, 0x00, 0x80, 0x20, 0x00,
+    ];
+
+    builder.load_commands = vec![
+        build::macho::LoadCommand::Segment(text_segment_id),
+        build::macho::LoadCommand::Segment(linkedit_segment_id),
+        build::macho::LoadCommand::Dylib(build::macho::Dylib {
+            cmd: macho::LC_ID_DYLIB,
+            name: b"@rpath/libfoo.dylib"[..].into(),
+            timestamp: 1,
+            current_version: 0x10000,
+            compatibility_version: 0x10000,
+        }),
+        build::macho::LoadCommand::Dylib(build::macho::Dylib {
+            cmd: macho::LC_LOAD_DYLIB,
+            name: b"/usr/lib/libSystem.B.dylib"[..].into(),
+            timestamp: 2,
+            current_version: 0x10000,
+            compatibility_version: 0x10000,
+        }),
+        build::macho::LoadCommand::LinkeditData {
+            cmd: macho::LC_DYLD_EXPORTS_TRIE,
+            data: exports.into(),
+        },
+        build::macho::LoadCommand::Symtab,
+        build::macho::LoadCommand::Dysymtab,
+    ];
+    builder
+}
+
+// Test that a dylib can be written, read and modified.
+#[test]
+fn test_dylib() {
+    let mut out = Vec::new();
+    dylib().write(&mut out).unwrap();
+    check_dylib(&out, b"@rpath/libfoo.dylib");
+
+    let mut builder = build::macho::Builder::read(&*out).unwrap();
+    assert!(!builder.is_object());
+    for command in &mut builder.load_commands {
+        if let build::macho::LoadCommand::Dylib(dylib) = command {
+            if dylib.cmd == macho::LC_ID_DYLIB {
+                dylib.name = b"@rpath/libfoo-with-a-longer-name.dylib"[..].into();
+            }
+        }
+    }
+    builder.load_commands.push(build::macho::LoadCommand::Rpath(
+        b"@loader_path/../lib"[..].into(),
+    ));
+    let mut out2 = Vec::new();
+    builder.write(&mut out2).unwrap();
+    check_dylib(&out2, b"@rpath/libfoo-with-a-longer-name.dylib");
+    assert_eq!(out[0x1000..0x4000], out2[0x1000..0x4000]);
+
+    // Writing without modifications must not change the file.
+    let builder = build::macho::Builder::read(&*out2).unwrap();
+    let mut out3 = Vec::new();
+    builder.write(&mut out3).unwrap();
+    assert_eq!(out2, out3);
+}
+
+fn check_dylib(data: &[u8], id: &[u8]) {
+    let file = MachOFile64::<object::Endianness>::parse(data).unwrap();
+    let endian = file.endian();
+
+    let text = file.section_by_name("__text").unwrap();
+    assert_eq!(text.address(), 0x1000);
+    assert_eq!(text.data().unwrap(), [0xc0, 0x03, 0x5f, 0xd6]);
+
+    let linkedit = file
+        .segments()
+        .find(|segment| segment.name() == Ok(Some("__LINKEDIT")))
+        .unwrap();
+    let (offset, size) = linkedit.file_range();
+    assert_eq!(offset, 0x4000);
+    assert_eq!(offset + size, data.len() as u64);
+    assert_eq!(linkedit.size(), 0x4000);
+
+    // Local, then defined external, then undefined symbols.
+    let symbols = file
+        .symbols()
+        .map(|symbol| symbol.name().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(symbols, ["_local", "_foo", "_bar"]);
+    let symbol = file.symbol_by_name("_foo").unwrap();
+    assert_eq!(symbol.section_index(), Some(text.index()));
+    let imports = file.imports().unwrap();
+    assert_eq!(imports.len(), 1);
+    assert_eq!(imports[0].name(), b"_bar");
+    assert_eq!(imports[0].library(), b"/usr/lib/libSystem.B.dylib");
+
+    let mut id_dylib = None;
+    let mut exports = Vec::new();
+    let mut commands = file.macho_header().load_commands(endian, data, 0).unwrap();
+    while let Some(command) = commands.next().unwrap() {
+        match command.variant().unwrap() {
+            LoadCommandVariant::IdDylib(dylib) => {
+                id_dylib = Some(command.string(endian, dylib.dylib.name).unwrap());
+            }
+            LoadCommandVariant::LinkeditData(linkedit)
+                if linkedit.cmd.get(endian) == macho::LC_DYLD_EXPORTS_TRIE =>
+            {
+                let mut trie = linkedit.exports_trie(endian, data).unwrap();
+                while let Some(export) = trie.next().unwrap() {
+                    exports.push(export.name().to_vec());
+                }
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(id_dylib, Some(id));
+    assert_eq!(exports, [b"_foo".to_vec()]);
+}
+
+// Test that chained fixups are used for imports and dynamic relocations.
+#[test]
+fn test_chained_fixups() {
+    let mut builder = build::macho::Builder::new(object::Endianness::Little, true);
+    builder.header.cputype = macho::CPU_TYPE_ARM64;
+    builder.header.cpusubtype = macho::CPU_SUBTYPE_ARM64E;
+    builder.header.filetype = macho::MH_DYLIB;
+    builder.header.flags = macho::MH_DYLDLINK | macho::MH_TWOLEVEL;
+
+    // `__DATA_CONST` uses `DYLD_CHAINED_PTR_ARM64E`, with a stride of 8 bytes.
+    let mut data_const = Vec::new();
+    // Rebase to 0x1_0000_1000 with high8 0x12, next 1.
+    data_const.extend_from_slice(&u64::to_le_bytes(0x1_0000_1000 | 0x12 << 43 | 1 << 51));
+    // Bind to import 0 with addend -4, next 1.
+    data_const.extend_from_slice(&u64::to_le_bytes((0x7fffc << 32) | 1 << 51 | 1 << 62));
+    // Authenticated rebase to offset 0x2000, key DA, next 1.
+    data_const.extend_from_slice(&u64::to_le_bytes(
+        0x2000 | 0x1234 << 32 | 1 << 48 | 2 << 49 | 1 << 51 | 1 << 63,
+    ));
+    // Authenticated bind to import 1, key IA.
+    data_const.extend_from_slice(&u64::to_le_bytes(1 | 0x5678 << 32 | 1 << 62 | 1 << 63));
+    data_const.resize(0x4000, 0);
+
+    // `__DATA` uses `DYLD_CHAINED_PTR_64_OFFSET`, with a stride of 4 bytes.
+    let mut data = vec![0; 0x10];
+    // Rebase to offset 0x3000, next 2.
+    data.extend_from_slice(&u64::to_le_bytes(0x3000 | 2 << 51));
+    // Bind to import 2 with addend 3.
+    data.extend_from_slice(&u64::to_le_bytes(2 | 3 << 24 | 1 << 63));
+    data.resize(0x4000, 0);
+
+    let mut fixups = Vec::new();
+    let u16 = |fixups: &mut Vec<u8>, value: u16| fixups.extend_from_slice(&value.to_le_bytes());
+    let u32 = |fixups: &mut Vec<u8>, value: u32| fixups.extend_from_slice(&value.to_le_bytes());
+    // dyld_chained_fixups_header
+    for value in [0, 0x20, 0x68, 0x80, 3, macho::DYLD_CHAINED_IMPORT_ADDEND, 0] {
+        u32(&mut fixups, value);
+    }
+    fixups.resize(0x20, 0);
+    // dyld_chained_starts_in_image
+    for value in [4, 0, 0x18, 0x30, 0] {
+        u32(&mut fixups, value);
+    }
+    fixups.resize(0x38, 0);
+    // dyld_chained_starts_in_segment
+    for (pointer_format, segment_offset, page_start) in [
+        (macho::DYLD_CHAINED_PTR_ARM64E, 0x4000u64, 0),
+        (macho::DYLD_CHAINED_PTR_64_OFFSET, 0x8000, 0x10),
+    ] {
+        u32(&mut fixups, 24);
+        u16(&mut fixups, 0x4000);
+        u16(&mut fixups, pointer_format);
+        fixups.extend_from_slice(&segment_offset.to_le_bytes());
+        u32(&mut fixups, 0);
+        u16(&mut fixups, 1);
+        u16(&mut fixups, page_start);
+    }
+    // dyld_chained_import_addend
+    for (import, addend) in [
+        (1 | 1 << 9, 0),
+        (1 | 1 << 8 | 9 << 9, 0),
+        (
+            macho::BIND_SPECIAL_DYLIB_FLAT_LOOKUP as u8 as u32 | 15 << 9,
+            8,
+        ),
+    ] {
+        u32(&mut fixups, import);
+        u32(&mut fixups, addend);
+    }
+    fixups.extend_from_slice(b"\0_malloc\0_free\0_missing\0");
+
+    let mut segment_ids = Vec::new();
+    for (name, vmaddr, data) in [
+        (&b"__TEXT"[..], 0x1_0000_0000, vec![0; 0x4000]),
+        (b"__DATA_CONST", 0x1_0000_4000, data_const),
+        (b"__DATA", 0x1_0000_8000, data),
+        (b"__LINKEDIT", 0x1_0000_c000, Vec::new()),
+    ] {
+        let segment = builder.segments.add();
+        segment.name = name.into();
+        segment.vmaddr = vmaddr;
+        segment.vmsize = data.len() as u64;
+        segment.fileoff = vmaddr - 0x1_0000_0000;
+        segment.filesize = data.len() as u64;
+        segment.maxprot = macho::VM_PROT_READ;
+        segment.initprot = macho::VM_PROT_READ;
+        segment.data = data.into();
+        segment_ids.push(segment.id());
+    }
+
+    for name in [&b"_malloc"[..], b"_free"] {
+        let symbol = builder.symbols.add();
+        symbol.name = name.into();
+        symbol.n_type = macho::N_UNDF | macho::N_EXT;
+        symbol.n_desc = 1 << 8;
+    }
+
+    builder.load_commands = segment_ids
+        .into_iter()
+        .map(build::macho::LoadCommand::Segment)
+        .collect();
+    builder.load_commands.extend([
+        build::macho::LoadCommand::Dylib(build::macho::Dylib {
+            cmd: macho::LC_ID_DYLIB,
+            name: b"@rpath/libfoo.dylib"[..].into(),
+            timestamp: 1,
+            current_version: 0x10000,
+            compatibility_version: 0x10000,
+        }),
+        build::macho::LoadCommand::Dylib(build::macho::Dylib {
+            cmd: macho::LC_LOAD_DYLIB,
+            name: b"/usr/lib/libSystem.B.dylib"[..].into(),
+            timestamp: 2,
+            current_version: 0x10000,
+            compatibility_version: 0x10000,
+        }),
+        build::macho::LoadCommand::LinkeditData {
+            cmd: macho::LC_DYLD_CHAINED_FIXUPS,
+            data: fixups.into(),
+        },
+        build::macho::LoadCommand::Symtab,
+        build::macho::LoadCommand::Dysymtab,
+    ]);
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+
+    let file = MachOFile64::<object::Endianness>::parse(&*out).unwrap();
+    let chained_fixups = file.chained_fixups().unwrap().unwrap();
+    let imports = chained_fixups
+        .imports()
+        .unwrap()
+        .iter()
+        .map(|import| {
+            let import = import.unwrap();
+            (
+                import.lib_ordinal,
+                import.weak_import,
+                import.name,
+                import.addend,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            (1, false, &b"_malloc"[..], 0),
+            (1, true, b"_free", 0),
+            (
+                macho::BIND_SPECIAL_DYLIB_FLAT_LOOKUP.into(),
+                false,
+                b"_missing",
+                8
+            ),
+        ]
+    );
+
+    let mut fixups = Vec::new();
+    for segment in chained_fixups.segments().unwrap() {
+        let segment = segment.unwrap();
+        let file_offset = 0x4000 * segment.segment_index() as u64;
+        for fixup in segment.fixups(&*out, file_offset, 0x1_0000_0000).unwrap() {
+            let fixup = fixup.unwrap();
+            fixups.push((
+                segment.segment_index(),
+                fixup.offset,
+                fixup.value,
+                fixup
+                    .auth
+                    .map(|auth| (auth.key, auth.diversity, auth.addr_div)),
+            ));
+        }
+    }
+    assert_eq!(
+        fixups,
+        [
+            (
+                1,
+                0,
+                object::read::macho::ChainedFixupValue::Rebase(0x1200_0001_0000_1000),
+                None
+            ),
+            (
+                1,
+                8,
+                object::read::macho::ChainedFixupValue::Bind {
+                    ordinal: 0,
+                    addend: -4
+                },
+                None
+            ),
+            (
+                1,
+                16,
+                object::read::macho::ChainedFixupValue::Rebase(0x1_0000_2000),
+                Some((macho::PtrauthKey::DA, 0x1234, true))
+            ),
+            (
+                1,
+                24,
+                object::read::macho::ChainedFixupValue::Bind {
+                    ordinal: 1,
+                    addend: 0
+                },
+                Some((macho::PtrauthKey::IA, 0x5678, false))
+            ),
+            (
+                2,
+                0x10,
+                object::read::macho::ChainedFixupValue::Rebase(0x1_0000_3000),
+                None
+            ),
+            (
+                2,
+                0x18,
+                object::read::macho::ChainedFixupValue::Bind {
+                    ordinal: 2,
+                    addend: 3
+                },
+                None
+            ),
+        ]
+    );
+
+    let imports = file
+        .imports()
+        .unwrap()
+        .iter()
+        .map(|import| (import.name(), import.library()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            (&b"_malloc"[..], &b"/usr/lib/libSystem.B.dylib"[..]),
+            (b"_free", b"/usr/lib/libSystem.B.dylib"),
+            (b"_missing", b""),
+        ]
+    );
+
+    // Library ordinals are ignored if the image does not use two-level namespaces.
+    let mut flat = out.clone();
+    // `MH_TWOLEVEL` is in the low byte of the little endian `flags` field.
+    flat[24] &= !(macho::MH_TWOLEVEL as u8);
+    let flat_file = MachOFile64::<object::Endianness>::parse(&*flat).unwrap();
+    assert!(flat_file
+        .imports()
+        .unwrap()
+        .iter()
+        .all(|import| import.library().is_empty()));
+
+    // The symbol table is used for imports if the chained fixups are malformed.
+    let mut fixups_offset = None;
+    let mut commands = file.macho_load_commands().unwrap();
+    while let Some(command) = commands.next().unwrap() {
+        if let Some(command) = command.dyld_chained_fixups().unwrap() {
+            fixups_offset = Some(command.dataoff.get(file.endian()) as usize);
+        }
+    }
+    let mut malformed = out.clone();
+    // `imports_format` is at offset 20 in the chained fixups header.
+    malformed[fixups_offset.unwrap() + 20] = 0xff;
+    let malformed_file = MachOFile64::<object::Endianness>::parse(&*malformed).unwrap();
+    let imports = malformed_file
+        .imports()
+        .unwrap()
+        .iter()
+        .map(|import| (import.name(), import.library()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            (&b"_free"[..], &b"/usr/lib/libSystem.B.dylib"[..]),
+            (b"_malloc", b"/usr/lib/libSystem.B.dylib"),
+        ]
+    );
+
+    let malloc = file.symbol_by_name("_malloc").unwrap().index();
+    let free = file.symbol_by_name("_free").unwrap().index();
+    let relocations = file
+        .dynamic_relocations()
+        .unwrap()
+        .map(|(offset, relocation)| {
+            assert_eq!(relocation.kind(), object::RelocationKind::Absolute);
+            assert_eq!(relocation.size(), 64);
+            (offset, relocation.target(), relocation.addend())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        relocations,
+        [
+            (
+                0x1_0000_4000,
+                object::RelocationTarget::Absolute,
+                0x1200_0001_0000_1000
+            ),
+            (0x1_0000_4008, object::RelocationTarget::Symbol(malloc), -4),
+            (
+                0x1_0000_4010,
+                object::RelocationTarget::Absolute,
+                0x1_0000_2000
+            ),
+            (0x1_0000_4018, object::RelocationTarget::Symbol(free), 0),
+            (
+                0x1_0000_8010,
+                object::RelocationTarget::Absolute,
+                0x1_0000_3000
+            ),
+            // The bind to `_missing` at 0x1_0000_8018 is skipped because there is
+            // no symbol for it.
+        ]
+    );
+}
+
+// Test that the dyld info opcodes are used for imports and dynamic relocations.
+#[test]
+fn test_dyld_info() {
+    let mut builder = build::macho::Builder::new(object::Endianness::Little, true);
+    builder.header.cputype = macho::CPU_TYPE_X86_64;
+    builder.header.cpusubtype = macho::CPU_SUBTYPE_X86_64_ALL;
+    builder.header.filetype = macho::MH_DYLIB;
+    builder.header.flags = macho::MH_DYLDLINK | macho::MH_TWOLEVEL;
+
+    let section = builder.sections.add();
+    section.name = b"__text"[..].into();
+    section.segment_name = b"__TEXT"[..].into();
+    section.addr = 0x800;
+    section.size = 1;
+    section.offset = 0x800;
+    section.flags = macho::S_REGULAR | macho::S_ATTR_PURE_INSTRUCTIONS;
+    // ret
+    section.data = vec![0xc3].into();
+    let text_id = section.id();
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&0x500u64.to_le_bytes());
+    data.extend_from_slice(&0x600u64.to_le_bytes());
+    data.resize(0x1000, 0);
+
+    let mut segment_ids = Vec::new();
+    for (name, vmaddr, data) in [
+        (&b"__TEXT"[..], 0, vec![0; 0x1000]),
+        (b"__DATA", 0x1000, data),
+        (b"__LINKEDIT", 0x2000, Vec::new()),
+    ] {
+        let segment = builder.segments.add();
+        segment.name = name.into();
+        segment.vmaddr = vmaddr;
+        segment.vmsize = data.len() as u64;
+        segment.fileoff = vmaddr;
+        segment.filesize = data.len() as u64;
+        segment.maxprot = macho::VM_PROT_READ;
+        segment.initprot = macho::VM_PROT_READ;
+        segment.data = data.into();
+        segment_ids.push(segment.id());
+    }
+    builder
+        .segments
+        .get_mut(segment_ids[0])
+        .sections
+        .push(text_id);
+
+    for name in [&b"_malloc"[..], b"_free"] {
+        let symbol = builder.symbols.add();
+        symbol.name = name.into();
+        symbol.n_type = macho::N_UNDF | macho::N_EXT;
+        symbol.n_desc = 1 << 8;
+    }
+    let symbol = builder.symbols.add();
+    symbol.name = b"_weak"[..].into();
+    symbol.n_type = macho::N_SECT | macho::N_EXT;
+    symbol.n_desc = macho::N_WEAK_DEF;
+    symbol.section = Some(text_id);
+    symbol.n_value = 0x800;
+
+    let rebase = vec![
+        macho::REBASE_OPCODE_SET_TYPE_IMM | macho::REBASE_TYPE_POINTER,
+        macho::REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 1,
+        0x00,
+        macho::REBASE_OPCODE_DO_REBASE_IMM_TIMES | 2,
+        macho::REBASE_OPCODE_DONE,
+    ];
+    let mut bind = vec![
+        macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 1,
+        macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM,
+    ];
+    bind.extend_from_slice(b"_malloc\0");
+    bind.extend_from_slice(&[
+        macho::BIND_OPCODE_SET_TYPE_IMM | macho::BIND_TYPE_POINTER,
+        macho::BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 1,
+        0x10,
+        macho::BIND_OPCODE_DO_BIND,
+        macho::BIND_OPCODE_SET_ADDEND_SLEB,
+        0x08,
+        macho::BIND_OPCODE_DO_BIND,
+        macho::BIND_OPCODE_SET_DYLIB_SPECIAL_IMM
+            | (macho::BIND_SPECIAL_DYLIB_FLAT_LOOKUP as u8 & macho::BIND_IMMEDIATE_MASK),
+        macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM,
+    ]);
+    bind.extend_from_slice(b"_missing\0");
+    bind.extend_from_slice(&[
+        macho::BIND_OPCODE_SET_ADDEND_SLEB,
+        0x00,
+        macho::BIND_OPCODE_ADD_ADDR_ULEB,
+        0x10,
+        macho::BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB,
+        0x02,
+        0x08,
+        macho::BIND_OPCODE_DONE,
+    ]);
+    let mut weak_bind = vec![
+        macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM
+            | macho::BIND_SYMBOL_FLAGS_NON_WEAK_DEFINITION,
+    ];
+    weak_bind.extend_from_slice(b"_strong\0");
+    weak_bind.push(macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM);
+    weak_bind.extend_from_slice(b"_weak\0");
+    weak_bind.extend_from_slice(&[
+        macho::BIND_OPCODE_SET_TYPE_IMM | macho::BIND_TYPE_POINTER,
+        macho::BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 1,
+        0x28,
+        macho::BIND_OPCODE_DO_BIND,
+        macho::BIND_OPCODE_DONE,
+    ]);
+    // Each lazy bind is terminated separately.
+    let mut lazy_bind = Vec::new();
+    for (offset, name) in [(0x20, &b"_free\0"[..]), (0x50, b"_malloc\0")] {
+        lazy_bind.extend_from_slice(&[
+            macho::BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 1,
+            offset,
+            macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 1,
+            macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM,
+        ]);
+        lazy_bind.extend_from_slice(name);
+        lazy_bind.extend_from_slice(&[macho::BIND_OPCODE_DO_BIND, macho::BIND_OPCODE_DONE]);
+    }
+
+    builder.load_commands = segment_ids
+        .into_iter()
+        .map(build::macho::LoadCommand::Segment)
+        .collect();
+    builder.load_commands.extend([
+        build::macho::LoadCommand::Dylib(build::macho::Dylib {
+            cmd: macho::LC_ID_DYLIB,
+            name: b"@rpath/libfoo.dylib"[..].into(),
+            timestamp: 1,
+            current_version: 0x10000,
+            compatibility_version: 0x10000,
+        }),
+        build::macho::LoadCommand::Dylib(build::macho::Dylib {
+            cmd: macho::LC_LOAD_DYLIB,
+            name: b"/usr/lib/libSystem.B.dylib"[..].into(),
+            timestamp: 2,
+            current_version: 0x10000,
+            compatibility_version: 0x10000,
+        }),
+        build::macho::LoadCommand::DyldInfo(build::macho::DyldInfo {
+            cmd: macho::LC_DYLD_INFO_ONLY,
+            rebase: rebase.into(),
+            bind: bind.into(),
+            weak_bind: weak_bind.into(),
+            lazy_bind: lazy_bind.into(),
+            export: Default::default(),
+        }),
+        build::macho::LoadCommand::Symtab,
+        build::macho::LoadCommand::Dysymtab,
+    ]);
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+
+    let file = MachOFile64::<object::Endianness>::parse(&*out).unwrap();
+    let endian = file.endian();
+    let dyld_info = file.dyld_info().unwrap().unwrap();
+    let rebases = dyld_info
+        .rebases(endian, &*out, true)
+        .unwrap()
+        .map(|rebase| rebase.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rebases,
+        [
+            DyldRebase {
+                segment_index: 1,
+                segment_offset: 0,
+                typ: macho::REBASE_TYPE_POINTER,
+            },
+            DyldRebase {
+                segment_index: 1,
+                segment_offset: 8,
+                typ: macho::REBASE_TYPE_POINTER,
+            },
+        ]
+    );
+
+    fn binds(binds: DyldBindIterator<'_>) -> Vec<(u64, i32, &[u8], i64)> {
+        binds
+            .map(|bind| {
+                let bind = bind.unwrap();
+                assert_eq!(bind.segment_index, 1);
+                assert_eq!(bind.typ, macho::BIND_TYPE_POINTER);
+                (
+                    bind.segment_offset,
+                    bind.lib_ordinal,
+                    bind.name,
+                    bind.addend,
+                )
+            })
+            .collect::<Vec<_>>()
+    }
+    assert_eq!(
+        binds(dyld_info.binds(endian, &*out, true).unwrap()),
+        [
+            (0x10, 1, &b"_malloc"[..], 0),
+            (0x18, 1, b"_malloc", 8),
+            (0x30, -2, b"_missing", 0),
+            (0x40, -2, b"_missing", 0),
+        ]
+    );
+    assert_eq!(
+        binds(dyld_info.weak_binds(endian, &*out, true).unwrap()),
+        [(0x28, -3, &b"_weak"[..], 0)]
+    );
+    assert_eq!(
+        binds(dyld_info.lazy_binds(endian, &*out, true).unwrap()),
+        [(0x20, 1, &b"_free"[..], 0), (0x50, 1, b"_malloc", 0)]
+    );
+
+    let imports = file
+        .imports()
+        .unwrap()
+        .iter()
+        .map(|import| (import.name(), import.library()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            (&b"_malloc"[..], &b"/usr/lib/libSystem.B.dylib"[..]),
+            (b"_missing", b""),
+            (b"_free", b"/usr/lib/libSystem.B.dylib"),
+        ]
+    );
+
+    // The symbol table is used for imports if the bind opcodes are malformed.
+    let mut malformed = out.clone();
+    malformed[dyld_info.bind_off.get(endian) as usize] = 0xf0;
+    let malformed_file = MachOFile64::<object::Endianness>::parse(&*malformed).unwrap();
+    let imports = malformed_file
+        .imports()
+        .unwrap()
+        .iter()
+        .map(|import| (import.name(), import.library()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            (&b"_free"[..], &b"/usr/lib/libSystem.B.dylib"[..]),
+            (b"_malloc", b"/usr/lib/libSystem.B.dylib"),
+        ]
+    );
+
+    let malloc = file.symbol_by_name("_malloc").unwrap().index();
+    let free = file.symbol_by_name("_free").unwrap().index();
+    let weak = file.symbol_by_name("_weak").unwrap().index();
+    let relocations = file
+        .dynamic_relocations()
+        .unwrap()
+        .map(|(offset, relocation)| {
+            assert_eq!(relocation.kind(), object::RelocationKind::Absolute);
+            assert_eq!(relocation.size(), 64);
+            (offset, relocation.target(), relocation.addend())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        relocations,
+        [
+            (0x1000, object::RelocationTarget::Absolute, 0x500),
+            (0x1008, object::RelocationTarget::Absolute, 0x600),
+            (0x1010, object::RelocationTarget::Symbol(malloc), 0),
+            (0x1018, object::RelocationTarget::Symbol(malloc), 8),
+            (0x1028, object::RelocationTarget::Symbol(weak), 0),
+            (0x1020, object::RelocationTarget::Symbol(free), 0),
+            (0x1050, object::RelocationTarget::Symbol(malloc), 0),
+        ]
+    );
+}
+
+// Test that an embedded code signature can be parsed and its code slots checked.
+#[test]
+#[cfg(feature = "codesign")]
+fn test_code_signature() {
+    let mut builder = dylib();
+    builder
+        .load_commands
+        .push(build::macho::LoadCommand::LinkeditData {
+            cmd: macho::LC_CODE_SIGNATURE,
+            data: vec![0; 0x1000].into(),
+        });
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+
+    let file = MachOFile64::<object::Endianness>::parse(&*out).unwrap();
+    let endian = file.endian();
+    let mut dataoff = 0;
+    let mut commands = file.macho_header().load_commands(endian, &*out, 0).unwrap();
+    while let Some(command) = commands.next().unwrap() {
+        if let LoadCommandVariant::LinkeditData(linkedit) = command.variant().unwrap() {
+            if linkedit.cmd.get(endian) == macho::LC_CODE_SIGNATURE {
+                dataoff = linkedit.dataoff.get(endian) as usize;
+            }
+        }
+    }
+    assert_ne!(dataoff, 0);
+
+    // Build an ad hoc signature with a SHA-256 CodeDirectory, empty requirements
+    // and entitlements. The code slots are filled in after parsing.
+    fn blob(magic: u32, data: &[u8]) -> Vec<u8> {
+        let mut blob = Vec::new();
+        blob.extend_from_slice(&magic.to_be_bytes());
+        blob.extend_from_slice(&(8 + data.len() as u32).to_be_bytes());
+        blob.extend_from_slice(data);
+        blob
+    }
+    let requirements = blob(macho::CSMAGIC_REQUIREMENTS, &0u32.to_be_bytes());
+    let entitlements = blob(
+        macho::CSMAGIC_EMBEDDED_ENTITLEMENTS,
+        b"<plist><dict></dict></plist>",
+    );
+
+    let n_code_slots = (dataoff + 0xfff) / 0x1000;
+    let hash_offset = 88 + 16 + 8 + 5 * 32;
+    let mut directory = Vec::new();
+    for value in [
+        macho::CS_SUPPORTSEXECSEG,
+        macho::CS_ADHOC,
+        hash_offset as u32,
+        88,
+        5,
+        n_code_slots as u32,
+        dataoff as u32,
+    ] {
+        directory.extend_from_slice(&value.to_be_bytes());
+    }
+    directory.extend_from_slice(&[32, macho::CS_HASHTYPE_SHA256, 0, 12]);
+    // spare2, scatter_offset, team_offset, spare3
+    for value in [0u32, 0, 88 + 16, 0] {
+        directory.extend_from_slice(&value.to_be_bytes());
+    }
+    // code_limit64, exec_seg_base, exec_seg_limit, exec_seg_flags
+    for value in [0u64, 0, 0x4000, 0] {
+        directory.extend_from_slice(&value.to_be_bytes());
+    }
+    directory.extend_from_slice(b"com.example.foo\0TEAMID\0\0");
+    // The blob header is added later.
+    directory.resize(hash_offset - 8 + n_code_slots * 32, 0);
+    let mut directory = blob(macho::CSMAGIC_CODEDIRECTORY, &directory);
+
+    let write_signature = |out: &mut Vec<u8>, directory: &[u8]| {
+        let blobs = [
+            (macho::CSSLOT_CODEDIRECTORY, directory),
+            (macho::CSSLOT_REQUIREMENTS, &requirements),
+            (macho::CSSLOT_ENTITLEMENTS, &entitlements),
+        ];
+        let mut signature = Vec::new();
+        let mut offset = 12 + 8 * blobs.len();
+        for (slot, data) in blobs {
+            signature.extend_from_slice(&slot.to_be_bytes());
+            signature.extend_from_slice(&(offset as u32).to_be_bytes());
+            offset += data.len();
+        }
+        for (_, data) in blobs {
+            signature.extend_from_slice(data);
+        }
+        let signature = blob(macho::CSMAGIC_EMBEDDED_SIGNATURE, &{
+            let mut data = (blobs.len() as u32).to_be_bytes().to_vec();
+            data.extend_from_slice(&signature);
+            data
+        });
+        out[dataoff..][..signature.len()].copy_from_slice(&signature);
+    };
+    write_signature(&mut out, &directory);
+
+    let file = MachOFile64::<object::Endianness>::parse(&*out).unwrap();
+    let signature = file.code_signature().unwrap().unwrap();
+    let blobs = signature
+        .blobs()
+        .map(|blob| {
+            let blob = blob.unwrap();
+            (blob.typ, blob.magic)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        blobs,
+        [
+            (macho::CSSLOT_CODEDIRECTORY, macho::CSMAGIC_CODEDIRECTORY),
+            (macho::CSSLOT_REQUIREMENTS, macho::CSMAGIC_REQUIREMENTS),
+            (
+                macho::CSSLOT_ENTITLEMENTS,
+                macho::CSMAGIC_EMBEDDED_ENTITLEMENTS
+            ),
+        ]
+    );
+    assert_eq!(signature.requirements().unwrap().unwrap().count(), 0);
+    assert_eq!(
+        signature.entitlements().unwrap(),
+        Some(&b"<plist><dict></dict></plist>"[..])
+    );
+    assert_eq!(signature.der_entitlements().unwrap(), None);
+    assert_eq!(signature.cms_signature().unwrap(), None);
+
+    let cd = signature.code_directory().unwrap().unwrap();
+    assert_eq!(cd.version(), macho::CS_SUPPORTSEXECSEG);
+    // Fields from later versions are zero, even though the identifier follows the header.
+    assert_eq!(cd.header().runtime.get(object::BigEndian), 0);
+    assert_eq!(cd.header().pre_encrypt_offset.get(object::BigEndian), 0);
+    assert_eq!(cd.flags(), macho::CS_ADHOC);
+    assert_eq!(cd.hash_type(), macho::CS_HASHTYPE_SHA256);
+    assert_eq!(cd.hash_size(), 32);
+    assert_eq!(cd.page_size(), 0x1000);
+    assert_eq!(cd.code_limit(), dataoff as u64);
+    assert_eq!(cd.n_code_slots() as usize, n_code_slots);
+    assert_eq!(cd.n_special_slots(), 5);
+    assert_eq!(cd.identifier().unwrap(), b"com.example.foo");
+    assert_eq!(cd.team_id().unwrap(), Some(&b"TEAMID"[..]));
+    assert_eq!(cd.header().exec_seg_limit.get(object::BigEndian), 0x4000);
+    assert_eq!(
+        cd.hash(b"abc").unwrap(),
+        [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+            0xf2, 0x00, 0x15, 0xad
+        ]
+    );
+
+    // Fill in the code slots and the special slots.
+    let mut hashes = Vec::new();
+    for (slot, data) in [
+        (macho::CSSLOT_ENTITLEMENTS, &entitlements),
+        (macho::CSSLOT_REQUIREMENTS, &requirements),
+    ] {
+        let offset = hash_offset - slot as usize * 32;
+        hashes.push((offset, cd.hash(data).unwrap()));
+    }
+    let pages = cd.code_pages(&*out, 0).unwrap().collect::<Vec<_>>();
+    assert_eq!(pages.len(), n_code_slots);
+    assert_eq!(
+        pages.last().unwrap().data.len(),
+        dataoff - (n_code_slots - 1) * 0x1000
+    );
+    for (i, page) in pages.iter().enumerate() {
+        assert_eq!(page.hash, [0; 32]);
+        hashes.push((hash_offset + i * 32, cd.hash(page.data).unwrap()));
+    }
+    for (offset, hash) in hashes {
+        directory[offset..][..32].copy_from_slice(&hash);
+    }
+    write_signature(&mut out, &directory);
+
+    let file = MachOFile64::<object::Endianness>::parse(&*out).unwrap();
+    let signature = file.code_signature().unwrap().unwrap();
+    let cd = signature.code_directory().unwrap().unwrap();
+    for page in cd.code_pages(&*out, 0).unwrap() {
+        assert_eq!(cd.hash(page.data).unwrap(), page.hash);
+    }
+    let entitlements_blob = signature.blob(macho::CSSLOT_ENTITLEMENTS).unwrap().unwrap();
+    assert_eq!(
+        cd.special_slot(macho::CSSLOT_ENTITLEMENTS).unwrap(),
+        Some(&*cd.hash(entitlements_blob.data).unwrap())
+    );
+    assert_eq!(
+        cd.special_slot(macho::CSSLOT_INFOSLOT).unwrap(),
+        Some(&[0; 32][..])
+    );
+    assert_eq!(cd.special_slot(6).unwrap(), None);
+    assert_eq!(
+        cd.cdhash().unwrap(),
+        cd.hash(cd.data()).unwrap()[..macho::CS_CDHASH_LEN]
+    );
+
+    // Modifying the code must invalidate the page hash.
+    let mut modified = out.clone();
+    modified[0x1000] ^= 0xff;
+    let page = cd.code_pages(&*modified, 0).unwrap().nth(1).unwrap();
+    assert_ne!(cd.hash(page.data).unwrap(), page.hash);
+}
+
+// Test that an ad hoc signature is generated when writing.
+#[test]
+#[cfg(feature = "codesign")]
+fn test_adhoc_signature() {
+    fn check_signature(data: &[u8], identifier: &[u8]) {
+        let file = MachOFile64::<object::Endianness>::parse(data).unwrap();
+        let endian = file.endian();
+        let mut signatures = Vec::new();
+        let mut commands = file.macho_header().load_commands(endian, data, 0).unwrap();
+        while let Some(command) = commands.next().unwrap() {
+            if let LoadCommandVariant::LinkeditData(linkedit) = command.variant().unwrap() {
+                if linkedit.cmd.get(endian) == macho::LC_CODE_SIGNATURE {
+                    signatures.push((
+                        linkedit.dataoff.get(endian) as usize,
+                        linkedit.datasize.get(endian) as usize,
+                    ));
+                }
+            }
+        }
+        assert_eq!(signatures.len(), 1);
+        let (dataoff, datasize) = signatures[0];
+        assert_eq!(dataoff % 16, 0);
+
+        // The signature must be the last data in `__LINKEDIT`.
+        let linkedit = file
+            .segments()
+            .find(|segment| segment.name() == Ok(Some("__LINKEDIT")))
+            .unwrap();
+        let (fileoff, filesize) = linkedit.file_range();
+        assert!(fileoff < dataoff as u64);
+        assert!(dataoff + datasize <= (fileoff + filesize) as usize);
+        assert!((fileoff + filesize) as usize - (dataoff + datasize) < 16);
+        assert_eq!(data.len() as u64, fileoff + filesize);
+
+        let signature = file.code_signature().unwrap().unwrap();
+        let blobs = signature
+            .blobs()
+            .map(|blob| {
+                let blob = blob.unwrap();
+                (blob.typ, blob.magic)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            blobs,
+            [
+                (macho::CSSLOT_CODEDIRECTORY, macho::CSMAGIC_CODEDIRECTORY),
+                (macho::CSSLOT_REQUIREMENTS, macho::CSMAGIC_REQUIREMENTS),
+            ]
+        );
+        assert_eq!(signature.data().len(), datasize);
+        assert_eq!(signature.requirements().unwrap().unwrap().count(), 0);
+        assert_eq!(signature.cms_signature().unwrap(), None);
+
+        let cd = signature.code_directory().unwrap().unwrap();
+        assert_eq!(cd.version(), macho::CS_SUPPORTSEXECSEG);
+        assert_eq!(cd.flags(), macho::CS_ADHOC);
+        assert_eq!(cd.hash_type(), macho::CS_HASHTYPE_SHA256);
+        assert_eq!(cd.hash_size(), 32);
+        assert_eq!(cd.page_size(), 0x1000);
+        assert_eq!(cd.code_limit(), dataoff as u64);
+        assert_eq!(cd.n_code_slots() as usize, (dataoff + 0xfff) / 0x1000);
+        assert_eq!(cd.identifier().unwrap(), identifier);
+        assert_eq!(cd.team_id().unwrap(), None);
+        assert_eq!(cd.header().exec_seg_base.get(object::BigEndian), 0);
+        assert_eq!(cd.header().exec_seg_limit.get(object::BigEndian), 0x4000);
+        assert_eq!(cd.header().exec_seg_flags.get(object::BigEndian), 0);
+
+        let requirements = signature.blob(macho::CSSLOT_REQUIREMENTS).unwrap().unwrap();
+        assert_eq!(
+            cd.special_slot(macho::CSSLOT_REQUIREMENTS).unwrap(),
+            Some(&*cd.hash(requirements.data).unwrap())
+        );
+        assert_eq!(
+            cd.special_slot(macho::CSSLOT_INFOSLOT).unwrap(),
+            Some(&[0; 32][..])
+        );
+        assert_eq!(cd.special_slot(macho::CSSLOT_ENTITLEMENTS).unwrap(), None);
+
+        let mut size = 0;
+        for page in cd.code_pages(data, 0).unwrap() {
+            assert_eq!(cd.hash(page.data).unwrap(), page.hash);
+            size += page.data.len();
+        }
+        assert_eq!(size, dataoff);
+    }
+
+    let mut builder = dylib();
+    builder.set_adhoc_signature(b"libfoo"[..].into());
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+    check_dylib(&out, b"@rpath/libfoo.dylib");
+    check_signature(&out, b"libfoo");
+
+    // Modify the image, and replace the existing signature.
+    let mut builder = build::macho::Builder::read(&*out).unwrap();
+    for command in &mut builder.load_commands {
+        if let build::macho::LoadCommand::Dylib(dylib) = command {
+            if dylib.cmd == macho::LC_ID_DYLIB {
+                dylib.name = b"@rpath/libbar.dylib"[..].into();
+            }
+        }
+    }
+    builder.set_adhoc_signature(b"libbar"[..].into());
+    let mut out2 = Vec::new();
+    builder.write(&mut out2).unwrap();
+    check_dylib(&out2, b"@rpath/libbar.dylib");
+    check_signature(&out2, b"libbar");
+    // The header has changed, so the first page hash must differ.
+    assert_ne!(out[..0x1000], out2[..0x1000]);
+
+    // Signing an image with an existing signature that is not modified
+    // must produce the same output.
+    let mut builder = build::macho::Builder::read(&*out2).unwrap();
+    builder.set_adhoc_signature(b"libbar"[..].into());
+    let mut out3 = Vec::new();
+    builder.write(&mut out3).unwrap();
+    assert_eq!(out2, out3);
+}
+
+// Test that writing an ad hoc signature fails if hashing is not supported.
+#[test]
+#[cfg(not(feature = "codesign"))]
+fn test_adhoc_signature_unsupported() {
+    let mut builder = dylib();
+    builder.set_adhoc_signature(b"libfoo"[..].into());
+    let mut out = Vec::new();
+    assert!(builder.write(&mut out).is_err());
+}
+
+// Test that a linked image can be created from scratch.
+#[test]
+fn test_layout_image() {
+    let mut builder = build::macho::Builder::new(object::Endianness::Little, true);
+    builder.header.cputype = macho::CPU_TYPE_ARM64;
+    builder.header.cpusubtype = macho::CPU_SUBTYPE_ARM64_ALL;
+    builder.header.filetype = macho::MH_DYLIB;
+    builder.header.flags = macho::MH_NOUNDEFS | macho::MH_DYLDLINK | macho::MH_TWOLEVEL;
+
+    let section = builder.sections.add();
+    section.name = b"__text"[..].into();
+    section.segment_name = b"__TEXT"[..].into();
+    section.align = 2;
+    section.flags = macho::S_REGULAR | macho::S_ATTR_PURE_INSTRUCTIONS;
+    // ret; ret
+    section.data = vec![0xc0, 0x03, 0x5f, 0xd6, 0xc0, 0x03, 0x5f, 0xd6].into();
+    let text_id = section.id();
+    let section = builder.sections.add();
+    section.name = b"__data"[..].into();
+    section.segment_name = b"__DATA"[..].into();
+    section.align = 3;
+    section.data = vec![1; 12].into();
+    let data_id = section.id();
+    let section = builder.sections.add();
+    section.name = b"__bss"[..].into();
+    section.segment_name = b"__DATA"[..].into();
+    section.align = 4;
+    section.size = 0x20;
+    section.flags = macho::S_ZEROFILL;
+    let bss_id = section.id();
+
+    let segment = builder.segments.add();
+    segment.name = b"__TEXT"[..].into();
+    segment.maxprot = macho::VM_PROT_READ | macho::VM_PROT_EXECUTE;
+    segment.initprot = macho::VM_PROT_READ | macho::VM_PROT_EXECUTE;
+    segment.sections.push(text_id);
+    let text_segment_id = segment.id();
+    let segment = builder.segments.add();
+    segment.name = b"__DATA"[..].into();
+    segment.maxprot = macho::VM_PROT_READ | macho::VM_PROT_WRITE;
+    segment.initprot = macho::VM_PROT_READ | macho::VM_PROT_WRITE;
+    segment.sections.push(data_id);
+    segment.sections.push(bss_id);
+    let data_segment_id = segment.id();
+    let segment = builder.segments.add();
+    segment.name = b"__LINKEDIT"[..].into();
+    segment.maxprot = macho::VM_PROT_READ;
+    segment.initprot = macho::VM_PROT_READ;
+    let linkedit_segment_id = segment.id();
+
+    builder.load_commands = vec![
+        build::macho::LoadCommand::Segment(text_segment_id),
+        build::macho::LoadCommand::Segment(data_segment_id),
+        build::macho::LoadCommand::Segment(linkedit_segment_id),
+        build::macho::LoadCommand::Dylib(build::macho::Dylib {
+            cmd: macho::LC_ID_DYLIB,
+            name: b"@rpath/libfoo.dylib"[..].into(),
+            timestamp: 1,
+            current_version: 0x10000,
+            compatibility_version: 0x10000,
+        }),
+        build::macho::LoadCommand::Dylib(build::macho::Dylib {
+            cmd: macho::LC_LOAD_DYLIB,
+            name: b"/usr/lib/libSystem.B.dylib"[..].into(),
+            timestamp: 2,
+            current_version: 0x10000,
+            compatibility_version: 0x10000,
+        }),
+        build::macho::LoadCommand::LinkeditData {
+            cmd: macho::LC_DYLD_EXPORTS_TRIE,
+            data: Default::default(),
+        },
+        build::macho::LoadCommand::Symtab,
+        build::macho::LoadCommand::Dysymtab,
+    ];
+    builder.layout_image().unwrap();
+
+    let text_addr = builder.sections.get(text_id).addr;
+    let data_addr = builder.sections.get(data_id).addr;
+    let bss_addr = builder.sections.get(bss_id).addr;
+    assert_eq!(text_addr % 4, 0);
+    assert_eq!(data_addr, 0x4000);
+    assert_eq!(bss_addr, 0x4010);
+    let symbols = [
+        (
+            &b"_foo"[..],
+            macho::N_SECT | macho::N_EXT,
+            Some(text_id),
+            0,
+            text_addr,
+        ),
+        (
+            b"_foobar",
+            macho::N_SECT | macho::N_EXT,
+            Some(text_id),
+            macho::N_WEAK_DEF,
+            text_addr + 4,
+        ),
+        (
+            b"_fo",
+            macho::N_SECT | macho::N_EXT,
+            Some(data_id),
+            0,
+            data_addr,
+        ),
+        (
+            b"_bss",
+            macho::N_SECT | macho::N_EXT,
+            Some(bss_id),
+            0,
+            bss_addr,
+        ),
+        (b"_abs", macho::N_ABS | macho::N_EXT, None, 0, 0x1234),
+        (
+            b"_hidden",
+            macho::N_SECT | macho::N_EXT | macho::N_PEXT,
+            Some(text_id),
+            0,
+            text_addr,
+        ),
+        (b"_local", macho::N_SECT, Some(text_id), 0, text_addr),
+        (b"_bar", macho::N_UNDF | macho::N_EXT, None, 1 << 8, 0),
+    ];
+    for (name, n_type, section, n_desc, n_value) in symbols {
+        let symbol = builder.symbols.add();
+        symbol.name = name.into();
+        symbol.n_type = n_type;
+        symbol.section = section;
+        symbol.n_desc = n_desc;
+        symbol.n_value = n_value;
+    }
+
+    let mut exports = builder.exports();
+    exports.push(build::macho::Export {
+        name: b"_baz"[..].into(),
+        flags: 0,
+        data: build::macho::ExportData::Reexport {
+            dylib_ordinal: 1,
+            import_name: b"_bar"[..].into(),
+        },
+    });
+    exports.push(build::macho::Export {
+        name: b"_resolved"[..].into(),
+        flags: 0,
+        data: build::macho::ExportData::StubAndResolver {
+            stub_address: text_addr,
+            resolver_address: text_addr + 4,
+        },
+    });
+    let trie = build::macho::write_exports_trie(&exports).unwrap();
+    for command in &mut builder.load_commands {
+        if let build::macho::LoadCommand::LinkeditData { cmd, data } = command {
+            if *cmd == macho::LC_DYLD_EXPORTS_TRIE {
+                *data = trie.clone().into();
+            }
+        }
+    }
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+
+    let file = MachOFile64::<object::Endianness>::parse(&*out).unwrap();
+    let endian = file.endian();
+    let text = file.section_by_name("__text").unwrap();
+    assert_eq!(text.address(), text_addr);
+    assert_eq!(text.file_range(), Some((text_addr, 8)));
+    assert_eq!(
+        text.data().unwrap(),
+        [0xc0, 0x03, 0x5f, 0xd6, 0xc0, 0x03, 0x5f, 0xd6]
+    );
+    let data = file.section_by_name("__data").unwrap();
+    assert_eq!(data.file_range(), Some((0x4000, 12)));
+    assert_eq!(data.data().unwrap(), [1; 12]);
+    let bss = file.section_by_name("__bss").unwrap();
+    assert_eq!(bss.address(), 0x4010);
+    assert_eq!(bss.size(), 0x20);
+    let segments = file
+        .segments()
+        .map(|segment| {
+            (
+                segment.name().unwrap().unwrap().to_string(),
+                segment.address(),
+                segment.size(),
+                segment.file_range(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let segments = segments
+        .iter()
+        .map(|(name, address, size, range)| (name.as_str(), *address, *size, *range))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        segments,
+        [
+            ("__TEXT", 0, 0x4000, (0, 0x4000)),
+            ("__DATA", 0x4000, 0x4000, (0x4000, 0x4000)),
+            (
+                "__LINKEDIT",
+                0x8000,
+                0x4000,
+                (0x8000, out.len() as u64 - 0x8000)
+            ),
+        ]
+    );
+
+    let exported = file
+        .exports()
+        .unwrap()
+        .iter()
+        .map(|export| (export.name().to_vec(), export.address()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exported,
+        [
+            (b"_abs".to_vec(), 0x1234),
+            (b"_bss".to_vec(), 0x4010),
+            (b"_fo".to_vec(), 0x4000),
+            (b"_foo".to_vec(), text_addr),
+            (b"_foobar".to_vec(), text_addr + 4),
+            (b"_hidden".to_vec(), text_addr),
+        ]
+    );
+    let imports = file.imports().unwrap();
+    assert_eq!(imports.len(), 1);
+    assert_eq!(imports[0].name(), b"_bar");
+    assert_eq!(imports[0].library(), b"/usr/lib/libSystem.B.dylib");
+
+    let mut trie_exports = Vec::new();
+    let mut commands = file.macho_header().load_commands(endian, &*out, 0).unwrap();
+    while let Some(command) = commands.next().unwrap() {
+        if let LoadCommandVariant::LinkeditData(linkedit) = command.variant().unwrap() {
+            if linkedit.cmd.get(endian) == macho::LC_DYLD_EXPORTS_TRIE {
+                let mut trie = linkedit.exports_trie(endian, &*out).unwrap();
+                while let Some(export) = trie.next().unwrap() {
+                    let data = match *export.data() {
+                        ExportData::Regular { address } => format!("{:#x}", address),
+                        ExportData::Reexport {
+                            dylib_ordinal,
+                            import_name,
+                        } => format!("{}:{}", dylib_ordinal, String::from_utf8_lossy(import_name)),
+                        ExportData::StubAndResolver {
+                            stub_address,
+                            resolver_address,
+                        } => format!("{:#x}/{:#x}", stub_address, resolver_address),
+                    };
+                    trie_exports.push((
+                        String::from_utf8(export.name().to_vec()).unwrap(),
+                        export.flags(),
+                        data,
+                    ));
+                }
+            }
+        }
+    }
+    trie_exports.sort();
+    let text = format!("{:#x}", text_addr);
+    let text4 = format!("{:#x}", text_addr + 4);
+    assert_eq!(
+        trie_exports,
+        [
+            (
+                "_abs".into(),
+                macho::EXPORT_SYMBOL_FLAGS_KIND_ABSOLUTE,
+                "0x1234".into()
+            ),
+            (
+                "_baz".into(),
+                macho::EXPORT_SYMBOL_FLAGS_REEXPORT,
+                "1:_bar".into()
+            ),
+            ("_bss".into(), 0, "0x4010".into()),
+            ("_fo".into(), 0, "0x4000".into()),
+            ("_foo".into(), 0, text.clone()),
+            (
+                "_foobar".into(),
+                macho::EXPORT_SYMBOL_FLAGS_WEAK_DEFINITION,
+                text4.clone()
+            ),
+            (
+                "_resolved".into(),
+                macho::EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER,
+                format!("{}/{}", text, text4)
+            ),
+        ]
+    );
+}
+
+// Test that an executable with a `__PAGEZERO` segment can be created from scratch.
+#[test]
+#[cfg(feature = "codesign")]
+fn test_layout_executable() {
+    let mut builder = build::macho::Builder::new(object::Endianness::Little, true);
+    builder.header.cputype = macho::CPU_TYPE_X86_64;
+    builder.header.cpusubtype = macho::CPU_SUBTYPE_X86_64_ALL;
+    builder.header.filetype = macho::MH_EXECUTE;
+    builder.header.flags =
+        macho::MH_NOUNDEFS | macho::MH_DYLDLINK | macho::MH_TWOLEVEL | macho::MH_PIE;
+
+    let section = builder.sections.add();
+    section.name = b"__text"[..].into();
+    section.segment_name = b"__TEXT"[..].into();
+    section.flags = macho::S_REGULAR | macho::S_ATTR_PURE_INSTRUCTIONS;
+    // xor eax, eax; ret
+    section.data = vec![0x31, 0xc0, 0xc3].into();
+    let text_id = section.id();
+
+    let segment = builder.segments.add();
+    segment.name = b"__PAGEZERO"[..].into();
+    segment.vmsize = 0x1_0000_0000;
+    let pagezero_segment_id = segment.id();
+    let segment = builder.segments.add();
+    segment.name = b"__TEXT"[..].into();
+    segment.maxprot = macho::VM_PROT_READ | macho::VM_PROT_EXECUTE;
+    segment.initprot = macho::VM_PROT_READ | macho::VM_PROT_EXECUTE;
+    segment.sections.push(text_id);
+    let text_segment_id = segment.id();
+    let segment = builder.segments.add();
+    segment.name = b"__LINKEDIT"[..].into();
+    segment.maxprot = macho::VM_PROT_READ;
+    segment.initprot = macho::VM_PROT_READ;
+    let linkedit_segment_id = segment.id();
+
+    let symbol = builder.symbols.add();
+    symbol.name = b"_main"[..].into();
+    symbol.n_type = macho::N_SECT | macho::N_EXT;
+    symbol.section = Some(text_id);
+    let main_id = symbol.id();
+
+    builder.load_commands = vec![
+        build::macho::LoadCommand::Segment(pagezero_segment_id),
+        build::macho::LoadCommand::Segment(text_segment_id),
+        build::macho::LoadCommand::Segment(linkedit_segment_id),
+        build::macho::LoadCommand::DyldInfo(build::macho::DyldInfo {
+            cmd: macho::LC_DYLD_INFO_ONLY,
+            rebase: Default::default(),
+            bind: Default::default(),
+            weak_bind: Default::default(),
+            lazy_bind: Default::default(),
+            export: Default::default(),
+        }),
+        build::macho::LoadCommand::Dylib(build::macho::Dylib {
+            cmd: macho::LC_LOAD_DYLIB,
+            name: b"/usr/lib/libSystem.B.dylib"[..].into(),
+            timestamp: 2,
+            current_version: 0x10000,
+            compatibility_version: 0x10000,
+        }),
+        build::macho::LoadCommand::Symtab,
+        build::macho::LoadCommand::Dysymtab,
+    ];
+    builder.set_adhoc_signature(b"main"[..].into());
+    builder.layout_image().unwrap();
+
+    let text_addr = builder.sections.get(text_id).addr;
+    builder.symbols.get_mut(main_id).n_value = text_addr;
+    let trie = build::macho::write_exports_trie(&builder.exports()).unwrap();
+    for command in &mut builder.load_commands {
+        if let build::macho::LoadCommand::DyldInfo(dyld_info) = command {
+            dyld_info.export = trie.clone().into();
+        }
+    }
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+
+    let file = MachOFile64::<object::Endianness>::parse(&*out).unwrap();
+    assert_eq!(file.kind(), object::ObjectKind::Executable);
+    let segments = file
+        .segments()
+        .map(|segment| {
+            (
+                segment.name().unwrap().unwrap().to_string(),
+                segment.address(),
+                segment.size(),
+                segment.file_range(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let segments = segments
+        .iter()
+        .map(|(name, address, size, range)| (name.as_str(), *address, *size, *range))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        segments,
+        [
+            ("__PAGEZERO", 0, 0x1_0000_0000, (0, 0)),
+            ("__TEXT", 0x1_0000_0000, 0x1000, (0, 0x1000)),
+            (
+                "__LINKEDIT",
+                0x1_0000_1000,
+                0x1000,
+                (0x1000, out.len() as u64 - 0x1000)
+            ),
+        ]
+    );
+    let text = file.section_by_name("__text").unwrap();
+    assert_eq!(text.address(), text_addr);
+    assert_eq!(text.file_range(), Some((text_addr - 0x1_0000_0000, 3)));
+    assert_eq!(text.data().unwrap(), [0x31, 0xc0, 0xc3]);
+    let symbol = file.symbol_by_name("_main").unwrap();
+    assert_eq!(symbol.address(), text_addr);
+
+    let endian = file.endian();
+    let mut commands = file.macho_header().load_commands(endian, &*out, 0).unwrap();
+    let mut export = None;
+    while let Some(command) = commands.next().unwrap() {
+        if let Some(dyld_info) = command.dyld_info().unwrap() {
+            let offset = dyld_info.export_off.get(endian) as usize;
+            let size = dyld_info.export_size.get(endian) as usize;
+            export = Some(out[offset..][..size].to_vec());
+        }
+    }
+    assert_eq!(export.as_deref(), Some(&trie[..]));
+    // `_main` at offset `text_addr - base`.
+    let mut expect = vec![0x00, 0x01, b'_', b'm', b'a', b'i', b'n', 0x00, 0x09];
+    let mut terminal = vec![0x00];
+    let mut value = text_addr - 0x1_0000_0000;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            terminal.push(byte);
+            break;
+        }
+        terminal.push(byte | 0x80);
+    }
+    expect.push(terminal.len() as u8);
+    expect.extend(terminal);
+    expect.push(0x00);
+    assert_eq!(trie, expect);
+
+    let signature = file.code_signature().unwrap().unwrap();
+    let cd = signature.code_directory().unwrap().unwrap();
+    assert_eq!(cd.identifier().unwrap(), b"main");
+    assert_eq!(
+        cd.header().exec_seg_flags.get(object::BigEndian),
+        macho::CS_EXECSEG_MAIN_BINARY
+    );
+    assert_eq!(cd.header().exec_seg_limit.get(object::BigEndian), 0x1000);
+    for page in cd.code_pages(&*out, 0).unwrap() {
+        assert_eq!(cd.hash(page.data).unwrap(), page.hash);
+    }
+}
@@ -1,3 +1,6 @@
 #![cfg(feature = "build")]
 
+mod coff;
 mod elf;
+mod macho;
+mod pe;
@@ -0,0 +1,275 @@
+use object::pe;
+use object::read::pe::PeFile64;
+use object::read::{Object, ObjectSection};
+use object::{build, pod, write, LittleEndian as LE, U16, U32};
+
+// Offsets within .rdata.
+const IMPORT_LOOKUP: u32 = 0x28;
+const IMPORT_ADDRESS: u32 = 0x38;
+const HINT_NAME: u32 = 0x48;
+const DLL_NAME: u32 = 0x58;
+const DEBUG_DIRECTORY: u32 = 0x70;
+const CODEVIEW: u32 = 0x90;
+const CODEVIEW_DATA: &[u8] =
+    b"RSDS\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10\x01\0\0\0test.pdb\0";
+
+/// Create a PE image containing an import table, a debug directory,
+/// base relocations and a certificate table.
+fn image(relocations: bool) -> Vec<u8> {
+    let rdata = |address: u32, offset: u32| {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(address + IMPORT_LOOKUP).to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&(address + DLL_NAME).to_le_bytes());
+        data.extend_from_slice(&(address + IMPORT_ADDRESS).to_le_bytes());
+        data.resize(IMPORT_LOOKUP as usize, 0);
+        for _ in 0..2 {
+            data.extend_from_slice(&u64::from(address + HINT_NAME).to_le_bytes());
+            data.extend_from_slice(&[0; 8]);
+        }
+        data.extend_from_slice(b"\0\0ExitProcess\0");
+        data.resize(DLL_NAME as usize, 0);
+        data.extend_from_slice(b"KERNEL32.dll\0");
+        data.resize(DEBUG_DIRECTORY as usize, 0);
+        data.extend_from_slice(pod::bytes_of(&pe::ImageDebugDirectory {
+            characteristics: U32::new(LE, 0),
+            time_date_stamp: U32::new(LE, 0),
+            major_version: U16::new(LE, 0),
+            minor_version: U16::new(LE, 0),
+            typ: U32::new(LE, pe::IMAGE_DEBUG_TYPE_CODEVIEW),
+            size_of_data: U32::new(LE, CODEVIEW_DATA.len() as u32),
+            address_of_raw_data: U32::new(LE, address + CODEVIEW),
+            pointer_to_raw_data: U32::new(LE, offset + CODEVIEW),
+        }));
+        data.resize(CODEVIEW as usize, 0);
+        data.extend_from_slice(CODEVIEW_DATA);
+        data
+    };
+    let text_data = [0x48, 0x8b, 0x05, 0, 0, 0, 0, 0xc3];
+    let certificate_table = b"\x10\0\0\0\0\x02\x02\0signatur";
+
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(2 + u16::from(relocations));
+    let text = writer.reserve_text_section(text_data.len() as u32);
+    let rdata_range = writer.reserve_rdata_section(rdata(0, 0).len() as u32);
+    let rdata_data = rdata(rdata_range.virtual_address, rdata_range.file_offset);
+    if relocations {
+        writer.add_reloc(text.virtual_address, pe::IMAGE_REL_BASED_DIR64);
+        writer.add_reloc(
+            rdata_range.virtual_address + 0x28,
+            pe::IMAGE_REL_BASED_DIR64,
+        );
+        writer.reserve_reloc_section();
+    }
+    writer.set_data_directory(
+        pe::IMAGE_DIRECTORY_ENTRY_IMPORT,
+        rdata_range.virtual_address,
+        2 * 20,
+    );
+    writer.set_data_directory(
+        pe::IMAGE_DIRECTORY_ENTRY_IAT,
+        rdata_range.virtual_address + IMPORT_ADDRESS,
+        16,
+    );
+    writer.set_data_directory(
+        pe::IMAGE_DIRECTORY_ENTRY_DEBUG,
+        rdata_range.virtual_address + DEBUG_DIRECTORY,
+        28,
+    );
+    writer.reserve_certificate_table(certificate_table.len() as u32);
+
+    writer.write_dos_header_and_stub().unwrap();
+    writer.write_nt_headers(write::pe::NtHeaders {
+        machine: pe::IMAGE_FILE_MACHINE_AMD64,
+        time_date_stamp: 0,
+        characteristics: pe::IMAGE_FILE_EXECUTABLE_IMAGE | pe::IMAGE_FILE_LARGE_ADDRESS_AWARE,
+        major_linker_version: 14,
+        minor_linker_version: 0,
+        address_of_entry_point: text.virtual_address,
+        image_base: 0x1_4000_0000,
+        major_operating_system_version: 6,
+        minor_operating_system_version: 0,
+        major_image_version: 0,
+        minor_image_version: 0,
+        major_subsystem_version: 6,
+        minor_subsystem_version: 0,
+        subsystem: pe::IMAGE_SUBSYSTEM_WINDOWS_CUI,
+        dll_characteristics: pe::IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE,
+        size_of_stack_reserve: 0x10_0000,
+        size_of_stack_commit: 0x1000,
+        size_of_heap_reserve: 0x10_0000,
+        size_of_heap_commit: 0x1000,
+    });
+    writer.write_section_headers();
+    writer.write_section(text.file_offset, &text_data);
+    writer.write_section(rdata_range.file_offset, &rdata_data);
+    if relocations {
+        writer.write_reloc_section();
+    }
+    writer.write_certificate_table(certificate_table);
+    data
+}
+
+fn section_names(file: &PeFile64) -> Vec<String> {
+    file.sections()
+        .map(|section| section.name().unwrap().to_string())
+        .collect()
+}
+
+fn base_relocations(file: &PeFile64, data: &[u8]) -> Vec<(u32, u16)> {
+    let mut relocations = Vec::new();
+    if let Some(blocks) = file
+        .data_directories()
+        .relocation_blocks(data, &file.section_table())
+        .unwrap()
+    {
+        for block in blocks {
+            for relocation in block.unwrap() {
+                relocations.push((relocation.virtual_address, relocation.typ));
+            }
+        }
+    }
+    relocations
+}
+
+// Test that an image is unchanged if it is not modified.
+#[test]
+fn test_noop() {
+    let data = image(true);
+    let builder = build::pe::Builder::read(&*data).unwrap();
+    assert!(builder.is_64);
+    assert_eq!(builder.sections.count(), 3);
+    assert_eq!(builder.base_relocations.len(), 2);
+    assert_eq!(builder.imports.len(), 1);
+
+    let mut out_data = Vec::new();
+    builder.write(&mut out_data).unwrap();
+    assert_eq!(data, out_data);
+}
+
+// Test modifications that require the image to be laid out again.
+#[test]
+fn test_modify() {
+    let data = image(true);
+    let mut builder = build::pe::Builder::read(&*data).unwrap();
+
+    // Force all sections to move to a new file offset.
+    builder.dos_stub.to_mut().resize(0x400, 0);
+    builder.nt_headers.subsystem = pe::IMAGE_SUBSYSTEM_WINDOWS_GUI;
+    for section in &mut builder.sections {
+        if &*section.name == b".rdata" {
+            section.name = b".rodata".to_vec().into();
+        }
+    }
+    builder.imports[0].name = b"kernelbase.dll".to_vec().into();
+    builder.base_relocations.push(build::pe::BaseRelocation {
+        virtual_address: 0x3008,
+        typ: pe::IMAGE_REL_BASED_DIR64,
+    });
+    let address = builder.next_section_address().unwrap();
+    let section = builder.sections.add();
+    section.name = b".new".to_vec().into();
+    section.characteristics = pe::IMAGE_SCN_CNT_INITIALIZED_DATA | pe::IMAGE_SCN_MEM_READ;
+    section.virtual_address = address;
+    section.virtual_size = 4;
+    section.data = vec![1, 2, 3, 4].into();
+
+    let mut out_data = Vec::new();
+    builder.write(&mut out_data).unwrap();
+
+    let file = PeFile64::parse(&*out_data).unwrap();
+    assert_eq!(
+        file.nt_headers().optional_header.subsystem.get(LE),
+        pe::IMAGE_SUBSYSTEM_WINDOWS_GUI
+    );
+    assert_eq!(section_names(&file), [".text", ".rodata", ".reloc", ".new"]);
+    let new = file.section_by_name(".new").unwrap();
+    assert_eq!(new.address(), 0x1_4000_4000);
+    assert_eq!(new.data().unwrap(), &[1, 2, 3, 4]);
+    assert_eq!(
+        base_relocations(&file, &out_data),
+        [
+            (0x1000, pe::IMAGE_REL_BASED_DIR64),
+            (0x2028, pe::IMAGE_REL_BASED_DIR64),
+            (0x3008, pe::IMAGE_REL_BASED_DIR64),
+        ]
+    );
+
+    let import_table = file.import_table().unwrap().unwrap();
+    let mut descriptors = import_table.descriptors().unwrap();
+    let descriptor = descriptors.next().unwrap().unwrap();
+    assert_eq!(
+        import_table.name(descriptor.name.get(LE)).unwrap(),
+        b"kernelbase.dll"
+    );
+    assert!(descriptors.next().unwrap().is_none());
+
+    // The debug directory must refer to the new file offset of the CodeView data.
+    let rodata = file.section_by_name(".rodata").unwrap();
+    let (rodata_offset, _) = rodata.file_range().unwrap();
+    assert_ne!(rodata_offset, 0x400);
+    let debug_directory = &rodata.data().unwrap()[DEBUG_DIRECTORY as usize..];
+    let (entry, _) = pod::from_bytes::<pe::ImageDebugDirectory>(debug_directory).unwrap();
+    let codeview_offset = entry.pointer_to_raw_data.get(LE);
+    assert_eq!(codeview_offset, rodata_offset as u32 + CODEVIEW);
+    assert_eq!(
+        &out_data[codeview_offset as usize..][..CODEVIEW_DATA.len()],
+        CODEVIEW_DATA
+    );
+
+    let dir = file
+        .data_directory(pe::IMAGE_DIRECTORY_ENTRY_SECURITY)
+        .unwrap();
+    let (offset, size) = dir.address_range();
+    assert_eq!(
+        &out_data[offset as usize..][..size as usize],
+        b"\x10\0\0\0\0\x02\x02\0signatur"
+    );
+}
+
+// Test deleting the section containing the base relocations.
+#[test]
+fn test_delete_relocations() {
+    let data = image(true);
+    let mut builder = build::pe::Builder::read(&*data).unwrap();
+    for section in &mut builder.sections {
+        if &*section.name == b".reloc" {
+            section.delete = true;
+        }
+    }
+
+    let mut out_data = Vec::new();
+    builder.write(&mut out_data).unwrap();
+
+    let file = PeFile64::parse(&*out_data).unwrap();
+    assert_eq!(section_names(&file), [".text", ".rdata"]);
+    assert!(file
+        .data_directory(pe::IMAGE_DIRECTORY_ENTRY_BASERELOC)
+        .is_none());
+    assert!(base_relocations(&file, &out_data).is_empty());
+}
+
+// Test adding base relocations to an image that has none.
+#[test]
+fn test_add_relocations() {
+    let data = image(false);
+    let mut builder = build::pe::Builder::read(&*data).unwrap();
+    assert!(builder.base_relocations.is_empty());
+    builder.base_relocations.push(build::pe::BaseRelocation {
+        virtual_address: 0x2028,
+        typ: pe::IMAGE_REL_BASED_DIR64,
+    });
+
+    let mut out_data = Vec::new();
+    builder.write(&mut out_data).unwrap();
+
+    let file = PeFile64::parse(&*out_data).unwrap();
+    assert_eq!(section_names(&file), [".text", ".rdata", ".reloc"]);
+    assert_eq!(
+        base_relocations(&file, &out_data),
+        [(0x2028, pe::IMAGE_REL_BASED_DIR64)]
+    );
+}
@@ -0,0 +1,292 @@
+use object::read::archive::{ArchiveFile, ArchiveKind};
+use object::write::archive::{Archive, ArchiveMember};
+use object::{read, write};
+use object::{Architecture, BinaryFormat, Endianness, SymbolFlags, SymbolKind, SymbolScope};
+
+fn object(format: BinaryFormat, architecture: Architecture, names: &[&[u8]]) -> Vec<u8> {
+    let mut object = write::Object::new(format, architecture, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    for name in names {
+        let value = object.append_section_data(text, &[0xc3; 4], 4);
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 4,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+    }
+    object.add_symbol(write::Symbol {
+        name: b"undefined".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.write().unwrap()
+}
+
+fn check_archive(kind: ArchiveKind, format: BinaryFormat, architecture: Architecture) {
+    let object1 = object(format, architecture, &[b"func1", b"func2"]);
+    let object2 = object(format, architecture, &[b"a_long_function_name"]);
+
+    let mut archive = Archive::new(kind);
+    let mut member = ArchiveMember::new(b"object1.o".to_vec(), &*object1);
+    member.date = 1234;
+    member.uid = 501;
+    member.gid = 20;
+    member.mode = 0o100644;
+    archive.add_member(member);
+    archive.add_member(ArchiveMember::new(
+        b"a_long_object_file_name.o".to_vec(),
+        &*object2,
+    ));
+    archive.add_member(ArchiveMember::new(b"odd.txt".to_vec(), &b"odd"[..]));
+    let bytes = archive.write().unwrap();
+
+    let file = ArchiveFile::parse(&*bytes).unwrap();
+    assert_eq!(file.kind(), kind);
+    assert!(!file.is_thin());
+
+    let members = file.members().collect::<read::Result<Vec<_>>>().unwrap();
+    assert_eq!(members.len(), 3);
+    assert_eq!(members[0].name(), b"object1.o");
+    assert_eq!(members[0].data(&*bytes).unwrap(), &object1[..]);
+    assert_eq!(members[0].date(), Some(1234));
+    assert_eq!(members[0].uid(), Some(501));
+    assert_eq!(members[0].gid(), Some(20));
+    assert_eq!(members[0].mode(), Some(0o100644));
+    assert_eq!(members[1].name(), b"a_long_object_file_name.o");
+    assert_eq!(members[1].data(&*bytes).unwrap(), &object2[..]);
+    assert_eq!(members[1].mode(), Some(0o644));
+    assert_eq!(members[2].name(), b"odd.txt");
+    assert_eq!(members[2].data(&*bytes).unwrap(), b"odd");
+
+    let mut symbols = Vec::new();
+    for symbol in file.symbols().unwrap().unwrap() {
+        let symbol = symbol.unwrap();
+        let member = file.member(symbol.offset()).unwrap();
+        symbols.push((symbol.name().to_vec(), member.name().to_vec()));
+    }
+    symbols.sort();
+    let prefix: &[u8] = if format == BinaryFormat::MachO {
+        b"_"
+    } else {
+        b""
+    };
+    let mut expected = vec![
+        ([prefix, b"func1"].concat(), b"object1.o".to_vec()),
+        ([prefix, b"func2"].concat(), b"object1.o".to_vec()),
+        (
+            [prefix, b"a_long_function_name"].concat(),
+            b"a_long_object_file_name.o".to_vec(),
+        ),
+    ];
+    expected.sort();
+    assert_eq!(symbols, expected);
+}
+
+#[test]
+fn archive_gnu() {
+    check_archive(ArchiveKind::Gnu, BinaryFormat::Elf, Architecture::X86_64);
+}
+
+#[test]
+fn archive_gnu64() {
+    check_archive(ArchiveKind::Gnu64, BinaryFormat::Elf, Architecture::X86_64);
+}
+
+#[test]
+fn archive_bsd() {
+    check_archive(ArchiveKind::Bsd, BinaryFormat::MachO, Architecture::X86_64);
+}
+
+#[test]
+fn archive_bsd64() {
+    check_archive(
+        ArchiveKind::Bsd64,
+        BinaryFormat::MachO,
+        Architecture::Aarch64,
+    );
+}
+
+#[test]
+fn archive_coff() {
+    check_archive(ArchiveKind::Coff, BinaryFormat::Coff, Architecture::X86_64);
+}
+
+#[test]
+fn archive_aixbig() {
+    check_archive(
+        ArchiveKind::AixBig,
+        BinaryFormat::Xcoff,
+        Architecture::PowerPc64,
+    );
+}
+
+#[test]
+fn archive_deterministic() {
+    let object = object(BinaryFormat::Elf, Architecture::X86_64, &[b"func"]);
+    let mut archive = Archive::new(ArchiveKind::Gnu);
+    let mut member = ArchiveMember::new(b"object.o".to_vec(), &*object);
+    member.date = 1234;
+    member.uid = 501;
+    member.gid = 20;
+    archive.add_member(member);
+    archive.deterministic = true;
+    let bytes = archive.write().unwrap();
+
+    let file = ArchiveFile::parse(&*bytes).unwrap();
+    let member = file.members().next().unwrap().unwrap();
+    assert_eq!(member.date(), Some(0));
+    assert_eq!(member.uid(), Some(0));
+    assert_eq!(member.gid(), Some(0));
+    assert_eq!(member.mode(), Some(0o644));
+
+    // The output must only depend on the member contents.
+    archive.members_mut()[0].date = 5678;
+    assert_eq!(archive.write().unwrap(), bytes);
+}
+
+#[test]
+fn archive_no_symbol_table() {
+    let object = object(BinaryFormat::Elf, Architecture::X86_64, &[b"func"]);
+    let mut archive = Archive::new(ArchiveKind::Gnu);
+    archive.add_member(ArchiveMember::new(
+        b"a_long_object_file_name.o".to_vec(),
+        &*object,
+    ));
+    archive.symbol_table = false;
+    let bytes = archive.write().unwrap();
+
+    let file = ArchiveFile::parse(&*bytes).unwrap();
+    assert_eq!(file.kind(), ArchiveKind::Gnu);
+    assert!(file.symbols().unwrap().is_none());
+    let member = file.members().next().unwrap().unwrap();
+    assert_eq!(member.name(), b"a_long_object_file_name.o");
+    assert_eq!(member.data(&*bytes).unwrap(), &object[..]);
+}
+
+#[test]
+fn archive_thin() {
+    let object = object(BinaryFormat::Elf, Architecture::X86_64, &[b"func"]);
+    let mut archive = Archive::new(ArchiveKind::Gnu);
+    archive.thin = true;
+    archive.add_member(ArchiveMember::new(b"obj/object.o".to_vec(), &*object));
+    archive.add_member(ArchiveMember::new(b"odd.txt".to_vec(), &b"odd"[..]));
+    let bytes = archive.write().unwrap();
+
+    let file = ArchiveFile::parse(&*bytes).unwrap();
+    assert_eq!(file.kind(), ArchiveKind::Gnu);
+    assert!(file.is_thin());
+    let members = file.members().collect::<read::Result<Vec<_>>>().unwrap();
+    assert_eq!(members.len(), 2);
+    assert!(members[0].is_thin());
+    assert_eq!(members[0].name(), b"obj/object.o");
+    assert_eq!(members[0].size(), object.len() as u64);
+    assert!(members[1].is_thin());
+    assert_eq!(members[1].name(), b"odd.txt");
+    assert_eq!(members[1].size(), 3);
+
+    let symbol = file.symbols().unwrap().unwrap().next().unwrap().unwrap();
+    assert_eq!(symbol.name(), b"func");
+    let member = file.member(symbol.offset()).unwrap();
+    assert_eq!(member.name(), b"obj/object.o");
+
+    let mut archive = Archive::new(ArchiveKind::Bsd);
+    archive.thin = true;
+    assert!(archive.write().is_err());
+}
+
+/// Create an empty temporary directory that is unique to this process.
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("object-round-trip-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn archive_flatten_thin() {
+    let dir = temp_dir("archive-flatten-thin");
+    std::fs::create_dir_all(dir.join("obj")).unwrap();
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    let object1 = object(BinaryFormat::Elf, Architecture::X86_64, &[b"func1"]);
+    let object2 = object(BinaryFormat::Elf, Architecture::X86_64, &[b"func2"]);
+    std::fs::write(dir.join("obj/object1.o"), &object1).unwrap();
+    std::fs::write(dir.join("object2.o"), &object2).unwrap();
+
+    let archive_path = dir.join("lib/thin.a");
+    let archive = Archive::new_thin(
+        ArchiveKind::Gnu,
+        &archive_path,
+        [dir.join("obj/object1.o"), dir.join("object2.o")],
+    )
+    .unwrap();
+    let thin = archive.write().unwrap();
+
+    let file = ArchiveFile::parse(&*thin).unwrap();
+    assert!(file.is_thin());
+    let members = file.members().collect::<read::Result<Vec<_>>>().unwrap();
+    assert_eq!(members.len(), 2);
+    assert_eq!(members[0].name(), b"../obj/object1.o");
+    assert_eq!(members[1].name(), b"../object2.o");
+
+    let archive = Archive::flatten_thin(&thin, &archive_path).unwrap();
+    let bytes = archive.write().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let file = ArchiveFile::parse(&*bytes).unwrap();
+    assert_eq!(file.kind(), ArchiveKind::Gnu);
+    assert!(!file.is_thin());
+    let members = file.members().collect::<read::Result<Vec<_>>>().unwrap();
+    assert_eq!(members.len(), 2);
+    assert_eq!(members[0].name(), b"object1.o");
+    assert_eq!(members[0].data(&*bytes).unwrap(), &object1[..]);
+    assert_eq!(members[1].name(), b"object2.o");
+    assert_eq!(members[1].data(&*bytes).unwrap(), &object2[..]);
+
+    let mut symbols = Vec::new();
+    for symbol in file.symbols().unwrap().unwrap() {
+        let symbol = symbol.unwrap();
+        let member = file.member(symbol.offset()).unwrap();
+        symbols.push((symbol.name().to_vec(), member.name().to_vec()));
+    }
+    assert_eq!(
+        symbols,
+        vec![
+            (b"func1".to_vec(), b"object1.o".to_vec()),
+            (b"func2".to_vec(), b"object2.o".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn archive_flatten_thin_duplicate() {
+    let dir = temp_dir("archive-flatten-thin-duplicate");
+    std::fs::create_dir_all(dir.join("a")).unwrap();
+    std::fs::create_dir_all(dir.join("b")).unwrap();
+    let object1 = object(BinaryFormat::Elf, Architecture::X86_64, &[b"func1"]);
+    let object2 = object(BinaryFormat::Elf, Architecture::X86_64, &[b"func2"]);
+    std::fs::write(dir.join("a/object.o"), &object1).unwrap();
+    std::fs::write(dir.join("b/object.o"), &object2).unwrap();
+
+    let archive_path = dir.join("thin.a");
+    let archive = Archive::new_thin(
+        ArchiveKind::Gnu,
+        &archive_path,
+        [dir.join("a/object.o"), dir.join("b/object.o")],
+    )
+    .unwrap();
+    let thin = archive.write().unwrap();
+    let result = Archive::flatten_thin(&thin, &archive_path);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(result.is_err());
+}
@@ -1,4 +1,4 @@
-use object::read::{Object, ObjectSection};
+use object::read::{Object, ObjectComdat, ObjectSection, ObjectSymbol};
 use object::{read, write};
 use object::{
     Architecture, BinaryFormat, Endianness, RelocationEncoding, RelocationFlags, RelocationKind,
@@ -56,3 +56,586 @@ fn reloc_overflow() {
     }
     assert_eq!(i, 0x10000);
 }
+
+#[test]
+fn bigobj() {
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    object.set_coff_bigobj(true);
+    object.add_file_symbol(b"a-long-file-name-that-needs-several-records.c".to_vec());
+
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 8], 4);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 8,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    let undef = object.add_symbol(write::Symbol {
+        name: b"a_long_undefined_symbol".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.add_symbol(write::Symbol {
+        name: b"weak".to_vec(),
+        value: 4,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: true,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 16], 8);
+    for (offset, symbol) in [(0, func), (8, undef)] {
+        object
+            .add_relocation(
+                data,
+                write::Relocation {
+                    offset,
+                    symbol,
+                    addend: 0,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: 64,
+                    },
+                },
+            )
+            .unwrap();
+    }
+    let comdat_section = object.add_subsection(write::StandardSection::Text, b"comdat");
+    object.append_section_data(comdat_section, &[0xc3], 1);
+    object.section_symbol(comdat_section);
+    let comdat_symbol = object.add_symbol(write::Symbol {
+        name: b"comdat".to_vec(),
+        value: 0,
+        size: 1,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(comdat_section),
+        flags: SymbolFlags::None,
+    });
+    object.add_comdat(write::Comdat {
+        kind: object::ComdatKind::Any,
+        symbol: comdat_symbol,
+        sections: vec![comdat_section],
+    });
+    let bytes = object.write().unwrap();
+
+    assert_eq!(
+        object::FileKind::parse(&*bytes),
+        Ok(object::FileKind::CoffBig)
+    );
+    let file = read::coff::CoffBigFile::<_>::parse(&*bytes).unwrap();
+    assert_eq!(file.architecture(), Architecture::X86_64);
+    let sections = file
+        .sections()
+        .map(|section| section.name().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(sections, [".text", ".data", ".text$comdat"]);
+
+    let text = file.section_by_name(".text").unwrap();
+    assert_eq!(text.data().unwrap(), [0xc3; 8]);
+    let data = file.section_by_name(".data").unwrap();
+    let relocations = data
+        .relocations()
+        .map(|(offset, relocation)| {
+            let read::RelocationTarget::Symbol(index) = relocation.target() else {
+                panic!("unexpected relocation target");
+            };
+            let symbol = file.symbol_by_index(index).unwrap();
+            (offset, symbol.name().unwrap().to_string())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        relocations,
+        [
+            (0, "func".to_string()),
+            (8, "a_long_undefined_symbol".to_string())
+        ]
+    );
+
+    let symbols = file
+        .symbols()
+        .map(|symbol| {
+            (
+                symbol.name().unwrap().to_string(),
+                symbol.kind(),
+                symbol.section_index().map(|index| index.0),
+                symbol.is_weak(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        symbols,
+        [
+            (
+                "a-long-file-name-that-needs-several-records.c".to_string(),
+                SymbolKind::File,
+                None,
+                false
+            ),
+            ("func".to_string(), SymbolKind::Text, Some(1), false),
+            (
+                "a_long_undefined_symbol".to_string(),
+                SymbolKind::Text,
+                None,
+                false
+            ),
+            (
+                ".weak.weak.func".to_string(),
+                SymbolKind::Data,
+                Some(1),
+                false
+            ),
+            ("weak".to_string(), SymbolKind::Text, None, true),
+            (
+                ".text$comdat".to_string(),
+                SymbolKind::Section,
+                Some(3),
+                false
+            ),
+            ("comdat".to_string(), SymbolKind::Text, Some(3), false),
+        ]
+    );
+
+    let comdats = file
+        .comdats()
+        .map(|comdat| {
+            (
+                comdat.name().unwrap().to_string(),
+                comdat.kind(),
+                comdat.sections().map(|index| index.0).collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        comdats,
+        [("comdat".to_string(), object::ComdatKind::Any, vec![3])]
+    );
+}
+
+#[test]
+fn bigobj_section_overflow() {
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let count = usize::from(object::pe::IMAGE_SYM_SECTION_MAX) + 1;
+    let mut last = None;
+    for _ in 0..count {
+        let section = object.add_section(Vec::new(), b".data".to_vec(), object::SectionKind::Data);
+        object.append_section_data(section, &[1], 1);
+        last = Some(section);
+    }
+    object.add_symbol(write::Symbol {
+        name: b"last".to_vec(),
+        value: 0,
+        size: 1,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(last.unwrap()),
+        flags: SymbolFlags::None,
+    });
+    object.section_symbol(last.unwrap());
+    let bytes = object.write().unwrap();
+
+    assert_eq!(
+        object::FileKind::parse(&*bytes),
+        Ok(object::FileKind::CoffBig)
+    );
+    let file = read::coff::CoffBigFile::<_>::parse(&*bytes).unwrap();
+    assert_eq!(file.sections().count(), count);
+    let symbol = file.symbol_by_name("last").unwrap();
+    assert_eq!(symbol.section_index(), Some(read::SectionIndex(count)));
+    let section = file.section_by_index(read::SectionIndex(count)).unwrap();
+    assert_eq!(section.data().unwrap(), [1]);
+
+    // Smaller files still use the classic header by default.
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    object.section_id(write::StandardSection::Text);
+    let bytes = object.write().unwrap();
+    assert_eq!(object::FileKind::parse(&*bytes), Ok(object::FileKind::Coff));
+}
+
+#[test]
+fn bigobj_writer_symbol() {
+    use object::pe;
+    use object::read::coff::{CoffHeader, ImageSymbol};
+
+    let mut buffer = Vec::new();
+    let mut writer = write::coff::Writer::new_bigobj(&mut buffer);
+    writer.reserve_symbol_index();
+    writer.reserve_symbol_index();
+    writer.reserve_file_header();
+    writer.reserve_section_headers(0);
+    writer.reserve_symtab_strtab();
+    writer
+        .write_file_header(write::coff::FileHeader {
+            machine: pe::IMAGE_FILE_MACHINE_AMD64,
+            ..Default::default()
+        })
+        .unwrap();
+    writer.write_symbol(write::coff::Symbol {
+        name: write::coff::Name::Short(*b"abs\0\0\0\0\0"),
+        section_number: pe::IMAGE_SYM_ABSOLUTE as u16,
+        ..Default::default()
+    });
+    writer.write_symbol_ex(write::coff::SymbolEx {
+        name: write::coff::Name::Short(*b"big\0\0\0\0\0"),
+        section_number: 0x12345,
+        ..Default::default()
+    });
+    writer.write_strtab();
+
+    let mut offset = 0;
+    let header = pe::AnonObjectHeaderBigobj::parse(&*buffer, &mut offset).unwrap();
+    let symbols = header.symbols(&*buffer).unwrap();
+    let symbol = symbols.symbol(read::SymbolIndex(0)).unwrap();
+    assert_eq!(symbol.section_number(), pe::IMAGE_SYM_ABSOLUTE);
+    let symbol = symbols.symbol(read::SymbolIndex(1)).unwrap();
+    assert_eq!(symbol.section_number(), 0x12345);
+}
+
+#[test]
+fn import_library() {
+    let mut library =
+        write::coff::ImportLibrary::new(b"foo.dll".to_vec(), object::pe::IMAGE_FILE_MACHINE_AMD64);
+    library.imports.push(write::coff::ShortImport::new(
+        b"func".to_vec(),
+        object::pe::IMPORT_OBJECT_CODE,
+    ));
+    library.imports.push(write::coff::ShortImport {
+        symbol: b"data".to_vec(),
+        import_type: object::pe::IMPORT_OBJECT_DATA,
+        name_type: object::pe::IMPORT_OBJECT_NAME,
+        ordinal_or_hint: 3,
+        export_name: Vec::new(),
+    });
+    library.imports.push(write::coff::ShortImport {
+        symbol: b"by_ordinal".to_vec(),
+        import_type: object::pe::IMPORT_OBJECT_CODE,
+        name_type: object::pe::IMPORT_OBJECT_ORDINAL,
+        ordinal_or_hint: 7,
+        export_name: Vec::new(),
+    });
+    library.imports.push(write::coff::ShortImport {
+        symbol: b"?prefixed".to_vec(),
+        import_type: object::pe::IMPORT_OBJECT_CODE,
+        name_type: object::pe::IMPORT_OBJECT_NAME_NO_PREFIX,
+        ordinal_or_hint: 0,
+        export_name: Vec::new(),
+    });
+    library.imports.push(write::coff::ShortImport {
+        symbol: b"_decorated@8".to_vec(),
+        import_type: object::pe::IMPORT_OBJECT_CONST,
+        name_type: object::pe::IMPORT_OBJECT_NAME_UNDECORATE,
+        ordinal_or_hint: 0,
+        export_name: Vec::new(),
+    });
+    library.imports.push(write::coff::ShortImport {
+        symbol: b"alias".to_vec(),
+        import_type: object::pe::IMPORT_OBJECT_CODE,
+        name_type: object::pe::IMPORT_OBJECT_NAME_EXPORTAS,
+        ordinal_or_hint: 0,
+        export_name: b"real_name".to_vec(),
+    });
+    let bytes = library.write().unwrap();
+
+    let archive = read::archive::ArchiveFile::parse(&*bytes).unwrap();
+    assert_eq!(archive.kind(), read::archive::ArchiveKind::Coff);
+    let mut symbols = archive
+        .symbols()
+        .unwrap()
+        .unwrap()
+        .map(|symbol| String::from_utf8(symbol.unwrap().name().to_vec()).unwrap())
+        .collect::<Vec<_>>();
+    symbols.sort();
+    assert_eq!(
+        symbols,
+        [
+            "?prefixed",
+            "__IMPORT_DESCRIPTOR_foo",
+            "__NULL_IMPORT_DESCRIPTOR",
+            "__imp_?prefixed",
+            "__imp__decorated@8",
+            "__imp_alias",
+            "__imp_by_ordinal",
+            "__imp_data",
+            "__imp_func",
+            "_decorated@8",
+            "alias",
+            "by_ordinal",
+            "func",
+            "\x7ffoo_NULL_THUNK_DATA",
+        ]
+    );
+
+    let members = archive
+        .members()
+        .map(|member| {
+            let member = member.unwrap();
+            assert_eq!(member.name(), b"foo.dll");
+            member.data(&*bytes).unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(members.len(), 9);
+
+    // The import descriptor.
+    let file = read::coff::CoffFile::<_>::parse(members[0]).unwrap();
+    assert_eq!(file.architecture(), Architecture::X86_64);
+    let idata2 = file.section_by_name(".idata$2").unwrap();
+    assert_eq!(idata2.data().unwrap(), [0; 20]);
+    let relocations = idata2
+        .relocations()
+        .map(|(offset, relocation)| {
+            let read::RelocationTarget::Symbol(index) = relocation.target() else {
+                panic!("unexpected relocation target");
+            };
+            let symbol = file.symbol_by_index(index).unwrap();
+            (
+                offset,
+                relocation.kind(),
+                symbol.name().unwrap().to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        relocations,
+        [
+            (12, RelocationKind::ImageOffset, ".idata$6".to_string()),
+            (0, RelocationKind::ImageOffset, ".idata$4".to_string()),
+            (16, RelocationKind::ImageOffset, ".idata$5".to_string()),
+        ]
+    );
+    let idata6 = file.section_by_name(".idata$6").unwrap();
+    assert_eq!(idata6.data().unwrap(), b"foo.dll\0");
+    let symbol = file.symbol_by_name("__IMPORT_DESCRIPTOR_foo").unwrap();
+    assert_eq!(symbol.section_index(), Some(idata2.index()));
+    assert!(file
+        .symbol_by_name("__NULL_IMPORT_DESCRIPTOR")
+        .unwrap()
+        .is_undefined());
+    assert!(file
+        .symbol_by_name("\x7ffoo_NULL_THUNK_DATA")
+        .unwrap()
+        .is_undefined());
+
+    // The null import descriptor.
+    let file = read::coff::CoffFile::<_>::parse(members[1]).unwrap();
+    let idata3 = file.section_by_name(".idata$3").unwrap();
+    assert_eq!(idata3.data().unwrap(), [0; 20]);
+    let symbol = file.symbol_by_name("__NULL_IMPORT_DESCRIPTOR").unwrap();
+    assert_eq!(symbol.section_index(), Some(idata3.index()));
+
+    // The null thunk.
+    let file = read::coff::CoffFile::<_>::parse(members[2]).unwrap();
+    let idata5 = file.section_by_name(".idata$5").unwrap();
+    assert_eq!(idata5.data().unwrap(), [0; 8]);
+    assert_eq!(idata5.align(), 8);
+    let idata4 = file.section_by_name(".idata$4").unwrap();
+    assert_eq!(idata4.data().unwrap(), [0; 8]);
+    let symbol = file.symbol_by_name("\x7ffoo_NULL_THUNK_DATA").unwrap();
+    assert_eq!(symbol.section_index(), Some(idata5.index()));
+
+    // The short import objects.
+    let imports = members[3..]
+        .iter()
+        .map(|data| {
+            let file = read::coff::ImportFile::parse(*data).unwrap();
+            assert_eq!(file.architecture(), Architecture::X86_64);
+            assert_eq!(file.dll(), b"foo.dll");
+            (
+                String::from_utf8(file.symbol().to_vec()).unwrap(),
+                file.import(),
+                file.import_type(),
+            )
+        })
+        .collect::<Vec<_>>();
+    use read::coff::{ImportName, ImportType};
+    assert_eq!(
+        imports,
+        [
+            ("func".into(), ImportName::Name(b"func"), ImportType::Code),
+            ("data".into(), ImportName::Name(b"data"), ImportType::Data),
+            (
+                "by_ordinal".into(),
+                ImportName::Ordinal(7),
+                ImportType::Code
+            ),
+            (
+                "?prefixed".into(),
+                ImportName::Name(b"prefixed"),
+                ImportType::Code
+            ),
+            (
+                "_decorated@8".into(),
+                ImportName::Name(b"decorated"),
+                ImportType::Const
+            ),
+            (
+                "alias".into(),
+                ImportName::Name(b"real_name"),
+                ImportType::Code
+            ),
+        ]
+    );
+    let header = object::pe::ImportObjectHeader::parse(members[4], &mut 0).unwrap();
+    assert_eq!(header.ordinal_or_hint.get(object::LittleEndian), 3);
+}
+
+#[test]
+fn module_definition() {
+    let text = b"; comment
+LIBRARY \"foo\" BASE=0x10000000
+STACKSIZE 0x100000,0x1000
+VERSION 1.2
+EXPORTS
+    func
+    data @3 DATA
+    by_ordinal @7 NONAME
+    internal=real_name PRIVATE
+    forward=bar.func @ 9
+    \"quoted name\" CONSTANT
+";
+    let def = read::coff::ModuleDefinition::parse(text).unwrap();
+    assert_eq!(def.name.as_deref(), Some(&b"foo"[..]));
+    assert!(def.is_dll);
+    assert_eq!(def.base, Some(0x1000_0000));
+    assert_eq!(def.stack_size, Some((0x10_0000, Some(0x1000))));
+    assert_eq!(def.heap_size, None);
+    assert_eq!(def.version, Some((1, 2)));
+    let exports = def
+        .exports
+        .iter()
+        .map(|export| {
+            (
+                String::from_utf8(export.name.clone()).unwrap(),
+                String::from_utf8(export.internal_name.clone()).unwrap(),
+                export.ordinal,
+                export.noname,
+                export.data,
+                export.constant,
+                export.private,
+            )
+        })
+        .collect::<Vec<_>>();
+    let exports = exports
+        .iter()
+        .map(
+            |(name, internal, ordinal, noname, data, constant, private)| {
+                (
+                    name.as_str(),
+                    internal.as_str(),
+                    *ordinal,
+                    *noname,
+                    *data,
+                    *constant,
+                    *private,
+                )
+            },
+        )
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exports,
+        [
+            ("func", "", None, false, false, false, false),
+            ("data", "", Some(3), false, true, false, false),
+            ("by_ordinal", "", Some(7), true, false, false, false),
+            ("internal", "real_name", None, false, false, false, true),
+            ("forward", "bar.func", Some(9), false, false, false, false),
+            ("quoted name", "", None, false, false, true, false),
+        ]
+    );
+    assert_eq!(def.exports[3].forward(), None);
+    assert_eq!(def.exports[4].forward(), Some((&b"bar"[..], &b"func"[..])));
+
+    // Writing and parsing again must give the same model.
+    let written = def.write();
+    assert_eq!(read::coff::ModuleDefinition::parse(&written).unwrap(), def);
+
+    assert_eq!(
+        String::from_utf8(def.coff_directives(write::CoffExportStyle::Msvc)).unwrap(),
+        " /EXPORT:\"func\" /EXPORT:\"data\",@3,DATA /EXPORT:\"by_ordinal\",@7,NONAME \
+         /EXPORT:\"internal=real_name\",PRIVATE /EXPORT:\"forward=bar.func\",@9 \
+         /EXPORT:\"quoted name\",CONSTANT"
+    );
+
+    let library = def
+        .import_library(object::pe::IMAGE_FILE_MACHINE_I386)
+        .unwrap();
+    assert_eq!(library.dll, b"foo.dll");
+    let imports = library
+        .imports
+        .iter()
+        .map(|import| {
+            (
+                String::from_utf8(import.symbol.clone()).unwrap(),
+                import.import_type,
+                import.name_type,
+                import.ordinal_or_hint,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            (
+                "_func".into(),
+                object::pe::IMPORT_OBJECT_CODE,
+                object::pe::IMPORT_OBJECT_NAME_UNDECORATE,
+                0
+            ),
+            (
+                "_data".into(),
+                object::pe::IMPORT_OBJECT_DATA,
+                object::pe::IMPORT_OBJECT_NAME_UNDECORATE,
+                3
+            ),
+            (
+                "_by_ordinal".into(),
+                object::pe::IMPORT_OBJECT_CODE,
+                object::pe::IMPORT_OBJECT_ORDINAL,
+                7
+            ),
+            (
+                "_forward".into(),
+                object::pe::IMPORT_OBJECT_CODE,
+                object::pe::IMPORT_OBJECT_NAME_UNDECORATE,
+                9
+            ),
+            (
+                "_quoted name".into(),
+                object::pe::IMPORT_OBJECT_CONST,
+                object::pe::IMPORT_OBJECT_NAME_UNDECORATE,
+                0
+            ),
+        ]
+    );
+
+    let bytes = library.write().unwrap();
+    let archive = read::archive::ArchiveFile::parse(&*bytes).unwrap();
+    let member = archive.members().nth(3).unwrap().unwrap();
+    let import = read::coff::ImportFile::parse(member.data(&*bytes).unwrap()).unwrap();
+    assert_eq!(import.dll(), b"foo.dll");
+    assert_eq!(import.symbol(), b"_func");
+    assert_eq!(import.import(), read::coff::ImportName::Name(b"func"));
+
+    assert!(read::coff::ModuleDefinition::parse(b"EXPORTS\n    func NONAME\n").is_err());
+    assert!(read::coff::ModuleDefinition::parse(b"EXPORTS\n    func @0\n").is_err());
+    assert!(read::coff::ModuleDefinition::parse(b"LIBRARY \"foo\n").is_err());
+    assert!(read::coff::ModuleDefinition::parse(b"func\n").is_err());
+}
@@ -300,3 +300,133 @@ fn gnu_property_inner<Elf: FileHeader<Endian = Endianness>>(architecture: Archit
     assert!(props.next().unwrap().is_none());
     assert!(notes.next().unwrap().is_none());
 }
+
+#[test]
+fn crel() {
+    crel_inner(Architecture::I386);
+    crel_inner(Architecture::X86_64);
+}
+
+fn crel_inner(architecture: Architecture) {
+    let write = |crel: bool| {
+        let mut object = write::Object::new(BinaryFormat::Elf, architecture, Endianness::Little);
+        object.set_elf_crel(crel);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0; 0x10000], 16);
+        let mut symbols = Vec::new();
+        for name in [&b"a"[..], b"b", b"c"] {
+            symbols.push(object.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value: 0,
+                size: 0,
+                kind: SymbolKind::Text,
+                scope: SymbolScope::Linkage,
+                weak: false,
+                section: write::SymbolSection::Undefined,
+                flags: SymbolFlags::None,
+            }));
+        }
+        // Includes decreasing offsets and offsets that need a multi-byte delta.
+        let relocations = [
+            (0x10, 0, 0, 32),
+            (0x18, 0, 4, 32),
+            (0x20, 1, -4, 32),
+            (0x8, 2, 0x1234, 32),
+            (0x8000, 1, 0, 32),
+            (0xfff8, 0, -0x8000_0000, 32),
+            (0x100, 2, 0, 16),
+        ];
+        for (offset, symbol, addend, size) in relocations {
+            object
+                .add_relocation(
+                    text,
+                    write::Relocation {
+                        offset,
+                        symbol: symbols[symbol],
+                        addend,
+                        flags: object::RelocationFlags::Generic {
+                            kind: object::RelocationKind::Absolute,
+                            encoding: object::RelocationEncoding::Generic,
+                            size,
+                        },
+                    },
+                )
+                .unwrap();
+        }
+        object.write().unwrap()
+    };
+
+    let rel_bytes = write(false);
+    let crel_bytes = write(true);
+    assert!(crel_bytes.len() < rel_bytes.len());
+
+    let rel_file = read::File::parse(&*rel_bytes).unwrap();
+    let crel_file = read::File::parse(&*crel_bytes).unwrap();
+    let crel_section = crel_file.section_by_name(".crel.text").unwrap();
+    assert_eq!(crel_section.kind(), SectionKind::Metadata);
+    if let object::SectionFlags::Elf { sh_flags } = crel_section.flags() {
+        assert_eq!(sh_flags, u64::from(elf::SHF_INFO_LINK));
+    }
+
+    let relocations = |file: &read::File| {
+        let text = file.section_by_name(".text").unwrap();
+        text.relocations()
+            .map(|(offset, relocation)| {
+                (
+                    offset,
+                    relocation.flags(),
+                    relocation.target(),
+                    relocation.addend(),
+                    relocation.has_implicit_addend(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let rel_relocations = relocations(&rel_file);
+    assert_eq!(rel_relocations.len(), 7);
+    assert_eq!(relocations(&crel_file), rel_relocations);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn compression() {
+    for architecture in [Architecture::I386, Architecture::X86_64] {
+        for format in [
+            object::CompressionFormat::Zlib,
+            object::CompressionFormat::Zstandard,
+        ] {
+            compression_inner(architecture, format);
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+fn compression_inner(architecture: Architecture, format: object::CompressionFormat) {
+    let debug_info = (0..0x1000u32).map(|i| (i % 7) as u8).collect::<Vec<_>>();
+    let mut object = write::Object::new(BinaryFormat::Elf, architecture, Endianness::Little);
+    object.set_elf_compression(format);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xcc; 0x100], 16);
+    let section = object.add_section(Vec::new(), b".debug_info".to_vec(), SectionKind::Debug);
+    object.append_section_data(section, &debug_info, 1);
+    let bytes = object.write().unwrap();
+
+    let file = read::File::parse(&*bytes).unwrap();
+    let text = file.section_by_name(".text").unwrap();
+    let object::SectionFlags::Elf { sh_flags } = text.flags() else {
+        panic!("Unexpected section flags");
+    };
+    assert_eq!(sh_flags & u64::from(elf::SHF_COMPRESSED), 0);
+    assert_eq!(text.data().unwrap(), &[0xcc; 0x100]);
+
+    let section = file.section_by_name(".debug_info").unwrap();
+    let object::SectionFlags::Elf { sh_flags } = section.flags() else {
+        panic!("Unexpected section flags");
+    };
+    assert_ne!(sh_flags & u64::from(elf::SHF_COMPRESSED), 0);
+    let compressed = section.compressed_data().unwrap();
+    assert_eq!(compressed.format, format);
+    assert_eq!(compressed.uncompressed_size, debug_info.len() as u64);
+    assert!(compressed.data.len() < debug_info.len());
+    assert_eq!(&*section.uncompressed_data().unwrap(), &debug_info[..]);
+}
@@ -1,6 +1,7 @@
-use object::read::macho::MachHeader;
+use object::read::macho::{FatArch, MachHeader, MachOFatFile32, MachOFatFile64};
 use object::read::{Object, ObjectSection};
-use object::{macho, read, write, Architecture, BinaryFormat, Endianness};
+use object::write::macho::{MachOFat, MachOFatArch};
+use object::{macho, read, write, Architecture, BinaryFormat, Endianness, FileKind};
 
 // Test that segment size is valid when the first section needs alignment.
 #[test]
@@ -62,3 +63,142 @@ fn issue_552_section_file_alignment() {
     assert_eq!(section.address(), 32);
     assert_eq!(section.size(), 1);
 }
+
+fn object(architecture: Architecture) -> write::Object<'static> {
+    let mut object = write::Object::new(BinaryFormat::MachO, architecture, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 4], 32);
+    object
+}
+
+// Test that a fat file can be created from object files, read, and thinned.
+#[test]
+fn fat_objects() {
+    let mut fat = MachOFat::new();
+    for architecture in [Architecture::X86_64, Architecture::Aarch64] {
+        let mut object = write::Object::new(BinaryFormat::MachO, architecture, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0xc3; 4], 32);
+        let arch = MachOFatArch::from_object(&object).unwrap();
+        assert_eq!(arch.align, 5);
+        fat.add_arch(arch);
+    }
+    let data = fat.write().unwrap();
+    assert_eq!(FileKind::parse(&*data).unwrap(), FileKind::MachOFat32);
+
+    let file = MachOFatFile32::parse(&*data).unwrap();
+    assert_eq!(file.header().magic.get(object::BigEndian), macho::FAT_MAGIC);
+    let arches = file.arches();
+    assert_eq!(arches.len(), 2);
+    assert_eq!(arches[0].cputype(), macho::CPU_TYPE_X86_64);
+    assert_eq!(arches[0].cpusubtype(), macho::CPU_SUBTYPE_X86_64_ALL);
+    assert_eq!(arches[1].cputype(), macho::CPU_TYPE_ARM64);
+    assert_eq!(arches[1].cpusubtype(), macho::CPU_SUBTYPE_ARM64_ALL);
+    for (arch, architecture) in arches
+        .iter()
+        .zip([Architecture::X86_64, Architecture::Aarch64])
+    {
+        assert_eq!(arch.offset() % 32, 0);
+        let slice = arch.data(&*data).unwrap();
+        assert_eq!(slice, object(architecture).write().unwrap());
+        let object = read::File::parse(slice).unwrap();
+        assert_eq!(object.architecture(), architecture);
+    }
+
+    // Reading and writing again must give the same file.
+    let mut fat = MachOFat::read(&data).unwrap();
+    assert!(!fat.fat64);
+    assert_eq!(fat.write().unwrap(), data);
+
+    // Extract a single arch.
+    let arch = fat
+        .find_arch(macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64_ALL)
+        .unwrap();
+    assert_eq!(arch.data, object(Architecture::Aarch64).write().unwrap());
+    assert!(fat
+        .find_arch(macho::CPU_TYPE_X86, macho::CPU_SUBTYPE_I386_ALL)
+        .is_none());
+
+    // Remove an arch.
+    let arch = fat.remove_arch(0);
+    assert_eq!(arch.cputype, macho::CPU_TYPE_X86_64);
+    let data = fat.write().unwrap();
+    let file = MachOFatFile32::parse(&*data).unwrap();
+    assert_eq!(file.arches().len(), 1);
+    assert_eq!(file.arches()[0].cputype(), macho::CPU_TYPE_ARM64);
+}
+
+// Test the page alignment of images, and the use of `FAT_MAGIC_64`.
+#[test]
+fn fat_images() {
+    let x86_64 = vec![1; 0x1234];
+    let arm64 = vec![2; 0x10];
+    let mut fat = MachOFat::new();
+    fat.add_arch(MachOFatArch::new(
+        macho::CPU_TYPE_X86_64,
+        macho::CPU_SUBTYPE_X86_64_ALL,
+        &*x86_64,
+    ));
+    fat.add_arch(MachOFatArch::new(
+        macho::CPU_TYPE_ARM64,
+        macho::CPU_SUBTYPE_ARM64_ALL,
+        &*arm64,
+    ));
+    assert_eq!(fat.arches()[0].align, 12);
+    assert_eq!(fat.arches()[1].align, 14);
+
+    let data = fat.write().unwrap();
+    let file = MachOFatFile32::parse(&*data).unwrap();
+    let arches = file
+        .arches()
+        .iter()
+        .map(|arch| (arch.offset(), arch.size(), arch.align()))
+        .collect::<Vec<_>>();
+    assert_eq!(arches, [(0x1000, 0x1234, 12), (0x4000, 0x10, 14)]);
+    assert_eq!(data.len(), 0x4010);
+    assert_eq!(file.arches()[0].data(&*data).unwrap(), x86_64);
+    assert_eq!(file.arches()[1].data(&*data).unwrap(), arm64);
+
+    fat.fat64 = true;
+    let data = fat.write().unwrap();
+    assert_eq!(FileKind::parse(&*data).unwrap(), FileKind::MachOFat64);
+    let file = MachOFatFile64::parse(&*data).unwrap();
+    assert_eq!(
+        file.header().magic.get(object::BigEndian),
+        macho::FAT_MAGIC_64
+    );
+    let arches = file
+        .arches()
+        .iter()
+        .map(|arch| (arch.offset(), arch.size(), arch.align()))
+        .collect::<Vec<_>>();
+    assert_eq!(arches, [(0x1000, 0x1234, 12), (0x4000, 0x10, 14)]);
+    assert_eq!(file.arches()[1].data(&*data).unwrap(), arm64);
+    assert!(MachOFat::read(&data).unwrap().fat64);
+}
+
+#[test]
+fn fat_errors() {
+    assert!(MachOFat::new().write().is_err());
+
+    // The capability bits of the subtype are ignored for duplicates.
+    let mut fat = MachOFat::new();
+    fat.add_arch(MachOFatArch::new(
+        macho::CPU_TYPE_X86_64,
+        macho::CPU_SUBTYPE_X86_64_ALL,
+        &[0; 4][..],
+    ));
+    fat.add_arch(MachOFatArch::new(
+        macho::CPU_TYPE_X86_64,
+        macho::CPU_SUBTYPE_X86_64_ALL | macho::CPU_SUBTYPE_LIB64,
+        &[0; 4][..],
+    ));
+    assert!(fat.write().is_err());
+    fat.remove_arch(1);
+    fat.arches_mut()[0].align = 16;
+    assert!(fat.write().is_err());
+
+    let object = write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    assert!(MachOFatArch::from_object(&object).is_err());
+    assert!(MachOFat::read(&object.write().unwrap()).is_err());
+}
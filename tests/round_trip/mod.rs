@@ -7,12 +7,14 @@ use object::{
     SectionKind, SymbolFlags, SymbolKind, SymbolScope, SymbolSection,
 };
 
+mod archive;
 mod bss;
 mod coff;
 mod comdat;
 mod common;
 mod elf;
 mod macho;
+mod pe;
 mod reloc;
 mod section_flags;
 mod tls;
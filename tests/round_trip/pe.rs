@@ -0,0 +1,1260 @@
+use object::pe;
+use object::read::pe::{
+    Arm64EpilogScope, Arm64UnwindCode, Arm64UnwindData, Certificate, DebugInfo, ExportTarget,
+    ImageNtHeaders, ImageThunkData, Import, PeFile, PeFile32, PeFile64, ResourceNameOrId,
+    VersionInfo, VersionTranslation,
+};
+use object::read::{Object, ReadRef};
+use object::{write, LittleEndian as LE, U32};
+
+/// Return the NT headers for a console executable.
+///
+/// Tests override the fields that they care about.
+fn nt_headers(machine: u16, is_64: bool) -> write::pe::NtHeaders {
+    write::pe::NtHeaders {
+        machine,
+        time_date_stamp: 0,
+        characteristics: pe::IMAGE_FILE_EXECUTABLE_IMAGE,
+        major_linker_version: 14,
+        minor_linker_version: 0,
+        address_of_entry_point: 0,
+        image_base: if is_64 { 0x1_4000_0000 } else { 0x40_0000 },
+        major_operating_system_version: 6,
+        minor_operating_system_version: 0,
+        major_image_version: 0,
+        minor_image_version: 0,
+        major_subsystem_version: 6,
+        minor_subsystem_version: 0,
+        subsystem: pe::IMAGE_SUBSYSTEM_WINDOWS_CUI,
+        dll_characteristics: 0,
+        size_of_stack_reserve: 0x10_0000,
+        size_of_stack_commit: 0x1000,
+        size_of_heap_reserve: 0x10_0000,
+        size_of_heap_commit: 0x1000,
+    }
+}
+
+/// Create a DLL with an import table and an export table.
+fn image(is_64: bool) -> Vec<u8> {
+    let dlls = [
+        write::pe::ImportDll {
+            name: b"KERNEL32.dll",
+            imports: vec![
+                write::pe::Import::Name(0x100, b"ExitProcess"),
+                write::pe::Import::Name(0, b"GetLastError"),
+            ],
+        },
+        write::pe::ImportDll {
+            name: b"ws2_32.dll",
+            imports: vec![write::pe::Import::Ordinal(23)],
+        },
+    ];
+    let text_data = [0xc3; 16];
+
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(is_64, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(3);
+    let text = writer.reserve_text_section(text_data.len() as u32);
+    let exports = [
+        write::pe::Export {
+            ordinal: 5,
+            name: Some(b"func"),
+            target: write::pe::ExportTarget::Address(text.virtual_address),
+        },
+        write::pe::Export {
+            ordinal: 3,
+            name: None,
+            target: write::pe::ExportTarget::Address(text.virtual_address + 8),
+        },
+        write::pe::Export {
+            ordinal: 6,
+            name: Some(b"by_name"),
+            target: write::pe::ExportTarget::ForwardByName(b"other", b"target"),
+        },
+        write::pe::Export {
+            ordinal: 7,
+            name: Some(b"by_ordinal"),
+            target: write::pe::ExportTarget::ForwardByOrdinal(b"other", 12),
+        },
+    ];
+    writer.reserve_import_section(&dlls);
+    assert_eq!(
+        writer.import_address(0, 1),
+        writer.import_address(0, 0) + if is_64 { 8 } else { 4 }
+    );
+    writer
+        .reserve_export_section(b"test.dll", &exports)
+        .unwrap();
+
+    writer.write_dos_header_and_stub().unwrap();
+    let machine = if is_64 {
+        pe::IMAGE_FILE_MACHINE_AMD64
+    } else {
+        pe::IMAGE_FILE_MACHINE_I386
+    };
+    writer.write_nt_headers(write::pe::NtHeaders {
+        characteristics: pe::IMAGE_FILE_EXECUTABLE_IMAGE | pe::IMAGE_FILE_DLL,
+        image_base: 0x1000_0000,
+        ..nt_headers(machine, is_64)
+    });
+    writer.write_section_headers();
+    writer.write_section(text.file_offset, &text_data);
+    writer.write_import_section();
+    writer.write_export_section();
+    data
+}
+
+fn check_imports<'data, Pe: ImageNtHeaders, R: ReadRef<'data>>(file: &PeFile<'data, Pe, R>) {
+    let import_table = file.import_table().unwrap().unwrap();
+    let mut descriptors = import_table.descriptors().unwrap();
+    let mut dlls = Vec::new();
+    while let Some(descriptor) = descriptors.next().unwrap() {
+        let name = import_table.name(descriptor.name.get(LE)).unwrap();
+        let mut lookups = import_table
+            .thunks(descriptor.original_first_thunk.get(LE))
+            .unwrap();
+        let mut addresses = import_table.thunks(descriptor.first_thunk.get(LE)).unwrap();
+        let mut imports = Vec::new();
+        while let Some(thunk) = lookups.next::<Pe>().unwrap() {
+            let address = addresses.next::<Pe>().unwrap().unwrap();
+            assert_eq!(thunk.raw(), address.raw());
+            imports.push(match import_table.import::<Pe>(thunk).unwrap() {
+                Import::Ordinal(ordinal) => format!("#{}", ordinal),
+                Import::Name(hint, name) => {
+                    format!("{}:{}", hint, String::from_utf8_lossy(name))
+                }
+            });
+        }
+        assert!(addresses.next::<Pe>().unwrap().is_none());
+        dlls.push((String::from_utf8_lossy(name).into_owned(), imports));
+    }
+    assert_eq!(
+        dlls,
+        [
+            (
+                "KERNEL32.dll".to_string(),
+                vec!["256:ExitProcess".to_string(), "0:GetLastError".to_string()]
+            ),
+            ("ws2_32.dll".to_string(), vec!["#23".to_string()]),
+        ]
+    );
+
+    let iat = file.data_directory(pe::IMAGE_DIRECTORY_ENTRY_IAT).unwrap();
+    assert_eq!(
+        iat.size.get(LE),
+        if Pe::is_type_64(file.nt_headers()) {
+            40
+        } else {
+            20
+        }
+    );
+}
+
+fn check_exports<'data, Pe: ImageNtHeaders, R: ReadRef<'data>>(file: &PeFile<'data, Pe, R>) {
+    // The .text section is the first section after the headers.
+    let text = 0x1000;
+    let export_table = file.export_table().unwrap().unwrap();
+    assert_eq!(export_table.ordinal_base(), 3);
+    let exports = export_table
+        .exports()
+        .unwrap()
+        .into_iter()
+        .map(|export| {
+            let target = match export.target {
+                ExportTarget::Address(address) => format!("{:#x}", address),
+                ExportTarget::ForwardByName(dll, name) => format!(
+                    "{}.{}",
+                    String::from_utf8_lossy(dll),
+                    String::from_utf8_lossy(name)
+                ),
+                ExportTarget::ForwardByOrdinal(dll, ordinal) => {
+                    format!("{}.#{}", String::from_utf8_lossy(dll), ordinal)
+                }
+            };
+            (
+                export.ordinal,
+                export
+                    .name
+                    .map(|name| String::from_utf8_lossy(name).into_owned()),
+                target,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exports,
+        [
+            (3, None, format!("{:#x}", text + 8)),
+            (4, None, "0x0".to_string()),
+            (5, Some("func".to_string()), format!("{:#x}", text)),
+            (6, Some("by_name".to_string()), "other.target".to_string()),
+            (7, Some("by_ordinal".to_string()), "other.#12".to_string()),
+        ]
+    );
+    // The name pointer table must be sorted.
+    let names = export_table
+        .name_iter()
+        .map(|(pointer, _)| export_table.name_from_pointer(pointer).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, [&b"by_name"[..], b"by_ordinal", b"func"]);
+
+    let def = object::read::coff::ModuleDefinition::from_pe_file(file).unwrap();
+    assert_eq!(
+        String::from_utf8(def.write()).unwrap(),
+        "LIBRARY test.dll
+EXPORTS
+    ord3 @3 NONAME
+    func @5
+    by_name=other.target @6
+    by_ordinal=other.#12 @7
+"
+    );
+}
+
+#[test]
+fn pe_import_export_64() {
+    let data = image(true);
+    let file = PeFile64::parse(&*data).unwrap();
+    check_imports(&file);
+    check_exports(&file);
+    // Only named imports, and named exports with an address, are returned here.
+    assert_eq!(file.exports().unwrap().len(), 1);
+    assert_eq!(file.imports().unwrap().len(), 2);
+}
+
+#[test]
+fn pe_import_export_32() {
+    let data = image(false);
+    let file = PeFile32::parse(&*data).unwrap();
+    check_imports(&file);
+    check_exports(&file);
+}
+
+#[test]
+fn pe_export_errors() {
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(1);
+    let export = |ordinal, name| write::pe::Export {
+        ordinal,
+        name,
+        target: write::pe::ExportTarget::Address(0x1000),
+    };
+    assert!(writer
+        .reserve_export_section(b"test.dll", &[export(1, None), export(1, Some(&b"a"[..]))])
+        .is_err());
+    assert!(writer
+        .reserve_export_section(
+            b"test.dll",
+            &[export(1, Some(&b"a"[..])), export(2, Some(&b"a"[..]))]
+        )
+        .is_err());
+    assert!(writer
+        .reserve_export_section(b"test.dll", &[export(1, None), export(0x1_0001, None)])
+        .is_err());
+}
+
+#[test]
+fn pe_resources() {
+    let manifest = b"<assembly/>";
+    let version = [1, 2, 3, 4, 5];
+    let my_type = "MYTYPE".encode_utf16().collect::<Vec<_>>();
+    let a = "A".encode_utf16().collect::<Vec<_>>();
+    let b = "B".encode_utf16().collect::<Vec<_>>();
+    let resource = |typ, name, language, data| write::pe::Resource {
+        typ,
+        name,
+        language,
+        code_page: 0,
+        data,
+    };
+    use write::pe::ResourceNameOrId::{Id, Name};
+    let resources = [
+        resource(Id(pe::RT_VERSION), Id(1), 0x409, &version[..]),
+        resource(Id(pe::RT_MANIFEST), Id(1), 0x409, &manifest[..]),
+        resource(Name(&my_type), Id(7), 0x407, b"de"),
+        resource(Name(&my_type), Name(&b), 0x409, b"b"),
+        resource(Name(&my_type), Name(&a), 0x409, b"a"),
+        resource(Id(pe::RT_VERSION), Id(1), 0x407, &version[1..]),
+    ];
+
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(1);
+    assert!(writer
+        .reserve_resource_section(&[resources[0], resources[0]])
+        .is_err());
+    let rsrc = writer.reserve_resource_section(&resources).unwrap();
+    writer.write_dos_header_and_stub().unwrap();
+    writer.write_nt_headers(write::pe::NtHeaders {
+        characteristics: pe::IMAGE_FILE_EXECUTABLE_IMAGE | pe::IMAGE_FILE_DLL,
+        image_base: 0x1000_0000,
+        ..nt_headers(pe::IMAGE_FILE_MACHINE_AMD64, true)
+    });
+    writer.write_section_headers();
+    writer.write_resource_section();
+
+    let file = PeFile64::parse(&*data).unwrap();
+    let sections = file.section_table();
+    let directory = file
+        .data_directories()
+        .resource_directory(&*data, &sections)
+        .unwrap()
+        .unwrap();
+    let name = |entry: &pe::ImageResourceDirectoryEntry| match entry.name_or_id() {
+        ResourceNameOrId::Name(name) => name.to_string_lossy(directory).unwrap(),
+        ResourceNameOrId::Id(id) => format!("#{}", id),
+    };
+    let mut found = Vec::new();
+    let root = directory.root().unwrap();
+    assert_eq!(root.header.number_of_named_entries.get(LE), 1);
+    assert_eq!(root.header.number_of_id_entries.get(LE), 2);
+    for typ in root.entries {
+        let names = typ.data(directory).unwrap().table().unwrap();
+        for name_entry in names.entries {
+            let languages = name_entry.data(directory).unwrap().table().unwrap();
+            for language in languages.entries {
+                let entry = language.data(directory).unwrap().data().unwrap();
+                let address = entry.offset_to_data.get(LE);
+                assert_eq!(address % 8, 0);
+                let resource_data = sections
+                    .pe_data_at(&*data, address)
+                    .unwrap()
+                    .get(..entry.size.get(LE) as usize)
+                    .unwrap();
+                found.push((
+                    name(typ),
+                    name(name_entry),
+                    language.name_or_id().id().unwrap(),
+                    resource_data.to_vec(),
+                ));
+            }
+        }
+    }
+    assert_eq!(
+        found,
+        [
+            ("MYTYPE".into(), "A".into(), 0x409, b"a".to_vec()),
+            ("MYTYPE".into(), "B".into(), 0x409, b"b".to_vec()),
+            ("MYTYPE".into(), "#7".into(), 0x407, b"de".to_vec()),
+            ("#16".into(), "#1".into(), 0x407, version[1..].to_vec()),
+            ("#16".into(), "#1".into(), 0x409, version.to_vec()),
+            ("#24".into(), "#1".into(), 0x409, manifest.to_vec()),
+        ]
+    );
+    assert_eq!(
+        file.data_directory(pe::IMAGE_DIRECTORY_ENTRY_RESOURCE)
+            .unwrap()
+            .size
+            .get(LE),
+        rsrc.virtual_size
+    );
+}
+
+/// Create a DLL containing only a resource section.
+fn resource_image(resources: &[write::pe::Resource<'_>]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(1);
+    writer.reserve_resource_section(resources).unwrap();
+    writer.write_dos_header_and_stub().unwrap();
+    writer.write_nt_headers(write::pe::NtHeaders {
+        characteristics: pe::IMAGE_FILE_EXECUTABLE_IMAGE | pe::IMAGE_FILE_DLL,
+        image_base: 0x1000_0000,
+        ..nt_headers(pe::IMAGE_FILE_MACHINE_AMD64, true)
+    });
+    writer.write_section_headers();
+    writer.write_resource_section();
+
+    data
+}
+
+/// Encode a block in a `VS_VERSIONINFO` tree.
+fn version_block(
+    key: &str,
+    value: &[u8],
+    value_length: u16,
+    is_text: bool,
+    children: &[u8],
+) -> Vec<u8> {
+    let mut data = vec![0; 6];
+    data[2..4].copy_from_slice(&value_length.to_le_bytes());
+    data[4..6].copy_from_slice(&u16::from(is_text).to_le_bytes());
+    for c in key.encode_utf16().chain(Some(0)) {
+        data.extend_from_slice(&c.to_le_bytes());
+    }
+    data.resize((data.len() + 3) & !3, 0);
+    data.extend_from_slice(value);
+    if !children.is_empty() {
+        data.resize((data.len() + 3) & !3, 0);
+        data.extend_from_slice(children);
+    }
+    let len = data.len() as u16;
+    data[0..2].copy_from_slice(&len.to_le_bytes());
+    data
+}
+
+fn version_string(key: &str, value: &str) -> Vec<u8> {
+    let mut utf16 = Vec::new();
+    for c in value.encode_utf16().chain(Some(0)) {
+        utf16.extend_from_slice(&c.to_le_bytes());
+    }
+    let mut data = version_block(key, &utf16, (utf16.len() / 2) as u16, true, &[]);
+    data.resize((data.len() + 3) & !3, 0);
+    data
+}
+
+#[test]
+fn pe_version_info() {
+    let fixed = pe::VsFixedFileInfo {
+        signature: U32::new(LE, pe::VS_FFI_SIGNATURE),
+        struc_version: U32::new(LE, pe::VS_FFI_STRUCVERSION),
+        file_version_ms: U32::new(LE, 0x0001_0002),
+        file_version_ls: U32::new(LE, 0x0003_0004),
+        product_version_ms: U32::new(LE, 0x0005_0006),
+        product_version_ls: U32::new(LE, 0x0007_0008),
+        file_flags_mask: U32::new(LE, pe::VS_FFI_FILEFLAGSMASK),
+        file_flags: U32::new(LE, pe::VS_FF_PRERELEASE),
+        file_os: U32::new(LE, pe::VOS_NT_WINDOWS32),
+        file_type: U32::new(LE, pe::VFT_DLL),
+        file_subtype: U32::new(LE, 0),
+        file_date_ms: U32::new(LE, 0),
+        file_date_ls: U32::new(LE, 0),
+    };
+    let mut strings = version_string("CompanyName", "Example");
+    strings.extend(version_string("FileVersion", "1.2.3.4"));
+    let mut german = version_string("FileVersion", "1,2,3,4");
+    german.extend(version_string("ProductName", "Beispiel"));
+    let mut tables = version_block("040904b0", &[], 0, true, &strings);
+    tables.resize((tables.len() + 3) & !3, 0);
+    tables.extend(version_block("040704b0", &[], 0, true, &german));
+    tables.resize((tables.len() + 3) & !3, 0);
+    let mut children = version_block("StringFileInfo", &[], 0, true, &tables);
+    children.resize((children.len() + 3) & !3, 0);
+    let translation = [0x09, 0x04, 0xb0, 0x04, 0x07, 0x04, 0xb0, 0x04];
+    let var = version_block("Translation", &translation, 8, false, &[]);
+    children.extend(version_block("VarFileInfo", &[], 0, true, &var));
+    let version = version_block(
+        "VS_VERSION_INFO",
+        object::pod::bytes_of(&fixed),
+        52,
+        false,
+        &children,
+    );
+    let manifest = b"<assembly/>";
+
+    let resource = |typ, name, data| write::pe::Resource {
+        typ: write::pe::ResourceNameOrId::Id(typ),
+        name: write::pe::ResourceNameOrId::Id(name),
+        language: 0x409,
+        code_page: 0,
+        data,
+    };
+    let data = resource_image(&[
+        resource(pe::RT_VERSION, 1, &version),
+        resource(
+            pe::RT_MANIFEST,
+            pe::ISOLATIONAWARE_MANIFEST_RESOURCE_ID,
+            &manifest[..],
+        ),
+    ]);
+    let file = PeFile64::parse(&*data).unwrap();
+    assert_eq!(file.manifest().unwrap(), Some(&manifest[..]));
+
+    let info = file.version_info().unwrap().unwrap();
+    assert_eq!(info.file_version(), Some([1, 2, 3, 4]));
+    assert_eq!(info.product_version(), Some([5, 6, 7, 8]));
+    assert_eq!(info.fixed.unwrap().file_type.get(LE), pe::VFT_DLL);
+    assert_eq!(
+        info.translations,
+        [
+            VersionTranslation {
+                language: 0x409,
+                code_page: 1200
+            },
+            VersionTranslation {
+                language: 0x407,
+                code_page: 1200
+            },
+        ]
+    );
+    let tables = info
+        .string_tables
+        .iter()
+        .map(|table| {
+            (
+                table.language,
+                table.code_page,
+                table
+                    .strings
+                    .iter()
+                    .map(|string| (string.key_to_string_lossy(), string.value_to_string_lossy()))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tables,
+        [
+            (
+                0x409,
+                1200,
+                vec![
+                    ("CompanyName".into(), "Example".into()),
+                    ("FileVersion".into(), "1.2.3.4".into()),
+                ]
+            ),
+            (
+                0x407,
+                1200,
+                vec![
+                    ("FileVersion".into(), "1,2,3,4".into()),
+                    ("ProductName".into(), "Beispiel".into()),
+                ]
+            ),
+        ]
+    );
+    let utf16 = |s: &str| s.encode_utf16().collect::<Vec<_>>();
+    let string = |value: Option<&[object::U16<LE>]>| {
+        value.map(|value| value.iter().map(|c| c.get(LE)).collect::<Vec<_>>())
+    };
+    assert_eq!(string(info.string("FileVersion")), Some(utf16("1.2.3.4")));
+    assert_eq!(string(info.string("ProductName")), None);
+    assert_eq!(
+        string(
+            info.string_table(0x407, 1200)
+                .unwrap()
+                .string("ProductName")
+        ),
+        Some(utf16("Beispiel"))
+    );
+
+    // Invalid data must not panic.
+    for len in 0..version.len() {
+        let _ = VersionInfo::parse(&version[..len]);
+    }
+    let mut bad = version.clone();
+    bad[0] = 0xff;
+    assert!(VersionInfo::parse(&bad).is_err());
+}
+
+#[test]
+fn pe_certificates() {
+    let text_data = [0xc3; 16];
+    let certificates = [
+        write::pe::Certificate {
+            revision: pe::WIN_CERT_REVISION_2_0,
+            certificate_type: pe::WIN_CERT_TYPE_PKCS_SIGNED_DATA,
+            data: &[0x30, 0x82, 0x00, 0x01, 0x02],
+        },
+        write::pe::Certificate {
+            revision: pe::WIN_CERT_REVISION_1_0,
+            certificate_type: pe::WIN_CERT_TYPE_X509,
+            data: &[0x30; 8],
+        },
+    ];
+
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(1);
+    let text = writer.reserve_text_section(text_data.len() as u32);
+    writer.reserve_certificates(&certificates).unwrap();
+    let check_sum_offset = writer.check_sum_offset();
+
+    writer.write_dos_header_and_stub().unwrap();
+    writer.write_nt_headers(write::pe::NtHeaders {
+        address_of_entry_point: text.virtual_address,
+        ..nt_headers(pe::IMAGE_FILE_MACHINE_AMD64, true)
+    });
+    writer.write_section_headers();
+    writer.write_section(text.file_offset, &text_data);
+    writer.write_certificates();
+    let check_sum = write::pe::update_check_sum(&mut data, check_sum_offset as usize).unwrap();
+
+    let file = PeFile64::parse(&*data).unwrap();
+    assert_eq!(file.check_sum_offset(), u64::from(check_sum_offset));
+    assert_eq!(
+        file.nt_headers().optional_header.check_sum.get(LE),
+        check_sum
+    );
+    assert_eq!(file.compute_check_sum().unwrap(), check_sum);
+
+    let certificates: Vec<Certificate<'_>> = file
+        .certificate_table()
+        .unwrap()
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(certificates.len(), 2);
+    assert_eq!(certificates[0].revision(), pe::WIN_CERT_REVISION_2_0);
+    assert_eq!(
+        certificates[0].certificate_type(),
+        pe::WIN_CERT_TYPE_PKCS_SIGNED_DATA
+    );
+    assert_eq!(certificates[0].data, &[0x30, 0x82, 0x00, 0x01, 0x02]);
+    assert_eq!(certificates[1].revision(), pe::WIN_CERT_REVISION_1_0);
+    assert_eq!(certificates[1].certificate_type(), pe::WIN_CERT_TYPE_X509);
+    assert_eq!(certificates[1].data, &[0x30; 8]);
+
+    let directory = file
+        .data_directory(pe::IMAGE_DIRECTORY_ENTRY_SECURITY)
+        .unwrap();
+    let table_offset = u64::from(directory.virtual_address.get(LE));
+    assert_eq!(
+        table_offset + u64::from(directory.size.get(LE)),
+        data.len() as u64
+    );
+    let directory_offset = file.certificate_directory_offset().unwrap();
+    let check_sum_offset = u64::from(check_sum_offset);
+    assert_eq!(
+        file.authenticode_ranges().unwrap(),
+        vec![
+            0..check_sum_offset,
+            check_sum_offset + 4..directory_offset,
+            directory_offset + 8..table_offset,
+        ]
+    );
+
+    // Changing the checksum or the certificates does not change the hashed data.
+    let mut modified = data.clone();
+    modified[check_sum_offset as usize] ^= 1;
+    modified[table_offset as usize + 8] ^= 1;
+    for range in file.authenticode_ranges().unwrap() {
+        let range = range.start as usize..range.end as usize;
+        assert_eq!(data[range.clone()], modified[range]);
+    }
+
+    // The checksum field is ignored, and the length is added.
+    let check_sum = |offset| write::pe::update_check_sum(&mut [1, 0, 2, 0, 0xff, 0xff, 9], offset);
+    assert_eq!(check_sum(0), Ok(0x10));
+    assert_eq!(check_sum(2), Ok(0x11));
+    assert!(check_sum(4).is_err());
+}
+
+#[test]
+fn pe_debug_directory() {
+    let text_data = [0xc3; 16];
+    let guid = [
+        0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+        0x10,
+    ];
+    let hash = [0x5a; 32];
+    let vc_feature = [
+        0u32.to_le_bytes(),
+        3u32.to_le_bytes(),
+        3u32.to_le_bytes(),
+        1u32.to_le_bytes(),
+        0u32.to_le_bytes(),
+    ]
+    .concat();
+    let mut pogo = Vec::new();
+    pogo.extend_from_slice(&pe::IMAGE_DEBUG_POGO_SIGNATURE_LTCG.to_le_bytes());
+    pogo.extend_from_slice(&0x1000u32.to_le_bytes());
+    pogo.extend_from_slice(&0x10u32.to_le_bytes());
+    pogo.extend_from_slice(b".text$mn\0\0\0\0");
+    pogo.extend_from_slice(&0x2000u32.to_le_bytes());
+    pogo.extend_from_slice(&0x8u32.to_le_bytes());
+    pogo.extend_from_slice(b".rdata\0\0");
+    let mut embedded_pdb = Vec::new();
+    embedded_pdb.extend_from_slice(b"MPDB");
+    embedded_pdb.extend_from_slice(&0x100u32.to_le_bytes());
+    embedded_pdb.extend_from_slice(&[0x63, 0x00]);
+
+    let entries = [
+        write::pe::DebugEntry {
+            time_date_stamp: 0x1234_5678,
+            major_version: 0,
+            minor_version: 0,
+            data: write::pe::DebugData::CodeView {
+                guid,
+                age: 3,
+                path: b"C:\\build\\test.pdb",
+            },
+        },
+        write::pe::DebugEntry {
+            time_date_stamp: 0,
+            major_version: 0,
+            minor_version: 0,
+            data: write::pe::DebugData::Other {
+                typ: pe::IMAGE_DEBUG_TYPE_VC_FEATURE,
+                data: &vc_feature,
+            },
+        },
+        write::pe::DebugEntry {
+            time_date_stamp: 0,
+            major_version: 0,
+            minor_version: 0,
+            data: write::pe::DebugData::Other {
+                typ: pe::IMAGE_DEBUG_TYPE_POGO,
+                data: &pogo,
+            },
+        },
+        write::pe::DebugEntry {
+            time_date_stamp: 0,
+            major_version: 0,
+            minor_version: 0,
+            data: write::pe::DebugData::Repro(&hash),
+        },
+        write::pe::DebugEntry {
+            time_date_stamp: 0,
+            major_version: 0,
+            minor_version: 0,
+            data: write::pe::DebugData::Repro(&[]),
+        },
+        write::pe::DebugEntry {
+            time_date_stamp: 0,
+            major_version: 0,
+            minor_version: 0,
+            data: write::pe::DebugData::ExDllCharacteristics(
+                pe::IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT,
+            ),
+        },
+        write::pe::DebugEntry {
+            time_date_stamp: 0,
+            major_version: 0x100,
+            minor_version: 0x100,
+            data: write::pe::DebugData::Other {
+                typ: pe::IMAGE_DEBUG_TYPE_EMBEDDED_PORTABLE_PDB,
+                data: &embedded_pdb,
+            },
+        },
+    ];
+
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(2);
+    let text = writer.reserve_text_section(text_data.len() as u32);
+    let debug = writer.reserve_debug_section(&entries);
+
+    writer.write_dos_header_and_stub().unwrap();
+    writer.write_nt_headers(write::pe::NtHeaders {
+        address_of_entry_point: text.virtual_address,
+        ..nt_headers(pe::IMAGE_FILE_MACHINE_AMD64, true)
+    });
+    writer.write_section_headers();
+    writer.write_section(text.file_offset, &text_data);
+    writer.write_debug_section();
+
+    let file = PeFile64::parse(&*data).unwrap();
+    let pdb_info = file.pdb_info().unwrap().unwrap();
+    assert_eq!(pdb_info.guid(), guid);
+    assert_eq!(pdb_info.age(), 3);
+    assert_eq!(pdb_info.path(), b"C:\\build\\test.pdb");
+
+    let debug_entries = file
+        .debug_directory()
+        .unwrap()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(debug_entries.len(), entries.len());
+    assert_eq!(
+        debug_entries[0].directory.time_date_stamp.get(LE),
+        0x1234_5678
+    );
+
+    match debug_entries[0].info().unwrap() {
+        DebugInfo::CodeView(info) => assert_eq!(info, pdb_info),
+        info => panic!("unexpected {:?}", info),
+    }
+    match debug_entries[1].info().unwrap() {
+        DebugInfo::VcFeature(info) => {
+            assert_eq!(info.c_cpp.get(LE), 3);
+            assert_eq!(info.gs.get(LE), 3);
+            assert_eq!(info.sdl.get(LE), 1);
+        }
+        info => panic!("unexpected {:?}", info),
+    }
+    match debug_entries[2].info().unwrap() {
+        DebugInfo::Pogo(pogo) => {
+            assert_eq!(pogo.signature(), pe::IMAGE_DEBUG_POGO_SIGNATURE_LTCG);
+            let pogo = pogo
+                .map(|entry| entry.map(|entry| (entry.rva, entry.size, entry.name)))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(
+                pogo,
+                [
+                    (0x1000, 0x10, &b".text$mn"[..]),
+                    (0x2000, 0x8, &b".rdata"[..])
+                ]
+            );
+        }
+        info => panic!("unexpected {:?}", info),
+    }
+    match debug_entries[3].info().unwrap() {
+        DebugInfo::Repro(repro) => assert_eq!(repro, hash),
+        info => panic!("unexpected {:?}", info),
+    }
+    assert_eq!(debug_entries[4].data, &[]);
+    match debug_entries[4].info().unwrap() {
+        DebugInfo::Repro(repro) => assert!(repro.is_empty()),
+        info => panic!("unexpected {:?}", info),
+    }
+    match debug_entries[5].info().unwrap() {
+        DebugInfo::ExDllCharacteristics(characteristics) => {
+            assert_eq!(characteristics, pe::IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT)
+        }
+        info => panic!("unexpected {:?}", info),
+    }
+    match debug_entries[6].info().unwrap() {
+        DebugInfo::EmbeddedPortablePdb(pdb) => {
+            assert_eq!(pdb.uncompressed_size, 0x100);
+            assert_eq!(pdb.data, &[0x63, 0x00]);
+        }
+        info => panic!("unexpected {:?}", info),
+    }
+
+    // Invalid data for other entry types must not prevent finding the CodeView entry.
+    // Move the VC feature entry first, and give it an invalid file offset.
+    let entry_size = std::mem::size_of::<pe::ImageDebugDirectory>();
+    let offset = debug.file_offset as usize;
+    let (codeview, vc_feature) = data[offset..][..2 * entry_size].split_at_mut(entry_size);
+    codeview.swap_with_slice(vc_feature);
+    data[offset + 24..][..4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let file = PeFile64::parse(&*data).unwrap();
+    assert!(file
+        .debug_directory()
+        .unwrap()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .is_err());
+    let pdb_info = file.pdb_info().unwrap().unwrap();
+    assert_eq!(pdb_info.path(), b"C:\\build\\test.pdb");
+}
+
+const EXCEPTION_TEXT_ADDRESS: u32 = 0x1000;
+const EXCEPTION_XDATA_ADDRESS: u32 = 0x2000;
+
+/// Create an image with the given `.pdata` and `.xdata` sections.
+fn exception_image(machine: u16, pdata: &[u8], xdata: &[u8]) -> Vec<u8> {
+    let text_data = [0; 0x100];
+
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(3);
+    let text = writer.reserve_text_section(text_data.len() as u32);
+    assert_eq!(text.virtual_address, EXCEPTION_TEXT_ADDRESS);
+    let xdata_range = writer.reserve_xdata_section(xdata.len() as u32);
+    assert_eq!(xdata_range.virtual_address, EXCEPTION_XDATA_ADDRESS);
+    let pdata_range = writer.reserve_pdata_section(pdata.len() as u32);
+
+    writer.write_dos_header_and_stub().unwrap();
+    writer.write_nt_headers(write::pe::NtHeaders {
+        address_of_entry_point: text.virtual_address,
+        ..nt_headers(machine, true)
+    });
+    writer.write_section_headers();
+    writer.write_section(text.file_offset, &text_data);
+    writer.write_section(xdata_range.file_offset, xdata);
+    writer.write_section(pdata_range.file_offset, pdata);
+    data
+}
+
+fn words(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+#[test]
+fn pe_exceptions_x64() {
+    use object::read::pe::X64UnwindOperation as Op;
+
+    let mut xdata = vec![
+        // Version 1, UNW_FLAG_EHANDLER.
+        0x09, 0x10, 6,    // RBP, offset 0x20.
+        0x25, // UWOP_SET_FPREG
+        0x10, 0x03, // UWOP_ALLOC_LARGE 0x100
+        0x0c, 0x01, 0x20, 0x00, // UWOP_SAVE_NONVOL RSI, 0x80
+        0x05, 0x64, 0x10, 0x00, // UWOP_PUSH_NONVOL RBP
+        0x01, 0x50,
+    ];
+    xdata.extend_from_slice(&words(&[0x1100, 0xaabb_ccdd]));
+    let chained_offset = xdata.len() as u32;
+    xdata.extend_from_slice(&[
+        // Version 1, UNW_FLAG_CHAININFO.
+        0x21, 0x00, 1, 0x00, // UWOP_ALLOC_SMALL 0x20
+        0x04, 0x32, 0x00, 0x00,
+    ]);
+    xdata.extend_from_slice(&words(&[0x1000, 0x1020, EXCEPTION_XDATA_ADDRESS]));
+    let pdata = words(&[
+        0x1000,
+        0x1020,
+        EXCEPTION_XDATA_ADDRESS,
+        0x1020,
+        0x1030,
+        EXCEPTION_XDATA_ADDRESS + chained_offset,
+    ]);
+    let data = exception_image(pe::IMAGE_FILE_MACHINE_AMD64, &pdata, &xdata);
+
+    let file = PeFile64::parse(&*data).unwrap();
+    let table = file.exception_table().unwrap().unwrap();
+    let functions = table.x64_functions().unwrap();
+    assert_eq!(functions.len(), 2);
+
+    let info = functions[0]
+        .unwind_info(&*data, &file.section_table())
+        .unwrap();
+    assert_eq!(info.version(), 1);
+    assert_eq!(info.flags(), pe::UNW_FLAG_EHANDLER);
+    assert_eq!(info.size_of_prolog(), 0x10);
+    assert_eq!(info.frame_register(), 5);
+    assert_eq!(info.frame_offset(), 0x20);
+    let codes = info
+        .codes()
+        .map(|code| code.map(|code| (code.code_offset, code.operation)))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        codes,
+        [
+            (0x10, Op::SetFpreg),
+            (0x0c, Op::Alloc(0x100)),
+            (
+                0x05,
+                Op::SaveNonvol {
+                    register: 6,
+                    offset: 0x80
+                }
+            ),
+            (0x01, Op::PushNonvol(5)),
+        ]
+    );
+    assert_eq!(info.handler(), Some(0x1100));
+    assert_eq!(&info.handler_data()[..4], &[0xdd, 0xcc, 0xbb, 0xaa]);
+    assert!(info.chained_function().is_none());
+
+    let info = functions[1]
+        .unwind_info(&*data, &file.section_table())
+        .unwrap();
+    assert_eq!(info.flags(), pe::UNW_FLAG_CHAININFO);
+    assert_eq!(
+        info.codes().collect::<Result<Vec<_>, _>>().unwrap()[0].operation,
+        Op::Alloc(0x20)
+    );
+    assert_eq!(info.handler(), None);
+    let chained = info.chained_function().unwrap();
+    assert_eq!(chained.begin_address.get(LE), 0x1000);
+    assert_eq!(
+        chained.unwind_info_address_or_data.get(LE),
+        EXCEPTION_XDATA_ADDRESS
+    );
+}
+
+#[test]
+fn pe_exceptions_arm64() {
+    let mut xdata = words(&[
+        // FunctionLength 0x40, X, 1 epilog scope, 2 code words.
+        (0x40 / 4) | 1 << 20 | 1 << 22 | 2 << 27,
+        // Epilog at 0x30, code index 4.
+        (0x30 / 4) | 4 << 22,
+    ]);
+    xdata.extend_from_slice(&[0xc8, 0x02, 0x81, 0xe4, 0xc8, 0x02, 0x81, 0xe4]);
+    xdata.extend_from_slice(&words(&[0x1100, 0]));
+    let packed = pe::ARM64_PDATA_PACKED_UNWIND_FUNCTION
+        | (0x20 / 4) << 2
+        | 2 << 16
+        | 3 << 21
+        | (0x20 / 16) << 23;
+    let pdata = words(&[0x1000, EXCEPTION_XDATA_ADDRESS, 0x1040, packed]);
+    let data = exception_image(pe::IMAGE_FILE_MACHINE_ARM64, &pdata, &xdata);
+
+    let file = PeFile64::parse(&*data).unwrap();
+    let table = file.exception_table().unwrap().unwrap();
+    let functions = table.arm64_functions().unwrap();
+    assert_eq!(functions.len(), 2);
+
+    assert_eq!(functions[0].flag(), pe::ARM64_PDATA_REF_TO_FULL_XDATA);
+    let info = match functions[0]
+        .unwind_data(&*data, &file.section_table())
+        .unwrap()
+    {
+        Arm64UnwindData::Full(info) => info,
+        unwind => panic!("unexpected {:?}", unwind),
+    };
+    assert_eq!(info.function_length(), 0x40);
+    assert_eq!(info.version(), 0);
+    assert_eq!(info.packed_epilog(), None);
+    assert_eq!(
+        info.epilog_scopes().collect::<Vec<_>>(),
+        [Arm64EpilogScope {
+            start_offset: 0x30,
+            start_index: 4
+        }]
+    );
+    let codes = info.codes().collect::<Result<Vec<_>, _>>().unwrap();
+    let save_regp = Arm64UnwindCode::SaveRegPair {
+        register: 19,
+        offset: 16,
+        pre_index: false,
+    };
+    let save_fplr_x = Arm64UnwindCode::SaveRegPair {
+        register: 29,
+        offset: 16,
+        pre_index: true,
+    };
+    assert_eq!(
+        codes,
+        [
+            (0, save_regp),
+            (2, save_fplr_x),
+            (3, Arm64UnwindCode::End),
+            (4, save_regp),
+            (6, save_fplr_x),
+            (7, Arm64UnwindCode::End),
+        ]
+    );
+    assert_eq!(info.handler(), Some(0x1100));
+
+    assert_eq!(functions[1].flag(), pe::ARM64_PDATA_PACKED_UNWIND_FUNCTION);
+    let packed = match functions[1]
+        .unwind_data(&*data, &file.section_table())
+        .unwrap()
+    {
+        Arm64UnwindData::Packed(packed) => packed,
+        unwind => panic!("unexpected {:?}", unwind),
+    };
+    assert_eq!(packed.function_length, 0x20);
+    assert_eq!(packed.reg_f, 0);
+    assert_eq!(packed.reg_i, 2);
+    assert!(!packed.h);
+    assert_eq!(packed.cr, 3);
+    assert_eq!(packed.frame_size, 0x20);
+}
+
+const LOAD_CONFIG_RDATA_ADDRESS: u32 = 0x2000;
+
+/// Create an image with the load config directory at the start of `.rdata`.
+fn load_config_image(is_64: bool, rdata: &[u8], config_size: u32) -> Vec<u8> {
+    let text_data = [0; 0x100];
+
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(is_64, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(2);
+    let text = writer.reserve_text_section(text_data.len() as u32);
+    let rdata_range = writer.reserve_rdata_section(rdata.len() as u32);
+    assert_eq!(rdata_range.virtual_address, LOAD_CONFIG_RDATA_ADDRESS);
+    writer.set_data_directory(
+        pe::IMAGE_DIRECTORY_ENTRY_LOAD_CONFIG,
+        rdata_range.virtual_address,
+        config_size,
+    );
+
+    writer.write_dos_header_and_stub().unwrap();
+    let machine = if is_64 {
+        pe::IMAGE_FILE_MACHINE_AMD64
+    } else {
+        pe::IMAGE_FILE_MACHINE_I386
+    };
+    writer.write_nt_headers(write::pe::NtHeaders {
+        address_of_entry_point: text.virtual_address,
+        dll_characteristics: pe::IMAGE_DLLCHARACTERISTICS_GUARD_CF,
+        ..nt_headers(machine, is_64)
+    });
+    writer.write_section_headers();
+    writer.write_section(text.file_offset, &text_data);
+    writer.write_section(rdata_range.file_offset, rdata);
+    data
+}
+
+#[test]
+fn pe_load_config_32() {
+    let image_base = 0x40_0000;
+    // A directory from an older linker that ends after `guard_flags`.
+    let config_size = 0x5c;
+    let mut rdata = vec![0; 0x100];
+    let (config, _) =
+        object::pod::from_bytes_mut::<pe::ImageLoadConfigDirectory32>(&mut rdata).unwrap();
+    config.size.set(LE, config_size);
+    config.security_cookie.set(LE, image_base + 0x3000);
+    config.sehandler_table.set(LE, image_base + 0x2080);
+    config.sehandler_count.set(LE, 2);
+    config.guard_cf_function_table.set(LE, image_base + 0x2090);
+    config.guard_cf_function_count.set(LE, 3);
+    config.guard_flags.set(
+        LE,
+        pe::IMAGE_GUARD_CF_INSTRUMENTED | pe::IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT,
+    );
+    // This is beyond `config_size` and must be ignored.
+    config
+        .guard_eh_continuation_table
+        .set(LE, image_base + 0x2090);
+    rdata[0x80..0x88].copy_from_slice(&words(&[0x1010, 0x1020]));
+    rdata[0x90..0x9c].copy_from_slice(&words(&[0x1000, 0x1040, 0x1080]));
+
+    let data = load_config_image(false, &rdata, 0x40);
+    let file = PeFile32::parse(&*data).unwrap();
+    let sections = file.section_table();
+    let config = file.load_config().unwrap().unwrap();
+    assert!(config.directory32().is_some());
+    assert!(config.directory64().is_none());
+    assert_eq!(config.size(), config_size);
+    assert_eq!(config.security_cookie(), u64::from(image_base) + 0x3000);
+    assert_eq!(config.guard_eh_continuation_table(), 0);
+    assert!(config
+        .guard_eh_continuation_targets(&*data, &sections)
+        .unwrap()
+        .is_none());
+    assert!(config
+        .dynamic_relocations(&*data, &sections)
+        .unwrap()
+        .is_none());
+
+    let handlers = config
+        .se_handlers(&*data, &sections)
+        .unwrap()
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(handlers, [0x1010, 0x1020]);
+
+    let functions = config
+        .guard_cf_functions(&*data, &sections)
+        .unwrap()
+        .unwrap()
+        .map(|function| function.rva)
+        .collect::<Vec<_>>();
+    assert_eq!(functions, [0x1000, 0x1040, 0x1080]);
+
+    // A count that extends beyond the section.
+    let mut bad_rdata = rdata.clone();
+    bad_rdata[0x44..0x48].copy_from_slice(&0x1000u32.to_le_bytes());
+    let data = load_config_image(false, &bad_rdata, 0x40);
+    let file = PeFile32::parse(&*data).unwrap();
+    let config = file.load_config().unwrap().unwrap();
+    assert!(config.se_handlers(&*data, &file.section_table()).is_err());
+}
+
+#[test]
+fn pe_load_config_64() {
+    use object::read::pe::GuardFunction;
+
+    let image_base = 0x1_4000_0000;
+    let config_size = std::mem::size_of::<pe::ImageLoadConfigDirectory64>();
+    assert!(config_size <= 0x180);
+    let mut rdata = vec![0; 0x200];
+    let (config, _) =
+        object::pod::from_bytes_mut::<pe::ImageLoadConfigDirectory64>(&mut rdata).unwrap();
+    config.size.set(LE, config_size as u32);
+    config.security_cookie.set(LE, image_base + 0x3000);
+    config
+        .guard_cf_check_function_pointer
+        .set(LE, image_base + 0x21f0);
+    config.guard_cf_function_table.set(LE, image_base + 0x2180);
+    config.guard_cf_function_count.set(LE, 2);
+    config
+        .guard_eh_continuation_table
+        .set(LE, image_base + 0x2190);
+    config.guard_eh_continuation_count.set(LE, 1);
+    config.guard_flags.set(
+        LE,
+        pe::IMAGE_GUARD_CF_INSTRUMENTED
+            | pe::IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT
+            | pe::IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT
+            | (1 << pe::IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT),
+    );
+    config.chpe_metadata_pointer.set(LE, image_base + 0x21f8);
+    config.dynamic_value_reloc_table_offset.set(LE, 0x1c0);
+    config.dynamic_value_reloc_table_section.set(LE, 2);
+
+    // Guard tables with one byte of flags per entry.
+    rdata[0x180..0x18a].copy_from_slice(&[
+        0x00,
+        0x10,
+        0,
+        0,
+        pe::IMAGE_GUARD_FLAG_FID_SUPPRESSED as u8,
+        0x40,
+        0x10,
+        0,
+        0,
+        0,
+    ]);
+    rdata[0x190..0x195].copy_from_slice(&[0x80, 0x10, 0, 0, 0]);
+
+    // Dynamic value relocation table with one symbol and one block.
+    let mut table = words(&[1, 24]);
+    table.extend_from_slice(
+        &u64::from(pe::IMAGE_DYNAMIC_RELOCATION_GUARD_RF_PROLOGUE).to_le_bytes(),
+    );
+    table.extend_from_slice(&words(&[12, 0x1000, 12]));
+    table.extend_from_slice(&[0x10, 0x00, 0x20, 0x00]);
+    rdata[0x1c0..0x1c0 + table.len()].copy_from_slice(&table);
+
+    let data = load_config_image(true, &rdata, 0x100);
+    let file = PeFile64::parse(&*data).unwrap();
+    let sections = file.section_table();
+    let config = file.load_config().unwrap().unwrap();
+    assert!(config.directory64().is_some());
+    assert_eq!(config.size(), config_size as u32);
+    assert_eq!(config.security_cookie(), image_base + 0x3000);
+    assert_eq!(
+        config.guard_cf_check_function_pointer(),
+        image_base + 0x21f0
+    );
+    assert_eq!(config.chpe_metadata_pointer(), image_base + 0x21f8);
+    assert!(config.se_handlers(&*data, &sections).unwrap().is_none());
+
+    let functions = config
+        .guard_cf_functions(&*data, &sections)
+        .unwrap()
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        functions,
+        [
+            GuardFunction {
+                rva: 0x1000,
+                flags: pe::IMAGE_GUARD_FLAG_FID_SUPPRESSED,
+            },
+            GuardFunction {
+                rva: 0x1040,
+                flags: 0,
+            },
+        ]
+    );
+    let targets = config
+        .guard_eh_continuation_targets(&*data, &sections)
+        .unwrap()
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        targets,
+        [GuardFunction {
+            rva: 0x1080,
+            flags: 0
+        }]
+    );
+
+    let mut relocations = config
+        .dynamic_relocations(&*data, &sections)
+        .unwrap()
+        .unwrap();
+    let mut relocation = relocations.next().unwrap().unwrap();
+    assert_eq!(
+        relocation.symbol,
+        u64::from(pe::IMAGE_DYNAMIC_RELOCATION_GUARD_RF_PROLOGUE)
+    );
+    let mut block = relocation.blocks.next().unwrap().unwrap();
+    assert_eq!(block.virtual_address(), 0x1000);
+    let offsets = block
+        .by_ref()
+        .map(|reloc| reloc.virtual_address)
+        .collect::<Vec<_>>();
+    assert_eq!(offsets, [0x1010, 0x1020]);
+    assert!(relocation.blocks.next().unwrap().is_none());
+    assert!(relocations.next().unwrap().is_none());
+}